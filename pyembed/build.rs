@@ -25,6 +25,9 @@ fn main() {
         }
     }
 
+    // Emit Py_3_X cfg flags so code can be conditional on the Python version.
+    pyo3_build_config::use_pyo3_cfgs();
+
     let interpreter_config = pyo3_build_config::get();
 
     // Re-export the path to the configured Python interpreter. Tests can
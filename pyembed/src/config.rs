@@ -6,13 +6,14 @@
 
 use {
     crate::NewInterpreterError,
-    oxidized_importer::{PackedResourcesSource, PythonResourcesState},
+    oxidized_importer::{PackedResourcesSource, PythonResourcesState, RuntimeResourceLocation},
     pyo3::ffi as pyffi,
     python_packaging::interpreter::{
         MemoryAllocatorBackend, MultiprocessingStartMethod, PythonInterpreterConfig,
-        PythonInterpreterProfile, TerminfoResolution,
+        PythonInterpreterProfile, RunMode, TerminfoResolution,
     },
     std::{
+        collections::BTreeMap,
         ffi::{CString, OsString},
         ops::Deref,
         path::PathBuf,
@@ -240,6 +241,28 @@ pub struct OxidizedPythonInterpreterConfig<'a> {
     /// [`pkgutil`]: https://docs.python.org/3/library/pkgutil.html
     pub oxidized_importer: bool,
 
+    /// Whether `OxidizedFinder` should defer execution of module code until first use.
+    ///
+    /// If [true], modules imported from source or bytecode by `OxidizedFinder`
+    /// are loaded via [`importlib.util.LazyLoader`]: the module object is
+    /// created on import but its code only runs once an attribute on it is
+    /// accessed. This can reduce startup time for applications importing
+    /// large dependency trees that are rarely used.
+    ///
+    /// Modules relying on side-effects of their import may misbehave when
+    /// this is enabled.
+    ///
+    /// Has no effect unless [Self::oxidized_importer] is [true].
+    ///
+    /// Default value: [false]
+    ///
+    /// Interpreter initialization behavior: `OxidizedFinder.lazy_loading` is
+    /// set after interpreter initialization completes. Modules imported during
+    /// initialization are loaded eagerly.
+    ///
+    /// [`importlib.util.LazyLoader`]: https://docs.python.org/3/library/importlib.html#importlib.util.LazyLoader
+    pub oxidized_importer_lazy_loading: bool,
+
     /// Whether to install the path-based finder.
     ///
     /// Controls whether to install the Python standard library `PathFinder` meta
@@ -346,6 +369,10 @@ pub struct OxidizedPythonInterpreterConfig<'a> {
     /// Enabling this has the same effect as calling `multiprocessing.freeze_support()`
     /// in your application code's `__main__` and replaces the need to do so.
     ///
+    /// Helper processes spawned by `multiprocessing`, such as the resource tracker
+    /// and the forkserver, are also detected and dispatched. These are invoked with
+    /// `-c "from multiprocessing.<module> import main; ..."` arguments.
+    ///
     /// Default value: [true]
     pub multiprocessing_auto_dispatch: bool,
 
@@ -383,6 +410,21 @@ pub struct OxidizedPythonInterpreterConfig<'a> {
     /// `sys._MEIPASS` will not be defined.
     pub sys_meipass: bool,
 
+    /// Whether to replace missing standard streams with `os.devnull`.
+    ///
+    /// Python sets `sys.stdin`, `sys.stdout`, and `sys.stderr` to `None` if the
+    /// corresponding file descriptor isn't available. This is the case for
+    /// Windows executables using the `windows` subsystem (GUI applications
+    /// without a console window). Code reading from or writing to these
+    /// streams then fails, often with an `AttributeError`.
+    ///
+    /// Default value: [false]
+    ///
+    /// Interpreter initialization behavior: If [true], each of `sys.stdin`,
+    /// `sys.stdout`, and `sys.stderr` that is `None` is replaced by a file
+    /// object for `os.devnull`. Available streams are left as-is.
+    pub replace_missing_stdio: bool,
+
     /// How to resolve the `terminfo` database.
     ///
     /// Default value: [TerminfoResolution::Dynamic]
@@ -391,6 +433,11 @@ pub struct OxidizedPythonInterpreterConfig<'a> {
     /// variable may be set for this process depending on what [TerminfoResolution]
     /// instructs to do.
     ///
+    /// [Self::resolve()] behavior: the token `$ORIGIN` in a
+    /// [TerminfoResolution::Static] value is expanded to the resolved value of
+    /// [Self::origin]. This allows pointing at a terminfo database installed
+    /// next to the application.
+    ///
     /// `terminfo` is not used on Windows and this setting is ignored on that
     /// platform.
     pub terminfo_resolution: TerminfoResolution,
@@ -407,8 +454,50 @@ pub struct OxidizedPythonInterpreterConfig<'a> {
     ///
     /// Interpreter initialization behavior: if set, the `TCL_LIBRARY` environment
     /// variable will be set for the current process.
+    ///
+    /// If [Self::tcl_files_embedded_prefix] is set, this path is interpreted
+    /// relative to the directory files are extracted to.
     pub tcl_library: Option<PathBuf>,
 
+    /// Name prefix of file resources in packed resources holding tcl files.
+    ///
+    /// When set, file resources having embedded data and a name beginning
+    /// with this prefix are extracted to a temporary directory during
+    /// interpreter initialization. [Self::tcl_library] is then resolved
+    /// relative to this directory, allowing `tkinter` to work from an
+    /// executable not having tcl files on the filesystem.
+    ///
+    /// The temporary directory is deleted when the interpreter is dropped.
+    ///
+    /// Default value: [None]
+    pub tcl_files_embedded_prefix: Option<String>,
+
+    /// Path to use to define the `QT_PLUGIN_PATH` environment variable.
+    ///
+    /// This directory should contain Qt plugin directories, such as
+    /// `platforms`.
+    ///
+    /// Default value: [None]
+    ///
+    /// [Self::resolve()] behavior: the token `$ORIGIN` is expanded to the
+    /// resolved value of [Self::origin].
+    ///
+    /// Interpreter initialization behavior: if set, the `QT_PLUGIN_PATH`
+    /// environment variable will be set for the current process.
+    pub qt_plugin_path: Option<PathBuf>,
+
+    /// Path to use to define the environment variables for QML imports.
+    ///
+    /// Default value: [None]
+    ///
+    /// [Self::resolve()] behavior: the token `$ORIGIN` is expanded to the
+    /// resolved value of [Self::origin].
+    ///
+    /// Interpreter initialization behavior: if set, the `QML2_IMPORT_PATH`
+    /// (Qt 5) and `QML_IMPORT_PATH` (Qt 6) environment variables will be
+    /// set for the current process.
+    pub qml_import_path: Option<PathBuf>,
+
     /// Environment variable holding the directory to write a loaded modules file.
     ///
     /// If this value is set and the environment it refers to is set,
@@ -421,6 +510,141 @@ pub struct OxidizedPythonInterpreterConfig<'a> {
     ///
     /// Default value: [None]
     pub write_modules_directory_env: Option<String>,
+
+    /// Ordered list of what to run when [MainPythonInterpreter::run()] is called.
+    ///
+    /// The first [RunMode] applying to the current invocation is used. If the
+    /// list is empty or no mode applies, [MainPythonInterpreter::run()] falls
+    /// back to `Py_RunMain()`, which honors the `run_*` fields of
+    /// [Self::interpreter_config].
+    ///
+    /// Default value: `vec![]`
+    ///
+    /// [MainPythonInterpreter::run()]: crate::MainPythonInterpreter::run
+    pub run_modes: Vec<RunMode>,
+
+    /// Command line flag for choosing the run mode at run-time.
+    ///
+    /// If set and the first command line argument equals this value, the
+    /// following argument is parsed as a [RunMode] and used instead of
+    /// [Self::run_modes]. Both arguments are removed from `sys.argv`.
+    /// e.g. with a value of `--run-mode`, `myapp --run-mode repl` starts a REPL.
+    ///
+    /// Default value: [None]
+    pub run_mode_flag: Option<String>,
+
+    /// Environment variables to define for the current process.
+    ///
+    /// This allows baking run-time configuration, such as debugging or logging
+    /// settings, into a build.
+    ///
+    /// Default value: empty
+    ///
+    /// [Self::resolve()] behavior: the token `$ORIGIN` in values is expanded to
+    /// the resolved value of [Self::origin].
+    ///
+    /// Interpreter initialization behavior: each variable not already defined
+    /// in the environment is set before the interpreter is initialized.
+    /// Variables already defined are left as-is so they can be overridden at
+    /// run-time. Python only reads `PYTHON*` variables if
+    /// [PythonInterpreterConfig::use_environment] is enabled.
+    pub environment_variables: BTreeMap<String, String>,
+
+    /// Directory to extract extension modules embedded in packed resources to.
+    ///
+    /// When set, extension module shared libraries embedded in packed
+    /// resources are written to this directory, along with any shared
+    /// libraries they depend on, and imported from there. Files are only
+    /// written if missing or different, so the directory acts as a cache
+    /// across runs. This allows single file executables to contain extension
+    /// modules on platforms that can't load shared libraries from memory, or
+    /// that fail to load from memory on Windows.
+    ///
+    /// Different builds should use different directories, as files in use by a
+    /// running process may not be replaceable.
+    ///
+    /// Default value: [None]
+    ///
+    /// [Self::resolve()] behavior: the token `$ORIGIN` is expanded to the
+    /// resolved value of [Self::origin]. Relative paths are interpreted
+    /// relative to the system's temporary directory.
+    ///
+    /// Importer behavior: if unset, extension modules embedded in packed
+    /// resources are loaded from memory on Windows and fail to import
+    /// elsewhere.
+    pub extension_modules_extraction_path: Option<PathBuf>,
+
+    /// Filesystem directories to import specific packages from.
+    ///
+    /// Keys are package names and values are directories searched like a
+    /// `sys.path` entry when importing that package or any of its descendants.
+    /// Modules found in the directory take precedence over packed resources.
+    /// Modules not found there are imported from packed resources as usual.
+    ///
+    /// This allows running a built application against a source checkout of
+    /// its own packages, so code edits don't require a rebuild.
+    ///
+    /// Default value: empty
+    ///
+    /// [Self::resolve()] behavior: the token `$ORIGIN` in values is expanded to
+    /// the resolved value of [Self::origin].
+    ///
+    /// Importer behavior: each package's resource locations are set to the
+    /// directory followed by the default resource locations. Only applies when
+    /// [Self::oxidized_importer] is enabled.
+    pub package_source_paths: BTreeMap<String, PathBuf>,
+
+    /// Filesystem directories to import plugins from.
+    ///
+    /// Each directory is searched like a `sys.path` entry, so packages
+    /// installed after the application was built, such as user-installed
+    /// plugins, can be imported alongside resources embedded in the binary.
+    /// Directories that don't exist when the interpreter starts are ignored.
+    ///
+    /// Default value: empty
+    ///
+    /// [Self::resolve()] behavior: the token `$ORIGIN` is expanded to the
+    /// resolved value of [Self::origin] and the token `$HOME` is expanded to
+    /// the current user's home directory. Paths containing `$HOME` are
+    /// dropped if the home directory can't be determined.
+    ///
+    /// Importer behavior: the directories are appended, in order, to the
+    /// default resource locations, so embedded modules take precedence.
+    /// If [Self::plugin_paths_override] is set, they are prepended instead.
+    /// Only applies when [Self::oxidized_importer] is enabled.
+    pub plugin_paths: Vec<PathBuf>,
+
+    /// Whether modules in [Self::plugin_paths] take precedence over embedded modules.
+    ///
+    /// Default value: `false`
+    ///
+    /// Importer behavior: if set, a module available from a plugin directory
+    /// is imported from there even if it is also embedded in the binary.
+    pub plugin_paths_override: bool,
+
+    /// Whether to set `__file__` on modules imported from memory.
+    ///
+    /// Some packages fail to import if `__file__` is absent. When enabled,
+    /// modules imported from in-memory source or bytecode have `__file__` set
+    /// to a virtual path under the executable, e.g.
+    /// `/path/to/myapp/foo/bar.py`. The path doesn't exist on the filesystem.
+    ///
+    /// Default value: `false`
+    ///
+    /// Importer behavior: sets `OxidizedFinder.file_emulation`. Only applies
+    /// when [Self::oxidized_importer] is enabled.
+    pub file_emulation: bool,
+
+    /// Per-package overrides of [Self::file_emulation].
+    ///
+    /// Keys are package names. Values apply to the package and all its
+    /// descendants and take precedence over [Self::file_emulation].
+    ///
+    /// Default value: empty
+    ///
+    /// Importer behavior: calls `OxidizedFinder.set_package_file_emulation()`
+    /// for each entry. Only applies when [Self::oxidized_importer] is enabled.
+    pub package_file_emulation: BTreeMap<String, bool>,
 }
 
 impl<'a> Default for OxidizedPythonInterpreterConfig<'a> {
@@ -442,6 +666,7 @@ impl<'a> Default for OxidizedPythonInterpreterConfig<'a> {
             allocator_debug: false,
             set_missing_path_configuration: true,
             oxidized_importer: false,
+            oxidized_importer_lazy_loading: false,
             filesystem_importer: true,
             packed_resources: vec![],
             extra_extension_modules: None,
@@ -451,9 +676,22 @@ impl<'a> Default for OxidizedPythonInterpreterConfig<'a> {
             multiprocessing_start_method: MultiprocessingStartMethod::Auto,
             sys_frozen: false,
             sys_meipass: false,
+            replace_missing_stdio: false,
             terminfo_resolution: TerminfoResolution::Dynamic,
             tcl_library: None,
+            tcl_files_embedded_prefix: None,
+            qt_plugin_path: None,
+            qml_import_path: None,
             write_modules_directory_env: None,
+            run_modes: vec![],
+            run_mode_flag: None,
+            environment_variables: BTreeMap::new(),
+            extension_modules_extraction_path: None,
+            package_source_paths: BTreeMap::new(),
+            plugin_paths: vec![],
+            plugin_paths_override: false,
+            file_emulation: false,
+            package_file_emulation: BTreeMap::new(),
         }
     }
 }
@@ -526,6 +764,64 @@ impl<'a> OxidizedPythonInterpreterConfig<'a> {
             .as_ref()
             .map(|x| PathBuf::from(x.display().to_string().replace("$ORIGIN", &origin_string)));
 
+        let qt_plugin_path = self
+            .qt_plugin_path
+            .as_ref()
+            .map(|x| PathBuf::from(x.display().to_string().replace("$ORIGIN", &origin_string)));
+
+        let qml_import_path = self
+            .qml_import_path
+            .as_ref()
+            .map(|x| PathBuf::from(x.display().to_string().replace("$ORIGIN", &origin_string)));
+
+        let terminfo_resolution = match self.terminfo_resolution {
+            TerminfoResolution::Static(v) => {
+                TerminfoResolution::Static(v.replace("$ORIGIN", &origin_string))
+            }
+            v => v,
+        };
+
+        let environment_variables = self
+            .environment_variables
+            .into_iter()
+            .map(|(k, v)| (k, v.replace("$ORIGIN", &origin_string)))
+            .collect::<BTreeMap<_, _>>();
+
+        let extension_modules_extraction_path =
+            self.extension_modules_extraction_path.as_ref().map(|x| {
+                std::env::temp_dir()
+                    .join(x.display().to_string().replace("$ORIGIN", &origin_string))
+            });
+
+        let package_source_paths = self
+            .package_source_paths
+            .into_iter()
+            .map(|(k, v)| {
+                (
+                    k,
+                    PathBuf::from(v.display().to_string().replace("$ORIGIN", &origin_string)),
+                )
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        let home_string = home_dir().map(|p| p.display().to_string());
+
+        let plugin_paths = self
+            .plugin_paths
+            .into_iter()
+            .filter_map(|p| {
+                let p = p.display().to_string().replace("$ORIGIN", &origin_string);
+
+                if p.contains("$HOME") {
+                    home_string
+                        .as_ref()
+                        .map(|home| PathBuf::from(p.replace("$HOME", home)))
+                } else {
+                    Some(PathBuf::from(p))
+                }
+            })
+            .collect::<Vec<_>>();
+
         Ok(ResolvedOxidizedPythonInterpreterConfig {
             inner: Self {
                 exe: Some(exe),
@@ -537,12 +833,28 @@ impl<'a> OxidizedPythonInterpreterConfig<'a> {
                 argv,
                 packed_resources,
                 tcl_library,
+                qt_plugin_path,
+                qml_import_path,
+                terminfo_resolution,
+                environment_variables,
+                extension_modules_extraction_path,
+                package_source_paths,
+                plugin_paths,
                 ..self
             },
         })
     }
 }
 
+/// Resolve the home directory of the current user.
+fn home_dir() -> Option<PathBuf> {
+    let key = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+
+    std::env::var_os(key)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
 /// An `OxidizedPythonInterpreterConfig` that has fields resolved.
 pub struct ResolvedOxidizedPythonInterpreterConfig<'a> {
     inner: OxidizedPythonInterpreterConfig<'a>,
@@ -614,6 +926,9 @@ impl<'a, 'config: 'a> TryFrom<&ResolvedOxidizedPythonInterpreterConfig<'config>>
         let mut state = Self::default();
         state.set_current_exe(config.exe().to_path_buf());
         state.set_origin(config.origin().to_path_buf());
+        state.set_extension_modules_extraction_path(
+            config.extension_modules_extraction_path.clone(),
+        );
 
         for source in &config.packed_resources {
             match source {
@@ -634,6 +949,45 @@ impl<'a, 'config: 'a> TryFrom<&ResolvedOxidizedPythonInterpreterConfig<'config>>
             .index_interpreter_builtins()
             .map_err(NewInterpreterError::Simple)?;
 
+        let plugin_locations = config
+            .plugin_paths
+            .iter()
+            .filter(|path| path.is_dir())
+            .map(|path| RuntimeResourceLocation::Filesystem(path.clone()))
+            .collect::<Vec<_>>();
+
+        if !plugin_locations.is_empty() {
+            let locations = if config.plugin_paths_override {
+                plugin_locations
+                    .into_iter()
+                    .chain(state.resource_locations().iter().cloned())
+                    .collect::<Vec<_>>()
+            } else {
+                state
+                    .resource_locations()
+                    .iter()
+                    .cloned()
+                    .chain(plugin_locations)
+                    .collect::<Vec<_>>()
+            };
+
+            state.set_resource_locations(locations);
+        }
+
+        state.set_file_emulation(config.file_emulation);
+
+        for (package, value) in &config.package_file_emulation {
+            state.set_package_file_emulation(package, Some(*value));
+        }
+
+        for (package, path) in &config.package_source_paths {
+            let locations = std::iter::once(RuntimeResourceLocation::Filesystem(path.clone()))
+                .chain(state.resource_locations().iter().cloned())
+                .collect::<Vec<_>>();
+
+            state.set_package_resource_locations(package, Some(locations));
+        }
+
         Ok(state)
     }
 }
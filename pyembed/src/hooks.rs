@@ -0,0 +1,190 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Callbacks for events in the lifecycle of an embedded Python interpreter.
+
+use {
+    crate::{config::ResolvedOxidizedPythonInterpreterConfig, error::NewInterpreterError},
+    pyo3::{prelude::*, types::PyModule},
+    std::sync::Arc,
+};
+
+type PreInitHook =
+    Box<dyn Fn(&ResolvedOxidizedPythonInterpreterConfig) -> Result<(), NewInterpreterError>>;
+type PostInitHook = Box<dyn Fn(Python) -> PyResult<()>>;
+type PreFinalizeHook = Arc<dyn Fn(Python) + Send + Sync>;
+type UnhandledExceptionHook = Arc<dyn Fn(Python, &PyErr) + Send + Sync>;
+
+/// Rust callbacks invoked during the lifecycle of a [crate::MainPythonInterpreter].
+///
+/// Hooks allow host applications to customize an interpreter (e.g. by
+/// adjusting `sys.path` or bridging Python logging to Rust logging) or
+/// observe its failures (e.g. for crash reporting) without modifying the
+/// code that runs it.
+///
+/// Instances are passed to [crate::MainPythonInterpreter::new_with_hooks()].
+/// Multiple callbacks can be registered for each event and they are called
+/// in the order they were registered.
+#[derive(Default)]
+pub struct InterpreterHooks {
+    pre_init: Vec<PreInitHook>,
+    post_init: Vec<PostInitHook>,
+    pre_finalize: Vec<PreFinalizeHook>,
+    unhandled_exception: Vec<UnhandledExceptionHook>,
+}
+
+impl InterpreterHooks {
+    /// Register a callback to run before the Python interpreter is initialized.
+    ///
+    /// The callback is called with the resolved interpreter config after the
+    /// process-wide interpreter guard is acquired and before any Python C API is
+    /// called. Returning an error aborts interpreter initialization.
+    pub fn register_pre_init<F>(&mut self, f: F) -> &mut Self
+    where
+        F: Fn(&ResolvedOxidizedPythonInterpreterConfig) -> Result<(), NewInterpreterError>
+            + 'static,
+    {
+        self.pre_init.push(Box::new(f));
+        self
+    }
+
+    /// Register a callback to run after the Python interpreter is initialized.
+    ///
+    /// The callback is called with the GIL held once the interpreter is
+    /// fully initialized and configured, before any configured code runs.
+    /// Returning an error aborts interpreter initialization.
+    pub fn register_post_init<F>(&mut self, f: F) -> &mut Self
+    where
+        F: Fn(Python) -> PyResult<()> + 'static,
+    {
+        self.post_init.push(Box::new(f));
+        self
+    }
+
+    /// Register a callback to run when the Python interpreter finalizes.
+    ///
+    /// The callback is called with the GIL held as an `atexit` handler. It
+    /// therefore runs whether the interpreter is finalized by dropping the
+    /// [crate::MainPythonInterpreter] or by `Py_RunMain()`. Handlers
+    /// registered via `atexit` by Python code run first.
+    pub fn register_pre_finalize<F>(&mut self, f: F) -> &mut Self
+    where
+        F: Fn(Python) + Send + Sync + 'static,
+    {
+        self.pre_finalize.push(Arc::new(f));
+        self
+    }
+
+    /// Register a callback to run for unhandled Python exceptions.
+    ///
+    /// The callback is called by `sys.excepthook` before the exception is
+    /// printed. This includes exceptions raised by code executed via
+    /// [crate::MainPythonInterpreter::run()] and
+    /// [crate::MainPythonInterpreter::py_runmain()]. `SystemExit` is not
+    /// considered unhandled.
+    pub fn register_unhandled_exception<F>(&mut self, f: F) -> &mut Self
+    where
+        F: Fn(Python, &PyErr) + Send + Sync + 'static,
+    {
+        self.unhandled_exception.push(Arc::new(f));
+        self
+    }
+
+    pub(crate) fn run_pre_init(
+        &self,
+        config: &ResolvedOxidizedPythonInterpreterConfig,
+    ) -> Result<(), NewInterpreterError> {
+        for hook in &self.pre_init {
+            hook(config)?;
+        }
+
+        Ok(())
+    }
+
+    /// Run post-init callbacks and install handlers for later events.
+    pub(crate) fn run_post_init(&self, py: Python) -> Result<(), NewInterpreterError> {
+        if !self.unhandled_exception.is_empty() {
+            let sys_module = py
+                .import("sys")
+                .map_err(|e| NewInterpreterError::new_from_pyerr(py, e, "obtaining sys module"))?;
+
+            install_excepthook(py, sys_module, self.unhandled_exception.clone()).map_err(|e| {
+                NewInterpreterError::new_from_pyerr(py, e, "installing sys.excepthook")
+            })?;
+        }
+
+        if !self.pre_finalize.is_empty() {
+            register_atexit(py, self.pre_finalize.clone()).map_err(|e| {
+                NewInterpreterError::new_from_pyerr(py, e, "registering pre-finalize hooks")
+            })?;
+        }
+
+        for hook in &self.post_init {
+            hook(py).map_err(|e| {
+                NewInterpreterError::new_from_pyerr(py, e, "running post-init hook")
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// `atexit` handler calling pre-finalize hooks.
+#[pyclass(module = "pyembed")]
+struct PreFinalizeHandler {
+    hooks: Vec<PreFinalizeHook>,
+}
+
+#[pymethods]
+impl PreFinalizeHandler {
+    fn __call__(&self, py: Python) {
+        for hook in &self.hooks {
+            hook(py);
+        }
+    }
+}
+
+/// `sys.excepthook` calling unhandled exception hooks then the original hook.
+#[pyclass(module = "pyembed")]
+struct ExceptHook {
+    hooks: Vec<UnhandledExceptionHook>,
+    original: PyObject,
+}
+
+#[pymethods]
+impl ExceptHook {
+    fn __call__(
+        &self,
+        py: Python,
+        exc_type: &PyAny,
+        value: &PyAny,
+        traceback: &PyAny,
+    ) -> PyResult<PyObject> {
+        let err = PyErr::from_value(value);
+
+        for hook in &self.hooks {
+            hook(py, &err);
+        }
+
+        self.original.call1(py, (exc_type, value, traceback))
+    }
+}
+
+fn install_excepthook(
+    py: Python,
+    sys_module: &PyModule,
+    hooks: Vec<UnhandledExceptionHook>,
+) -> PyResult<()> {
+    let original = sys_module.getattr("excepthook")?.into_py(py);
+
+    sys_module.setattr("excepthook", Py::new(py, ExceptHook { hooks, original })?)
+}
+
+fn register_atexit(py: Python, hooks: Vec<PreFinalizeHook>) -> PyResult<()> {
+    let handler = Py::new(py, PreFinalizeHandler { hooks })?;
+
+    py.import("atexit")?.call_method1("register", (handler,))?;
+
+    Ok(())
+}
@@ -9,8 +9,10 @@ use {
         config::{OxidizedPythonInterpreterConfig, ResolvedOxidizedPythonInterpreterConfig},
         conversion::osstring_to_bytes,
         error::NewInterpreterError,
+        hooks::InterpreterHooks,
         osutils::resolve_terminfo_dirs,
         pyalloc::PythonMemoryAllocator,
+        subinterpreter::{SubInterpreter, SubInterpreterConfig},
     },
     once_cell::sync::Lazy,
     oxidized_importer::{
@@ -19,13 +21,18 @@ use {
         OXIDIZED_IMPORTER_NAME_STR,
     },
     pyo3::{
-        exceptions::PyRuntimeError, ffi as pyffi, prelude::*, types::PyDict, AsPyPointer,
-        PyTypeInfo,
+        exceptions::{PyModuleNotFoundError, PyRuntimeError, PyValueError},
+        ffi as pyffi,
+        prelude::*,
+        types::{PyDict, PyList, PyTuple},
+        AsPyPointer, PyTypeInfo,
     },
-    python_packaging::interpreter::{MultiprocessingStartMethod, TerminfoResolution},
+    python_packaging::interpreter::{MultiprocessingStartMethod, RunMode, TerminfoResolution},
     std::{
         collections::BTreeSet,
-        env, fs,
+        env,
+        ffi::OsString,
+        fs,
         io::Write,
         os::raw::c_char,
         path::{Path, PathBuf},
@@ -92,6 +99,8 @@ pub struct MainPythonInterpreter<'interpreter, 'resources: 'interpreter> {
     pub(crate) allocator: Option<PythonMemoryAllocator>,
     /// File to write containing list of modules when the interpreter finalizes.
     write_modules_path: Option<PathBuf>,
+    /// Directory embedded tcl files were extracted to.
+    tcl_files_path: Option<PathBuf>,
 }
 
 impl<'interpreter, 'resources> MainPythonInterpreter<'interpreter, 'resources> {
@@ -100,6 +109,17 @@ impl<'interpreter, 'resources> MainPythonInterpreter<'interpreter, 'resources> {
     /// The Python interpreter is initialized as a side-effect. The GIL is held.
     pub fn new(
         config: OxidizedPythonInterpreterConfig<'resources>,
+    ) -> Result<MainPythonInterpreter<'interpreter, 'resources>, NewInterpreterError> {
+        Self::new_with_hooks(config, InterpreterHooks::default())
+    }
+
+    /// Construct a Python interpreter from a configuration and lifecycle hooks.
+    ///
+    /// This is like [Self::new()] except the callbacks registered on `hooks`
+    /// are called as the interpreter goes through its lifecycle.
+    pub fn new_with_hooks(
+        config: OxidizedPythonInterpreterConfig<'resources>,
+        hooks: InterpreterHooks,
     ) -> Result<MainPythonInterpreter<'interpreter, 'resources>, NewInterpreterError> {
         let config: ResolvedOxidizedPythonInterpreterConfig<'resources> = config.try_into()?;
 
@@ -115,14 +135,21 @@ impl<'interpreter, 'resources> MainPythonInterpreter<'interpreter, 'resources> {
             TerminfoResolution::None => {}
         }
 
+        for (key, value) in &config.environment_variables {
+            if env::var_os(key).is_none() {
+                env::set_var(key, value);
+            }
+        }
+
         let mut res = MainPythonInterpreter {
             config,
             interpreter_guard: None,
             allocator: None,
             write_modules_path: None,
+            tcl_files_path: None,
         };
 
-        res.init()?;
+        res.init(&hooks)?;
 
         Ok(res)
     }
@@ -139,16 +166,37 @@ impl<'interpreter, 'resources> MainPythonInterpreter<'interpreter, 'resources> {
     /// of interpreter initialization.
     ///
     /// The GIL is not held after the interpreter is initialized.
-    fn init(&mut self) -> Result<(), NewInterpreterError> {
+    fn init(&mut self, hooks: &InterpreterHooks) -> Result<(), NewInterpreterError> {
         assert!(self.interpreter_guard.is_none());
         self.interpreter_guard = Some(GLOBAL_INTERPRETER_GUARD.lock().map_err(|_| {
             NewInterpreterError::Simple("unable to acquire global interpreter guard")
         })?);
 
-        if let Some(tcl_library) = &self.config.tcl_library {
+        if let Some(prefix) = &self.config.tcl_files_embedded_prefix {
+            let dest_dir = env::temp_dir().join(format!("pyembed-tcl-{}", std::process::id()));
+            // Record the directory before extraction so it is cleaned up on
+            // partial failure.
+            self.tcl_files_path = Some(dest_dir.clone());
+            extract_embedded_files(&self.config, prefix, &dest_dir)?;
+
+            if let Some(tcl_library) = &self.config.tcl_library {
+                std::env::set_var("TCL_LIBRARY", dest_dir.join(tcl_library));
+            }
+        } else if let Some(tcl_library) = &self.config.tcl_library {
             std::env::set_var("TCL_LIBRARY", tcl_library);
         }
 
+        if let Some(path) = &self.config.qt_plugin_path {
+            std::env::set_var("QT_PLUGIN_PATH", path);
+        }
+
+        if let Some(path) = &self.config.qml_import_path {
+            std::env::set_var("QML2_IMPORT_PATH", path);
+            std::env::set_var("QML_IMPORT_PATH", path);
+        }
+
+        hooks.run_pre_init(&self.config)?;
+
         set_pyimport_inittab(&self.config);
 
         // Pre-configure Python.
@@ -249,6 +297,8 @@ impl<'interpreter, 'resources> MainPythonInterpreter<'interpreter, 'resources> {
         self.write_modules_path =
             self.with_gil(|py| self.init_post_main(py, oxidized_finder_loaded))?;
 
+        self.with_gil(|py| hooks.run_post_init(py))?;
+
         debug_assert_eq!(unsafe { pyffi::PyGILState_Check() }, 0);
 
         Ok(())
@@ -392,6 +442,19 @@ impl<'interpreter, 'resources> MainPythonInterpreter<'interpreter, 'resources> {
                     "installing OxidizedFinder in sys.path_hooks",
                 )
             })?;
+
+            // Lazy loading is enabled after initialization because modules imported
+            // during initialization (e.g. `encodings`) rely on side-effects of their
+            // execution.
+            if self.config.oxidized_importer_lazy_loading {
+                finder.setattr("lazy_loading", true).map_err(|err| {
+                    NewInterpreterError::new_from_pyerr(
+                        py,
+                        err,
+                        "enabling OxidizedFinder lazy loading",
+                    )
+                })?;
+            }
         }
 
         if self.config.argvb {
@@ -450,6 +513,12 @@ impl<'interpreter, 'resources> MainPythonInterpreter<'interpreter, 'resources> {
             }
         }
 
+        if self.config.replace_missing_stdio {
+            replace_missing_stdio(py, sys_module).map_err(|err| {
+                NewInterpreterError::new_from_pyerr(py, err, "replacing missing stdio streams")
+            })?;
+        }
+
         let write_modules_path = if let Some(key) = &self.config.write_modules_directory_env {
             if let Ok(path) = std::env::var(key) {
                 let path = PathBuf::from(path);
@@ -502,6 +571,76 @@ impl<'interpreter, 'resources> MainPythonInterpreter<'interpreter, 'resources> {
         Python::with_gil(f)
     }
 
+    /// Import a Python module.
+    pub fn import_module(&self, name: &str) -> PyResult<Py<PyModule>> {
+        self.with_gil(|py| Ok(py.import(name)?.into()))
+    }
+
+    /// Call a function in a Python module and convert its result to a Rust value.
+    ///
+    /// `args` is a tuple of Rust values that are converted to Python objects.
+    /// e.g. `interp.call::<_, String>("os.path", "join", ("a", "b"))`.
+    pub fn call<A, R>(&self, module: &str, function: &str, args: A) -> PyResult<R>
+    where
+        A: IntoPy<Py<PyTuple>>,
+        R: for<'py> FromPyObject<'py>,
+    {
+        self.with_gil(|py| py.import(module)?.getattr(function)?.call1(args)?.extract())
+    }
+
+    /// Evaluate a Python expression and convert its result to a Rust value.
+    pub fn eval<R>(&self, code: &str) -> PyResult<R>
+    where
+        R: for<'py> FromPyObject<'py>,
+    {
+        self.with_gil(|py| py.eval(code, None, None)?.extract())
+    }
+
+    /// Run a coroutine to completion in a new `asyncio` event loop.
+    ///
+    /// `f` is called with the GIL held and returns a coroutine, such as the
+    /// result of calling an `async def` function. The coroutine is run with
+    /// `asyncio.run()` and its result is converted to a Rust value.
+    ///
+    /// This must not be called while an event loop is running in the
+    /// current thread.
+    pub fn run_async<F, R>(&self, f: F) -> PyResult<R>
+    where
+        F: for<'py> FnOnce(Python<'py>) -> PyResult<&'py PyAny>,
+        R: for<'py> FromPyObject<'py>,
+    {
+        self.with_gil(|py| {
+            let coroutine = f(py)?;
+
+            py.import("asyncio")?
+                .call_method1("run", (coroutine,))?
+                .extract()
+        })
+    }
+
+    /// Call an `async def` function in a Python module and run it to completion.
+    ///
+    /// This is a convenience wrapper around [Self::run_async()].
+    pub fn call_async<A, R>(&self, module: &str, function: &str, args: A) -> PyResult<R>
+    where
+        A: IntoPy<Py<PyTuple>>,
+        R: for<'py> FromPyObject<'py>,
+    {
+        self.run_async(|py| py.import(module)?.getattr(function)?.call1(args))
+    }
+
+    /// Create a Python sub-interpreter.
+    ///
+    /// The sub-interpreter has its own modules and `sys` state, isolating code
+    /// running in it from the main interpreter and other sub-interpreters. See
+    /// [SubInterpreter] for caveats.
+    pub fn new_sub_interpreter(
+        &self,
+        config: &SubInterpreterConfig,
+    ) -> Result<SubInterpreter<'_>, NewInterpreterError> {
+        SubInterpreter::new(config)
+    }
+
     /// Runs `Py_RunMain()` and finalizes the interpreter.
     ///
     /// This will execute whatever is configured by the Python interpreter config
@@ -579,6 +718,29 @@ impl<'interpreter, 'resources> MainPythonInterpreter<'interpreter, 'resources> {
         })
     }
 
+    /// Run a `multiprocessing` helper process command.
+    ///
+    /// `command` is Python source code as returned by
+    /// [Self::multiprocessing_helper_command].
+    pub fn run_multiprocessing_helper(&self, command: &str) -> PyResult<i32> {
+        self.with_gil(|py| {
+            py.run(command, None, None)?;
+
+            Ok(0)
+        })
+    }
+
+    /// Resolve the command of a `multiprocessing` helper process.
+    ///
+    /// Besides workers, `multiprocessing` spawns helper processes like the
+    /// resource tracker and the forkserver. These are launched as
+    /// `sys.executable [interpreter flags] -c <code>`, which executables that
+    /// don't process arguments like `python` do wouldn't honor. Returns `<code>`
+    /// if the current process looks like one of these helpers.
+    pub fn multiprocessing_helper_command(&self) -> Option<String> {
+        multiprocessing_helper_command(self.config.resolve_sys_argv())
+    }
+
     /// Whether the Python interpreter is in "multiprocessing worker" mode.
     ///
     /// The `multiprocessing` module can work by spawning new processes
@@ -590,29 +752,202 @@ impl<'interpreter, 'resources> MainPythonInterpreter<'interpreter, 'resources> {
         argv.len() >= 2 && argv[1] == "--multiprocessing-fork"
     }
 
+    /// Resolve the [RunMode] to use for the current invocation.
+    ///
+    /// If [OxidizedPythonInterpreterConfig::run_mode_flag] is the first element
+    /// of `sys.argv`, the run mode is parsed from the following element and both
+    /// elements are removed from `sys.argv`. Otherwise, the first entry in
+    /// [OxidizedPythonInterpreterConfig::run_modes] that applies is returned.
+    ///
+    /// Returns `None` if no run mode applies.
+    pub fn resolve_run_mode(&self) -> PyResult<Option<RunMode>> {
+        self.with_gil(|py| {
+            let sys = py.import("sys")?;
+            let argv = sys.getattr("argv")?.downcast::<PyList>()?;
+
+            if let Some(flag) = &self.config.run_mode_flag {
+                let first = argv
+                    .get_item(1)
+                    .ok()
+                    .and_then(|v| v.extract::<String>().ok());
+
+                if first.as_deref() == Some(flag.as_str()) {
+                    let value = argv
+                        .get_item(2)
+                        .map_err(|_| {
+                            PyValueError::new_err(format!("{} requires a run mode argument", flag))
+                        })?
+                        .extract::<String>()?;
+
+                    let mode = RunMode::try_from(value).map_err(PyValueError::new_err)?;
+
+                    argv.call_method1("pop", (1,))?;
+                    argv.call_method1("pop", (1,))?;
+
+                    return Ok(Some(mode));
+                }
+            }
+
+            for mode in &self.config.run_modes {
+                let applies = match mode {
+                    RunMode::Module(name) => {
+                        // find_spec() raises if a parent package doesn't exist.
+                        match py
+                            .import("importlib.util")?
+                            .call_method1("find_spec", (name,))
+                        {
+                            Ok(spec) => !spec.is_none(),
+                            Err(e) if e.is_instance_of::<PyModuleNotFoundError>(py) => false,
+                            Err(e) => return Err(e),
+                        }
+                    }
+                    RunMode::File => argv.len() >= 2,
+                    RunMode::Eval(_) | RunMode::Repl => true,
+                };
+
+                if applies {
+                    return Ok(Some(mode.clone()));
+                }
+            }
+
+            Ok(None)
+        })
+    }
+
+    /// Run the interpreter in the given [RunMode].
+    ///
+    /// This mirrors what the `python` executable does for the equivalent
+    /// command line arguments.
+    pub fn run_mode(&self, mode: &RunMode) -> PyResult<i32> {
+        self.with_gil(|py| {
+            match mode {
+                RunMode::Module(name) => {
+                    // This is what `python -m` calls. It sets sys.argv[0] to the
+                    // module's path.
+                    py.import("runpy")?
+                        .call_method1("_run_module_as_main", (name,))?;
+                }
+                RunMode::Eval(code) => {
+                    let main = py.import("__main__")?;
+                    py.run(code, Some(main.dict()), None)?;
+                }
+                RunMode::File => {
+                    let sys = py.import("sys")?;
+                    let argv = sys.getattr("argv")?.downcast::<PyList>()?;
+
+                    // The file becomes sys.argv[0], like `python <file>`.
+                    argv.call_method1("pop", (0,))?;
+                    let path = argv.get_item(0)?;
+
+                    let kwargs = PyDict::new(py);
+                    kwargs.set_item("run_name", "__main__")?;
+
+                    py.import("runpy")?
+                        .call_method("run_path", (path,), Some(kwargs))?;
+                }
+                RunMode::Repl => {
+                    // Importing readline enables line editing in input(), which
+                    // the REPL uses. It isn't available everywhere.
+                    let _ = py.import("readline");
+
+                    let main = py.import("__main__")?;
+                    let kwargs = PyDict::new(py);
+                    kwargs.set_item("local", main.dict())?;
+
+                    py.import("code")?
+                        .call_method("interact", (), Some(kwargs))?;
+                }
+            }
+
+            Ok(0)
+        })
+    }
+
     /// Runs the Python interpreter.
     ///
     /// If multiprocessing dispatch is enabled, this will check if the
     /// current process invocation appears to be a spawned multiprocessing worker
-    /// and dispatch to multiprocessing accordingly.
+    /// or helper process and dispatch to multiprocessing accordingly.
     ///
-    /// Otherwise, this delegates to [Self::py_runmain].
+    /// Otherwise, if a run mode is resolved by [Self::resolve_run_mode], it is
+    /// run with [Self::run_mode]. If not, this delegates to [Self::py_runmain].
     pub fn run(self) -> i32 {
-        if self.config.multiprocessing_auto_dispatch && self.is_multiprocessing() {
-            match self.run_multiprocessing() {
-                Ok(code) => code,
-                Err(e) => {
-                    self.with_gil(|py| {
-                        e.print(py);
-                    });
-
-                    1
-                }
-            }
+        let res = if !self.config.multiprocessing_auto_dispatch {
+            None
+        } else if self.is_multiprocessing() {
+            Some(self.run_multiprocessing())
         } else {
-            self.py_runmain()
+            self.multiprocessing_helper_command()
+                .map(|command| self.run_multiprocessing_helper(&command))
+        };
+
+        let res = res.or_else(|| match self.resolve_run_mode() {
+            Ok(mode) => mode.map(|mode| self.run_mode(&mode)),
+            Err(e) => Some(Err(e)),
+        });
+
+        match res {
+            Some(Ok(code)) => code,
+            Some(Err(e)) => {
+                self.with_gil(|py| {
+                    e.print(py);
+                });
+
+                1
+            }
+            None => self.py_runmain(),
+        }
+    }
+}
+
+/// Resolve the Python code a `multiprocessing` helper process should run.
+///
+/// `multiprocessing` launches its resource tracker and forkserver processes with
+/// arguments `[interpreter flags] -c "from multiprocessing.<module> import main; ..."`.
+/// Only code importing from `multiprocessing` is recognized so `-c` arguments
+/// intended for the application aren't intercepted.
+pub(crate) fn multiprocessing_helper_command(argv: &[OsString]) -> Option<String> {
+    let index = argv.iter().skip(1).position(|arg| arg == "-c")? + 1;
+
+    // Arguments preceding `-c` should come from
+    // `multiprocessing.util._args_from_interpreter_flags()`. These are all flags
+    // or values for `-W` and `-X`.
+    let mut flags = argv[1..index].iter().map(|arg| arg.to_string_lossy());
+    while let Some(flag) = flags.next() {
+        match flag.as_ref() {
+            "-W" | "-X" => {
+                flags.next()?;
+            }
+            flag if flag.starts_with('-') && flag.len() > 1 => {}
+            _ => return None,
+        }
+    }
+
+    let command = argv.get(index + 1)?.to_str()?;
+
+    if command.starts_with("from multiprocessing.") {
+        Some(command.to_string())
+    } else {
+        None
+    }
+}
+
+/// Replace standard streams that are `None` with file objects for `os.devnull`.
+///
+/// Python defines missing standard streams as `None`, e.g. in Windows GUI
+/// applications without a console.
+pub(crate) fn replace_missing_stdio(py: Python, sys_module: &PyModule) -> PyResult<()> {
+    let devnull = py.import("os")?.getattr("devnull")?;
+    let open = py.import("io")?.getattr("open")?;
+
+    for (name, mode) in [("stdin", "r"), ("stdout", "w"), ("stderr", "w")] {
+        if sys_module.getattr(name)?.is_none() {
+            let stream = open.call1((devnull, mode, -1, "utf-8"))?;
+            sys_module.setattr(name, stream)?;
         }
     }
+
+    Ok(())
 }
 
 static mut ORIGINAL_BUILTIN_EXTENSIONS: Option<Vec<pyffi::_inittab>> = None;
@@ -725,8 +1060,64 @@ fn write_modules_to_path(py: Python, path: &Path) -> Result<(), &'static str> {
     Ok(())
 }
 
+/// Extract file resources with embedded data having a name prefix to a directory.
+///
+/// The prefix is stripped from resource names to derive the destination path.
+fn extract_embedded_files(
+    config: &ResolvedOxidizedPythonInterpreterConfig,
+    prefix: &str,
+    dest_dir: &Path,
+) -> Result<(), NewInterpreterError> {
+    let state = PythonResourcesState::try_from(config)?;
+
+    let files = state.resolve_embedded_files(prefix).map_err(|e| {
+        NewInterpreterError::Dynamic(format!("error resolving embedded files: {}", e))
+    })?;
+
+    for (name, data, executable) in files {
+        let dest_path = dest_dir.join(&name[prefix.len()..]);
+
+        if let Some(parent) = dest_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                NewInterpreterError::Dynamic(format!(
+                    "error creating directory {}: {}",
+                    parent.display(),
+                    e
+                ))
+            })?;
+        }
+
+        fs::write(&dest_path, &data).map_err(|e| {
+            NewInterpreterError::Dynamic(format!("error writing {}: {}", dest_path.display(), e))
+        })?;
+
+        #[cfg(unix)]
+        if executable {
+            use std::os::unix::fs::PermissionsExt;
+
+            fs::set_permissions(&dest_path, fs::Permissions::from_mode(0o755)).map_err(|e| {
+                NewInterpreterError::Dynamic(format!(
+                    "error setting permissions on {}: {}",
+                    dest_path.display(),
+                    e
+                ))
+            })?;
+        }
+        #[cfg(not(unix))]
+        let _ = executable;
+    }
+
+    Ok(())
+}
+
 impl<'interpreter, 'resources> Drop for MainPythonInterpreter<'interpreter, 'resources> {
     fn drop(&mut self) {
+        if let Some(path) = self.tcl_files_path.take() {
+            // Errors are ignored because there is nothing meaningful we can do
+            // about them during drop.
+            let _ = fs::remove_dir_all(path);
+        }
+
         // Interpreter may have been finalized already. Possibly through our invocation
         // of Py_RunMain(). Possibly something out-of-band beyond our control. We don't
         // muck with the interpreter after finalization because this will likely result
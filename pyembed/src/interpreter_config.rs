@@ -187,6 +187,22 @@ fn set_legacy_windows_stdio(config: &mut pyffi::PyConfig, value: bool) {
     config.legacy_windows_stdio = if value { 1 } else { 0 };
 }
 
+#[cfg(Py_3_11)]
+fn set_use_frozen_modules(config: &mut pyffi::PyConfig, value: bool) {
+    config.use_frozen_modules = if value { 1 } else { 0 };
+}
+
+#[cfg(not(Py_3_11))]
+fn set_use_frozen_modules(_config: &mut pyffi::PyConfig, _value: bool) {}
+
+#[cfg(Py_3_11)]
+fn set_safe_path(config: &mut pyffi::PyConfig, value: bool) {
+    config.safe_path = if value { 1 } else { 0 };
+}
+
+#[cfg(not(Py_3_11))]
+fn set_safe_path(_config: &mut pyffi::PyConfig, _value: bool) {}
+
 #[cfg(target_family = "unix")]
 pub fn set_argv(
     config: &mut pyffi::PyConfig,
@@ -474,6 +490,12 @@ pub fn python_interpreter_config_to_py_config(
     if let Some(pathconfig_warnings) = value.pathconfig_warnings {
         config.pathconfig_warnings = if pathconfig_warnings { 1 } else { 0 };
     }
+    if let Some(use_frozen_modules) = value.use_frozen_modules {
+        set_use_frozen_modules(&mut config, use_frozen_modules);
+    }
+    if let Some(safe_path) = value.safe_path {
+        set_safe_path(&mut config, safe_path);
+    }
     if let Some(python_path_env) = &value.python_path_env {
         set_config_string_from_str(
             &config,
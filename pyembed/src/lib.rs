@@ -57,10 +57,12 @@ The optional `serialization` feature controls whether configuration types
 mod config;
 mod conversion;
 mod error;
+mod hooks;
 mod interpreter;
 mod interpreter_config;
 mod osutils;
 mod pyalloc;
+mod subinterpreter;
 pub mod technotes;
 #[cfg(test)]
 mod test;
@@ -73,14 +75,16 @@ pub use {
             ResolvedOxidizedPythonInterpreterConfig,
         },
         error::NewInterpreterError,
+        hooks::InterpreterHooks,
         interpreter::MainPythonInterpreter,
         pyalloc::PythonMemoryAllocator,
+        subinterpreter::{SubInterpreter, SubInterpreterConfig},
     },
     oxidized_importer::{PackedResourcesSource, PythonResourcesState},
     python_packaging::{
         interpreter::{
             Allocator, BytesWarning, CheckHashPycsMode, CoerceCLocale, MemoryAllocatorBackend,
-            MultiprocessingStartMethod, PythonInterpreterConfig, PythonInterpreterProfile,
+            MultiprocessingStartMethod, PythonInterpreterConfig, PythonInterpreterProfile, RunMode,
             TerminfoResolution,
         },
         resource::BytecodeOptimizationLevel,
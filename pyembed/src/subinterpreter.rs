@@ -0,0 +1,152 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Manage Python sub-interpreters.
+
+use {
+    crate::error::NewInterpreterError,
+    pyo3::{ffi as pyffi, prelude::*, GILPool},
+    std::{marker::PhantomData, path::PathBuf},
+};
+
+/// Configuration for a Python sub-interpreter.
+///
+/// Sub-interpreters inherit the configuration of the main interpreter,
+/// as CPython doesn't support configuring them at creation time. Fields
+/// on this type are applied to the sub-interpreter after it is created.
+#[derive(Clone, Debug, Default)]
+pub struct SubInterpreterConfig {
+    /// Paths to prepend to `sys.path`.
+    pub sys_paths: Vec<PathBuf>,
+
+    /// Value to set `sys.argv` to.
+    ///
+    /// If not set, `sys.argv` is inherited from the main interpreter.
+    pub argv: Option<Vec<String>>,
+}
+
+/// A Python sub-interpreter.
+///
+/// Sub-interpreters have their own set of imported modules, `sys` state,
+/// and builtins, which allows isolating workloads from each other and from
+/// the main interpreter. They share the GIL and process-level state like
+/// environment variables and file descriptors with the main interpreter.
+///
+/// Instances are constructed via
+/// [crate::MainPythonInterpreter::new_sub_interpreter()]. They are bound to
+/// the thread that created them and can't outlive the main interpreter.
+///
+/// The in-memory importer isn't installed in sub-interpreters. Modules are
+/// imported with Python's default importers.
+///
+/// Dropping an instance calls `Py_EndInterpreter()` to finalize the
+/// sub-interpreter.
+pub struct SubInterpreter<'interpreter> {
+    tstate: *mut pyffi::PyThreadState,
+    _main: PhantomData<&'interpreter ()>,
+}
+
+impl<'interpreter> SubInterpreter<'interpreter> {
+    /// Create a new sub-interpreter.
+    ///
+    /// The main interpreter must be initialized.
+    pub(crate) fn new(config: &SubInterpreterConfig) -> Result<Self, NewInterpreterError> {
+        let tstate = unsafe {
+            let gil = pyffi::PyGILState_Ensure();
+            let main_tstate = pyffi::PyThreadState_Get();
+
+            // Swaps the current thread state to the new interpreter.
+            let tstate = pyffi::Py_NewInterpreter();
+
+            pyffi::PyThreadState_Swap(main_tstate);
+            pyffi::PyGILState_Release(gil);
+
+            tstate
+        };
+
+        if tstate.is_null() {
+            return Err(NewInterpreterError::Simple(
+                "unable to create Python sub-interpreter",
+            ));
+        }
+
+        let interp = Self {
+            tstate,
+            _main: PhantomData,
+        };
+
+        interp.with_gil(|py| {
+            interp.apply_config(py, config).map_err(|e| {
+                NewInterpreterError::new_from_pyerr(py, e, "configuring sub-interpreter")
+            })
+        })?;
+
+        Ok(interp)
+    }
+
+    fn apply_config(&self, py: Python, config: &SubInterpreterConfig) -> PyResult<()> {
+        let sys = py.import("sys")?;
+
+        if !config.sys_paths.is_empty() {
+            let sys_path = sys.getattr("path")?;
+
+            for (i, path) in config.sys_paths.iter().enumerate() {
+                sys_path.call_method1("insert", (i, path.display().to_string()))?;
+            }
+        }
+
+        if let Some(argv) = &config.argv {
+            sys.setattr("argv", argv.clone())?;
+        }
+
+        Ok(())
+    }
+
+    /// Run a function with the GIL held and this sub-interpreter active.
+    ///
+    /// Python objects obtained from the provided [Python] belong to this
+    /// sub-interpreter and must not be used with another interpreter.
+    pub fn with_gil<F, R>(&self, f: F) -> R
+    where
+        F: for<'py> FnOnce(Python<'py>) -> R,
+    {
+        unsafe {
+            let gil = pyffi::PyGILState_Ensure();
+            let previous = pyffi::PyThreadState_Swap(self.tstate);
+
+            // Objects owned by the pool are released while this
+            // sub-interpreter is still active.
+            let res = {
+                let pool = GILPool::new();
+                f(pool.python())
+            };
+
+            pyffi::PyThreadState_Swap(previous);
+            pyffi::PyGILState_Release(gil);
+
+            res
+        }
+    }
+}
+
+impl<'interpreter> Drop for SubInterpreter<'interpreter> {
+    fn drop(&mut self) {
+        // The main interpreter may have been finalized out-of-band, which
+        // also finalizes sub-interpreters.
+        if unsafe { pyffi::Py_IsInitialized() } == 0 {
+            return;
+        }
+
+        unsafe {
+            let gil = pyffi::PyGILState_Ensure();
+            let main_tstate = pyffi::PyThreadState_Get();
+
+            pyffi::PyThreadState_Swap(self.tstate);
+            pyffi::Py_EndInterpreter(self.tstate);
+
+            pyffi::PyThreadState_Swap(main_tstate);
+            pyffi::PyGILState_Release(gil);
+        }
+    }
+}
@@ -11,7 +11,10 @@ use {
         types::{PyBytes, PyList, PyString, PyStringData},
     },
     python_packaging::{
-        interpreter::{BytesWarning, MemoryAllocatorBackend, PythonInterpreterProfile},
+        interpreter::{
+            BytesWarning, MemoryAllocatorBackend, PythonInterpreterProfile, RunMode,
+            TerminfoResolution,
+        },
         resource::BytecodeOptimizationLevel,
     },
     rusty_fork::rusty_fork_test,
@@ -555,6 +558,74 @@ rusty_fork_test! {
         assert_eq!(config.tcl_library, Some(origin.join("lib").join("tcl8.6")));
     }
 
+    #[test]
+    fn test_qt_paths_origin() {
+        let mut config = default_interpreter_config();
+        config.qt_plugin_path = Some(PathBuf::from("$ORIGIN").join("lib").join("plugins"));
+        config.qml_import_path = Some(PathBuf::from("$ORIGIN").join("lib").join("qml"));
+
+        let config = config.resolve().unwrap();
+
+        let origin = std::env::current_exe()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .to_path_buf();
+
+        assert_eq!(
+            config.qt_plugin_path,
+            Some(origin.join("lib").join("plugins"))
+        );
+        assert_eq!(config.qml_import_path, Some(origin.join("lib").join("qml")));
+    }
+
+    #[test]
+    fn test_terminfo_static_origin() {
+        let mut config = default_interpreter_config();
+        config.terminfo_resolution = TerminfoResolution::Static("$ORIGIN/terminfo".to_string());
+
+        let config = config.resolve().unwrap();
+
+        let origin = std::env::current_exe()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .to_path_buf();
+
+        assert_eq!(
+            config.terminfo_resolution,
+            TerminfoResolution::Static(format!("{}/terminfo", origin.display()))
+        );
+    }
+
+    #[test]
+    fn test_extension_modules_extraction_path() {
+        let mut config = default_interpreter_config();
+        config.extension_modules_extraction_path = Some("myapp-extensions".into());
+
+        let config = config.resolve().unwrap();
+        assert_eq!(
+            config.extension_modules_extraction_path,
+            Some(std::env::temp_dir().join("myapp-extensions"))
+        );
+
+        let mut config = default_interpreter_config();
+        config.extension_modules_extraction_path = Some("$ORIGIN/extensions".into());
+
+        let config = config.resolve().unwrap();
+
+        let origin = std::env::current_exe()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .to_path_buf();
+
+        assert_eq!(
+            config.extension_modules_extraction_path,
+            Some(PathBuf::from(format!("{}/extensions", origin.display())))
+        );
+    }
+
     #[test]
     fn test_dev_mode() {
         let mut config = default_interpreter_config();
@@ -601,6 +672,22 @@ rusty_fork_test! {
         });
     }
 
+    #[cfg(Py_3_11)]
+    #[test]
+    fn test_safe_path() {
+        let mut config = default_interpreter_config();
+        config.interpreter_config.safe_path = Some(true);
+
+        let interp = MainPythonInterpreter::new(config).unwrap();
+
+        interp.with_gil(|py| {
+            let sys = py.import("sys").unwrap();
+
+            let flags = sys.getattr("flags").unwrap();
+            assert!(flags.getattr("safe_path").unwrap().extract::<bool>().unwrap());
+        });
+    }
+
     #[test]
     fn test_bytes_warning_warn() {
         let mut config = default_interpreter_config();
@@ -779,4 +866,117 @@ rusty_fork_test! {
             assert_eq!(flags.getattr("dont_write_bytecode").unwrap().extract::<i64>().unwrap(), 1);
         });
     }
+
+    #[test]
+    fn test_environment_variables() {
+        std::env::set_var("PYEMBED_TEST_EXISTING", "original");
+
+        let mut config = default_interpreter_config();
+        config
+            .environment_variables
+            .insert("PYEMBED_TEST_NEW".to_string(), "$ORIGIN/foo".to_string());
+        config
+            .environment_variables
+            .insert("PYEMBED_TEST_EXISTING".to_string(), "ignored".to_string());
+
+        let interp = MainPythonInterpreter::new(config).unwrap();
+
+        let origin = std::env::current_exe()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .to_path_buf();
+
+        interp.with_gil(|py| {
+            let environ = py.import("os").unwrap().getattr("environ").unwrap();
+
+            assert_eq!(
+                environ
+                    .get_item("PYEMBED_TEST_NEW")
+                    .unwrap()
+                    .extract::<String>()
+                    .unwrap(),
+                format!("{}/foo", origin.display())
+            );
+            assert_eq!(
+                environ
+                    .get_item("PYEMBED_TEST_EXISTING")
+                    .unwrap()
+                    .extract::<String>()
+                    .unwrap(),
+                "original"
+            );
+        });
+    }
+
+    #[test]
+    fn test_run_modes_fallback() {
+        let mut config = default_interpreter_config();
+        config.run_modes = vec![
+            RunMode::Module("does_not_exist.foo".to_string()),
+            RunMode::File,
+            RunMode::Module("json.tool".to_string()),
+            RunMode::Repl,
+        ];
+
+        let interp = MainPythonInterpreter::new(config).unwrap();
+
+        assert_eq!(
+            interp.resolve_run_mode().unwrap(),
+            Some(RunMode::Module("json.tool".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_run_modes_file() {
+        let mut config = default_interpreter_config();
+        config.argv.as_mut().unwrap().push(OsString::from("script.py"));
+        config.run_modes = vec![RunMode::File, RunMode::Repl];
+
+        let interp = MainPythonInterpreter::new(config).unwrap();
+
+        assert_eq!(interp.resolve_run_mode().unwrap(), Some(RunMode::File));
+    }
+
+    #[test]
+    fn test_run_modes_empty() {
+        let config = default_interpreter_config();
+
+        let interp = MainPythonInterpreter::new(config).unwrap();
+
+        assert_eq!(interp.resolve_run_mode().unwrap(), None);
+    }
+
+    #[test]
+    fn test_run_mode_flag() {
+        let mut config = default_interpreter_config();
+        config.argv.as_mut().unwrap().push(OsString::from("--run-mode"));
+        config.argv.as_mut().unwrap().push(OsString::from("eval:x = 42"));
+        config.argv.as_mut().unwrap().push(OsString::from("foo"));
+        config.run_modes = vec![RunMode::Repl];
+        config.run_mode_flag = Some("--run-mode".to_string());
+
+        let interp = MainPythonInterpreter::new(config).unwrap();
+
+        let mode = interp.resolve_run_mode().unwrap().unwrap();
+        assert_eq!(mode, RunMode::Eval("x = 42".to_string()));
+
+        interp.with_gil(|py| {
+            let sys = py.import("sys").unwrap();
+
+            let argv = sys
+                .getattr("argv")
+                .unwrap()
+                .extract::<Vec<String>>()
+                .unwrap();
+            assert_eq!(argv, vec![PYTHON_INTERPRETER_PATH, "foo"]);
+        });
+
+        assert_eq!(interp.run_mode(&mode).unwrap(), 0);
+
+        interp.with_gil(|py| {
+            let main = py.import("__main__").unwrap();
+            assert_eq!(main.getattr("x").unwrap().extract::<i64>().unwrap(), 42);
+        });
+    }
 }
@@ -4,9 +4,19 @@
 
 use {
     super::{default_interpreter_config, run_py_test},
-    crate::MainPythonInterpreter,
-    pyo3::ffi as pyffi,
+    crate::{
+        interpreter::{multiprocessing_helper_command, replace_missing_stdio},
+        InterpreterHooks, MainPythonInterpreter, SubInterpreterConfig,
+    },
+    pyo3::{exceptions::PyValueError, ffi as pyffi},
     rusty_fork::rusty_fork_test,
+    std::{
+        ffi::OsString,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    },
 };
 
 rusty_fork_test! {
@@ -35,8 +45,215 @@ rusty_fork_test! {
         std::mem::drop(interp);
     }
 
+    #[test]
+    fn test_replace_missing_stdio() {
+        let config = default_interpreter_config();
+        let interp = MainPythonInterpreter::new(config).unwrap();
+        interp.with_gil(|py| {
+            let sys = py.import("sys").unwrap();
+            let stdout = sys.getattr("stdout").unwrap();
+            sys.setattr("stderr", py.None()).unwrap();
+
+            replace_missing_stdio(py, sys).unwrap();
+
+            assert!(sys.getattr("stdout").unwrap().is(stdout));
+
+            let stderr = sys.getattr("stderr").unwrap();
+            assert!(!stderr.is_none());
+            stderr.call_method1("write", ("ignored",)).unwrap();
+        });
+    }
+
+    #[test]
+    fn test_lifecycle_hooks() {
+        let pre_init = Arc::new(AtomicUsize::new(0));
+        let pre_finalize = Arc::new(AtomicUsize::new(0));
+        let unhandled = Arc::new(AtomicUsize::new(0));
+
+        let mut hooks = InterpreterHooks::default();
+        {
+            let pre_init = pre_init.clone();
+            hooks.register_pre_init(move |_| {
+                assert_eq!(unsafe { pyffi::Py_IsInitialized() }, 0);
+                pre_init.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            });
+        }
+        hooks.register_post_init(|py| py.import("sys")?.setattr("post_init", true));
+        {
+            let pre_finalize = pre_finalize.clone();
+            hooks.register_pre_finalize(move |_| {
+                pre_finalize.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        {
+            let unhandled = unhandled.clone();
+            hooks.register_unhandled_exception(move |py, err| {
+                assert!(err.is_instance_of::<PyValueError>(py));
+                unhandled.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+
+        let config = default_interpreter_config();
+        let interp = MainPythonInterpreter::new_with_hooks(config, hooks).unwrap();
+        assert_eq!(pre_init.load(Ordering::SeqCst), 1);
+
+        interp.with_gil(|py| {
+            let sys = py.import("sys").unwrap();
+            assert!(sys.getattr("post_init").unwrap().extract::<bool>().unwrap());
+
+            PyValueError::new_err("unhandled").print(py);
+            assert_eq!(unhandled.load(Ordering::SeqCst), 1);
+        });
+
+        assert_eq!(pre_finalize.load(Ordering::SeqCst), 0);
+        std::mem::drop(interp);
+        assert_eq!(pre_finalize.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_post_init_hook_error() {
+        let mut hooks = InterpreterHooks::default();
+        hooks.register_post_init(|_| Err(PyValueError::new_err("post-init failure")));
+
+        let config = default_interpreter_config();
+        let err = MainPythonInterpreter::new_with_hooks(config, hooks)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("post-init failure"));
+    }
+
+    #[test]
+    fn test_sub_interpreter() {
+        let config = default_interpreter_config();
+        let interp = MainPythonInterpreter::new(config).unwrap();
+
+        let sub = interp
+            .new_sub_interpreter(&SubInterpreterConfig {
+                sys_paths: vec!["/sub-interpreter".into()],
+                argv: Some(vec!["plugin".to_string()]),
+            })
+            .unwrap();
+
+        sub.with_gil(|py| {
+            let sys = py.import("sys").unwrap();
+            sys.setattr("isolated_value", 42).unwrap();
+
+            let path = sys.getattr("path").unwrap().extract::<Vec<String>>().unwrap();
+            assert_eq!(path[0], "/sub-interpreter");
+
+            let argv = sys.getattr("argv").unwrap().extract::<Vec<String>>().unwrap();
+            assert_eq!(argv, vec!["plugin".to_string()]);
+        });
+
+        interp.with_gil(|py| {
+            let sys = py.import("sys").unwrap();
+            assert!(!sys.hasattr("isolated_value").unwrap());
+        });
+
+        sub.with_gil(|py| {
+            let sys = py.import("sys").unwrap();
+            assert_eq!(sys.getattr("isolated_value").unwrap().extract::<i64>().unwrap(), 42);
+        });
+
+        std::mem::drop(sub);
+
+        interp.with_gil(|py| {
+            py.import("sys").unwrap();
+        });
+    }
+
+    #[test]
+    fn test_call_helpers() {
+        let config = default_interpreter_config();
+        let interp = MainPythonInterpreter::new(config).unwrap();
+
+        let module = interp.import_module("os.path").unwrap();
+        interp.with_gil(|py| {
+            assert!(module.as_ref(py).hasattr("join").unwrap());
+        });
+
+        let res: i64 = interp.call("operator", "add", (1, 2)).unwrap();
+        assert_eq!(res, 3);
+
+        let res: Vec<i64> = interp.eval("[x * 2 for x in range(3)]").unwrap();
+        assert_eq!(res, vec![0, 2, 4]);
+
+        assert!(interp
+            .call::<_, String>("operator", "does_not_exist", ())
+            .is_err());
+
+        let res: i64 = interp
+            .run_async(|py| {
+                let locals = pyo3::types::PyDict::new(py);
+                py.run(
+                    "async def double(x):\n    return x * 2\n",
+                    None,
+                    Some(locals),
+                )?;
+                locals.get_item("double").unwrap().call1((21,))
+            })
+            .unwrap();
+        assert_eq!(res, 42);
+
+        let res: String = interp
+            .call_async("asyncio", "sleep", (0, "done"))
+            .unwrap();
+        assert_eq!(res, "done");
+    }
+
     #[test]
     fn multiprocessing_py() {
         run_py_test("test_multiprocessing.py").unwrap()
     }
 }
+
+fn argv(args: &[&str]) -> Vec<OsString> {
+    args.iter().map(OsString::from).collect()
+}
+
+#[test]
+fn multiprocessing_helper_command_detection() {
+    assert_eq!(multiprocessing_helper_command(&argv(&["app"])), None);
+    assert_eq!(
+        multiprocessing_helper_command(&argv(&["app", "-c", "print('hello')"])),
+        None
+    );
+    assert_eq!(
+        multiprocessing_helper_command(&argv(&["app", "--multiprocessing-fork", "pipe_handle=5"])),
+        None
+    );
+
+    assert_eq!(
+        multiprocessing_helper_command(&argv(&[
+            "app",
+            "-c",
+            "from multiprocessing.resource_tracker import main;main(5)"
+        ])),
+        Some("from multiprocessing.resource_tracker import main;main(5)".to_string())
+    );
+    assert_eq!(
+        multiprocessing_helper_command(&argv(&[
+            "app",
+            "-B",
+            "-W",
+            "ignore",
+            "-X",
+            "dev",
+            "-c",
+            "from multiprocessing.forkserver import main; main(3, 4, None)"
+        ])),
+        Some("from multiprocessing.forkserver import main; main(3, 4, None)".to_string())
+    );
+
+    // Application arguments before -c mean this isn't a helper process.
+    assert_eq!(
+        multiprocessing_helper_command(&argv(&[
+            "app",
+            "subcommand",
+            "-c",
+            "from multiprocessing.resource_tracker import main;main(5)"
+        ])),
+        None
+    );
+}
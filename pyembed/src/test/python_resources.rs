@@ -5,9 +5,12 @@
 use {
     crate::OxidizedPythonInterpreterConfig,
     anyhow::{anyhow, Result},
-    oxidized_importer::{PackedResourcesSource, PyTempDir, PythonResourcesState},
+    oxidized_importer::{
+        PackedResourcesSource, PyTempDir, PythonResourcesState, RuntimeResourceLocation,
+    },
     python_packed_resources::Resource,
     rusty_fork::rusty_fork_test,
+    std::path::PathBuf,
 };
 
 #[test]
@@ -99,6 +102,204 @@ fn test_memory_mapped_file_resources() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_package_source_paths() -> Result<()> {
+    let mut config = OxidizedPythonInterpreterConfig::default();
+    config
+        .package_source_paths
+        .insert("myapp".to_string(), PathBuf::from("$ORIGIN/src"));
+
+    let resolved = config.resolve()?;
+    let origin = resolved.origin().clone();
+    let resources = PythonResourcesState::try_from(&resolved)?;
+
+    let expected = vec![
+        RuntimeResourceLocation::Filesystem(PathBuf::from(format!("{}/src", origin.display()))),
+        RuntimeResourceLocation::InMemory,
+        RuntimeResourceLocation::FilesystemRelative,
+    ];
+
+    assert_eq!(
+        resources.package_resource_locations("myapp"),
+        Some(expected.as_slice())
+    );
+    assert_eq!(resources.resolve_resource_locations("myapp.sub"), expected);
+    assert_eq!(
+        resources.resolve_resource_locations("other"),
+        resources.resource_locations()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_plugin_paths() -> Result<()> {
+    let current_dir = std::env::current_exe()?
+        .parent()
+        .ok_or_else(|| anyhow!("unable to find current exe parent"))?
+        .to_path_buf();
+
+    if !current_dir.join("test_plugin_paths").exists() {
+        std::fs::create_dir(current_dir.join("test_plugin_paths"))?;
+    }
+
+    let mut config = OxidizedPythonInterpreterConfig::default();
+    config.plugin_paths = vec![
+        PathBuf::from("$ORIGIN/test_plugin_paths"),
+        PathBuf::from("$ORIGIN/test_plugin_paths_missing"),
+    ];
+
+    let resolved = config.resolve()?;
+    let plugins = resolved.origin().join("test_plugin_paths");
+    let resources = PythonResourcesState::try_from(&resolved)?;
+
+    assert_eq!(
+        resources.resource_locations(),
+        &[
+            RuntimeResourceLocation::InMemory,
+            RuntimeResourceLocation::FilesystemRelative,
+            RuntimeResourceLocation::Filesystem(plugins.clone()),
+        ]
+    );
+
+    let mut config = OxidizedPythonInterpreterConfig::default();
+    config.plugin_paths = vec![plugins.clone()];
+    config.plugin_paths_override = true;
+
+    let resolved = config.resolve()?;
+    let resources = PythonResourcesState::try_from(&resolved)?;
+
+    assert_eq!(
+        resources.resource_locations(),
+        &[
+            RuntimeResourceLocation::Filesystem(plugins),
+            RuntimeResourceLocation::InMemory,
+            RuntimeResourceLocation::FilesystemRelative,
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_file_emulation() -> Result<()> {
+    let resources =
+        PythonResourcesState::try_from(&OxidizedPythonInterpreterConfig::default().resolve()?)?;
+    assert!(!resources.resolve_file_emulation("foo"));
+
+    let mut config = OxidizedPythonInterpreterConfig::default();
+    config.file_emulation = true;
+    config.package_file_emulation = [("foo.bar".to_string(), false)].into_iter().collect();
+
+    let resources = PythonResourcesState::try_from(&config.resolve()?)?;
+
+    assert!(resources.file_emulation());
+    assert_eq!(resources.package_file_emulation("foo.bar"), Some(false));
+    assert!(resources.resolve_file_emulation("foo"));
+    assert!(!resources.resolve_file_emulation("foo.bar"));
+    assert!(!resources.resolve_file_emulation("foo.bar.baz"));
+    assert!(resources.resolve_file_emulation("foo.barbaz"));
+
+    Ok(())
+}
+
+#[test]
+fn test_data_blobs() -> Result<()> {
+    let mut state0 = PythonResourcesState::default();
+    for (namespace, path, data) in [
+        ("models", "en/model.bin", b"en".to_vec()),
+        ("models", "de/model.bin", b"de".to_vec()),
+        ("templates", "index.html", b"<html>".to_vec()),
+    ] {
+        state0
+            .add_resource(Resource {
+                name: format!("{}/{}", namespace, path).into(),
+                data_namespace: Some(namespace.into()),
+                file_data_embedded: Some(data.into()),
+                ..Default::default()
+            })
+            .unwrap();
+    }
+    let data0 = state0.serialize_resources(true, true)?;
+
+    let mut resources = PythonResourcesState::default();
+    resources.index_data(&data0).unwrap();
+
+    assert_eq!(resources.data_namespaces(), vec!["models", "templates"]);
+    assert_eq!(
+        resources.data_paths("models"),
+        vec!["de/model.bin", "en/model.bin"]
+    );
+    assert!(resources.data_paths("other").is_empty());
+    assert_eq!(
+        resources.resolve_data("models", "en/model.bin")?.as_deref(),
+        Some(b"en".as_ref())
+    );
+    assert!(resources.resolve_data("models", "index.html")?.is_none());
+    assert!(resources.resolve_data("other", "index.html")?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_merge_indexed_resources() -> Result<()> {
+    let module =
+        |name: &'static str, source: Option<Vec<u8>>, bytecode: Option<Vec<u8>>| Resource {
+            name: name.into(),
+            is_python_module: true,
+            in_memory_source: source.map(|v| v.into()),
+            in_memory_bytecode: bytecode.map(|v| v.into()),
+            ..Default::default()
+        };
+
+    let mut state0 = PythonResourcesState::default();
+    state0
+        .add_resource(module("foo", Some(vec![42]), None))
+        .unwrap();
+    state0
+        .add_resource(module("bar", Some(vec![42, 42]), None))
+        .unwrap();
+    let data0 = state0.serialize_resources(true, true)?;
+    assert!(data0.starts_with(python_packed_resources::HEADER_V4));
+
+    let mut state1 = PythonResourcesState::default();
+    state1
+        .add_resource(module("foo", None, Some(vec![1])))
+        .unwrap();
+    let data1 = state1.serialize_resources(true, true)?;
+
+    let mut data2 = vec![];
+    python_packed_resources::write_packed_resources_v3(
+        &[module("bar", None, Some(vec![2]))],
+        &mut data2,
+        None,
+    )?;
+
+    let mut resources = PythonResourcesState::default();
+    resources.index_data(&data0).unwrap();
+    resources.index_data(&data1).unwrap();
+    resources.index_data(&data2).unwrap();
+
+    assert!(resources.has_resource("foo"));
+    assert!(!resources.has_resource("baz"));
+
+    let merged = resources.serialize_resources(true, true)?;
+    let merged = python_packed_resources::load_resources(&merged)
+        .map_err(|e| anyhow!(e))?
+        .collect::<Result<Vec<_>, &'static str>>()
+        .map_err(|e| anyhow!(e))?;
+
+    assert_eq!(
+        merged,
+        vec![
+            module("bar", Some(vec![42, 42]), Some(vec![2])),
+            module("foo", Some(vec![42]), Some(vec![1])),
+        ]
+    );
+
+    Ok(())
+}
+
 fn get_interpreter<'interp, 'rsrc>() -> crate::MainPythonInterpreter<'interp, 'rsrc> {
     let mut config = crate::OxidizedPythonInterpreterConfig::default();
     config.interpreter_config.parse_argv = Some(false);
@@ -122,6 +122,29 @@ class TestImporterMetadata(unittest.TestCase):
         )
         self.assertEqual(len(dists), 1)
 
+    def test_find_distributions_name_normalization(self):
+        metadata_path = self.td / "zope.interface-1.0.dist-info" / "METADATA"
+        metadata_path.parent.mkdir()
+
+        with metadata_path.open("w", encoding="utf-8") as fh:
+            fh.write("Name: zope.interface\n")
+            fh.write("Version: 1.0\n")
+
+        f = self._finder_from_td()
+
+        for name in (
+            "zope.interface",
+            "zope-interface",
+            "Zope_Interface",
+            "zope--interface",
+        ):
+            dists = list(
+                f.find_distributions(
+                    importlib.metadata.DistributionFinder.Context(name=name)
+                )
+            )
+            self.assertEqual(len(dists), 1, name)
+
     def test_read_text(self):
         self._write_metadata()
         f = self._finder_from_td()
@@ -297,8 +320,94 @@ class TestImporterMetadata(unittest.TestCase):
 
         dist = list(f.find_distributions())[0]
 
-        with self.assertRaises(AttributeError):
-            dist.locate_file("METADATA")
+        path = dist.locate_file("my_package/__init__.py")
+        self.assertIsInstance(path, pathlib.Path)
+        self.assertEqual(
+            path, pathlib.Path(f.path_hook_base_str) / "my_package" / "__init__.py"
+        )
+
+    def test_files_missing(self):
+        self._write_metadata()
+        f = self._finder_from_td()
+
+        dist = list(f.find_distributions())[0]
+
+        self.assertIsNone(dist.files)
+
+    def test_files_record(self):
+        self._write_metadata()
+
+        with (self.td / "my_package-1.0.dist-info" / "RECORD").open(
+            "w", encoding="utf-8"
+        ) as fh:
+            fh.write("my_package/__init__.py,sha256=abcd,42\n")
+            fh.write("my_package-1.0.dist-info/RECORD,,\n")
+
+        f = self._finder_from_td()
+
+        dist = list(f.find_distributions())[0]
+        files = dist.files
+
+        self.assertIsInstance(files, list)
+        self.assertEqual(len(files), 2)
+
+        self.assertIsInstance(files[0], importlib.metadata.PackagePath)
+        self.assertEqual(str(files[0]), "my_package/__init__.py")
+        self.assertEqual(files[0].hash.mode, "sha256")
+        self.assertEqual(files[0].hash.value, "abcd")
+        self.assertEqual(files[0].size, 42)
+        self.assertIs(files[0].dist, dist)
+        self.assertEqual(
+            files[0].locate(), dist.locate_file("my_package/__init__.py")
+        )
+
+        self.assertEqual(str(files[1]), "my_package-1.0.dist-info/RECORD")
+        self.assertIsNone(files[1].hash)
+        self.assertIsNone(files[1].size)
+
+    def test_files_egg_info_sources(self):
+        pkginfo_path = self.td / "my_package-1.0.egg-info" / "PKG-INFO"
+        pkginfo_path.parent.mkdir()
+
+        with pkginfo_path.open("w", encoding="utf-8") as fh:
+            fh.write("Name: my_package\n")
+            fh.write("Version: 1.0\n")
+
+        with (self.td / "my_package-1.0.egg-info" / "SOURCES.txt").open(
+            "w", encoding="utf-8"
+        ) as fh:
+            fh.write("my_package/__init__.py\n")
+
+        f = self._finder_from_td()
+
+        dist = list(f.find_distributions())[0]
+        files = dist.files
+
+        self.assertEqual([str(p) for p in files], ["my_package/__init__.py"])
+        self.assertIsNone(files[0].hash)
+        self.assertIsNone(files[0].size)
+
+    def test_importlib_metadata_entry_points(self):
+        self._write_metadata()
+
+        entry_points_path = self.td / "my_package-1.0.dist-info" / "entry_points.txt"
+        with entry_points_path.open("w", encoding="utf-8") as fh:
+            fh.write("[my_plugins]\n")
+            fh.write("plugin = my_package:plugin\n")
+
+        f = self._finder_from_td()
+
+        sys.meta_path = [f]
+        sys.path = []
+
+        if sys.version_info >= (3, 10):
+            eps = list(importlib.metadata.entry_points(group="my_plugins"))
+        else:
+            eps = importlib.metadata.entry_points().get("my_plugins", [])
+
+        self.assertEqual(len(eps), 1)
+        self.assertEqual(eps[0].name, "plugin")
+        self.assertEqual(eps[0].value, "my_package:plugin")
 
     def test_distribution_from_name(self):
         self._write_metadata()
@@ -318,6 +427,19 @@ class TestImporterMetadata(unittest.TestCase):
         self.assertEqual(metadata["Name"], "my_package")
         self.assertEqual(metadata["Version"], "1.0")
 
+    def test_importlib_metadata_version(self):
+        self._write_metadata()
+        f = self._finder_from_td()
+
+        sys.meta_path = [f]
+        sys.path = []
+
+        self.assertEqual(importlib.metadata.version("my_package"), "1.0")
+        self.assertEqual(importlib.metadata.version("my-package"), "1.0")
+
+        with self.assertRaises(importlib.metadata.PackageNotFoundError):
+            importlib.metadata.version("missing")
+
     def test_distribution_discover(self):
         self._write_metadata()
         f = self._finder_from_td()
@@ -11,6 +11,7 @@ SYMBOL_ATTRIBUTES = {
         "entry_points",
         "files",
         "from_name",
+        "locate_file",
         "metadata",
         "name",
         "read_text",
@@ -21,13 +22,19 @@ SYMBOL_ATTRIBUTES = {
         "add_resource",
         "add_resources",
         "create_module",
+        "data_namespaces",
+        "data_paths",
         "exec_module",
+        "extension_modules_extraction_path",
+        "file_emulation",
         "find_distributions",
         "find_module",
         "find_spec",
         "get_code",
         "get_data",
         "get_filename",
+        "get_package_file_emulation",
+        "get_package_resource_locations",
         "get_resource_reader",
         "get_source",
         "index_bytes",
@@ -38,12 +45,17 @@ SYMBOL_ATTRIBUTES = {
         "indexed_resources",
         "invalidate_caches",
         "iter_modules",
+        "lazy_loading",
         "multiprocessing_set_start_method",
         "origin",
         "path_hook",
         "path_hook_base_str",
         "pkg_resources_import_auto_register",
+        "read_data",
+        "resource_locations",
         "serialize_indexed_resources",
+        "set_package_file_emulation",
+        "set_package_resource_locations",
     },
     "OxidizedPathEntryFinder": {
         "_package",
@@ -99,10 +111,23 @@ SYMBOL_ATTRIBUTES = {
     },
     "OxidizedResourceReader": {
         "contents",
+        "files",
         "is_resource",
         "open_resource",
         "resource_path",
     },
+    "OxidizedResourceTraversable": {
+        "__rtruediv__",
+        "__truediv__",
+        "is_dir",
+        "is_file",
+        "iterdir",
+        "joinpath",
+        "name",
+        "open",
+        "read_bytes",
+        "read_text",
+    },
     "OxidizedZipFinder": {
         "create_module",
         "exec_module",
@@ -14,6 +14,7 @@ import unittest
 
 from oxidized_importer import (
     OxidizedFinder,
+    OxidizedResource,
     OxidizedResourceCollector,
     find_resources_in_path,
 )
@@ -45,9 +46,9 @@ class TestImporterModuleLoading(unittest.TestCase):
 
         return package_path
 
-    def _finder_from_td(self):
+    def _finder_from_td(self, path=None):
         collector = OxidizedResourceCollector(allowed_locations=["in-memory"])
-        for r in find_resources_in_path(self.td):
+        for r in find_resources_in_path(path or self.td):
             collector.add_in_memory(r)
 
         f = OxidizedFinder()
@@ -182,6 +183,269 @@ class TestImporterModuleLoading(unittest.TestCase):
         self.assertIn("dotinit.bar", sys.modules)
         self.assertNotIn("dotinit.__init__", sys.modules)
 
+    def _namespace_finder(self, name):
+        r = OxidizedResource()
+        r.is_module = True
+        r.is_package = True
+        r.is_namespace_package = True
+        r.name = name
+
+        child = OxidizedResource()
+        child.is_module = True
+        child.name = "%s.packed" % name
+        child.in_memory_source = b"packed = True\n"
+
+        f = OxidizedFinder()
+        f.add_resources([r, child])
+
+        return f
+
+    def test_namespace_package(self):
+        f = self._namespace_finder("my_namespace")
+
+        spec = f.find_spec("my_namespace", None)
+        self.assertIsInstance(spec, importlib.machinery.ModuleSpec)
+        self.assertEqual(spec.name, "my_namespace")
+        self.assertIsNone(spec.loader)
+        self.assertIsNone(spec.origin)
+        self.assertEqual(
+            list(spec.submodule_search_locations),
+            [os.path.join(f.path_hook_base_str, "my_namespace")],
+        )
+
+        m = importlib.util.module_from_spec(spec)
+        self.assertEqual(m.__name__, "my_namespace")
+        self.assertFalse(hasattr(m, "__file__"))
+
+    def test_namespace_package_filesystem_portion(self):
+        portion = self.td / "my_namespace"
+        portion.mkdir()
+
+        with (portion / "on_disk.py").open("wb") as fh:
+            fh.write(b"on_disk = True\n")
+
+        f = self._namespace_finder("my_namespace")
+
+        spec = f.find_spec("my_namespace", [str(self.td)])
+        self.assertIsInstance(spec, importlib.machinery.ModuleSpec)
+        self.assertIsNone(spec.origin)
+        self.assertEqual(
+            list(spec.submodule_search_locations),
+            [str(portion), os.path.join(f.path_hook_base_str, "my_namespace")],
+        )
+
+        sys.meta_path.insert(0, f)
+        sys.path.insert(0, str(self.td))
+        try:
+            on_disk = importlib.import_module("my_namespace.on_disk")
+            packed = importlib.import_module("my_namespace.packed")
+        finally:
+            sys.path.remove(str(self.td))
+            for name in list(sys.modules):
+                if name == "my_namespace" or name.startswith("my_namespace."):
+                    del sys.modules[name]
+
+        self.assertTrue(on_disk.on_disk)
+        self.assertTrue(packed.packed)
+
+    def test_namespace_package_regular_package_precedence(self):
+        self._make_package("my_namespace")
+
+        f = self._namespace_finder("my_namespace")
+
+        self.assertIsNone(f.find_spec("my_namespace", [str(self.td)]))
+
+    def test_lazy_loading_default(self):
+        f = OxidizedFinder()
+        self.assertFalse(f.lazy_loading)
+
+        f.lazy_loading = True
+        self.assertTrue(f.lazy_loading)
+
+    def test_lazy_loading(self):
+        with (self.td / "lazy_module.py").open("wb") as fh:
+            fh.write(b"import sys\nsys._lazy_module_executed = True\nvalue = 42\n")
+
+        f = self._finder_from_td()
+        f.lazy_loading = True
+
+        spec = f.find_spec("lazy_module", None)
+        self.assertIsInstance(spec.loader, importlib.util.LazyLoader)
+
+        sys.meta_path.insert(0, f)
+        try:
+            m = importlib.import_module("lazy_module")
+            self.assertFalse(hasattr(sys, "_lazy_module_executed"))
+
+            self.assertEqual(m.value, 42)
+            self.assertTrue(sys._lazy_module_executed)
+            self.assertIs(m.__loader__, f)
+            self.assertIs(m.__spec__.loader, f)
+        finally:
+            sys.modules.pop("lazy_module", None)
+            if hasattr(sys, "_lazy_module_executed"):
+                del sys._lazy_module_executed
+
+
+    def _write_module(self, root, name, source):
+        parts = name.split(".")
+        path = root
+        for part in parts[:-1]:
+            path = path / part
+            path.mkdir(parents=True, exist_ok=True)
+            (path / "__init__.py").touch()
+
+        root.mkdir(parents=True, exist_ok=True)
+        with (path / ("%s.py" % parts[-1])).open("w", encoding="utf-8") as fh:
+            fh.write(source)
+
+    def test_resource_locations_default(self):
+        f = OxidizedFinder()
+        self.assertEqual(f.resource_locations, ["in-memory", "filesystem-relative"])
+
+        f.resource_locations = ["filesystem-relative", "filesystem:plugins"]
+        self.assertEqual(
+            f.resource_locations, ["filesystem-relative", "filesystem:plugins"]
+        )
+
+        with self.assertRaisesRegex(ValueError, "unknown resource location"):
+            f.resource_locations = ["bad"]
+
+        with self.assertRaises(ValueError):
+            f.resource_locations = ["filesystem:"]
+
+    def test_resource_locations_exclude_in_memory(self):
+        self._write_module(self.td, "my_module", "value = 1\n")
+        f = self._finder_from_td()
+        f.index_interpreter_builtins()
+
+        self.assertIsNotNone(f.find_spec("my_module", None))
+
+        f.resource_locations = ["filesystem-relative"]
+        self.assertIsNone(f.find_spec("my_module", None))
+
+        # Built-in modules are always available.
+        self.assertIsNotNone(f.find_spec("_io", None))
+
+    def test_resource_locations_filesystem(self):
+        embedded = self.td / "embedded"
+        plugins = self.td / "plugins"
+        self._write_module(embedded, "my_module", "value = 'embedded'\n")
+        self._write_module(plugins, "my_module", "value = 'plugin'\n")
+        self._write_module(plugins, "other_module", "value = 'other'\n")
+
+        f = self._finder_from_td(embedded)
+        f.resource_locations = ["in-memory", "filesystem:%s" % plugins]
+
+        spec = f.find_spec("my_module", None)
+        self.assertIs(spec.loader, f)
+
+        spec = f.find_spec("other_module", None)
+        self.assertIsInstance(spec.loader, importlib.machinery.SourceFileLoader)
+        self.assertEqual(spec.origin, str(plugins / "other_module.py"))
+
+        f.resource_locations = ["filesystem:%s" % plugins, "in-memory"]
+
+        spec = f.find_spec("my_module", None)
+        self.assertIsInstance(spec.loader, importlib.machinery.SourceFileLoader)
+        self.assertEqual(spec.origin, str(plugins / "my_module.py"))
+
+    def test_package_resource_locations(self):
+        embedded = self.td / "embedded"
+        plugins = self.td / "plugins"
+        self._write_module(embedded, "my_app.plugins.builtin", "value = 1\n")
+        self._write_module(plugins, "my_app.plugins.extra", "value = 2\n")
+
+        f = self._finder_from_td(embedded)
+        self.assertIsNone(f.get_package_resource_locations("my_app.plugins"))
+        self.assertIsNone(f.find_spec("my_app.plugins.extra", None))
+
+        f.set_package_resource_locations(
+            "my_app.plugins", ["in-memory", "filesystem:%s" % plugins]
+        )
+        self.assertEqual(
+            f.get_package_resource_locations("my_app.plugins"),
+            ["in-memory", "filesystem:%s" % plugins],
+        )
+
+        self.assertIs(f.find_spec("my_app.plugins.builtin", None).loader, f)
+
+        spec = f.find_spec("my_app.plugins.extra", None)
+        self.assertEqual(
+            spec.origin, str(plugins / "my_app" / "plugins" / "extra.py")
+        )
+
+        # Packages outside the override use the default locations.
+        f.resource_locations = []
+        self.assertIsNone(f.find_spec("my_app", None))
+        self.assertIsNotNone(f.find_spec("my_app.plugins", None))
+
+        f.set_package_resource_locations("my_app.plugins", None)
+        self.assertIsNone(f.get_package_resource_locations("my_app.plugins"))
+        self.assertIsNone(f.find_spec("my_app.plugins.extra", None))
+
+    def test_file_emulation(self):
+        p = self._make_package("my_package")
+        with (p / "my_module.py").open("wb") as fh:
+            fh.write(b"value = 1\n")
+        with (p / "resource.txt").open("wb") as fh:
+            fh.write(b"resource")
+
+        f = self._finder_from_td()
+        self.assertFalse(f.file_emulation)
+        self.assertIsNone(f.find_spec("my_package", None).origin)
+
+        f.file_emulation = True
+        self.assertTrue(f.file_emulation)
+
+        package_file = os.path.join(f.path_hook_base_str, "my_package", "__init__.py")
+        module_file = os.path.join(f.path_hook_base_str, "my_package", "my_module.py")
+
+        spec = f.find_spec("my_package", None)
+        self.assertEqual(spec.origin, package_file)
+        self.assertTrue(spec.has_location)
+        self.assertEqual(
+            spec.submodule_search_locations,
+            [os.path.join(f.path_hook_base_str, "my_package")],
+        )
+
+        m = importlib.util.module_from_spec(spec)
+        self.assertEqual(m.__file__, package_file)
+        self.assertFalse(hasattr(m, "__cached__"))
+        self.assertEqual(f.get_filename("my_package"), package_file)
+
+        spec = f.find_spec("my_package.my_module", None)
+        self.assertEqual(spec.origin, module_file)
+
+        # Resources next to the emulated file can be read via the loader.
+        self.assertEqual(
+            f.get_data(os.path.join(os.path.dirname(package_file), "resource.txt")),
+            b"resource",
+        )
+
+    def test_package_file_emulation(self):
+        p = self._make_package("my_package.child")
+        with (p / "my_module.py").open("wb") as fh:
+            fh.write(b"value = 1\n")
+
+        f = self._finder_from_td()
+        self.assertIsNone(f.get_package_file_emulation("my_package"))
+
+        f.set_package_file_emulation("my_package", True)
+        self.assertTrue(f.get_package_file_emulation("my_package"))
+        self.assertIsNotNone(f.find_spec("my_package", None).origin)
+        self.assertIsNotNone(f.find_spec("my_package.child.my_module", None).origin)
+
+        f.set_package_file_emulation("my_package.child", False)
+        self.assertIsNotNone(f.find_spec("my_package", None).origin)
+        self.assertIsNone(f.find_spec("my_package.child", None).origin)
+        self.assertIsNone(f.find_spec("my_package.child.my_module", None).origin)
+
+        f.set_package_file_emulation("my_package", None)
+        f.set_package_file_emulation("my_package.child", None)
+        self.assertIsNone(f.get_package_file_emulation("my_package"))
+        self.assertIsNone(f.find_spec("my_package", None).origin)
+
 
 if __name__ == "__main__":
     unittest.main()
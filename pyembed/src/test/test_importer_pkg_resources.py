@@ -232,7 +232,7 @@ class TestImporterPkgResources(unittest.TestCase):
         with self.assertRaises(NotImplementedError):
             provider.run_script("foo", "ns")
 
-        with self.assertRaises(NotImplementedError):
+        with self.assertRaises(IOError):
             provider.get_resource_filename(None, "foo")
 
         with self.assertRaises(IOError):
@@ -420,8 +420,51 @@ class TestImporterPkgResources(unittest.TestCase):
         with self.assertRaises(OSError):
             pkg_resources.resource_stream("package0.p0child0", "missing")
 
-        with self.assertRaises(NotImplementedError):
-            pkg_resources.resource_filename("package0", "file0")
+        manager = pkg_resources.ResourceManager()
+        manager.set_extraction_path(str(self.td / "extract"))
+
+        path = pathlib.Path(manager.resource_filename("package0", "file0"))
+        self.assertTrue(str(path).startswith(str(self.td / "extract")))
+        self.assertEqual(path.read_bytes(), b"foo")
+
+        # Extracting again reuses the existing file.
+        self.assertEqual(manager.resource_filename("package0", "file0"), str(path))
+
+        path = pathlib.Path(
+            manager.resource_filename("package0.p0child0", "childfile0")
+        )
+        self.assertEqual(path.read_bytes(), b"foo")
+
+        with self.assertRaises(OSError):
+            manager.resource_filename("package0", "missing")
+
+    def test_resource_filename_directory(self):
+        my_package_path = self.td / "my_package"
+        (my_package_path / "subdir" / "grandchild").mkdir(parents=True)
+
+        with (my_package_path / "__init__.py").open("wb"):
+            pass
+
+        with (my_package_path / "subdir" / "child0.txt").open("wb") as fh:
+            fh.write(b"child")
+
+        with (my_package_path / "subdir" / "grandchild" / "grandchild.txt").open(
+            "wb"
+        ) as fh:
+            fh.write(b"grandchild")
+
+        f = self._finder_from_td()
+        sys.meta_path.insert(0, f)
+
+        manager = pkg_resources.ResourceManager()
+        manager.set_extraction_path(str(self.td / "extract"))
+
+        path = pathlib.Path(manager.resource_filename("my_package", "subdir"))
+        self.assertTrue(path.is_dir())
+        self.assertEqual((path / "child0.txt").read_bytes(), b"child")
+        self.assertEqual(
+            (path / "grandchild" / "grandchild.txt").read_bytes(), b"grandchild"
+        )
 
 
 if __name__ == "__main__":
@@ -2,9 +2,12 @@
 # License, v. 2.0. If a copy of the MPL was not distributed with this
 # file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+import importlib.abc
+import importlib.resources
 import io
 import os
 import pathlib
+import sys
 import tempfile
 import unittest
 
@@ -12,6 +15,7 @@ from oxidized_importer import (
     OxidizedFinder,
     OxidizedResourceCollector,
     OxidizedResourceReader,
+    OxidizedResourceTraversable,
     find_resources_in_path,
 )
 
@@ -125,6 +129,61 @@ class TestImporterResourceReading(unittest.TestCase):
         self.assertEqual(r.open_resource("child0/a.txt").getvalue(), b"a")
         self.assertEqual(r.open_resource("child1/b.txt").getvalue(), b"b")
 
+    @unittest.skipIf(sys.version_info < (3, 9), "Traversable requires Python 3.9")
+    def test_files(self):
+        p = self._make_package("my_package")
+
+        with (p / "resource.txt").open("wb") as fh:
+            fh.write(b"my resource")
+
+        child_path = p / "child" / "grandchild"
+        child_path.mkdir(parents=True)
+
+        with (child_path / "a.txt").open("wb") as fh:
+            fh.write(b"a")
+
+        f = self._finder_from_td()
+        root = f.get_resource_reader("my_package").files()
+
+        self.assertIsInstance(root, OxidizedResourceTraversable)
+        self.assertIsInstance(root, importlib.abc.Traversable)
+        self.assertEqual(root.name, "my_package")
+        self.assertTrue(root.is_dir())
+        self.assertFalse(root.is_file())
+
+        self.assertEqual(
+            [(c.name, c.is_dir()) for c in root.iterdir()],
+            [("child", True), ("resource.txt", False)],
+        )
+
+        resource = root / "resource.txt"
+        self.assertTrue(resource.is_file())
+        self.assertFalse(resource.is_dir())
+        self.assertEqual(resource.read_bytes(), b"my resource")
+        self.assertEqual(resource.read_text(), "my resource")
+        self.assertEqual(resource.open("rb").read(), b"my resource")
+        self.assertEqual(resource.open().read(), "my resource")
+
+        with self.assertRaises(NotADirectoryError):
+            list(resource.iterdir())
+
+        a = root.joinpath("child", "grandchild", "a.txt")
+        self.assertEqual(a.name, "a.txt")
+        self.assertEqual(a.read_bytes(), b"a")
+        self.assertEqual(
+            [c.name for c in (root / "child").iterdir()], ["grandchild"]
+        )
+
+        missing = root / "missing.txt"
+        self.assertFalse(missing.is_file())
+        self.assertFalse(missing.is_dir())
+
+        with self.assertRaises(FileNotFoundError):
+            missing.read_bytes()
+
+        with importlib.resources.as_file(resource) as path:
+            self.assertEqual(path.read_bytes(), b"my resource")
+
 
 if __name__ == "__main__":
     unittest.main()
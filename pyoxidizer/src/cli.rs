@@ -4,6 +4,7 @@
 
 use {
     crate::{
+        content_cache,
         environment::{default_target_triple, PYOXIDIZER_VERSION},
         project_building, projectmgmt,
     },
@@ -23,6 +24,51 @@ existing PyOxidizer enabled project.
 
 This command will invoke Rust's build system tool (Cargo) to build
 the project.
+
+With --watch, the command keeps running after the build completes and
+rebuilds the requested targets whenever the configuration file, files it
+references, or other files in the project directory change.
+
+With --debug-starlark, evaluation of the configuration file pauses at calls
+to breakpoint() and before and after each target is resolved, presenting a
+prompt for inspecting values. Type `help` at the prompt for a list of
+commands.
+";
+
+const CACHE_ABOUT: &str = "\
+Manage PyOxidizer's content-addressed build cache.
+
+Builds store compiled Python bytecode, files downloaded by pip, and zstd
+compressed packed resources in this cache, keyed by a digest of their
+inputs, so later builds with the same inputs can reuse them.
+
+The cache is split into the following namespaces:
+
+bytecode
+   Compiled Python bytecode. Keyed on the Python distribution, source
+   content, filename, and optimization level.
+
+downloads
+   Files downloaded by pip. Keyed on their SHA-256. Reused when requirements
+   files pin hashes.
+
+segments
+   Packed resources segments holding a single zstd compressed resource.
+   Keyed on the compression level and the uncompressed resource content.
+";
+
+const CHECK_ABOUT: &str = "\
+Check a PyOxidizer configuration file for errors without building.
+
+The PATH argument is a filesystem path to a directory containing an
+existing PyOxidizer configuration file.
+
+The configuration file is scanned for uses of removed APIs. It is then
+evaluated and the function of every registered target is called, which
+validates arguments passed to functions and methods as well as attribute
+names. Errors in each target are reported separately. Nothing is built.
+
+The command exits with a non-zero status if any problems are found.
 ";
 
 const INIT_RUST_PROJECT_ABOUT: &str = "\
@@ -41,6 +87,11 @@ Created projects inherit settings such as Python distribution URLs and
 dependency crate versions and locations from the PyOxidizer executable
 they were created with.
 
+With --workspace, the project is added as a member of an existing Cargo
+workspace. The PATH must be inside the workspace directory. The
+workspace's Cargo.toml has the project added to its members list and the
+project uses the workspace's Cargo.lock, target directory, and profiles.
+
 On success, instructions on potential next steps are printed.
 ";
 
@@ -59,6 +110,9 @@ embedding of Python in a larger binary. It can be used to write:
   struct for configuring the embedded Python interpreter.
 * tcl/tk support files (for tkinter module support).
 * Microsoft Visual C++ Redistributable Runtime DLLs (Windows only).
+* Python header files, a C header describing the interpreter configuration,
+  a CMake package config file, and a pkg-config file for consuming these
+  files from C/C++ build systems.
 
 This command essentially does what the `run-build-script` command does except
 it doesn't require the presence of a PyOxidizer configuration file. Instead,
@@ -68,6 +122,23 @@ appropriate for your use case, you can always define a configuration file to
 customize them and use `run-build-script` to produce similar output files.
 ";
 
+const GENERATE_BAZEL_ABOUT: &str = "\
+Generate a Bazel repository for embedding Python in a binary.
+
+This writes the same files as `generate-python-embedding-artifacts` plus
+Bazel files exposing them as build targets:
+
+* `BUILD.bazel` defining a `python` `cc_library` linking the embedded
+  Python and filegroups for packed resources, the standard library, and
+  other files.
+* `python_embedding.bzl` defining a `python_embedded_cc_binary` macro.
+* `WORKSPACE.bazel` and `MODULE.bazel` so the directory can be consumed as
+  an external repository, e.g. via `local_repository()`.
+
+Only statically linked libpython is supported. On Windows, use
+`--flavor standalone_static`.
+";
+
 const RUN_BUILD_SCRIPT_ABOUT: &str = "\
 Runs a crate build script to generate Python artifacts.
 
@@ -79,6 +150,24 @@ This command executes the functionality to derive various artifacts and
 emits special lines that tell the Rust build system how to consume them.
 ";
 
+const TEST_ABOUT: &str = "\
+Build and run test targets in a PyOxidizer configuration file.
+
+Test targets are registered in the configuration file with register_test().
+Their functions typically return a PythonExecutable derived with
+PythonExecutable.to_test_target(), which runs a unittest or pytest entry
+point inside the built binary.
+
+Each test target is built and its binary executed. A target passes if its
+binary exits with status 0. Failures to build a target are reported as
+failures of that target.
+
+The TARGET arguments name the test targets to run. If none are given, all
+registered test targets are run.
+
+The command exits with a non-zero status if any test target fails.
+";
+
 const RESOURCES_SCAN_ABOUT: &str = "\
 Scan a directory or file for Python resources.
 
@@ -225,13 +314,31 @@ pub fn run_cli() -> Result<()> {
         );
 
     let app = app.subcommand(
-        Command::new("analyze").about("Analyze a built binary").arg(
-            Arg::new("path")
-                .action(ArgAction::Set)
-                .value_parser(value_parser!(PathBuf))
-                .required(true)
-                .help("Path to executable to analyze"),
-        ),
+        Command::new("analyze")
+            .about("Analyze a built binary")
+            .arg(
+                Arg::new("dependency_graph")
+                    .long("dependency-graph")
+                    .action(ArgAction::Set)
+                    .value_name("FORMAT")
+                    .value_parser(["json", "dot", "dot-modules"])
+                    .help("Emit the Python module dependency graph of the binary instead"),
+            )
+            .arg(
+                Arg::new("output")
+                    .long("output")
+                    .action(ArgAction::Set)
+                    .value_parser(value_parser!(PathBuf))
+                    .requires("dependency_graph")
+                    .help("File to write the dependency graph to instead of stdout"),
+            )
+            .arg(
+                Arg::new("path")
+                    .action(ArgAction::Set)
+                    .value_parser(value_parser!(PathBuf))
+                    .required(true)
+                    .help("Path to executable to analyze"),
+            ),
     );
 
     let app = app.subcommand(add_env_args(
@@ -259,6 +366,18 @@ pub fn run_cli() -> Result<()> {
                     .value_name("PATH")
                     .help("Directory containing project to build"),
             )
+            .arg(
+                Arg::new("watch")
+                    .long("watch")
+                    .action(ArgAction::SetTrue)
+                    .help("Rebuild targets when source files change"),
+            )
+            .arg(
+                Arg::new("debug_starlark")
+                    .long("debug-starlark")
+                    .action(ArgAction::SetTrue)
+                    .help("Debug evaluation of the configuration file interactively"),
+            )
             .arg(
                 Arg::new("targets")
                     .value_name("TARGET")
@@ -268,9 +387,52 @@ pub fn run_cli() -> Result<()> {
             ),
     ));
 
+    let app = app.subcommand(
+        Command::new("cache")
+            .about("Manage PyOxidizer's content-addressed build cache")
+            .long_about(CACHE_ABOUT)
+            .subcommand_required(true)
+            .subcommand(Command::new("info").about("Show the size of each cache namespace"))
+            .subcommand(
+                Command::new("purge").about("Remove cached data").arg(
+                    Arg::new("namespace")
+                        .long("namespace")
+                        .action(ArgAction::Set)
+                        .value_parser(content_cache::NAMESPACES.to_vec())
+                        .help("Only remove data in this namespace"),
+                ),
+            ),
+    );
+
     let app =
         app.subcommand(Command::new("cache-clear").about("Clear PyOxidizer's user-specific cache"));
 
+    let app = app.subcommand(add_env_args(
+        Command::new("check")
+            .about("Check a PyOxidizer configuration file for errors")
+            .long_about(CHECK_ABOUT)
+            .arg(
+                Arg::new("target_triple")
+                    .long("target-triple")
+                    .action(ArgAction::Set)
+                    .help("Rust target triple to evaluate for"),
+            )
+            .arg(
+                Arg::new("release")
+                    .long("release")
+                    .action(ArgAction::SetTrue)
+                    .help("Evaluate in release mode"),
+            )
+            .arg(
+                Arg::new("path")
+                    .action(ArgAction::Set)
+                    .value_parser(value_parser!(PathBuf))
+                    .default_value(".")
+                    .value_name("PATH")
+                    .help("Directory containing project to check"),
+            ),
+    ));
+
     let app = app.subcommand(
         Command::new("find-resources")
             .about("Find resources in a file or directory")
@@ -318,6 +480,20 @@ pub fn run_cli() -> Result<()> {
             ),
     );
 
+    let app = app.subcommand(add_python_distribution_args(
+        Command::new("generate-bazel")
+            .about("Generate a Bazel repository for embedding Python in a binary")
+            .long_about(GENERATE_BAZEL_ABOUT)
+            .arg(
+                Arg::new("dest_path")
+                    .action(ArgAction::Set)
+                    .value_parser(value_parser!(PathBuf))
+                    .value_name("DESTINATION_PATH")
+                    .required(true)
+                    .help("Output directory for written files"),
+            ),
+    ));
+
     let app = app.subcommand(add_python_distribution_args(
         Command::new("generate-python-embedding-artifacts")
             .about("Generate files useful for embedding Python in a [Rust] binary")
@@ -366,6 +542,14 @@ pub fn run_cli() -> Result<()> {
                     .required(true)
                     .value_name("PATH")
                     .help("Path of project directory to create"),
+            )
+            .arg(
+                Arg::new("workspace")
+                    .long("workspace")
+                    .action(ArgAction::Set)
+                    .value_parser(value_parser!(PathBuf))
+                    .value_name("WORKSPACE")
+                    .help("Root directory of an existing Cargo workspace to add the project to"),
             ),
     );
 
@@ -478,6 +662,19 @@ pub fn run_cli() -> Result<()> {
                     .action(ArgAction::Set)
                     .help("Build target to run"),
             )
+            .arg(
+                Arg::new("dev")
+                    .long("dev")
+                    .action(ArgAction::SetTrue)
+                    .help("Import application packages from the source checkout"),
+            )
+            .arg(
+                Arg::new("trace_imports")
+                    .long("trace-imports")
+                    .action(ArgAction::Set)
+                    .value_name("PATH")
+                    .help("Record imported modules to a resource filter file"),
+            )
             .arg(Arg::new("extra").action(ArgAction::Append).num_args(0..)),
     ));
 
@@ -511,6 +708,39 @@ pub fn run_cli() -> Result<()> {
             ),
     );
 
+    let app = app.subcommand(add_env_args(
+        Command::new("test")
+            .about("Build and run test targets in a PyOxidizer configuration file")
+            .long_about(TEST_ABOUT)
+            .arg(
+                Arg::new("target_triple")
+                    .long("target-triple")
+                    .action(ArgAction::Set)
+                    .help("Rust target triple to build for"),
+            )
+            .arg(
+                Arg::new("release")
+                    .long("release")
+                    .action(ArgAction::SetTrue)
+                    .help("Build release binaries"),
+            )
+            .arg(
+                Arg::new("path")
+                    .long("path")
+                    .action(ArgAction::Set)
+                    .value_parser(value_parser!(PathBuf))
+                    .default_value(".")
+                    .value_name("PATH")
+                    .help("Directory containing project to test"),
+            )
+            .arg(
+                Arg::new("targets")
+                    .action(ArgAction::Append)
+                    .value_name("TARGET")
+                    .help("Test target to run"),
+            ),
+    ));
+
     let matches = app.get_matches();
 
     let verbose = matches.contains_id("verbose");
@@ -545,9 +775,17 @@ pub fn run_cli() -> Result<()> {
         "analyze" => {
             let path = args.get_one::<PathBuf>("path").unwrap();
 
-            tugger_binary_analysis::analyze_file(path.clone());
+            if let Some(format) = args.get_one::<String>("dependency_graph") {
+                projectmgmt::analyze_dependency_graph(
+                    path,
+                    format,
+                    args.get_one::<PathBuf>("output").map(|x| x.as_path()),
+                )
+            } else {
+                tugger_binary_analysis::analyze_file(path.clone());
 
-            Ok(())
+                Ok(())
+            }
         }
 
         "build" => {
@@ -555,6 +793,8 @@ pub fn run_cli() -> Result<()> {
             let release = args.get_flag("release");
             let target_triple = args.get_one::<String>("target_triple");
             let path = args.get_one::<PathBuf>("path").unwrap();
+            let watch = args.get_flag("watch");
+            let debug_starlark = args.get_flag("debug_starlark");
             let resolve_targets = args
                 .get_many::<String>("targets")
                 .map(|x| x.cloned().collect::<Vec<_>>());
@@ -567,11 +807,38 @@ pub fn run_cli() -> Result<()> {
                 starlark_vars,
                 release,
                 verbose,
+                watch,
+                debug_starlark,
             )
         }
 
+        "cache" => match args.subcommand() {
+            Some(("info", _)) => projectmgmt::cache_info(&env),
+            Some(("purge", args)) => projectmgmt::cache_purge(
+                &env,
+                args.get_one::<String>("namespace").map(|x| x.as_str()),
+            ),
+            _ => Err(anyhow!("invalid sub-command")),
+        },
+
         "cache-clear" => projectmgmt::cache_clear(&env),
 
+        "check" => {
+            let starlark_vars = starlark_vars(args)?;
+            let release = args.get_flag("release");
+            let target_triple = args.get_one::<String>("target_triple");
+            let path = args.get_one::<PathBuf>("path").unwrap();
+
+            projectmgmt::check(
+                &env,
+                path,
+                target_triple.map(|x| x.as_str()),
+                starlark_vars,
+                release,
+                verbose,
+            )
+        }
+
         "find-resources" => {
             let path = args.get_one::<PathBuf>("path");
             let distributions_dir = args.get_one::<PathBuf>("distributions_dir");
@@ -595,6 +862,27 @@ pub fn run_cli() -> Result<()> {
             }
         }
 
+        "generate-bazel" => {
+            let target_triple = args
+                .get_one::<String>("target_triple")
+                .expect("target_triple should have default");
+            let flavor = args
+                .get_one::<String>("flavor")
+                .expect("flavor should have default");
+            let python_version = args.get_one::<String>("python_version");
+            let dest_path = args
+                .get_one::<PathBuf>("dest_path")
+                .expect("dest_path should be required");
+
+            projectmgmt::generate_bazel(
+                &env,
+                target_triple,
+                flavor,
+                python_version.map(|x| x.as_str()),
+                dest_path,
+            )
+        }
+
         "generate-python-embedding-artifacts" => {
             let target_triple = args
                 .get_one::<String>("target_triple")
@@ -644,7 +932,9 @@ pub fn run_cli() -> Result<()> {
             let path = args.get_one::<String>("path").unwrap();
             let project_path = Path::new(path);
 
-            projectmgmt::init_rust_project(&env, project_path)
+            let workspace = args.get_one::<PathBuf>("workspace");
+
+            projectmgmt::init_rust_project(&env, project_path, workspace.map(|x| x.as_path()))
         }
 
         "python-distribution-extract" => {
@@ -698,6 +988,8 @@ pub fn run_cli() -> Result<()> {
             let release = args.get_flag("release");
             let path = args.get_one::<String>("path").unwrap();
             let target = args.get_one::<String>("target");
+            let dev = args.get_flag("dev");
+            let trace_imports = args.get_one::<String>("trace_imports");
             let extra = args
                 .get_many::<String>("extra")
                 .unwrap_or_default()
@@ -710,6 +1002,8 @@ pub fn run_cli() -> Result<()> {
                 target_triple.map(|x| x.as_str()),
                 release,
                 target.map(|x| x.as_str()),
+                dev,
+                trace_imports.map(Path::new),
                 starlark_vars,
                 &extra,
                 verbose,
@@ -733,6 +1027,28 @@ pub fn run_cli() -> Result<()> {
             )
         }
 
+        "test" => {
+            let starlark_vars = starlark_vars(args)?;
+            let target_triple = args.get_one::<String>("target_triple");
+            let release = args.get_flag("release");
+            let path = args.get_one::<PathBuf>("path").unwrap();
+            let targets = args
+                .get_many::<String>("targets")
+                .unwrap_or_default()
+                .map(|x| x.as_str())
+                .collect::<Vec<_>>();
+
+            projectmgmt::test(
+                &env,
+                path,
+                target_triple.map(|x| x.as_str()),
+                release,
+                &targets,
+                starlark_vars,
+                verbose,
+            )
+        }
+
         _ => Err(anyhow!("invalid sub-command")),
     }
 }
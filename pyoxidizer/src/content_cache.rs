@@ -0,0 +1,631 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*!
+Content-addressed storage of build artifacts.
+
+Expensive build steps store their outputs in a [ContentCache], keyed by a
+digest of everything that influences the output. Subsequent builds with
+identical inputs reuse the stored outputs instead of redoing the work.
+
+Entries are grouped into namespaces. Each entry is a directory named after its
+digest holding a single file. Entries are immutable once written, so the cache
+can be shared by concurrent processes.
+*/
+
+use {
+    anyhow::{anyhow, Context, Result},
+    log::warn,
+    python_packaging::{
+        bytecode::{CompileMode, PythonBytecodeCompiler},
+        resource::BytecodeOptimizationLevel,
+        resource_collection::CompiledResourcesCollection,
+    },
+    python_packed_resources::{load_resources, write_packed_resources_v3, Resource},
+    sha2::{Digest, Sha256},
+    std::path::{Path, PathBuf},
+    uuid::Uuid,
+};
+
+/// Namespace holding compiled Python bytecode.
+pub const NAMESPACE_BYTECODE: &str = "bytecode";
+
+/// Namespace holding files downloaded by pip, keyed by their SHA-256.
+pub const NAMESPACE_DOWNLOADS: &str = "downloads";
+
+/// Namespace holding packed resources segments of zstd compressed resources.
+pub const NAMESPACE_SEGMENTS: &str = "segments";
+
+/// All known namespaces.
+pub const NAMESPACES: &[&str] = &[NAMESPACE_BYTECODE, NAMESPACE_DOWNLOADS, NAMESPACE_SEGMENTS];
+
+/// Filename of data in entries that don't have a meaningful filename.
+const DATA_FILENAME: &str = "data";
+
+/// Describes the content of a namespace in a [ContentCache].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NamespaceStats {
+    /// Name of the namespace.
+    pub namespace: String,
+
+    /// Number of entries in the namespace.
+    pub entries: u64,
+
+    /// Total size in bytes of files in the namespace.
+    pub size: u64,
+}
+
+/// A content-addressed store of build artifacts.
+#[derive(Clone, Debug)]
+pub struct ContentCache {
+    root: PathBuf,
+}
+
+impl ContentCache {
+    /// Construct an instance storing data in the given directory.
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    /// The directory holding cached data.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Compute the digest identifying an entry from the inputs that produce it.
+    ///
+    /// Parts are length prefixed so different splits of the same bytes
+    /// produce different digests.
+    pub fn digest(parts: &[&[u8]]) -> String {
+        let mut hasher = Sha256::new();
+
+        for part in parts {
+            hasher.update((part.len() as u64).to_le_bytes());
+            hasher.update(part);
+        }
+
+        hex::encode(hasher.finalize())
+    }
+
+    fn validate_namespace(namespace: &str) -> Result<()> {
+        if NAMESPACES.contains(&namespace) {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "unknown cache namespace {}; must be one of {}",
+                namespace,
+                NAMESPACES.join(", ")
+            ))
+        }
+    }
+
+    fn entry_dir(&self, namespace: &str, digest: &str) -> PathBuf {
+        self.root
+            .join(namespace)
+            .join(&digest[0..2.min(digest.len())])
+            .join(digest)
+    }
+
+    /// Resolve the path of a file stored in an entry, if present.
+    pub fn get_path(&self, namespace: &str, digest: &str) -> Result<Option<PathBuf>> {
+        let entry_dir = self.entry_dir(namespace, digest);
+
+        if !entry_dir.is_dir() {
+            return Ok(None);
+        }
+
+        Ok(std::fs::read_dir(&entry_dir)
+            .with_context(|| format!("reading {}", entry_dir.display()))?
+            .next()
+            .transpose()?
+            .map(|entry| entry.path()))
+    }
+
+    /// Obtain the data stored in an entry, if present.
+    pub fn get(&self, namespace: &str, digest: &str) -> Result<Option<Vec<u8>>> {
+        if let Some(path) = self.get_path(namespace, digest)? {
+            Ok(Some(
+                std::fs::read(&path).with_context(|| format!("reading {}", path.display()))?,
+            ))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Store data in an entry under the given filename.
+    ///
+    /// Existing entries are left as is, since an entry's content is fully
+    /// determined by its digest.
+    pub fn put_named(
+        &self,
+        namespace: &str,
+        digest: &str,
+        filename: &str,
+        data: &[u8],
+    ) -> Result<PathBuf> {
+        Self::validate_namespace(namespace)?;
+
+        let entry_dir = self.entry_dir(namespace, digest);
+        let dest_path = entry_dir.join(filename);
+
+        if entry_dir.exists() {
+            return Ok(dest_path);
+        }
+
+        let parent = entry_dir
+            .parent()
+            .ok_or_else(|| anyhow!("unable to resolve parent directory"))?;
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("creating {}", parent.display()))?;
+
+        // Populate a temporary directory and rename it into place so other
+        // processes never see a partially written entry.
+        let temp_dir = parent.join(format!(".tmp-{}", Uuid::new_v4()));
+        std::fs::create_dir(&temp_dir)
+            .with_context(|| format!("creating {}", temp_dir.display()))?;
+        std::fs::write(temp_dir.join(filename), data)
+            .with_context(|| format!("writing {}", temp_dir.display()))?;
+
+        if let Err(e) = std::fs::rename(&temp_dir, &entry_dir) {
+            std::fs::remove_dir_all(&temp_dir)
+                .with_context(|| format!("removing {}", temp_dir.display()))?;
+
+            // Another process won the race to populate the entry.
+            if !entry_dir.exists() {
+                return Err(e).with_context(|| format!("renaming to {}", entry_dir.display()));
+            }
+        }
+
+        Ok(dest_path)
+    }
+
+    /// Store data in an entry.
+    pub fn put(&self, namespace: &str, digest: &str, data: &[u8]) -> Result<()> {
+        self.put_named(namespace, digest, DATA_FILENAME, data)?;
+
+        Ok(())
+    }
+
+    /// Obtain statistics about each namespace.
+    pub fn stats(&self) -> Result<Vec<NamespaceStats>> {
+        NAMESPACES
+            .iter()
+            .map(|namespace| {
+                let mut stats = NamespaceStats {
+                    namespace: namespace.to_string(),
+                    entries: 0,
+                    size: 0,
+                };
+
+                let path = self.root.join(namespace);
+                if !path.exists() {
+                    return Ok(stats);
+                }
+
+                for entry in walkdir::WalkDir::new(&path).min_depth(2).max_depth(3) {
+                    let entry = entry?;
+
+                    if entry.depth() == 2 && entry.file_type().is_dir() {
+                        stats.entries += 1;
+                    } else if entry.depth() == 3 && entry.file_type().is_file() {
+                        stats.size += entry.metadata()?.len();
+                    }
+                }
+
+                Ok(stats)
+            })
+            .collect()
+    }
+
+    /// Remove cached data.
+    ///
+    /// If `namespace` is defined, only that namespace is removed.
+    pub fn purge(&self, namespace: Option<&str>) -> Result<()> {
+        let path = if let Some(namespace) = namespace {
+            Self::validate_namespace(namespace)?;
+            self.root.join(namespace)
+        } else {
+            self.root.clone()
+        };
+
+        if path.exists() {
+            remove_dir_all::remove_dir_all(&path)
+                .with_context(|| format!("removing {}", path.display()))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A [PythonBytecodeCompiler] that stores results in a [ContentCache].
+///
+/// Entries are keyed on an identifier of the interpreter performing
+/// compilation, its bytecode magic number, and all compilation inputs.
+pub struct CachingBytecodeCompiler<'a> {
+    inner: &'a mut dyn PythonBytecodeCompiler,
+    cache: ContentCache,
+    interpreter: String,
+}
+
+impl<'a> CachingBytecodeCompiler<'a> {
+    /// Construct an instance wrapping another compiler.
+    ///
+    /// `interpreter` identifies the interpreter used by `inner`. It should
+    /// change whenever the interpreter could produce different bytecode, e.g.
+    /// by incorporating the hash of its distribution.
+    pub fn new(
+        inner: &'a mut dyn PythonBytecodeCompiler,
+        cache: ContentCache,
+        interpreter: impl ToString,
+    ) -> Self {
+        Self {
+            inner,
+            cache,
+            interpreter: interpreter.to_string(),
+        }
+    }
+}
+
+impl<'a> PythonBytecodeCompiler for CachingBytecodeCompiler<'a> {
+    fn get_magic_number(&self) -> u32 {
+        self.inner.get_magic_number()
+    }
+
+    fn compile(
+        &mut self,
+        source: &[u8],
+        filename: &str,
+        optimize: BytecodeOptimizationLevel,
+        output_mode: CompileMode,
+    ) -> Result<Vec<u8>> {
+        let optimize_byte = match optimize {
+            BytecodeOptimizationLevel::Zero => 0u8,
+            BytecodeOptimizationLevel::One => 1,
+            BytecodeOptimizationLevel::Two => 2,
+        };
+        let mode_byte = match output_mode {
+            CompileMode::Bytecode => 0u8,
+            CompileMode::PycCheckedHash => 1,
+            CompileMode::PycUncheckedHash => 2,
+        };
+
+        let digest = ContentCache::digest(&[
+            self.interpreter.as_bytes(),
+            &self.inner.get_magic_number().to_le_bytes(),
+            filename.as_bytes(),
+            &[optimize_byte, mode_byte],
+            source,
+        ]);
+
+        // Cache failures shouldn't fail the build. We just do the work instead.
+        match self.cache.get(NAMESPACE_BYTECODE, &digest) {
+            Ok(Some(data)) => return Ok(data),
+            Ok(None) => {}
+            Err(e) => warn!("error reading bytecode cache: {:?}", e),
+        }
+
+        let data = self
+            .inner
+            .compile(source, filename, optimize, output_mode)?;
+
+        if let Err(e) = self.cache.put(NAMESPACE_BYTECODE, &digest, &data) {
+            warn!("error writing bytecode cache: {:?}", e);
+        }
+
+        Ok(data)
+    }
+}
+
+/// Obtain a copy of a resource without its mapping fields.
+///
+/// Iteration order of mappings isn't deterministic, so they can't be
+/// serialized to derive a digest.
+fn without_maps<'a>(resource: &Resource<'a, u8>) -> Resource<'a, u8> {
+    Resource {
+        in_memory_package_resources: None,
+        in_memory_distribution_resources: None,
+        relative_path_package_resources: None,
+        relative_path_distribution_resources: None,
+        ..resource.clone()
+    }
+}
+
+/// Compute the digest of the segment holding a compressed resource.
+///
+/// Relative path mappings aren't compressed and don't contribute.
+fn segment_digest(resource: &Resource<u8>, level: i32) -> Result<String> {
+    let mut serialized = vec![];
+    write_packed_resources_v3(&[without_maps(resource)], &mut serialized, None)?;
+
+    let maps = [
+        &resource.in_memory_package_resources,
+        &resource.in_memory_distribution_resources,
+    ];
+
+    // Distinguishes missing and empty mappings.
+    let lengths = maps
+        .iter()
+        .map(|map| {
+            map.as_ref()
+                .map_or(0, |map| map.len() as u64 + 1)
+                .to_le_bytes()
+        })
+        .collect::<Vec<_>>();
+
+    let level = level.to_le_bytes();
+    let mut parts: Vec<&[u8]> = vec![b"zstd".as_ref(), level.as_ref(), serialized.as_ref()];
+
+    for (map, length) in maps.iter().zip(&lengths) {
+        parts.push(length);
+
+        let mut entries = map.iter().flatten().collect::<Vec<_>>();
+        entries.sort();
+
+        for (key, value) in entries {
+            parts.push(key.as_bytes());
+            parts.push(value);
+        }
+    }
+
+    Ok(ContentCache::digest(&parts))
+}
+
+/// Parse a segment holding a single resource named `name`.
+fn parse_segment(data: &[u8], name: &str) -> Result<Resource<'static, u8>> {
+    let mut resources = load_resources(data).map_err(|e| anyhow!(e))?;
+
+    match (resources.next(), resources.next()) {
+        (Some(Ok(resource)), None) if resource.name == name => Ok(resource.to_owned()),
+        _ => Err(anyhow!("segment does not hold resource {}", name)),
+    }
+}
+
+/// Compress in-memory data of resources with zstd, reusing cached results.
+///
+/// Each compressed resource is stored in the segments namespace as packed
+/// resources data holding just that resource. Entries are keyed on the
+/// compression level and the content of the uncompressed resource.
+///
+/// See [Resource::to_zstd_compressed] for which data is compressed.
+pub fn zstd_compress_resources_cached(
+    cache: &ContentCache,
+    resources: &mut CompiledResourcesCollection,
+    level: i32,
+) -> Result<()> {
+    for (name, resource) in resources.resources.iter_mut() {
+        let digest = segment_digest(resource, level)
+            .with_context(|| format!("computing segment digest of {}", name))?;
+
+        // Cache failures shouldn't fail the build. We just do the work instead.
+        let cached = match cache.get(NAMESPACE_SEGMENTS, &digest) {
+            Ok(Some(data)) => match parse_segment(&data, name) {
+                Ok(compressed) => Some(compressed),
+                Err(e) => {
+                    warn!("error parsing segments cache entry: {:?}", e);
+                    None
+                }
+            },
+            Ok(None) => None,
+            Err(e) => {
+                warn!("error reading segments cache: {:?}", e);
+                None
+            }
+        };
+
+        let compressed = if let Some(compressed) = cached {
+            compressed
+        } else {
+            let compressed = resource
+                .to_zstd_compressed(level)
+                .with_context(|| format!("compressing {}", name))?;
+
+            let segment_resource = Resource {
+                relative_path_package_resources: None,
+                relative_path_distribution_resources: None,
+                ..compressed.clone()
+            };
+
+            let mut segment = vec![];
+            write_packed_resources_v3(&[segment_resource], &mut segment, None)
+                .with_context(|| format!("serializing segment of {}", name))?;
+
+            if let Err(e) = cache.put(NAMESPACE_SEGMENTS, &digest, &segment) {
+                warn!("error writing segments cache: {:?}", e);
+            }
+
+            compressed
+        };
+
+        *resource = Resource {
+            relative_path_package_resources: resource.relative_path_package_resources.take(),
+            relative_path_distribution_resources: resource
+                .relative_path_distribution_resources
+                .take(),
+            ..compressed
+        };
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct CountingCompiler {
+        calls: usize,
+    }
+
+    impl PythonBytecodeCompiler for CountingCompiler {
+        fn get_magic_number(&self) -> u32 {
+            42
+        }
+
+        fn compile(
+            &mut self,
+            source: &[u8],
+            _filename: &str,
+            _optimize: BytecodeOptimizationLevel,
+            _output_mode: CompileMode,
+        ) -> Result<Vec<u8>> {
+            self.calls += 1;
+
+            Ok(source.to_vec())
+        }
+    }
+
+    #[test]
+    fn test_put_get_purge() -> Result<()> {
+        let temp_dir = tempfile::Builder::new()
+            .prefix("pyoxidizer-test")
+            .tempdir()?;
+        let cache = ContentCache::new(temp_dir.path());
+
+        let digest = ContentCache::digest(&[b"foo"]);
+        assert_ne!(digest, ContentCache::digest(&[b"fo", b"o"]));
+
+        assert!(cache.get(NAMESPACE_BYTECODE, &digest)?.is_none());
+        cache.put(NAMESPACE_BYTECODE, &digest, b"data")?;
+        assert_eq!(
+            cache.get(NAMESPACE_BYTECODE, &digest)?,
+            Some(b"data".to_vec())
+        );
+
+        let path = cache.put_named(NAMESPACE_DOWNLOADS, &digest, "foo.whl", b"wheel")?;
+        assert_eq!(path.file_name().unwrap(), "foo.whl");
+        assert_eq!(cache.get_path(NAMESPACE_DOWNLOADS, &digest)?, Some(path));
+
+        assert!(cache.put("unknown", &digest, b"data").is_err());
+
+        assert_eq!(
+            cache.stats()?,
+            vec![
+                NamespaceStats {
+                    namespace: NAMESPACE_BYTECODE.to_string(),
+                    entries: 1,
+                    size: 4,
+                },
+                NamespaceStats {
+                    namespace: NAMESPACE_DOWNLOADS.to_string(),
+                    entries: 1,
+                    size: 5,
+                },
+                NamespaceStats {
+                    namespace: NAMESPACE_SEGMENTS.to_string(),
+                    entries: 0,
+                    size: 0,
+                }
+            ]
+        );
+
+        cache.purge(Some(NAMESPACE_BYTECODE))?;
+        assert!(cache.get(NAMESPACE_BYTECODE, &digest)?.is_none());
+        assert!(cache.get(NAMESPACE_DOWNLOADS, &digest)?.is_some());
+
+        cache.purge(None)?;
+        assert!(cache.get(NAMESPACE_DOWNLOADS, &digest)?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_caching_bytecode_compiler() -> Result<()> {
+        let temp_dir = tempfile::Builder::new()
+            .prefix("pyoxidizer-test")
+            .tempdir()?;
+        let cache = ContentCache::new(temp_dir.path());
+
+        let mut inner = CountingCompiler { calls: 0 };
+        {
+            let mut compiler = CachingBytecodeCompiler::new(&mut inner, cache.clone(), "python");
+
+            for _ in 0..2 {
+                compiler.compile(
+                    b"foo",
+                    "foo.py",
+                    BytecodeOptimizationLevel::Zero,
+                    CompileMode::Bytecode,
+                )?;
+            }
+            compiler.compile(
+                b"foo",
+                "foo.py",
+                BytecodeOptimizationLevel::One,
+                CompileMode::Bytecode,
+            )?;
+        }
+        assert_eq!(inner.calls, 2);
+
+        // A different interpreter doesn't share entries.
+        let mut compiler = CachingBytecodeCompiler::new(&mut inner, cache, "other");
+        compiler.compile(
+            b"foo",
+            "foo.py",
+            BytecodeOptimizationLevel::Zero,
+            CompileMode::Bytecode,
+        )?;
+        assert_eq!(inner.calls, 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_zstd_compress_resources_cached() -> Result<()> {
+        let temp_dir = tempfile::Builder::new()
+            .prefix("pyoxidizer-test")
+            .tempdir()?;
+        let cache = ContentCache::new(temp_dir.path());
+
+        let resource = Resource {
+            name: "foo".into(),
+            is_python_module: true,
+            in_memory_source: Some(b"import bar".to_vec().into()),
+            in_memory_package_resources: Some(
+                [("data.txt".into(), b"data".to_vec().into())]
+                    .into_iter()
+                    .collect(),
+            ),
+            relative_path_package_resources: Some(
+                [("other.txt".into(), Path::new("foo/other.txt").into())]
+                    .into_iter()
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+        let expected = resource.to_zstd_compressed(3)?;
+
+        for _ in 0..2 {
+            let mut resources = CompiledResourcesCollection::default();
+            resources
+                .resources
+                .insert("foo".to_string(), resource.clone());
+
+            zstd_compress_resources_cached(&cache, &mut resources, 3)?;
+            assert_eq!(resources.resources.get("foo"), Some(&expected));
+
+            let stats = cache.stats()?;
+            let segments = stats
+                .iter()
+                .find(|stats| stats.namespace == NAMESPACE_SEGMENTS)
+                .unwrap();
+            assert_eq!(segments.entries, 1);
+        }
+
+        // A different level doesn't share entries.
+        let mut resources = CompiledResourcesCollection::default();
+        resources.resources.insert("foo".to_string(), resource);
+        zstd_compress_resources_cached(&cache, &mut resources, 4)?;
+
+        let stats = cache.stats()?;
+        let segments = stats
+            .iter()
+            .find(|stats| stats.namespace == NAMESPACE_SEGMENTS)
+            .unwrap();
+        assert_eq!(segments.entries, 2);
+
+        Ok(())
+    }
+}
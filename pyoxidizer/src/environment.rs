@@ -5,7 +5,10 @@
 //! Resolve details about the PyOxidizer execution environment.
 
 use {
-    crate::{project_layout::PyembedLocation, py_packaging::distribution::AppleSdkInfo},
+    crate::{
+        content_cache::ContentCache, project_layout::PyembedLocation,
+        py_packaging::distribution::AppleSdkInfo,
+    },
     anyhow::{anyhow, Context, Result},
     apple_sdk::{AppleSdk, ParsedSdk, SdkSearch, SdkSearchLocation, SdkSorting},
     log::{info, warn},
@@ -103,6 +106,7 @@ pub const RUST_TOOLCHAIN_VERSION: &str = "1.66.0";
 pub static LINUX_TARGET_TRIPLES: Lazy<Vec<&'static str>> = Lazy::new(|| {
     vec![
         "aarch64-unknown-linux-gnu",
+        "aarch64-unknown-linux-musl",
         "x86_64-unknown-linux-gnu",
         "x86_64-unknown-linux-musl",
     ]
@@ -115,6 +119,7 @@ pub static MACOS_TARGET_TRIPLES: Lazy<Vec<&'static str>> =
 /// Target triples for Windows.
 pub static WINDOWS_TARGET_TRIPLES: Lazy<Vec<&'static str>> = Lazy::new(|| {
     vec![
+        "aarch64-pc-windows-msvc",
         "i686-pc-windows-gnu",
         "i686-pc-windows-msvc",
         "x86_64-pc-windows-gnu",
@@ -304,6 +309,11 @@ impl Environment {
         &self.cache_dir
     }
 
+    /// Content-addressed cache of build artifacts.
+    pub fn content_cache(&self) -> ContentCache {
+        ContentCache::new(self.cache_dir.join("content"))
+    }
+
     /// Directory to use for storing Python distributions.
     pub fn python_distributions_dir(&self) -> PathBuf {
         self.cache_dir.join("python_distributions")
@@ -508,6 +518,64 @@ impl Environment {
         Ok(sdk)
     }
 
+    /// Resolve a toolchain for building Windows MSVC binaries on this machine.
+    ///
+    /// This is used when targeting `*-pc-windows-msvc` from a non-Windows
+    /// machine. LLVM's MSVC compatible tools (`clang-cl`, `lld-link`, and
+    /// `llvm-lib`) must be available on `PATH`.
+    ///
+    /// The MSVC CRT and Windows SDK headers and libraries are located via the
+    /// `PYOXIDIZER_XWIN_PATH` environment variable, which should refer to the
+    /// output directory of `xwin splat`. If not set, the `INCLUDE` and `LIB`
+    /// environment variables are expected to locate these files.
+    pub fn resolve_msvc_cross_toolchain(&self) -> Result<MsvcCrossToolchain> {
+        let find = |name: &str| -> Result<PathBuf> {
+            self.find_executable(name)
+                .with_context(|| format!("searching for {}", name))?
+                .ok_or_else(|| {
+                    anyhow!(
+                        "{} not found; an LLVM toolchain is required to cross-compile to Windows",
+                        name
+                    )
+                })
+        };
+
+        let clang_cl = find("clang-cl")?;
+        let lld_link = find("lld-link")?;
+        let llvm_lib = find("llvm-lib")?;
+
+        let llvm_rc = self
+            .find_executable("llvm-rc")
+            .context("searching for llvm-rc")?;
+        if llvm_rc.is_none() {
+            warn!("llvm-rc not found; compiling Windows resources may fail");
+        }
+
+        let xwin_path = if let Some(path) = env::var_os("PYOXIDIZER_XWIN_PATH") {
+            let path = PathBuf::from(path);
+
+            if !path.join("crt").is_dir() || !path.join("sdk").is_dir() {
+                return Err(anyhow!(
+                    "PYOXIDIZER_XWIN_PATH ({}) does not look like the output of `xwin splat`",
+                    path.display()
+                ));
+            }
+
+            Some(path)
+        } else {
+            warn!("PYOXIDIZER_XWIN_PATH not set; relying on INCLUDE and LIB to locate the Windows SDK");
+            None
+        };
+
+        Ok(MsvcCrossToolchain {
+            clang_cl,
+            lld_link,
+            llvm_lib,
+            llvm_rc,
+            xwin_path,
+        })
+    }
+
     /// Create a new temporary directory.
     pub fn temporary_directory(&self, prefix: &str) -> Result<tempfile::TempDir> {
         let mut builder = tempfile::Builder::new();
@@ -526,6 +594,80 @@ impl Environment {
     }
 }
 
+/// Whether building for a target triple requires cross-compiling to Windows MSVC.
+pub fn is_msvc_cross_compile(target_triple: &str) -> bool {
+    target_triple.ends_with("-pc-windows-msvc") && !cfg!(windows)
+}
+
+/// Tools and files for building Windows MSVC binaries from a non-Windows machine.
+#[derive(Clone, Debug)]
+pub struct MsvcCrossToolchain {
+    /// Path to `clang-cl` executable.
+    pub clang_cl: PathBuf,
+
+    /// Path to `lld-link` executable.
+    pub lld_link: PathBuf,
+
+    /// Path to `llvm-lib` executable.
+    pub llvm_lib: PathBuf,
+
+    /// Path to `llvm-rc` executable.
+    pub llvm_rc: Option<PathBuf>,
+
+    /// Path to a directory produced by `xwin splat`.
+    pub xwin_path: Option<PathBuf>,
+}
+
+impl MsvcCrossToolchain {
+    /// Flags to pass to `clang-cl` when compiling for a target triple.
+    pub fn c_flags(&self, target_triple: &str) -> Vec<String> {
+        let mut flags = vec![
+            format!("--target={}", target_triple),
+            "-Wno-unused-command-line-argument".to_string(),
+        ];
+
+        if let Some(path) = &self.xwin_path {
+            for include in [
+                "crt/include",
+                "sdk/include/ucrt",
+                "sdk/include/um",
+                "sdk/include/shared",
+            ] {
+                flags.push(format!("/imsvc{}", path.join(include).display()));
+            }
+        }
+
+        flags
+    }
+
+    /// Directories containing libraries to link against for a target triple.
+    pub fn library_paths(&self, target_triple: &str) -> Result<Vec<PathBuf>> {
+        let path = if let Some(path) = &self.xwin_path {
+            path
+        } else {
+            return Ok(vec![]);
+        };
+
+        let arch = match target_triple.split('-').next() {
+            Some("x86_64") => "x86_64",
+            Some("i686") => "x86",
+            Some("aarch64") => "aarch64",
+            _ => {
+                return Err(anyhow!(
+                    "unable to resolve Windows SDK architecture for {}",
+                    target_triple
+                ))
+            }
+        };
+
+        Ok(vec![
+            path.join("crt").join("lib").join(arch),
+            path.join("sdk").join("lib").join("um").join(arch),
+            path.join("sdk").join("lib").join("ucrt").join(arch),
+        ])
+    }
+}
+
 /// Represents an available Rust toolchain.
 #[derive(Clone, Debug)]
 pub struct RustEnvironment {
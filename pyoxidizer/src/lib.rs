@@ -11,6 +11,7 @@ distribution and embedding it in a larger binary, oftentimes an executable.
 This library exposes that functionality to other tools.
 */
 
+pub mod content_cache;
 mod default_python_distributions;
 pub mod environment;
 pub mod licensing;
@@ -20,6 +21,7 @@ pub mod projectmgmt;
 pub mod py_packaging;
 pub mod python_distributions;
 pub mod starlark;
+pub mod watch;
 
 #[cfg(test)]
 mod testutil;
@@ -4,7 +4,10 @@
 
 use {
     crate::{
-        environment::{canonicalize_path, Environment, RustEnvironment},
+        environment::{
+            canonicalize_path, default_target_triple, is_msvc_cross_compile, Environment,
+            RustEnvironment,
+        },
         licensing::{licenses_from_cargo_manifest, log_licensing_info},
         project_layout::initialize_project,
         py_packaging::{
@@ -78,6 +81,30 @@ pub fn find_pyoxidizer_config_file_env(start_dir: &Path) -> Option<PathBuf> {
     find_pyoxidizer_config_file(start_dir)
 }
 
+/// Append flags to `RUSTFLAGS` in a set of custom environment variables.
+///
+/// Flags are appended to the custom `RUSTFLAGS` if defined. Otherwise they are
+/// appended to the value inherited from the current process so it isn't
+/// overridden.
+fn append_rust_flags(envs: &mut BTreeMap<String, String>, flags: &[String]) {
+    if flags.is_empty() {
+        return;
+    }
+
+    let extra_flags = flags.join(" ");
+
+    let rust_flags = match envs
+        .get("RUSTFLAGS")
+        .cloned()
+        .or_else(|| std::env::var("RUSTFLAGS").ok())
+    {
+        Some(value) if !value.is_empty() => format!("{} {}", value, extra_flags),
+        _ => extra_flags,
+    };
+
+    envs.insert("RUSTFLAGS".to_string(), rust_flags);
+}
+
 /// Describes an environment and settings used to build a project.
 pub struct BuildEnvironment {
     /// Describes the Rust toolchain we're using.
@@ -163,8 +190,78 @@ impl BuildEnvironment {
             }
         }
 
+        // Cross-compiling to aarch64 musl from a non-aarch64 machine requires a linker that
+        // understands the target. The system `cc` cargo defaults to can't produce these
+        // binaries. So if the user hasn't told cargo which linker to use, look for a musl
+        // cross toolchain with the conventional name and use it for both cargo and the `cc`
+        // crate.
+        if target_triple == "aarch64-unknown-linux-musl"
+            && !default_target_triple().starts_with("aarch64-unknown-linux")
+            && std::env::var_os("CARGO_TARGET_AARCH64_UNKNOWN_LINUX_MUSL_LINKER").is_none()
+        {
+            if let Some(linker) = env
+                .find_executable("aarch64-linux-musl-gcc")
+                .context("searching for aarch64 musl linker")?
+            {
+                let linker = linker.display().to_string();
+
+                envs.insert(
+                    "CARGO_TARGET_AARCH64_UNKNOWN_LINUX_MUSL_LINKER".to_string(),
+                    linker.clone(),
+                );
+                if std::env::var_os("CC_aarch64_unknown_linux_musl").is_none() {
+                    envs.insert("CC_aarch64_unknown_linux_musl".to_string(), linker);
+                }
+            } else {
+                warn!("aarch64-linux-musl-gcc not found; linking may fail");
+                warn!("(set CARGO_TARGET_AARCH64_UNKNOWN_LINUX_MUSL_LINKER to define a linker)");
+            }
+        }
+
         let mut rust_flags = vec![];
 
+        // Cross-compiling to Windows MSVC from another platform uses LLVM's MSVC
+        // compatible tools in place of the MSVC toolchain, as cargo-xwin does. We
+        // configure cargo and the `cc` crate to use them unless the user has already
+        // defined a setting.
+        if is_msvc_cross_compile(target_triple) {
+            let toolchain = env
+                .resolve_msvc_cross_toolchain()
+                .context("resolving toolchain for cross-compiling to Windows")?;
+
+            let target_env = target_triple.replace('-', "_");
+
+            let mut set_default = |key: String, value: String| {
+                if std::env::var_os(&key).is_none() {
+                    envs.insert(key, value);
+                }
+            };
+
+            set_default(
+                format!("CARGO_TARGET_{}_LINKER", target_env.to_uppercase()),
+                toolchain.lld_link.display().to_string(),
+            );
+            set_default(
+                format!("CC_{}", target_env),
+                toolchain.clang_cl.display().to_string(),
+            );
+            set_default(
+                format!("AR_{}", target_env),
+                toolchain.llvm_lib.display().to_string(),
+            );
+            set_default(
+                format!("CFLAGS_{}", target_env),
+                toolchain.c_flags(target_triple).join(" "),
+            );
+            if let Some(llvm_rc) = &toolchain.llvm_rc {
+                set_default(format!("RC_{}", target_env), llvm_rc.display().to_string());
+            }
+
+            for path in toolchain.library_paths(target_triple)? {
+                rust_flags.push(format!("-Lnative={}", path.display()));
+            }
+        }
+
         // Windows standalone_static distributions require the non-DLL CRT.
         // This requires telling Rust to use the static CRT.
         //
@@ -213,6 +310,52 @@ impl BuildEnvironment {
         })
     }
 
+    /// Configure the environment so builds produce reproducible output.
+    ///
+    /// Each path in `remap_paths` is replaced by its paired placeholder in
+    /// compiled output so the location of the build doesn't influence its
+    /// content. Settings already defined by the caller's environment are
+    /// respected.
+    pub fn make_deterministic(&mut self, target_triple: &str, remap_paths: &[(&Path, &str)]) {
+        let mut remap_paths = remap_paths
+            .iter()
+            .map(|(path, replacement)| (path.to_path_buf(), *replacement))
+            .collect::<Vec<_>>();
+
+        // Paths to registry crates differ between machines.
+        if let Some(cargo_home) = std::env::var_os("CARGO_HOME")
+            .map(PathBuf::from)
+            .or_else(|| dirs::home_dir().map(|home| home.join(".cargo")))
+        {
+            remap_paths.push((cargo_home, "/cargo"));
+        }
+
+        // rustc gives precedence to the last matching prefix. So order from
+        // least to most specific.
+        remap_paths.sort_by_key(|(path, _)| path.as_os_str().len());
+
+        let mut rust_flags = remap_paths
+            .iter()
+            .map(|(path, replacement)| {
+                format!("--remap-path-prefix={}={}", path.display(), replacement)
+            })
+            .collect::<Vec<_>>();
+
+        // Omits timestamps from PE headers.
+        if target_triple.contains("-windows-msvc") {
+            rust_flags.push("-C".to_string());
+            rust_flags.push("link-arg=/Brepro".to_string());
+        }
+
+        append_rust_flags(&mut self.extra_environment_vars, &rust_flags);
+
+        // Honored by C compilers invoked by crate build scripts for __DATE__ and __TIME__.
+        if std::env::var_os("SOURCE_DATE_EPOCH").is_none() {
+            self.extra_environment_vars
+                .insert("SOURCE_DATE_EPOCH".to_string(), "0".to_string());
+        }
+    }
+
     /// Resolve the full set of environment variables to use in build processes.
     pub fn environment_variables(&self) -> HashMap<String, String> {
         let mut envs = std::env::vars().collect::<HashMap<_, _>>();
@@ -287,7 +430,7 @@ pub fn build_executable_with_rust_project<'a>(
         .write_files(artifacts_path)
         .context("writing embedded python context files")?;
 
-    let build_env = BuildEnvironment::new(
+    let mut build_env = BuildEnvironment::new(
         env,
         exe.target_triple(),
         artifacts_path,
@@ -297,17 +440,154 @@ pub fn build_executable_with_rust_project<'a>(
     )
     .context("resolving build environment")?;
 
+    if exe.deterministic() {
+        build_env.make_deterministic(
+            target_triple,
+            &[
+                (project_path, "/pyoxidizer/project"),
+                (build_path, "/pyoxidizer/build"),
+                (artifacts_path, "/pyoxidizer/artifacts"),
+            ],
+        );
+    }
+
     warn!(
         "building with Rust {}",
         build_env.rust_environment.rust_version.semver
     );
 
+    let exe_name = if target_triple.contains("pc-windows") {
+        format!("{}.exe", bin_name)
+    } else {
+        bin_name.to_string()
+    };
+
+    let pgo_training_args = exe.pgo_training_args();
+
+    if exe.bolt() {
+        if pgo_training_args.is_none() {
+            return Err(anyhow!(
+                "BOLT optimization requires pgo_training_args to be set"
+            ));
+        }
+
+        if !target_triple.contains("-linux-") {
+            return Err(anyhow!("BOLT optimization is only supported on Linux"));
+        }
+    }
+
+    let pgo_training_args = if release {
+        pgo_training_args
+    } else {
+        if pgo_training_args.is_some() {
+            warn!("profile-guided optimization is only performed for release builds");
+        }
+
+        None
+    };
+
+    if pgo_training_args.is_some() && target_triple != build_env.rust_environment.rust_version.host
+    {
+        return Err(anyhow!(
+            "profile-guided optimization requires running the built executable; cannot target {} from {}",
+            target_triple,
+            build_env.rust_environment.rust_version.host
+        ));
+    }
+
+    let mut extra_rust_flags = vec![];
+
+    if let Some(training_args) = pgo_training_args {
+        let profile_path = build_pgo_profile(
+            env,
+            &build_env,
+            project_path,
+            bin_name,
+            &exe_name,
+            exe,
+            build_path,
+            target_triple,
+            locked,
+            training_args,
+        )
+        .context("collecting profile-guided optimization profile")?;
+
+        extra_rust_flags.push(format!("-Cprofile-use={}", profile_path.display()));
+        extra_rust_flags.push("-Cllvm-args=-pgo-warn-mismatch=false".to_string());
+
+        // BOLT needs relocations to rewrite the binary.
+        if exe.bolt() {
+            extra_rust_flags.push("-Clink-args=-Wl,--emit-relocs".to_string());
+        }
+    }
+
     let target_base_path = build_path.join("target");
-    let target_triple_base_path =
-        target_base_path
-            .join(target_triple)
-            .join(if release { "release" } else { "debug" });
 
+    let exe_path = run_cargo_build(
+        &build_env,
+        project_path,
+        bin_name,
+        &exe_name,
+        exe,
+        &target_base_path,
+        target_triple,
+        release,
+        locked,
+        &extra_rust_flags,
+    )?;
+
+    if let Some(training_args) = pgo_training_args.filter(|_| exe.bolt()) {
+        bolt_optimize(env, &build_env, &exe_path, build_path, training_args)
+            .context("optimizing executable with BOLT")?;
+    }
+
+    let exe_data =
+        std::fs::read(&exe_path).with_context(|| format!("reading {}", exe_path.display()))?;
+    let exe_name = exe_path.file_name().unwrap().to_string_lossy().to_string();
+
+    // Construct unified licensing info by combining the Python licensing metadata
+    // with the dynamically derived licensing info for Rust crates from the Cargo manifest.
+    for component in licenses_from_cargo_manifest(
+        project_path.join("Cargo.toml"),
+        false,
+        cargo_features(exe),
+        Some(target_triple),
+        &build_env.rust_environment,
+        include_self_license,
+    )?
+    .into_components()
+    {
+        embedded_data.add_licensed_component(component)?;
+    }
+
+    // Inform user about licensing info.
+    log_licensing_info(embedded_data.licensing());
+
+    Ok(BuiltExecutable {
+        exe_path: Some(exe_path),
+        exe_name,
+        exe_data,
+        binary_data: embedded_data,
+    })
+}
+
+/// Run `cargo build` for a project embedding Python.
+///
+/// `extra_rust_flags` are appended to `RUSTFLAGS`. Returns the path to the
+/// built executable.
+#[allow(clippy::too_many_arguments)]
+fn run_cargo_build(
+    build_env: &BuildEnvironment,
+    project_path: &Path,
+    bin_name: &str,
+    exe_name: &str,
+    exe: &dyn PythonBinaryBuilder,
+    target_base_path: &Path,
+    target_triple: &str,
+    release: bool,
+    locked: bool,
+    extra_rust_flags: &[String],
+) -> Result<PathBuf> {
     let mut args = vec!["build", "--target", target_triple];
 
     let target_dir = target_base_path.display().to_string();
@@ -334,9 +614,12 @@ pub fn build_executable_with_rust_project<'a>(
         args.push(&features);
     }
 
+    let mut extra_environment_vars = build_env.extra_environment_vars.clone();
+    append_rust_flags(&mut extra_environment_vars, extra_rust_flags);
+
     let mut log_args = vec![];
 
-    for (k, v) in &build_env.extra_environment_vars {
+    for (k, v) in &extra_environment_vars {
         log_args.push(format!("{}={}", k, v));
     }
     log_args.push(build_env.rust_environment.cargo_exe.display().to_string());
@@ -347,10 +630,13 @@ pub fn build_executable_with_rust_project<'a>(
         shlex::join(log_args.iter().map(|x| x.as_str()))
     );
 
+    let mut envs = build_env.environment_variables();
+    envs.extend(extra_environment_vars);
+
     // TODO force cargo to colorize output under certain circumstances?
     let command = cmd(&build_env.rust_environment.cargo_exe, &args)
         .dir(project_path)
-        .full_env(build_env.environment_variables())
+        .full_env(envs)
         .stderr_to_stdout()
         .unchecked()
         .reader()
@@ -369,46 +655,213 @@ pub fn build_executable_with_rust_project<'a>(
         return Err(anyhow!("cargo build failed"));
     }
 
-    let exe_name = if target_triple.contains("pc-windows") {
-        format!("{}.exe", bin_name)
+    let exe_path = target_base_path
+        .join(target_triple)
+        .join(if release { "release" } else { "debug" })
+        .join(exe_name);
+
+    if !exe_path.exists() {
+        return Err(anyhow!("{} does not exist", exe_path.display()));
+    }
+
+    Ok(exe_path)
+}
+
+/// Find an LLVM tool.
+///
+/// Tools installed by the `llvm-tools-preview` rustup component are preferred
+/// over tools on `PATH`.
+fn find_llvm_tool(env: &Environment, build_env: &BuildEnvironment, name: &str) -> Result<PathBuf> {
+    let sysroot = cmd(
+        &build_env.rust_environment.rustc_exe,
+        &["--print", "sysroot"],
+    )
+    .stderr_null()
+    .read()
+    .context("resolving Rust sysroot")?;
+
+    let exe_name = if cfg!(windows) {
+        format!("{}.exe", name)
     } else {
-        bin_name.to_string()
+        name.to_string()
     };
 
-    let exe_path = target_triple_base_path.join(&exe_name);
+    let rustup_path = PathBuf::from(sysroot.trim())
+        .join("lib")
+        .join("rustlib")
+        .join(&build_env.rust_environment.rust_version.host)
+        .join("bin")
+        .join(&exe_name);
 
-    if !exe_path.exists() {
-        return Err(anyhow!("{} does not exist", exe_path.display()));
+    if rustup_path.exists() {
+        return Ok(rustup_path);
     }
 
-    let exe_data =
-        std::fs::read(&exe_path).with_context(|| format!("reading {}", exe_path.display()))?;
-    let exe_name = exe_path.file_name().unwrap().to_string_lossy().to_string();
+    env.find_executable(name)
+        .with_context(|| format!("searching for {}", name))?
+        .ok_or_else(|| {
+            anyhow!(
+                "unable to find {}; try `rustup component add llvm-tools-preview`",
+                name
+            )
+        })
+}
 
-    // Construct unified licensing info by combining the Python licensing metadata
-    // with the dynamically derived licensing info for Rust crates from the Cargo manifest.
-    for component in licenses_from_cargo_manifest(
-        project_path.join("Cargo.toml"),
-        false,
-        cargo_features(exe),
-        Some(target_triple),
-        &build_env.rust_environment,
-        include_self_license,
-    )?
-    .into_components()
-    {
-        embedded_data.add_licensed_component(component)?;
+/// Run an executable with training arguments to collect optimization profiles.
+fn run_training(exe_path: &Path, training_args: &[String], envs: &[(&str, &Path)]) -> Result<()> {
+    warn!(
+        "running training command: {} {}",
+        exe_path.display(),
+        shlex::join(training_args.iter().map(|x| x.as_str()))
+    );
+
+    let mut command = cmd(exe_path, training_args).stdout_to_stderr();
+    for (key, value) in envs {
+        command = command.env(key, value);
     }
 
-    // Inform user about licensing info.
-    log_licensing_info(embedded_data.licensing());
+    command
+        .run()
+        .with_context(|| format!("running {}", exe_path.display()))?;
 
-    Ok(BuiltExecutable {
-        exe_path: Some(exe_path),
+    Ok(())
+}
+
+/// Build an instrumented executable and collect a profile for profile-guided optimization.
+///
+/// Returns the path to the merged profile.
+#[allow(clippy::too_many_arguments)]
+fn build_pgo_profile(
+    env: &Environment,
+    build_env: &BuildEnvironment,
+    project_path: &Path,
+    bin_name: &str,
+    exe_name: &str,
+    exe: &dyn PythonBinaryBuilder,
+    build_path: &Path,
+    target_triple: &str,
+    locked: bool,
+    training_args: &[String],
+) -> Result<PathBuf> {
+    let profiles_path = build_path.join("pgo-profiles");
+    if profiles_path.exists() {
+        std::fs::remove_dir_all(&profiles_path)
+            .with_context(|| format!("removing {}", profiles_path.display()))?;
+    }
+    create_dir_all(&profiles_path)
+        .with_context(|| format!("creating {}", profiles_path.display()))?;
+
+    warn!("building instrumented executable for profile-guided optimization");
+    let instrumented_exe = run_cargo_build(
+        build_env,
+        project_path,
+        bin_name,
         exe_name,
-        exe_data,
-        binary_data: embedded_data,
-    })
+        exe,
+        &build_path.join("target-pgo"),
+        target_triple,
+        true,
+        locked,
+        &[format!("-Cprofile-generate={}", profiles_path.display())],
+    )
+    .context("building instrumented executable")?;
+
+    run_training(
+        &instrumented_exe,
+        training_args,
+        &[("LLVM_PROFILE_FILE", &profiles_path.join("%m_%p.profraw"))],
+    )?;
+
+    let profdata = find_llvm_tool(env, build_env, "llvm-profdata")?;
+    let merged_path = build_path.join("pgo.profdata");
+
+    cmd(
+        &profdata,
+        &[
+            "merge".to_string(),
+            "-o".to_string(),
+            merged_path.display().to_string(),
+            profiles_path.display().to_string(),
+        ],
+    )
+    .stdout_to_stderr()
+    .run()
+    .context("merging profiles")?;
+
+    Ok(merged_path)
+}
+
+/// Optimize a linked executable with BOLT.
+///
+/// The executable is instrumented, run with `training_args`, and rewritten in
+/// place using the collected profile.
+fn bolt_optimize(
+    env: &Environment,
+    build_env: &BuildEnvironment,
+    exe_path: &Path,
+    build_path: &Path,
+    training_args: &[String],
+) -> Result<()> {
+    if !build_env
+        .rust_environment
+        .rust_version
+        .host
+        .contains("-linux-")
+    {
+        return Err(anyhow!("BOLT is only supported for Linux executables"));
+    }
+
+    let bolt = find_llvm_tool(env, build_env, "llvm-bolt")?;
+
+    let instrumented_exe = build_path.join("bolt-instrumented");
+    let profile_path = build_path.join("bolt.fdata");
+    let optimized_exe = build_path.join("bolt-optimized");
+
+    if profile_path.exists() {
+        std::fs::remove_file(&profile_path)
+            .with_context(|| format!("removing {}", profile_path.display()))?;
+    }
+
+    warn!("instrumenting executable with BOLT");
+    cmd(
+        &bolt,
+        &[
+            exe_path.display().to_string(),
+            "-instrument".to_string(),
+            format!("--instrumentation-file={}", profile_path.display()),
+            "-o".to_string(),
+            instrumented_exe.display().to_string(),
+        ],
+    )
+    .stdout_to_stderr()
+    .run()
+    .context("instrumenting executable")?;
+
+    run_training(&instrumented_exe, training_args, &[])?;
+
+    warn!("optimizing executable with BOLT");
+    cmd(
+        &bolt,
+        &[
+            exe_path.display().to_string(),
+            "-o".to_string(),
+            optimized_exe.display().to_string(),
+            format!("-data={}", profile_path.display()),
+            "-reorder-blocks=ext-tsp".to_string(),
+            "-reorder-functions=hfsort".to_string(),
+            "-split-functions".to_string(),
+            "-split-all-cold".to_string(),
+            "-dyno-stats".to_string(),
+        ],
+    )
+    .stdout_to_stderr()
+    .run()
+    .context("optimizing executable")?;
+
+    std::fs::rename(&optimized_exe, exe_path)
+        .with_context(|| format!("replacing {}", exe_path.display()))?;
+
+    Ok(())
 }
 
 /// Build a Python executable using a temporary Rust project.
@@ -441,6 +894,8 @@ pub fn build_python_executable<'a>(
         None,
         &[],
         exe.windows_subsystem(),
+        exe.windows_resources(),
+        None,
     )
     .context("initializing project")?;
 
@@ -699,6 +1154,41 @@ mod tests {
     #[cfg(target_env = "msvc")]
     use crate::py_packaging::distribution::DistributionFlavor;
 
+    #[test]
+    fn test_append_rust_flags() {
+        let original = std::env::var_os("RUSTFLAGS");
+
+        std::env::set_var("RUSTFLAGS", "-Cdebuginfo=0");
+        let mut inherited = BTreeMap::new();
+        append_rust_flags(&mut inherited, &["--remap-path-prefix=/a=/b".to_string()]);
+
+        let mut custom = BTreeMap::new();
+        custom.insert("RUSTFLAGS".to_string(), "-Copt-level=1".to_string());
+        append_rust_flags(&mut custom, &["--remap-path-prefix=/a=/b".to_string()]);
+
+        std::env::remove_var("RUSTFLAGS");
+        let mut unset = BTreeMap::new();
+        append_rust_flags(&mut unset, &["--remap-path-prefix=/a=/b".to_string()]);
+        append_rust_flags(&mut unset, &[]);
+
+        if let Some(value) = original {
+            std::env::set_var("RUSTFLAGS", value);
+        }
+
+        assert_eq!(
+            inherited.get("RUSTFLAGS").map(|x| x.as_str()),
+            Some("-Cdebuginfo=0 --remap-path-prefix=/a=/b")
+        );
+        assert_eq!(
+            custom.get("RUSTFLAGS").map(|x| x.as_str()),
+            Some("-Copt-level=1 --remap-path-prefix=/a=/b")
+        );
+        assert_eq!(
+            unset.get("RUSTFLAGS").map(|x| x.as_str()),
+            Some("--remap-path-prefix=/a=/b")
+        );
+    }
+
     #[test]
     fn test_empty_project() -> Result<()> {
         let env = get_env()?;
@@ -5,7 +5,10 @@
 //! Handle file layout of PyOxidizer projects.
 
 use {
-    crate::environment::{PyOxidizerSource, BUILD_GIT_COMMIT, PYOXIDIZER_VERSION},
+    crate::{
+        environment::{PyOxidizerSource, BUILD_GIT_COMMIT, PYOXIDIZER_VERSION},
+        py_packaging::binary::{windows_version_numeric, WindowsExecutableResources},
+    },
     anyhow::{anyhow, Context, Result},
     handlebars::Handlebars,
     once_cell::sync::Lazy,
@@ -90,6 +93,16 @@ struct TemplateData {
     program_name: Option<String>,
     code: Option<String>,
     pip_install_simple: Vec<String>,
+
+    icon_filename: Option<String>,
+    version_info: bool,
+    file_version_numeric: Option<String>,
+    product_version_numeric: Option<String>,
+    version_strings: BTreeMap<String, String>,
+    execution_level: Option<String>,
+    dpi_awareness: Option<String>,
+
+    workspace_member: bool,
 }
 
 impl TemplateData {
@@ -105,6 +118,14 @@ impl TemplateData {
             program_name: None,
             code: None,
             pip_install_simple: Vec::new(),
+            icon_filename: None,
+            version_info: false,
+            file_version_numeric: None,
+            product_version_numeric: None,
+            version_strings: BTreeMap::new(),
+            execution_level: None,
+            dpi_awareness: None,
+            workspace_member: false,
         }
     }
 }
@@ -317,10 +338,65 @@ pub fn write_new_pyoxidizer_config_file(
 /// might be able to remove this someday. It isn't clear if you get long
 /// paths support if using that version of the Windows SDK or if you have
 /// to be running on a modern Windows version as well.
-pub fn write_application_manifest(project_dir: &Path, program_name: &str) -> Result<()> {
+///
+/// The icon, version information, and manifest settings in `resources` are
+/// also written. The icon file is copied into `project_dir`.
+pub fn write_application_manifest(
+    project_dir: &Path,
+    program_name: &str,
+    resources: &WindowsExecutableResources,
+) -> Result<()> {
     let mut data = TemplateData::new();
     data.program_name = Some(program_name.to_string());
 
+    if let Some(icon_path) = &resources.icon_path {
+        let icon_filename = format!("{}.ico", program_name);
+        let dest_path = project_dir.join(&icon_filename);
+        println!("writing {}", dest_path.display());
+        std::fs::copy(icon_path, &dest_path)
+            .with_context(|| format!("copying icon {}", icon_path.display()))?;
+        data.icon_filename = Some(icon_filename);
+    }
+
+    if resources.has_version_info() {
+        data.version_info = true;
+        data.file_version_numeric = Some(windows_version_numeric(
+            resources.file_version.as_deref().unwrap_or("0"),
+        )?);
+        data.product_version_numeric = Some(windows_version_numeric(
+            resources
+                .product_version
+                .as_deref()
+                .or(resources.file_version.as_deref())
+                .unwrap_or("0"),
+        )?);
+
+        let mut strings = BTreeMap::new();
+        strings.insert("InternalName", Some(program_name.to_string()));
+        strings.insert("OriginalFilename", Some(format!("{}.exe", program_name)));
+        strings.insert("CompanyName", resources.company_name.clone());
+        strings.insert("FileDescription", resources.file_description.clone());
+        strings.insert("FileVersion", resources.file_version.clone());
+        strings.insert("LegalCopyright", resources.legal_copyright.clone());
+        strings.insert("ProductName", resources.product_name.clone());
+        strings.insert(
+            "ProductVersion",
+            resources
+                .product_version
+                .clone()
+                .or_else(|| resources.file_version.clone()),
+        );
+
+        // Quotes in resource script strings are escaped by doubling them.
+        data.version_strings = strings
+            .into_iter()
+            .filter_map(|(k, v)| v.map(|v| (k.to_string(), v.replace('"', "\"\""))))
+            .collect();
+    }
+
+    data.execution_level = resources.manifest_execution_level.clone();
+    data.dpi_awareness = resources.manifest_dpi_awareness.clone();
+
     let manifest_path = project_dir.join(format!("{}.exe.manifest", program_name));
     let manifest_data = HANDLEBARS.render("exe.manifest", &data)?;
     println!("writing {}", manifest_path.display());
@@ -362,7 +438,14 @@ impl PyembedLocation {
 }
 
 /// Update the Cargo.toml of a new Rust project to use pyembed.
-pub fn update_new_cargo_toml(path: &Path, pyembed_location: &PyembedLocation) -> Result<()> {
+///
+/// If `workspace_member` is false, the manifest declares its own workspace so
+/// it isn't considered part of a workspace in a parent directory.
+pub fn update_new_cargo_toml(
+    path: &Path,
+    pyembed_location: &PyembedLocation,
+    workspace_member: bool,
+) -> Result<()> {
     let content = std::fs::read_to_string(path)?;
 
     // Insert a `[package]` content after the `version = *\n` line. We key off
@@ -395,7 +478,8 @@ pub fn update_new_cargo_toml(path: &Path, pyembed_location: &PyembedLocation) ->
     ));
     content.push('\n');
 
-    let data = TemplateData::new();
+    let mut data = TemplateData::new();
+    data.workspace_member = workspace_member;
     content.push_str(
         &HANDLEBARS
             .render("cargo-extra.toml", &data)
@@ -407,13 +491,268 @@ pub fn update_new_cargo_toml(path: &Path, pyembed_location: &PyembedLocation) ->
     Ok(())
 }
 
+/// Load the `[workspace]` table of a Cargo workspace's root manifest.
+fn load_workspace(manifest_path: &Path) -> Result<cargo_toml::Workspace> {
+    let data = std::fs::read(manifest_path)
+        .with_context(|| format!("reading {}", manifest_path.display()))?;
+    let manifest = cargo_toml::Manifest::from_slice(&data)
+        .with_context(|| format!("parsing {}", manifest_path.display()))?;
+
+    manifest.workspace.ok_or_else(|| {
+        anyhow!(
+            "{} does not define a [workspace] table",
+            manifest_path.display()
+        )
+    })
+}
+
+/// Whether a path relative to the workspace root matches a member or exclude list.
+fn workspace_path_matches(patterns: &[String], member: &str) -> bool {
+    patterns.iter().any(|pattern| {
+        let pattern = pattern.trim_end_matches('/');
+
+        pattern == member
+            || glob::Pattern::new(pattern)
+                .map(|p| p.matches(member))
+                .unwrap_or(false)
+    })
+}
+
+/// Resolve the path of a project relative to the root of a Cargo workspace.
+///
+/// The project path doesn't need to exist. The returned path uses `/` as
+/// separator, as is conventional in Cargo manifests.
+pub fn workspace_member_path(workspace_root: &Path, project_path: &Path) -> Result<String> {
+    let workspace_root = workspace_root
+        .canonicalize()
+        .with_context(|| format!("resolving {}", workspace_root.display()))?;
+
+    // Canonicalize the nearest existing ancestor and append the remainder.
+    let mut existing = if project_path.is_absolute() {
+        project_path.to_path_buf()
+    } else {
+        std::env::current_dir()?.join(project_path)
+    };
+    let mut remainder = vec![];
+    while !existing.exists() {
+        remainder.push(
+            existing
+                .file_name()
+                .ok_or_else(|| anyhow!("unable to resolve {}", project_path.display()))?
+                .to_os_string(),
+        );
+        existing.pop();
+    }
+    let mut absolute = existing.canonicalize()?;
+    absolute.extend(remainder.iter().rev());
+
+    let relative = absolute.strip_prefix(&workspace_root).map_err(|_| {
+        anyhow!(
+            "{} is not inside the workspace at {}",
+            project_path.display(),
+            workspace_root.display()
+        )
+    })?;
+
+    let components = relative
+        .iter()
+        .map(|c| {
+            c.to_str()
+                .ok_or_else(|| anyhow!("project path is not valid UTF-8"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if components.is_empty() {
+        return Err(anyhow!("project path cannot be the root of the workspace"));
+    }
+
+    Ok(components.join("/"))
+}
+
+/// Find the offset of the `]` closing the TOML array opening at `open`.
+fn find_toml_array_end(content: &str, open: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut chars = content[open..].char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open + i);
+                }
+            }
+            '"' | '\'' => {
+                let mut escaped = false;
+                for (_, inner) in chars.by_ref() {
+                    if escaped {
+                        escaped = false;
+                    } else if inner == '\\' && c == '"' {
+                        escaped = true;
+                    } else if inner == c {
+                        break;
+                    }
+                }
+            }
+            '#' => {
+                for (_, inner) in chars.by_ref() {
+                    if inner == '\n' {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Add an entry to the `members` list of the `[workspace]` table in a Cargo.toml.
+///
+/// The manifest is edited textually so its formatting and comments are preserved.
+fn insert_workspace_member(content: &str, member: &str) -> Result<String> {
+    let entry = format!("\"{}\"", member.replace('\\', "\\\\").replace('"', "\\\""));
+
+    let mut offset = 0;
+    let mut header_end = None;
+    let mut members_line = None;
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+
+        if header_end.is_some() {
+            if trimmed.starts_with('[') {
+                break;
+            }
+
+            if let Some(rest) = trimmed.strip_prefix("members") {
+                if rest.trim_start().starts_with('=') {
+                    members_line = Some(offset);
+                    break;
+                }
+            }
+        } else if trimmed == "[workspace]" {
+            header_end = Some(offset + line.len());
+        }
+
+        offset += line.len();
+    }
+
+    let header_end = header_end.ok_or_else(|| anyhow!("could not find [workspace] table"))?;
+
+    let members_line = match members_line {
+        Some(offset) => offset,
+        None => {
+            let mut res = content[0..header_end].to_string();
+            if !res.ends_with('\n') {
+                res.push('\n');
+            }
+            res.push_str(&format!("members = [{}]\n", entry));
+            res.push_str(&content[header_end..]);
+
+            return Ok(res);
+        }
+    };
+
+    let open = members_line
+        + content[members_line..]
+            .find('[')
+            .ok_or_else(|| anyhow!("could not find start of workspace members"))?;
+    let close = find_toml_array_end(content, open)
+        .ok_or_else(|| anyhow!("could not find end of workspace members"))?;
+
+    let inner = &content[open + 1..close];
+
+    let (insert_at, insertion) = if inner.contains('\n') {
+        // One entry per line. Add a line before the closing bracket, matching
+        // the indentation of existing entries.
+        let close_line_start = content[0..close].rfind('\n').unwrap() + 1;
+
+        let mut indent = "    ".to_string();
+        let mut needs_comma = None;
+
+        let mut line_offset = open + 1;
+        for line in content[open + 1..close_line_start].split_inclusive('\n') {
+            let code = match line.find('#') {
+                Some(i) => &line[0..i],
+                None => line,
+            };
+            let code_trimmed = code.trim();
+
+            if !code_trimmed.is_empty() {
+                indent = code[0..code.len() - code.trim_start().len()].to_string();
+                needs_comma = if code_trimmed.ends_with(',') {
+                    None
+                } else {
+                    Some(line_offset + code.trim_end().len())
+                };
+            }
+
+            line_offset += line.len();
+        }
+
+        let mut res = content[0..close_line_start].to_string();
+        if let Some(comma) = needs_comma {
+            res.insert(comma, ',');
+        }
+        res.push_str(&format!("{}{},\n", indent, entry));
+        res.push_str(&content[close_line_start..]);
+
+        return Ok(res);
+    } else if inner.trim().is_empty() {
+        (open + 1, entry)
+    } else if inner.trim_end().ends_with(',') {
+        (open + 1 + inner.trim_end().len(), format!(" {}", entry))
+    } else {
+        (open + 1 + inner.trim_end().len(), format!(", {}", entry))
+    };
+
+    let mut res = content.to_string();
+    res.insert_str(insert_at, &insertion);
+
+    Ok(res)
+}
+
+/// Register a project as a member of a Cargo workspace.
+///
+/// `member` is the path of the project relative to the workspace root. The
+/// manifest isn't modified if the workspace already includes the project.
+pub fn add_workspace_member(manifest_path: &Path, member: &str) -> Result<()> {
+    let workspace = load_workspace(manifest_path)?;
+
+    if workspace_path_matches(&workspace.members, member) {
+        return Ok(());
+    }
+
+    let content = std::fs::read_to_string(manifest_path)?;
+    let content = insert_workspace_member(&content, member)
+        .with_context(|| format!("updating {}", manifest_path.display()))?;
+
+    println!(
+        "adding {} to workspace members in {}",
+        member,
+        manifest_path.display()
+    );
+    std::fs::write(manifest_path, content)?;
+
+    Ok(())
+}
+
 /// Initialize a new Rust project using PyOxidizer.
 ///
 /// The created binary application will have the name of the final
 /// path component.
 ///
 /// `windows_subsystem` is the value of the `windows_subsystem` compiler
-/// attribute.
+/// attribute. `windows_resources` defines resources to compile into the
+/// executable when building for Windows.
+///
+/// If `workspace` is defined, it is the root directory of an existing Cargo
+/// workspace the project is added to as a member. The project then uses the
+/// workspace's `Cargo.lock`, target directory, and profiles.
+#[allow(clippy::too_many_arguments)]
 pub fn initialize_project(
     source: &PyOxidizerSource,
     project_path: &Path,
@@ -421,7 +760,27 @@ pub fn initialize_project(
     code: Option<&str>,
     pip_install: &[&str],
     windows_subsystem: &str,
+    windows_resources: &WindowsExecutableResources,
+    workspace: Option<&Path>,
 ) -> Result<()> {
+    let workspace_member = if let Some(workspace_root) = workspace {
+        let manifest_path = workspace_root.join("Cargo.toml");
+        let workspace = load_workspace(&manifest_path)?;
+        let member = workspace_member_path(workspace_root, project_path)?;
+
+        if workspace_path_matches(&workspace.exclude, &member) {
+            return Err(anyhow!(
+                "{} is excluded from the workspace in {}",
+                member,
+                manifest_path.display()
+            ));
+        }
+
+        Some((manifest_path, member))
+    } else {
+        None
+    };
+
     let status = std::process::Command::new(cargo_exe)
         .arg("init")
         .arg("--bin")
@@ -435,17 +794,155 @@ pub fn initialize_project(
 
     let path = PathBuf::from(project_path);
     let name = path.iter().last().unwrap().to_str().unwrap();
-    update_new_cargo_toml(&path.join("Cargo.toml"), &source.as_pyembed_location())
-        .context("updating Cargo.toml")?;
+    update_new_cargo_toml(
+        &path.join("Cargo.toml"),
+        &source.as_pyembed_location(),
+        workspace_member.is_some(),
+    )
+    .context("updating Cargo.toml")?;
     write_new_cargo_config(&path).context("writing cargo config")?;
-    write_new_cargo_lock(&path, name, &source.as_pyembed_location())
-        .context("writing Cargo.lock")?;
+    // Workspace members share the workspace's Cargo.lock, which Cargo will
+    // update on first build.
+    if workspace_member.is_none() {
+        write_new_cargo_lock(&path, name, &source.as_pyembed_location())
+            .context("writing Cargo.lock")?;
+    }
     write_new_build_rs(&path.join("build.rs"), name).context("writing build.rs")?;
     write_new_main_rs(&path.join("src").join("main.rs"), windows_subsystem)
         .context("writing main.rs")?;
     write_new_pyoxidizer_config_file(source, &path, name, code, pip_install)
         .context("writing PyOxidizer config file")?;
-    write_application_manifest(&path, name).context("writing application manifest")?;
+    write_application_manifest(&path, name, windows_resources)
+        .context("writing application manifest")?;
+
+    if let Some((manifest_path, member)) = workspace_member {
+        add_workspace_member(&manifest_path, &member).context("updating workspace manifest")?;
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_application_manifest_default() -> Result<()> {
+        let temp_dir = tempfile::Builder::new()
+            .prefix("pyoxidizer-test")
+            .tempdir()?;
+
+        write_application_manifest(
+            temp_dir.path(),
+            "myapp",
+            &WindowsExecutableResources::default(),
+        )?;
+
+        let rc = std::fs::read_to_string(temp_dir.path().join("myapp-manifest.rc"))?;
+        assert!(rc.contains("1 RT_MANIFEST \"myapp.exe.manifest\""));
+        assert!(!rc.contains("ICON"));
+        assert!(!rc.contains("VERSIONINFO"));
+
+        let manifest = std::fs::read_to_string(temp_dir.path().join("myapp.exe.manifest"))?;
+        assert!(manifest.contains("longPathAware"));
+        assert!(!manifest.contains("requestedExecutionLevel"));
+        assert!(!manifest.contains("dpiAwareness"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_application_manifest_resources() -> Result<()> {
+        let temp_dir = tempfile::Builder::new()
+            .prefix("pyoxidizer-test")
+            .tempdir()?;
+
+        let icon_path = temp_dir.path().join("source.ico");
+        std::fs::write(&icon_path, b"icon")?;
+
+        let resources = WindowsExecutableResources {
+            icon_path: Some(icon_path),
+            file_version: Some("1.2.3".into()),
+            company_name: Some("Company \"Inc\"".into()),
+            manifest_execution_level: Some("requireAdministrator".into()),
+            manifest_dpi_awareness: Some("permonitorv2".into()),
+            ..Default::default()
+        };
+
+        write_application_manifest(temp_dir.path(), "myapp", &resources)?;
+
+        assert_eq!(std::fs::read(temp_dir.path().join("myapp.ico"))?, b"icon");
+
+        let rc = std::fs::read_to_string(temp_dir.path().join("myapp-manifest.rc"))?;
+        assert!(rc.contains("1 ICON \"myapp.ico\""));
+        assert!(rc.contains("FILEVERSION 1,2,3,0"));
+        assert!(rc.contains("PRODUCTVERSION 1,2,3,0"));
+        assert!(rc.contains("VALUE \"CompanyName\", \"Company \"\"Inc\"\"\""));
+        assert!(rc.contains("VALUE \"ProductVersion\", \"1.2.3\""));
+        assert!(rc.contains("VALUE \"OriginalFilename\", \"myapp.exe\""));
+        assert!(!rc.contains("ProductName"));
+
+        let manifest = std::fs::read_to_string(temp_dir.path().join("myapp.exe.manifest"))?;
+        assert!(manifest.contains("level=\"requireAdministrator\""));
+        assert!(manifest.contains("<ws2:dpiAwareness>permonitorv2</ws2:dpiAwareness>"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_insert_workspace_member() -> Result<()> {
+        assert_eq!(
+            insert_workspace_member("[workspace]\n\n[profile.release]\nlto = true\n", "app")?,
+            "[workspace]\nmembers = [\"app\"]\n\n[profile.release]\nlto = true\n"
+        );
+        assert_eq!(
+            insert_workspace_member("[workspace]\nmembers = []\n", "app")?,
+            "[workspace]\nmembers = [\"app\"]\n"
+        );
+        assert_eq!(
+            insert_workspace_member("[workspace]\nmembers = [\"a\", \"b\"]\n", "apps/app")?,
+            "[workspace]\nmembers = [\"a\", \"b\", \"apps/app\"]\n"
+        );
+        assert_eq!(
+            insert_workspace_member(
+                "[workspace]\nresolver = \"2\"\nmembers = [\n  \"a\",\n  \"b\" # [comment]\n]\n",
+                "app"
+            )?,
+            "[workspace]\nresolver = \"2\"\nmembers = [\n  \"a\",\n  \"b\", # [comment]\n  \"app\",\n]\n"
+        );
+        assert!(insert_workspace_member("[package]\nname = \"foo\"\n", "app").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_workspace_member() -> Result<()> {
+        let temp_dir = tempfile::Builder::new()
+            .prefix("pyoxidizer-test")
+            .tempdir()?;
+
+        let manifest_path = temp_dir.path().join("Cargo.toml");
+        std::fs::write(
+            &manifest_path,
+            "[workspace]\nmembers = [\"crates/*\"]\n\n[profile.release]\nlto = true\n",
+        )?;
+
+        assert_eq!(
+            workspace_member_path(temp_dir.path(), &temp_dir.path().join("apps").join("app"))?,
+            "apps/app"
+        );
+        assert!(workspace_member_path(temp_dir.path(), temp_dir.path()).is_err());
+
+        // Already matched by a glob.
+        add_workspace_member(&manifest_path, "crates/app")?;
+        add_workspace_member(&manifest_path, "apps/app")?;
+        add_workspace_member(&manifest_path, "apps/app")?;
+
+        assert_eq!(
+            std::fs::read_to_string(&manifest_path)?,
+            "[workspace]\nmembers = [\"crates/*\", \"apps/app\"]\n\n[profile.release]\nlto = true\n"
+        );
+
+        Ok(())
+    }
+}
@@ -11,15 +11,22 @@ use {
         project_building::find_pyoxidizer_config_file_env,
         project_layout::{initialize_project, write_new_pyoxidizer_config_file},
         py_packaging::{
+            binary::WindowsExecutableResources,
+            dependency_graph::DependencyGraph,
             distribution::{
                 default_distribution_location, resolve_distribution,
                 resolve_python_distribution_archive, BinaryLibpythonLinkMode, DistributionCache,
                 DistributionFlavor, PythonDistribution,
             },
+            filtering::{write_resource_names_file_from_traces, TRACE_IMPORTS_ENV},
             standalone_distribution::StandaloneDistribution,
         },
         python_distributions::PYTHON_DISTRIBUTIONS,
-        starlark::eval::EvaluationContextBuilder,
+        starlark::{
+            debugger::StarlarkDebugger, eval::EvaluationContextBuilder,
+            lint::find_removed_api_references,
+        },
+        watch::{wait_for_changes, FileSnapshot},
     },
     anyhow::{anyhow, Context, Result},
     python_packaging::licensing::LicenseFlavor,
@@ -29,15 +36,21 @@ use {
         resource::PythonResource,
         wheel::WheelArchive,
     },
-    simple_file_manifest::{FileData, FileManifest},
+    simple_file_manifest::{FileData, FileEntry, FileManifest},
+    starlark_dialect_build_targets::RunMode,
     std::{
         collections::HashMap,
         fs::create_dir_all,
         io::{Cursor, Read},
         path::{Path, PathBuf},
+        sync::Arc,
+        time::Duration,
     },
 };
 
+/// How often to poll the filesystem for changes in `build --watch` mode.
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
 /// Attempt to resolve the default Rust target for a build.
 pub fn default_target() -> Result<String> {
     // TODO derive these more intelligently.
@@ -48,7 +61,11 @@ pub fn default_target() -> Result<String> {
             Ok("x86_64-unknown-linux-gnu".to_string())
         }
     } else if cfg!(target_os = "windows") {
-        Ok("x86_64-pc-windows-msvc".to_string())
+        if cfg!(target_arch = "aarch64") {
+            Ok("aarch64-pc-windows-msvc".to_string())
+        } else {
+            Ok("x86_64-pc-windows-msvc".to_string())
+        }
     } else if cfg!(target_os = "macos") {
         if cfg!(target_arch = "aarch64") {
             Ok("aarch64-apple-darwin".to_string())
@@ -101,6 +118,50 @@ pub fn list_targets(env: &Environment, project_path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Evaluate a config file and build the requested targets.
+///
+/// Returns paths that should be monitored for changes and paths that should
+/// be excluded from monitoring.
+#[allow(clippy::too_many_arguments)]
+fn build_config(
+    env: &Environment,
+    config_path: &Path,
+    target_triple: &str,
+    resolve_targets: Option<Vec<String>>,
+    extra_vars: HashMap<String, Option<String>>,
+    release: bool,
+    verbose: bool,
+    debug_starlark: bool,
+) -> Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+    let mut builder =
+        EvaluationContextBuilder::new(env, config_path.to_path_buf(), target_triple.to_string())
+            .extra_vars(extra_vars)
+            .release(release)
+            .verbose(verbose)
+            .resolve_targets_optional(resolve_targets);
+
+    if debug_starlark {
+        builder = builder.debugger(Arc::new(StarlarkDebugger::stdio()));
+    }
+
+    let mut context = builder.into_context()?;
+
+    context.evaluate_file(config_path)?;
+
+    for target in context.targets_to_resolve()? {
+        context.build_resolved_target(&target)?;
+    }
+
+    let mut roots = vec![config_path
+        .parent()
+        .ok_or_else(|| anyhow!("unable to resolve parent directory of config file"))?
+        .to_path_buf()];
+    roots.extend(context.referenced_paths().map_err(|e| anyhow!("{:?}", e))?);
+    let build_path = context.build_path().map_err(|e| anyhow!("{:?}", e))?;
+
+    Ok((roots, vec![build_path]))
+}
+
 /// Build a PyOxidizer enabled project.
 ///
 /// This is a glorified wrapper around `cargo build`. Our goal is to get the
@@ -114,6 +175,93 @@ pub fn build(
     extra_vars: HashMap<String, Option<String>>,
     release: bool,
     verbose: bool,
+    watch: bool,
+    debug_starlark: bool,
+) -> Result<()> {
+    let config_path = find_pyoxidizer_config_file_env(project_path).ok_or_else(|| {
+        anyhow!(
+            "unable to find PyOxidizer config file at {}",
+            project_path.display()
+        )
+    })?;
+    let target_triple = resolve_target(target_triple)?;
+
+    if !watch {
+        build_config(
+            env,
+            &config_path,
+            &target_triple,
+            resolve_targets,
+            extra_vars,
+            release,
+            verbose,
+            debug_starlark,
+        )?;
+
+        return Ok(());
+    }
+
+    let config_dir = config_path
+        .parent()
+        .ok_or_else(|| anyhow!("unable to resolve parent directory of config file"))?;
+    let mut roots = vec![config_dir.to_path_buf()];
+    let mut excludes = vec![config_dir.join("build")];
+
+    loop {
+        // Captured before building so changes made while building trigger
+        // another build.
+        let snapshot = FileSnapshot::capture(&roots, &excludes);
+        let (previous_roots, previous_excludes) = (roots.clone(), excludes.clone());
+
+        // Errors are reported but don't stop watching, as the next change
+        // will likely fix them.
+        match build_config(
+            env,
+            &config_path,
+            &target_triple,
+            resolve_targets.clone(),
+            extra_vars.clone(),
+            release,
+            verbose,
+            debug_starlark,
+        ) {
+            Ok((watch_roots, watch_excludes)) => {
+                roots = watch_roots;
+                excludes = watch_excludes;
+            }
+            Err(e) => {
+                eprintln!("error: {:?}", e);
+            }
+        }
+
+        // The build can discover new paths to watch. Their state before the
+        // build is unknown.
+        let snapshot = if roots != previous_roots || excludes != previous_excludes {
+            snapshot.retarget(FileSnapshot::capture(&roots, &excludes))
+        } else {
+            snapshot
+        };
+
+        println!(
+            "watching {} files for changes (press CTRL+C to stop)",
+            snapshot.len()
+        );
+
+        let (changed, _) = wait_for_changes(&snapshot, &roots, &excludes, WATCH_POLL_INTERVAL);
+        for path in changed {
+            println!("{} changed", path.display());
+        }
+    }
+}
+
+/// Check a PyOxidizer configuration file for errors without building anything.
+pub fn check(
+    env: &Environment,
+    project_path: &Path,
+    target_triple: Option<&str>,
+    extra_vars: HashMap<String, Option<String>>,
+    release: bool,
+    verbose: bool,
 ) -> Result<()> {
     let config_path = find_pyoxidizer_config_file_env(project_path).ok_or_else(|| {
         anyhow!(
@@ -123,20 +271,49 @@ pub fn build(
     })?;
     let target_triple = resolve_target(target_triple)?;
 
+    let source = std::fs::read_to_string(&config_path)
+        .with_context(|| format!("reading {}", config_path.display()))?;
+
+    let mut problems = 0;
+
+    for finding in find_removed_api_references(&source) {
+        println!("{}:{}", config_path.display(), finding);
+        problems += 1;
+    }
+
+    // Evaluate without resolving targets so each target can be resolved and
+    // reported on individually.
     let mut context = EvaluationContextBuilder::new(env, config_path.clone(), target_triple)
         .extra_vars(extra_vars)
         .release(release)
         .verbose(verbose)
-        .resolve_targets_optional(resolve_targets)
+        .resolve_targets(vec![])
         .into_context()?;
 
-    context.evaluate_file(&config_path)?;
+    if let Err(e) = context.evaluate_file(&config_path) {
+        println!("{}: error evaluating file: {}", config_path.display(), e);
 
-    for target in context.targets_to_resolve()? {
-        context.build_resolved_target(&target)?;
+        return Err(anyhow!("{} problems found", problems + 1));
     }
 
-    Ok(())
+    for target in context.target_names()? {
+        match context.resolve_target(&target) {
+            Ok(_) => {
+                println!("target {}: ok", target);
+            }
+            Err(e) => {
+                println!("target {}: {:?}", target, e);
+                problems += 1;
+            }
+        }
+    }
+
+    if problems > 0 {
+        Err(anyhow!("{} problems found", problems))
+    } else {
+        println!("no problems found");
+        Ok(())
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -146,6 +323,8 @@ pub fn run(
     target_triple: Option<&str>,
     release: bool,
     target: Option<&str>,
+    dev: bool,
+    trace_imports: Option<&Path>,
     extra_vars: HashMap<String, Option<String>>,
     _extra_args: &[&str],
     verbose: bool,
@@ -163,11 +342,153 @@ pub fn run(
         .release(release)
         .verbose(verbose)
         .resolve_target_optional(target)
+        .dev(dev)
+        .trace_imports(trace_imports.is_some())
         .into_context()?;
 
     context.evaluate_file(&config_path)?;
 
-    context.run_target(target)
+    let trace_path = match trace_imports {
+        Some(path) => path,
+        None => return context.run_target(target),
+    };
+
+    let temp_dir = tempfile::Builder::new()
+        .prefix("pyoxidizer-trace-imports")
+        .tempdir()?;
+    std::env::set_var(TRACE_IMPORTS_ENV, temp_dir.path());
+
+    // Imports are recorded even if the application fails.
+    let res = context.run_target(target);
+
+    let count = write_resource_names_file_from_traces(temp_dir.path(), trace_path)?;
+    println!("wrote {} resource names to {}", count, trace_path.display());
+
+    res
+}
+
+/// Build and run test targets in a PyOxidizer configuration file.
+///
+/// Runs all registered test targets if `targets` is empty.
+#[allow(clippy::too_many_arguments)]
+pub fn test(
+    env: &Environment,
+    project_path: &Path,
+    target_triple: Option<&str>,
+    release: bool,
+    targets: &[&str],
+    extra_vars: HashMap<String, Option<String>>,
+    verbose: bool,
+) -> Result<()> {
+    let config_path = find_pyoxidizer_config_file_env(project_path).ok_or_else(|| {
+        anyhow!(
+            "unable to find PyOxidizer config file at {}",
+            project_path.display()
+        )
+    })?;
+    let target_triple = resolve_target(target_triple)?;
+
+    // Test targets are resolved individually after evaluation so a failure in
+    // one doesn't prevent others from running.
+    let mut context = EvaluationContextBuilder::new(env, config_path.clone(), target_triple)
+        .extra_vars(extra_vars)
+        .release(release)
+        .verbose(verbose)
+        .resolve_targets(vec![])
+        .into_context()?;
+
+    context.evaluate_file(&config_path)?;
+
+    let test_targets = context.test_targets()?;
+
+    let targets = if targets.is_empty() {
+        test_targets
+    } else {
+        targets
+            .iter()
+            .map(|target| {
+                if test_targets.iter().any(|t| t == target) {
+                    Ok(target.to_string())
+                } else {
+                    Err(anyhow!("{} is not a registered test target", target))
+                }
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    if targets.is_empty() {
+        return Err(anyhow!(
+            "no test targets registered; use register_test() to define some"
+        ));
+    }
+
+    let mut failures = vec![];
+
+    for target in &targets {
+        println!("running test target {}", target);
+
+        let res = context.resolve_target(target).and_then(|_| {
+            let resolved = context.build_resolved_target(target)?;
+
+            let path = match resolved.run_mode {
+                RunMode::Path { path } => path,
+                RunMode::None => {
+                    return Err(anyhow!("target does not produce a runnable binary"));
+                }
+            };
+
+            std::process::Command::new(&path)
+                .current_dir(path.parent().unwrap())
+                .status()
+                .with_context(|| format!("running {}", path.display()))
+        });
+
+        match res {
+            Ok(status) if status.success() => {
+                println!("test target {}: ok", target);
+            }
+            Ok(status) => {
+                println!("test target {}: FAILED ({})", target, status);
+                failures.push(target.as_str());
+            }
+            Err(e) => {
+                println!("test target {}: FAILED ({:?})", target, e);
+                failures.push(target.as_str());
+            }
+        }
+    }
+
+    println!(
+        "{} test targets passed; {} failed",
+        targets.len() - failures.len(),
+        failures.len()
+    );
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!("test targets failed: {}", failures.join(", ")))
+    }
+}
+
+/// Emit the Python dependency graph of a built binary.
+pub fn analyze_dependency_graph(path: &Path, format: &str, output: Option<&Path>) -> Result<()> {
+    let graph = DependencyGraph::from_artifact(path)?;
+
+    let data = match format {
+        "json" => graph.to_json()?,
+        "dot" => graph.to_dot(),
+        "dot-modules" => graph.to_module_dot(),
+        _ => return Err(anyhow!("unknown dependency graph format: {}", format)),
+    };
+
+    if let Some(output) = output {
+        std::fs::write(output, data).with_context(|| format!("writing {}", output.display()))?;
+    } else {
+        print!("{}", data);
+    }
+
+    Ok(())
 }
 
 pub fn cache_clear(env: &Environment) -> Result<()> {
@@ -179,6 +500,32 @@ pub fn cache_clear(env: &Environment) -> Result<()> {
     Ok(())
 }
 
+pub fn cache_info(env: &Environment) -> Result<()> {
+    let cache = env.content_cache();
+
+    println!("{}", cache.root().display());
+    for stats in cache.stats()? {
+        println!(
+            "{}: {} entries, {} bytes",
+            stats.namespace, stats.entries, stats.size
+        );
+    }
+
+    Ok(())
+}
+
+pub fn cache_purge(env: &Environment, namespace: Option<&str>) -> Result<()> {
+    let cache = env.content_cache();
+
+    if let Some(namespace) = namespace {
+        println!("removing {}", cache.root().join(namespace).display());
+    } else {
+        println!("removing {}", cache.root().display());
+    }
+
+    cache.purge(namespace)
+}
+
 /// Find resources given a source path.
 pub fn find_resources(
     env: &Environment,
@@ -334,7 +681,11 @@ pub fn init_config_file(
 }
 
 /// Initialize a new Rust project with PyOxidizer support.
-pub fn init_rust_project(env: &Environment, project_path: &Path) -> Result<()> {
+pub fn init_rust_project(
+    env: &Environment,
+    project_path: &Path,
+    workspace: Option<&Path>,
+) -> Result<()> {
     let cargo_exe = env
         .ensure_rust_toolchain(None)
         .context("resolving Rust environment")?
@@ -347,12 +698,21 @@ pub fn init_rust_project(env: &Environment, project_path: &Path) -> Result<()> {
         None,
         &[],
         "console",
+        &WindowsExecutableResources::default(),
+        workspace,
     )?;
     println!();
     println!(
         "A new Rust binary application has been created in {}",
         project_path.display()
     );
+    if let Some(workspace) = workspace {
+        println!(
+            "It is a member of the Cargo workspace in {} and will use that \
+            workspace's Cargo.lock, target directory, and profiles.",
+            workspace.display()
+        );
+    }
     print!(
         r#"
 This application can be built most easily by doing the following:
@@ -577,6 +937,28 @@ pub fn generate_python_embedding_artifacts(
     flavor: &str,
     python_version: Option<&str>,
     dest_path: &Path,
+) -> Result<()> {
+    write_python_embedding_artifacts(env, target_triple, flavor, python_version, dest_path, false)
+}
+
+/// Generate artifacts for embedding Python in a binary along with Bazel files consuming them.
+pub fn generate_bazel(
+    env: &Environment,
+    target_triple: &str,
+    flavor: &str,
+    python_version: Option<&str>,
+    dest_path: &Path,
+) -> Result<()> {
+    write_python_embedding_artifacts(env, target_triple, flavor, python_version, dest_path, true)
+}
+
+fn write_python_embedding_artifacts(
+    env: &Environment,
+    target_triple: &str,
+    flavor: &str,
+    python_version: Option<&str>,
+    dest_path: &Path,
+    bazel: bool,
 ) -> Result<()> {
     let flavor = DistributionFlavor::try_from(flavor).map_err(|e| anyhow!("{}", e))?;
 
@@ -658,6 +1040,31 @@ pub fn generate_python_embedding_artifacts(
     m.materialize_files_with_replace(dest_path.join("stdlib"))
         .context("writing standard library")?;
 
+    // Write out Python's headers and files for consumption by C/C++ build systems.
+    let include_path = dest_path.join("include");
+    let mut m = FileManifest::default();
+    for (name, path) in &dist.includes {
+        m.add_file_entry(name, FileEntry::new_from_path(path, false))?;
+    }
+    m.materialize_files_with_replace(&include_path)
+        .context("writing Python headers")?;
+
+    embedded_context
+        .write_interpreter_config_h(&dest_path)
+        .context("writing interpreter config C header")?;
+    embedded_context
+        .write_pkg_config(&dest_path, &include_path)
+        .context("writing pkg-config file")?;
+    embedded_context
+        .write_cmake_config(&dest_path, &include_path, Some(&dest_path.join("stdlib")))
+        .context("writing CMake config file")?;
+
+    if bazel {
+        embedded_context
+            .write_bazel_files(&dest_path, &include_path, &dest_path.join("stdlib"))
+            .context("writing Bazel files")?;
+    }
+
     Ok(())
 }
 
@@ -9,10 +9,14 @@ Defining and manipulating binaries embedding Python.
 use {
     crate::{
         environment::Environment,
-        py_packaging::{distribution::AppleSdkInfo, embedding::EmbeddedPythonContext},
+        py_packaging::{
+            distribution::AppleSdkInfo,
+            embedding::{EmbeddedPythonContext, LinkingAnnotation},
+        },
     },
-    anyhow::Result,
+    anyhow::{anyhow, Result},
     python_packaging::{
+        interpreter::RunMode,
         licensing::{LicensedComponent, LicensedComponents},
         policy::PythonPackagingPolicy,
         resource::{
@@ -20,11 +24,16 @@ use {
             PythonPackageResource, PythonResource,
         },
         resource_collection::{
-            AddResourceAction, PrePackagedResource, PythonResourceAddCollectionContext,
+            AddResourceAction, CompiledResourcesCollection, PrePackagedResource,
+            PythonResourceAddCollectionContext,
         },
     },
     simple_file_manifest::File,
-    std::{collections::HashMap, path::Path, sync::Arc},
+    std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+        sync::Arc,
+    },
     tugger_windows::VcRedistributablePlatform,
 };
 
@@ -143,6 +152,142 @@ impl TryFrom<&str> for WindowsRuntimeDllsMode {
     }
 }
 
+/// Describes how OpenSSL is provided to the `ssl` and `hashlib` modules.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OpensslMode {
+    /// Statically link the OpenSSL library from the Python distribution.
+    ///
+    /// The binary has no run-time dependency on OpenSSL. But the location of
+    /// the certificate store is the one the distribution was built with,
+    /// which may not exist on the machine running the binary.
+    Static,
+
+    /// Dynamically link the system's OpenSSL library.
+    ///
+    /// The system's certificate store and configuration (e.g. FIPS mode) are
+    /// used. The system library must be ABI compatible with the OpenSSL the
+    /// distribution was built against. Only supported on Linux.
+    System,
+
+    /// Statically link OpenSSL and install a CA certificate bundle next to the binary.
+    ///
+    /// `SSL_CERT_FILE` is set at run-time to point at the bundle, unless already
+    /// defined. The bundle is copied from the build machine.
+    CaBundle,
+}
+
+impl ToString for OpensslMode {
+    fn to_string(&self) -> String {
+        match self {
+            Self::Static => "static",
+            Self::System => "system",
+            Self::CaBundle => "ca-bundle",
+        }
+        .to_string()
+    }
+}
+
+impl TryFrom<&str> for OpensslMode {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "static" => Ok(Self::Static),
+            "system" => Ok(Self::System),
+            "ca-bundle" => Ok(Self::CaBundle),
+            _ => Err(format!(
+                "{} is not a valid mode; must be 'static', 'system', or 'ca-bundle'",
+                value
+            )),
+        }
+    }
+}
+
+/// Valid values for the `requestedExecutionLevel` in Windows application manifests.
+pub const WINDOWS_MANIFEST_EXECUTION_LEVELS: &[&str] =
+    &["asInvoker", "highestAvailable", "requireAdministrator"];
+
+/// Valid values for `dpiAwareness` in Windows application manifests.
+pub const WINDOWS_MANIFEST_DPI_AWARENESS: &[&str] =
+    &["unaware", "system", "permonitor", "permonitorv2"];
+
+/// Describes resources compiled into Windows executables.
+///
+/// Fields left as `None` are omitted from the executable.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WindowsExecutableResources {
+    /// Path to a `.ico` file defining the executable's icon.
+    pub icon_path: Option<PathBuf>,
+
+    /// Version of the file, in `X.Y.Z.W` form.
+    pub file_version: Option<String>,
+
+    /// Version of the product the file is distributed with, in `X.Y.Z.W` form.
+    pub product_version: Option<String>,
+
+    /// Name of the company producing the file.
+    pub company_name: Option<String>,
+
+    /// Name of the product the file is distributed with.
+    pub product_name: Option<String>,
+
+    /// Description of the file presented to users.
+    pub file_description: Option<String>,
+
+    /// Copyright notices applying to the file.
+    pub legal_copyright: Option<String>,
+
+    /// The `requestedExecutionLevel` in the application manifest.
+    ///
+    /// Must be a value in [WINDOWS_MANIFEST_EXECUTION_LEVELS].
+    pub manifest_execution_level: Option<String>,
+
+    /// The `dpiAwareness` in the application manifest.
+    ///
+    /// Must be a value in [WINDOWS_MANIFEST_DPI_AWARENESS].
+    pub manifest_dpi_awareness: Option<String>,
+}
+
+impl WindowsExecutableResources {
+    /// Whether the resources define a `VERSIONINFO` resource.
+    pub fn has_version_info(&self) -> bool {
+        self.file_version.is_some()
+            || self.product_version.is_some()
+            || self.company_name.is_some()
+            || self.product_name.is_some()
+            || self.file_description.is_some()
+            || self.legal_copyright.is_some()
+    }
+}
+
+/// Convert a `X.Y.Z.W` version string to the numeric form used by `VERSIONINFO`.
+///
+/// Missing components are `0`. e.g. `1.2` becomes `1,2,0,0`.
+pub fn windows_version_numeric(value: &str) -> Result<String> {
+    let mut parts = value
+        .split('.')
+        .map(|part| {
+            part.parse::<u16>()
+                .map_err(|_| anyhow!("{} is not a valid Windows version", value))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if parts.len() > 4 {
+        return Err(anyhow!(
+            "{} is not a valid Windows version; at most 4 components allowed",
+            value
+        ));
+    }
+
+    parts.resize(4, 0);
+
+    Ok(parts
+        .iter()
+        .map(|part| part.to_string())
+        .collect::<Vec<_>>()
+        .join(","))
+}
+
 /// A callable that can influence PythonResourceAddCollectionContext.
 pub type ResourceAddCollectionContextCallback<'a> = Box<
     dyn Fn(
@@ -165,6 +310,9 @@ pub trait PythonBinaryBuilder {
     /// Clone self into a Box'ed trait object.
     fn clone_trait(&self) -> Arc<dyn PythonBinaryBuilder>;
 
+    /// Clone self into a Box'ed trait object.
+    fn clone_box(&self) -> Box<dyn PythonBinaryBuilder>;
+
     /// The name of the binary.
     fn name(&self) -> String;
 
@@ -211,24 +359,102 @@ pub trait PythonBinaryBuilder {
     /// Set the value for `windows_runtime_dlls_mode()`.
     fn set_windows_runtime_dlls_mode(&mut self, value: WindowsRuntimeDllsMode);
 
+    /// Obtain how OpenSSL is provided to the built binary.
+    fn openssl_mode(&self) -> &OpensslMode;
+
+    /// Set how OpenSSL is provided to the built binary.
+    ///
+    /// Errors if the mode isn't supported for the target.
+    fn set_openssl_mode(&mut self, value: OpensslMode) -> Result<()>;
+
     /// The directory to install tcl/tk files into.
     fn tcl_files_path(&self) -> &Option<String>;
 
     /// Set the directory to install tcl/tk files into.
     fn set_tcl_files_path(&mut self, value: Option<String>);
 
+    /// Whether tcl/tk files are embedded in packed resources.
+    ///
+    /// If true, tcl/tk files are stored in packed resources under
+    /// `tcl_files_path()` and extracted to a temporary directory at run-time
+    /// instead of being installed next to the binary.
+    fn tcl_files_embedded(&self) -> bool;
+
+    /// Set whether tcl/tk files are embedded in packed resources.
+    fn set_tcl_files_embedded(&mut self, value: bool);
+
+    /// The directory to install a terminfo database into.
+    ///
+    /// If set, the build machine's terminfo database is installed in this
+    /// directory relative to the built binary and the interpreter is
+    /// configured to use it.
+    fn terminfo_files_path(&self) -> &Option<String>;
+
+    /// Set the directory to install a terminfo database into.
+    fn set_terminfo_files_path(&mut self, value: Option<String>);
+
+    /// Set the directory containing Qt plugins, relative to the built binary.
+    fn set_qt_plugin_path(&mut self, value: Option<String>);
+
+    /// Set the directory containing QML imports, relative to the built binary.
+    fn set_qml_import_path(&mut self, value: Option<String>);
+
+    /// Replace the run modes of the interpreter.
+    ///
+    /// The run mode flag is cleared so the run modes can't be overridden at run time.
+    fn set_run_modes(&mut self, value: Vec<RunMode>);
+
+    /// Import a package from a filesystem directory ahead of packed resources.
+    ///
+    /// `path` is a directory containing the package, searched like a `sys.path` entry.
+    fn add_package_source_path(&mut self, package: &str, path: &Path);
+
     /// The value of the `windows_subsystem` Rust attribute for the generated Rust project.
     fn windows_subsystem(&self) -> &str;
 
     /// Set the value of the `windows_subsystem` Rust attribute for generated Rust projects.
     fn set_windows_subsystem(&mut self, value: &str) -> Result<()>;
 
+    /// Resources to compile into Windows executables.
+    ///
+    /// These are ignored when not building for Windows.
+    fn windows_resources(&self) -> &WindowsExecutableResources;
+
+    /// Set the resources to compile into Windows executables.
+    fn set_windows_resources(&mut self, value: WindowsExecutableResources);
+
     /// Obtain the path of a filename to write containing a licensing report.
     fn licenses_filename(&self) -> Option<&str>;
 
     /// Set the path of a filename to write containing a licensing report.
     fn set_licenses_filename(&mut self, value: Option<String>);
 
+    /// Obtain the path of a filename to write containing a software bill of materials.
+    fn sbom_filename(&self) -> Option<&str>;
+
+    /// Set the path of a filename to write containing a software bill of materials.
+    fn set_sbom_filename(&mut self, value: Option<String>);
+
+    /// Whether to build in a mode that produces byte-identical output from identical inputs.
+    fn deterministic(&self) -> bool;
+
+    /// Set whether to build in a mode that produces byte-identical output from identical inputs.
+    fn set_deterministic(&mut self, value: bool);
+
+    /// Arguments to run an instrumented executable with to collect optimization profiles.
+    ///
+    /// When set, release builds are optimized with profile-guided optimization.
+    fn pgo_training_args(&self) -> Option<&[String]>;
+
+    /// Set arguments to run an instrumented executable with to collect optimization profiles.
+    fn set_pgo_training_args(&mut self, value: Option<Vec<String>>);
+
+    /// Whether to optimize the executable with BOLT after it is linked.
+    fn bolt(&self) -> bool;
+
+    /// Set whether to optimize the executable with BOLT after it is linked.
+    fn set_bolt(&mut self, value: bool);
+
     /// How packed Python resources will be loaded by the binary.
     fn packed_resources_load_mode(&self) -> &PackedResourcesLoadMode;
 
@@ -257,22 +483,34 @@ pub trait PythonBinaryBuilder {
     /// Runs `pip download` using the binary builder's settings.
     ///
     /// Returns resources discovered from the Python packages downloaded.
+    ///
+    /// If `requirements_path` is defined, downloaded packages are verified
+    /// against the `--hash` pins in that requirements file.
+    ///
+    /// If `build_from_source` is true, packages without a compatible wheel
+    /// are built from source distributions.
     fn pip_download(
         &mut self,
         env: &Environment,
         verbose: bool,
         args: &[String],
+        requirements_path: Option<&Path>,
+        build_from_source: bool,
     ) -> Result<Vec<PythonResource>>;
 
     /// Runs `pip install` using the binary builder's settings.
     ///
     /// Returns resources discovered as part of performing an install.
+    ///
+    /// If `requirements_path` is defined, packages are verified against the
+    /// `--hash` pins in that requirements file before being installed.
     fn pip_install(
         &mut self,
         env: &Environment,
         verbose: bool,
         install_args: &[String],
         extra_envs: &HashMap<String, String>,
+        requirements_path: Option<&Path>,
     ) -> Result<Vec<PythonResource>>;
 
     /// Reads Python resources from the filesystem.
@@ -297,6 +535,17 @@ pub trait PythonBinaryBuilder {
         extra_global_arguments: &[String],
     ) -> Result<Vec<PythonResource>>;
 
+    /// Builds a wheel from a local source tree via its PEP 517 build backend.
+    ///
+    /// Returns resources contained in the built wheel.
+    fn pep517_build_wheel(
+        &mut self,
+        env: &Environment,
+        package_path: &Path,
+        verbose: bool,
+        extra_envs: &HashMap<String, String>,
+    ) -> Result<Vec<PythonResource>>;
+
     /// Add resources from the Python distribution to the builder.
     ///
     /// This method should likely be called soon after object construction
@@ -373,6 +622,12 @@ pub trait PythonBinaryBuilder {
         add_context: Option<PythonResourceAddCollectionContext>,
     ) -> Result<Vec<AddResourceAction>>;
 
+    /// Add a `File` as an arbitrary data blob belonging to a named namespace.
+    ///
+    /// Data blobs are always embedded in packed resources and are exposed
+    /// by the run-time APIs for enumerating and reading application data.
+    fn add_data_file(&mut self, namespace: &str, file: &File) -> Result<Vec<AddResourceAction>>;
+
     /// Filter embedded resources against names in files.
     ///
     /// `files` is files to read names from.
@@ -384,6 +639,27 @@ pub trait PythonBinaryBuilder {
         glob_patterns: &[&str],
     ) -> Result<()>;
 
+    /// Remove Python modules that aren't imported by the application.
+    ///
+    /// Imports are found by scanning source code, starting from `roots`, the
+    /// modules the interpreter runs, and modules the interpreter imports on its
+    /// own. `roots` are scanned as if executed as `__main__`. Modules and
+    /// extension modules that are never imported are removed.
+    ///
+    /// `allow` is names of modules to always retain, along with their
+    /// submodules. This is used for modules imported dynamically.
+    fn prune_unused_modules(&mut self, roots: &[&str], allow: &[&str]) -> Result<()>;
+
+    /// Obtain additional annotations influencing how the binary is linked.
+    fn linking_annotations(&self) -> &[LinkingAnnotation];
+
+    /// Add an annotation influencing how the binary is linked.
+    ///
+    /// This can be used to link additional native libraries and object files
+    /// into the binary. Annotations are applied in addition to those required
+    /// to link libpython.
+    fn add_linking_annotation(&mut self, annotation: LinkingAnnotation);
+
     /// Whether the binary requires the jemalloc library.
     fn requires_jemalloc(&self) -> bool;
 
@@ -403,6 +679,12 @@ pub trait PythonBinaryBuilder {
     /// generate a licensing report.
     fn add_licensed_component(&mut self, component: LicensedComponent) -> Result<()>;
 
+    /// Compile resources that would be embedded in the binary.
+    ///
+    /// This performs bytecode compilation and returns the collection of
+    /// resources that would be serialized to packed resources data.
+    fn compile_resources(&self, env: &Environment) -> Result<CompiledResourcesCollection>;
+
     /// Obtain an `EmbeddedPythonContext` instance from this one.
     fn to_embedded_python_context(
         &self,
@@ -449,4 +731,16 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_windows_version_numeric() -> Result<()> {
+        assert_eq!(windows_version_numeric("1")?, "1,0,0,0");
+        assert_eq!(windows_version_numeric("1.2.3")?, "1,2,3,0");
+        assert_eq!(windows_version_numeric("1.2.3.4")?, "1,2,3,4");
+        assert!(windows_version_numeric("1.2.3.4.5").is_err());
+        assert!(windows_version_numeric("1.2-beta").is_err());
+        assert!(windows_version_numeric("70000").is_err());
+
+        Ok(())
+    }
 }
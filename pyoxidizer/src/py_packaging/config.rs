@@ -12,12 +12,13 @@ use {
     python_packaging::{
         interpreter::{
             Allocator, BytesWarning, CheckHashPycsMode, CoerceCLocale, MemoryAllocatorBackend,
-            MultiprocessingStartMethod, PythonInterpreterConfig, PythonInterpreterProfile,
+            MultiprocessingStartMethod, PythonInterpreterConfig, PythonInterpreterProfile, RunMode,
             TerminfoResolution,
         },
         resource::BytecodeOptimizationLevel,
     },
     std::{
+        collections::BTreeMap,
         io::Write,
         path::{Path, PathBuf},
     },
@@ -125,6 +126,7 @@ pub struct PyembedPythonInterpreterConfig {
     pub allocator_debug: bool,
     pub set_missing_path_configuration: bool,
     pub oxidized_importer: bool,
+    pub oxidized_importer_lazy_loading: bool,
     pub filesystem_importer: bool,
     pub packed_resources: Vec<PyembedPackedResourcesSource>,
     pub argvb: bool,
@@ -132,9 +134,22 @@ pub struct PyembedPythonInterpreterConfig {
     pub multiprocessing_start_method: MultiprocessingStartMethod,
     pub sys_frozen: bool,
     pub sys_meipass: bool,
+    pub replace_missing_stdio: bool,
     pub terminfo_resolution: TerminfoResolution,
     pub tcl_library: Option<PathBuf>,
+    pub tcl_files_embedded_prefix: Option<String>,
+    pub qt_plugin_path: Option<PathBuf>,
+    pub qml_import_path: Option<PathBuf>,
     pub write_modules_directory_env: Option<String>,
+    pub run_modes: Vec<RunMode>,
+    pub run_mode_flag: Option<String>,
+    pub environment_variables: BTreeMap<String, String>,
+    pub extension_modules_extraction_path: Option<PathBuf>,
+    pub package_source_paths: BTreeMap<String, PathBuf>,
+    pub plugin_paths: Vec<PathBuf>,
+    pub plugin_paths_override: bool,
+    pub file_emulation: bool,
+    pub package_file_emulation: BTreeMap<String, bool>,
 }
 
 impl Default for PyembedPythonInterpreterConfig {
@@ -160,6 +175,7 @@ impl Default for PyembedPythonInterpreterConfig {
             allocator_debug: false,
             set_missing_path_configuration: true,
             oxidized_importer: true,
+            oxidized_importer_lazy_loading: false,
             filesystem_importer: false,
             packed_resources: vec![],
             argvb: false,
@@ -167,9 +183,22 @@ impl Default for PyembedPythonInterpreterConfig {
             multiprocessing_start_method: MultiprocessingStartMethod::Auto,
             sys_frozen: true,
             sys_meipass: false,
+            replace_missing_stdio: true,
             terminfo_resolution: TerminfoResolution::None,
             tcl_library: None,
+            tcl_files_embedded_prefix: None,
+            qt_plugin_path: None,
+            qml_import_path: None,
             write_modules_directory_env: None,
+            run_modes: vec![],
+            run_mode_flag: None,
+            environment_variables: BTreeMap::new(),
+            extension_modules_extraction_path: None,
+            package_source_paths: BTreeMap::new(),
+            plugin_paths: vec![],
+            plugin_paths_override: false,
+            file_emulation: false,
+            package_file_emulation: BTreeMap::new(),
         }
     }
 }
@@ -229,12 +258,14 @@ impl PyembedPythonInterpreterConfig {
             run_command: {},\n        \
             run_filename: {},\n        \
             run_module: {},\n        \
+            safe_path: {},\n        \
             show_ref_count: {},\n        \
             site_import: {},\n        \
             skip_first_source_line: {},\n        \
             stdio_encoding: {},\n        \
             stdio_errors: {},\n        \
             tracemalloc: {},\n        \
+            use_frozen_modules: {},\n        \
             user_site_directory: {},\n        \
             verbose: {},\n        \
             warn_options: {},\n        \
@@ -249,6 +280,7 @@ impl PyembedPythonInterpreterConfig {
             allocator_debug: {},\n    \
             set_missing_path_configuration: {},\n    \
             oxidized_importer: {},\n    \
+            oxidized_importer_lazy_loading: {},\n    \
             filesystem_importer: {},\n    \
             packed_resources: {},\n    \
             extra_extension_modules: None,\n    \
@@ -258,9 +290,22 @@ impl PyembedPythonInterpreterConfig {
             multiprocessing_start_method: {},\n    \
             sys_frozen: {},\n    \
             sys_meipass: {},\n    \
+            replace_missing_stdio: {},\n    \
             terminfo_resolution: {},\n    \
             tcl_library: {},\n    \
+            tcl_files_embedded_prefix: {},\n    \
+            qt_plugin_path: {},\n    \
+            qml_import_path: {},\n    \
             write_modules_directory_env: {},\n    \
+            run_modes: {},\n    \
+            run_mode_flag: {},\n    \
+            environment_variables: {},\n    \
+            extension_modules_extraction_path: {},\n    \
+            package_source_paths: {},\n    \
+            plugin_paths: {},\n    \
+            plugin_paths_override: {},\n    \
+            file_emulation: {},\n    \
+            package_file_emulation: {},\n    \
             }}\n\
             ",
             match self.config.profile {
@@ -356,12 +401,14 @@ impl PyembedPythonInterpreterConfig {
             optional_string_to_string(&self.config.run_command),
             optional_pathbuf_to_string(&self.config.run_filename),
             optional_string_to_string(&self.config.run_module),
+            optional_bool_to_string(&self.config.safe_path),
             optional_bool_to_string(&self.config.show_ref_count),
             optional_bool_to_string(&self.config.site_import),
             optional_bool_to_string(&self.config.skip_first_source_line),
             optional_string_to_string(&self.config.stdio_encoding),
             optional_string_to_string(&self.config.stdio_errors),
             optional_bool_to_string(&self.config.tracemalloc),
+            optional_bool_to_string(&self.config.use_frozen_modules),
             optional_bool_to_string(&self.config.user_site_directory),
             optional_bool_to_string(&self.config.verbose),
             optional_vec_string_to_string(&self.config.warn_options),
@@ -381,6 +428,7 @@ impl PyembedPythonInterpreterConfig {
             self.allocator_debug,
             self.set_missing_path_configuration,
             self.oxidized_importer,
+            self.oxidized_importer_lazy_loading,
             self.filesystem_importer,
             format!(
                 "vec![{}]",
@@ -405,15 +453,75 @@ impl PyembedPythonInterpreterConfig {
             },
             self.sys_frozen,
             self.sys_meipass,
+            self.replace_missing_stdio,
             match self.terminfo_resolution {
                 TerminfoResolution::Dynamic => "pyembed::TerminfoResolution::Dynamic".to_string(),
                 TerminfoResolution::None => "pyembed::TerminfoResolution::None".to_string(),
                 TerminfoResolution::Static(ref v) => {
-                    format!("pyembed::TerminfoResolution::Static(r###\"{}\"###", v)
+                    format!(
+                        "pyembed::TerminfoResolution::Static(r###\"{}\"###.to_string())",
+                        v
+                    )
                 }
             },
             optional_pathbuf_to_string(&self.tcl_library),
+            optional_string_to_string(&self.tcl_files_embedded_prefix),
+            optional_pathbuf_to_string(&self.qt_plugin_path),
+            optional_pathbuf_to_string(&self.qml_import_path),
             optional_string_to_string(&self.write_modules_directory_env),
+            format!(
+                "vec![{}]",
+                self.run_modes
+                    .iter()
+                    .map(|mode| match mode {
+                        RunMode::Module(name) =>
+                            format!("pyembed::RunMode::Module(r###\"{}\"###.to_string())", name),
+                        RunMode::Eval(code) =>
+                            format!("pyembed::RunMode::Eval(r###\"{}\"###.to_string())", code),
+                        RunMode::File => "pyembed::RunMode::File".to_string(),
+                        RunMode::Repl => "pyembed::RunMode::Repl".to_string(),
+                    })
+                    .join(", ")
+            ),
+            optional_string_to_string(&self.run_mode_flag),
+            format!(
+                "vec![{}].into_iter().collect()",
+                self.environment_variables
+                    .iter()
+                    .map(|(k, v)| format!(
+                        "(r###\"{}\"###.to_string(), r###\"{}\"###.to_string())",
+                        k, v
+                    ))
+                    .join(", ")
+            ),
+            optional_pathbuf_to_string(&self.extension_modules_extraction_path),
+            format!(
+                "vec![{}].into_iter().collect()",
+                self.package_source_paths
+                    .iter()
+                    .map(|(k, v)| format!(
+                        "(r###\"{}\"###.to_string(), std::path::PathBuf::from(r###\"{}\"###))",
+                        k,
+                        v.display()
+                    ))
+                    .join(", ")
+            ),
+            format!(
+                "vec![{}]",
+                self.plugin_paths
+                    .iter()
+                    .map(|p| format!("std::path::PathBuf::from(r###\"{}\"###)", p.display()))
+                    .join(", ")
+            ),
+            self.plugin_paths_override,
+            self.file_emulation,
+            format!(
+                "vec![{}].into_iter().collect()",
+                self.package_file_emulation
+                    .iter()
+                    .map(|(k, v)| format!("(r###\"{}\"###.to_string(), {})", k, v))
+                    .join(", ")
+            ),
         );
 
         Ok(code)
@@ -570,12 +678,14 @@ mod tests {
                 run_command: Some("command".into()),
                 run_filename: Some("filename".into()),
                 run_module: Some("module".into()),
+                safe_path: Some(true),
                 show_ref_count: Some(false),
                 site_import: Some(true),
                 skip_first_source_line: Some(false),
                 stdio_encoding: Some("encoding".into()),
                 stdio_errors: Some("errors".into()),
                 tracemalloc: Some(false),
+                use_frozen_modules: Some(false),
                 user_site_directory: Some(false),
                 verbose: Some(true),
                 warn_options: Some(vec!["option0".into(), "option1".into()]),
@@ -590,6 +700,7 @@ mod tests {
             allocator_debug: true,
             set_missing_path_configuration: false,
             oxidized_importer: true,
+            oxidized_importer_lazy_loading: true,
             filesystem_importer: true,
             packed_resources: vec![
                 PyembedPackedResourcesSource::MemoryIncludeBytes(PathBuf::from("packed-resources")),
@@ -600,9 +711,30 @@ mod tests {
             argvb: true,
             sys_frozen: false,
             sys_meipass: true,
-            terminfo_resolution: TerminfoResolution::Dynamic,
+            replace_missing_stdio: false,
+            terminfo_resolution: TerminfoResolution::Static("$ORIGIN/terminfo".into()),
             tcl_library: Some("path".into()),
+            tcl_files_embedded_prefix: Some("prefix/".into()),
+            qt_plugin_path: Some("plugins".into()),
+            qml_import_path: Some("qml".into()),
             write_modules_directory_env: Some("env".into()),
+            run_modes: vec![
+                RunMode::Module("module".into()),
+                RunMode::Eval("import sys".into()),
+                RunMode::File,
+                RunMode::Repl,
+            ],
+            run_mode_flag: Some("--run-mode".into()),
+            environment_variables: BTreeMap::from([
+                ("FOO".into(), "foo".into()),
+                ("BAR".into(), "$ORIGIN/bar".into()),
+            ]),
+            extension_modules_extraction_path: Some("extensions".into()),
+            package_source_paths: BTreeMap::from([("myapp".into(), "$ORIGIN/src".into())]),
+            plugin_paths: vec!["$HOME/.myapp/plugins".into()],
+            plugin_paths_override: true,
+            file_emulation: true,
+            package_file_emulation: BTreeMap::from([("myapp".into(), false)]),
             multiprocessing_auto_dispatch: false,
             multiprocessing_start_method: MultiprocessingStartMethod::Spawn,
         };
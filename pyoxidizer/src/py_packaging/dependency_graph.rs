@@ -0,0 +1,952 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! Python module and distribution dependency graphs of built artifacts.
+
+The packed resources data embedded in a built binary describes every Python
+module it contains. By scanning module source code for `import` statements
+and attributing modules to the package distributions that installed them,
+we can reconstruct which modules and distributions pull in which others.
+This helps identify what can be removed to shrink a binary.
+
+Imports are discovered by scanning source code line by line rather than by
+parsing it. Modules without source code have no outgoing edges. Imports
+that are computed at run-time, such as via `importlib.import_module()`,
+aren't detected.
+*/
+
+use {
+    anyhow::{anyhow, Context, Result},
+    python_packed_resources::{load_resources, Resource, HEADER_V3, HEADER_V4},
+    serde::Serialize,
+    std::{
+        borrow::Cow,
+        collections::{BTreeMap, BTreeSet, VecDeque},
+        path::Path,
+    },
+};
+
+/// An `import` or `from ... import` statement.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct ImportStatement {
+    /// The module being imported from.
+    ///
+    /// Empty for `from . import x`.
+    module: String,
+
+    /// Number of leading dots in a relative import.
+    level: usize,
+
+    /// Names imported by `from ... import`.
+    names: Vec<String>,
+}
+
+fn is_dotted_name(s: &str) -> bool {
+    !s.is_empty()
+        && s.split('.').all(|part| {
+            !part.is_empty()
+                && !part.starts_with(|c: char| c.is_ascii_digit())
+                && part.chars().all(|c| c.is_alphanumeric() || c == '_')
+        })
+}
+
+fn parse_import_statement(statement: &str, res: &mut Vec<ImportStatement>) {
+    if let Some(rest) = statement.strip_prefix("import ") {
+        for part in rest.split(',') {
+            if let Some(name) = part.split_whitespace().next() {
+                if is_dotted_name(name) {
+                    res.push(ImportStatement {
+                        module: name.to_string(),
+                        level: 0,
+                        names: vec![],
+                    });
+                }
+            }
+        }
+    } else if let Some(rest) = statement.strip_prefix("from ") {
+        if let Some((module, names)) = rest.split_once(" import") {
+            let module = module.trim();
+            let level = module.chars().take_while(|c| *c == '.').count();
+            let module = &module[level..];
+
+            if (!module.is_empty() && !is_dotted_name(module)) || (module.is_empty() && level == 0)
+            {
+                return;
+            }
+
+            let names = names
+                .trim()
+                .trim_start_matches('(')
+                .trim_end_matches(')')
+                .split(',')
+                .filter_map(|part| part.split_whitespace().next())
+                .filter(|name| is_dotted_name(name))
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>();
+
+            res.push(ImportStatement {
+                module: module.to_string(),
+                level,
+                names,
+            });
+        }
+    }
+}
+
+/// Find import statements in Python source code.
+fn find_imports(source: &str) -> Vec<ImportStatement> {
+    let mut res = vec![];
+    let mut statement = String::new();
+    let mut depth = 0;
+
+    for line in source.lines() {
+        let line = match line.find('#') {
+            Some(i) => &line[0..i],
+            None => line,
+        };
+
+        if statement.is_empty() {
+            let trimmed = line.trim_start();
+            if !trimmed.starts_with("import ") && !trimmed.starts_with("from ") {
+                continue;
+            }
+        }
+
+        let (line, continued) = match line.trim_end().strip_suffix('\\') {
+            Some(line) => (line, true),
+            None => (line, false),
+        };
+
+        statement.push_str(line.trim());
+        statement.push(' ');
+
+        depth += line.matches('(').count() as i64 - line.matches(')').count() as i64;
+
+        if continued || depth > 0 {
+            continue;
+        }
+
+        for part in statement.split(';') {
+            parse_import_statement(part.trim(), &mut res);
+        }
+
+        statement.clear();
+        depth = 0;
+    }
+
+    res
+}
+
+/// Derive the name of a Python module from a path in a distribution's `RECORD` file.
+pub(crate) fn module_name_from_record_path(path: &str) -> Option<String> {
+    let (parent, filename) = match path.rsplit_once('/') {
+        Some((parent, filename)) => (Some(parent), filename),
+        None => (None, path),
+    };
+
+    let stem = if let Some(stem) = filename.strip_suffix(".py") {
+        stem
+    } else if filename.ends_with(".so") || filename.ends_with(".pyd") {
+        filename.split('.').next()?
+    } else {
+        return None;
+    };
+
+    let mut parts = parent
+        .map(|p| p.split('/').collect::<Vec<_>>())
+        .unwrap_or_default();
+    if stem != "__init__" {
+        parts.push(stem);
+    }
+
+    let name = parts.join(".");
+
+    if is_dotted_name(&name) {
+        Some(name)
+    } else {
+        None
+    }
+}
+
+/// A Python module in a dependency graph.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ModuleNode {
+    /// Fully qualified name of the module.
+    pub name: String,
+
+    /// Name of the distribution providing this module, if known.
+    pub distribution: Option<String>,
+
+    /// Whether the module is a package.
+    pub is_package: bool,
+
+    /// Size in bytes of data for this module in the artifact.
+    pub size: u64,
+
+    /// Modules in the artifact imported by this module.
+    pub imports: BTreeSet<String>,
+
+    /// Modules imported by this module that aren't in the artifact.
+    pub unresolved_imports: BTreeSet<String>,
+}
+
+/// A Python package distribution in a dependency graph.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct DistributionNode {
+    /// Name of the distribution.
+    pub name: String,
+
+    /// Version of the distribution, if known.
+    pub version: Option<String>,
+
+    /// Distributions declared as requirements in the distribution's metadata.
+    pub requires: Vec<String>,
+
+    /// Modules provided by this distribution.
+    pub modules: BTreeSet<String>,
+}
+
+/// A distribution depended on by another distribution.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct DistributionDependency {
+    /// Name of the distribution depended on.
+    pub name: String,
+
+    /// Chain of module imports leading to the dependency.
+    ///
+    /// The first module belongs to the depending distribution and the last to
+    /// the dependency. For direct dependencies, this is a single import.
+    pub via: Vec<String>,
+}
+
+/// The module and distribution dependency graph of an artifact.
+#[derive(Clone, Debug, Default)]
+pub struct DependencyGraph {
+    modules: BTreeMap<String, ModuleNode>,
+    distributions: BTreeMap<String, DistributionNode>,
+}
+
+#[derive(Serialize)]
+struct DistributionReport<'a> {
+    #[serde(flatten)]
+    distribution: &'a DistributionNode,
+    size: u64,
+    dependencies: Vec<DistributionDependency>,
+    transitive_dependencies: Vec<DistributionDependency>,
+    transitive_size: u64,
+}
+
+#[derive(Serialize)]
+struct GraphReport<'a> {
+    modules: Vec<&'a ModuleNode>,
+    distributions: Vec<DistributionReport<'a>>,
+}
+
+/// Quote a string for use as a Graphviz identifier.
+fn dot_quote(s: &str) -> String {
+    format!(
+        "\"{}\"",
+        s.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+/// Find and parse packed resources data in a binary.
+///
+/// `data` can be packed resources data or a binary embedding it. If multiple
+/// candidates are found, the one with the most resources wins.
+pub fn find_packed_resources(data: &[u8]) -> Option<Vec<Resource<u8>>> {
+    data.windows(HEADER_V3.len())
+        .enumerate()
+        .filter(|(_, window)| *window == HEADER_V3 || *window == HEADER_V4)
+        .filter_map(|(offset, _)| {
+            load_resources(&data[offset..])
+                .ok()?
+                .collect::<Result<Vec<_>, &'static str>>()
+                .ok()
+        })
+        .filter(|resources| !resources.is_empty())
+        .max_by_key(|resources| resources.len())
+}
+
+impl DependencyGraph {
+    /// Construct an instance from packed resources.
+    ///
+    /// `base_dir` is the directory that paths of resources installed next to
+    /// the binary are relative to.
+    pub fn from_resources(resources: &[Resource<u8>], base_dir: &Path) -> Result<Self> {
+        let mut graph = Self::default();
+        let mut sources = BTreeMap::new();
+        let mut distribution_files = BTreeMap::new();
+
+        for resource in resources {
+            let resolve = |data: &Cow<[u8]>| -> Result<Vec<u8>> {
+                Ok(resource.resolve_in_memory_data(data)?.into_owned())
+            };
+            let read_relative = |path: &Path| -> Result<Vec<u8>> {
+                let path = base_dir.join(path);
+                std::fs::read(&path).with_context(|| format!("reading {}", path.display()))
+            };
+            let relative_size = |path: &Path| -> u64 {
+                std::fs::metadata(base_dir.join(path))
+                    .map(|m| m.len())
+                    .unwrap_or(0)
+            };
+
+            let mut files = BTreeMap::new();
+            if let Some(resources) = &resource.in_memory_distribution_resources {
+                for (name, data) in resources {
+                    files.insert(name.to_string(), resolve(data)?);
+                }
+            }
+            if let Some(resources) = &resource.relative_path_distribution_resources {
+                for (name, path) in resources {
+                    if let Ok(data) = read_relative(path) {
+                        files.insert(name.to_string(), data);
+                    }
+                }
+            }
+            if !files.is_empty() {
+                distribution_files.insert(resource.name.to_string(), files);
+            }
+
+            if !(resource.is_python_module
+                || resource.is_python_extension_module
+                || resource.is_python_builtin_extension_module
+                || resource.is_python_frozen_module)
+            {
+                continue;
+            }
+
+            // Resources holding only distribution metadata aren't modules.
+            if resource.in_memory_source.is_none()
+                && resource.in_memory_bytecode.is_none()
+                && resource.in_memory_bytecode_opt1.is_none()
+                && resource.in_memory_bytecode_opt2.is_none()
+                && resource.in_memory_extension_module_shared_library.is_none()
+                && resource.relative_path_module_source.is_none()
+                && resource.relative_path_module_bytecode.is_none()
+                && resource.relative_path_module_bytecode_opt1.is_none()
+                && resource.relative_path_module_bytecode_opt2.is_none()
+                && resource
+                    .relative_path_extension_module_shared_library
+                    .is_none()
+                && !resource.is_python_builtin_extension_module
+                && !resource.is_python_frozen_module
+                && !resource.is_python_namespace_package
+                && resource.in_memory_package_resources.is_none()
+                && resource.relative_path_package_resources.is_none()
+            {
+                continue;
+            }
+
+            let mut size = 0;
+            for data in [
+                &resource.in_memory_source,
+                &resource.in_memory_bytecode,
+                &resource.in_memory_bytecode_opt1,
+                &resource.in_memory_bytecode_opt2,
+                &resource.in_memory_extension_module_shared_library,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                size += data.len() as u64;
+            }
+            for path in [
+                &resource.relative_path_module_source,
+                &resource.relative_path_module_bytecode,
+                &resource.relative_path_module_bytecode_opt1,
+                &resource.relative_path_module_bytecode_opt2,
+                &resource.relative_path_extension_module_shared_library,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                size += relative_size(path);
+            }
+            if let Some(resources) = &resource.in_memory_package_resources {
+                size += resources.values().map(|d| d.len() as u64).sum::<u64>();
+            }
+            if let Some(resources) = &resource.relative_path_package_resources {
+                size += resources.values().map(|p| relative_size(p)).sum::<u64>();
+            }
+
+            if let Some(data) = &resource.in_memory_source {
+                sources.insert(resource.name.to_string(), resolve(data)?);
+            } else if let Some(path) = &resource.relative_path_module_source {
+                sources.insert(resource.name.to_string(), read_relative(path)?);
+            }
+
+            graph.modules.insert(
+                resource.name.to_string(),
+                ModuleNode {
+                    name: resource.name.to_string(),
+                    is_package: resource.is_python_package,
+                    size,
+                    ..ModuleNode::default()
+                },
+            );
+        }
+
+        for (name, source) in sources {
+            graph.add_imports(&name, &String::from_utf8_lossy(&source));
+        }
+
+        for (package, files) in distribution_files {
+            graph.add_distribution(&package, &files);
+        }
+
+        Ok(graph)
+    }
+
+    /// Construct an instance from a built binary or packed resources file.
+    pub fn from_artifact(path: &Path) -> Result<Self> {
+        let data = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+
+        let resources = find_packed_resources(&data).ok_or_else(|| {
+            anyhow!(
+                "unable to find packed resources data in {}; is it a binary built by PyOxidizer?",
+                path.display()
+            )
+        })?;
+
+        let base_dir = path
+            .parent()
+            .ok_or_else(|| anyhow!("unable to resolve parent directory"))?;
+
+        Self::from_resources(&resources, base_dir)
+    }
+
+    /// Modules in the graph, keyed by name.
+    pub fn modules(&self) -> &BTreeMap<String, ModuleNode> {
+        &self.modules
+    }
+
+    /// Distributions in the graph, keyed by name.
+    pub fn distributions(&self) -> &BTreeMap<String, DistributionNode> {
+        &self.distributions
+    }
+
+    fn add_imports(&mut self, name: &str, source: &str) {
+        let is_package = self.modules[name].is_package;
+        let mut imports = BTreeSet::new();
+        let mut unresolved = BTreeSet::new();
+
+        for statement in find_imports(source) {
+            let base = if statement.level > 0 {
+                let mut package = name.split('.').collect::<Vec<_>>();
+                if !is_package {
+                    package.pop();
+                }
+                for _ in 1..statement.level {
+                    package.pop();
+                }
+                if !statement.module.is_empty() {
+                    package.push(&statement.module);
+                }
+
+                package.join(".")
+            } else {
+                statement.module.clone()
+            };
+
+            if base.is_empty() {
+                continue;
+            }
+
+            // Importing a module imports its parent packages.
+            let mut prefix = String::new();
+            for part in base.split('.') {
+                if !prefix.is_empty() {
+                    prefix.push('.');
+                }
+                prefix.push_str(part);
+
+                if self.modules.contains_key(&prefix) {
+                    imports.insert(prefix.clone());
+                }
+            }
+            if !self.modules.contains_key(&base) {
+                unresolved.insert(base.clone());
+            }
+
+            // `from package import name` may import a submodule.
+            for imported in statement.names {
+                let candidate = format!("{}.{}", base, imported);
+                if self.modules.contains_key(&candidate) {
+                    imports.insert(candidate);
+                }
+            }
+        }
+
+        imports.remove(name);
+
+        let module = self.modules.get_mut(name).expect("module should exist");
+        module.imports = imports;
+        module.unresolved_imports = unresolved;
+    }
+
+    fn add_distribution(&mut self, package: &str, files: &BTreeMap<String, Vec<u8>>) {
+        let mut distribution = DistributionNode {
+            name: package.to_string(),
+            ..DistributionNode::default()
+        };
+
+        if let Some(metadata) = files.get("METADATA").or_else(|| files.get("PKG-INFO")) {
+            for line in String::from_utf8_lossy(metadata).lines() {
+                // Headers end at the first blank line.
+                if line.is_empty() {
+                    break;
+                }
+
+                if let Some(value) = line.strip_prefix("Name: ") {
+                    distribution.name = value.trim().to_string();
+                } else if let Some(value) = line.strip_prefix("Version: ") {
+                    distribution.version = Some(value.trim().to_string());
+                } else if let Some(value) = line.strip_prefix("Requires-Dist: ") {
+                    let requirement = value
+                        .split(|c: char| c.is_whitespace() || ";([<>=!~".contains(c))
+                        .next()
+                        .unwrap_or_default();
+                    if !requirement.is_empty() {
+                        distribution.requires.push(requirement.to_string());
+                    }
+                }
+            }
+        }
+
+        let mut claim = |graph: &mut Self, module: &str| {
+            if let Some(node) = graph.modules.get_mut(module) {
+                if node.distribution.is_none() {
+                    node.distribution = Some(distribution.name.clone());
+                    distribution.modules.insert(module.to_string());
+                }
+            }
+        };
+
+        if let Some(record) = files.get("RECORD") {
+            for line in String::from_utf8_lossy(record).lines() {
+                let path = line.split(',').next().unwrap_or_default();
+                if let Some(module) = module_name_from_record_path(path) {
+                    claim(self, &module);
+                }
+            }
+        }
+
+        if let Some(top_level) = files.get("top_level.txt") {
+            let top_level = String::from_utf8_lossy(top_level);
+            let names = self.modules.keys().cloned().collect::<Vec<_>>();
+
+            for top in top_level
+                .lines()
+                .map(|l| l.trim())
+                .filter(|l| !l.is_empty())
+            {
+                let prefix = format!("{}.", top);
+                for name in names.iter().filter(|n| *n == top || n.starts_with(&prefix)) {
+                    claim(self, name);
+                }
+            }
+        }
+
+        self.distributions
+            .insert(distribution.name.clone(), distribution);
+    }
+
+    /// Distributions directly imported by modules of a distribution.
+    ///
+    /// Each dependency records the first import responsible for it.
+    pub fn distribution_dependencies(&self, distribution: &str) -> Vec<DistributionDependency> {
+        let mut res = BTreeMap::new();
+
+        if let Some(dist) = self.distributions.get(distribution) {
+            for module in &dist.modules {
+                for imported in &self.modules[module].imports {
+                    if let Some(other) = &self.modules[imported].distribution {
+                        if other != distribution && !res.contains_key(other) {
+                            res.insert(
+                                other.clone(),
+                                DistributionDependency {
+                                    name: other.clone(),
+                                    via: vec![module.clone(), imported.clone()],
+                                },
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        res.into_values().collect()
+    }
+
+    /// Modules reachable from a distribution's modules, excluding its own.
+    ///
+    /// Values are the shortest chain of imports reaching the module.
+    fn reachable_modules(&self, distribution: &str) -> BTreeMap<String, Vec<String>> {
+        let mut chains: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut queue = VecDeque::new();
+
+        if let Some(dist) = self.distributions.get(distribution) {
+            for module in &dist.modules {
+                chains.insert(module.clone(), vec![module.clone()]);
+                queue.push_back(module.clone());
+            }
+        }
+
+        while let Some(module) = queue.pop_front() {
+            for imported in &self.modules[&module].imports {
+                if !chains.contains_key(imported) {
+                    let mut chain = chains[&module].clone();
+                    chain.push(imported.clone());
+                    chains.insert(imported.clone(), chain);
+                    queue.push_back(imported.clone());
+                }
+            }
+        }
+
+        chains.retain(|name, _| self.modules[name].distribution.as_deref() != Some(distribution));
+
+        chains
+    }
+
+    /// Distributions transitively imported by modules of a distribution.
+    ///
+    /// Each dependency records the shortest chain of imports responsible for it.
+    pub fn transitive_distribution_dependencies(
+        &self,
+        distribution: &str,
+    ) -> Vec<DistributionDependency> {
+        let mut res: BTreeMap<String, DistributionDependency> = BTreeMap::new();
+
+        for (name, chain) in self.reachable_modules(distribution) {
+            if let Some(other) = &self.modules[&name].distribution {
+                let replace = match res.get(other) {
+                    Some(existing) => chain.len() < existing.via.len(),
+                    None => true,
+                };
+
+                if replace {
+                    res.insert(
+                        other.clone(),
+                        DistributionDependency {
+                            name: other.clone(),
+                            via: chain,
+                        },
+                    );
+                }
+            }
+        }
+
+        res.into_values().collect()
+    }
+
+    /// Serialize the graph to JSON.
+    pub fn to_json(&self) -> Result<String> {
+        let report = GraphReport {
+            modules: self.modules.values().collect(),
+            distributions: self
+                .distributions
+                .values()
+                .map(|distribution| DistributionReport {
+                    distribution,
+                    size: distribution
+                        .modules
+                        .iter()
+                        .map(|m| self.modules[m].size)
+                        .sum(),
+                    dependencies: self.distribution_dependencies(&distribution.name),
+                    transitive_dependencies: self
+                        .transitive_distribution_dependencies(&distribution.name),
+                    transitive_size: self
+                        .reachable_modules(&distribution.name)
+                        .keys()
+                        .map(|m| self.modules[m].size)
+                        .sum(),
+                })
+                .collect(),
+        };
+
+        Ok(serde_json::to_string_pretty(&report)? + "\n")
+    }
+
+    /// Render the distribution dependency graph in Graphviz DOT format.
+    pub fn to_dot(&self) -> String {
+        let mut lines = vec![
+            "digraph dependencies {".to_string(),
+            "  rankdir=LR;".to_string(),
+        ];
+
+        for distribution in self.distributions.values() {
+            let size = distribution
+                .modules
+                .iter()
+                .map(|m| self.modules[m].size)
+                .sum::<u64>();
+
+            lines.push(format!(
+                "  {} [label={}];",
+                dot_quote(&distribution.name),
+                dot_quote(&format!("{}\n{} bytes", distribution.name, size))
+            ));
+        }
+
+        for distribution in self.distributions.values() {
+            for dependency in self.distribution_dependencies(&distribution.name) {
+                lines.push(format!(
+                    "  {} -> {} [tooltip={}];",
+                    dot_quote(&distribution.name),
+                    dot_quote(&dependency.name),
+                    dot_quote(&dependency.via.join(" -> "))
+                ));
+            }
+        }
+
+        lines.push("}".to_string());
+        lines.push(String::new());
+
+        lines.join("\n")
+    }
+
+    /// Render the module dependency graph in Graphviz DOT format.
+    ///
+    /// Modules are grouped by the distribution providing them.
+    pub fn to_module_dot(&self) -> String {
+        let mut lines = vec![
+            "digraph dependencies {".to_string(),
+            "  rankdir=LR;".to_string(),
+        ];
+
+        for (i, distribution) in self.distributions.values().enumerate() {
+            lines.push(format!("  subgraph cluster_{} {{", i));
+            lines.push(format!("    label={};", dot_quote(&distribution.name)));
+            for module in &distribution.modules {
+                lines.push(format!("    {};", dot_quote(module)));
+            }
+            lines.push("  }".to_string());
+        }
+
+        for module in self.modules.values() {
+            if module.distribution.is_none() {
+                lines.push(format!("  {};", dot_quote(&module.name)));
+            }
+        }
+
+        for module in self.modules.values() {
+            for imported in &module.imports {
+                lines.push(format!(
+                    "  {} -> {};",
+                    dot_quote(&module.name),
+                    dot_quote(imported)
+                ));
+            }
+        }
+
+        lines.push("}".to_string());
+        lines.push(String::new());
+
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        python_packed_resources::{write_packed_resources_v3, write_packed_resources_v4},
+        std::collections::HashMap,
+    };
+
+    fn module(name: &str, source: &str, is_package: bool) -> Resource<'static, u8> {
+        Resource {
+            name: Cow::Owned(name.to_string()),
+            is_python_module: true,
+            is_python_package: is_package,
+            in_memory_source: Some(Cow::Owned(source.as_bytes().to_vec())),
+            ..Resource::default()
+        }
+    }
+
+    fn with_distribution(
+        resource: Resource<'static, u8>,
+        files: &[(&str, &str)],
+    ) -> Resource<'static, u8> {
+        Resource {
+            in_memory_distribution_resources: Some(
+                files
+                    .iter()
+                    .map(|(name, data)| {
+                        (
+                            Cow::Owned(name.to_string()),
+                            Cow::Owned(data.as_bytes().to_vec()),
+                        )
+                    })
+                    .collect::<HashMap<_, _>>(),
+            ),
+            ..resource
+        }
+    }
+
+    #[test]
+    fn test_find_imports() {
+        let source = "import os, sys as system\n\
+            from . import sibling\n\
+            from ..parent.mod import (\n    a,\n    b as c,\n)\n\
+            def f():\n    import json  # comment\n\
+            x = 'import nothing'\n\
+            from foo import bar; import baz\n";
+
+        assert_eq!(
+            find_imports(source),
+            vec![
+                ImportStatement {
+                    module: "os".to_string(),
+                    level: 0,
+                    names: vec![]
+                },
+                ImportStatement {
+                    module: "sys".to_string(),
+                    level: 0,
+                    names: vec![]
+                },
+                ImportStatement {
+                    module: "".to_string(),
+                    level: 1,
+                    names: vec!["sibling".to_string()]
+                },
+                ImportStatement {
+                    module: "parent.mod".to_string(),
+                    level: 2,
+                    names: vec!["a".to_string(), "b".to_string()]
+                },
+                ImportStatement {
+                    module: "json".to_string(),
+                    level: 0,
+                    names: vec![]
+                },
+                ImportStatement {
+                    module: "foo".to_string(),
+                    level: 0,
+                    names: vec!["bar".to_string()]
+                },
+                ImportStatement {
+                    module: "baz".to_string(),
+                    level: 0,
+                    names: vec![]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_module_name_from_record_path() {
+        assert_eq!(
+            module_name_from_record_path("foo/__init__.py"),
+            Some("foo".to_string())
+        );
+        assert_eq!(
+            module_name_from_record_path("foo/bar.py"),
+            Some("foo.bar".to_string())
+        );
+        assert_eq!(
+            module_name_from_record_path("foo/_speedups.cpython-310-x86_64-linux-gnu.so"),
+            Some("foo._speedups".to_string())
+        );
+        assert_eq!(
+            module_name_from_record_path("foo-1.0.dist-info/RECORD"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_graph() -> Result<()> {
+        let resources = vec![
+            module("app", "import requests\nimport os\nimport missing\n", false),
+            module("os", "", false),
+            with_distribution(
+                module("requests", "from . import api\n", true),
+                &[
+                    (
+                        "METADATA",
+                        "Name: requests\nVersion: 2.28.1\nRequires-Dist: urllib3 (<1.27,>=1.21.1)\n\nBody\n",
+                    ),
+                    ("RECORD", "requests/__init__.py,,\nrequests/api.py,,\n"),
+                ],
+            ),
+            module("requests.api", "import urllib3\n", false),
+            with_distribution(
+                module("urllib3", "", true),
+                &[("METADATA", "Name: urllib3\n"), ("top_level.txt", "urllib3\n")],
+            ),
+        ];
+
+        // Version 3 data is also recognized.
+        let mut data = vec![];
+        write_packed_resources_v3(&resources, &mut data, None)?;
+        assert_eq!(find_packed_resources(&data), Some(resources.clone()));
+
+        // Round trip through serialized packed resources embedded in a larger blob.
+        let mut data = b"\x00pyembed\x04garbage".to_vec();
+        write_packed_resources_v4(&resources, &mut data, None)?;
+        data.extend(b"trailer");
+        let resources = find_packed_resources(&data).unwrap();
+
+        let graph = DependencyGraph::from_resources(&resources, Path::new("/nonexistent"))?;
+
+        let app = &graph.modules()["app"];
+        assert_eq!(app.distribution, None);
+        assert_eq!(
+            app.imports.iter().collect::<Vec<_>>(),
+            vec!["os", "requests"]
+        );
+        assert_eq!(
+            app.unresolved_imports.iter().collect::<Vec<_>>(),
+            vec!["missing"]
+        );
+        assert_eq!(
+            graph.modules()["requests"]
+                .imports
+                .iter()
+                .collect::<Vec<_>>(),
+            vec!["requests.api"]
+        );
+
+        let requests = &graph.distributions()["requests"];
+        assert_eq!(requests.version, Some("2.28.1".to_string()));
+        assert_eq!(requests.requires, vec!["urllib3".to_string()]);
+        assert_eq!(
+            requests.modules.iter().collect::<Vec<_>>(),
+            vec!["requests", "requests.api"]
+        );
+        assert_eq!(
+            graph.modules()["urllib3"].distribution,
+            Some("urllib3".to_string())
+        );
+
+        assert_eq!(
+            graph.distribution_dependencies("requests"),
+            vec![DistributionDependency {
+                name: "urllib3".to_string(),
+                via: vec!["requests.api".to_string(), "urllib3".to_string()],
+            }]
+        );
+        assert_eq!(
+            graph.transitive_distribution_dependencies("requests"),
+            graph.distribution_dependencies("requests")
+        );
+
+        let json = graph.to_json()?;
+        assert!(json.contains("\"transitive_dependencies\""));
+
+        let dot = graph.to_dot();
+        assert!(dot.contains("  \"requests\" -> \"urllib3\""));
+
+        let dot = graph.to_module_dot();
+        assert!(dot.contains("  \"requests.api\" -> \"urllib3\";"));
+
+        Ok(())
+    }
+}
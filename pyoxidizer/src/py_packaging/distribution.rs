@@ -15,7 +15,7 @@ use {
     crate::{environment::Environment, python_distributions::PYTHON_DISTRIBUTIONS},
     anyhow::{anyhow, Context, Result},
     fs2::FileExt,
-    log::info,
+    log::{info, warn},
     python_packaging::{
         bytecode::PythonBytecodeCompiler, module_util::PythonModuleSuffixes,
         policy::PythonPackagingPolicy, resource::PythonResource,
@@ -50,8 +50,18 @@ pub enum BinaryLibpythonLinkMode {
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub enum PythonDistributionLocation {
-    Local { local_path: String, sha256: String },
-    Url { url: String, sha256: String },
+    Local {
+        local_path: String,
+        sha256: String,
+    },
+    Url {
+        url: String,
+        sha256: String,
+    },
+    /// A directory containing an already extracted distribution.
+    Directory {
+        path: String,
+    },
 }
 
 impl std::fmt::Display for PythonDistributionLocation {
@@ -63,6 +73,9 @@ impl std::fmt::Display for PythonDistributionLocation {
             Self::Url { url, sha256 } => {
                 write!(f, "{} (sha256={})", url, sha256)
             }
+            Self::Directory { path } => {
+                write!(f, "{} (directory)", path)
+            }
         }
     }
 }
@@ -409,9 +422,49 @@ pub fn resolve_python_distribution_archive(
         PythonDistributionLocation::Url { url, sha256 } => {
             download_distribution(url, sha256, cache_dir)
         }
+        PythonDistributionLocation::Directory { path } => Err(anyhow!(
+            "Python distribution {} is a directory, not an archive",
+            path
+        )),
     }
 }
 
+/// Resolve the location of a Python distribution on the local filesystem.
+///
+/// `path` can be a distribution archive or a directory containing an
+/// extracted distribution, such as one produced by compiling CPython locally
+/// and providing a `python/PYTHON.json` metadata file.
+///
+/// The SHA-256 of an archive is computed if not provided. Directories are
+/// not hashed and cannot have a SHA-256 defined.
+pub fn local_distribution_location(
+    path: &Path,
+    sha256: Option<&str>,
+) -> Result<PythonDistributionLocation> {
+    let local_path = path.display().to_string();
+
+    if path.is_dir() {
+        if sha256.is_some() {
+            return Err(anyhow!(
+                "sha256 cannot be defined for distribution directory {}",
+                local_path
+            ));
+        }
+
+        return Ok(PythonDistributionLocation::Directory { path: local_path });
+    }
+
+    let sha256 = if let Some(sha256) = sha256 {
+        sha256.to_string()
+    } else if path.exists() {
+        hex::encode(sha256_path(path))
+    } else {
+        return Err(anyhow!("Python distribution {} does not exist", local_path));
+    };
+
+    Ok(PythonDistributionLocation::Local { local_path, sha256 })
+}
+
 /// Resolve a Python distribution archive.
 ///
 /// Returns a tuple of (archive path, extract directory).
@@ -419,15 +472,21 @@ pub fn resolve_python_distribution_from_location(
     location: &PythonDistributionLocation,
     distributions_dir: &Path,
 ) -> Result<(PathBuf, PathBuf)> {
-    info!("resolving Python distribution {}", location);
-    let path = resolve_python_distribution_archive(location, distributions_dir)?;
-    info!("Python distribution available at {}", path.display());
-
     let distribution_hash = match location {
         PythonDistributionLocation::Local { sha256, .. } => sha256,
         PythonDistributionLocation::Url { sha256, .. } => sha256,
+        PythonDistributionLocation::Directory { path } => {
+            return Err(anyhow!(
+                "Python distribution {} is a directory, not an archive",
+                path
+            ))
+        }
     };
 
+    info!("resolving Python distribution {}", location);
+    let path = resolve_python_distribution_archive(location, distributions_dir)?;
+    info!("Python distribution available at {}", path.display());
+
     let distribution_path = distributions_dir.join(format!("python.{}", &distribution_hash[0..12]));
 
     Ok((path, distribution_path))
@@ -581,12 +640,27 @@ impl DistributionCache {
         python_major_minor_version: Option<&str>,
         dest_dir: Option<&Path>,
     ) -> Result<Arc<StandaloneDistribution>> {
-        let location = default_distribution_location(
+        let host_triple = crate::environment::default_target_triple();
+
+        // Windows on ARM can run x86 binaries via emulation. If there isn't a native
+        // distribution for the machine, fall back to one that runs under emulation.
+        let location = match default_distribution_location(
             &DistributionFlavor::Standalone,
-            crate::environment::default_target_triple(),
+            host_triple,
             python_major_minor_version,
-        )
-        .context("resolving host distribution location")?;
+        ) {
+            Ok(location) => location,
+            Err(_) if host_triple == "aarch64-pc-windows-msvc" => {
+                warn!("no native ARM64 Windows Python distribution; using an emulated x86 one");
+                default_distribution_location(
+                    &DistributionFlavor::Standalone,
+                    "i686-pc-windows-msvc",
+                    python_major_minor_version,
+                )
+                .context("resolving host distribution location")?
+            }
+            Err(e) => return Err(e).context("resolving host distribution location"),
+        };
 
         self.resolve_distribution(&location, dest_dir)
             .context("resolving host distribution from location")
@@ -630,4 +704,42 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_local_distribution_location() -> Result<()> {
+        let env = get_env()?;
+        let temp_dir = env.temporary_directory("pyoxidizer-test")?;
+
+        let archive_path = temp_dir.path().join("cpython.tar.zst");
+        std::fs::write(&archive_path, b"foo")?;
+
+        assert_eq!(
+            local_distribution_location(&archive_path, None)?,
+            PythonDistributionLocation::Local {
+                local_path: archive_path.display().to_string(),
+                sha256: "2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae"
+                    .to_string(),
+            }
+        );
+        assert_eq!(
+            local_distribution_location(&archive_path, Some("deadbeef"))?,
+            PythonDistributionLocation::Local {
+                local_path: archive_path.display().to_string(),
+                sha256: "deadbeef".to_string(),
+            }
+        );
+
+        assert_eq!(
+            local_distribution_location(temp_dir.path(), None)?,
+            PythonDistributionLocation::Directory {
+                path: temp_dir.path().display().to_string(),
+            }
+        );
+        assert!(local_distribution_location(temp_dir.path(), Some("deadbeef")).is_err());
+        assert!(local_distribution_location(&temp_dir.path().join("missing"), None).is_err());
+
+        temp_dir.close()?;
+
+        Ok(())
+    }
 }
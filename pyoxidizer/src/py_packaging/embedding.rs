@@ -11,6 +11,7 @@ use {
         BuildFlags, InterpreterConfig as PyO3InterpreterConfig, PythonImplementation, PythonVersion,
     },
     python_packaging::{
+        interpreter::PythonInterpreterProfile,
         licensing::{LicensedComponent, LicensedComponents},
         resource_collection::CompiledResourcesCollection,
     },
@@ -58,6 +59,18 @@ impl LinkingAnnotation {
             }
         }
     }
+
+    /// Convert the instance to arguments for a C compiler driver like `cc`.
+    pub fn to_linker_args(&self) -> Vec<String> {
+        match self {
+            Self::LinkFramework(framework) => vec!["-framework".into(), framework.clone()],
+            Self::LinkLibrary(lib) | Self::LinkLibraryStatic(lib) => vec![format!("-l{}", lib)],
+            Self::Search(path) | Self::SearchNative(path) => {
+                vec![format!("-L{}", path.display())]
+            }
+            Self::Argument(arg) => vec![arg.clone()],
+        }
+    }
 }
 
 /// Resolver linking annotations for a given target triple.
@@ -279,6 +292,47 @@ impl From<LinkStaticLibraryData> for LibpythonLinkSettings {
 /// Filename of artifact containing the default PythonInterpreterConfig.
 pub const DEFAULT_PYTHON_CONFIG_FILENAME: &str = "default_python_config.rs";
 
+/// Filename of C header describing the default interpreter configuration.
+pub const DEFAULT_PYTHON_CONFIG_H_FILENAME: &str = "python_embedding_config.h";
+
+/// Name of the CMake package and pkg-config module for embedding artifacts.
+pub const BUILD_SYSTEM_PACKAGE_NAME: &str = "PythonEmbedding";
+
+/// Starlark macros written alongside generated Bazel BUILD files.
+const BAZEL_MACROS: &str = r#"# Generated by PyOxidizer. Do not edit.
+
+"""Macros for building binaries embedding Python."""
+
+def python_embedded_cc_binary(name, deps = [], data = [], **kwargs):
+    """Define a cc_binary linking the embedded Python.
+
+    The binary has access to the packed resources, standard library, and
+    extra files (such as tcl/tk support files) at run-time via runfiles.
+    """
+    native.cc_binary(
+        name = name,
+        deps = deps + [Label("//:python")],
+        data = data + [
+            Label("//:packed_resources"),
+            Label("//:stdlib"),
+            Label("//:extra_files"),
+        ],
+        **kwargs
+    )
+"#;
+
+/// Quote a string for use in a C string literal.
+fn c_string_literal(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Quote a string for use in a CMake quoted argument.
+///
+/// Variable references are preserved.
+fn cmake_quoted(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "/").replace('"', "\\\""))
+}
+
 /// Holds context necessary to embed Python in a binary.
 pub struct EmbeddedPythonContext<'a> {
     /// The configuration for the embedded interpreter.
@@ -425,6 +479,398 @@ impl<'a> EmbeddedPythonContext<'a> {
         Ok(())
     }
 
+    /// Write a C header defining macros describing the interpreter configuration.
+    ///
+    /// This allows C and C++ programs to configure the interpreter consistently
+    /// with the other generated artifacts.
+    pub fn write_interpreter_config_h(&self, dest_dir: impl AsRef<Path>) -> Result<()> {
+        let mut lines = vec![
+            "/* Generated by PyOxidizer. Do not edit. */".to_string(),
+            "".to_string(),
+            "#ifndef PYTHON_EMBEDDING_CONFIG_H".to_string(),
+            "#define PYTHON_EMBEDDING_CONFIG_H".to_string(),
+            "".to_string(),
+            format!(
+                "#define PYTHON_EMBEDDING_PYTHON_VERSION {}",
+                c_string_literal(&format!(
+                    "{}.{}",
+                    self.python_version.major, self.python_version.minor
+                ))
+            ),
+            format!(
+                "#define PYTHON_EMBEDDING_PROFILE_ISOLATED {}",
+                u8::from(self.config.config.profile == PythonInterpreterProfile::Isolated)
+            ),
+            format!(
+                "#define PYTHON_EMBEDDING_LINK_STATIC {}",
+                u8::from(matches!(
+                    self.link_settings,
+                    LibpythonLinkSettings::StaticData(_)
+                ))
+            ),
+            format!(
+                "#define PYTHON_EMBEDDING_OXIDIZED_IMPORTER {}",
+                u8::from(self.config.oxidized_importer)
+            ),
+            format!(
+                "#define PYTHON_EMBEDDING_FILESYSTEM_IMPORTER {}",
+                u8::from(self.config.filesystem_importer)
+            ),
+        ];
+
+        if let Some((_, path)) = self.pending_resources.first() {
+            lines.push(format!(
+                "#define PYTHON_EMBEDDING_PACKED_RESOURCES {}",
+                c_string_literal(&path.display().to_string())
+            ));
+        }
+
+        if let Some(paths) = &self.config.config.module_search_paths {
+            lines.push(format!(
+                "#define PYTHON_EMBEDDING_MODULE_SEARCH_PATHS_COUNT {}",
+                paths.len()
+            ));
+            lines.push(format!(
+                "#define PYTHON_EMBEDDING_MODULE_SEARCH_PATHS {{ {} }}",
+                paths
+                    .iter()
+                    .map(|p| c_string_literal(&p.display().to_string()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+
+        lines.push("".to_string());
+        lines.push("#endif".to_string());
+        lines.push("".to_string());
+
+        let path = dest_dir.as_ref().join(DEFAULT_PYTHON_CONFIG_H_FILENAME);
+        std::fs::write(&path, lines.join("\n"))
+            .with_context(|| format!("writing {}", path.display()))?;
+
+        Ok(())
+    }
+
+    /// Resolve arguments to pass to a C compiler driver to link libpython.
+    fn c_linker_args(&self, dest_dir: &Path) -> Result<Vec<String>> {
+        Ok(self
+            .link_settings
+            .linking_annotations(dest_dir, false, &self.target_triple)?
+            .iter()
+            .flat_map(|a| a.to_linker_args())
+            .collect::<Vec<_>>())
+    }
+
+    /// Write a pkg-config file for linking the embedded Python.
+    ///
+    /// `include_dir` is the directory containing Python's header files.
+    pub fn write_pkg_config(&self, dest_dir: &Path, include_dir: &Path) -> Result<()> {
+        let version = format!(
+            "{}.{}",
+            self.python_version.major, self.python_version.minor
+        );
+
+        let prefix = dest_dir.display().to_string();
+        let relative = |path: &Path| match path.strip_prefix(dest_dir) {
+            Ok(rest) => format!("${{prefix}}/{}", rest.display()),
+            Err(_) => path.display().to_string(),
+        };
+
+        let libs = self
+            .c_linker_args(dest_dir)?
+            .into_iter()
+            .map(|arg| arg.replace(&prefix, "${prefix}"))
+            .collect::<Vec<_>>();
+
+        let content = [
+            format!("prefix={}", prefix),
+            format!("includedir={}", relative(include_dir)),
+            "".to_string(),
+            format!("Name: {}", BUILD_SYSTEM_PACKAGE_NAME),
+            "Description: Python interpreter for embedding, generated by PyOxidizer".to_string(),
+            format!("Version: {}", version),
+            format!("Libs: {}", libs.join(" ")),
+            "Cflags: -I${includedir}".to_string(),
+            "".to_string(),
+        ]
+        .join("\n");
+
+        let path = dest_dir.join(format!("{}.pc", BUILD_SYSTEM_PACKAGE_NAME.to_lowercase()));
+        std::fs::write(&path, content).with_context(|| format!("writing {}", path.display()))?;
+
+        Ok(())
+    }
+
+    /// Write a CMake package config file for linking the embedded Python.
+    ///
+    /// The file defines an imported `PythonEmbedding::Python` target and
+    /// variables pointing to other artifacts. `include_dir` is the directory
+    /// containing Python's header files. `stdlib_dir` is the directory
+    /// containing the Python standard library, if any.
+    pub fn write_cmake_config(
+        &self,
+        dest_dir: &Path,
+        include_dir: &Path,
+        stdlib_dir: Option<&Path>,
+    ) -> Result<()> {
+        let cmake_dir = dest_dir.join("cmake");
+        std::fs::create_dir_all(&cmake_dir)
+            .with_context(|| format!("creating {}", cmake_dir.display()))?;
+
+        // Paths are relative to the package directory so the artifacts can be moved.
+        let prefix = dest_dir.display().to_string();
+        let relocate = |value: &str| value.replace(&prefix, "${_PYTHON_EMBEDDING_PREFIX}");
+        let path_value = |path: &Path| cmake_quoted(&relocate(&path.display().to_string()));
+
+        let mut link_libraries = vec![];
+        let mut link_directories = vec![];
+        let mut link_options = vec![];
+
+        for annotation in
+            self.link_settings
+                .linking_annotations(dest_dir, false, &self.target_triple)?
+        {
+            match annotation {
+                LinkingAnnotation::LinkFramework(framework) => {
+                    link_libraries.push(format!("-framework {}", framework));
+                }
+                LinkingAnnotation::LinkLibrary(lib) | LinkingAnnotation::LinkLibraryStatic(lib) => {
+                    link_libraries.push(lib);
+                }
+                LinkingAnnotation::Search(path) | LinkingAnnotation::SearchNative(path) => {
+                    link_directories.push(relocate(&path.display().to_string()));
+                }
+                LinkingAnnotation::Argument(arg) => {
+                    link_options.push(arg);
+                }
+            }
+        }
+
+        let list = |values: &[String]| cmake_quoted(&values.join(";"));
+
+        let mut lines = vec![
+            "# Generated by PyOxidizer. Do not edit.".to_string(),
+            "".to_string(),
+            "get_filename_component(_PYTHON_EMBEDDING_PREFIX \"${CMAKE_CURRENT_LIST_DIR}/..\" ABSOLUTE)".to_string(),
+            "".to_string(),
+            format!(
+                "set({}_VERSION {})",
+                BUILD_SYSTEM_PACKAGE_NAME,
+                cmake_quoted(&format!(
+                    "{}.{}",
+                    self.python_version.major, self.python_version.minor
+                ))
+            ),
+            format!(
+                "set({}_INCLUDE_DIR {})",
+                BUILD_SYSTEM_PACKAGE_NAME,
+                path_value(include_dir)
+            ),
+            format!(
+                "set({}_CONFIG_HEADER {})",
+                BUILD_SYSTEM_PACKAGE_NAME,
+                path_value(&dest_dir.join(DEFAULT_PYTHON_CONFIG_H_FILENAME))
+            ),
+        ];
+
+        if let Some((_, path)) = self.pending_resources.first() {
+            lines.push(format!(
+                "set({}_PACKED_RESOURCES {})",
+                BUILD_SYSTEM_PACKAGE_NAME,
+                path_value(&dest_dir.join(path))
+            ));
+        }
+
+        if let Some(stdlib_dir) = stdlib_dir {
+            lines.push(format!(
+                "set({}_STDLIB_DIR {})",
+                BUILD_SYSTEM_PACKAGE_NAME,
+                path_value(stdlib_dir)
+            ));
+        }
+
+        lines.extend([
+            "".to_string(),
+            format!("if(NOT TARGET {}::Python)", BUILD_SYSTEM_PACKAGE_NAME),
+            format!(
+                "  add_library({}::Python INTERFACE IMPORTED)",
+                BUILD_SYSTEM_PACKAGE_NAME
+            ),
+            format!(
+                "  set_target_properties({}::Python PROPERTIES",
+                BUILD_SYSTEM_PACKAGE_NAME
+            ),
+            format!(
+                "    INTERFACE_INCLUDE_DIRECTORIES {}",
+                list(&[
+                    relocate(&include_dir.display().to_string()),
+                    "${_PYTHON_EMBEDDING_PREFIX}".to_string()
+                ])
+            ),
+            format!("    INTERFACE_LINK_DIRECTORIES {}", list(&link_directories)),
+            format!("    INTERFACE_LINK_LIBRARIES {}", list(&link_libraries)),
+            format!("    INTERFACE_LINK_OPTIONS {}", list(&link_options)),
+            "  )".to_string(),
+            "endif()".to_string(),
+            "".to_string(),
+            "unset(_PYTHON_EMBEDDING_PREFIX)".to_string(),
+            "".to_string(),
+        ]);
+
+        let path = cmake_dir.join(format!("{}Config.cmake", BUILD_SYSTEM_PACKAGE_NAME));
+        std::fs::write(&path, lines.join("\n"))
+            .with_context(|| format!("writing {}", path.display()))?;
+
+        Ok(())
+    }
+
+    /// Write Bazel files exposing the artifacts in `dest_dir` as build targets.
+    ///
+    /// `dest_dir` becomes the root of a Bazel repository defining a `python`
+    /// `cc_library` and targets for other artifacts, plus a
+    /// `python_embedding.bzl` file with a macro for defining binaries
+    /// embedding Python. Only statically linked libpython is supported.
+    pub fn write_bazel_files(
+        &self,
+        dest_dir: &Path,
+        include_dir: &Path,
+        stdlib_dir: &Path,
+    ) -> Result<()> {
+        let library = match &self.link_settings {
+            LibpythonLinkSettings::StaticData(l) => l.library_path(dest_dir, &self.target_triple),
+            LibpythonLinkSettings::ExistingDynamic(_) => {
+                return Err(anyhow!(
+                    "Bazel files can only be generated for a statically linked libpython"
+                ));
+            }
+        };
+
+        let relative = |path: &Path| -> Result<String> {
+            Ok(path
+                .strip_prefix(dest_dir)
+                .with_context(|| format!("{} is not in {}", path.display(), dest_dir.display()))?
+                .display()
+                .to_string()
+                .replace('\\', "/"))
+        };
+
+        // Starlark string literals escape the same characters as C's.
+        let list = |values: &[String]| {
+            format!(
+                "[{}]",
+                values
+                    .iter()
+                    .map(|v| c_string_literal(v))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        };
+
+        // The library itself and the search path to it are expressed as
+        // sources of the cc_library.
+        let linkopts = self
+            .link_settings
+            .linking_annotations(dest_dir, false, &self.target_triple)?
+            .into_iter()
+            .filter(|annotation| match annotation {
+                LinkingAnnotation::LinkLibraryStatic(_) => false,
+                LinkingAnnotation::SearchNative(path) => path != dest_dir,
+                _ => true,
+            })
+            .flat_map(|annotation| annotation.to_linker_args())
+            .collect::<Vec<_>>();
+
+        let include_dir = relative(include_dir)?;
+
+        let mut lines = vec![
+            "# Generated by PyOxidizer. Do not edit.".to_string(),
+            "".to_string(),
+            "package(default_visibility = [\"//visibility:public\"])".to_string(),
+            "".to_string(),
+            "cc_library(".to_string(),
+            "    name = \"python\",".to_string(),
+            format!("    srcs = {},", list(&[relative(&library)?])),
+            format!(
+                "    hdrs = glob([{}]) + [{}],",
+                c_string_literal(&format!("{}/**/*.h", include_dir)),
+                c_string_literal(DEFAULT_PYTHON_CONFIG_H_FILENAME)
+            ),
+            format!("    includes = {},", list(&[include_dir, ".".to_string()])),
+            format!("    linkopts = {},", list(&linkopts)),
+            "    linkstatic = True,".to_string(),
+            ")".to_string(),
+        ];
+
+        let mut filegroup = |name: &str, srcs: String| {
+            lines.extend([
+                "".to_string(),
+                "filegroup(".to_string(),
+                format!("    name = {},", c_string_literal(name)),
+                format!("    srcs = {},", srcs),
+                ")".to_string(),
+            ]);
+        };
+
+        filegroup(
+            "packed_resources",
+            list(
+                &self
+                    .pending_resources
+                    .iter()
+                    .map(|(_, path)| path.display().to_string())
+                    .collect::<Vec<_>>(),
+            ),
+        );
+        filegroup(
+            "stdlib",
+            format!(
+                "glob([{}])",
+                c_string_literal(&format!("{}/**", relative(stdlib_dir)?))
+            ),
+        );
+        filegroup(
+            "extra_files",
+            list(
+                &self
+                    .extra_files
+                    .iter_entries()
+                    .map(|(path, _)| path.display().to_string().replace('\\', "/"))
+                    .collect::<Vec<_>>(),
+            ),
+        );
+        filegroup(
+            "default_python_config_rs",
+            list(&[DEFAULT_PYTHON_CONFIG_FILENAME.to_string()]),
+        );
+        filegroup(
+            "pyo3_config",
+            list(&[relative(&self.pyo3_config_path(dest_dir))?]),
+        );
+        lines.push("".to_string());
+
+        let path = dest_dir.join("BUILD.bazel");
+        std::fs::write(&path, lines.join("\n"))
+            .with_context(|| format!("writing {}", path.display()))?;
+
+        let path = dest_dir.join("python_embedding.bzl");
+        std::fs::write(&path, BAZEL_MACROS)
+            .with_context(|| format!("writing {}", path.display()))?;
+
+        for (filename, content) in [
+            (
+                "WORKSPACE.bazel",
+                "workspace(name = \"python_embedding\")\n",
+            ),
+            ("MODULE.bazel", "module(name = \"python_embedding\")\n"),
+        ] {
+            let path = dest_dir.join(filename);
+            std::fs::write(&path, content)
+                .with_context(|| format!("writing {}", path.display()))?;
+        }
+
+        Ok(())
+    }
+
     /// Write out files needed to build a binary against our configuration.
     pub fn write_files(&self, dest_dir: &Path) -> Result<()> {
         self.write_packed_resources(dest_dir)
@@ -476,6 +922,32 @@ impl<'a> EmbeddedPythonContext<'a> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_linker_args() {
+        assert_eq!(
+            LinkingAnnotation::LinkFramework("CoreFoundation".into()).to_linker_args(),
+            vec!["-framework", "CoreFoundation"]
+        );
+        assert_eq!(
+            LinkingAnnotation::LinkLibraryStatic("python3".into()).to_linker_args(),
+            vec!["-lpython3"]
+        );
+        assert_eq!(
+            LinkingAnnotation::SearchNative("/artifacts".into()).to_linker_args(),
+            vec!["-L/artifacts"]
+        );
+        assert_eq!(
+            LinkingAnnotation::Argument("-Wl,-export-dynamic".into()).to_linker_args(),
+            vec!["-Wl,-export-dynamic"]
+        );
+    }
+
+    #[test]
+    fn test_cmake_quoted() {
+        assert_eq!(cmake_quoted("${prefix}/lib"), "\"${prefix}/lib\"");
+        assert_eq!(cmake_quoted("C:\\Python\\include"), "\"C:/Python/include\"");
+    }
+
     #[test]
     fn test_dynamic_library_name() -> Result<()> {
         assert_eq!(
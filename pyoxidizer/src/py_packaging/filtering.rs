@@ -7,16 +7,22 @@ Utility code for filtering.
 */
 
 use {
+    super::dependency_graph::module_name_from_record_path,
     anyhow::{anyhow, Result},
     log::warn,
+    python_packaging::{package_metadata::normalize_distribution_name, resource::PythonResource},
     std::{
         collections::{BTreeMap, BTreeSet},
         fs::File,
-        io::{BufRead, BufReader},
+        io::{BufRead, BufReader, Write},
         path::Path,
     },
 };
 
+/// Environment variable defining the directory built executables write imported
+/// module names to when tracing imports.
+pub const TRACE_IMPORTS_ENV: &str = "PYOXIDIZER_TRACE_IMPORTS_DIR";
+
 pub fn read_resource_names_file(path: &Path) -> Result<BTreeSet<String>> {
     let fh = File::open(path)?;
 
@@ -65,6 +71,52 @@ pub fn resolve_resource_names_from_files(
     Ok(include_names)
 }
 
+/// Write a resource names file from the modules recorded by traced executables.
+///
+/// `modules_dir` is the directory executables wrote `modules-*` files to. Names
+/// already present in `dest` are retained so multiple traces can be combined.
+///
+/// Returns the number of names in the written file.
+pub fn write_resource_names_file_from_traces(modules_dir: &Path, dest: &Path) -> Result<usize> {
+    let mut names = if dest.exists() {
+        read_resource_names_file(dest)?
+    } else {
+        BTreeSet::new()
+    };
+
+    let mut traced = false;
+
+    for entry in std::fs::read_dir(modules_dir)? {
+        let path = entry?.path();
+
+        if path
+            .file_name()
+            .and_then(|x| x.to_str())
+            .map_or(false, |x| x.starts_with("modules-"))
+        {
+            names.extend(read_resource_names_file(&path)?);
+            traced = true;
+        }
+    }
+
+    if !traced {
+        return Err(anyhow!(
+            "no imported modules were recorded; does the target run a Python executable?"
+        ));
+    }
+
+    let mut fh = File::create(dest)?;
+    writeln!(
+        fh,
+        "# Resource names recorded by `pyoxidizer run --trace-imports`."
+    )?;
+    for name in &names {
+        writeln!(fh, "{}", name)?;
+    }
+
+    Ok(names.len())
+}
+
 pub fn filter_btreemap<V>(m: &mut BTreeMap<String, V>, f: &BTreeSet<String>) {
     let keys: Vec<String> = m.keys().cloned().collect();
 
@@ -75,3 +127,146 @@ pub fn filter_btreemap<V>(m: &mut BTreeMap<String, V>, f: &BTreeSet<String>) {
         }
     }
 }
+
+/// Resolve names of distributions providing any of the given packages.
+///
+/// Distribution metadata is keyed by distribution name, which often differs
+/// from the names of the packages a distribution installs. So membership is
+/// derived from the distribution's `RECORD` and `top_level.txt` files.
+pub fn distributions_providing_packages(
+    resources: &[PythonResource],
+    packages: &[String],
+) -> Result<BTreeSet<String>> {
+    let wanted = |name: &str| {
+        packages
+            .iter()
+            .any(|package| name == package || name.starts_with(&format!("{}.", package)))
+    };
+
+    let mut res = BTreeSet::new();
+
+    for resource in resources {
+        let resource = match resource {
+            PythonResource::PackageDistributionResource(resource) => resource,
+            _ => continue,
+        };
+
+        if res.contains(&resource.package) {
+            continue;
+        }
+
+        let provides = if packages.iter().any(|package| {
+            normalize_distribution_name(package) == normalize_distribution_name(&resource.package)
+        }) {
+            true
+        } else if resource.name == "RECORD" {
+            String::from_utf8_lossy(&resource.data.resolve_content()?)
+                .lines()
+                .filter_map(|line| module_name_from_record_path(line.split(',').next()?))
+                .any(|name| wanted(&name))
+        } else if resource.name == "top_level.txt" {
+            String::from_utf8_lossy(&resource.data.resolve_content()?)
+                .lines()
+                .map(|line| line.trim())
+                .any(wanted)
+        } else {
+            false
+        };
+
+        if provides {
+            res.insert(resource.package.clone());
+        }
+    }
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        python_packaging::resource::{
+            PythonPackageDistributionResource, PythonPackageDistributionResourceFlavor,
+        },
+        simple_file_manifest::FileData,
+    };
+
+    fn dist_resource(package: &str, name: &str, data: &str) -> PythonResource<'static> {
+        PythonPackageDistributionResource {
+            location: PythonPackageDistributionResourceFlavor::DistInfo,
+            package: package.to_string(),
+            version: "1.0".to_string(),
+            name: name.to_string(),
+            data: FileData::Memory(data.as_bytes().to_vec()),
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_distributions_providing_packages() -> Result<()> {
+        let resources = vec![
+            dist_resource("my-app", "METADATA", "Name: my-app\n"),
+            dist_resource("PyYAML", "METADATA", "Name: PyYAML\n"),
+            dist_resource(
+                "PyYAML",
+                "RECORD",
+                "yaml/__init__.py,,\n_yaml/__init__.py,,\nPyYAML-1.0.dist-info/RECORD,,\n",
+            ),
+            dist_resource("protobuf", "top_level.txt", "google\n"),
+            dist_resource("other", "METADATA", "Name: other\n"),
+        ];
+
+        assert_eq!(
+            distributions_providing_packages(&resources, &["my_app".to_string()])?,
+            BTreeSet::from(["my-app".to_string()])
+        );
+        assert_eq!(
+            distributions_providing_packages(&resources, &["yaml".to_string()])?,
+            BTreeSet::from(["PyYAML".to_string()])
+        );
+        assert_eq!(
+            distributions_providing_packages(&resources, &["google".to_string()])?,
+            BTreeSet::from(["protobuf".to_string()])
+        );
+        assert!(distributions_providing_packages(&resources, &["missing".to_string()])?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_resource_names_file_from_traces() -> Result<()> {
+        let td = tempfile::Builder::new()
+            .prefix("pyoxidizer-test")
+            .tempdir()?;
+        let modules_dir = td.path().join("modules");
+        let dest = td.path().join("filter.txt");
+
+        std::fs::create_dir(&modules_dir)?;
+        assert!(write_resource_names_file_from_traces(&modules_dir, &dest).is_err());
+
+        std::fs::write(modules_dir.join("modules-1"), "foo\nfoo.bar\n")?;
+        std::fs::write(modules_dir.join("modules-2"), "baz\nfoo\n")?;
+        std::fs::write(modules_dir.join("other"), "ignored\n")?;
+
+        assert_eq!(
+            write_resource_names_file_from_traces(&modules_dir, &dest)?,
+            3
+        );
+        assert_eq!(
+            read_resource_names_file(&dest)?,
+            BTreeSet::from(["baz".to_string(), "foo".to_string(), "foo.bar".to_string()])
+        );
+
+        // Existing names are retained.
+        std::fs::remove_file(modules_dir.join("modules-1"))?;
+        std::fs::remove_file(modules_dir.join("modules-2"))?;
+        std::fs::write(modules_dir.join("modules-3"), "other\n")?;
+
+        assert_eq!(
+            write_resource_names_file_from_traces(&modules_dir, &dest)?,
+            4
+        );
+
+        Ok(())
+    }
+}
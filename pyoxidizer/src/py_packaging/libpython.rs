@@ -8,7 +8,7 @@ Build a custom libraries containing Python.
 
 use {
     crate::{
-        environment::Environment,
+        environment::{is_msvc_cross_compile, Environment},
         py_packaging::{distribution::AppleSdkInfo, embedding::LinkingAnnotation},
     },
     anyhow::{anyhow, Context, Result},
@@ -178,6 +178,10 @@ pub struct LibpythonInfo {
 
 /// Create a static libpython from a Python distribution.
 ///
+/// If `deterministic` is set, the temporary build directory is scrubbed from
+/// compiled objects and archive members have stable names, so identical inputs
+/// produce an identical library.
+///
 /// Returns a struct describing the generated libpython.
 #[allow(clippy::too_many_arguments)]
 pub fn link_libpython(
@@ -187,6 +191,7 @@ pub fn link_libpython(
     target_triple: &str,
     opt_level: &str,
     apple_sdk_info: Option<&AppleSdkInfo>,
+    deterministic: bool,
 ) -> Result<LibpythonInfo> {
     let temp_dir = env.temporary_directory("pyoxidizer-libpython")?;
 
@@ -200,6 +205,17 @@ pub fn link_libpython(
 
     let windows = crate::environment::WINDOWS_TARGET_TRIPLES.contains(&target_triple);
 
+    // The cc crate can't find an MSVC toolchain when not running on Windows. So
+    // point it at LLVM's MSVC compatible tools when cross-compiling.
+    let msvc_cross_toolchain = if is_msvc_cross_compile(target_triple) {
+        Some(
+            env.resolve_msvc_cross_toolchain()
+                .context("resolving toolchain for cross-compiling to Windows")?,
+        )
+    } else {
+        None
+    };
+
     // We derive a custom Modules/config.c from the set of extension modules.
     // We need to do this because config.c defines the built-in extensions and
     // their initialization functions and the file generated by the source
@@ -263,6 +279,26 @@ pub fn link_libpython(
         build.flag(&format!("{}", sdk.path().display()));
     }
 
+    if let Some(toolchain) = &msvc_cross_toolchain {
+        build.compiler(&toolchain.clang_cl);
+
+        for flag in toolchain.c_flags(target_triple) {
+            build.flag(&flag);
+        }
+    }
+
+    if deterministic {
+        if windows {
+            // Omits timestamps from COFF headers. Supported by cl.exe and clang-cl.
+            build.flag("/Brepro");
+        } else {
+            build.flag(&format!(
+                "-ffile-prefix-map={}=.",
+                temp_dir.path().display()
+            ));
+        }
+    }
+
     build
         .out_dir(&config_c_dir)
         .host(host_triple)
@@ -277,6 +313,17 @@ pub fn link_libpython(
 
     let mut objects = BTreeSet::new();
 
+    // The name of the object file emitted by the cc crate is derived from the
+    // random temporary directory. Give it a stable name so the archive member
+    // name and its position in the archive are stable.
+    let config_object_path = if deterministic {
+        let path = libpython_dir.join("config.o");
+        fs::copy(&config_object_path, &path).context("copying config.c object file")?;
+        path
+    } else {
+        config_object_path
+    };
+
     // Link our custom config.c's object file.
     objects.insert(config_object_path);
 
@@ -340,6 +387,14 @@ pub fn link_libpython(
         // We handle this ourselves.
         build.cargo_metadata(false);
 
+        if let Some(toolchain) = &msvc_cross_toolchain {
+            build.archiver(&toolchain.llvm_lib);
+        }
+
+        if deterministic && windows {
+            build.ar_flag("/Brepro");
+        }
+
         for object in objects {
             build.object(object);
         }
@@ -10,12 +10,15 @@ This module tree holds functionality that is centered around Python.
 
 pub mod binary;
 pub mod config;
+pub mod dependency_graph;
 pub mod distribution;
 pub mod distutils;
 pub mod embedding;
 pub mod filtering;
 pub mod libpython;
+pub mod packed_resources_wheel;
 pub mod packaging_tool;
 pub mod resource;
+pub mod sbom;
 pub mod standalone_builder;
 pub mod standalone_distribution;
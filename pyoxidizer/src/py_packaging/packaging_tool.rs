@@ -11,7 +11,10 @@ use {
         binary::LibpythonLinkMode, distribution::PythonDistribution,
         distutils::read_built_extensions, standalone_distribution::resolve_python_paths,
     },
-    crate::environment::Environment,
+    crate::{
+        content_cache::{ContentCache, NAMESPACE_DOWNLOADS},
+        environment::Environment,
+    },
     anyhow::{anyhow, Context, Result},
     duct::{cmd, ReaderHandle},
     log::warn,
@@ -19,8 +22,9 @@ use {
         filesystem_scanning::find_python_resources, policy::PythonPackagingPolicy,
         resource::PythonResource, wheel::WheelArchive,
     },
+    sha2::{Digest, Sha256, Sha384, Sha512},
     std::{
-        collections::{hash_map::RandomState, HashMap},
+        collections::{hash_map::RandomState, BTreeSet, HashMap},
         hash::BuildHasher,
         io::{BufRead, BufReader},
         path::{Path, PathBuf},
@@ -41,6 +45,152 @@ fn log_command_output(handle: &ReaderHandle) {
     }
 }
 
+/// Read the `--hash` pins from a pip requirements file.
+///
+/// Returns `(algorithm, hex digest)` pairs for every hash in the file. Line
+/// continuations and comments are handled. Nested requirements files
+/// (`-r`) are not followed.
+pub fn read_requirements_hashes(path: &Path) -> Result<BTreeSet<(String, String)>> {
+    let data = std::fs::read_to_string(path)
+        .with_context(|| format!("reading requirements file {}", path.display()))?;
+
+    let mut hashes = BTreeSet::new();
+
+    for line in data.replace("\\\r\n", " ").replace("\\\n", " ").lines() {
+        let line = match line.find(" #") {
+            Some(pos) => &line[0..pos],
+            None => line,
+        };
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        let mut words = line.split_whitespace();
+        while let Some(word) = words.next() {
+            let value = if let Some(value) = word.strip_prefix("--hash=") {
+                value
+            } else if word == "--hash" {
+                words
+                    .next()
+                    .ok_or_else(|| anyhow!("--hash missing value in {}", path.display()))?
+            } else {
+                continue;
+            };
+
+            let (algorithm, digest) = value
+                .split_once(':')
+                .ok_or_else(|| anyhow!("malformed --hash value: {}", value))?;
+
+            if !matches!(algorithm, "sha256" | "sha384" | "sha512") {
+                return Err(anyhow!("unsupported hash algorithm: {}", algorithm));
+            }
+
+            hashes.insert((algorithm.to_string(), digest.to_lowercase()));
+        }
+    }
+
+    if hashes.is_empty() {
+        return Err(anyhow!(
+            "requirements file {} does not contain any --hash pins",
+            path.display()
+        ));
+    }
+
+    Ok(hashes)
+}
+
+/// Verify that every file in a directory matches a pinned hash.
+///
+/// Errors if any file's digest isn't in `hashes`.
+fn verify_artifact_hashes(dir: &Path, hashes: &BTreeSet<(String, String)>) -> Result<()> {
+    let mut files = std::fs::read_dir(dir)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>>>()?;
+    files.sort();
+
+    for path in files {
+        let data = std::fs::read(&path)?;
+
+        let matched = hashes.iter().any(|(algorithm, digest)| {
+            let actual = match algorithm.as_str() {
+                "sha256" => hex::encode(Sha256::digest(&data)),
+                "sha384" => hex::encode(Sha384::digest(&data)),
+                "sha512" => hex::encode(Sha512::digest(&data)),
+                _ => return false,
+            };
+
+            &actual == digest
+        });
+
+        if !matched {
+            return Err(anyhow!(
+                "hash of {} does not match any hash in requirements file",
+                path.display()
+            ));
+        }
+
+        warn!("verified hash of {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Copy previously downloaded artifacts matching pinned SHA-256 hashes into a directory.
+///
+/// The directory can be given to pip via `--find-links` so matching artifacts
+/// don't need to be downloaded again.
+fn seed_cached_downloads(
+    cache: &ContentCache,
+    dir: &Path,
+    hashes: &BTreeSet<(String, String)>,
+) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+
+    for (algorithm, digest) in hashes {
+        if algorithm != "sha256" {
+            continue;
+        }
+
+        if let Some(path) = cache.get_path(NAMESPACE_DOWNLOADS, digest)? {
+            let filename = path
+                .file_name()
+                .ok_or_else(|| anyhow!("unable to resolve filename of cached download"))?;
+
+            warn!("using cached download {}", filename.to_string_lossy());
+            std::fs::copy(&path, dir.join(filename))
+                .with_context(|| format!("copying {}", path.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Store downloaded artifacts in the content cache, keyed by their SHA-256.
+fn store_downloads(cache: &ContentCache, dir: &Path) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if !path.is_file() {
+            continue;
+        }
+
+        let data = std::fs::read(&path)?;
+        let filename = path
+            .file_name()
+            .ok_or_else(|| anyhow!("unable to resolve filename of download"))?
+            .to_string_lossy();
+
+        cache.put_named(
+            NAMESPACE_DOWNLOADS,
+            &hex::encode(Sha256::digest(&data)),
+            &filename,
+            &data,
+        )?;
+    }
+
+    Ok(())
+}
+
 /// Find resources installed as part of a packaging operation.
 pub fn find_resources<'a>(
     dist: &dyn PythonDistribution,
@@ -97,19 +247,66 @@ pub fn find_resources<'a>(
 /// distribution targeting a different platform allows this command to
 /// resolve resources for a non-native platform, which enables it to be used
 /// when cross-compiling.
+///
+/// If `requirements_path` is defined, requirements are read from that file
+/// and every downloaded wheel must match one of its `--hash` pins.
+///
+/// If `build_from_source` is true, packages without a compatible wheel are
+/// downloaded as source distributions and built against `taget_dist` using
+/// the same mechanism as [pip_install()]. Extension modules are compiled
+/// according to `libpython_link_mode`. This requires that `taget_dist` be
+/// runnable on the current machine, so it isn't supported when
+/// cross-compiling.
+#[allow(clippy::too_many_arguments)]
 pub fn pip_download<'a>(
     env: &Environment,
     host_dist: &dyn PythonDistribution,
     taget_dist: &dyn PythonDistribution,
     policy: &PythonPackagingPolicy,
+    libpython_link_mode: LibpythonLinkMode,
     verbose: bool,
     args: &[String],
+    requirements_path: Option<&Path>,
+    build_from_source: bool,
 ) -> Result<Vec<PythonResource<'a>>> {
+    if build_from_source
+        && !taget_dist
+            .compatible_host_triples()
+            .iter()
+            .any(|triple| triple == host_dist.target_triple())
+    {
+        return Err(anyhow!(
+            "building packages from source is not supported when cross-compiling to {}",
+            taget_dist.target_triple()
+        ));
+    }
+
+    let hashes = requirements_path
+        .map(read_requirements_hashes)
+        .transpose()?;
+
     let temp_dir = env.temporary_directory("pyoxidizer-pip-download")?;
 
-    host_dist.ensure_pip()?;
+    // When building from source, pip needs to evaluate the target environment
+    // itself. So we run the target distribution's pip, which will select
+    // wheels compatible with it.
+    let pip_dist = if build_from_source {
+        taget_dist
+    } else {
+        host_dist
+    };
 
-    let target_dir = temp_dir.path();
+    pip_dist.ensure_pip()?;
+
+    let download_dir = temp_dir.path().join("downloads");
+    let target_dir = download_dir.as_path();
+    std::fs::create_dir(target_dir)?;
+
+    let cache = env.content_cache();
+    let cached_dir = temp_dir.path().join("cached");
+    if let Some(hashes) = &hashes {
+        seed_cached_downloads(&cache, &cached_dir, hashes)?;
+    }
 
     warn!("pip downloading to {}", target_dir.display());
 
@@ -128,29 +325,47 @@ pub fn pip_download<'a>(
         // Download packages to our temporary directory.
         "--dest".to_string(),
         format!("{}", target_dir.display()),
-        // Only download wheels.
-        "--only-binary=:all:".to_string(),
-        // We download files compatible with the distribution we're targeting.
-        format!(
-            "--platform={}",
-            taget_dist.python_platform_compatibility_tag()
-        ),
-        format!("--python-version={}", taget_dist.python_version()),
-        format!(
-            "--implementation={}",
-            taget_dist.python_implementation_short()
-        ),
     ]);
 
-    if let Some(abi) = taget_dist.python_abi_tag() {
-        pip_args.push(format!("--abi={}", abi));
+    if build_from_source {
+        // Use wheels when available and fall back to source distributions.
+        pip_args.push("--prefer-binary".to_string());
+    } else {
+        pip_args.extend(vec![
+            // Only download wheels.
+            "--only-binary=:all:".to_string(),
+            // We download files compatible with the distribution we're targeting.
+            format!(
+                "--platform={}",
+                taget_dist.python_platform_compatibility_tag()
+            ),
+            format!("--python-version={}", taget_dist.python_version()),
+            format!(
+                "--implementation={}",
+                taget_dist.python_implementation_short()
+            ),
+        ]);
+
+        if let Some(abi) = taget_dist.python_abi_tag() {
+            pip_args.push(format!("--abi={}", abi));
+        }
+    }
+
+    if let Some(path) = requirements_path {
+        pip_args.extend(vec![
+            "--require-hashes".to_string(),
+            "--find-links".to_string(),
+            format!("{}", cached_dir.display()),
+            "-r".to_string(),
+            format!("{}", path.display()),
+        ]);
     }
 
     pip_args.extend(args.iter().cloned());
 
     warn!("running python {:?}", pip_args);
 
-    let command = cmd(host_dist.python_exe_path(), &pip_args)
+    let command = cmd(pip_dist.python_exe_path(), &pip_args)
         .stderr_to_stdout()
         .unchecked()
         .reader()?;
@@ -164,9 +379,16 @@ pub fn pip_download<'a>(
         return Err(anyhow!("error running pip"));
     }
 
-    // Since we used --only-binary=:all: above, we should only have .whl files
-    // in the destination directory. Iterate over them and collect resources
-    // from each.
+    if let Some(hashes) = &hashes {
+        verify_artifact_hashes(target_dir, hashes)?;
+    }
+
+    store_downloads(&cache, target_dir).context("storing downloads in cache")?;
+
+    // Unless building from source, we used --only-binary=:all: above, so we
+    // should only have .whl files in the destination directory. Iterate over
+    // them and collect resources from each. Source distributions are built
+    // and installed with pip, which compiles their extension modules.
 
     let mut files = std::fs::read_dir(target_dir)?
         .map(|entry| Ok(entry?.path()))
@@ -177,6 +399,26 @@ pub fn pip_download<'a>(
     let mut res = Vec::new();
 
     for path in &files {
+        if path.extension().and_then(|ext| ext.to_str()) != Some("whl") {
+            warn!("building {} from source", path.display());
+
+            res.extend(
+                pip_install(
+                    env,
+                    taget_dist,
+                    policy,
+                    libpython_link_mode,
+                    verbose,
+                    &["--no-deps".to_string(), format!("{}", path.display())],
+                    &HashMap::<String, String, RandomState>::new(),
+                    None,
+                )
+                .with_context(|| format!("building {}", path.display()))?,
+            );
+
+            continue;
+        }
+
         let wheel = WheelArchive::from_path(path)?;
 
         res.extend(wheel.python_resources(
@@ -193,6 +435,12 @@ pub fn pip_download<'a>(
 }
 
 /// Run `pip install` and return found resources.
+///
+/// If `requirements_path` is defined, requirements are read from that file.
+/// They are first downloaded with `pip download` and every downloaded
+/// artifact must match one of the file's `--hash` pins. Only the verified
+/// artifacts are then installed.
+#[allow(clippy::too_many_arguments)]
 pub fn pip_install<'a, S: BuildHasher>(
     env: &Environment,
     dist: &dyn PythonDistribution,
@@ -201,8 +449,14 @@ pub fn pip_install<'a, S: BuildHasher>(
     verbose: bool,
     install_args: &[String],
     extra_envs: &HashMap<String, String, S>,
+    requirements_path: Option<&Path>,
 ) -> Result<Vec<PythonResource<'a>>> {
+    let hashes = requirements_path
+        .map(read_requirements_hashes)
+        .transpose()?;
+
     let temp_dir = env.temporary_directory("pyoxidizer-pip-install")?;
+    let cache = env.content_cache();
 
     dist.ensure_pip()?;
 
@@ -229,11 +483,62 @@ pub fn pip_install<'a, S: BuildHasher>(
         pip_args.push("--verbose".to_string());
     }
 
-    pip_args.extend(vec![
-        "install".to_string(),
-        "--target".to_string(),
-        format!("{}", target_dir.display()),
-    ]);
+    if let (Some(path), Some(hashes)) = (requirements_path, &hashes) {
+        let download_dir = temp_dir.path().join("downloads");
+        let cached_dir = temp_dir.path().join("cached");
+
+        seed_cached_downloads(&cache, &cached_dir, hashes)?;
+
+        warn!("pip downloading to {}", download_dir.display());
+
+        let mut download_args = pip_args.clone();
+        download_args.extend(vec![
+            "download".to_string(),
+            "--dest".to_string(),
+            format!("{}", download_dir.display()),
+            "--require-hashes".to_string(),
+            "--find-links".to_string(),
+            format!("{}", cached_dir.display()),
+            "-r".to_string(),
+            format!("{}", path.display()),
+        ]);
+
+        let command = cmd(dist.python_exe_path(), &download_args)
+            .full_env(&env)
+            .stderr_to_stdout()
+            .unchecked()
+            .reader()?;
+
+        log_command_output(&command);
+
+        let output = command
+            .try_wait()?
+            .ok_or_else(|| anyhow!("unable to wait on command"))?;
+        if !output.status.success() {
+            return Err(anyhow!("error running pip download"));
+        }
+
+        verify_artifact_hashes(&download_dir, hashes)?;
+        store_downloads(&cache, &download_dir).context("storing downloads in cache")?;
+
+        pip_args.extend(vec![
+            "install".to_string(),
+            "--target".to_string(),
+            format!("{}", target_dir.display()),
+            "--no-index".to_string(),
+            "--find-links".to_string(),
+            format!("{}", download_dir.display()),
+            "--require-hashes".to_string(),
+            "-r".to_string(),
+            format!("{}", path.display()),
+        ]);
+    } else {
+        pip_args.extend(vec![
+            "install".to_string(),
+            "--target".to_string(),
+            format!("{}", target_dir.display()),
+        ]);
+    }
 
     pip_args.extend(install_args.iter().cloned());
 
@@ -262,6 +567,115 @@ pub fn pip_install<'a, S: BuildHasher>(
     Ok(resources)
 }
 
+/// Build a wheel from a local source tree using its PEP 517 build backend.
+///
+/// `pip wheel` is used to drive the build. It creates an isolated build
+/// environment containing the packages declared in the project's
+/// `[build-system] requires`, invokes the backend's `build_wheel` hook, and
+/// writes the resulting wheel. Resources are then collected from that wheel.
+///
+/// Only the project itself is built: dependencies are not followed.
+#[allow(clippy::too_many_arguments)]
+pub fn pep517_build_wheel<'a, S: BuildHasher>(
+    env: &Environment,
+    dist: &dyn PythonDistribution,
+    policy: &PythonPackagingPolicy,
+    libpython_link_mode: LibpythonLinkMode,
+    package_path: &Path,
+    verbose: bool,
+    extra_envs: &HashMap<String, String, S>,
+) -> Result<Vec<PythonResource<'a>>> {
+    if !package_path.is_absolute() {
+        return Err(anyhow!(
+            "package_path must be absolute: got {:?}",
+            package_path.display()
+        ));
+    }
+
+    let temp_dir = env.temporary_directory("pyoxidizer-pep517-build")?;
+
+    dist.ensure_pip()?;
+
+    let mut env: HashMap<String, String, RandomState> = std::env::vars().collect();
+    for (k, v) in dist.resolve_distutils(libpython_link_mode, temp_dir.path(), &[])? {
+        env.insert(k, v);
+    }
+
+    for (key, value) in extra_envs.iter() {
+        env.insert(key.clone(), value.clone());
+    }
+
+    let wheel_dir = temp_dir.path().join("wheels");
+
+    warn!(
+        "building wheel for {} to {}",
+        package_path.display(),
+        wheel_dir.display()
+    );
+
+    let mut pip_args: Vec<String> = vec![
+        "-m".to_string(),
+        "pip".to_string(),
+        "--disable-pip-version-check".to_string(),
+    ];
+
+    if verbose {
+        pip_args.push("--verbose".to_string());
+    }
+
+    pip_args.extend(vec![
+        "wheel".to_string(),
+        "--use-pep517".to_string(),
+        "--no-deps".to_string(),
+        "--wheel-dir".to_string(),
+        format!("{}", wheel_dir.display()),
+        format!("{}", package_path.display()),
+    ]);
+
+    let command = cmd(dist.python_exe_path(), &pip_args)
+        .full_env(&env)
+        .stderr_to_stdout()
+        .unchecked()
+        .reader()?;
+
+    log_command_output(&command);
+
+    let output = command
+        .try_wait()?
+        .ok_or_else(|| anyhow!("unable to wait on command"))?;
+    if !output.status.success() {
+        return Err(anyhow!("error running pip wheel"));
+    }
+
+    let wheels = std::fs::read_dir(&wheel_dir)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .filter(|path| path.extension().map(|x| x == "whl").unwrap_or(false))
+        .collect::<Vec<_>>();
+
+    let wheel_path = match wheels.as_slice() {
+        [path] => path,
+        [] => return Err(anyhow!("build backend did not produce a wheel")),
+        _ => return Err(anyhow!("build backend produced multiple wheels")),
+    };
+
+    warn!("collecting resources from {}", wheel_path.display());
+
+    let wheel = WheelArchive::from_path(wheel_path)?;
+
+    let resources = wheel.python_resources(
+        dist.cache_tag(),
+        &dist.python_module_suffixes()?,
+        policy.file_scanner_emit_files(),
+        policy.file_scanner_classify_files(),
+    )?;
+
+    temp_dir.close().context("closing temporary directory")?;
+
+    Ok(resources)
+}
+
 /// Discover Python resources from a populated virtualenv directory.
 pub fn read_virtualenv<'a>(
     dist: &dyn PythonDistribution,
@@ -371,6 +785,62 @@ mod tests {
         std::{collections::BTreeSet, ops::Deref},
     };
 
+    #[test]
+    fn test_read_requirements_hashes() -> Result<()> {
+        let env = get_env()?;
+        let temp_dir = env.temporary_directory("pyoxidizer-test")?;
+
+        let path = temp_dir.path().join("requirements.txt");
+        std::fs::write(
+            &path,
+            "# comment --hash=sha256:ignored\n\
+            foo==1.0 \\\n    --hash=sha256:ABCD \\\n    --hash sha512:ef01\n\
+            bar==2.0 --hash=sha384:2345  # trailing comment\n",
+        )?;
+
+        assert_eq!(
+            read_requirements_hashes(&path)?,
+            [
+                ("sha256".to_string(), "abcd".to_string()),
+                ("sha384".to_string(), "2345".to_string()),
+                ("sha512".to_string(), "ef01".to_string()),
+            ]
+            .into_iter()
+            .collect::<BTreeSet<_>>()
+        );
+
+        std::fs::write(&path, "foo==1.0\n")?;
+        assert!(read_requirements_hashes(&path).is_err());
+
+        std::fs::write(&path, "foo==1.0 --hash=md5:abcd\n")?;
+        assert!(read_requirements_hashes(&path).is_err());
+
+        temp_dir.close()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_artifact_hashes() -> Result<()> {
+        let env = get_env()?;
+        let temp_dir = env.temporary_directory("pyoxidizer-test")?;
+
+        let artifacts = temp_dir.path().join("artifacts");
+        std::fs::create_dir(&artifacts)?;
+        std::fs::write(artifacts.join("foo-1.0-py3-none-any.whl"), b"foo")?;
+
+        let mut hashes = BTreeSet::new();
+        hashes.insert(("sha256".to_string(), "0".repeat(64)));
+        assert!(verify_artifact_hashes(&artifacts, &hashes).is_err());
+
+        hashes.insert(("sha256".to_string(), hex::encode(Sha256::digest(b"foo"))));
+        verify_artifact_hashes(&artifacts, &hashes)?;
+
+        temp_dir.close()?;
+
+        Ok(())
+    }
+
     #[test]
     fn test_install_black() -> Result<()> {
         let env = get_env()?;
@@ -384,6 +854,7 @@ mod tests {
             false,
             &["black==19.10b0".to_string()],
             &HashMap::new(),
+            None,
         )?;
 
         assert!(resources.iter().any(|r| r.full_name() == "appdirs"));
@@ -407,6 +878,7 @@ mod tests {
             false,
             &["cffi==1.15.0".to_string()],
             &HashMap::new(),
+            None,
         )?;
 
         let ems = resources
@@ -445,8 +917,11 @@ mod tests {
                 &*host_dist,
                 &*target_dist,
                 &policy,
+                LibpythonLinkMode::Dynamic,
                 false,
                 &["zstandard==0.19.0".to_string()],
+                None,
+                false,
             )?;
 
             assert!(!resources.is_empty());
@@ -517,6 +992,78 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_pip_download_build_from_source() -> Result<()> {
+        let env = get_env()?;
+        let distribution = get_default_distribution(None)?;
+        let policy = distribution.create_packaging_policy()?;
+
+        // Forbid wheels so the package is built from its source distribution.
+        let resources = pip_download(
+            &env,
+            distribution.deref(),
+            distribution.deref(),
+            &policy,
+            LibpythonLinkMode::Dynamic,
+            false,
+            &[
+                "--no-binary=pyflakes".to_string(),
+                "pyflakes==2.2.0".to_string(),
+            ],
+            None,
+            true,
+        )?;
+
+        assert!(resources.iter().any(|r| r.full_name() == "pyflakes"));
+        assert!(resources
+            .iter()
+            .any(|r| r.full_name() == "pyflakes.checker"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pip_download_build_from_source_cross_compile() -> Result<()> {
+        let env = get_env()?;
+
+        for target_dist in get_all_standalone_distributions()? {
+            let host_dist = get_host_distribution_from_target(&target_dist)?;
+
+            if target_dist
+                .compatible_host_triples()
+                .iter()
+                .any(|triple| triple == host_dist.target_triple())
+            {
+                continue;
+            }
+
+            let policy = target_dist.create_packaging_policy()?;
+
+            let res = pip_download(
+                &env,
+                &*host_dist,
+                &*target_dist,
+                &policy,
+                LibpythonLinkMode::Dynamic,
+                false,
+                &["zstandard==0.19.0".to_string()],
+                None,
+                true,
+            );
+
+            let err = res
+                .err()
+                .expect("building from source when cross-compiling should fail");
+            assert!(
+                format!("{}", err).contains("cross-compiling"),
+                "target triple: {}",
+                target_dist.target_triple
+            );
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_pip_download_numpy() -> Result<()> {
         let env = get_env()?;
@@ -544,8 +1091,11 @@ mod tests {
                 &*host_dist,
                 &*target_dist,
                 &policy,
+                LibpythonLinkMode::Dynamic,
                 false,
                 &["numpy==1.24.1".to_string()],
+                None,
+                false,
             );
 
             let resources = res?;
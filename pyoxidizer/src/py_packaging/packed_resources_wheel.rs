@@ -0,0 +1,206 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*!
+Build wheels distributing packed resources for `oxidized_importer`.
+
+These wheels can be installed into a regular CPython installation. They
+contain a packed resources file and a small Python package registering an
+`oxidized_importer.OxidizedFinder` servicing it, giving applications fast
+imports without embedding Python in a binary.
+*/
+
+use {
+    crate::{
+        environment::{Environment, PYOXIDIZER_VERSION},
+        py_packaging::binary::PythonBinaryBuilder,
+    },
+    anyhow::{anyhow, Context, Result},
+    python_packaging::wheel_builder::WheelBuilder,
+    std::path::Path,
+};
+
+/// Requirement on the `oxidized_importer` distribution loading the packed resources.
+///
+/// Must be able to read the packed resources format we write.
+pub const OXIDIZED_IMPORTER_REQUIREMENT: &str = "oxidized_importer>=0.9";
+
+/// Filename of the packed resources data within the loader package.
+const PACKED_RESOURCES_FILENAME: &str = "packed-resources";
+
+/// Derive the name of the Python package loading resources for a distribution.
+pub fn loader_package_name(distribution: &str) -> String {
+    format!(
+        "{}_packed_resources",
+        distribution.to_lowercase().replace(['-', '.'], "_")
+    )
+}
+
+/// Resolve the wheel platform tag for a Rust target triple.
+fn wheel_platform_tag(target_triple: &str) -> Result<&'static str> {
+    Ok(match target_triple {
+        "aarch64-unknown-linux-gnu" => "manylinux2014_aarch64",
+        "i686-unknown-linux-gnu" => "manylinux2014_i686",
+        "x86_64-unknown-linux-gnu" => "manylinux2014_x86_64",
+        "x86_64-unknown-linux-musl" => "musllinux_1_1_x86_64",
+        "aarch64-apple-darwin" => "macosx_11_0_arm64",
+        "x86_64-apple-darwin" => "macosx_10_9_x86_64",
+        "i686-pc-windows-msvc" => "win32",
+        "x86_64-pc-windows-msvc" => "win_amd64",
+        _ => {
+            return Err(anyhow!(
+                "unable to resolve wheel platform tag for {}",
+                target_triple
+            ))
+        }
+    })
+}
+
+fn loader_module_source(distribution: &str) -> String {
+    format!(
+        r#"# Generated by PyOxidizer. Do not edit.
+"""Load packed resources for {distribution} via oxidized_importer."""
+
+import os
+import sys
+
+_FINDER = None
+
+
+def install():
+    """Register an OxidizedFinder servicing the packed resources.
+
+    The finder is inserted at the front of sys.meta_path, so it takes
+    precedence over other importers. Calling this again returns the
+    already registered finder.
+    """
+    global _FINDER
+
+    if _FINDER is None:
+        from oxidized_importer import OxidizedFinder
+
+        here = os.path.dirname(os.path.abspath(__file__))
+        finder = OxidizedFinder(relative_path_origin=here)
+        finder.index_file_memory_mapped(os.path.join(here, "{filename}"))
+        sys.meta_path.insert(0, finder)
+
+        _FINDER = finder
+
+    return _FINDER
+"#,
+        distribution = distribution,
+        filename = PACKED_RESOURCES_FILENAME,
+    )
+}
+
+/// Construct a [WheelBuilder] distributing the resources of a binary.
+///
+/// The wheel contains a package named by [loader_package_name()] holding the
+/// packed resources, files for resources loaded from relative paths, and an
+/// `install()` function registering an `OxidizedFinder` for them. If
+/// `install_on_startup` is set, a `.pth` file calls `install()` when the
+/// interpreter starts.
+///
+/// Bytecode is specific to a Python version, so the wheel is tagged for the
+/// binary's Python version. If extension modules or shared libraries are
+/// present, the wheel is also tagged for the binary's target platform.
+pub fn packed_resources_wheel_builder(
+    exe: &dyn PythonBinaryBuilder,
+    env: &Environment,
+    distribution: &str,
+    version: &str,
+    install_on_startup: bool,
+) -> Result<WheelBuilder> {
+    let resources = exe.compile_resources(env).context("compiling resources")?;
+
+    let mut packed_resources = vec![];
+    resources
+        .write_packed_resources(&mut packed_resources)
+        .context("serializing packed resources")?;
+
+    let python_tag = exe
+        .cache_tag()
+        .strip_prefix("cpython-")
+        .map(|version| format!("cp{}", version))
+        .ok_or_else(|| anyhow!("unsupported Python cache tag: {}", exe.cache_tag()))?;
+
+    let native_code = resources.resources.values().any(|resource| {
+        resource.in_memory_extension_module_shared_library.is_some()
+            || resource.in_memory_shared_library.is_some()
+            || resource
+                .relative_path_extension_module_shared_library
+                .is_some()
+    });
+
+    let mut builder = WheelBuilder::new(distribution, version);
+    builder.set_generator(format!("pyoxidizer {}", PYOXIDIZER_VERSION));
+    builder.set_python_tag(&python_tag);
+
+    if native_code {
+        builder.set_abi_tag(&python_tag);
+        builder.set_platform_tag(wheel_platform_tag(exe.target_triple())?);
+    } else {
+        builder.set_root_is_purelib(true);
+    }
+
+    builder.add_file_dist_info(
+        "METADATA",
+        format!(
+            "Metadata-Version: 2.1\nName: {}\nVersion: {}\nRequires-Dist: {}\n",
+            distribution, version, OXIDIZED_IMPORTER_REQUIREMENT
+        )
+        .as_bytes(),
+    )?;
+
+    let package = loader_package_name(distribution);
+    let package_path = Path::new(&package);
+
+    builder.add_file(
+        package_path.join("__init__.py"),
+        loader_module_source(distribution).as_bytes(),
+    )?;
+    builder.add_file(
+        package_path.join(PACKED_RESOURCES_FILENAME),
+        packed_resources,
+    )?;
+
+    for (path, entry) in resources.extra_files_manifest()?.iter_entries() {
+        builder.add_file(package_path.join(path), entry.clone())?;
+    }
+
+    if install_on_startup {
+        builder.add_file(
+            format!("{}.pth", package),
+            format!("import {}; {}.install()\n", package, package).as_bytes(),
+        )?;
+    }
+
+    Ok(builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loader_package_name() {
+        assert_eq!(loader_package_name("myapp"), "myapp_packed_resources");
+        assert_eq!(
+            loader_package_name("My-App.Core"),
+            "my_app_core_packed_resources"
+        );
+    }
+
+    #[test]
+    fn test_wheel_platform_tag() -> Result<()> {
+        assert_eq!(
+            wheel_platform_tag("x86_64-unknown-linux-gnu")?,
+            "manylinux2014_x86_64"
+        );
+        assert_eq!(wheel_platform_tag("x86_64-pc-windows-msvc")?, "win_amd64");
+        assert!(wheel_platform_tag("riscv64gc-unknown-linux-gnu").is_err());
+
+        Ok(())
+    }
+}
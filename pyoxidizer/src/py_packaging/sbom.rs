@@ -0,0 +1,437 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! Software bill of materials (SBOM) generation.
+
+This module produces [CycloneDX](https://cyclonedx.org/) documents describing
+the components packaged in a built binary.
+*/
+
+use {
+    anyhow::{Context, Result},
+    python_packaging::{
+        licensing::{ComponentFlavor, LicenseFlavor, LicensedComponent, LicensedComponents},
+        resource_collection::PrePackagedResource,
+    },
+    serde::Serialize,
+    std::collections::BTreeMap,
+};
+
+/// The CycloneDX specification version of documents we emit.
+const CYCLONEDX_SPEC_VERSION: &str = "1.4";
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Bom {
+    bom_format: &'static str,
+    spec_version: &'static str,
+    serial_number: String,
+    version: u32,
+    metadata: Metadata,
+    components: Vec<Component>,
+}
+
+#[derive(Serialize)]
+struct Metadata {
+    tools: Vec<Tool>,
+    component: Component,
+}
+
+#[derive(Serialize)]
+struct Tool {
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Component {
+    #[serde(rename = "type")]
+    component_type: &'static str,
+    #[serde(rename = "bom-ref", skip_serializing_if = "Option::is_none")]
+    bom_ref: Option<String>,
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    purl: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    licenses: Vec<LicenseChoice>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    external_references: Vec<ExternalReference>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    components: Vec<Component>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum LicenseChoice {
+    Expression { expression: String },
+    License { license: License },
+}
+
+#[derive(Serialize)]
+struct License {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<LicenseText>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LicenseText {
+    content_type: &'static str,
+    content: String,
+}
+
+#[derive(Serialize)]
+struct ExternalReference {
+    #[serde(rename = "type")]
+    reference_type: &'static str,
+    url: String,
+}
+
+/// Resolve paths of files a Python distribution installed from its `RECORD` file.
+///
+/// Files installed outside the package root, bytecode caches, and the
+/// distribution's own metadata are ignored.
+fn distribution_record_paths(resource: &PrePackagedResource) -> Result<Vec<String>> {
+    let record = if let Some(data) = resource
+        .in_memory_distribution_resources
+        .as_ref()
+        .and_then(|resources| resources.get("RECORD"))
+    {
+        data
+    } else if let Some((_, data)) = resource
+        .relative_path_distribution_resources
+        .as_ref()
+        .and_then(|resources| resources.get("RECORD"))
+    {
+        data
+    } else {
+        return Ok(vec![]);
+    };
+
+    let data = record
+        .resolve_content()
+        .with_context(|| format!("reading RECORD of {}", resource.name))?;
+
+    Ok(String::from_utf8_lossy(&data)
+        .lines()
+        .filter_map(|line| {
+            // Lines are CSV. Paths containing commas are quoted.
+            let path = if let Some(rest) = line.strip_prefix('"') {
+                rest.split_once("\",")
+                    .map(|(path, _)| path.replace("\"\"", "\""))?
+            } else {
+                line.split(',').next()?.to_string()
+            };
+
+            if path.is_empty()
+                || path.starts_with("../")
+                || path.contains("__pycache__/")
+                || path
+                    .split('/')
+                    .next()
+                    .map(|first| first.ends_with(".dist-info") || first.ends_with(".egg-info"))
+                    .unwrap_or(false)
+            {
+                None
+            } else {
+                Some(path)
+            }
+        })
+        .collect())
+}
+
+/// Whether a file installed by a distribution is present in collected resources.
+fn is_packaged_file(resources: &BTreeMap<&String, &PrePackagedResource>, path: &str) -> bool {
+    let parts = path.split('/').collect::<Vec<_>>();
+    let (file_name, dirs) = parts.split_last().expect("split always yields an element");
+    let package = dirs.join(".");
+
+    let module_name = |stem: &str| {
+        if package.is_empty() {
+            stem.to_string()
+        } else {
+            format!("{}.{}", package, stem)
+        }
+    };
+
+    if let Some(stem) = file_name.strip_suffix(".py") {
+        let name = if stem == "__init__" {
+            package.clone()
+        } else {
+            module_name(stem)
+        };
+
+        return resources
+            .get(&name)
+            .map(|r| r.has_module_code())
+            .unwrap_or(false);
+    }
+
+    if file_name.ends_with(".so") || file_name.ends_with(".pyd") {
+        let stem = file_name.split('.').next().unwrap_or_default();
+
+        if let Some(r) = resources.get(&module_name(stem)) {
+            if r.is_extension_module || r.is_builtin_extension_module {
+                return true;
+            }
+        }
+    }
+
+    // Otherwise look for a resource file in the nearest enclosing package.
+    (1..parts.len()).rev().any(|i| {
+        let package = parts[..i].join(".");
+        let relative_name = parts[i..].join("/");
+
+        resources.get(&package).map_or(false, |r| {
+            r.in_memory_resources
+                .as_ref()
+                .map_or(false, |x| x.contains_key(&relative_name))
+                || r.relative_path_package_resources
+                    .as_ref()
+                    .map_or(false, |x| x.contains_key(&relative_name))
+        })
+    })
+}
+
+/// Convert the licensing of a component to CycloneDX license entries.
+fn component_licenses(component: &LicensedComponent) -> Vec<LicenseChoice> {
+    let mut licenses = match component.license() {
+        LicenseFlavor::None => vec![],
+        LicenseFlavor::Spdx(expression) | LicenseFlavor::OtherExpression(expression) => {
+            vec![LicenseChoice::Expression {
+                expression: expression.to_string(),
+            }]
+        }
+        LicenseFlavor::PublicDomain => vec![LicenseChoice::License {
+            license: License {
+                name: "Public Domain".to_string(),
+                text: None,
+            },
+        }],
+        LicenseFlavor::Unknown(terms) => terms
+            .iter()
+            .map(|term| LicenseChoice::License {
+                license: License {
+                    name: term.clone(),
+                    text: None,
+                },
+            })
+            .collect(),
+    };
+
+    licenses.extend(
+        component
+            .license_texts()
+            .iter()
+            .map(|text| LicenseChoice::License {
+                license: License {
+                    name: "License text".to_string(),
+                    text: Some(LicenseText {
+                        content_type: "text/plain",
+                        content: text.clone(),
+                    }),
+                },
+            }),
+    );
+
+    licenses
+}
+
+/// Produce a CycloneDX JSON document describing packaged components.
+///
+/// Every licensed component becomes a component in the document. Python
+/// packages additionally list the files they contributed, as recorded in
+/// their `RECORD` file and present in `resources`.
+///
+/// Documents have a random serial number unless `deterministic` is set, in
+/// which case the serial number is derived from the document content.
+pub fn cyclonedx_document<'a>(
+    application_name: &str,
+    licensing: &LicensedComponents,
+    resources: impl Iterator<Item = (&'a String, &'a PrePackagedResource)>,
+    deterministic: bool,
+) -> Result<String> {
+    let resources = resources.collect::<BTreeMap<_, _>>();
+
+    let components = licensing
+        .iter_components()
+        .map(|component| {
+            let (name, description) = match component.flavor() {
+                ComponentFlavor::PythonDistribution(name) => (name, "Python distribution"),
+                ComponentFlavor::PythonStandardLibraryModule(name) => {
+                    (name, "Python standard library module")
+                }
+                ComponentFlavor::PythonStandardLibraryExtensionModule(name) => {
+                    (name, "Python standard library extension module")
+                }
+                ComponentFlavor::PythonExtensionModule(name) => (name, "Python extension module"),
+                ComponentFlavor::PythonModule(name) => (name, "Python package"),
+                ComponentFlavor::Library(name) => (name, "Library"),
+                ComponentFlavor::RustCrate(name) => (name, "Rust crate"),
+            };
+
+            let (purl, files) = if let ComponentFlavor::PythonModule(name) = component.flavor() {
+                let purl = component.version().map(|version| {
+                    format!(
+                        "pkg:pypi/{}@{}",
+                        name.to_lowercase().replace('_', "-"),
+                        version
+                    )
+                });
+
+                let files = if let Some(resource) = resources.get(name) {
+                    distribution_record_paths(resource)?
+                        .into_iter()
+                        .filter(|path| is_packaged_file(&resources, path))
+                        .map(|path| Component {
+                            component_type: "file",
+                            name: path,
+                            ..Default::default()
+                        })
+                        .collect::<Vec<_>>()
+                } else {
+                    vec![]
+                };
+
+                (purl, files)
+            } else {
+                (None, vec![])
+            };
+
+            Ok(Component {
+                component_type: "library",
+                bom_ref: Some(component.flavor().to_string()),
+                name: name.clone(),
+                version: component.version().map(|x| x.to_string()),
+                author: if component.authors().is_empty() {
+                    None
+                } else {
+                    Some(component.authors().join(", "))
+                },
+                description: Some(description.to_string()),
+                purl,
+                licenses: component_licenses(component),
+                external_references: component
+                    .homepage()
+                    .map(|url| ExternalReference {
+                        reference_type: "website",
+                        url: url.to_string(),
+                    })
+                    .into_iter()
+                    .collect(),
+                components: files,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let serial_number = if deterministic {
+        uuid::Uuid::new_v5(
+            &uuid::Uuid::NAMESPACE_OID,
+            &serde_json::to_vec(&(application_name, &components))?,
+        )
+    } else {
+        uuid::Uuid::new_v4()
+    };
+
+    let bom = Bom {
+        bom_format: "CycloneDX",
+        spec_version: CYCLONEDX_SPEC_VERSION,
+        serial_number: format!("urn:uuid:{}", serial_number),
+        version: 1,
+        metadata: Metadata {
+            tools: vec![Tool {
+                name: "PyOxidizer",
+                version: env!("CARGO_PKG_VERSION"),
+            }],
+            component: Component {
+                component_type: "application",
+                name: application_name.to_string(),
+                ..Default::default()
+            },
+        },
+        components,
+    };
+
+    Ok(serde_json::to_string_pretty(&bom)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, simple_file_manifest::FileData};
+
+    #[test]
+    fn test_cyclonedx_document() -> Result<()> {
+        let mut licensing = LicensedComponents::default();
+
+        let mut component =
+            LicensedComponent::new_spdx(ComponentFlavor::PythonModule("foo".into()), "MIT")?;
+        component.set_version("1.0");
+        component.set_homepage("https://example.com/foo");
+        licensing.add_component(component);
+
+        let foo = PrePackagedResource {
+            name: "foo".into(),
+            is_module: true,
+            is_package: true,
+            in_memory_source: Some(FileData::Memory(vec![])),
+            in_memory_resources: Some(BTreeMap::from([(
+                "data/x.txt".to_string(),
+                FileData::Memory(vec![]),
+            )])),
+            in_memory_distribution_resources: Some(BTreeMap::from([(
+                "RECORD".to_string(),
+                FileData::Memory(
+                    b"foo/__init__.py,sha256=abc,0\n\
+                    foo/__pycache__/__init__.cpython-310.pyc,,\n\
+                    foo/data/x.txt,sha256=abc,0\n\
+                    foo/tests.py,sha256=abc,0\n\
+                    foo-1.0.dist-info/RECORD,,\n\
+                    ../../bin/foo,sha256=abc,0\n"
+                        .to_vec(),
+                ),
+            )])),
+            ..PrePackagedResource::default()
+        };
+        let resources = vec![(&foo.name, &foo)];
+
+        let doc = cyclonedx_document("myapp", &licensing, resources.iter().cloned(), false)?;
+        let value: serde_json::Value = serde_json::from_str(&doc)?;
+
+        assert_eq!(value["bomFormat"], "CycloneDX");
+        assert_eq!(value["metadata"]["component"]["name"], "myapp");
+
+        let component = &value["components"][0];
+        assert_eq!(component["name"], "foo");
+        assert_eq!(component["version"], "1.0");
+        assert_eq!(component["purl"], "pkg:pypi/foo@1.0");
+        assert_eq!(component["licenses"][0]["expression"], "MIT");
+        assert_eq!(
+            component["externalReferences"][0]["url"],
+            "https://example.com/foo"
+        );
+
+        let files = component["components"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(files, vec!["foo/__init__.py", "foo/data/x.txt"]);
+
+        let a = cyclonedx_document("myapp", &licensing, resources.iter().cloned(), true)?;
+        let b = cyclonedx_document("myapp", &licensing, resources.iter().cloned(), true)?;
+        assert_eq!(a, b);
+        assert_ne!(doc, a);
+
+        Ok(())
+    }
+}
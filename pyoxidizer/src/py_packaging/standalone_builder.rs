@@ -5,8 +5,9 @@
 use {
     super::{
         binary::{
-            LibpythonLinkMode, PackedResourcesLoadMode, PythonBinaryBuilder,
-            ResourceAddCollectionContextCallback, WindowsRuntimeDllsMode,
+            LibpythonLinkMode, OpensslMode, PackedResourcesLoadMode, PythonBinaryBuilder,
+            ResourceAddCollectionContextCallback, WindowsExecutableResources,
+            WindowsRuntimeDllsMode,
         },
         config::{PyembedPackedResourcesSource, PyembedPythonInterpreterConfig},
         distribution::{AppleSdkInfo, BinaryLibpythonLinkMode, PythonDistribution},
@@ -14,38 +15,48 @@ use {
             EmbeddedPythonContext, LibpythonLinkSettings, LinkSharedLibraryPath,
             LinkStaticLibraryData, LinkingAnnotation,
         },
-        filtering::{filter_btreemap, resolve_resource_names_from_files},
+        filtering::{
+            distributions_providing_packages, filter_btreemap, resolve_resource_names_from_files,
+        },
         libpython::link_libpython,
         packaging_tool::{
-            find_resources, pip_download, pip_install, read_virtualenv, setup_py_install,
+            find_resources, pep517_build_wheel, pip_download, pip_install, read_virtualenv,
+            setup_py_install,
         },
+        sbom::cyclonedx_document,
         standalone_distribution::StandaloneDistribution,
     },
-    crate::environment::Environment,
+    crate::{
+        content_cache::{zstd_compress_resources_cached, CachingBytecodeCompiler},
+        environment::Environment,
+    },
     anyhow::{anyhow, Context, Result},
     log::warn,
     once_cell::sync::Lazy,
     pyo3_build_config::{BuildFlag, BuildFlags, PythonImplementation, PythonVersion},
     python_packaging::{
         bytecode::BytecodeCompiler,
-        interpreter::MemoryAllocatorBackend,
+        interpreter::{MemoryAllocatorBackend, RunMode, TerminfoResolution},
         libpython::LibPythonBuildContext,
         licensing::{
             derive_package_license_infos, ComponentFlavor, LicensedComponent, LicensedComponents,
         },
         location::AbstractResourceLocation,
         policy::PythonPackagingPolicy,
+        python_source::find_imported_modules,
         resource::{
             PythonExtensionModule, PythonModuleSource, PythonPackageDistributionResource,
             PythonPackageResource, PythonResource,
         },
         resource_collection::{
-            AddResourceAction, PrePackagedResource, PythonResourceAddCollectionContext,
-            PythonResourceCollector,
+            AddResourceAction, CompiledResourcesCollection, PrePackagedResource,
+            PythonResourceAddCollectionContext, PythonResourceCollector,
         },
     },
+    python_packed_resources::Resource,
     simple_file_manifest::{File, FileData, FileEntry, FileManifest},
     std::{
+        borrow::Cow,
         collections::{BTreeMap, BTreeSet, HashMap},
         path::{Path, PathBuf},
         str::FromStr,
@@ -54,6 +65,70 @@ use {
     tugger_windows::{find_visual_cpp_redistributable, VcRedistributablePlatform},
 };
 
+/// Directories on the build machine to copy a terminfo database from.
+///
+/// Ordered by precedence. This matches the search order of Debian's ncurses.
+const HOST_TERMINFO_DIRS: &[&str] = &[
+    "/etc/terminfo",
+    "/lib/terminfo",
+    "/usr/share/terminfo",
+    "/usr/lib/terminfo",
+];
+
+/// Files on the build machine to copy a CA certificate bundle from.
+///
+/// Ordered by precedence.
+const HOST_CA_BUNDLE_PATHS: &[&str] = &[
+    // Debian, Ubuntu, Gentoo, Arch.
+    "/etc/ssl/certs/ca-certificates.crt",
+    // Fedora, RHEL.
+    "/etc/pki/ca-trust/extracted/pem/tls-ca-bundle.pem",
+    "/etc/pki/tls/certs/ca-bundle.crt",
+    // openSUSE.
+    "/etc/ssl/ca-bundle.pem",
+    // Alpine, macOS.
+    "/etc/ssl/cert.pem",
+];
+
+/// Filename of the CA certificate bundle installed next to the binary.
+const OPENSSL_CA_BUNDLE_FILENAME: &str = "cacert.pem";
+
+/// Names of the OpenSSL libraries linked by the `_ssl` and `_hashlib` extension modules.
+const OPENSSL_LIBRARIES: &[&str] = &["ssl", "crypto"];
+
+/// Modules imported by the interpreter and `pyembed` independently of application code.
+const INTERPRETER_MODULES: &[&str] = &[
+    "_collections_abc",
+    "_sitebuiltins",
+    "abc",
+    "code",
+    "codecs",
+    "genericpath",
+    "importlib",
+    "importlib.machinery",
+    "importlib.util",
+    "io",
+    "linecache",
+    "ntpath",
+    "os",
+    "posixpath",
+    "readline",
+    "runpy",
+    "site",
+    "sitecustomize",
+    "stat",
+    "tokenize",
+    "traceback",
+    "usercustomize",
+    "warnings",
+];
+
+/// Packages whose modules are imported by name at run-time and are always retained.
+const INTERPRETER_PACKAGES: &[&str] = &["encodings"];
+
+/// zstd compression level used for packed resources data.
+const PACKED_RESOURCES_ZSTD_LEVEL: i32 = 19;
+
 /// Libraries that we should not link against on Linux.
 static LINUX_IGNORE_LIBRARIES: Lazy<Vec<&'static str>> = Lazy::new(|| vec!["dl", "m"]);
 
@@ -124,14 +199,41 @@ pub struct StandalonePythonExecutableBuilder {
     /// Filename to write out with licensing information.
     licenses_filename: Option<String>,
 
+    /// Filename to write out with a CycloneDX software bill of materials.
+    sbom_filename: Option<String>,
+
+    /// Whether to normalize sources of nondeterminism in build outputs.
+    deterministic: bool,
+
+    /// Arguments to run instrumented executables with for profile-guided optimization.
+    pgo_training_args: Option<Vec<String>>,
+
+    /// Whether to optimize the executable with BOLT.
+    bolt: bool,
+
     /// Value for the `windows_subsystem` Rust attribute for generated Rust projects.
     windows_subsystem: String,
 
     /// Path to install tcl/tk files into.
     tcl_files_path: Option<String>,
 
+    /// Whether to embed tcl/tk files in packed resources.
+    tcl_files_embedded: bool,
+
+    /// Path to install a terminfo database into.
+    terminfo_files_path: Option<String>,
+
     /// Describes how Windows runtime DLLs should be handled during builds.
     windows_runtime_dlls_mode: WindowsRuntimeDllsMode,
+
+    /// Describes how OpenSSL is provided to the binary.
+    openssl_mode: OpensslMode,
+
+    /// Resources to compile into Windows executables.
+    windows_resources: WindowsExecutableResources,
+
+    /// Additional annotations influencing how the binary is linked.
+    linking_annotations: Vec<LinkingAnnotation>,
 }
 
 impl StandalonePythonExecutableBuilder {
@@ -193,8 +295,11 @@ impl StandalonePythonExecutableBuilder {
 
         let mut allowed_extension_module_locations = vec![];
 
-        if supports_in_memory_dynamically_linked_extension_loading
-            && packaging_policy.allow_in_memory_shared_library_loading()
+        // Extension modules extracted at run-time can be embedded regardless of
+        // whether the platform can load them from memory.
+        if (supports_in_memory_dynamically_linked_extension_loading
+            && packaging_policy.allow_in_memory_shared_library_loading())
+            || config.extension_modules_extraction_path.is_some()
         {
             allowed_extension_module_locations.push(AbstractResourceLocation::InMemory);
         }
@@ -228,9 +333,18 @@ impl StandalonePythonExecutableBuilder {
             config,
             host_python_exe,
             licenses_filename: Some("COPYING.txt".into()),
+            sbom_filename: None,
+            deterministic: false,
+            pgo_training_args: None,
+            bolt: false,
             windows_subsystem: "console".to_string(),
             tcl_files_path: None,
+            tcl_files_embedded: false,
+            terminfo_files_path: None,
             windows_runtime_dlls_mode: WindowsRuntimeDllsMode::WhenPresent,
+            openssl_mode: OpensslMode::Static,
+            windows_resources: WindowsExecutableResources::default(),
+            linking_annotations: vec![],
         });
 
         builder.add_distribution_core_state()?;
@@ -313,23 +427,23 @@ impl StandalonePythonExecutableBuilder {
             LibpythonLinkMode::Static => {
                 warn!("generating custom link library containing Python...");
 
-                let mut link_contexts = vec![&self.core_build_context];
-                for c in self.extension_build_contexts.values() {
-                    link_contexts.push(c);
-                }
-
                 let library_info = link_libpython(
                     env,
-                    &LibPythonBuildContext::merge(&link_contexts),
+                    &self.resolve_libpython_build_context(),
                     &self.host_triple,
                     &self.target_triple,
                     opt_level,
                     self.apple_sdk_info(),
+                    self.deterministic,
                 )?;
 
+                let mut linking_annotations = library_info.linking_annotations;
+                linking_annotations.extend(self.openssl_linking_annotations());
+                linking_annotations.extend(self.linking_annotations.iter().cloned());
+
                 Ok(LinkStaticLibraryData {
                     library_data: library_info.libpython_data,
-                    linking_annotations: library_info.linking_annotations,
+                    linking_annotations,
                 }
                 .into())
             }
@@ -359,8 +473,9 @@ impl StandalonePythonExecutableBuilder {
                     library_search_path.to_path_buf()
                 };
 
-                let linking_annotations =
+                let mut linking_annotations =
                     vec![LinkingAnnotation::SearchNative(library_search_path)];
+                linking_annotations.extend(self.linking_annotations.iter().cloned());
 
                 Ok(LinkSharedLibraryPath {
                     library_path,
@@ -372,6 +487,83 @@ impl StandalonePythonExecutableBuilder {
         }
     }
 
+    /// Resolve the context for building a custom libpython.
+    ///
+    /// Merges the core and extension module contexts and applies the OpenSSL mode.
+    fn resolve_libpython_build_context(&self) -> LibPythonBuildContext {
+        let mut link_contexts = vec![&self.core_build_context];
+        for c in self.extension_build_contexts.values() {
+            link_contexts.push(c);
+        }
+
+        let mut context = LibPythonBuildContext::merge(&link_contexts);
+
+        // The system libraries are linked via linker arguments instead. See
+        // openssl_linking_annotations().
+        if self.openssl_mode == OpensslMode::System {
+            context
+                .static_libraries
+                .retain(|name| !OPENSSL_LIBRARIES.contains(&name.as_str()));
+        }
+
+        context
+    }
+
+    /// Resolve linking annotations for linking the system's OpenSSL.
+    ///
+    /// The distribution's static OpenSSL libraries live in a directory on the
+    /// library search path, so a plain `-lssl` could resolve to them. Exact
+    /// filenames are used to ensure the shared libraries are linked.
+    fn openssl_linking_annotations(&self) -> Vec<LinkingAnnotation> {
+        if self.openssl_mode != OpensslMode::System {
+            return vec![];
+        }
+
+        let linked = self.extension_build_contexts.values().any(|c| {
+            c.static_libraries
+                .iter()
+                .any(|name| OPENSSL_LIBRARIES.contains(&name.as_str()))
+        });
+
+        if !linked {
+            return vec![];
+        }
+
+        OPENSSL_LIBRARIES
+            .iter()
+            .map(|name| LinkingAnnotation::Argument(format!("-l:lib{}.so", name)))
+            .collect()
+    }
+
+    /// Resolve the CA certificate bundle to install for this binary.
+    ///
+    /// The first bundle found at [HOST_CA_BUNDLE_PATHS] is used.
+    fn resolve_ca_bundle_files(&self) -> Result<FileManifest> {
+        let mut manifest = FileManifest::default();
+
+        if self.openssl_mode != OpensslMode::CaBundle {
+            return Ok(manifest);
+        }
+
+        let path = HOST_CA_BUNDLE_PATHS
+            .iter()
+            .map(Path::new)
+            .find(|path| path.is_file())
+            .ok_or_else(|| {
+                anyhow!(
+                    "unable to locate a CA certificate bundle to install; searched {}",
+                    HOST_CA_BUNDLE_PATHS.join(", ")
+                )
+            })?;
+
+        manifest.add_file_entry(
+            OPENSSL_CA_BUNDLE_FILENAME,
+            FileEntry::new_from_path(path, false),
+        )?;
+
+        Ok(manifest)
+    }
+
     /// Resolves Windows runtime DLLs file needed for this binary given current settings.
     fn resolve_windows_runtime_dll_files(&self) -> Result<FileManifest> {
         let mut manifest = FileManifest::default();
@@ -411,6 +603,88 @@ impl StandalonePythonExecutableBuilder {
 
         Ok(manifest)
     }
+
+    /// Resolve terminfo database files to install for this binary.
+    ///
+    /// The database is copied from the build machine. Entries in directories
+    /// earlier in [HOST_TERMINFO_DIRS] take precedence. Compiled terminfo
+    /// entries are platform independent, so this works when cross-compiling.
+    fn resolve_terminfo_files(&self) -> Result<FileManifest> {
+        let mut manifest = FileManifest::default();
+
+        let install_path = if let Some(path) = &self.terminfo_files_path {
+            PathBuf::from(path)
+        } else {
+            return Ok(manifest);
+        };
+
+        // Windows doesn't use the terminfo database.
+        if crate::environment::WINDOWS_TARGET_TRIPLES.contains(&self.target_triple.as_str()) {
+            return Ok(manifest);
+        }
+
+        let mut files = BTreeMap::new();
+
+        for root in HOST_TERMINFO_DIRS.iter().map(Path::new) {
+            if !root.is_dir() {
+                continue;
+            }
+
+            for entry in walkdir::WalkDir::new(root)
+                .follow_links(true)
+                .sort_by(|a, b| a.file_name().cmp(b.file_name()))
+            {
+                let entry = entry?;
+                let path = entry.path();
+
+                if path.is_dir() {
+                    continue;
+                }
+
+                let rel_path = path.strip_prefix(root)?.to_path_buf();
+
+                files.entry(rel_path).or_insert_with(|| path.to_path_buf());
+            }
+        }
+
+        if files.is_empty() {
+            return Err(anyhow!(
+                "unable to locate a terminfo database to install; searched {}",
+                HOST_TERMINFO_DIRS.join(", ")
+            ));
+        }
+
+        for (rel_path, path) in files {
+            manifest.add_file_entry(install_path.join(rel_path), FileEntry::try_from(path)?)?;
+        }
+
+        Ok(manifest)
+    }
+
+    /// Update interpreter config settings related to tcl files.
+    fn update_tcl_config(&mut self) {
+        let path = if let Some(path) = &self.tcl_files_path {
+            path
+        } else {
+            self.config.tcl_library = None;
+            self.config.tcl_files_embedded_prefix = None;
+            return;
+        };
+
+        let library_directory = self
+            .target_distribution
+            .tcl_library_path_directory()
+            .expect("should have a tcl library path directory");
+
+        if self.tcl_files_embedded {
+            self.config.tcl_library = Some(PathBuf::from(path).join(library_directory));
+            self.config.tcl_files_embedded_prefix = Some(format!("{}/", path));
+        } else {
+            self.config.tcl_library =
+                Some(PathBuf::from("$ORIGIN").join(path).join(library_directory));
+            self.config.tcl_files_embedded_prefix = None;
+        }
+    }
 }
 
 impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
@@ -418,6 +692,10 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
         Arc::new(self.clone())
     }
 
+    fn clone_box(&self) -> Box<dyn PythonBinaryBuilder> {
+        Box::new(self.clone())
+    }
+
     fn name(&self) -> String {
         self.exe_name.clone()
     }
@@ -476,24 +754,78 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
         self.windows_runtime_dlls_mode = value;
     }
 
+    fn openssl_mode(&self) -> &OpensslMode {
+        &self.openssl_mode
+    }
+
+    fn set_openssl_mode(&mut self, value: OpensslMode) -> Result<()> {
+        if value == OpensslMode::System {
+            if !self.target_triple.contains("-linux-") {
+                return Err(anyhow!(
+                    "OpenSSL mode 'system' is only supported on Linux targets"
+                ));
+            }
+
+            if self.link_mode != LibpythonLinkMode::Static {
+                return Err(anyhow!(
+                    "OpenSSL mode 'system' requires statically linking libpython"
+                ));
+            }
+        }
+
+        self.openssl_mode = value;
+
+        Ok(())
+    }
+
     fn tcl_files_path(&self) -> &Option<String> {
         &self.tcl_files_path
     }
 
     fn set_tcl_files_path(&mut self, value: Option<String>) {
         self.tcl_files_path = value;
+        self.update_tcl_config();
+    }
 
-        self.config.tcl_library = if let Some(path) = &self.tcl_files_path {
-            Some(
-                PathBuf::from("$ORIGIN").join(path).join(
-                    self.target_distribution
-                        .tcl_library_path_directory()
-                        .expect("should have a tcl library path directory"),
-                ),
-            )
+    fn tcl_files_embedded(&self) -> bool {
+        self.tcl_files_embedded
+    }
+
+    fn set_tcl_files_embedded(&mut self, value: bool) {
+        self.tcl_files_embedded = value;
+        self.update_tcl_config();
+    }
+
+    fn terminfo_files_path(&self) -> &Option<String> {
+        &self.terminfo_files_path
+    }
+
+    fn set_terminfo_files_path(&mut self, value: Option<String>) {
+        self.config.terminfo_resolution = if let Some(path) = &value {
+            TerminfoResolution::Static(format!("$ORIGIN/{}", path))
         } else {
-            None
+            TerminfoResolution::Dynamic
         };
+        self.terminfo_files_path = value;
+    }
+
+    fn set_qt_plugin_path(&mut self, value: Option<String>) {
+        self.config.qt_plugin_path = value.map(|path| PathBuf::from("$ORIGIN").join(path));
+    }
+
+    fn set_qml_import_path(&mut self, value: Option<String>) {
+        self.config.qml_import_path = value.map(|path| PathBuf::from("$ORIGIN").join(path));
+    }
+
+    fn set_run_modes(&mut self, value: Vec<RunMode>) {
+        self.config.run_modes = value;
+        self.config.run_mode_flag = None;
+    }
+
+    fn add_package_source_path(&mut self, package: &str, path: &Path) {
+        self.config
+            .package_source_paths
+            .insert(package.to_string(), path.to_path_buf());
     }
 
     fn windows_subsystem(&self) -> &str {
@@ -506,6 +838,14 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
         Ok(())
     }
 
+    fn windows_resources(&self) -> &WindowsExecutableResources {
+        &self.windows_resources
+    }
+
+    fn set_windows_resources(&mut self, value: WindowsExecutableResources) {
+        self.windows_resources = value;
+    }
+
     fn licenses_filename(&self) -> Option<&str> {
         self.licenses_filename.as_deref()
     }
@@ -514,6 +854,38 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
         self.licenses_filename = value;
     }
 
+    fn sbom_filename(&self) -> Option<&str> {
+        self.sbom_filename.as_deref()
+    }
+
+    fn set_sbom_filename(&mut self, value: Option<String>) {
+        self.sbom_filename = value;
+    }
+
+    fn deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    fn set_deterministic(&mut self, value: bool) {
+        self.deterministic = value;
+    }
+
+    fn pgo_training_args(&self) -> Option<&[String]> {
+        self.pgo_training_args.as_deref()
+    }
+
+    fn set_pgo_training_args(&mut self, value: Option<Vec<String>>) {
+        self.pgo_training_args = value;
+    }
+
+    fn bolt(&self) -> bool {
+        self.bolt
+    }
+
+    fn set_bolt(&mut self, value: bool) {
+        self.bolt = value;
+    }
+
     fn packed_resources_load_mode(&self) -> &PackedResourcesLoadMode {
         &self.resources_load_mode
     }
@@ -545,14 +917,19 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
         env: &Environment,
         verbose: bool,
         args: &[String],
+        requirements_path: Option<&Path>,
+        build_from_source: bool,
     ) -> Result<Vec<PythonResource>> {
         let resources = pip_download(
             env,
             &*self.host_distribution,
             &*self.target_distribution,
             self.python_packaging_policy(),
+            self.link_mode,
             verbose,
             args,
+            requirements_path,
+            build_from_source,
         )
         .context("calling pip download")?;
 
@@ -568,6 +945,7 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
         verbose: bool,
         install_args: &[String],
         extra_envs: &HashMap<String, String>,
+        requirements_path: Option<&Path>,
     ) -> Result<Vec<PythonResource>> {
         let resources = pip_install(
             env,
@@ -577,6 +955,7 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
             verbose,
             install_args,
             extra_envs,
+            requirements_path,
         )
         .context("calling pip install")?;
 
@@ -597,16 +976,30 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
             path,
             None,
         )
-        .context("finding resources")?
-        .iter()
-        .filter_map(|x| {
-            if x.is_in_packages(packages) {
-                Some(x.clone())
-            } else {
-                None
-            }
-        })
-        .collect::<Vec<_>>();
+        .context("finding resources")?;
+
+        // Retain metadata of distributions providing the packages so
+        // importlib.metadata can answer queries about them.
+        let distributions = distributions_providing_packages(&resources, packages)
+            .context("resolving package distributions")?;
+
+        let resources = resources
+            .iter()
+            .filter_map(|x| {
+                let include = match x {
+                    PythonResource::PackageDistributionResource(r) => {
+                        distributions.contains(&r.package)
+                    }
+                    _ => x.is_in_packages(packages),
+                };
+
+                if include {
+                    Some(x.clone())
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
 
         self.index_package_license_info_from_resources(&resources)
             .context("indexing package license metadata")?;
@@ -654,6 +1047,30 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
         Ok(resources)
     }
 
+    fn pep517_build_wheel(
+        &mut self,
+        env: &Environment,
+        package_path: &Path,
+        verbose: bool,
+        extra_envs: &HashMap<String, String>,
+    ) -> Result<Vec<PythonResource>> {
+        let resources = pep517_build_wheel(
+            env,
+            &*self.target_distribution,
+            self.python_packaging_policy(),
+            self.link_mode,
+            package_path,
+            verbose,
+            extra_envs,
+        )
+        .context("building wheel via PEP 517")?;
+
+        self.index_package_license_info_from_resources(&resources)
+            .context("indexing package license metadata")?;
+
+        Ok(resources)
+    }
+
     fn add_distribution_resources(
         &mut self,
         callback: Option<ResourceAddCollectionContextCallback>,
@@ -836,6 +1253,10 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
             .add_file_data_with_context(file, &add_context)
     }
 
+    fn add_data_file(&mut self, namespace: &str, file: &File) -> Result<Vec<AddResourceAction>> {
+        self.resources_collector.add_data_file(namespace, file)
+    }
+
     fn filter_resources_from_files(
         &mut self,
         files: &[&Path],
@@ -860,6 +1281,135 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
         Ok(())
     }
 
+    fn prune_unused_modules(&mut self, roots: &[&str], allow: &[&str]) -> Result<()> {
+        // Modules executed as `__main__`. For packages, this is the package's
+        // `__main__` module.
+        let mut main_modules = roots.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        let mut search = vec![];
+
+        if let Some(name) = &self.config.config.run_module {
+            main_modules.push(name.clone());
+        }
+        if let Some(code) = &self.config.config.run_command {
+            search.extend(find_imported_modules(
+                code.as_bytes(),
+                "__main__",
+                false,
+                true,
+            ));
+        }
+        for mode in &self.config.run_modes {
+            match mode {
+                RunMode::Module(name) => main_modules.push(name.clone()),
+                RunMode::Eval(code) => search.extend(find_imported_modules(
+                    code.as_bytes(),
+                    "__main__",
+                    false,
+                    true,
+                )),
+                RunMode::File | RunMode::Repl => {}
+            }
+        }
+
+        if main_modules.is_empty() && search.is_empty() && allow.is_empty() {
+            return Err(anyhow!(
+                "unable to determine which modules the application imports; specify roots explicitly"
+            ));
+        }
+
+        main_modules.extend(
+            main_modules
+                .iter()
+                .map(|name| format!("{}.__main__", name))
+                .collect::<Vec<_>>(),
+        );
+
+        let is_allowed = |name: &str| {
+            allow
+                .iter()
+                .chain(INTERPRETER_PACKAGES.iter())
+                .any(|package| {
+                    name == *package
+                        || name
+                            .strip_prefix(*package)
+                            .map_or(false, |rest| rest.starts_with('.'))
+                })
+        };
+
+        search.extend(INTERPRETER_MODULES.iter().map(|x| x.to_string()));
+        if self.config.write_modules_directory_env.is_some() {
+            search.push("uuid".to_string());
+        }
+        search.extend(
+            self.resources_collector
+                .iter_resources()
+                .filter(|(name, _)| is_allowed(name))
+                .map(|(name, _)| name.clone()),
+        );
+        // Extension modules the interpreter requires must always be present.
+        search.extend(
+            self.extension_build_contexts
+                .keys()
+                .filter(|name| {
+                    self.target_distribution
+                        .extension_modules
+                        .get(*name)
+                        .map_or(false, |variants| variants.iter().any(|em| em.required))
+                })
+                .cloned(),
+        );
+
+        let main_modules = main_modules.iter().map(|x| x.as_str()).collect::<Vec<_>>();
+
+        let mut reached = self.resources_collector.find_imported_module_closure(
+            &search.iter().map(|x| x.as_str()).collect::<Vec<_>>(),
+            &main_modules,
+        )?;
+
+        // pyembed imports multiprocessing.spawn when dispatching to a child process.
+        if self.config.multiprocessing_auto_dispatch && reached.contains("multiprocessing") {
+            search.push("multiprocessing.spawn".to_string());
+            reached = self.resources_collector.find_imported_module_closure(
+                &search.iter().map(|x| x.as_str()).collect::<Vec<_>>(),
+                &main_modules,
+            )?;
+        }
+
+        warn!("pruning modules not imported by the application");
+
+        // Resources without module code, such as namespace packages and
+        // distribution metadata, are retained.
+        self.resources_collector.filter_resources_mut(|resource| {
+            if resource.is_python_resource()
+                && resource.has_module_code()
+                && !resource.is_frozen_module
+                && !reached.contains(&resource.name)
+            {
+                warn!("removing {}", resource.name);
+                false
+            } else {
+                true
+            }
+        })?;
+
+        let remaining = self
+            .resources_collector
+            .iter_resources()
+            .map(|(name, _)| name.clone())
+            .collect::<BTreeSet<_>>();
+        filter_btreemap(&mut self.extension_build_contexts, &remaining);
+
+        Ok(())
+    }
+
+    fn linking_annotations(&self) -> &[LinkingAnnotation] {
+        &self.linking_annotations
+    }
+
+    fn add_linking_annotation(&mut self, annotation: LinkingAnnotation) {
+        self.linking_annotations.push(annotation);
+    }
+
     fn requires_jemalloc(&self) -> bool {
         self.config.allocator_backend == MemoryAllocatorBackend::Jemalloc
     }
@@ -880,11 +1430,7 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
         self.resources_collector.add_licensed_component(component)
     }
 
-    fn to_embedded_python_context(
-        &self,
-        env: &Environment,
-        opt_level: &str,
-    ) -> Result<EmbeddedPythonContext> {
+    fn compile_resources(&self, env: &Environment) -> Result<CompiledResourcesCollection> {
         let mut file_seen = false;
         for module in self.resources_collector.find_dunder_file()? {
             file_seen = true;
@@ -897,15 +1443,71 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
             warn!("See https://github.com/indygreg/PyOxidizer/issues/69 for more");
         }
 
-        let compiled_resources = {
-            let temp_dir = env.temporary_directory("pyoxidizer-bytecode-compile")?;
-            let mut compiler = BytecodeCompiler::new(self.host_python_exe_path(), temp_dir.path())?;
-            let resources = self.resources_collector.compile_resources(&mut compiler)?;
+        let temp_dir = env.temporary_directory("pyoxidizer-bytecode-compile")?;
+        let mut compiler = BytecodeCompiler::new(self.host_python_exe_path(), temp_dir.path())?;
+
+        // The path of an extracted distribution incorporates the hash of its
+        // archive. Distributions used from a directory may be rebuilt in place,
+        // so also incorporate the state of the executable.
+        let metadata = std::fs::metadata(self.host_python_exe_path())?;
+        let interpreter = format!(
+            "{}:{}:{:?}",
+            self.host_python_exe_path().display(),
+            metadata.len(),
+            metadata.modified().ok()
+        );
+        let mut compiler =
+            CachingBytecodeCompiler::new(&mut compiler, env.content_cache(), interpreter);
+
+        let mut resources = self.resources_collector.compile_resources(&mut compiler)?;
 
-            temp_dir.close().context("closing temporary directory")?;
+        temp_dir.close().context("closing temporary directory")?;
 
-            resources
-        };
+        if let Some(tcl_files_path) = self.tcl_files_path() {
+            if self.tcl_files_embedded {
+                for (path, location) in self.target_distribution.tcl_files()? {
+                    let name = format!(
+                        "{}/{}",
+                        tcl_files_path,
+                        path.display().to_string().replace('\\', "/")
+                    );
+
+                    resources.resources.insert(
+                        name.clone(),
+                        Resource {
+                            name: Cow::Owned(name),
+                            is_utf8_filename_data: true,
+                            file_executable: location.is_executable(),
+                            file_data_embedded: Some(Cow::Owned(
+                                location
+                                    .resolve_content()
+                                    .context("resolving tcl file content")?,
+                            )),
+                            ..Resource::default()
+                        },
+                    );
+                }
+            }
+        }
+
+        if self.packaging_policy.packed_resources_zstd_compression() {
+            zstd_compress_resources_cached(
+                &env.content_cache(),
+                &mut resources,
+                PACKED_RESOURCES_ZSTD_LEVEL,
+            )
+            .context("compressing packed resources")?;
+        }
+
+        Ok(resources)
+    }
+
+    fn to_embedded_python_context(
+        &self,
+        env: &Environment,
+        opt_level: &str,
+    ) -> Result<EmbeddedPythonContext> {
+        let compiled_resources = self.compile_resources(env)?;
 
         let mut pending_resources = vec![];
 
@@ -962,16 +1564,49 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
         }
 
         if let Some(tcl_files_path) = self.tcl_files_path() {
-            for (path, location) in self.target_distribution.tcl_files()? {
-                let install_path = PathBuf::from(tcl_files_path).join(path);
+            if !self.tcl_files_embedded {
+                for (path, location) in self.target_distribution.tcl_files()? {
+                    let install_path = PathBuf::from(tcl_files_path).join(path);
 
-                extra_files.add_file_entry(&install_path, location)?;
+                    extra_files.add_file_entry(&install_path, location)?;
+                }
             }
         }
 
+        extra_files.add_manifest(&self.resolve_terminfo_files()?)?;
+
+        if self.openssl_mode == OpensslMode::CaBundle {
+            extra_files.add_manifest(&self.resolve_ca_bundle_files()?)?;
+
+            config
+                .environment_variables
+                .entry("SSL_CERT_FILE".to_string())
+                .or_insert_with(|| format!("$ORIGIN/{}", OPENSSL_CA_BUNDLE_FILENAME));
+        }
+
         // Install Windows runtime DLLs if told to do so.
         extra_files.add_manifest(&self.resolve_windows_runtime_dll_files()?)?;
 
+        let licensing = self.licensed_components()?;
+
+        self.packaging_policy
+            .check_license_policy(&licensing)
+            .context("verifying licenses of packaged components")?;
+
+        if let Some(filename) = &self.sbom_filename {
+            let document = cyclonedx_document(
+                &self.exe_name,
+                &licensing,
+                self.resources_collector.iter_resources(),
+                self.deterministic,
+            )?;
+
+            extra_files.add_file_entry(
+                filename,
+                FileEntry::new_from_data(document.as_bytes(), false),
+            )?;
+        }
+
         let python_implementation = if self
             .target_distribution
             .python_implementation
@@ -1043,7 +1678,7 @@ impl PythonBinaryBuilder for StandalonePythonExecutableBuilder {
             python_exe_host: self.host_python_exe.clone(),
             python_build_flags,
             licensing_filename: self.licenses_filename.clone(),
-            licensing: self.licensed_components()?,
+            licensing,
         };
 
         context.synchronize_licensing()?;
@@ -1358,6 +1993,85 @@ pub mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_license_policy_violation() -> Result<()> {
+        let options = StandalonePythonExecutableBuilderOptions::default();
+        let mut exe = options.new_builder()?;
+
+        exe.packaging_policy.add_denied_license("AGPL-3.0-only")?;
+        exe.add_licensed_component(LicensedComponent::new_spdx(
+            ComponentFlavor::Library("foo".into()),
+            "AGPL-3.0-only",
+        )?)?;
+
+        match exe.to_embedded_python_context(&get_env()?, "0") {
+            Ok(_) => panic!("license policy violation should error"),
+            Err(err) => {
+                assert!(format!("{:?}", err)
+                    .contains("library foo: license AGPL-3.0-only is not allowed"))
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sbom_filename() -> Result<()> {
+        let options = StandalonePythonExecutableBuilderOptions::default();
+        let mut exe = options.new_builder()?;
+
+        let embedded = exe.to_embedded_python_context(&get_env()?, "0")?;
+        assert!(!embedded.extra_files.has_path(Path::new("sbom.cdx.json")));
+
+        exe.set_sbom_filename(Some("sbom.cdx.json".into()));
+        let embedded = exe.to_embedded_python_context(&get_env()?, "0")?;
+        assert!(
+            embedded.extra_files.has_path(Path::new("sbom.cdx.json")),
+            "SBOM file should be present in extra files manifest"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deterministic() -> Result<()> {
+        let options = StandalonePythonExecutableBuilderOptions {
+            libpython_link_mode: BinaryLibpythonLinkMode::Static,
+            ..StandalonePythonExecutableBuilderOptions::default()
+        };
+        let mut exe = options.new_builder()?;
+        exe.set_deterministic(true);
+        exe.set_sbom_filename(Some("sbom.cdx.json".into()));
+
+        let env = get_env()?;
+        let a = exe.to_embedded_python_context(&env, "0")?;
+        let b = exe.to_embedded_python_context(&env, "0")?;
+
+        let sbom = |embedded: &EmbeddedPythonContext| -> Result<Vec<u8>> {
+            let (_, entry) = embedded
+                .extra_files
+                .iter_entries()
+                .find(|(path, _)| path.as_path() == Path::new("sbom.cdx.json"))
+                .ok_or_else(|| anyhow!("SBOM not present"))?;
+
+            entry.file_data().resolve_content()
+        };
+        assert_eq!(sbom(&a)?, sbom(&b)?);
+
+        if let (
+            LibpythonLinkSettings::StaticData(a_link),
+            LibpythonLinkSettings::StaticData(b_link),
+        ) = (&a.link_settings, &b.link_settings)
+        {
+            assert!(
+                a_link.library_data == b_link.library_data,
+                "libpython should be identical across builds"
+            );
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_minimal_extensions_present() -> Result<()> {
         let options = StandalonePythonExecutableBuilderOptions::default();
@@ -1618,6 +2332,33 @@ pub mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_linux_extension_in_memory_extracted() -> Result<()> {
+        let options = StandalonePythonExecutableBuilderOptions {
+            target_triple: "x86_64-unknown-linux-gnu".to_string(),
+            extension_module_filter: Some(ExtensionModuleFilter::Minimal),
+            libpython_link_mode: BinaryLibpythonLinkMode::Dynamic,
+            resources_location: Some(ConcreteResourceLocation::InMemory),
+            resources_location_fallback: Some(None),
+            config: PyembedPythonInterpreterConfig {
+                extension_modules_extraction_path: Some("extensions".into()),
+                ..PyembedPythonInterpreterConfig::default()
+            },
+            ..StandalonePythonExecutableBuilderOptions::default()
+        };
+
+        let mut builder = options.new_builder()?;
+
+        builder.add_python_extension_module(&EXTENSION_MODULE_SHARED_LIBRARY_ONLY, None)?;
+        assert_extension_shared_library(
+            &builder,
+            &EXTENSION_MODULE_SHARED_LIBRARY_ONLY,
+            ConcreteResourceLocation::InMemory,
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_linux_distribution_extension_filesystem_relative_only() -> Result<()> {
         for libpython_link_mode in vec![
@@ -1734,6 +2475,71 @@ pub mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_linux_openssl_mode_system() -> Result<()> {
+        let options = StandalonePythonExecutableBuilderOptions {
+            target_triple: "x86_64-unknown-linux-gnu".to_string(),
+            extension_module_filter: Some(ExtensionModuleFilter::Minimal),
+            libpython_link_mode: BinaryLibpythonLinkMode::Static,
+            ..StandalonePythonExecutableBuilderOptions::default()
+        };
+
+        let mut builder = options.new_builder()?;
+
+        let ssl = builder
+            .target_distribution
+            .extension_modules
+            .get("_ssl")
+            .unwrap()
+            .default_variant()
+            .clone();
+        builder.add_python_extension_module(&ssl, None)?;
+
+        let context = builder.resolve_libpython_build_context();
+        assert!(context.static_libraries.contains("ssl"));
+        assert!(context.static_libraries.contains("crypto"));
+        assert!(builder.openssl_linking_annotations().is_empty());
+
+        builder.set_openssl_mode(OpensslMode::System)?;
+
+        let context = builder.resolve_libpython_build_context();
+        assert!(!context.static_libraries.contains("ssl"));
+        assert!(!context.static_libraries.contains("crypto"));
+        assert_eq!(
+            builder.openssl_linking_annotations(),
+            vec![
+                LinkingAnnotation::Argument("-l:libssl.so".to_string()),
+                LinkingAnnotation::Argument("-l:libcrypto.so".to_string()),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_openssl_mode_system_unsupported() -> Result<()> {
+        let options = StandalonePythonExecutableBuilderOptions {
+            target_triple: "x86_64-unknown-linux-gnu".to_string(),
+            libpython_link_mode: BinaryLibpythonLinkMode::Dynamic,
+            ..StandalonePythonExecutableBuilderOptions::default()
+        };
+        let mut builder = options.new_builder()?;
+        assert!(builder.set_openssl_mode(OpensslMode::System).is_err());
+
+        let options = StandalonePythonExecutableBuilderOptions {
+            target_triple: "x86_64-pc-windows-msvc".to_string(),
+            ..StandalonePythonExecutableBuilderOptions::default()
+        };
+        let mut builder = options.new_builder()?;
+        assert!(builder.set_openssl_mode(OpensslMode::System).is_err());
+        assert_eq!(builder.openssl_mode(), &OpensslMode::Static);
+
+        builder.set_openssl_mode(OpensslMode::CaBundle)?;
+        assert_eq!(builder.openssl_mode(), &OpensslMode::CaBundle);
+
+        Ok(())
+    }
+
     #[test]
     fn test_linux_musl_distribution_dynamic() {
         let options = StandalonePythonExecutableBuilderOptions {
@@ -2955,6 +3761,7 @@ pub mod tests {
                 false,
                 &["pyyaml==5.3.1".to_string()],
                 &HashMap::new(),
+                None,
             )?;
 
             let extensions = resources
@@ -251,6 +251,43 @@ fn parse_python_json(path: &Path) -> Result<PythonJsonMain> {
     Ok(v)
 }
 
+/// Verify that a parsed PYTHON.json is consistent with the distribution it describes.
+///
+/// Official distributions always pass. This exists to give actionable errors
+/// for distributions produced by other means.
+fn validate_python_json(pi: &PythonJsonMain, python_path: &Path) -> Result<()> {
+    if parse_python_major_minor_version(&pi.python_version) != pi.python_major_minor_version {
+        return Err(anyhow!(
+            "PYTHON.json python_major_minor_version {} does not match python_version {}",
+            pi.python_major_minor_version,
+            pi.python_version
+        ));
+    }
+
+    let mut referenced = vec![("python_exe", &pi.python_exe)];
+    referenced.extend(pi.build_info.core.objs.iter().map(|p| ("core object", p)));
+    referenced.extend(
+        pi.build_info
+            .core
+            .links
+            .iter()
+            .filter_map(|l| l.path_static.as_ref())
+            .map(|p| ("core library", p)),
+    );
+
+    for (what, path) in referenced {
+        if !python_path.join(path).exists() {
+            return Err(anyhow!(
+                "{} {} referenced by PYTHON.json does not exist",
+                what,
+                python_path.join(path).display()
+            ));
+        }
+    }
+
+    Ok(())
+}
+
 fn parse_python_json_from_distribution(dist_dir: &Path) -> Result<PythonJsonMain> {
     let python_json_path = dist_dir.join("python").join("PYTHON.json");
     parse_python_json(&python_json_path)
@@ -519,6 +556,10 @@ impl StandaloneDistribution {
         location: &PythonDistributionLocation,
         distributions_dir: &Path,
     ) -> Result<Self> {
+        if let PythonDistributionLocation::Directory { path } = location {
+            return Self::from_directory(Path::new(path));
+        }
+
         let (archive_path, extract_path) =
             resolve_python_distribution_from_location(location, distributions_dir)?;
 
@@ -712,6 +753,8 @@ impl StandaloneDistribution {
         }
 
         let pi = parse_python_json_from_distribution(dist_dir)?;
+        validate_python_json(&pi, &python_path)
+            .with_context(|| format!("validating Python distribution {}", dist_dir.display()))?;
 
         // Derive the distribution's license from a license file, if present.
         let core_license = if let Some(ref python_license_path) = pi.license_path {
@@ -1070,16 +1113,20 @@ impl PythonDistribution for StandaloneDistribution {
                 "x86_64-apple-darwin" => vec![],
                 // 32-bit Windows GNU on 32-bit Windows MSVC and 64-bit Windows.
                 "i686-pc-windows-gnu" => vec![
+                    "aarch64-pc-windows-msvc",
                     "i686-pc-windows-msvc",
                     "x86_64-pc-windows-gnu",
                     "x86_64-pc-windows-msvc",
                 ],
                 // 32-bit Windows MSVC runs on 32-bit Windows MSVC and 64-bit Windows.
                 "i686-pc-windows-msvc" => vec![
+                    "aarch64-pc-windows-msvc",
                     "i686-pc-windows-gnu",
                     "x86_64-pc-windows-gnu",
                     "x86_64-pc-windows-msvc",
                 ],
+                // ARM64 Windows binaries only run on ARM64 Windows.
+                "aarch64-pc-windows-msvc" => vec![],
                 // 64-bit Windows GNU/MSVC runs on the other.
                 "x86_64-pc-windows-gnu" => vec!["x86_64-pc-windows-msvc"],
                 "x86_64-pc-windows-msvc" => vec!["x86_64-pc-windows-gnu"],
@@ -1510,6 +1557,7 @@ pub mod tests {
                 dist.target_triple(),
             ) {
                 (_, "aarch64-unknown-linux-gnu") => (linux_dropped.clone(), linux_added.clone()),
+                (_, "aarch64-unknown-linux-musl") => (linux_dropped.clone(), linux_added.clone()),
                 (_, "x86_64-unknown-linux-gnu") => (linux_dropped.clone(), linux_added.clone()),
                 (_, "x86_64_v2-unknown-linux-gnu") => (linux_dropped.clone(), linux_added.clone()),
                 (_, "x86_64_v3-unknown-linux-gnu") => (linux_dropped.clone(), linux_added.clone()),
@@ -0,0 +1,437 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*!
+Interactive debugging of Starlark configuration evaluation.
+
+When a [StarlarkDebugger] is attached to an evaluation, calls to the
+`breakpoint()` Starlark function pause evaluation and present a prompt for
+inspecting the values passed to it. The debugger can also step through
+target resolution, pausing before each target's function is called and
+after it returns.
+
+Without an attached debugger, `breakpoint()` does nothing, so configuration
+files can retain breakpoints.
+*/
+
+use {
+    crate::starlark::env::{get_context, PyOxidizerEnvironmentContext},
+    linked_hash_map::LinkedHashMap,
+    starlark::{
+        environment::TypeValues,
+        values::{
+            error::{RuntimeError, ValueError},
+            none::NoneType,
+            Value, ValueResult,
+        },
+        {
+            starlark_fun, starlark_module, starlark_parse_param_type, starlark_signature,
+            starlark_signature_extraction, starlark_signatures,
+        },
+    },
+    starlark_dialect_build_targets::TargetObserver,
+    std::{
+        io::{BufRead, Write},
+        sync::Mutex,
+    },
+};
+
+const PROMPT: &str = "(starlark-debug) ";
+
+const HELP: &str = "\
+commands:
+  c, continue     resume evaluation until the next breakpoint
+  s, step         resume evaluation until the next breakpoint or target boundary
+  l, locals       list names of values available for inspection
+  p, print EXPR   print a value; EXPR is a name followed by optional .attribute parts
+  d, dir EXPR     print the attributes of a value
+  bt, where       print the stack of targets being resolved
+  q, quit         abort evaluation
+  h, help         print this help
+";
+
+fn debugger_error(message: impl ToString) -> ValueError {
+    ValueError::from(RuntimeError {
+        code: "PYOXIDIZER_DEBUGGER",
+        message: message.to_string(),
+        label: "breakpoint()".to_string(),
+    })
+}
+
+struct DebuggerState {
+    input: Box<dyn BufRead + Send>,
+    output: Box<dyn Write + Send>,
+
+    /// Whether to pause at the next target boundary.
+    stepping: bool,
+
+    /// Whether input is exhausted, in which case we no longer pause.
+    detached: bool,
+
+    /// Names of targets being resolved, outermost first.
+    target_stack: Vec<String>,
+}
+
+/// An interactive debugger for Starlark evaluation.
+pub struct StarlarkDebugger {
+    state: Mutex<DebuggerState>,
+}
+
+impl std::fmt::Debug for StarlarkDebugger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StarlarkDebugger").finish_non_exhaustive()
+    }
+}
+
+impl StarlarkDebugger {
+    /// Construct an instance reading commands from `input` and writing to `output`.
+    ///
+    /// The debugger initially steps, so it pauses before the first target
+    /// is resolved.
+    pub fn new(input: Box<dyn BufRead + Send>, output: Box<dyn Write + Send>) -> Self {
+        Self {
+            state: Mutex::new(DebuggerState {
+                input,
+                output,
+                stepping: true,
+                detached: false,
+                target_stack: vec![],
+            }),
+        }
+    }
+
+    /// Construct an instance interacting with the terminal.
+    ///
+    /// Output is written to stderr so it doesn't mix with build output.
+    pub fn stdio() -> Self {
+        Self::new(
+            Box::new(std::io::BufReader::new(std::io::stdin())),
+            Box::new(std::io::stderr()),
+        )
+    }
+
+    /// Pause evaluation at a breakpoint.
+    pub fn breakpoint(&self, values: &[(String, Value)]) -> Result<(), ValueError> {
+        let mut state = self.state.lock().expect("lock should not be poisoned");
+
+        let location = match state.target_stack.last() {
+            Some(target) => format!("breakpoint in target {}", target),
+            None => "breakpoint".to_string(),
+        };
+
+        Self::pause(&mut state, &location, values)
+    }
+
+    fn pause(
+        state: &mut DebuggerState,
+        location: &str,
+        values: &[(String, Value)],
+    ) -> Result<(), ValueError> {
+        if state.detached {
+            return Ok(());
+        }
+
+        Self::run_prompt(state, location, values).map_err(debugger_error)?
+    }
+
+    /// Read and execute commands until evaluation should resume.
+    ///
+    /// The outer result captures I/O errors. The inner result is returned
+    /// to Starlark.
+    fn run_prompt(
+        state: &mut DebuggerState,
+        location: &str,
+        values: &[(String, Value)],
+    ) -> std::io::Result<Result<(), ValueError>> {
+        writeln!(state.output, "{}", location)?;
+        Self::write_locals(state, values)?;
+
+        loop {
+            write!(state.output, "{}", PROMPT)?;
+            state.output.flush()?;
+
+            let mut line = String::new();
+            if state.input.read_line(&mut line)? == 0 {
+                writeln!(state.output)?;
+                writeln!(state.output, "end of input; detaching debugger")?;
+                state.detached = true;
+                return Ok(Ok(()));
+            }
+
+            let line = line.trim();
+            let (command, arg) = match line.split_once(char::is_whitespace) {
+                Some((command, arg)) => (command, arg.trim()),
+                None => (line, ""),
+            };
+
+            match command {
+                "" => {}
+                "c" | "continue" => {
+                    state.stepping = false;
+                    return Ok(Ok(()));
+                }
+                "s" | "step" => {
+                    state.stepping = true;
+                    return Ok(Ok(()));
+                }
+                "l" | "locals" => {
+                    Self::write_locals(state, values)?;
+                }
+                "p" | "print" => match Self::lookup(values, arg) {
+                    Ok(value) => {
+                        writeln!(state.output, "{} ({})", value.to_repr(), value.get_type())?;
+                    }
+                    Err(e) => {
+                        writeln!(state.output, "{}", e)?;
+                    }
+                },
+                "d" | "dir" => match Self::lookup(values, arg) {
+                    Ok(value) => {
+                        Self::write_attrs(state, &value)?;
+                    }
+                    Err(e) => {
+                        writeln!(state.output, "{}", e)?;
+                    }
+                },
+                "bt" | "where" => {
+                    if state.target_stack.is_empty() {
+                        writeln!(state.output, "no targets being resolved")?;
+                    }
+                    for (i, target) in state.target_stack.iter().enumerate() {
+                        writeln!(state.output, "{}{}", "  ".repeat(i), target)?;
+                    }
+                }
+                "q" | "quit" => {
+                    return Ok(Err(debugger_error("evaluation aborted by debugger")));
+                }
+                "h" | "help" => {
+                    write!(state.output, "{}", HELP)?;
+                }
+                _ => {
+                    writeln!(
+                        state.output,
+                        "unknown command {}; type help for a list of commands",
+                        command
+                    )?;
+                }
+            }
+        }
+    }
+
+    fn write_locals(state: &mut DebuggerState, values: &[(String, Value)]) -> std::io::Result<()> {
+        if values.is_empty() {
+            writeln!(state.output, "no values available for inspection")?;
+        }
+        for (name, value) in values {
+            writeln!(state.output, "  {}: {}", name, value.get_type())?;
+        }
+
+        Ok(())
+    }
+
+    fn write_attrs(state: &mut DebuggerState, value: &Value) -> std::io::Result<()> {
+        let attrs = value.dir_attr().unwrap_or_default();
+
+        if attrs.is_empty() {
+            writeln!(state.output, "{} has no attributes", value.get_type())?;
+        }
+        for attr in attrs {
+            match value.get_attr(&attr) {
+                Ok(v) => writeln!(state.output, "  {} = {}", attr, v.to_repr())?,
+                Err(_) => writeln!(state.output, "  {}", attr)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Resolve an expression of the form `name.attr.attr` against values.
+    fn lookup(values: &[(String, Value)], expr: &str) -> Result<Value, String> {
+        let mut parts = expr.split('.');
+        let name = parts.next().unwrap_or_default();
+
+        if name.is_empty() {
+            return Err("expected a value name".to_string());
+        }
+
+        let mut value = values
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.clone())
+            .ok_or_else(|| format!("no value named {}", name))?;
+
+        for attr in parts {
+            value = value
+                .get_attr(attr)
+                .map_err(|_| format!("{} has no attribute {}", value.get_type(), attr))?;
+        }
+
+        Ok(value)
+    }
+}
+
+impl TargetObserver for StarlarkDebugger {
+    fn before_resolve(&self, target: &str, depends: &[(String, Value)]) -> Result<(), ValueError> {
+        let mut state = self.state.lock().expect("lock should not be poisoned");
+        state.target_stack.push(target.to_string());
+
+        if state.stepping {
+            Self::pause(&mut state, &format!("resolving target {}", target), depends)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn after_resolve(&self, target: &str, value: &Value) -> Result<(), ValueError> {
+        let mut state = self.state.lock().expect("lock should not be poisoned");
+        state.target_stack.pop();
+
+        if state.stepping {
+            Self::pause(
+                &mut state,
+                &format!("resolved target {}", target),
+                &[("result".to_string(), value.clone())],
+            )
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// breakpoint(**kwargs)
+fn starlark_breakpoint(
+    type_values: &TypeValues,
+    kwargs: LinkedHashMap<String, Value>,
+) -> ValueResult {
+    let debugger = {
+        let pyoxidizer_context_value = get_context(type_values)?;
+        let pyoxidizer_context = pyoxidizer_context_value
+            .downcast_ref::<PyOxidizerEnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        pyoxidizer_context.debugger.clone()
+    };
+
+    if let Some(debugger) = debugger {
+        debugger.breakpoint(&kwargs.into_iter().collect::<Vec<_>>())?;
+    }
+
+    Ok(Value::new(NoneType::None))
+}
+
+starlark_module! { debugger_module =>
+    breakpoint(env env, **kwargs) {
+        starlark_breakpoint(env, kwargs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::starlark::testutil::*, anyhow::Result, std::sync::Arc};
+
+    /// A writer whose content can be inspected after it is moved into the debugger.
+    #[derive(Clone, Default)]
+    struct SharedOutput(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedOutput {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedOutput {
+        fn text(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    fn debugger(commands: &str) -> (Arc<StarlarkDebugger>, SharedOutput) {
+        let output = SharedOutput::default();
+
+        let debugger = Arc::new(StarlarkDebugger::new(
+            Box::new(std::io::Cursor::new(commands.as_bytes().to_vec())),
+            Box::new(output.clone()),
+        ));
+
+        (debugger, output)
+    }
+
+    #[test]
+    fn test_breakpoint_no_debugger() {
+        starlark_ok("breakpoint(x = 1)");
+    }
+
+    #[test]
+    fn test_breakpoint_inspect() -> Result<()> {
+        let (debugger, output) = debugger("l\np x\np y.missing\np z\nc\n");
+
+        let mut eval = test_evaluation_context_builder()?
+            .debugger(debugger)
+            .into_context()?;
+        eval.eval("breakpoint(x = 42, y = 'foo')")?;
+
+        let text = output.text();
+        assert!(text.starts_with("breakpoint\n"));
+        assert!(text.contains("  x: int\n  y: string\n"));
+        assert!(text.contains("42 (int)\n"));
+        assert!(text.contains("string has no attribute missing\n"));
+        assert!(text.contains("no value named z\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_breakpoint_quit() -> Result<()> {
+        let (debugger, _) = debugger("q\n");
+
+        let mut eval = test_evaluation_context_builder()?
+            .debugger(debugger)
+            .into_context()?;
+        assert!(eval.eval("breakpoint()").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_step_targets() -> Result<()> {
+        let (debugger, output) = debugger("s\np result\nbt\nc\n");
+
+        let mut eval = test_evaluation_context_builder()?
+            .debugger(debugger)
+            .into_context()?;
+        eval.eval("def foo(): return 'foo_value'")?;
+        eval.eval("def bar(foo): return foo")?;
+        eval.eval("register_target('foo', foo)")?;
+        eval.eval("register_target('bar', bar, depends = ['foo'])")?;
+        eval.eval("resolve_target('bar')")?;
+
+        let text = output.text();
+        assert!(text.contains("resolving target foo\n"));
+        assert!(text.contains("resolved target foo\n"));
+        assert!(text.contains("\"foo_value\" (string)\n"));
+        assert!(text.contains("no targets being resolved\n"));
+        assert!(!text.contains("resolving target bar\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_end_of_input_detaches() -> Result<()> {
+        let (debugger, output) = debugger("");
+
+        let mut eval = test_evaluation_context_builder()?
+            .debugger(debugger)
+            .into_context()?;
+        eval.eval("breakpoint()")?;
+        eval.eval("breakpoint()")?;
+
+        assert_eq!(output.text().matches("breakpoint\n").count(), 1);
+
+        Ok(())
+    }
+}
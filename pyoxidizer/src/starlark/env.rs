@@ -3,7 +3,7 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use {
-    crate::py_packaging::distribution::DistributionCache,
+    crate::{py_packaging::distribution::DistributionCache, starlark::debugger::StarlarkDebugger},
     anyhow::{Context, Result},
     starlark::{
         environment::{Environment, EnvironmentError, TypeValues},
@@ -15,9 +15,9 @@ use {
     },
     starlark_dialect_build_targets::{get_context_value, EnvironmentContext},
     std::{
-        collections::HashMap,
+        collections::{BTreeSet, HashMap},
         path::{Path, PathBuf},
-        sync::Arc,
+        sync::{Arc, Mutex},
     },
     tugger::starlark::TuggerContext,
 };
@@ -59,6 +59,30 @@ pub struct PyOxidizerEnvironmentContext {
 
     /// Extra variables to inject into Starlark environment.
     extra_vars: HashMap<String, Option<String>>,
+
+    /// Whether this evaluation only builds one architecture of a macOS universal binary.
+    ///
+    /// When set, executables don't attempt to build and merge other architectures.
+    pub apple_universal_binary_slice: bool,
+
+    /// Whether evaluating in development mode.
+    ///
+    /// When set, packages read from package roots are imported from their
+    /// source directories at run-time instead of being packaged.
+    pub dev: bool,
+
+    /// Whether built executables record the modules they import.
+    ///
+    /// When set, Python executables write the names of imported modules to
+    /// the directory defined by the `PYOXIDIZER_TRACE_IMPORTS_DIR` environment
+    /// variable.
+    pub trace_imports: bool,
+
+    /// Filesystem paths referenced by the configuration.
+    referenced_paths: Mutex<BTreeSet<PathBuf>>,
+
+    /// Debugger to pause evaluation at breakpoints and target boundaries.
+    pub debugger: Option<Arc<StarlarkDebugger>>,
 }
 
 impl PyOxidizerEnvironmentContext {
@@ -101,6 +125,11 @@ impl PyOxidizerEnvironmentContext {
             build_opt_level: build_opt_level.to_string(),
             distribution_cache,
             extra_vars,
+            apple_universal_binary_slice: false,
+            dev: false,
+            trace_imports: false,
+            referenced_paths: Mutex::new(BTreeSet::new()),
+            debugger: None,
         })
     }
 
@@ -108,6 +137,32 @@ impl PyOxidizerEnvironmentContext {
         &self.env
     }
 
+    /// Extra variables injected into the Starlark environment.
+    pub fn extra_vars(&self) -> &HashMap<String, Option<String>> {
+        &self.extra_vars
+    }
+
+    /// Record a filesystem path referenced by the configuration.
+    ///
+    /// Relative paths are resolved against [Self::cwd]. Referenced paths are
+    /// monitored for changes by `pyoxidizer build --watch`.
+    pub fn add_referenced_path(&self, path: impl AsRef<Path>) {
+        self.referenced_paths
+            .lock()
+            .expect("lock should not be poisoned")
+            .insert(self.cwd.join(path));
+    }
+
+    /// Filesystem paths referenced by the configuration.
+    pub fn referenced_paths(&self) -> Vec<PathBuf> {
+        self.referenced_paths
+            .lock()
+            .expect("lock should not be poisoned")
+            .iter()
+            .cloned()
+            .collect()
+    }
+
     pub fn build_path(&self, type_values: &TypeValues) -> Result<PathBuf, ValueError> {
         let build_targets_context_value = get_context_value(type_values)?;
         let context = build_targets_context_value
@@ -177,6 +232,7 @@ pub fn register_starlark_dialect(
 ) -> Result<(), EnvironmentError> {
     starlark_dialect_build_targets::register_starlark_dialect(env, type_values)?;
     tugger::starlark::register_starlark_dialect(env, type_values)?;
+    super::debugger::debugger_module(env, type_values);
     super::file_resource::file_resource_env(env, type_values);
     super::python_distribution::python_distribution_module(env, type_values);
     super::python_embedded_resources::python_embedded_resources_module(env, type_values);
@@ -201,15 +257,29 @@ pub fn populate_environment(
 
     build_targets_context.build_script_mode = build_script_mode;
 
-    build_targets_context.set_target_build_path_prefix(Some(
+    if let Some(debugger) = &context.debugger {
+        build_targets_context.set_target_observer(Some(debugger.clone()));
+    }
+
+    // Development and tracing builds reference the source checkout or record
+    // imports and shouldn't clobber regular build artifacts.
+    let mut build_path_prefix =
         PathBuf::from(&context.build_target_triple).join(if context.build_release {
             "release"
         } else {
             "debug"
-        }),
-    ));
+        });
+    if context.dev {
+        build_path_prefix = build_path_prefix.join("dev");
+    }
+    if context.trace_imports {
+        build_path_prefix = build_path_prefix.join("trace-imports");
+    }
+
+    build_targets_context.set_target_build_path_prefix(Some(build_path_prefix));
 
-    let tugger_context = TuggerContext::new();
+    let mut tugger_context = TuggerContext::new();
+    tugger_context.http_cache_dir = Some(context.env.cache_dir().join("http"));
 
     starlark_dialect_build_targets::populate_environment(env, type_values, build_targets_context)?;
     tugger::starlark::populate_environment(env, type_values, tugger_context)?;
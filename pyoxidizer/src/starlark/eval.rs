@@ -6,9 +6,12 @@ use {
     crate::{
         environment::default_target_triple,
         py_packaging::distribution::DistributionCache,
-        starlark::env::{
-            populate_environment, register_starlark_dialect, PyOxidizerContext,
-            PyOxidizerEnvironmentContext,
+        starlark::{
+            debugger::StarlarkDebugger,
+            env::{
+                populate_environment, register_starlark_dialect, PyOxidizerContext,
+                PyOxidizerEnvironmentContext,
+            },
         },
     },
     anyhow::{anyhow, Result},
@@ -46,6 +49,10 @@ pub struct EvaluationContextBuilder {
     build_opt_level: String,
     distribution_cache: Option<Arc<DistributionCache>>,
     extra_vars: HashMap<String, Option<String>>,
+    apple_universal_binary_slice: bool,
+    dev: bool,
+    trace_imports: bool,
+    debugger: Option<Arc<StarlarkDebugger>>,
 }
 
 impl EvaluationContextBuilder {
@@ -65,6 +72,10 @@ impl EvaluationContextBuilder {
             build_opt_level: "0".to_string(),
             distribution_cache: None,
             extra_vars: HashMap::new(),
+            apple_universal_binary_slice: false,
+            dev: false,
+            trace_imports: false,
+            debugger: None,
         }
     }
 
@@ -139,6 +150,37 @@ impl EvaluationContextBuilder {
         self.extra_vars = extra_vars;
         self
     }
+
+    /// Mark the evaluation as building a single architecture of a macOS universal binary.
+    #[must_use]
+    pub fn apple_universal_binary_slice(mut self, value: bool) -> Self {
+        self.apple_universal_binary_slice = value;
+        self
+    }
+
+    /// Evaluate in development mode.
+    ///
+    /// Packages read from package roots are imported from their source
+    /// directories instead of being packaged.
+    #[must_use]
+    pub fn dev(mut self, value: bool) -> Self {
+        self.dev = value;
+        self
+    }
+
+    /// Build executables that record the modules they import.
+    #[must_use]
+    pub fn trace_imports(mut self, value: bool) -> Self {
+        self.trace_imports = value;
+        self
+    }
+
+    /// Attach a debugger to the evaluation.
+    #[must_use]
+    pub fn debugger(mut self, debugger: Arc<StarlarkDebugger>) -> Self {
+        self.debugger = Some(debugger);
+        self
+    }
 }
 
 /// Interface to evaluate Starlark configuration files.
@@ -164,7 +206,7 @@ impl TryFrom<EvaluationContextBuilder> for EvaluationContext {
 
 impl EvaluationContext {
     pub fn from_builder(builder: EvaluationContextBuilder) -> Result<Self> {
-        let context = PyOxidizerEnvironmentContext::new(
+        let mut context = PyOxidizerEnvironmentContext::new(
             &builder.env,
             builder.verbose,
             &builder.config_path,
@@ -175,6 +217,10 @@ impl EvaluationContext {
             builder.distribution_cache,
             builder.extra_vars,
         )?;
+        context.apple_universal_binary_slice = builder.apple_universal_binary_slice;
+        context.dev = builder.dev;
+        context.trace_imports = builder.trace_imports;
+        context.debugger = builder.debugger;
 
         let (mut parent_env, mut type_values) = starlark::stdlib::global_environment();
 
@@ -319,6 +365,16 @@ impl EvaluationContext {
         pyoxidizer_context.build_path(&self.type_values)
     }
 
+    /// Filesystem paths referenced by the evaluated configuration.
+    pub fn referenced_paths(&self) -> Result<Vec<PathBuf>, ValueError> {
+        let pyoxidizer_context_value = self.pyoxidizer_context_value()?;
+        let pyoxidizer_context = pyoxidizer_context_value
+            .downcast_ref::<PyOxidizerEnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        Ok(pyoxidizer_context.referenced_paths())
+    }
+
     pub fn target_build_path(&self, target: &str) -> Result<PathBuf> {
         let context_value = self.build_targets_context_value()?;
         let context = context_value.downcast_ref::<EnvironmentContext>().unwrap();
@@ -348,6 +404,26 @@ impl EvaluationContext {
             .collect::<Vec<_>>())
     }
 
+    /// Names of targets registered as tests, in registration order.
+    pub fn test_targets(&self) -> Result<Vec<String>> {
+        let raw_context = self.build_targets_context_value()?;
+        let context = raw_context
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or_else(|| anyhow!("context has incorrect type"))?;
+
+        Ok(context.test_targets().clone())
+    }
+
+    /// Resolve a registered target by calling its function.
+    ///
+    /// Targets it depends on are resolved first. Nothing is built.
+    pub fn resolve_target(&mut self, target: &str) -> Result<Value> {
+        self.eval(&format!(
+            "resolve_target({})",
+            Value::from(target).to_repr()
+        ))
+    }
+
     /// Obtain targets that should be resolved.
     pub fn targets_to_resolve(&self) -> Result<Vec<String>> {
         let raw_context = self.build_targets_context_value()?;
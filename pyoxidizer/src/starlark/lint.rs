@@ -0,0 +1,335 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*!
+Static checks of Starlark configuration files.
+
+Evaluation only reports problems in code that actually runs. The checks in
+this module scan the source of a configuration file so problems in branches
+and targets that weren't evaluated are reported too.
+*/
+
+/// Attributes and methods that no longer exist, with advice on what to use instead.
+const REMOVED_ATTRIBUTES: &[(&str, &str)] = &[
+    ("add_module_source", "use add_python_resource()"),
+    ("add_module_bytecode", "use add_python_resource()"),
+    ("add_package_resource", "use add_python_resource()"),
+    (
+        "add_package_distribution_resource",
+        "use add_python_resource()",
+    ),
+    ("add_extension_module", "use add_python_resource()"),
+    ("add_python_module_source", "use add_python_resource()"),
+    ("add_python_module_bytecode", "use add_python_resource()"),
+    ("add_python_package_resource", "use add_python_resource()"),
+    (
+        "add_python_package_distribution_resource",
+        "use add_python_resource()",
+    ),
+    ("add_python_extension_module", "use add_python_resource()"),
+    (
+        "add_in_memory_module_source",
+        "set add_location on the resource and use add_python_resource()",
+    ),
+    (
+        "add_filesystem_relative_module_source",
+        "set add_location on the resource and use add_python_resource()",
+    ),
+    (
+        "add_in_memory_module_bytecode",
+        "set add_location on the resource and use add_python_resource()",
+    ),
+    (
+        "add_filesystem_relative_module_bytecode",
+        "set add_location on the resource and use add_python_resource()",
+    ),
+    (
+        "add_in_memory_package_resource",
+        "set add_location on the resource and use add_python_resource()",
+    ),
+    (
+        "add_filesystem_relative_package_resource",
+        "set add_location on the resource and use add_python_resource()",
+    ),
+    (
+        "add_in_memory_package_distribution_resource",
+        "set add_location on the resource and use add_python_resource()",
+    ),
+    (
+        "add_filesystem_relative_package_distribution_resource",
+        "set add_location on the resource and use add_python_resource()",
+    ),
+    (
+        "add_in_memory_extension_module",
+        "set add_location on the resource and use add_python_resource()",
+    ),
+    (
+        "add_filesystem_relative_extension_module",
+        "set add_location on the resource and use add_python_resource()",
+    ),
+    (
+        "add_in_memory_python_resource",
+        "set add_location on the resource and use add_python_resource()",
+    ),
+    (
+        "add_filesystem_relative_python_resource",
+        "set add_location on the resource and use add_python_resource()",
+    ),
+    (
+        "add_in_memory_python_resources",
+        "set add_location on resources and use add_python_resources()",
+    ),
+    (
+        "add_filesystem_relative_python_resources",
+        "set add_location on resources and use add_python_resources()",
+    ),
+    ("to_embedded_data", "use to_embedded_resources()"),
+    ("resources_data", "use python_resources()"),
+    ("package_resources", "use python_resources()"),
+    ("source_modules", "use python_resources()"),
+    ("extension_modules", "use python_resources()"),
+    ("raw_allocator", "use allocator_backend"),
+    (
+        "run_mode",
+        "set one of the run_* attributes or leave them all None to start a REPL",
+    ),
+    (
+        "show_alloc_count",
+        "this setting is not supported by Python 3.9+",
+    ),
+];
+
+/// Global symbols that no longer exist, with advice on what to use instead.
+const REMOVED_GLOBALS: &[(&str, &str)] = &[
+    (
+        "PythonBytecodeModule",
+        "set add_bytecode_* attributes on PythonModuleSource",
+    ),
+    ("PythonSourceModule", "use PythonModuleSource"),
+    ("PythonResourceData", "use PythonPackageResource"),
+    ("PythonEmbeddedData", "use PythonEmbeddedResources"),
+];
+
+/// Global functions that can no longer be called, with advice on what to use instead.
+const REMOVED_CALLS: &[(&str, &str)] = &[(
+    "PythonInterpreterConfig",
+    "use PythonDistribution.make_python_interpreter_config()",
+)];
+
+/// A problem found in a configuration file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LintFinding {
+    /// 1-based line number of the problem.
+    pub line: usize,
+
+    /// 1-based column number of the problem.
+    pub column: usize,
+
+    /// Description of the problem.
+    pub message: String,
+}
+
+impl std::fmt::Display for LintFinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+/// An identifier in Starlark source.
+struct Identifier {
+    name: String,
+    line: usize,
+    column: usize,
+    /// Whether the identifier is an attribute access (`x.name`).
+    attribute: bool,
+    /// Whether the identifier is followed by a call (`name(`).
+    called: bool,
+}
+
+/// Extract identifiers from Starlark source, ignoring strings and comments.
+fn identifiers(source: &str) -> Vec<Identifier> {
+    let chars = source.chars().collect::<Vec<_>>();
+    let mut res = vec![];
+
+    let mut i = 0;
+    let mut line = 1;
+    let mut column = 1;
+    let mut previous_significant = None;
+
+    let advance = |i: &mut usize, line: &mut usize, column: &mut usize| {
+        if chars[*i] == '\n' {
+            *line += 1;
+            *column = 1;
+        } else {
+            *column += 1;
+        }
+        *i += 1;
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '#' {
+            while i < chars.len() && chars[i] != '\n' {
+                advance(&mut i, &mut line, &mut column);
+            }
+        } else if c == '"' || c == '\'' {
+            let triple = i + 2 < chars.len() && chars[i + 1] == c && chars[i + 2] == c;
+            let delimiter_len = if triple { 3 } else { 1 };
+
+            for _ in 0..delimiter_len {
+                advance(&mut i, &mut line, &mut column);
+            }
+
+            while i < chars.len() {
+                if chars[i] == '\\' {
+                    advance(&mut i, &mut line, &mut column);
+                    if i < chars.len() {
+                        advance(&mut i, &mut line, &mut column);
+                    }
+                } else if chars[i] == c
+                    && (!triple || (i + 2 < chars.len() && chars[i + 1] == c && chars[i + 2] == c))
+                {
+                    for _ in 0..delimiter_len {
+                        advance(&mut i, &mut line, &mut column);
+                    }
+                    break;
+                } else if chars[i] == '\n' && !triple {
+                    break;
+                } else {
+                    advance(&mut i, &mut line, &mut column);
+                }
+            }
+
+            previous_significant = Some(c);
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let (start_line, start_column) = (line, column);
+            let mut name = String::new();
+
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                name.push(chars[i]);
+                advance(&mut i, &mut line, &mut column);
+            }
+
+            let mut next = i;
+            while next < chars.len() && (chars[next] == ' ' || chars[next] == '\t') {
+                next += 1;
+            }
+
+            res.push(Identifier {
+                name,
+                line: start_line,
+                column: start_column,
+                attribute: previous_significant == Some('.'),
+                called: next < chars.len() && chars[next] == '(',
+            });
+
+            previous_significant = Some('a');
+        } else if c.is_ascii_digit() {
+            // Consume numbers so a decimal point isn't mistaken for attribute access.
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                advance(&mut i, &mut line, &mut column);
+            }
+
+            previous_significant = Some('0');
+        } else {
+            if !c.is_whitespace() {
+                previous_significant = Some(c);
+            }
+            advance(&mut i, &mut line, &mut column);
+        }
+    }
+
+    res
+}
+
+/// Find references to attributes, methods, and global symbols that were removed.
+pub fn find_removed_api_references(source: &str) -> Vec<LintFinding> {
+    let lookup = |table: &[(&str, &str)], name: &str| {
+        table
+            .iter()
+            .find(|(removed, _)| *removed == name)
+            .map(|(_, advice)| advice.to_string())
+    };
+
+    identifiers(source)
+        .into_iter()
+        .filter_map(|ident| {
+            let message = if ident.attribute {
+                lookup(REMOVED_ATTRIBUTES, &ident.name)
+                    .map(|advice| format!("{} has been removed; {}", ident.name, advice))
+            } else if let Some(advice) = lookup(REMOVED_GLOBALS, &ident.name) {
+                Some(format!("{} has been removed; {}", ident.name, advice))
+            } else if ident.called {
+                lookup(REMOVED_CALLS, &ident.name)
+                    .map(|advice| format!("{}() has been removed; {}", ident.name, advice))
+            } else {
+                None
+            }?;
+
+            Some(LintFinding {
+                line: ident.line,
+                column: ident.column,
+                message,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_removed_attributes() {
+        let findings = find_removed_api_references(
+            "def make_exe():\n    exe.add_in_memory_module_source(m)\n    c.raw_allocator = 'system'\n",
+        );
+
+        assert_eq!(
+            findings,
+            vec![
+                LintFinding {
+                    line: 2,
+                    column: 9,
+                    message: "add_in_memory_module_source has been removed; set add_location on the resource and use add_python_resource()".to_string(),
+                },
+                LintFinding {
+                    line: 3,
+                    column: 7,
+                    message: "raw_allocator has been removed; use allocator_backend".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_removed_attribute_names_not_attributes() {
+        // A variable with the name of a removed attribute is fine.
+        assert!(find_removed_api_references("run_mode = 1\nprint(run_mode)").is_empty());
+    }
+
+    #[test]
+    fn test_ignores_strings_and_comments() {
+        assert!(find_removed_api_references(
+            "# exe.add_module_source()\nx = 'exe.raw_allocator'\ny = \"\"\"\nPythonSourceModule\n\"\"\"\n"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_removed_globals() {
+        let findings = find_removed_api_references(
+            "m = PythonSourceModule\nc = PythonInterpreterConfig()\nt = type(x) == 'PythonInterpreterConfig'\n",
+        );
+
+        assert_eq!(
+            findings
+                .iter()
+                .map(|f| (f.line, f.column))
+                .collect::<Vec<_>>(),
+            vec![(1, 5), (2, 5)]
+        );
+    }
+}
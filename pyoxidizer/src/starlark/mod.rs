@@ -8,10 +8,12 @@ The `starlark` module and related sub-modules define the
 define Oxidized Python binaries.
 */
 
+pub mod debugger;
 pub mod env;
 pub mod eval;
 pub mod file;
 pub mod file_resource;
+pub mod lint;
 pub mod python_distribution;
 pub mod python_embedded_resources;
 pub mod python_executable;
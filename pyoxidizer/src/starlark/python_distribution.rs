@@ -13,9 +13,10 @@ use {
     crate::py_packaging::{
         distribution::BinaryLibpythonLinkMode,
         distribution::{
-            default_distribution_location, DistributionFlavor, PythonDistribution,
-            PythonDistributionLocation,
+            default_distribution_location, local_distribution_location, DistributionFlavor,
+            PythonDistribution, PythonDistributionLocation,
         },
+        filtering::TRACE_IMPORTS_ENV,
     },
     anyhow::{anyhow, Result},
     log::{info, warn},
@@ -37,7 +38,7 @@ use {
         },
     },
     starlark_dialect_build_targets::{optional_str_arg, optional_type_arg},
-    std::{ops::Deref, sync::Arc},
+    std::{ops::Deref, path::Path, sync::Arc},
 };
 
 /// A Starlark Value wrapper for `PythonDistribution` traits.
@@ -154,27 +155,58 @@ impl PythonDistributionValue {
     }
 
     /// PythonDistribution()
-    fn from_args(sha256: String, local_path: &Value, url: &Value, flavor: String) -> ValueResult {
-        optional_str_arg("local_path", local_path)?;
-        optional_str_arg("url", url)?;
-
-        if local_path.get_type() != "NoneType" && url.get_type() != "NoneType" {
-            return Err(ValueError::from(RuntimeError {
-                code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
-                message: "cannot define both local_path and url".to_string(),
-                label: "cannot define both local_path and url".to_string(),
-            }));
-        }
+    fn from_args(
+        type_values: &TypeValues,
+        sha256: &Value,
+        local_path: &Value,
+        url: &Value,
+        flavor: String,
+    ) -> ValueResult {
+        let sha256 = optional_str_arg("sha256", sha256)?;
+        let local_path = optional_str_arg("local_path", local_path)?;
+        let url = optional_str_arg("url", url)?;
 
-        let distribution = if local_path.get_type() != "NoneType" {
-            PythonDistributionLocation::Local {
-                local_path: local_path.to_string(),
-                sha256,
+        let distribution = match (local_path, url) {
+            (Some(_), Some(_)) => {
+                return Err(ValueError::from(RuntimeError {
+                    code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                    message: "cannot define both local_path and url".to_string(),
+                    label: "cannot define both local_path and url".to_string(),
+                }));
             }
-        } else {
-            PythonDistributionLocation::Url {
-                url: url.to_string(),
-                sha256,
+            (Some(local_path), None) => {
+                let pyoxidizer_context_value = get_context(type_values)?;
+                let pyoxidizer_context = pyoxidizer_context_value
+                    .downcast_ref::<PyOxidizerEnvironmentContext>()
+                    .ok_or(ValueError::IncorrectParameterType)?;
+                pyoxidizer_context.add_referenced_path(&local_path);
+
+                local_distribution_location(Path::new(&local_path), sha256.as_deref()).map_err(
+                    |e| {
+                        ValueError::from(RuntimeError {
+                            code: "PYOXIDIZER_BUILD",
+                            message: format!("{:?}", e),
+                            label: "PythonDistribution()".to_string(),
+                        })
+                    },
+                )?
+            }
+            (None, Some(url)) => PythonDistributionLocation::Url {
+                url,
+                sha256: sha256.ok_or_else(|| {
+                    ValueError::from(RuntimeError {
+                        code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                        message: "sha256 must be defined when using url".to_string(),
+                        label: "PythonDistribution()".to_string(),
+                    })
+                })?,
+            },
+            (None, None) => {
+                return Err(ValueError::from(RuntimeError {
+                    code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                    message: "one of local_path or url must be defined".to_string(),
+                    label: "PythonDistribution()".to_string(),
+                }));
             }
         };
 
@@ -318,6 +350,12 @@ impl PythonDistributionValue {
             Some(host_dist.clone_trait())
         };
 
+        let mut config = config.inner(LABEL)?.deref().clone();
+
+        if pyoxidizer_context.trace_imports {
+            config.write_modules_directory_env = Some(TRACE_IMPORTS_ENV.to_string());
+        }
+
         let mut builder = dist
             .as_python_executable_builder(
                 &pyoxidizer_context.build_host_triple,
@@ -326,7 +364,7 @@ impl PythonDistributionValue {
                 // TODO make configurable
                 BinaryLibpythonLinkMode::Default,
                 policy.inner(LABEL)?.deref(),
-                config.inner(LABEL)?.deref(),
+                &config,
                 host_distribution,
             )
             .map_err(|e| {
@@ -417,8 +455,14 @@ impl PythonDistributionValue {
 
 starlark_module! { python_distribution_module =>
     #[allow(non_snake_case)]
-    PythonDistribution(sha256: String, local_path=NoneType::None, url=NoneType::None, flavor: String = "standalone".to_string()) {
-        PythonDistributionValue::from_args(sha256, &local_path, &url, flavor)
+    PythonDistribution(
+        env env,
+        sha256=NoneType::None,
+        local_path=NoneType::None,
+        url=NoneType::None,
+        flavor: String = "standalone".to_string()
+    ) {
+        PythonDistributionValue::from_args(env, &sha256, &local_path, &url, flavor)
     }
 
     PythonDistribution.make_python_packaging_policy(env env, this) {
@@ -583,7 +627,13 @@ mod tests {
     #[test]
     fn test_python_distribution_no_args() {
         let err = starlark_nok("PythonDistribution()");
-        assert!(err.message.starts_with("Missing parameter sha256"));
+        assert_eq!(err.message, "one of local_path or url must be defined");
+    }
+
+    #[test]
+    fn test_python_distribution_url_no_sha256() {
+        let err = starlark_nok("PythonDistribution(url='some_url')");
+        assert_eq!(err.message, "sha256 must be defined when using url");
     }
 
     #[test]
@@ -618,6 +668,31 @@ mod tests {
         assert_eq!(x.source, wanted);
     }
 
+    #[test]
+    fn test_python_distribution_local_directory() -> Result<()> {
+        let temp_dir = tempfile::Builder::new()
+            .prefix("pyoxidizer-test")
+            .tempdir()?;
+        let path = temp_dir.path().display().to_string().replace('\\', "/");
+
+        let mut env = test_evaluation_context_builder()?.into_context()?;
+        let dist = env.eval(&format!("PythonDistribution(local_path='{}')", path))?;
+        let wanted = PythonDistributionLocation::Directory { path: path.clone() };
+
+        let x = dist.downcast_ref::<PythonDistributionValue>().unwrap();
+        assert_eq!(x.source, wanted);
+
+        let err = starlark_nok(&format!(
+            "PythonDistribution('sha256', local_path='{}')",
+            path
+        ));
+        assert!(err
+            .message
+            .contains("sha256 cannot be defined for distribution directory"));
+
+        Ok(())
+    }
+
     #[test]
     fn test_make_python_packaging_policy() {
         let policy = starlark_ok("default_python_distribution().make_python_packaging_policy()");
@@ -13,20 +13,34 @@ use {
         python_package_distribution_resource::PythonPackageDistributionResourceValue,
         python_package_resource::PythonPackageResourceValue,
         python_packaging_policy::PythonPackagingPolicyValue,
-        python_resource::{is_resource_starlark_compatible, python_resource_to_value},
+        python_resource::{
+            add_context_for_value, is_resource_starlark_compatible, python_resource_for_value,
+            python_resource_to_value,
+        },
         util::ToValue,
     },
     crate::{
         licensing::licenses_from_cargo_manifest,
         project_building::build_python_executable,
         py_packaging::binary::PythonBinaryBuilder,
-        py_packaging::binary::{PackedResourcesLoadMode, WindowsRuntimeDllsMode},
+        py_packaging::binary::{
+            windows_version_numeric, OpensslMode, PackedResourcesLoadMode, WindowsRuntimeDllsMode,
+            WINDOWS_MANIFEST_DPI_AWARENESS, WINDOWS_MANIFEST_EXECUTION_LEVELS,
+        },
+        py_packaging::embedding::LinkingAnnotation,
+        py_packaging::packed_resources_wheel::packed_resources_wheel_builder,
+        starlark::eval::EvaluationContextBuilder,
     },
     anyhow::{anyhow, Context, Result},
     linked_hash_map::LinkedHashMap,
     log::{info, warn},
-    python_packaging::resource::PythonModuleSource,
-    simple_file_manifest::FileData,
+    python_packaging::{
+        interpreter::RunMode as PythonRunMode,
+        location::ConcreteResourceLocation,
+        qt::{is_qt_resource, qt_resource_path, QtLayout},
+        resource::{PythonModuleSource, PythonResource},
+    },
+    simple_file_manifest::{FileData, FileEntry, FileManifest},
     starlark::{
         environment::TypeValues,
         eval::call_stack::CallStack,
@@ -44,9 +58,10 @@ use {
     },
     starlark_dialect_build_targets::{
         optional_dict_arg, optional_list_arg, optional_str_arg, optional_type_arg,
-        required_list_arg, ResolvedTarget, ResolvedTargetValue, RunMode, ToOptional,
+        required_list_arg, ResolvedTarget, ResolvedTargetValue, RunMode, ToOptional, TryToOptional,
     },
     std::{
+        borrow::Cow,
         collections::HashMap,
         io::Write,
         ops::Deref,
@@ -54,15 +69,137 @@ use {
         sync::{Arc, Mutex, MutexGuard},
     },
     tugger::starlark::{
+        appimage_builder::AppImageBuilderValue,
         code_signing::{handle_signable_event, SigningAction, SigningContext},
+        debian_package_builder::DebianPackageBuilderValue,
         file_manifest::FileManifestValue,
+        macos_application_bundle_builder::MacOsApplicationBundleBuilderValue,
+        python_wheel_builder::PythonWheelBuilderValue,
+        rpm_package_builder::RpmPackageBuilderValue,
+        snapcraft::SnapcraftBuilderValue,
         wix_bundle_builder::WiXBundleBuilderValue,
         wix_msi_builder::WiXMsiBuilderValue,
     },
+    tugger_appimage::{target_triple_to_appimage_arch, AppImageBuilder},
+    tugger_apple::UniversalBinaryBuilder,
     tugger_code_signing::SigningDestination,
+    tugger_debian::{target_triple_to_debian_arch, ControlParagraph, DebBuilder},
+    tugger_rpm::{target_triple_to_rpm_arch, RpmBuilder},
+    tugger_snapcraft::{
+        Confinement, SnapApp, SnapPart, Snapcraft, SnapcraftBuilder, SnapcraftInvocation,
+    },
     tugger_wix::target_triple_to_wix_arch,
 };
 
+/// Snap base used by snaps derived from executables.
+const SNAP_BASE: &str = "core22";
+
+/// Directory in the snapcraft build environment holding executable files.
+const SNAP_SOURCE_DIRECTORY: &str = "app";
+
+/// Define a snap running an executable installed from [SNAP_SOURCE_DIRECTORY].
+///
+/// The snap has an app and a part named after the snap.
+fn snapcraft_for_executable(
+    name: String,
+    version: String,
+    summary: String,
+    description: String,
+    exe_name: String,
+    confinement: Confinement,
+    plugs: Vec<Cow<'static, str>>,
+) -> Snapcraft<'static> {
+    let mut snap = Snapcraft::new(
+        Cow::Owned(name.clone()),
+        Cow::Owned(version),
+        Cow::Owned(summary),
+        Cow::Owned(description),
+    );
+    snap.base = Some(Cow::Borrowed(SNAP_BASE));
+    snap.confinement = Some(confinement);
+    snap.apps.insert(
+        Cow::Owned(name.clone()),
+        SnapApp {
+            command: Some(Cow::Owned(exe_name)),
+            plugs,
+            ..SnapApp::default()
+        },
+    );
+    snap.parts.insert(
+        Cow::Owned(name),
+        SnapPart {
+            plugin: Some(Cow::Borrowed("dump")),
+            source: Some(Cow::Borrowed(SNAP_SOURCE_DIRECTORY)),
+            ..SnapPart::default()
+        },
+    );
+
+    snap
+}
+
+/// Define a `.deb` installing an executable's files under `/usr/lib/<package>`.
+///
+/// `manifest` holds the executable's files, already prefixed with that
+/// directory. A `/usr/bin/<package>` script launching the executable is added.
+fn deb_builder_for_executable(
+    package: &str,
+    version: &str,
+    architecture: &str,
+    maintainer: &str,
+    description: &str,
+    exe_name: &str,
+    manifest: &FileManifest,
+) -> Result<DebBuilder> {
+    let mut control = ControlParagraph::default();
+    control.set_field("Package", package);
+    control.set_field("Version", version);
+    control.set_field("Architecture", architecture);
+    control.set_field("Maintainer", maintainer);
+    control.set_field("Section", "misc");
+    control.set_field("Priority", "optional");
+    control.set_field("Description", description);
+
+    let mut builder = DebBuilder::new(control);
+    builder.install_manifest(manifest)?;
+
+    let launcher = format!("#!/bin/sh\nexec /usr/lib/{}/{} \"$@\"\n", package, exe_name);
+    builder.install_file(
+        format!("usr/bin/{}", package),
+        FileEntry::new_from_data(launcher.into_bytes(), true),
+    )?;
+
+    Ok(builder)
+}
+
+/// Define an `.rpm` installing an executable's files under `/usr/lib/<name>`.
+///
+/// `manifest` holds the executable's files, already prefixed with that
+/// directory. A `/usr/bin/<name>` script launching the executable is added.
+#[allow(clippy::too_many_arguments)]
+fn rpm_builder_for_executable(
+    name: &str,
+    version: &str,
+    release: &str,
+    architecture: &str,
+    summary: &str,
+    license: &str,
+    exe_name: &str,
+    manifest: &FileManifest,
+) -> Result<RpmBuilder> {
+    let mut builder = RpmBuilder::new(name, version, release, architecture);
+    builder.set_summary(summary);
+    builder.set_license(license);
+    builder.install_manifest(manifest)?;
+
+    let launcher = format!("#!/bin/sh\nexec /usr/lib/{}/{} \"$@\"\n", name, exe_name);
+    builder.install_file(
+        format!("usr/bin/{}", name),
+        FileEntry::new_from_data(launcher.into_bytes(), true),
+    )?;
+
+    Ok(builder)
+}
+
 fn error_context<F, T>(label: &str, f: F) -> Result<T, ValueError>
 where
     F: FnOnce() -> anyhow::Result<T>,
@@ -77,11 +214,21 @@ where
 }
 
 pub fn build_internal(
-    exe: MutexGuard<Box<dyn PythonBinaryBuilder>>,
+    mut exe: MutexGuard<Box<dyn PythonBinaryBuilder>>,
     type_values: &TypeValues,
     target: &str,
     context: &PyOxidizerEnvironmentContext,
 ) -> Result<(ResolvedTarget, PathBuf)> {
+    // Icon paths are relative to the config file.
+    if let Some(icon_path) = &exe.windows_resources().icon_path {
+        if icon_path.is_relative() {
+            let mut resources = exe.windows_resources().clone();
+            resources.icon_path = Some(context.cwd.join(icon_path));
+            context.add_referenced_path(icon_path);
+            exe.set_windows_resources(resources);
+        }
+    }
+
     // Build an executable by writing out a temporary Rust project
     // and building it.
     let build = build_python_executable(
@@ -119,6 +266,131 @@ pub fn build_internal(
     ))
 }
 
+/// Format a string as a Python string literal.
+fn python_string_literal(value: &str) -> String {
+    format!(
+        "'{}'",
+        value
+            .replace('\\', "\\\\")
+            .replace('\'', "\\'")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r")
+    )
+}
+
+/// Resolve the Python code that runs tests with a test runner.
+///
+/// Arguments on the command line of the built binary are appended to `args`.
+pub fn test_runner_code(runner: &str, args: &[String]) -> Result<String> {
+    let args = args
+        .iter()
+        .map(|x| python_string_literal(x))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    match runner {
+        // unittest.main() calls sys.exit() itself. Without arguments it attempts
+        // test discovery, which requires test files on the filesystem.
+        "unittest" => Ok(format!(
+            "import sys, unittest; unittest.main(module=None, argv=sys.argv[:1] + [{}] + sys.argv[1:])",
+            args
+        )),
+        "pytest" => Ok(format!(
+            "import sys, pytest; sys.exit(pytest.main([{}] + sys.argv[1:]))",
+            args
+        )),
+        _ => Err(anyhow!(
+            "unknown test runner {}; must be unittest or pytest",
+            runner
+        )),
+    }
+}
+
+/// Resolve the target triple of the other architecture in a macOS universal binary.
+fn apple_universal_binary_partner_triple(target_triple: &str) -> Option<&'static str> {
+    match target_triple {
+        "aarch64-apple-darwin" => Some("x86_64-apple-darwin"),
+        "x86_64-apple-darwin" => Some("aarch64-apple-darwin"),
+        _ => None,
+    }
+}
+
+/// Build the other architecture of an executable and merge it into a universal binary.
+///
+/// The configuration file is evaluated again for the other architecture's target triple
+/// and `target` is built in that context. The resulting binary is merged with the one
+/// at `exe_path`, replacing it with a universal binary.
+fn build_apple_universal_binary(
+    context: &PyOxidizerEnvironmentContext,
+    target: &str,
+    exe_path: &Path,
+) -> Result<()> {
+    let partner_triple = apple_universal_binary_partner_triple(&context.build_target_triple)
+        .ok_or_else(|| {
+            anyhow!(
+                "universal binaries can only be built for macOS targets; got {}",
+                context.build_target_triple
+            )
+        })?;
+
+    warn!(
+        "building {} architecture of universal binary {}",
+        partner_triple,
+        exe_path.display()
+    );
+
+    let mut partner =
+        EvaluationContextBuilder::new(context.env(), context.config_path.clone(), partner_triple)
+            .release(context.build_release)
+            .verbose(context.verbose)
+            .extra_vars(context.extra_vars().clone())
+            .distribution_cache(context.distribution_cache.clone())
+            .resolve_target(target)
+            .apple_universal_binary_slice(true)
+            .into_context()
+            .with_context(|| format!("creating evaluation context for {}", partner_triple))?;
+
+    partner
+        .evaluate_file(&context.config_path)
+        .with_context(|| format!("evaluating config for {}", partner_triple))?;
+
+    let resolved = partner
+        .build_resolved_target(target)
+        .with_context(|| format!("building {} for {}", target, partner_triple))?;
+
+    let partner_path = match resolved.run_mode {
+        RunMode::Path { path } => path,
+        _ => {
+            return Err(anyhow!(
+                "building {} for {} did not produce an executable",
+                target,
+                partner_triple
+            ))
+        }
+    };
+
+    let mut builder = UniversalBinaryBuilder::default();
+    for path in [exe_path, partner_path.as_path()] {
+        builder
+            .add_binary(std::fs::read(path).with_context(|| format!("reading {}", path.display()))?)
+            .with_context(|| format!("adding {} to universal binary", path.display()))?;
+    }
+
+    let mut data = vec![];
+    builder
+        .write(&mut data)
+        .context("writing universal binary")?;
+
+    warn!("writing universal binary to {}", exe_path.display());
+    let mut fh =
+        std::fs::File::create(exe_path).context(format!("creating {}", exe_path.display()))?;
+    fh.write_all(&data)
+        .context(format!("writing {}", exe_path.display()))?;
+    simple_file_manifest::set_executable(&mut fh).context("making binary executable")?;
+
+    Ok(())
+}
+
 /// Represents a builder for a Python executable.
 pub struct PythonExecutableValue {
     // The non-Starlark API to construct the builder returns a Box<T> and it is
@@ -130,6 +402,9 @@ pub struct PythonExecutableValue {
     // values_for_descendant_check_and_freeze() without the borrow checker
     // complaining due to a temporary vec/array.
     policy: Vec<Value>,
+
+    /// Whether to build a universal binary containing all macOS architectures.
+    macos_universal_binary: bool,
 }
 
 impl PythonExecutableValue {
@@ -137,6 +412,7 @@ impl PythonExecutableValue {
         Self {
             exe: Arc::new(Mutex::new(exe)),
             policy: vec![Value::new(policy)],
+            macos_universal_binary: false,
         }
     }
 
@@ -176,18 +452,42 @@ impl TypedValue for PythonExecutableValue {
         let exe = self.inner(&format!("PythonExecutable.{}", attribute))?;
 
         match attribute {
+            "bolt" => Ok(Value::from(exe.bolt())),
+            "deterministic" => Ok(Value::from(exe.deterministic())),
             "licenses_filename" => Ok(exe.licenses_filename().to_value()),
+            "macos_universal_binary" => Ok(Value::from(self.macos_universal_binary)),
+            "openssl_mode" => Ok(Value::from(exe.openssl_mode().to_string())),
             "packed_resources_load_mode" => {
                 Ok(Value::from(exe.packed_resources_load_mode().to_string()))
             }
+            "pgo_training_args" => Ok(exe.pgo_training_args().map(|args| args.to_vec()).to_value()),
+            "sbom_filename" => Ok(exe.sbom_filename().to_value()),
+            "tcl_files_embedded" => Ok(Value::from(exe.tcl_files_embedded())),
             "tcl_files_path" => match exe.tcl_files_path() {
                 Some(value) => Ok(Value::from(value.to_string())),
                 None => Ok(Value::from(NoneType::None)),
             },
+            "terminfo_files_path" => match exe.terminfo_files_path() {
+                Some(value) => Ok(Value::from(value.to_string())),
+                None => Ok(Value::from(NoneType::None)),
+            },
             "windows_runtime_dlls_mode" => {
                 Ok(Value::from(exe.windows_runtime_dlls_mode().to_string()))
             }
             "windows_subsystem" => Ok(Value::from(exe.windows_subsystem())),
+            "windows_icon_path" => Ok(exe.windows_resources().icon_path.to_value()),
+            "windows_file_version" => Ok(exe.windows_resources().file_version.to_value()),
+            "windows_product_version" => Ok(exe.windows_resources().product_version.to_value()),
+            "windows_company_name" => Ok(exe.windows_resources().company_name.to_value()),
+            "windows_product_name" => Ok(exe.windows_resources().product_name.to_value()),
+            "windows_file_description" => Ok(exe.windows_resources().file_description.to_value()),
+            "windows_legal_copyright" => Ok(exe.windows_resources().legal_copyright.to_value()),
+            "windows_manifest_execution_level" => {
+                Ok(exe.windows_resources().manifest_execution_level.to_value())
+            }
+            "windows_manifest_dpi_awareness" => {
+                Ok(exe.windows_resources().manifest_dpi_awareness.to_value())
+            }
             _ => Err(ValueError::OperationNotSupported {
                 op: UnsupportedOperation::GetAttr(attribute.to_string()),
                 left: Self::TYPE.to_string(),
@@ -199,11 +499,28 @@ impl TypedValue for PythonExecutableValue {
     fn has_attr(&self, attribute: &str) -> Result<bool, ValueError> {
         Ok(matches!(
             attribute,
-            "licenses_filename"
+            "bolt"
+                | "deterministic"
+                | "licenses_filename"
+                | "macos_universal_binary"
+                | "openssl_mode"
                 | "packed_resources_load_mode"
+                | "pgo_training_args"
+                | "sbom_filename"
+                | "tcl_files_embedded"
                 | "tcl_files_path"
+                | "terminfo_files_path"
                 | "windows_runtime_dlls_mode"
                 | "windows_subsystem"
+                | "windows_icon_path"
+                | "windows_file_version"
+                | "windows_product_version"
+                | "windows_company_name"
+                | "windows_product_name"
+                | "windows_file_description"
+                | "windows_legal_copyright"
+                | "windows_manifest_execution_level"
+                | "windows_manifest_dpi_awareness"
         ))
     }
 
@@ -211,12 +528,60 @@ impl TypedValue for PythonExecutableValue {
         let mut exe = self.inner(&format!("PythonExecutable.{}", attribute))?;
 
         match attribute {
+            "bolt" => {
+                exe.set_bolt(value.to_bool());
+
+                Ok(())
+            }
+            "deterministic" => {
+                exe.set_deterministic(value.to_bool());
+
+                Ok(())
+            }
             "licenses_filename" => {
                 let value = optional_str_arg("licenses_filename", &value)?;
                 exe.set_licenses_filename(value);
 
                 Ok(())
             }
+            "macos_universal_binary" => {
+                let value = value.to_bool();
+
+                if value && apple_universal_binary_partner_triple(exe.target_triple()).is_none() {
+                    return Err(ValueError::from(RuntimeError {
+                        code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                        message: format!(
+                            "universal binaries can only be built for macOS targets; got {}",
+                            exe.target_triple()
+                        ),
+                        label: format!("{}.{}", Self::TYPE, attribute),
+                    }));
+                }
+
+                drop(exe);
+                self.macos_universal_binary = value;
+
+                Ok(())
+            }
+            "openssl_mode" => {
+                let mode = OpensslMode::try_from(value.to_string().as_str()).map_err(|e| {
+                    ValueError::from(RuntimeError {
+                        code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                        message: e,
+                        label: format!("{}.{}", Self::TYPE, attribute),
+                    })
+                })?;
+
+                exe.set_openssl_mode(mode).map_err(|e| {
+                    ValueError::from(RuntimeError {
+                        code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                        message: format!("{:?}", e),
+                        label: format!("{}.{}", Self::TYPE, attribute),
+                    })
+                })?;
+
+                Ok(())
+            }
             "packed_resources_load_mode" => {
                 exe.set_packed_resources_load_mode(
                     PackedResourcesLoadMode::try_from(value.to_string().as_str()).map_err(|e| {
@@ -230,11 +595,33 @@ impl TypedValue for PythonExecutableValue {
 
                 Ok(())
             }
+            "pgo_training_args" => {
+                optional_list_arg("pgo_training_args", "string", &value)?;
+                exe.set_pgo_training_args(value.try_to_optional()?);
+
+                Ok(())
+            }
+            "sbom_filename" => {
+                let value = optional_str_arg("sbom_filename", &value)?;
+                exe.set_sbom_filename(value);
+
+                Ok(())
+            }
+            "tcl_files_embedded" => {
+                exe.set_tcl_files_embedded(value.to_bool());
+
+                Ok(())
+            }
             "tcl_files_path" => {
                 exe.set_tcl_files_path(value.to_optional());
 
                 Ok(())
             }
+            "terminfo_files_path" => {
+                exe.set_terminfo_files_path(value.to_optional());
+
+                Ok(())
+            }
             "windows_runtime_dlls_mode" => {
                 exe.set_windows_runtime_dlls_mode(
                     WindowsRuntimeDllsMode::try_from(value.to_string().as_str()).map_err(|e| {
@@ -260,6 +647,74 @@ impl TypedValue for PythonExecutableValue {
 
                 Ok(())
             }
+            "windows_icon_path"
+            | "windows_file_version"
+            | "windows_product_version"
+            | "windows_company_name"
+            | "windows_product_name"
+            | "windows_file_description"
+            | "windows_legal_copyright"
+            | "windows_manifest_execution_level"
+            | "windows_manifest_dpi_awareness" => {
+                let value = optional_str_arg(attribute, &value)?;
+
+                let invalid_value = |message: String| {
+                    ValueError::from(RuntimeError {
+                        code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                        message,
+                        label: format!("{}.{}", Self::TYPE, attribute),
+                    })
+                };
+
+                if let Some(value) = &value {
+                    match attribute {
+                        "windows_file_version" | "windows_product_version" => {
+                            windows_version_numeric(value)
+                                .map_err(|e| invalid_value(e.to_string()))?;
+                        }
+                        "windows_manifest_execution_level"
+                            if !WINDOWS_MANIFEST_EXECUTION_LEVELS.contains(&value.as_str()) =>
+                        {
+                            return Err(invalid_value(format!(
+                                "{} is not a valid execution level; must be one of {}",
+                                value,
+                                WINDOWS_MANIFEST_EXECUTION_LEVELS.join(", ")
+                            )));
+                        }
+                        "windows_manifest_dpi_awareness"
+                            if !WINDOWS_MANIFEST_DPI_AWARENESS.contains(&value.as_str()) =>
+                        {
+                            return Err(invalid_value(format!(
+                                "{} is not a valid DPI awareness; must be one of {}",
+                                value,
+                                WINDOWS_MANIFEST_DPI_AWARENESS.join(", ")
+                            )));
+                        }
+                        _ => {}
+                    }
+                }
+
+                let mut resources = exe.windows_resources().clone();
+
+                match attribute {
+                    "windows_icon_path" => resources.icon_path = value.map(PathBuf::from),
+                    "windows_file_version" => resources.file_version = value,
+                    "windows_product_version" => resources.product_version = value,
+                    "windows_company_name" => resources.company_name = value,
+                    "windows_product_name" => resources.product_name = value,
+                    "windows_file_description" => resources.file_description = value,
+                    "windows_legal_copyright" => resources.legal_copyright = value,
+                    "windows_manifest_execution_level" => {
+                        resources.manifest_execution_level = value
+                    }
+                    "windows_manifest_dpi_awareness" => resources.manifest_dpi_awareness = value,
+                    _ => unreachable!(),
+                }
+
+                exe.set_windows_resources(resources);
+
+                Ok(())
+            }
             _ => Err(ValueError::OperationNotSupported {
                 op: UnsupportedOperation::SetAttr(attribute.to_string()),
                 left: Self::TYPE.to_string(),
@@ -290,6 +745,12 @@ impl PythonExecutableValue {
             build_internal(exe, type_values, &target, &pyoxidizer_context)
         })?;
 
+        if self.macos_universal_binary && !pyoxidizer_context.apple_universal_binary_slice {
+            error_context(LABEL, || {
+                build_apple_universal_binary(&pyoxidizer_context, &target, &exe_path)
+            })?;
+        }
+
         let candidate = exe_path.clone().into();
         let mut context = SigningContext::new(
             "PythonExecutable.build()",
@@ -343,16 +804,19 @@ impl PythonExecutableValue {
         Ok(Value::new(value))
     }
 
-    /// PythonExecutable.pip_download(args)
+    /// PythonExecutable.pip_download(args, requirements_path=None, build_from_source=False)
     pub fn pip_download(
         &mut self,
         type_values: &TypeValues,
         call_stack: &mut CallStack,
         args: &Value,
+        requirements_path: &Value,
+        build_from_source: bool,
     ) -> ValueResult {
         const LABEL: &str = "PythonExecutable.pip_download()";
 
         required_list_arg("args", "string", args)?;
+        let requirements_path = optional_str_arg("requirements_path", requirements_path)?;
 
         let args: Vec<String> = args.iter()?.iter().map(|x| x.to_string()).collect();
 
@@ -361,12 +825,24 @@ impl PythonExecutableValue {
             .downcast_ref::<PyOxidizerEnvironmentContext>()
             .ok_or(ValueError::IncorrectParameterType)?;
 
+        let requirements_path =
+            requirements_path.map(|p| PathBuf::from(&pyoxidizer_context.cwd).join(p));
+        if let Some(path) = &requirements_path {
+            pyoxidizer_context.add_referenced_path(path);
+        }
+
         let python_packaging_policy = self.python_packaging_policy();
 
         let mut exe = self.inner(LABEL)?;
 
         let resources = error_context("PythonExecutable.pip_download()", || {
-            exe.pip_download(pyoxidizer_context.env(), pyoxidizer_context.verbose, &args)
+            exe.pip_download(
+                pyoxidizer_context.env(),
+                pyoxidizer_context.verbose,
+                &args,
+                requirements_path.as_deref(),
+                build_from_source,
+            )
         })?;
 
         let resources = resources
@@ -386,18 +862,20 @@ impl PythonExecutableValue {
         Ok(Value::from(resources))
     }
 
-    /// PythonExecutable.pip_install(args, extra_envs=None)
+    /// PythonExecutable.pip_install(args, extra_envs=None, requirements_path=None)
     pub fn pip_install(
         &mut self,
         type_values: &TypeValues,
         call_stack: &mut CallStack,
         args: &Value,
         extra_envs: &Value,
+        requirements_path: &Value,
     ) -> ValueResult {
         const LABEL: &str = "PythonExecutable.pip_install()";
 
         required_list_arg("args", "string", args)?;
         optional_dict_arg("extra_envs", "string", "string", extra_envs)?;
+        let requirements_path = optional_str_arg("requirements_path", requirements_path)?;
 
         let args: Vec<String> = args.iter()?.iter().map(|x| x.to_string()).collect();
 
@@ -420,6 +898,12 @@ impl PythonExecutableValue {
             .downcast_ref::<PyOxidizerEnvironmentContext>()
             .ok_or(ValueError::IncorrectParameterType)?;
 
+        let requirements_path =
+            requirements_path.map(|p| PathBuf::from(&pyoxidizer_context.cwd).join(p));
+        if let Some(path) = &requirements_path {
+            pyoxidizer_context.add_referenced_path(path);
+        }
+
         let python_packaging_policy = self.python_packaging_policy();
 
         let mut exe = self.inner(LABEL)?;
@@ -430,6 +914,7 @@ impl PythonExecutableValue {
                 pyoxidizer_context.verbose,
                 &args,
                 &extra_envs,
+                requirements_path.as_deref(),
             )
         })?;
 
@@ -468,10 +953,34 @@ impl PythonExecutableValue {
             .map(|x| x.to_string())
             .collect::<Vec<String>>();
 
+        let dev_path = {
+            let pyoxidizer_context_value = get_context(type_values)?;
+            let pyoxidizer_context = pyoxidizer_context_value
+                .downcast_ref::<PyOxidizerEnvironmentContext>()
+                .ok_or(ValueError::IncorrectParameterType)?;
+            pyoxidizer_context.add_referenced_path(&path);
+
+            if pyoxidizer_context.dev {
+                Some(pyoxidizer_context.cwd.join(&path))
+            } else {
+                None
+            }
+        };
+
         let python_packaging_policy = self.python_packaging_policy();
 
         let mut exe = self.inner(LABEL)?;
 
+        // In development mode packages are imported from the source checkout
+        // so edits don't require a rebuild.
+        if let Some(dev_path) = dev_path {
+            for package in &packages {
+                exe.add_package_source_path(package, &dev_path);
+            }
+
+            return Ok(Value::from(Vec::<Value>::new()));
+        }
+
         let resources =
             error_context(LABEL, || exe.read_package_root(Path::new(&path), &packages))?;
 
@@ -524,6 +1033,76 @@ impl PythonExecutableValue {
         Ok(Value::from(resources))
     }
 
+    /// PythonExecutable.build_wheel(package_path, extra_envs=None)
+    pub fn build_wheel(
+        &mut self,
+        type_values: &TypeValues,
+        call_stack: &mut CallStack,
+        package_path: String,
+        extra_envs: &Value,
+    ) -> ValueResult {
+        const LABEL: &str = "PythonExecutable.build_wheel()";
+
+        optional_dict_arg("extra_envs", "string", "string", extra_envs)?;
+
+        let extra_envs = match extra_envs.get_type() {
+            "dict" => extra_envs
+                .iter()?
+                .iter()
+                .map(|key| {
+                    let k = key.to_string();
+                    let v = extra_envs.at(key).unwrap().to_string();
+                    (k, v)
+                })
+                .collect(),
+            "NoneType" => HashMap::new(),
+            _ => panic!("should have validated type above"),
+        };
+
+        let package_path = PathBuf::from(package_path);
+
+        let pyoxidizer_context_value = get_context(type_values)?;
+        let pyoxidizer_context = pyoxidizer_context_value
+            .downcast_ref::<PyOxidizerEnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let package_path = if package_path.is_absolute() {
+            package_path
+        } else {
+            PathBuf::from(&pyoxidizer_context.cwd).join(package_path)
+        };
+        pyoxidizer_context.add_referenced_path(&package_path);
+
+        let python_packaging_policy = self.python_packaging_policy();
+
+        let mut exe = self.inner(LABEL)?;
+
+        let resources = error_context(LABEL, || {
+            exe.pep517_build_wheel(
+                pyoxidizer_context.env(),
+                &package_path,
+                pyoxidizer_context.verbose,
+                &extra_envs,
+            )
+        })?;
+
+        let resources = resources
+            .iter()
+            .filter(|r| is_resource_starlark_compatible(r))
+            .map(|r| {
+                python_resource_to_value(
+                    LABEL,
+                    type_values,
+                    call_stack,
+                    r,
+                    &python_packaging_policy,
+                )
+            })
+            .collect::<Result<Vec<Value>, ValueError>>()?;
+
+        Ok(Value::from(resources))
+    }
+
     /// PythonExecutable.setup_py_install(package_path, extra_envs=None, extra_global_arguments=None)
     pub fn setup_py_install(
         &mut self,
@@ -573,6 +1152,7 @@ impl PythonExecutableValue {
         } else {
             PathBuf::from(&pyoxidizer_context.cwd).join(package_path)
         };
+        pyoxidizer_context.add_referenced_path(&package_path);
 
         let python_packaging_policy = self.python_packaging_policy();
 
@@ -721,6 +1301,41 @@ impl PythonExecutableValue {
         Ok(Value::new(NoneType::None))
     }
 
+    /// PythonExecutable.add_data_files(namespace, manifest)
+    pub fn add_data_files(&mut self, namespace: String, manifest: &Value) -> ValueResult {
+        const LABEL: &str = "PythonExecutable.add_data_files()";
+
+        let manifest = match manifest.get_type() {
+            FileManifestValue::TYPE => manifest.downcast_ref::<FileManifestValue>().unwrap(),
+            _ => {
+                return Err(ValueError::from(RuntimeError {
+                    code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                    message: "manifest argument must be a FileManifest".to_string(),
+                    label: LABEL.to_string(),
+                }))
+            }
+        };
+
+        let manifest = manifest.inner(LABEL)?;
+
+        let mut exe = self.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            for file in manifest.iter_files() {
+                for action in exe
+                    .add_data_file(&namespace, &file)
+                    .with_context(|| format!("adding data file {}", file.path_string()))?
+                {
+                    info!("{}", action.to_string());
+                }
+            }
+
+            Ok(())
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
     /// PythonExecutable.add_python_resource(resource)
     pub fn add_python_resource(&mut self, resource: &Value, label: &str) -> ValueResult {
         match resource.get_type() {
@@ -767,6 +1382,152 @@ impl PythonExecutableValue {
         Ok(Value::new(NoneType::None))
     }
 
+    /// PythonExecutable.add_qt_resources(resources, path="lib")
+    pub fn add_qt_resources(&mut self, resources: &Value, path: String) -> ValueResult {
+        let label = "add_qt_resources()";
+
+        let mut qt_paths = vec![];
+
+        for resource in &resources.iter()? {
+            let python_resource = python_resource_for_value(&resource, label)?;
+
+            if !is_qt_resource(&python_resource) {
+                self.add_python_resource(&resource, label)?;
+                continue;
+            }
+
+            if let Some(qt_path) = qt_resource_path(&python_resource) {
+                qt_paths.push(qt_path);
+            }
+
+            let mut exe = self.inner(label)?;
+
+            // Qt locates its files relative to the bindings package. So the
+            // package and everything it depends on must be installed on the
+            // filesystem next to each other.
+            let mut add_context = match add_context_for_value(&resource, label)? {
+                Some(add_context) => add_context,
+                None => exe
+                    .python_packaging_policy()
+                    .derive_add_collection_context(&python_resource),
+            };
+            add_context.location = ConcreteResourceLocation::RelativePath(path.clone());
+            add_context.location_fallback = None;
+
+            error_context(label, || {
+                let actions = match &python_resource {
+                    PythonResource::ModuleSource(m) => {
+                        exe.add_python_module_source(m, Some(add_context))
+                    }
+                    PythonResource::PackageResource(r) => {
+                        exe.add_python_package_resource(r, Some(add_context))
+                    }
+                    PythonResource::PackageDistributionResource(r) => {
+                        exe.add_python_package_distribution_resource(r, Some(add_context))
+                    }
+                    PythonResource::ExtensionModule(em) => {
+                        exe.add_python_extension_module(em, Some(add_context))
+                    }
+                    PythonResource::File(f) => exe.add_file_data(f, Some(add_context)),
+                    _ => Err(anyhow!("unexpected resource type")),
+                }
+                .with_context(|| format!("adding {}", resource.to_repr()))?;
+
+                for action in actions {
+                    info!("{}", action.to_string());
+                }
+
+                Ok(())
+            })?;
+        }
+
+        let layout = QtLayout::from_paths(qt_paths.iter().map(|p| p.as_str()));
+
+        let mut exe = self.inner(label)?;
+        if let Some(plugins_path) = layout.plugins_path {
+            exe.set_qt_plugin_path(Some(format!("{}/{}", path, plugins_path)));
+        }
+        if let Some(qml_path) = layout.qml_path {
+            exe.set_qml_import_path(Some(format!("{}/{}", path, qml_path)));
+        }
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// PythonExecutable.add_link_search_path(path)
+    pub fn add_link_search_path(&mut self, type_values: &TypeValues, path: String) -> ValueResult {
+        const LABEL: &str = "PythonExecutable.add_link_search_path()";
+
+        let pyoxidizer_context_value = get_context(type_values)?;
+        let pyoxidizer_context = pyoxidizer_context_value
+            .downcast_ref::<PyOxidizerEnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let path = PathBuf::from(&pyoxidizer_context.cwd).join(path);
+
+        self.inner(LABEL)?
+            .add_linking_annotation(LinkingAnnotation::SearchNative(path));
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// PythonExecutable.add_link_library(name, kind="dylib")
+    pub fn add_link_library(&mut self, name: String, kind: String) -> ValueResult {
+        const LABEL: &str = "PythonExecutable.add_link_library()";
+
+        let mut exe = self.inner(LABEL)?;
+
+        let annotation = match kind.as_str() {
+            "dylib" => LinkingAnnotation::LinkLibrary(name),
+            "static" => LinkingAnnotation::LinkLibraryStatic(name),
+            "framework" => {
+                if !exe.target_triple().contains("-apple-") {
+                    return Err(ValueError::from(RuntimeError {
+                        code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                        message: format!(
+                            "frameworks can only be linked for Apple targets; got {}",
+                            exe.target_triple()
+                        ),
+                        label: LABEL.to_string(),
+                    }));
+                }
+
+                LinkingAnnotation::LinkFramework(name)
+            }
+            _ => {
+                return Err(ValueError::from(RuntimeError {
+                    code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                    message: format!(
+                        "kind must be one of `dylib`, `static`, or `framework`; got {}",
+                        kind
+                    ),
+                    label: LABEL.to_string(),
+                }))
+            }
+        };
+
+        exe.add_linking_annotation(annotation);
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// PythonExecutable.add_link_object_file(path)
+    pub fn add_link_object_file(&mut self, type_values: &TypeValues, path: String) -> ValueResult {
+        const LABEL: &str = "PythonExecutable.add_link_object_file()";
+
+        let pyoxidizer_context_value = get_context(type_values)?;
+        let pyoxidizer_context = pyoxidizer_context_value
+            .downcast_ref::<PyOxidizerEnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let path = PathBuf::from(&pyoxidizer_context.cwd).join(path);
+
+        self.inner(LABEL)?
+            .add_linking_annotation(LinkingAnnotation::Argument(path.display().to_string()));
+
+        Ok(Value::new(NoneType::None))
+    }
+
     /// Add licensing information from a `Cargo.toml` manifest.
     pub fn add_cargo_manifest_licensing(
         &mut self,
@@ -815,38 +1576,142 @@ impl PythonExecutableValue {
         Ok(Value::new(NoneType::None))
     }
 
-    /// PythonExecutable.to_embedded_resources()
-    pub fn to_embedded_resources(&self) -> ValueResult {
-        const LABEL: &str = "PythonExecutable.to_embedded_resources()";
-
-        Ok(Value::new(PythonEmbeddedResourcesValue {
-            exe: self.inner(LABEL)?.clone_trait(),
-        }))
-    }
+    /// PythonExecutable.to_appimage_builder(name, architecture=None)
+    pub fn to_appimage_builder(
+        &self,
+        type_values: &TypeValues,
+        name: String,
+        architecture: &Value,
+    ) -> ValueResult {
+        const LABEL: &str = "PythonExecutable.to_appimage_builder()";
 
-    /// PythonExecutable.to_file_manifest(prefix)
-    pub fn to_file_manifest(&self, type_values: &TypeValues, prefix: String) -> ValueResult {
-        const LABEL: &str = "PythonExecutable.to_file_manifest()";
+        let architecture = optional_str_arg("architecture", architecture)?;
 
         let pyoxidizer_context_value = get_context(type_values)?;
         let pyoxidizer_context = pyoxidizer_context_value
             .downcast_ref::<PyOxidizerEnvironmentContext>()
             .ok_or(ValueError::IncorrectParameterType)?;
 
-        let manifest_value = FileManifestValue::new_from_args()?;
-        let mut manifest = manifest_value
-            .downcast_mut::<FileManifestValue>()
-            .unwrap()
-            .unwrap();
+        let architecture = error_context(LABEL, || {
+            if let Some(architecture) = architecture {
+                Ok(architecture)
+            } else {
+                target_triple_to_appimage_arch(&pyoxidizer_context.build_target_triple)
+                    .map(|arch| arch.to_string())
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "unable to resolve AppImage architecture for {}; specify `architecture`",
+                            pyoxidizer_context.build_target_triple
+                        )
+                    })
+            }
+        })?;
 
-        let exe = self.inner(LABEL)?;
+        let exe_name = self.inner(LABEL)?.name();
+        let prefix = format!("usr/lib/{}", name);
 
-        error_context(LABEL, || {
-            file_manifest_add_python_executable(
-                &mut manifest,
-                pyoxidizer_context.env(),
-                &prefix,
-                &**exe,
+        let manifest_value = self.to_file_manifest(type_values, prefix.clone())?;
+        let manifest = manifest_value.downcast_ref::<FileManifestValue>().unwrap();
+        let manifest = manifest.inner(LABEL)?;
+
+        let builder = error_context(LABEL, || {
+            let mut builder =
+                AppImageBuilder::new(&name, architecture, format!("{}/{}", prefix, exe_name));
+            builder.add_manifest(&manifest)?;
+
+            Ok(builder)
+        })?;
+
+        AppImageBuilderValue::new_from_builder(builder)
+    }
+
+    /// PythonExecutable.to_debian_package_builder(package, version, maintainer, description, architecture=None)
+    pub fn to_debian_package_builder(
+        &self,
+        type_values: &TypeValues,
+        package: String,
+        version: String,
+        maintainer: String,
+        description: String,
+        architecture: &Value,
+    ) -> ValueResult {
+        const LABEL: &str = "PythonExecutable.to_debian_package_builder()";
+
+        let architecture = optional_str_arg("architecture", architecture)?;
+
+        let pyoxidizer_context_value = get_context(type_values)?;
+        let pyoxidizer_context = pyoxidizer_context_value
+            .downcast_ref::<PyOxidizerEnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let architecture = error_context(LABEL, || {
+            if let Some(architecture) = architecture {
+                Ok(architecture)
+            } else {
+                target_triple_to_debian_arch(&pyoxidizer_context.build_target_triple)
+                    .map(|arch| arch.to_string())
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "unable to resolve Debian architecture for {}; specify `architecture`",
+                            pyoxidizer_context.build_target_triple
+                        )
+                    })
+            }
+        })?;
+
+        let exe_name = self.inner(LABEL)?.name();
+
+        let manifest_value = self.to_file_manifest(type_values, format!("usr/lib/{}", package))?;
+        let manifest = manifest_value.downcast_ref::<FileManifestValue>().unwrap();
+        let manifest = manifest.inner(LABEL)?;
+
+        let builder = error_context(LABEL, || {
+            deb_builder_for_executable(
+                &package,
+                &version,
+                &architecture,
+                &maintainer,
+                &description,
+                &exe_name,
+                &manifest,
+            )
+        })?;
+
+        DebianPackageBuilderValue::new_from_builder(builder)
+    }
+
+    /// PythonExecutable.to_embedded_resources()
+    pub fn to_embedded_resources(&self) -> ValueResult {
+        const LABEL: &str = "PythonExecutable.to_embedded_resources()";
+
+        Ok(Value::new(PythonEmbeddedResourcesValue {
+            exe: self.inner(LABEL)?.clone_trait(),
+        }))
+    }
+
+    /// PythonExecutable.to_file_manifest(prefix)
+    pub fn to_file_manifest(&self, type_values: &TypeValues, prefix: String) -> ValueResult {
+        const LABEL: &str = "PythonExecutable.to_file_manifest()";
+
+        let pyoxidizer_context_value = get_context(type_values)?;
+        let pyoxidizer_context = pyoxidizer_context_value
+            .downcast_ref::<PyOxidizerEnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let manifest_value = FileManifestValue::new_from_args()?;
+        let mut manifest = manifest_value
+            .downcast_mut::<FileManifestValue>()
+            .unwrap()
+            .unwrap();
+
+        let exe = self.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            file_manifest_add_python_executable(
+                &mut manifest,
+                pyoxidizer_context.env(),
+                &prefix,
+                &**exe,
                 &pyoxidizer_context.build_target_triple,
                 pyoxidizer_context.build_release,
                 &pyoxidizer_context.build_opt_level,
@@ -857,6 +1722,187 @@ impl PythonExecutableValue {
         Ok(manifest_value.clone())
     }
 
+    /// PythonExecutable.to_python_wheel_builder(distribution, version, install_on_startup=True)
+    pub fn to_python_wheel_builder(
+        &self,
+        type_values: &TypeValues,
+        distribution: String,
+        version: String,
+        install_on_startup: bool,
+    ) -> ValueResult {
+        const LABEL: &str = "PythonExecutable.to_python_wheel_builder()";
+
+        let pyoxidizer_context_value = get_context(type_values)?;
+        let pyoxidizer_context = pyoxidizer_context_value
+            .downcast_ref::<PyOxidizerEnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let exe = self.inner(LABEL)?;
+
+        let builder = error_context(LABEL, || {
+            packed_resources_wheel_builder(
+                &**exe,
+                pyoxidizer_context.env(),
+                &distribution,
+                &version,
+                install_on_startup,
+            )
+        })?;
+
+        PythonWheelBuilderValue::new_from_builder(builder)
+    }
+
+    /// PythonExecutable.to_rpm_package_builder(name, version, release, summary, license, architecture=None)
+    #[allow(clippy::too_many_arguments)]
+    pub fn to_rpm_package_builder(
+        &self,
+        type_values: &TypeValues,
+        name: String,
+        version: String,
+        release: String,
+        summary: String,
+        license: String,
+        architecture: &Value,
+    ) -> ValueResult {
+        const LABEL: &str = "PythonExecutable.to_rpm_package_builder()";
+
+        let architecture = optional_str_arg("architecture", architecture)?;
+
+        let pyoxidizer_context_value = get_context(type_values)?;
+        let pyoxidizer_context = pyoxidizer_context_value
+            .downcast_ref::<PyOxidizerEnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let architecture = error_context(LABEL, || {
+            if let Some(architecture) = architecture {
+                Ok(architecture)
+            } else {
+                target_triple_to_rpm_arch(&pyoxidizer_context.build_target_triple)
+                    .map(|arch| arch.to_string())
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "unable to resolve RPM architecture for {}; specify `architecture`",
+                            pyoxidizer_context.build_target_triple
+                        )
+                    })
+            }
+        })?;
+
+        let exe_name = self.inner(LABEL)?.name();
+
+        let manifest_value = self.to_file_manifest(type_values, format!("usr/lib/{}", name))?;
+        let manifest = manifest_value.downcast_ref::<FileManifestValue>().unwrap();
+        let manifest = manifest.inner(LABEL)?;
+
+        let builder = error_context(LABEL, || {
+            rpm_builder_for_executable(
+                &name,
+                &version,
+                &release,
+                &architecture,
+                &summary,
+                &license,
+                &exe_name,
+                &manifest,
+            )
+        })?;
+
+        RpmPackageBuilderValue::new_from_builder(builder)
+    }
+
+    /// PythonExecutable.to_snapcraft_builder(name, version, summary, description, confinement="strict", plugs=None)
+    #[allow(clippy::too_many_arguments)]
+    pub fn to_snapcraft_builder(
+        &self,
+        type_values: &TypeValues,
+        name: String,
+        version: String,
+        summary: String,
+        description: String,
+        confinement: String,
+        plugs: &Value,
+    ) -> ValueResult {
+        const LABEL: &str = "PythonExecutable.to_snapcraft_builder()";
+
+        optional_list_arg("plugs", "string", plugs)?;
+
+        let plugs = match plugs.get_type() {
+            "list" => plugs
+                .iter()?
+                .iter()
+                .map(|x| Cow::Owned(x.to_string()))
+                .collect::<Vec<_>>(),
+            _ => vec![],
+        };
+
+        let confinement = Confinement::try_from(confinement.as_str()).map_err(|_| {
+            ValueError::Runtime(RuntimeError {
+                code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                message: format!(
+                    "invalid confinement: {}; must be strict, devmode, or classic",
+                    confinement
+                ),
+                label: LABEL.to_string(),
+            })
+        })?;
+
+        let snap = snapcraft_for_executable(
+            name,
+            version,
+            summary,
+            description,
+            self.inner(LABEL)?.name(),
+            confinement,
+            plugs,
+        );
+
+        let manifest_value =
+            self.to_file_manifest(type_values, SNAP_SOURCE_DIRECTORY.to_string())?;
+        let manifest = manifest_value.downcast_ref::<FileManifestValue>().unwrap();
+        let manifest = manifest.inner(LABEL)?;
+
+        // Running `snapcraft` without arguments builds the snap.
+        let builder = error_context(LABEL, || {
+            SnapcraftBuilder::new(snap).install_manifest(&manifest)
+        })?
+        .add_invocation(SnapcraftInvocation {
+            args: vec![],
+            purge_build: true,
+        });
+
+        Ok(Value::new(SnapcraftBuilderValue { inner: builder }))
+    }
+
+    /// PythonExecutable.to_test_target(runner="unittest", args=None)
+    pub fn to_test_target(&self, runner: String, args: &Value) -> ValueResult {
+        const LABEL: &str = "PythonExecutable.to_test_target()";
+
+        optional_list_arg("args", "string", args)?;
+
+        let args: Vec<String> = match args.get_type() {
+            "list" => args.iter()?.iter().map(|x| x.to_string()).collect(),
+            _ => vec![],
+        };
+
+        if runner == "unittest" && args.is_empty() {
+            return Err(ValueError::Runtime(RuntimeError {
+                code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                message: "args must name the test modules to run with unittest".to_string(),
+                label: LABEL.to_string(),
+            }));
+        }
+
+        let code = error_context(LABEL, || test_runner_code(&runner, &args))?;
+
+        let mut exe = self.inner(LABEL)?.clone_box();
+        exe.set_run_modes(vec![PythonRunMode::Eval(code)]);
+
+        Ok(Value::new(PythonExecutableValue::new(
+            exe,
+            self.python_packaging_policy(),
+        )))
+    }
+
     /// PythonExecutable.to_wix_bundle_builder(id_prefix, name, version, manufacturer, msi_builder_callback)
     #[allow(clippy::too_many_arguments)]
     pub fn to_wix_bundle_builder(
@@ -919,6 +1965,9 @@ impl PythonExecutableValue {
             "x86_64-pc-windows-msvc" => {
                 bundle_builder.add_vc_redistributable(type_values, "x64".to_string())?;
             }
+            "aarch64-pc-windows-msvc" => {
+                bundle_builder.add_vc_redistributable(type_values, "arm64".to_string())?;
+            }
             _ => {}
         }
 
@@ -965,6 +2014,39 @@ impl PythonExecutableValue {
         Ok(builder_value.clone())
     }
 
+    /// PythonExecutable.to_macos_application_bundle_builder(bundle_name, identifier, version)
+    pub fn to_macos_application_bundle_builder(
+        &self,
+        type_values: &TypeValues,
+        bundle_name: String,
+        identifier: String,
+        version: String,
+    ) -> ValueResult {
+        const LABEL: &str = "PythonExecutable.to_macos_application_bundle_builder()";
+
+        let manifest_value = self.to_file_manifest(type_values, ".".to_string())?;
+        let manifest = manifest_value.downcast_ref::<FileManifestValue>().unwrap();
+
+        let exe_name = self.inner(LABEL)?.name();
+
+        let builder_value = MacOsApplicationBundleBuilderValue::new_from_args(bundle_name.clone())?;
+        let mut builder = builder_value
+            .downcast_mut::<MacOsApplicationBundleBuilderValue>()
+            .unwrap()
+            .unwrap();
+
+        builder.set_info_plist_required_keys(
+            bundle_name,
+            identifier,
+            version,
+            "????".to_string(),
+            exe_name,
+        )?;
+        builder.add_macos_manifest(manifest.deref().clone())?;
+
+        Ok(builder_value.clone())
+    }
+
     /// PythonExecutable.filter_resources_from_files(files=None, glob_files=None)
     pub fn filter_resources_from_files(
         &mut self,
@@ -1003,6 +2085,35 @@ impl PythonExecutableValue {
 
         Ok(Value::new(NoneType::None))
     }
+
+    /// PythonExecutable.prune_unused_modules(roots=None, allow=None)
+    pub fn prune_unused_modules(&mut self, roots: &Value, allow: &Value) -> ValueResult {
+        const LABEL: &str = "PythonExecutable.prune_unused_modules()";
+
+        optional_list_arg("roots", "string", roots)?;
+        optional_list_arg("allow", "string", allow)?;
+
+        let roots = match roots.get_type() {
+            "list" => roots.iter()?.iter().map(|x| x.to_string()).collect(),
+            "NoneType" => Vec::new(),
+            _ => panic!("type should have been validated above"),
+        };
+
+        let allow = match allow.get_type() {
+            "list" => allow.iter()?.iter().map(|x| x.to_string()).collect(),
+            "NoneType" => Vec::new(),
+            _ => panic!("type should have been validated above"),
+        };
+
+        let roots_refs = roots.iter().map(|x| x.as_ref()).collect::<Vec<&str>>();
+        let allow_refs = allow.iter().map(|x| x.as_ref()).collect::<Vec<&str>>();
+
+        let mut exe = self.inner(LABEL)?;
+
+        error_context(LABEL, || exe.prune_unused_modules(&roots_refs, &allow_refs))?;
+
+        Ok(Value::new(NoneType::None))
+    }
 }
 
 starlark_module! { python_executable_env =>
@@ -1027,10 +2138,12 @@ starlark_module! { python_executable_env =>
         env env,
         call_stack cs,
         this,
-        args
+        args,
+        requirements_path=NoneType::None,
+        build_from_source: bool = false
     ) {
         let mut this = this.downcast_mut::<PythonExecutableValue>().unwrap().unwrap();
-        this.pip_download(env, cs, &args)
+        this.pip_download(env, cs, &args, &requirements_path, build_from_source)
     }
 
     PythonExecutable.pip_install(
@@ -1038,10 +2151,11 @@ starlark_module! { python_executable_env =>
         call_stack cs,
         this,
         args,
-        extra_envs=NoneType::None
+        extra_envs=NoneType::None,
+        requirements_path=NoneType::None
     ) {
         let mut this = this.downcast_mut::<PythonExecutableValue>().unwrap().unwrap();
-        this.pip_install(env, cs, &args, &extra_envs)
+        this.pip_install(env, cs, &args, &extra_envs, &requirements_path)
     }
 
     PythonExecutable.read_package_root(
@@ -1065,6 +2179,17 @@ starlark_module! { python_executable_env =>
         this.read_virtualenv(env, cs, path)
     }
 
+    PythonExecutable.build_wheel(
+        env env,
+        call_stack cs,
+        this,
+        package_path: String,
+        extra_envs=NoneType::None
+    ) {
+        let mut this = this.downcast_mut::<PythonExecutableValue>().unwrap().unwrap();
+        this.build_wheel(env, cs, package_path, &extra_envs)
+    }
+
     PythonExecutable.setup_py_install(
         env env,
         call_stack cs,
@@ -1088,6 +2213,15 @@ starlark_module! { python_executable_env =>
         )
     }
 
+    PythonExecutable.add_data_files(
+        this,
+        namespace: String,
+        manifest
+    ) {
+        let mut this = this.downcast_mut::<PythonExecutableValue>().unwrap().unwrap();
+        this.add_data_files(namespace, &manifest)
+    }
+
     PythonExecutable.add_python_resources(
         this,
         resources
@@ -1098,6 +2232,30 @@ starlark_module! { python_executable_env =>
         )
     }
 
+    PythonExecutable.add_qt_resources(
+        this,
+        resources,
+        path: String = "lib".to_string()
+    ) {
+        let mut this = this.downcast_mut::<PythonExecutableValue>().unwrap().unwrap();
+        this.add_qt_resources(&resources, path)
+    }
+
+    PythonExecutable.add_link_search_path(env env, this, path: String) {
+        let mut this = this.downcast_mut::<PythonExecutableValue>().unwrap().unwrap();
+        this.add_link_search_path(env, path)
+    }
+
+    PythonExecutable.add_link_library(this, name: String, kind: String = "dylib".to_string()) {
+        let mut this = this.downcast_mut::<PythonExecutableValue>().unwrap().unwrap();
+        this.add_link_library(name, kind)
+    }
+
+    PythonExecutable.add_link_object_file(env env, this, path: String) {
+        let mut this = this.downcast_mut::<PythonExecutableValue>().unwrap().unwrap();
+        this.add_link_object_file(env, path)
+    }
+
     PythonExecutable.add_cargo_manifest_licensing(
         env env,
         this,
@@ -1118,16 +2276,96 @@ starlark_module! { python_executable_env =>
         this.filter_resources_from_files(&files, &glob_files)
     }
 
+    PythonExecutable.prune_unused_modules(
+        this,
+        roots=NoneType::None,
+        allow=NoneType::None)
+    {
+        let mut this = this.downcast_mut::<PythonExecutableValue>().unwrap().unwrap();
+        this.prune_unused_modules(&roots, &allow)
+    }
+
     PythonExecutable.to_embedded_resources(this) {
         let this = this.downcast_ref::<PythonExecutableValue>().unwrap();
         this.to_embedded_resources()
     }
 
+    PythonExecutable.to_appimage_builder(
+        env env,
+        this,
+        name: String,
+        architecture = NoneType::None
+    ) {
+        let this = this.downcast_ref::<PythonExecutableValue>().unwrap();
+        this.to_appimage_builder(env, name, &architecture)
+    }
+
+    PythonExecutable.to_debian_package_builder(
+        env env,
+        this,
+        package: String,
+        version: String,
+        maintainer: String,
+        description: String,
+        architecture = NoneType::None
+    ) {
+        let this = this.downcast_ref::<PythonExecutableValue>().unwrap();
+        this.to_debian_package_builder(env, package, version, maintainer, description, &architecture)
+    }
+
     PythonExecutable.to_file_manifest(env env, this, prefix: String) {
         let this = this.downcast_ref::<PythonExecutableValue>().unwrap();
         this.to_file_manifest(env, prefix)
     }
 
+    PythonExecutable.to_python_wheel_builder(
+        env env,
+        this,
+        distribution: String,
+        version: String,
+        install_on_startup: bool = true
+    ) {
+        let this = this.downcast_ref::<PythonExecutableValue>().unwrap();
+        this.to_python_wheel_builder(env, distribution, version, install_on_startup)
+    }
+
+    PythonExecutable.to_rpm_package_builder(
+        env env,
+        this,
+        name: String,
+        version: String,
+        release: String,
+        summary: String,
+        license: String,
+        architecture = NoneType::None
+    ) {
+        let this = this.downcast_ref::<PythonExecutableValue>().unwrap();
+        this.to_rpm_package_builder(env, name, version, release, summary, license, &architecture)
+    }
+
+    PythonExecutable.to_snapcraft_builder(
+        env env,
+        this,
+        name: String,
+        version: String,
+        summary: String,
+        description: String,
+        confinement: String = "strict".to_string(),
+        plugs = NoneType::None
+    ) {
+        let this = this.downcast_ref::<PythonExecutableValue>().unwrap();
+        this.to_snapcraft_builder(env, name, version, summary, description, confinement, &plugs)
+    }
+
+    PythonExecutable.to_test_target(
+        this,
+        runner: String = "unittest".to_string(),
+        args = NoneType::None
+    ) {
+        let this = this.downcast_ref::<PythonExecutableValue>().unwrap();
+        this.to_test_target(runner, &args)
+    }
+
     PythonExecutable.to_wix_bundle_builder(
         env env,
         call_stack cs,
@@ -1162,6 +2400,17 @@ starlark_module! { python_executable_env =>
         let this = this.downcast_ref::<PythonExecutableValue>().unwrap();
         this.to_wix_msi_builder(env, cs, id_prefix, product_name, product_version, product_manufacturer)
     }
+
+    PythonExecutable.to_macos_application_bundle_builder(
+        env env,
+        this,
+        bundle_name: String,
+        identifier: String,
+        version: String
+    ) {
+        let this = this.downcast_ref::<PythonExecutableValue>().unwrap();
+        this.to_macos_application_bundle_builder(env, bundle_name, identifier, version)
+    }
 }
 
 #[cfg(test)]
@@ -1170,6 +2419,7 @@ mod tests {
         super::super::testutil::*,
         super::*,
         crate::{python_distributions::PYTHON_DISTRIBUTIONS, testutil::*},
+        std::collections::{BTreeMap, BTreeSet},
     };
 
     #[test]
@@ -1306,18 +2556,67 @@ mod tests {
     }
 
     #[test]
-    fn test_read_package_root_simple() -> Result<()> {
+    fn test_build_wheel_flit() -> Result<()> {
         let temp_dir = get_env()?.temporary_directory("pyoxidizer-test")?;
 
         let root = temp_dir.path();
-        std::fs::create_dir(root.join("bar"))?;
-        let bar_init = root.join("bar").join("__init__.py");
-        std::fs::write(&bar_init, "# bar")?;
+        std::fs::write(
+            root.join("pyproject.toml"),
+            "[build-system]\n\
+            requires = [\"flit_core >=3.2,<4\"]\n\
+            build-backend = \"flit_core.buildapi\"\n\
+            \n\
+            [project]\n\
+            name = \"pep517_demo\"\n\
+            version = \"0.1\"\n\
+            description = \"demo\"\n",
+        )?;
+        std::fs::create_dir(root.join("pep517_demo"))?;
+        std::fs::write(root.join("pep517_demo").join("__init__.py"), "")?;
 
-        let foo_path = root.join("foo.py");
-        std::fs::write(&foo_path, "# foo")?;
+        let mut env = test_evaluation_context_builder()?.into_context()?;
 
-        let baz_path = root.join("baz.py");
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("policy = dist.make_python_packaging_policy()")?;
+        env.eval("policy.include_distribution_sources = False")?;
+        env.eval("exe = dist.to_python_executable('testapp', packaging_policy = policy)")?;
+
+        let resources = env.eval(&format!(
+            "exe.build_wheel('{}')",
+            root.display().to_string().replace('\\', "/")
+        ))?;
+        assert_eq!(resources.get_type(), "list");
+
+        let raw_it = resources.iter().unwrap();
+        assert!(raw_it.iter().any(|v| {
+            v.get_type() == PythonModuleSourceValue::TYPE
+                && v.downcast_ref::<PythonModuleSourceValue>()
+                    .unwrap()
+                    .inner("ignored")
+                    .unwrap()
+                    .m
+                    .name
+                    == "pep517_demo"
+        }));
+
+        temp_dir.close()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_package_root_simple() -> Result<()> {
+        let temp_dir = get_env()?.temporary_directory("pyoxidizer-test")?;
+
+        let root = temp_dir.path();
+        std::fs::create_dir(root.join("bar"))?;
+        let bar_init = root.join("bar").join("__init__.py");
+        std::fs::write(&bar_init, "# bar")?;
+
+        let foo_path = root.join("foo.py");
+        std::fs::write(&foo_path, "# foo")?;
+
+        let baz_path = root.join("baz.py");
         std::fs::write(&baz_path, "# baz")?;
 
         std::fs::create_dir(root.join("extra"))?;
@@ -1364,6 +2663,46 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_read_package_root_dev() -> Result<()> {
+        let temp_dir = get_env()?.temporary_directory("pyoxidizer-test")?;
+
+        let root = temp_dir.path();
+        std::fs::create_dir(root.join("bar"))?;
+        std::fs::write(root.join("bar").join("__init__.py"), "# bar")?;
+
+        let mut env = test_evaluation_context_builder()?
+            .dev(true)
+            .into_context()?;
+        add_exe(&mut env)?;
+
+        let resources = env.eval(&format!(
+            "exe.read_package_root(\"{}\", packages=['bar'])",
+            root.display().to_string().replace('\\', "/")
+        ))?;
+
+        assert_eq!(resources.get_type(), "list");
+        assert_eq!(resources.length().unwrap(), 0);
+
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutableValue>().unwrap();
+        let embedded = exe
+            .inner("ignored")
+            .unwrap()
+            .to_embedded_python_context(&get_env()?, "0")?;
+        assert_eq!(
+            embedded.config.package_source_paths,
+            BTreeMap::from([(
+                "bar".to_string(),
+                PathBuf::from(root.display().to_string().replace('\\', "/"))
+            )])
+        );
+
+        temp_dir.close()?;
+
+        Ok(())
+    }
+
     #[test]
     fn licenses_filename() -> Result<()> {
         let mut env = test_evaluation_context_builder()?.into_context()?;
@@ -1385,6 +2724,225 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn sbom_filename() -> Result<()> {
+        let mut env = test_evaluation_context_builder()?.into_context()?;
+        add_exe(&mut env)?;
+
+        let v = env.eval("exe.sbom_filename")?;
+        assert_eq!(v.get_type(), "NoneType");
+
+        env.eval("exe.sbom_filename = 'sbom.cdx.json'")?;
+        let v = env.eval("exe.sbom_filename")?;
+        assert_eq!(v.get_type(), "string");
+        assert_eq!(v.to_string(), "sbom.cdx.json");
+
+        env.eval("exe.sbom_filename = None")?;
+        let v = env.eval("exe.sbom_filename")?;
+        assert_eq!(v.get_type(), "NoneType");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deterministic() -> Result<()> {
+        let mut env = test_evaluation_context_builder()?.into_context()?;
+        add_exe(&mut env)?;
+
+        let v = env.eval("exe.deterministic")?;
+        assert_eq!(v.get_type(), "bool");
+        assert!(!v.to_bool());
+
+        env.eval("exe.deterministic = True")?;
+        let v = env.eval("exe.deterministic")?;
+        assert!(v.to_bool());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pgo() -> Result<()> {
+        let mut env = test_evaluation_context_builder()?.into_context()?;
+        add_exe(&mut env)?;
+
+        let v = env.eval("exe.pgo_training_args")?;
+        assert_eq!(v.get_type(), "NoneType");
+
+        let v = env.eval("exe.bolt")?;
+        assert_eq!(v.get_type(), "bool");
+        assert!(!v.to_bool());
+
+        env.eval("exe.pgo_training_args = ['-c', 'import json']")?;
+        let v = env.eval("exe.pgo_training_args")?;
+        assert_eq!(v.get_type(), "list");
+        assert_eq!(v.to_vec()?.len(), 2);
+        assert_eq!(v.at(Value::from(1))?.to_string(), "import json");
+
+        assert!(env.eval("exe.pgo_training_args = [1]").is_err());
+
+        env.eval("exe.bolt = True")?;
+        assert!(env.eval("exe.bolt")?.to_bool());
+
+        env.eval("exe.pgo_training_args = None")?;
+        let v = env.eval("exe.pgo_training_args")?;
+        assert_eq!(v.get_type(), "NoneType");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_python_wheel_builder() -> Result<()> {
+        let mut env = test_evaluation_context_builder()?.into_context()?;
+        add_exe(&mut env)?;
+
+        env.eval("wheel = exe.to_python_wheel_builder('my-app', '1.0')")?;
+
+        let v = env.eval("wheel")?;
+        assert_eq!(v.get_type(), "PythonWheelBuilder");
+
+        let v = env.eval("wheel.wheel_file_name")?;
+        assert!(v.to_string().starts_with("my_app-1.0-cp3"));
+
+        let v = env.eval("wheel.to_file_content()")?;
+        assert_eq!(v.get_type(), "FileContent");
+
+        assert!(env
+            .eval("exe.to_python_wheel_builder('my-app', '1.0', install_on_startup = False)")
+            .is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deb_builder_for_executable() -> Result<()> {
+        let mut manifest = FileManifest::default();
+        manifest.add_file_entry(
+            "usr/lib/myapp/testapp",
+            FileEntry::new_from_data(vec![42], true),
+        )?;
+
+        let builder = deb_builder_for_executable(
+            "myapp",
+            "1.0-1",
+            "amd64",
+            "Jane Doe <jane@example.com>",
+            "My app",
+            "testapp",
+            &manifest,
+        )?;
+
+        assert_eq!(builder.deb_file_name()?, "myapp_1.0-1_amd64.deb");
+        assert_eq!(builder.control().field("Section"), Some("misc"));
+
+        let launcher = builder
+            .install_files()
+            .get("usr/bin/myapp")
+            .ok_or_else(|| anyhow!("launcher not installed"))?;
+        assert!(launcher.is_executable());
+        assert_eq!(
+            launcher.resolve_content()?,
+            b"#!/bin/sh\nexec /usr/lib/myapp/testapp \"$@\"\n".to_vec()
+        );
+        assert!(builder
+            .install_files()
+            .get("usr/lib/myapp/testapp")
+            .is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_debian_package_builder_bad_args() -> Result<()> {
+        let mut env = test_evaluation_context_builder()?.into_context()?;
+        add_exe(&mut env)?;
+
+        assert!(env
+            .eval("exe.to_debian_package_builder('myapp', '1.0', 'me', 'd', architecture = 1)")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rpm_builder_for_executable() -> Result<()> {
+        let mut manifest = FileManifest::default();
+        manifest.add_file_entry(
+            "usr/lib/myapp/testapp",
+            FileEntry::new_from_data(vec![42], true),
+        )?;
+
+        let builder = rpm_builder_for_executable(
+            "myapp", "1.0", "1", "x86_64", "My app", "MPL-2.0", "testapp", &manifest,
+        )?;
+
+        assert_eq!(builder.rpm_file_name(), "myapp-1.0-1.x86_64.rpm");
+
+        let launcher = builder
+            .install_files()
+            .get("usr/bin/myapp")
+            .ok_or_else(|| anyhow!("launcher not installed"))?;
+        assert!(launcher.is_executable());
+        assert_eq!(
+            launcher.resolve_content()?,
+            b"#!/bin/sh\nexec /usr/lib/myapp/testapp \"$@\"\n".to_vec()
+        );
+
+        let mut data = vec![];
+        builder.write_rpm(&mut data)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_rpm_package_builder_bad_args() -> Result<()> {
+        let mut env = test_evaluation_context_builder()?.into_context()?;
+        add_exe(&mut env)?;
+
+        assert!(env
+            .eval("exe.to_rpm_package_builder('myapp', '1.0', '1', 's', 'MIT', architecture = 1)")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapcraft_for_executable() {
+        let snap = snapcraft_for_executable(
+            "myapp".into(),
+            "1.0".into(),
+            "summary".into(),
+            "description".into(),
+            "testapp".into(),
+            Confinement::Strict,
+            vec!["home".into(), "network".into()],
+        );
+
+        assert_eq!(snap.base, Some("core22".into()));
+        assert_eq!(snap.confinement, Some(Confinement::Strict));
+        assert_eq!(snap.apps["myapp"].command, Some("testapp".into()));
+        assert_eq!(
+            snap.apps["myapp"].plugs,
+            vec![Cow::Borrowed("home"), Cow::Borrowed("network")]
+        );
+        assert_eq!(snap.parts["myapp"].plugin, Some("dump".into()));
+        assert_eq!(snap.parts["myapp"].source, Some("app".into()));
+    }
+
+    #[test]
+    fn test_to_snapcraft_builder_bad_args() -> Result<()> {
+        let mut env = test_evaluation_context_builder()?.into_context()?;
+        add_exe(&mut env)?;
+
+        assert!(env
+            .eval("exe.to_snapcraft_builder('myapp', '1.0', 's', 'd', confinement = 'bogus')")
+            .is_err());
+        assert!(env
+            .eval("exe.to_snapcraft_builder('myapp', '1.0', 's', 'd', plugs = [1])")
+            .is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn test_windows_runtime_dlls_mode() -> Result<()> {
         let mut env = test_evaluation_context_builder()?.into_context()?;
@@ -1412,6 +2970,108 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_prune_unused_modules() -> Result<()> {
+        let mut env = test_evaluation_context_builder()?.into_context()?;
+        add_exe(&mut env)?;
+
+        env.eval("exe.add_python_resource(exe.make_python_module_source('myapp', 'import json'))")?;
+        env.eval("exe.add_python_resource(exe.make_python_module_source('unused', ''))")?;
+
+        // Nothing to start from.
+        assert!(env.eval("exe.prune_unused_modules()").is_err());
+
+        env.eval("exe.prune_unused_modules(roots=['myapp'], allow=['xml.dom'])")?;
+
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutableValue>().unwrap();
+        let inner = exe.inner("ignored").unwrap();
+
+        let names = inner
+            .iter_resources()
+            .map(|(name, _)| name.as_str())
+            .collect::<BTreeSet<_>>();
+
+        for name in [
+            "myapp",
+            "json",
+            "json.decoder",
+            "encodings.utf_8",
+            "runpy",
+            "xml",
+            "xml.dom.minidom",
+        ] {
+            assert!(names.contains(name), "{} retained", name);
+        }
+
+        for name in ["unused", "unittest", "xmlrpc.client"] {
+            assert!(!names.contains(name), "{} removed", name);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_data_files() -> Result<()> {
+        let mut env = test_evaluation_context_builder()?.into_context()?;
+        add_exe(&mut env)?;
+
+        env.eval("m = FileManifest()")?;
+        env.eval("c = FileContent(filename = 'model.bin', content = 'weights')")?;
+        env.eval("m.add_file(c, directory = 'en')")?;
+
+        assert!(env.eval("exe.add_data_files('models', None)").is_err());
+        assert!(env.eval("exe.add_data_files('a/b', m)").is_err());
+
+        env.eval("exe.add_data_files('models', m)")?;
+
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutableValue>().unwrap();
+        let inner = exe.inner("ignored").unwrap();
+
+        let resource = inner
+            .iter_resources()
+            .find_map(|(name, resource)| {
+                if name == "models/en/model.bin" {
+                    Some(resource)
+                } else {
+                    None
+                }
+            })
+            .unwrap();
+        assert_eq!(resource.data_namespace, Some("models".to_string()));
+        assert_eq!(
+            resource.file_data_embedded,
+            Some(FileData::Memory(b"weights".to_vec()))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_openssl_mode() -> Result<()> {
+        let mut env = test_evaluation_context_builder()?.into_context()?;
+        add_exe(&mut env)?;
+
+        let value = env.eval("exe.openssl_mode")?;
+        assert_eq!(value.get_type(), "string");
+        assert_eq!(value.to_string(), "static");
+
+        let value = env.eval("exe.openssl_mode = 'ca-bundle'; exe.openssl_mode")?;
+        assert_eq!(value.to_string(), "ca-bundle");
+
+        assert!(env.eval("exe.openssl_mode = 'bad'").is_err());
+
+        if !crate::environment::default_target_triple().contains("-linux-") {
+            assert!(env.eval("exe.openssl_mode = 'system'").is_err());
+        }
+
+        let value = env.eval("exe.openssl_mode = 'static'; exe.openssl_mode")?;
+        assert_eq!(value.to_string(), "static");
+
+        Ok(())
+    }
+
     #[test]
     fn test_packed_resources_load_mode() -> Result<()> {
         let mut env = test_evaluation_context_builder()?.into_context()?;
@@ -1445,6 +3105,81 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_windows_resources() -> Result<()> {
+        let mut env = test_evaluation_context_builder()?.into_context()?;
+        add_exe(&mut env)?;
+
+        for attr in [
+            "windows_icon_path",
+            "windows_file_version",
+            "windows_product_version",
+            "windows_company_name",
+            "windows_product_name",
+            "windows_file_description",
+            "windows_legal_copyright",
+            "windows_manifest_execution_level",
+            "windows_manifest_dpi_awareness",
+        ] {
+            let value = env.eval(&format!("exe.{}", attr))?;
+            assert_eq!(value.get_type(), "NoneType");
+        }
+
+        let value = env.eval("exe.windows_icon_path = 'app.ico'; exe.windows_icon_path")?;
+        assert_eq!(value.to_string(), "app.ico");
+
+        let value = env.eval("exe.windows_file_version = '1.2.3'; exe.windows_file_version")?;
+        assert_eq!(value.to_string(), "1.2.3");
+        assert!(env.eval("exe.windows_file_version = '1.2.x'").is_err());
+        assert!(env
+            .eval("exe.windows_product_version = '1.2.3.4.5'")
+            .is_err());
+
+        let value = env.eval("exe.windows_company_name = 'ACME'; exe.windows_company_name")?;
+        assert_eq!(value.to_string(), "ACME");
+
+        let value = env.eval(
+            "exe.windows_manifest_execution_level = 'requireAdministrator'; exe.windows_manifest_execution_level",
+        )?;
+        assert_eq!(value.to_string(), "requireAdministrator");
+        assert!(env
+            .eval("exe.windows_manifest_execution_level = 'root'")
+            .is_err());
+
+        let value = env.eval(
+            "exe.windows_manifest_dpi_awareness = 'permonitorv2'; exe.windows_manifest_dpi_awareness",
+        )?;
+        assert_eq!(value.to_string(), "permonitorv2");
+        assert!(env
+            .eval("exe.windows_manifest_dpi_awareness = 'high'")
+            .is_err());
+
+        let value = env.eval("exe.windows_company_name = None; exe.windows_company_name")?;
+        assert_eq!(value.get_type(), "NoneType");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_macos_universal_binary() -> Result<()> {
+        let mut env = test_evaluation_context_builder()?.into_context()?;
+        add_exe(&mut env)?;
+
+        let value = env.eval("exe.macos_universal_binary")?;
+        assert_eq!(value.get_type(), "bool");
+        assert!(!value.to_bool());
+
+        if crate::environment::default_target_triple().ends_with("-apple-darwin") {
+            let value =
+                env.eval("exe.macos_universal_binary = True; exe.macos_universal_binary")?;
+            assert!(value.to_bool());
+        } else {
+            assert!(env.eval("exe.macos_universal_binary = True").is_err());
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_tcl_files_path() -> Result<()> {
         let mut env = test_evaluation_context_builder()?.into_context()?;
@@ -1463,6 +3198,169 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_tcl_files_embedded() -> Result<()> {
+        let mut env = test_evaluation_context_builder()?.into_context()?;
+        add_exe(&mut env)?;
+
+        let value = env.eval("exe.tcl_files_embedded")?;
+        assert_eq!(value.get_type(), "bool");
+        assert!(!value.to_bool());
+
+        let value = env.eval("exe.tcl_files_embedded = True; exe.tcl_files_embedded")?;
+        assert!(value.to_bool());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_terminfo_files_path() -> Result<()> {
+        let mut env = test_evaluation_context_builder()?.into_context()?;
+        add_exe(&mut env)?;
+
+        let value = env.eval("exe.terminfo_files_path")?;
+        assert_eq!(value.get_type(), "NoneType");
+
+        let value = env.eval("exe.terminfo_files_path = 'terminfo'; exe.terminfo_files_path")?;
+        assert_eq!(value.to_string(), "terminfo");
+
+        let value = env.eval("exe.terminfo_files_path = None; exe.terminfo_files_path")?;
+        assert_eq!(value.get_type(), "NoneType");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_qt_resources() -> Result<()> {
+        let mut env = test_evaluation_context_builder()?.into_context()?;
+        add_exe(&mut env)?;
+
+        env.eval("qt = exe.make_python_module_source('PySide6', '', is_package=True)")?;
+        env.eval("other = exe.make_python_module_source('foo', '')")?;
+        env.eval("exe.add_qt_resources([qt, other], path='qt')")?;
+
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutableValue>().unwrap();
+        let inner = exe.inner("ignored").unwrap();
+
+        let resources = inner.iter_resources().collect::<HashMap<_, _>>();
+
+        let qt = resources.get(&"PySide6".to_string()).unwrap();
+        assert!(qt.in_memory_bytecode.is_none());
+        assert_eq!(qt.relative_path_bytecode.as_ref().unwrap().0, "qt");
+
+        let other = resources.get(&"foo".to_string()).unwrap();
+        assert!(other.in_memory_bytecode.is_some());
+        assert!(other.relative_path_bytecode.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_link_library() -> Result<()> {
+        let mut env = test_evaluation_context_builder()?.into_context()?;
+        add_exe(&mut env)?;
+
+        env.eval("exe.add_link_library('foo')")?;
+        env.eval("exe.add_link_library('bar', kind='static')")?;
+        assert!(env
+            .eval("exe.add_link_library('baz', kind='invalid')")
+            .is_err());
+
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutableValue>().unwrap();
+        let inner = exe.inner("ignored").unwrap();
+
+        assert_eq!(
+            inner.linking_annotations(),
+            &[
+                LinkingAnnotation::LinkLibrary("foo".to_string()),
+                LinkingAnnotation::LinkLibraryStatic("bar".to_string()),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_link_search_path_and_object_file() -> Result<()> {
+        let mut env = test_evaluation_context_builder()?.into_context()?;
+        add_exe(&mut env)?;
+
+        env.eval("exe.add_link_search_path('lib')")?;
+        env.eval("exe.add_link_object_file('foo.o')")?;
+
+        let cwd = PathBuf::from(env.eval("CWD")?.to_string());
+
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutableValue>().unwrap();
+        let inner = exe.inner("ignored").unwrap();
+
+        assert_eq!(
+            inner.linking_annotations(),
+            &[
+                LinkingAnnotation::SearchNative(cwd.join("lib")),
+                LinkingAnnotation::Argument(cwd.join("foo.o").display().to_string()),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_test_runner_code() -> Result<()> {
+        assert_eq!(
+            test_runner_code("unittest", &["foo.tests".to_string()])?,
+            "import sys, unittest; unittest.main(module=None, argv=sys.argv[:1] + ['foo.tests'] + sys.argv[1:])"
+        );
+        assert_eq!(
+            test_runner_code("pytest", &["--pyargs".to_string(), "it's".to_string()])?,
+            "import sys, pytest; sys.exit(pytest.main(['--pyargs', 'it\\'s'] + sys.argv[1:]))"
+        );
+        assert!(test_runner_code("nose", &[]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_test_target() -> Result<()> {
+        let mut env = test_evaluation_context_builder()?.into_context()?;
+        add_exe(&mut env)?;
+
+        assert!(env.eval("exe.to_test_target()").is_err());
+        assert!(env
+            .eval("exe.to_test_target(runner='nose', args=['foo'])")
+            .is_err());
+
+        let test_exe = env.eval("exe.to_test_target(args=['foo.tests'])")?;
+        assert_eq!(test_exe.get_type(), "PythonExecutable");
+
+        let test_exe = test_exe.downcast_ref::<PythonExecutableValue>().unwrap();
+        let embedded = test_exe
+            .inner("ignored")
+            .unwrap()
+            .to_embedded_python_context(&get_env()?, "0")?;
+        assert_eq!(
+            embedded.config.run_modes,
+            vec![PythonRunMode::Eval(test_runner_code(
+                "unittest",
+                &["foo.tests".to_string()]
+            )?)]
+        );
+        assert_eq!(embedded.config.run_mode_flag, None);
+
+        // The original executable is unchanged.
+        let exe = env.eval("exe")?;
+        let exe = exe.downcast_ref::<PythonExecutableValue>().unwrap();
+        let embedded = exe
+            .inner("ignored")
+            .unwrap()
+            .to_embedded_python_context(&get_env()?, "0")?;
+        assert!(embedded.config.run_modes.is_empty());
+
+        Ok(())
+    }
+
     #[test]
     fn test_to_wix_bundle_builder_callback() -> Result<()> {
         let mut env = test_evaluation_context_builder()?.into_context()?;
@@ -1522,4 +3420,29 @@ mod tests {
 
         Ok(())
     }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn test_to_macos_application_bundle_builder() -> Result<()> {
+        let mut env = test_evaluation_context_builder()?.into_context()?;
+        add_exe(&mut env)?;
+        let builder_value = env.eval(
+            "exe.to_macos_application_bundle_builder('My App', 'com.example.my_app', '0.1')",
+        )?;
+        let builder = builder_value
+            .downcast_ref::<MacOsApplicationBundleBuilderValue>()
+            .unwrap();
+
+        assert_eq!(
+            builder.inner.get_info_plist_key("CFBundleExecutable")?,
+            Some("testapp".into())
+        );
+        assert!(builder
+            .inner
+            .files()
+            .get("Contents/MacOS/testapp")
+            .is_some());
+
+        Ok(())
+    }
 }
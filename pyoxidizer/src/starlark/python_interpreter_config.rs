@@ -8,7 +8,7 @@ use {
     python_packaging::{
         interpreter::{
             Allocator, BytesWarning, CheckHashPycsMode, CoerceCLocale, MemoryAllocatorBackend,
-            MultiprocessingStartMethod, PythonInterpreterProfile, TerminfoResolution,
+            MultiprocessingStartMethod, PythonInterpreterProfile, RunMode, TerminfoResolution,
         },
         resource::BytecodeOptimizationLevel,
     },
@@ -19,8 +19,10 @@ use {
         none::NoneType,
         {Mutable, TypedValue, Value, ValueResult},
     },
-    starlark_dialect_build_targets::{ToOptional, TryToOptional},
+    starlark_dialect_build_targets::{optional_dict_arg, ToOptional, TryToOptional},
     std::{
+        collections::{BTreeMap, HashMap},
+        path::PathBuf,
         str::FromStr,
         sync::{Arc, Mutex, MutexGuard},
     },
@@ -198,12 +200,14 @@ impl TypedValue for PythonInterpreterConfigValue {
             "run_command" => inner.config.run_command.to_value(),
             "run_filename" => inner.config.run_filename.to_value(),
             "run_module" => inner.config.run_module.to_value(),
+            "safe_path" => inner.config.safe_path.to_value(),
             "show_ref_count" => inner.config.show_ref_count.to_value(),
             "site_import" => inner.config.site_import.to_value(),
             "skip_first_source_line" => inner.config.skip_first_source_line.to_value(),
             "stdio_encoding" => inner.config.stdio_encoding.to_value(),
             "stdio_errors" => inner.config.stdio_errors.to_value(),
             "tracemalloc" => inner.config.tracemalloc.to_value(),
+            "use_frozen_modules" => inner.config.use_frozen_modules.to_value(),
             "user_site_directory" => inner.config.user_site_directory.to_value(),
             "verbose" => inner.config.verbose.to_value(),
             "warn_options" => inner.config.warn_options.to_value(),
@@ -216,6 +220,7 @@ impl TypedValue for PythonInterpreterConfigValue {
             "allocator_pymalloc_arena" => Value::from(inner.allocator_pymalloc_arena),
             "allocator_debug" => Value::from(inner.allocator_debug),
             "oxidized_importer" => Value::from(inner.oxidized_importer),
+            "oxidized_importer_lazy_loading" => Value::from(inner.oxidized_importer_lazy_loading),
             "filesystem_importer" => Value::from(inner.filesystem_importer),
             "argvb" => Value::from(inner.argvb),
             "multiprocessing_auto_dispatch" => Value::from(inner.multiprocessing_auto_dispatch),
@@ -224,8 +229,50 @@ impl TypedValue for PythonInterpreterConfigValue {
             }
             "sys_frozen" => Value::from(inner.sys_frozen),
             "sys_meipass" => Value::from(inner.sys_meipass),
+            "replace_missing_stdio" => Value::from(inner.replace_missing_stdio),
             "terminfo_resolution" => inner.terminfo_resolution.to_value(),
             "write_modules_directory_env" => inner.write_modules_directory_env.to_value(),
+            "run_modes" => Value::from(
+                inner
+                    .run_modes
+                    .iter()
+                    .map(|mode| mode.to_string())
+                    .collect::<Vec<_>>(),
+            ),
+            "run_mode_flag" => inner.run_mode_flag.to_value(),
+            "environment_variables" => Value::try_from(
+                inner
+                    .environment_variables
+                    .clone()
+                    .into_iter()
+                    .collect::<HashMap<_, _>>(),
+            )?,
+            "extension_modules_extraction_path" => {
+                inner.extension_modules_extraction_path.to_value()
+            }
+            "package_source_paths" => Value::try_from(
+                inner
+                    .package_source_paths
+                    .iter()
+                    .map(|(k, v)| (k.clone(), format!("{}", v.display())))
+                    .collect::<HashMap<_, _>>(),
+            )?,
+            "plugin_paths" => Value::from(
+                inner
+                    .plugin_paths
+                    .iter()
+                    .map(|p| format!("{}", p.display()))
+                    .collect::<Vec<_>>(),
+            ),
+            "plugin_paths_override" => Value::from(inner.plugin_paths_override),
+            "file_emulation" => Value::from(inner.file_emulation),
+            "package_file_emulation" => Value::try_from(
+                inner
+                    .package_file_emulation
+                    .iter()
+                    .map(|(k, v)| (k.clone(), *v))
+                    .collect::<HashMap<_, _>>(),
+            )?,
             attr => {
                 return Err(ValueError::OperationNotSupported {
                     op: UnsupportedOperation::GetAttr(attr.to_string()),
@@ -285,12 +332,14 @@ impl TypedValue for PythonInterpreterConfigValue {
                 | "run_command"
                 | "run_filename"
                 | "run_module"
+                | "safe_path"
                 | "show_ref_count"
                 | "site_import"
                 | "skip_first_source_line"
                 | "stdio_encoding"
                 | "stdio_errors"
                 | "tracemalloc"
+                | "use_frozen_modules"
                 | "user_site_directory"
                 | "verbose"
                 | "warn_options"
@@ -303,14 +352,25 @@ impl TypedValue for PythonInterpreterConfigValue {
                 | "allocator_pymalloc_arena"
                 | "allocator_debug"
                 | "oxidized_importer"
+                | "oxidized_importer_lazy_loading"
                 | "filesystem_importer"
                 | "argvb"
                 | "multiprocessing_auto_dispatch"
                 | "multiprocessing_start_method"
                 | "sys_frozen"
                 | "sys_meipass"
+                | "replace_missing_stdio"
                 | "terminfo_resolution"
                 | "write_modules_directory_env"
+                | "run_modes"
+                | "run_mode_flag"
+                | "environment_variables"
+                | "extension_modules_extraction_path"
+                | "package_source_paths"
+                | "plugin_paths"
+                | "plugin_paths_override"
+                | "file_emulation"
+                | "package_file_emulation"
         ))
     }
 
@@ -516,6 +576,9 @@ impl TypedValue for PythonInterpreterConfigValue {
             "run_module" => {
                 inner.config.run_module = value.to_optional();
             }
+            "safe_path" => {
+                inner.config.safe_path = value.to_optional();
+            }
             "show_ref_count" => {
                 inner.config.show_ref_count = value.to_optional();
             }
@@ -534,6 +597,9 @@ impl TypedValue for PythonInterpreterConfigValue {
             "tracemalloc" => {
                 inner.config.tracemalloc = value.to_optional();
             }
+            "use_frozen_modules" => {
+                inner.config.use_frozen_modules = value.to_optional();
+            }
             "user_site_directory" => {
                 inner.config.user_site_directory = value.to_optional();
             }
@@ -577,6 +643,9 @@ impl TypedValue for PythonInterpreterConfigValue {
             "oxidized_importer" => {
                 inner.oxidized_importer = value.to_bool();
             }
+            "oxidized_importer_lazy_loading" => {
+                inner.oxidized_importer_lazy_loading = value.to_bool();
+            }
             "filesystem_importer" => {
                 inner.filesystem_importer = value.to_bool();
             }
@@ -604,6 +673,9 @@ impl TypedValue for PythonInterpreterConfigValue {
             "sys_meipass" => {
                 inner.sys_meipass = value.to_bool();
             }
+            "replace_missing_stdio" => {
+                inner.replace_missing_stdio = value.to_bool();
+            }
             "terminfo_resolution" => {
                 inner.terminfo_resolution =
                     TerminfoResolution::try_from(value.to_string().as_str()).map_err(|e| {
@@ -617,6 +689,84 @@ impl TypedValue for PythonInterpreterConfigValue {
             "write_modules_directory_env" => {
                 inner.write_modules_directory_env = value.to_optional();
             }
+            "run_modes" => {
+                let values: Option<Vec<String>> = value.try_to_optional()?;
+
+                inner.run_modes = values
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|v| {
+                        RunMode::try_from(v).map_err(|e| {
+                            ValueError::from(RuntimeError {
+                                code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                                message: e,
+                                label: format!("{}.{}", Self::TYPE, attribute),
+                            })
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+            }
+            "run_mode_flag" => {
+                inner.run_mode_flag = value.to_optional();
+            }
+            "environment_variables" => {
+                optional_dict_arg(attribute, "string", "string", &value)?;
+
+                inner.environment_variables = match value.get_type() {
+                    "dict" => value
+                        .iter()?
+                        .iter()
+                        .map(|key| {
+                            let k = key.to_string();
+                            let v = value.at(key).unwrap().to_string();
+                            (k, v)
+                        })
+                        .collect(),
+                    _ => BTreeMap::new(),
+                };
+            }
+            "extension_modules_extraction_path" => {
+                let path: Option<String> = value.to_optional();
+                inner.extension_modules_extraction_path = path.map(PathBuf::from);
+            }
+            "package_source_paths" => {
+                optional_dict_arg(attribute, "string", "string", &value)?;
+
+                inner.package_source_paths = match value.get_type() {
+                    "dict" => value
+                        .iter()?
+                        .iter()
+                        .map(|key| {
+                            let k = key.to_string();
+                            let v = PathBuf::from(value.at(key).unwrap().to_string());
+                            (k, v)
+                        })
+                        .collect(),
+                    _ => BTreeMap::new(),
+                };
+            }
+            "plugin_paths" => {
+                let values: Option<Vec<PathBuf>> = value.try_to_optional()?;
+                inner.plugin_paths = values.unwrap_or_default();
+            }
+            "plugin_paths_override" => {
+                inner.plugin_paths_override = value.to_bool();
+            }
+            "file_emulation" => {
+                inner.file_emulation = value.to_bool();
+            }
+            "package_file_emulation" => {
+                optional_dict_arg(attribute, "string", "bool", &value)?;
+
+                inner.package_file_emulation = match value.get_type() {
+                    "dict" => value
+                        .iter()?
+                        .iter()
+                        .map(|key| (key.to_string(), value.at(key).unwrap().to_bool()))
+                        .collect(),
+                    _ => BTreeMap::new(),
+                };
+            }
             attr => {
                 return Err(ValueError::OperationNotSupported {
                     op: UnsupportedOperation::SetAttr(attr.to_string()),
@@ -1089,6 +1239,21 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_safe_path() -> Result<()> {
+        let mut env = get_env()?;
+
+        eval_assert(&mut env, "config.safe_path == None")?;
+
+        env.eval("config.safe_path = True")?;
+        eval_assert(&mut env, "config.safe_path == True")?;
+
+        env.eval("config.safe_path = None")?;
+        eval_assert(&mut env, "config.safe_path == None")?;
+
+        Ok(())
+    }
+
     #[test]
     fn test_show_ref_count() -> Result<()> {
         let mut env = get_env()?;
@@ -1143,6 +1308,21 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_use_frozen_modules() -> Result<()> {
+        let mut env = get_env()?;
+
+        eval_assert(&mut env, "config.use_frozen_modules == None")?;
+
+        env.eval("config.use_frozen_modules = False")?;
+        eval_assert(&mut env, "config.use_frozen_modules == False")?;
+
+        env.eval("config.use_frozen_modules = None")?;
+        eval_assert(&mut env, "config.use_frozen_modules == None")?;
+
+        Ok(())
+    }
+
     #[test]
     fn test_user_site_directory() -> Result<()> {
         let mut env = get_env()?;
@@ -1287,6 +1467,18 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_oxidized_importer_lazy_loading() -> Result<()> {
+        let mut env = get_env()?;
+
+        eval_assert(&mut env, "config.oxidized_importer_lazy_loading == False")?;
+
+        env.eval("config.oxidized_importer_lazy_loading = True")?;
+        eval_assert(&mut env, "config.oxidized_importer_lazy_loading == True")?;
+
+        Ok(())
+    }
+
     #[test]
     fn test_filesystem_importer() -> Result<()> {
         let mut env = get_env()?;
@@ -1362,6 +1554,18 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_replace_missing_stdio() -> Result<()> {
+        let mut env = get_env()?;
+
+        eval_assert(&mut env, "config.replace_missing_stdio == True")?;
+
+        env.eval("config.replace_missing_stdio = False")?;
+        eval_assert(&mut env, "config.replace_missing_stdio == False")?;
+
+        Ok(())
+    }
+
     #[test]
     fn test_terminfo_resolution() -> Result<()> {
         let mut env = get_env()?;
@@ -1385,4 +1589,143 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_run_modes() -> Result<()> {
+        let mut env = get_env()?;
+
+        eval_assert(&mut env, "config.run_modes == []")?;
+
+        env.eval("config.run_modes = ['module:foo', 'eval:import sys', 'file', 'repl']")?;
+        eval_assert(
+            &mut env,
+            "config.run_modes == ['module:foo', 'eval:import sys', 'file', 'repl']",
+        )?;
+
+        env.eval("config.run_modes = None")?;
+        eval_assert(&mut env, "config.run_modes == []")?;
+
+        assert!(env.eval("config.run_modes = ['invalid']").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_mode_flag() -> Result<()> {
+        let mut env = get_env()?;
+
+        eval_assert(&mut env, "config.run_mode_flag == None")?;
+
+        env.eval("config.run_mode_flag = '--run-mode'")?;
+        eval_assert(&mut env, "config.run_mode_flag == '--run-mode'")?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_environment_variables() -> Result<()> {
+        let mut env = get_env()?;
+
+        eval_assert(&mut env, "config.environment_variables == {}")?;
+
+        env.eval("config.environment_variables = {'FOO': 'foo', 'BAR': '$ORIGIN/bar'}")?;
+        eval_assert(
+            &mut env,
+            "config.environment_variables == {'FOO': 'foo', 'BAR': '$ORIGIN/bar'}",
+        )?;
+
+        env.eval("config.environment_variables = None")?;
+        eval_assert(&mut env, "config.environment_variables == {}")?;
+
+        assert!(env
+            .eval("config.environment_variables = {'FOO': 1}")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_extension_modules_extraction_path() -> Result<()> {
+        let mut env = get_env()?;
+
+        eval_assert(&mut env, "config.extension_modules_extraction_path == None")?;
+
+        env.eval("config.extension_modules_extraction_path = 'myapp-1.0-extensions'")?;
+        eval_assert(
+            &mut env,
+            "config.extension_modules_extraction_path == 'myapp-1.0-extensions'",
+        )?;
+
+        env.eval("config.extension_modules_extraction_path = None")?;
+        eval_assert(&mut env, "config.extension_modules_extraction_path == None")?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_package_source_paths() -> Result<()> {
+        let mut env = get_env()?;
+
+        eval_assert(&mut env, "config.package_source_paths == {}")?;
+
+        env.eval("config.package_source_paths = {'myapp': '/src/myapp'}")?;
+        eval_assert(
+            &mut env,
+            "config.package_source_paths == {'myapp': '/src/myapp'}",
+        )?;
+
+        env.eval("config.package_source_paths = None")?;
+        eval_assert(&mut env, "config.package_source_paths == {}")?;
+
+        assert!(env
+            .eval("config.package_source_paths = {'myapp': 1}")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_plugin_paths() -> Result<()> {
+        let mut env = get_env()?;
+
+        eval_assert(&mut env, "config.plugin_paths == []")?;
+        eval_assert(&mut env, "config.plugin_paths_override == False")?;
+
+        env.eval("config.plugin_paths = ['$HOME/.myapp/plugins']")?;
+        eval_assert(&mut env, "config.plugin_paths == ['$HOME/.myapp/plugins']")?;
+
+        env.eval("config.plugin_paths = None")?;
+        eval_assert(&mut env, "config.plugin_paths == []")?;
+
+        env.eval("config.plugin_paths_override = True")?;
+        eval_assert(&mut env, "config.plugin_paths_override == True")?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_emulation() -> Result<()> {
+        let mut env = get_env()?;
+
+        eval_assert(&mut env, "config.file_emulation == False")?;
+        eval_assert(&mut env, "config.package_file_emulation == {}")?;
+
+        env.eval("config.file_emulation = True")?;
+        eval_assert(&mut env, "config.file_emulation == True")?;
+
+        env.eval("config.package_file_emulation = {'myapp': False}")?;
+        eval_assert(
+            &mut env,
+            "config.package_file_emulation == {'myapp': False}",
+        )?;
+
+        env.eval("config.package_file_emulation = None")?;
+        eval_assert(&mut env, "config.package_file_emulation == {}")?;
+
+        assert!(env
+            .eval("config.package_file_emulation = {'myapp': 'yes'}")
+            .is_err());
+
+        Ok(())
+    }
 }
@@ -4,10 +4,14 @@
 
 use {
     super::python_resource::ResourceCollectionContext,
+    anyhow::anyhow,
     linked_hash_map::LinkedHashMap,
     python_packaging::{
         location::ConcreteResourceLocation,
-        policy::{ExtensionModuleFilter, PythonPackagingPolicy, ResourceHandlingMode},
+        policy::{
+            ExtensionModuleFilter, PythonPackagingPolicy, ResourceHandlingMode, ResourceNamePattern,
+        },
+        resource::BytecodeOptimizationLevel,
     },
     starlark::{
         environment::TypeValues,
@@ -20,7 +24,7 @@ use {
             Mutable, TypedValue, Value, ValueResult,
         },
     },
-    starlark_dialect_build_targets::required_type_arg,
+    starlark_dialect_build_targets::{required_list_arg, required_type_arg},
     std::{
         ops::Deref,
         sync::{Arc, Mutex, MutexGuard},
@@ -123,6 +127,7 @@ impl TypedValue for PythonPackagingPolicyValue {
             "allow_in_memory_shared_library_loading" => {
                 Value::from(inner.allow_in_memory_shared_library_loading())
             }
+            "allow_unknown_licenses" => Value::from(inner.allow_unknown_licenses()),
             "bytecode_optimize_level_zero" => Value::from(inner.bytecode_optimize_level_zero()),
             "bytecode_optimize_level_one" => Value::from(inner.bytecode_optimize_level_one()),
             "bytecode_optimize_level_two" => Value::from(inner.bytecode_optimize_level_two()),
@@ -137,6 +142,9 @@ impl TypedValue for PythonPackagingPolicyValue {
                 Value::from(inner.include_non_distribution_sources())
             }
             "include_test" => Value::from(inner.include_test()),
+            "packed_resources_zstd_compression" => {
+                Value::from(inner.packed_resources_zstd_compression())
+            }
             "preferred_extension_module_variants" => {
                 Value::try_from(inner.preferred_extension_module_variants().clone())?
             }
@@ -162,6 +170,7 @@ impl TypedValue for PythonPackagingPolicyValue {
             attribute,
             "allow_files"
                 | "allow_in_memory_shared_library_loading"
+                | "allow_unknown_licenses"
                 | "bytecode_optimize_level_zero"
                 | "bytecode_optimize_level_one"
                 | "bytecode_optimize_level_two"
@@ -174,6 +183,7 @@ impl TypedValue for PythonPackagingPolicyValue {
                 | "include_file_resources"
                 | "include_non_distribution_sources"
                 | "include_test"
+                | "packed_resources_zstd_compression"
                 | "preferred_extension_module_variants"
                 | "resources_location"
                 | "resources_location_fallback"
@@ -190,6 +200,9 @@ impl TypedValue for PythonPackagingPolicyValue {
             "allow_in_memory_shared_library_loading" => {
                 inner.set_allow_in_memory_shared_library_loading(value.to_bool());
             }
+            "allow_unknown_licenses" => {
+                inner.set_allow_unknown_licenses(value.to_bool());
+            }
             "bytecode_optimize_level_zero" => {
                 inner.set_bytecode_optimize_level_zero(value.to_bool());
             }
@@ -235,6 +248,9 @@ impl TypedValue for PythonPackagingPolicyValue {
             "include_test" => {
                 inner.set_include_test(value.to_bool());
             }
+            "packed_resources_zstd_compression" => {
+                inner.set_packed_resources_zstd_compression(value.to_bool());
+            }
             "resources_location" => {
                 inner.set_resources_location(
                     ConcreteResourceLocation::try_from(value.to_string().as_str()).map_err(
@@ -278,8 +294,105 @@ impl TypedValue for PythonPackagingPolicyValue {
     }
 }
 
+/// Resolve `ResourceNamePattern` from Starlark arguments.
+fn resource_name_patterns(
+    label: &str,
+    patterns: &Value,
+    syntax: &str,
+) -> Result<Vec<ResourceNamePattern>, ValueError> {
+    required_list_arg("patterns", "string", patterns)?;
+
+    patterns
+        .iter()?
+        .iter()
+        .map(|pattern| {
+            let pattern = pattern.to_string();
+
+            match syntax {
+                "glob" => ResourceNamePattern::new_glob(&pattern),
+                "regex" => ResourceNamePattern::new_regex(&pattern),
+                _ => Err(anyhow!(
+                    "{} is not a valid pattern syntax; use \"glob\" or \"regex\"",
+                    syntax
+                )),
+            }
+            .map_err(|e| {
+                ValueError::from(RuntimeError {
+                    code: "PYTHON_PACKAGING_POLICY",
+                    message: format!("{:?}", e),
+                    label: label.to_string(),
+                })
+            })
+        })
+        .collect()
+}
+
 // Starlark methods.
 impl PythonPackagingPolicyValue {
+    fn starlark_allow_licenses(&mut self, names: &Value) -> ValueResult {
+        const LABEL: &str = "PythonPackagingPolicy.allow_licenses()";
+
+        required_list_arg("names", "string", names)?;
+
+        let mut inner = self.inner(LABEL)?;
+        for name in names.iter()?.iter() {
+            inner.add_allowed_license(&name.to_string()).map_err(|e| {
+                ValueError::from(RuntimeError {
+                    code: "PYTHON_PACKAGING_POLICY",
+                    message: format!("{:?}", e),
+                    label: LABEL.to_string(),
+                })
+            })?;
+        }
+
+        Ok(Value::from(NoneType::None))
+    }
+
+    fn starlark_deny_licenses(&mut self, names: &Value) -> ValueResult {
+        const LABEL: &str = "PythonPackagingPolicy.deny_licenses()";
+
+        required_list_arg("names", "string", names)?;
+
+        let mut inner = self.inner(LABEL)?;
+        for name in names.iter()?.iter() {
+            inner.add_denied_license(&name.to_string()).map_err(|e| {
+                ValueError::from(RuntimeError {
+                    code: "PYTHON_PACKAGING_POLICY",
+                    message: format!("{:?}", e),
+                    label: LABEL.to_string(),
+                })
+            })?;
+        }
+
+        Ok(Value::from(NoneType::None))
+    }
+
+    fn starlark_exclude_resources(&mut self, patterns: &Value, syntax: String) -> ValueResult {
+        const LABEL: &str = "PythonPackagingPolicy.exclude_resources()";
+
+        let patterns = resource_name_patterns(LABEL, patterns, &syntax)?;
+
+        let mut inner = self.inner(LABEL)?;
+        for pattern in patterns {
+            inner.add_resource_name_exclude(pattern);
+        }
+
+        Ok(Value::from(NoneType::None))
+    }
+
+    fn starlark_include_resources(&mut self, patterns: &Value, syntax: String) -> ValueResult {
+        const LABEL: &str = "PythonPackagingPolicy.include_resources()";
+
+        let patterns = resource_name_patterns(LABEL, patterns, &syntax)?;
+
+        let mut inner = self.inner(LABEL)?;
+        for pattern in patterns {
+            inner.add_resource_name_include(pattern);
+        }
+
+        Ok(Value::from(NoneType::None))
+    }
+
     fn starlark_register_resource_callback(&mut self, func: &Value) -> ValueResult {
         required_type_arg("func", "function", func)?;
 
@@ -288,6 +401,35 @@ impl PythonPackagingPolicyValue {
         Ok(Value::from(NoneType::None))
     }
 
+    fn starlark_set_package_bytecode_optimize_levels(
+        &mut self,
+        package: String,
+        levels: &Value,
+    ) -> ValueResult {
+        const LABEL: &str = "PythonPackagingPolicy.set_package_bytecode_optimize_levels()";
+
+        required_list_arg("levels", "int", levels)?;
+
+        let levels = levels
+            .iter()?
+            .iter()
+            .map(|level| {
+                BytecodeOptimizationLevel::try_from(level.to_int()? as i32).map_err(|e| {
+                    ValueError::from(RuntimeError {
+                        code: "PYTHON_PACKAGING_POLICY",
+                        message: e.to_string(),
+                        label: LABEL.to_string(),
+                    })
+                })
+            })
+            .collect::<Result<Vec<_>, ValueError>>()?;
+
+        self.inner(LABEL)?
+            .set_package_bytecode_optimize_levels(&package, levels);
+
+        Ok(Value::from(NoneType::None))
+    }
+
     #[allow(clippy::unnecessary_wraps)]
     fn starlark_set_preferred_extension_module_variant(
         &mut self,
@@ -318,11 +460,48 @@ impl PythonPackagingPolicyValue {
 }
 
 starlark_module! { python_packaging_policy_module =>
+    PythonPackagingPolicy.allow_licenses(this, names) {
+        let mut this = this.downcast_mut::<PythonPackagingPolicyValue>().unwrap().unwrap();
+        this.starlark_allow_licenses(&names)
+    }
+
+    PythonPackagingPolicy.deny_licenses(this, names) {
+        let mut this = this.downcast_mut::<PythonPackagingPolicyValue>().unwrap().unwrap();
+        this.starlark_deny_licenses(&names)
+    }
+
+    PythonPackagingPolicy.exclude_resources(
+        this,
+        patterns,
+        syntax: String = "glob".to_string()
+    ) {
+        let mut this = this.downcast_mut::<PythonPackagingPolicyValue>().unwrap().unwrap();
+        this.starlark_exclude_resources(&patterns, syntax)
+    }
+
+    PythonPackagingPolicy.include_resources(
+        this,
+        patterns,
+        syntax: String = "glob".to_string()
+    ) {
+        let mut this = this.downcast_mut::<PythonPackagingPolicyValue>().unwrap().unwrap();
+        this.starlark_include_resources(&patterns, syntax)
+    }
+
     PythonPackagingPolicy.register_resource_callback(this, func) {
         let mut this = this.downcast_mut::<PythonPackagingPolicyValue>().unwrap().unwrap();
         this.starlark_register_resource_callback(&func)
     }
 
+    PythonPackagingPolicy.set_package_bytecode_optimize_levels(
+        this,
+        package: String,
+        levels
+    ) {
+        let mut this = this.downcast_mut::<PythonPackagingPolicyValue>().unwrap().unwrap();
+        this.starlark_set_package_bytecode_optimize_levels(package, &levels)
+    }
+
     PythonPackagingPolicy.set_preferred_extension_module_variant(
         this,
         name: String,
@@ -563,6 +742,21 @@ mod tests {
         )?;
         assert!(value.to_bool());
 
+        // packed_resources_zstd_compression
+        let value = env.eval("policy.packed_resources_zstd_compression")?;
+        assert_eq!(value.get_type(), "bool");
+        assert_eq!(value.to_bool(), policy.packed_resources_zstd_compression());
+
+        let value = env.eval(
+            "policy.packed_resources_zstd_compression = True; policy.packed_resources_zstd_compression",
+        )?;
+        assert!(value.to_bool());
+
+        let value = env.eval(
+            "policy.packed_resources_zstd_compression = False; policy.packed_resources_zstd_compression",
+        )?;
+        assert!(!value.to_bool());
+
         Ok(())
     }
 
@@ -627,6 +821,138 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_set_package_bytecode_optimize_levels() -> Result<()> {
+        let mut env = test_evaluation_context_builder()?.into_context()?;
+
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("policy = dist.make_python_packaging_policy()")?;
+
+        assert!(env
+            .eval("policy.set_package_bytecode_optimize_levels('foo', 2)")
+            .is_err());
+        assert!(env
+            .eval("policy.set_package_bytecode_optimize_levels('foo', [3])")
+            .is_err());
+
+        env.eval("policy.set_package_bytecode_optimize_levels('foo', [1, 2])")?;
+
+        {
+            let policy_value = env.eval("policy")?;
+            let policy = policy_value
+                .downcast_ref::<PythonPackagingPolicyValue>()
+                .unwrap();
+            assert_eq!(
+                policy
+                    .inner("ignored")
+                    .unwrap()
+                    .package_bytecode_optimize_levels()
+                    .get("foo"),
+                Some(&vec![
+                    BytecodeOptimizationLevel::One,
+                    BytecodeOptimizationLevel::Two
+                ])
+            );
+        }
+
+        env.eval("exe = dist.to_python_executable('testapp', packaging_policy=policy)")?;
+        let m = env.eval("exe.make_python_module_source('foo.bar', '')")?;
+        assert!(!m
+            .get_attr("add_bytecode_optimization_level_zero")
+            .unwrap()
+            .to_bool());
+        assert!(m
+            .get_attr("add_bytecode_optimization_level_one")
+            .unwrap()
+            .to_bool());
+        assert!(m
+            .get_attr("add_bytecode_optimization_level_two")
+            .unwrap()
+            .to_bool());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_license_policy() -> Result<()> {
+        let mut env = test_evaluation_context_builder()?.into_context()?;
+
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("policy = dist.make_python_packaging_policy()")?;
+
+        let value = env.eval("policy.allow_unknown_licenses")?;
+        assert!(value.to_bool());
+        let value =
+            env.eval("policy.allow_unknown_licenses = False; policy.allow_unknown_licenses")?;
+        assert!(!value.to_bool());
+
+        assert!(env.eval("policy.deny_licenses('copyleft')").is_err());
+        assert!(env.eval("policy.deny_licenses(['not-a-license'])").is_err());
+        assert!(env
+            .eval("policy.allow_licenses(['not-a-license'])")
+            .is_err());
+
+        env.eval("policy.deny_licenses(['copyleft', 'GPL-3.0-only'])")?;
+        env.eval("policy.allow_licenses(['MIT'])")?;
+
+        let policy_value = env.eval("policy")?;
+        let policy = policy_value
+            .downcast_ref::<PythonPackagingPolicyValue>()
+            .unwrap();
+        let inner = policy.inner("ignored").unwrap();
+        assert_eq!(
+            inner.denied_licenses().iter().collect::<Vec<_>>(),
+            vec!["GPL-3.0-only", "copyleft"]
+        );
+        assert_eq!(
+            inner.allowed_licenses().iter().collect::<Vec<_>>(),
+            vec!["MIT"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_include_exclude_resources() -> Result<()> {
+        let mut env = test_evaluation_context_builder()?.into_context()?;
+
+        env.eval("dist = default_python_distribution()")?;
+        env.eval("policy = dist.make_python_packaging_policy()")?;
+
+        assert!(env.eval("policy.exclude_resources('foo')").is_err());
+        assert!(env.eval("policy.exclude_resources([42])").is_err());
+        assert!(env
+            .eval("policy.exclude_resources(['foo'], syntax='invalid')")
+            .is_err());
+        assert!(env
+            .eval("policy.exclude_resources(['('], syntax='regex')")
+            .is_err());
+
+        env.eval("policy.include_resources(['app', 'app.*'])")?;
+        env.eval("policy.exclude_resources(['*.tests', '*.tests.*'])")?;
+        env.eval(r#"policy.exclude_resources(['app\\.internal_\\w+'], syntax='regex')"#)?;
+        env.eval("exe = dist.to_python_executable('testapp', packaging_policy=policy)")?;
+
+        for (name, include) in [
+            ("app", true),
+            ("app.cli", true),
+            ("app.tests", false),
+            ("app.tests.test_cli", false),
+            ("app.internal_foo", false),
+            ("other", false),
+        ] {
+            let m = env.eval(&format!("exe.make_python_module_source('{}', '')", name))?;
+            assert_eq!(
+                m.get_attr("add_include").unwrap().to_bool(),
+                include,
+                "{}",
+                name
+            );
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_stdlib_extension_module_enable() -> Result<()> {
         let mut env = test_evaluation_context_builder()?.into_context()?;
@@ -337,3 +337,57 @@ pub fn add_context_for_value(
         })),
     }
 }
+
+/// Attempt to resolve the `PythonResource` for a Value.
+pub fn python_resource_for_value(
+    value: &Value,
+    label: &str,
+) -> Result<PythonResource<'static>, ValueError> {
+    match value.get_type() {
+        "PythonModuleSource" => Ok(PythonResource::from(
+            value
+                .downcast_ref::<PythonModuleSourceValue>()
+                .unwrap()
+                .inner(label)?
+                .m
+                .clone(),
+        )),
+        "PythonPackageResource" => Ok(PythonResource::from(
+            value
+                .downcast_ref::<PythonPackageResourceValue>()
+                .unwrap()
+                .inner(label)?
+                .r
+                .clone(),
+        )),
+        "PythonPackageDistributionResource" => Ok(PythonResource::from(
+            value
+                .downcast_ref::<PythonPackageDistributionResourceValue>()
+                .unwrap()
+                .inner(label)?
+                .r
+                .clone(),
+        )),
+        "PythonExtensionModule" => Ok(PythonResource::from(
+            value
+                .downcast_ref::<PythonExtensionModuleValue>()
+                .unwrap()
+                .inner(label)?
+                .em
+                .clone(),
+        )),
+        "File" => Ok(PythonResource::from(
+            value
+                .downcast_ref::<FileValue>()
+                .unwrap()
+                .inner(label)?
+                .file
+                .clone(),
+        )),
+        t => Err(ValueError::from(RuntimeError {
+            code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+            message: format!("unable to obtain Python resource from {}", t),
+            label: label.to_string(),
+        })),
+    }
+}
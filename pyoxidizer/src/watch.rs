@@ -0,0 +1,239 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! Monitor the filesystem for changes.
+
+This powers `pyoxidizer build --watch`. Files are polled for modifications
+rather than relying on platform specific notification mechanisms.
+*/
+
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+/// Names of directories that are never descended into.
+const IGNORED_DIRECTORY_NAMES: &[&str] = &[".git", ".hg", "__pycache__", "target"];
+
+/// Point-in-time record of the state of files under a set of paths.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FileSnapshot {
+    files: BTreeMap<PathBuf, (Option<SystemTime>, u64)>,
+}
+
+impl FileSnapshot {
+    /// Capture the state of files under `roots`.
+    ///
+    /// Roots can be files or directories. Directories are walked recursively.
+    /// Paths under any of `excludes` are ignored. Roots that don't exist are
+    /// silently ignored so their creation is picked up by a later snapshot.
+    pub fn capture(roots: &[PathBuf], excludes: &[PathBuf]) -> Self {
+        let mut files = BTreeMap::new();
+
+        for root in roots {
+            let walk = walkdir::WalkDir::new(root)
+                .follow_links(true)
+                .into_iter()
+                .filter_entry(|entry| {
+                    let path = entry.path();
+
+                    if excludes.iter().any(|exclude| path.starts_with(exclude)) {
+                        return false;
+                    }
+
+                    !(entry.file_type().is_dir()
+                        && entry
+                            .file_name()
+                            .to_str()
+                            .map(|name| IGNORED_DIRECTORY_NAMES.contains(&name))
+                            .unwrap_or(false))
+                });
+
+            // Errors are most likely files disappearing during the walk. The
+            // next snapshot will reflect the new state.
+            for entry in walk.filter_map(|entry| entry.ok()) {
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+
+                if let Ok(metadata) = entry.metadata() {
+                    files.insert(
+                        entry.path().to_path_buf(),
+                        (metadata.modified().ok(), metadata.len()),
+                    );
+                }
+            }
+        }
+
+        Self { files }
+    }
+
+    /// Number of files in this snapshot.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Whether this snapshot contains no files.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Adapt this snapshot to the paths captured by a later snapshot.
+    ///
+    /// Paths in both snapshots keep their state from this one, so modifications
+    /// made between the two captures are still detected. Paths only in `later`
+    /// take their state from it. Paths only in this snapshot are dropped.
+    pub fn retarget(&self, later: Self) -> Self {
+        Self {
+            files: later
+                .files
+                .into_iter()
+                .map(|(path, state)| {
+                    let state = self.files.get(&path).cloned().unwrap_or(state);
+                    (path, state)
+                })
+                .collect(),
+        }
+    }
+
+    /// Obtain paths that were added, removed, or modified relative to another snapshot.
+    pub fn changed_paths(&self, other: &Self) -> Vec<PathBuf> {
+        let mut paths = self
+            .files
+            .iter()
+            .filter(|(path, state)| other.files.get(*path) != Some(state))
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+
+        paths.extend(
+            other
+                .files
+                .keys()
+                .filter(|path| !self.files.contains_key(*path))
+                .cloned(),
+        );
+        paths.sort();
+
+        paths
+    }
+}
+
+/// Block until files under `roots` change relative to `snapshot`.
+///
+/// The filesystem is polled every `interval`. Once a change is seen, polling
+/// continues until the filesystem is stable for an interval so a burst of
+/// writes (e.g. from a version control checkout) results in a single event.
+///
+/// Returns the paths that changed along with the snapshot they were
+/// compared against.
+pub fn wait_for_changes(
+    snapshot: &FileSnapshot,
+    roots: &[PathBuf],
+    excludes: &[PathBuf],
+    interval: Duration,
+) -> (Vec<PathBuf>, FileSnapshot) {
+    let mut current = loop {
+        std::thread::sleep(interval);
+
+        let current = FileSnapshot::capture(roots, excludes);
+        if &current != snapshot {
+            break current;
+        }
+    };
+
+    loop {
+        std::thread::sleep(interval);
+
+        let next = FileSnapshot::capture(roots, excludes);
+        if next == current {
+            break;
+        }
+        current = next;
+    }
+
+    (current.changed_paths(snapshot), current)
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, anyhow::Result};
+
+    #[test]
+    fn test_snapshot_changes() -> Result<()> {
+        let temp_dir = tempfile::Builder::new()
+            .prefix("pyoxidizer-test")
+            .tempdir()?;
+        let root = temp_dir.path().to_path_buf();
+
+        std::fs::write(root.join("pyoxidizer.bzl"), b"foo")?;
+        std::fs::create_dir_all(root.join("pkg").join("__pycache__"))?;
+        std::fs::write(root.join("pkg").join("__init__.py"), b"")?;
+        std::fs::write(
+            root.join("pkg").join("__pycache__").join("__init__.pyc"),
+            b"",
+        )?;
+        std::fs::create_dir_all(root.join("build"))?;
+        std::fs::write(root.join("build").join("out"), b"")?;
+
+        let roots = vec![root.clone()];
+        let excludes = vec![root.join("build")];
+
+        let before = FileSnapshot::capture(&roots, &excludes);
+        assert_eq!(before.len(), 2);
+
+        std::fs::write(root.join("build").join("out"), b"changed")?;
+        std::fs::write(
+            root.join("pkg").join("__pycache__").join("__init__.pyc"),
+            b"changed",
+        )?;
+        assert_eq!(FileSnapshot::capture(&roots, &excludes), before);
+
+        std::fs::write(root.join("pyoxidizer.bzl"), b"foobar")?;
+        std::fs::write(root.join("pkg").join("new.py"), b"")?;
+        std::fs::remove_file(root.join("pkg").join("__init__.py"))?;
+
+        let after = FileSnapshot::capture(&roots, &excludes);
+        assert_eq!(
+            after.changed_paths(&before),
+            vec![
+                root.join("pkg").join("__init__.py"),
+                root.join("pkg").join("new.py"),
+                root.join("pyoxidizer.bzl"),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_retarget() -> Result<()> {
+        let temp_dir = tempfile::Builder::new()
+            .prefix("pyoxidizer-test")
+            .tempdir()?;
+        let root = temp_dir.path().to_path_buf();
+        let src = root.join("src");
+
+        std::fs::write(root.join("pyoxidizer.bzl"), b"foo")?;
+        std::fs::create_dir_all(&src)?;
+        std::fs::write(src.join("app.py"), b"")?;
+
+        let before = FileSnapshot::capture(&[root.clone()], &[src.clone()]);
+        assert_eq!(before.len(), 1);
+
+        // Modified after the first capture, e.g. while building.
+        std::fs::write(root.join("pyoxidizer.bzl"), b"foobar")?;
+
+        let roots = vec![root.clone(), src.clone()];
+        let after = FileSnapshot::capture(&roots, &[]);
+        let snapshot = before.retarget(after.clone());
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(
+            after.changed_paths(&snapshot),
+            vec![root.join("pyoxidizer.bzl")]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,8 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+fn main() {
+    // Emit Py_3_X cfg flags so code can be conditional on the Python version.
+    pyo3_build_config::use_pyo3_cfgs();
+}
@@ -9,6 +9,8 @@ This module defines a Python meta path importer and associated functionality
 for importing Python modules from memory.
 */
 
+#[cfg(unix)]
+use std::ffi::CString;
 #[cfg(windows)]
 use {
     crate::memory_dll::{free_library_memory, get_proc_address_memory, load_library_memory},
@@ -22,20 +24,28 @@ use {
         path_entry_finder::OxidizedPathEntryFinder,
         pkg_resources::register_pkg_resources_with_module,
         python_resources::{
-            pyobject_to_resource, ModuleFlavor, OxidizedResource, PythonResourcesState,
+            pyobject_to_resource, ImportablePythonModule, ModuleFlavor, OxidizedResource,
+            PythonResourcesState, RuntimeResourceLocation,
         },
         resource_reader::OxidizedResourceReader,
         OXIDIZED_IMPORTER_NAME_STR,
     },
+    once_cell::sync::OnceCell,
     pyo3::{
-        exceptions::{PyImportError, PyValueError},
+        exceptions::{PyFileNotFoundError, PyImportError, PyValueError},
         ffi as pyffi,
         prelude::*,
         types::{PyBytes, PyDict, PyList, PyString, PyTuple},
         AsPyPointer, FromPyPointer, PyNativeType, PyTraverseError, PyVisit,
     },
     python_packaging::resource::BytecodeOptimizationLevel,
-    std::sync::Arc,
+    std::{
+        path::{Path, PathBuf},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+    },
 };
 
 #[cfg(windows)]
@@ -112,10 +122,99 @@ fn extension_module_shared_library_create_module(
     _sys_modules: &PyAny,
     _spec: &PyAny,
     _name_py: &PyAny,
-    _name: &str,
+    name: &str,
     _library_data: &[u8],
 ) -> PyResult<Py<PyAny>> {
-    panic!("should only be called on Windows");
+    Err(PyImportError::new_err((
+        "extension modules cannot be loaded from memory on this platform; an extraction directory must be configured",
+        name.to_owned(),
+    )))
+}
+
+/// Write data to a file unless the file already has identical content.
+fn write_extracted_file(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    if std::fs::read(path)
+        .map(|existing| existing == data)
+        .unwrap_or(false)
+    {
+        return Ok(());
+    }
+
+    // Write to a temporary file and rename so other processes never observe
+    // a partially written library.
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(format!(".{}.tmp", std::process::id()));
+    let temp_path = PathBuf::from(temp_path);
+
+    std::fs::write(&temp_path, data)?;
+    std::fs::rename(&temp_path, path)
+}
+
+/// Load a shared library so it satisfies dependencies of libraries loaded later.
+///
+/// The dynamic loader matches already loaded libraries by `SONAME`, so this
+/// works regardless of the library's filename or the `RPATH` of dependents.
+#[cfg(unix)]
+fn preload_shared_library(path: &Path) -> std::io::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path_c = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+
+    // The handle is intentionally leaked: the library needs to remain loaded for
+    // as long as extension modules linking it are.
+    let handle = unsafe { libc::dlopen(path_c.as_ptr(), libc::RTLD_NOW | libc::RTLD_GLOBAL) };
+
+    if handle.is_null() {
+        let error = unsafe { libc::dlerror() };
+        let message = if error.is_null() {
+            "unknown error".to_string()
+        } else {
+            unsafe { std::ffi::CStr::from_ptr(error) }
+                .to_string_lossy()
+                .to_string()
+        };
+
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("unable to load {}: {}", path.display(), message),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Windows searches the directory of an extension module for its DLL dependencies.
+#[cfg(windows)]
+fn preload_shared_library(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Write an in-memory extension module and its shared library dependencies to a directory.
+///
+/// Returns the path of the written extension module.
+fn extract_extension_module(
+    resources_state: &PythonResourcesState<u8>,
+    module: &ImportablePythonModule<u8>,
+    extraction_dir: &Path,
+    name: &str,
+    suffix: &str,
+    library_data: &[u8],
+) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(extraction_dir)?;
+
+    for dependency in module.shared_library_dependency_names().iter().flatten() {
+        if let Some(data) = resources_state.resolve_in_memory_shared_library_data(dependency) {
+            let path = extraction_dir.join(dependency.as_ref());
+            write_extracted_file(&path, data)?;
+            preload_shared_library(&path)?;
+        }
+    }
+
+    let path = extraction_dir.join(format!("{}{}", name, suffix));
+    write_extracted_file(&path, library_data)?;
+
+    Ok(path)
 }
 
 /// Reimplementation of `_PyImport_LoadDynamicModuleWithSpec()`.
@@ -271,6 +370,16 @@ pub struct ImporterState {
     pub(crate) multiprocessing_set_start_method: Option<String>,
     /// Whether to automatically register ourself with `pkg_resources` when it is imported.
     pub(crate) pkg_resources_import_auto_register: bool,
+    /// Whether to defer execution of module code until first attribute access.
+    ///
+    /// When set, specs for source and bytecode modules are wrapped with
+    /// `importlib.util.LazyLoader`.
+    lazy_loading: AtomicBool,
+    /// Whether we are in the middle of importing `importlib.util` to resolve
+    /// `LazyLoader`. Used to prevent recursion.
+    lazy_loader_resolving: AtomicBool,
+    /// `importlib.util.LazyLoader` class. Resolved on first lazy import.
+    lazy_loader_type: OnceCell<Py<PyAny>>,
     /// Holds state about importable resources.
     ///
     /// This field is a PyCapsule and is a glorified wrapper around
@@ -389,6 +498,9 @@ impl ImporterState {
             multiprocessing_set_start_method: None,
             // TODO value should come from config.
             pkg_resources_import_auto_register: true,
+            lazy_loading: AtomicBool::new(false),
+            lazy_loader_resolving: AtomicBool::new(false),
+            lazy_loader_type: OnceCell::new(),
             resources_state: capsule,
         })
     }
@@ -411,6 +523,10 @@ impl ImporterState {
         visit.call(&self.exec_fn)?;
         visit.call(&self.resources_state)?;
 
+        if let Some(lazy_loader_type) = self.lazy_loader_type.get() {
+            visit.call(lazy_loader_type)?;
+        }
+
         Ok(())
     }
 
@@ -448,6 +564,45 @@ impl ImporterState {
     pub fn set_multiprocessing_set_start_method(&mut self, value: Option<String>) {
         self.multiprocessing_set_start_method = value;
     }
+
+    /// Whether module code execution is deferred until first attribute access.
+    pub fn lazy_loading(&self) -> bool {
+        self.lazy_loading.load(Ordering::Relaxed)
+    }
+
+    /// Set whether to defer execution of module code until first attribute access.
+    pub fn set_lazy_loading(&self, value: bool) {
+        self.lazy_loading.store(value, Ordering::Relaxed);
+    }
+
+    /// Obtain the `importlib.util.LazyLoader` type to wrap loaders with.
+    ///
+    /// Returns `None` if lazy loading is disabled or if we are being called
+    /// recursively while `importlib.util` itself is being imported. Modules
+    /// imported to satisfy the resolution of `LazyLoader` are loaded eagerly.
+    fn lazy_loader_type<'p>(&self, py: Python<'p>) -> PyResult<Option<&'p PyAny>> {
+        if !self.lazy_loading() {
+            return Ok(None);
+        }
+
+        if let Some(t) = self.lazy_loader_type.get() {
+            return Ok(Some(t.as_ref(py)));
+        }
+
+        if self.lazy_loader_resolving.swap(true, Ordering::SeqCst) {
+            return Ok(None);
+        }
+
+        let res = py
+            .import("importlib.util")
+            .and_then(|m| m.getattr("LazyLoader"));
+        self.lazy_loader_resolving.store(false, Ordering::SeqCst);
+
+        let t = res?.into_py(py);
+        let t = self.lazy_loader_type.get_or_init(|| t);
+
+        Ok(Some(t.as_ref(py)))
+    }
 }
 
 impl Drop for ImporterState {
@@ -463,6 +618,56 @@ impl Drop for ImporterState {
     }
 }
 
+/// Parse resource locations from their string representation.
+fn resource_locations_from_strings(values: Vec<String>) -> PyResult<Vec<RuntimeResourceLocation>> {
+    values
+        .iter()
+        .map(|value| RuntimeResourceLocation::try_from(value.as_str()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(PyValueError::new_err)
+}
+
+/// Find a module spec in a filesystem directory treated as a `sys.path` entry.
+///
+/// Only regular modules and packages are found. Namespace package portions are
+/// ignored so they don't shadow modules available from other locations.
+fn find_spec_in_directory<'p>(
+    py: Python<'p>,
+    fullname: &str,
+    directory: &Path,
+) -> PyResult<&'p PyAny> {
+    let mut search_path = directory.to_path_buf();
+    if let Some((parent, _)) = fullname.rsplit_once('.') {
+        search_path.extend(parent.split('.'));
+    }
+
+    if !search_path.is_dir() {
+        return Ok(py.None().into_ref(py));
+    }
+
+    let bootstrap_external = py.import("_frozen_importlib_external")?;
+
+    let mut args = vec![search_path.into_py(py)];
+    for details in bootstrap_external
+        .call_method0("_get_supported_file_loaders")?
+        .iter()?
+    {
+        args.push(details?.into_py(py));
+    }
+
+    let file_finder = bootstrap_external
+        .getattr("FileFinder")?
+        .call1(PyTuple::new(py, args))?;
+
+    let spec = file_finder.call_method1("find_spec", (fullname,))?;
+
+    if !spec.is_none() && spec.getattr("loader")?.is_none() {
+        Ok(py.None().into_ref(py))
+    } else {
+        Ok(spec)
+    }
+}
+
 /// Python type to import modules.
 ///
 /// This type implements the importlib.abc.MetaPathFinder interface for
@@ -504,19 +709,11 @@ impl OxidizedFinder {
             state: importer_state,
         })
     }
-}
-
-#[pymethods]
-impl OxidizedFinder {
-    fn __traverse__(&self, visit: PyVisit) -> Result<(), PyTraverseError> {
-        self.state.gc_traverse(visit)
-    }
-
-    // Start of importlib.abc.MetaPathFinder interface.
 
-    #[pyo3(signature=(fullname, path, target=None))]
-    fn find_spec<'p>(
+    /// Resolve the `ModuleSpec` for a module known to our resources.
+    fn resolve_spec<'p>(
         slf: &'p PyCell<Self>,
+        module: &ImportablePythonModule<u8>,
         fullname: String,
         path: &PyAny,
         target: Option<&PyAny>,
@@ -524,22 +721,28 @@ impl OxidizedFinder {
         let py = slf.py();
         let finder = slf.borrow();
 
-        let module = match finder
-            .state
-            .get_resources_state()
-            .resolve_importable_module(&fullname, finder.state.optimize_level)
-        {
-            Some(module) => module,
-            None => return Ok(py.None().into_ref(py)),
-        };
-
         match module.flavor {
-            ModuleFlavor::Extension | ModuleFlavor::SourceBytecode => module.resolve_module_spec(
+            ModuleFlavor::Extension => module.resolve_module_spec(
                 py,
                 finder.state.module_spec_type.clone_ref(py).into_ref(py),
                 slf,
                 finder.state.optimize_level,
             ),
+            ModuleFlavor::SourceBytecode => {
+                // LazyLoader wraps us and defers exec_module() until first attribute
+                // access on the module.
+                let loader = match finder.state.lazy_loader_type(py)? {
+                    Some(lazy_loader_type) => lazy_loader_type.call1((slf,))?,
+                    None => slf.as_ref(),
+                };
+
+                module.resolve_module_spec(
+                    py,
+                    finder.state.module_spec_type.clone_ref(py).into_ref(py),
+                    loader,
+                    finder.state.optimize_level,
+                )
+            }
             ModuleFlavor::Builtin => {
                 // BuiltinImporter.find_spec() always returns None if `path` is defined.
                 // And it doesn't use `target`. So don't proxy these values.
@@ -554,7 +757,105 @@ impl OxidizedFinder {
                 .frozen_importer
                 .call_method(py, "find_spec", (fullname, path, target), None)?
                 .into_ref(py)),
+            ModuleFlavor::Namespace => {
+                // A spec without a loader is how the import machinery recognizes
+                // namespace packages.
+                let spec = module.resolve_module_spec(
+                    py,
+                    finder.state.module_spec_type.clone_ref(py).into_ref(py),
+                    py.None().into_ref(py),
+                    finder.state.optimize_level,
+                )?;
+
+                // Portions of a namespace package may be provided by other
+                // distributions on the filesystem. Defer to the path based importer,
+                // if it is active, so those portions are discovered.
+                let path_finder = py
+                    .import("_frozen_importlib_external")?
+                    .getattr("PathFinder")?;
+                let meta_path = finder.state.sys_module.getattr(py, "meta_path")?;
+
+                if !meta_path.as_ref(py).contains(path_finder)? {
+                    return Ok(spec);
+                }
+
+                let fs_spec = path_finder.call_method("find_spec", (&fullname, path), None)?;
+
+                if fs_spec.is_none() {
+                    Ok(spec)
+                } else if !fs_spec.getattr("origin")?.is_none() {
+                    // A regular package takes precedence over namespace portions.
+                    // Let the path based importer load it.
+                    Ok(py.None().into_ref(py))
+                } else {
+                    // Merge our locations into the filesystem namespace path so
+                    // `__path__` reflects all portions.
+                    let locations = fs_spec.getattr("submodule_search_locations")?;
+                    for location in spec.getattr("submodule_search_locations")?.iter()? {
+                        locations.call_method1("append", (location?,))?;
+                    }
+
+                    Ok(fs_spec)
+                }
+            }
+        }
+    }
+}
+
+#[pymethods]
+impl OxidizedFinder {
+    fn __traverse__(&self, visit: PyVisit) -> Result<(), PyTraverseError> {
+        self.state.gc_traverse(visit)
+    }
+
+    // Start of importlib.abc.MetaPathFinder interface.
+
+    #[pyo3(signature=(fullname, path, target=None))]
+    fn find_spec<'p>(
+        slf: &'p PyCell<Self>,
+        fullname: String,
+        path: &PyAny,
+        target: Option<&PyAny>,
+    ) -> PyResult<&'p PyAny> {
+        let py = slf.py();
+        let finder = slf.borrow();
+        let resources_state = finder.state.get_resources_state();
+
+        let module =
+            resources_state.resolve_importable_module(&fullname, finder.state.optimize_level);
+
+        // Built-in and frozen modules are part of the interpreter and aren't
+        // subject to resource locations.
+        if let Some(module) = &module {
+            if matches!(module.flavor, ModuleFlavor::Builtin | ModuleFlavor::Frozen) {
+                return Self::resolve_spec(slf, module, fullname, path, target);
+            }
+        }
+
+        for location in resources_state.resolve_resource_locations(&fullname) {
+            match location {
+                RuntimeResourceLocation::Filesystem(directory) => {
+                    let spec = find_spec_in_directory(
+                        py,
+                        &fullname,
+                        &resources_state.origin().join(directory),
+                    )?;
+
+                    if !spec.is_none() {
+                        return Ok(spec);
+                    }
+                }
+                location => {
+                    if let Some(module) = &module {
+                        if module.is_available_at(location) {
+                            return Self::resolve_spec(slf, module, fullname, path, target);
+                        }
+                    }
+                }
+            }
         }
+
+        Ok(py.None().into_ref(py))
     }
 
     fn find_module<'p>(
@@ -607,25 +908,55 @@ impl OxidizedFinder {
             // potentially work around this and move all extension module
             // initialization into `exec_module()`.
             if let Some(library_data) = &module.in_memory_extension_module_shared_library() {
-                let sys_modules = state.sys_module.getattr(py, "modules")?;
+                let resources_state = state.get_resources_state();
+
+                if let Some(extraction_dir) = resources_state.extension_modules_extraction_path() {
+                    let suffixes = state
+                        .imp_module
+                        .getattr(py, "extension_suffixes")?
+                        .call0(py)?
+                        .extract::<Vec<String>>(py)?;
+                    let suffix = suffixes.first().map(|s| s.as_str()).unwrap_or_default();
+
+                    let path = extract_extension_module(
+                        resources_state,
+                        &module,
+                        extraction_dir,
+                        &key,
+                        suffix,
+                        library_data,
+                    )
+                    .map_err(|e| {
+                        PyImportError::new_err((
+                            format!("error extracting extension module {}: {}", key, e),
+                            key.clone(),
+                        ))
+                    })?;
+
+                    // `imp.create_dynamic()` loads the library from the spec's origin.
+                    spec.setattr("origin", path.into_py(py))?;
+                    spec.setattr("has_location", true)?;
+                } else {
+                    let sys_modules = state.sys_module.getattr(py, "modules")?;
+
+                    return extension_module_shared_library_create_module(
+                        resources_state,
+                        py,
+                        sys_modules.into_ref(py),
+                        spec,
+                        name,
+                        &key,
+                        library_data,
+                    );
+                }
+            }
 
-                extension_module_shared_library_create_module(
-                    state.get_resources_state(),
-                    py,
-                    sys_modules.into_ref(py),
-                    spec,
-                    name,
-                    &key,
-                    library_data,
-                )
-            } else {
-                // Call `imp.create_dynamic()` for dynamic extension modules.
-                let create_dynamic = state.imp_module.getattr(py, "create_dynamic")?;
+            // Call `imp.create_dynamic()` for dynamic extension modules.
+            let create_dynamic = state.imp_module.getattr(py, "create_dynamic")?;
 
-                state
-                    .call_with_frames_removed
-                    .call(py, (&create_dynamic, spec), None)
-            }
+            state
+                .call_with_frames_removed
+                .call(py, (&create_dynamic, spec), None)
         } else {
             Ok(py.None())
         }
@@ -940,6 +1271,110 @@ impl OxidizedFinder {
         }
     }
 
+    #[getter]
+    fn lazy_loading(&self) -> bool {
+        self.state.lazy_loading()
+    }
+
+    #[setter]
+    fn set_lazy_loading(&self, value: bool) {
+        self.state.set_lazy_loading(value)
+    }
+
+    #[getter]
+    fn resource_locations(&self) -> Vec<String> {
+        self.state
+            .get_resources_state()
+            .resource_locations()
+            .iter()
+            .map(|location| location.to_string())
+            .collect()
+    }
+
+    #[setter]
+    fn set_resource_locations(&self, value: Vec<String>) -> PyResult<()> {
+        let locations = resource_locations_from_strings(value)?;
+
+        self.state
+            .get_resources_state_mut()
+            .set_resource_locations(locations);
+
+        Ok(())
+    }
+
+    fn get_package_resource_locations(&self, package: &str) -> Option<Vec<String>> {
+        self.state
+            .get_resources_state()
+            .package_resource_locations(package)
+            .map(|locations| {
+                locations
+                    .iter()
+                    .map(|location| location.to_string())
+                    .collect()
+            })
+    }
+
+    fn set_package_resource_locations(
+        &self,
+        package: &str,
+        locations: Option<Vec<String>>,
+    ) -> PyResult<()> {
+        let locations = locations.map(resource_locations_from_strings).transpose()?;
+
+        self.state
+            .get_resources_state_mut()
+            .set_package_resource_locations(package, locations);
+
+        Ok(())
+    }
+
+    #[getter]
+    fn file_emulation(&self) -> bool {
+        self.state.get_resources_state().file_emulation()
+    }
+
+    #[setter]
+    fn set_file_emulation(&self, value: bool) {
+        self.state
+            .get_resources_state_mut()
+            .set_file_emulation(value);
+    }
+
+    fn get_package_file_emulation(&self, package: &str) -> Option<bool> {
+        self.state
+            .get_resources_state()
+            .package_file_emulation(package)
+    }
+
+    fn set_package_file_emulation(&self, package: &str, value: Option<bool>) {
+        self.state
+            .get_resources_state_mut()
+            .set_package_file_emulation(package, value);
+    }
+
+    #[getter]
+    fn extension_modules_extraction_path<'p>(&self, py: Python<'p>) -> Option<&'p PyAny> {
+        self.state
+            .get_resources_state()
+            .extension_modules_extraction_path()
+            .map(|path| path.into_py(py).into_ref(py))
+    }
+
+    #[setter]
+    fn set_extension_modules_extraction_path(&self, py: Python, value: &PyAny) -> PyResult<()> {
+        let path = if value.is_none() {
+            None
+        } else {
+            Some(pyobject_to_pathbuf(py, value)?)
+        };
+
+        self.state
+            .get_resources_state_mut()
+            .set_extension_modules_extraction_path(path);
+
+        Ok(())
+    }
+
     #[getter]
     fn origin<'p>(&self, py: Python<'p>) -> &'p PyAny {
         self.state
@@ -1026,6 +1461,36 @@ impl OxidizedFinder {
         Ok(())
     }
 
+    fn data_namespaces(&self) -> Vec<String> {
+        self.state
+            .get_resources_state()
+            .data_namespaces()
+            .into_iter()
+            .map(|namespace| namespace.to_string())
+            .collect()
+    }
+
+    fn data_paths(&self, namespace: &str) -> Vec<String> {
+        self.state
+            .get_resources_state()
+            .data_paths(namespace)
+            .into_iter()
+            .map(|path| path.to_string())
+            .collect()
+    }
+
+    fn read_data<'p>(&self, py: Python<'p>, namespace: &str, path: &str) -> PyResult<&'p PyBytes> {
+        let resources_state = self.state.get_resources_state();
+
+        match resources_state.resolve_data(namespace, path)? {
+            Some(data) => Ok(PyBytes::new(py, &data)),
+            None => Err(PyFileNotFoundError::new_err(format!(
+                "data {}/{} not found",
+                namespace, path
+            ))),
+        }
+    }
+
     fn indexed_resources<'p>(&self, py: Python<'p>) -> PyResult<&'p PyList> {
         let resources_state = self.state.get_resources_state();
 
@@ -1198,80 +1663,6 @@ impl OxidizedFinder {
     }
 }
 
-/// Path-like object facilitating Python resource access.
-///
-/// This implements importlib.abc.Traversable.
-#[pyclass(module = "oxidized_importer")]
-pub(crate) struct PyOxidizerTraversable {
-    state: Arc<ImporterState>,
-    path: String,
-}
-
-#[pymethods]
-impl PyOxidizerTraversable {
-    /// Yield Traversable objects in self.
-    fn iterdir(&self) -> PyResult<&PyAny> {
-        unimplemented!()
-    }
-
-    /// Read contents of self as bytes.
-    fn read_bytes(&self) -> PyResult<&PyAny> {
-        unimplemented!()
-    }
-
-    /// Read contents of self as text.
-    fn read_text(&self) -> PyResult<&PyAny> {
-        unimplemented!()
-    }
-
-    /// Return True if self is a dir.
-    fn is_dir(&self) -> PyResult<bool> {
-        // We are a directory if the current path is a known package.
-        // TODO We may need to expand this definition in the future to cover
-        // virtual subdirectories in addressable resources. But this will require
-        // changes to the resources data format to capture said annotations.
-        if let Some(entry) = self
-            .state
-            .get_resources_state()
-            .resolve_importable_module(&self.path, self.state.optimize_level)
-        {
-            if entry.is_package {
-                return Ok(true);
-            }
-        }
-
-        Ok(false)
-    }
-
-    /// Return True if self is a file.
-    fn is_file(&self) -> PyResult<&PyAny> {
-        unimplemented!()
-    }
-
-    /// Return Traversable child in self.
-    #[allow(unused)]
-    fn joinpath(&self, child: &PyAny) -> PyResult<&PyAny> {
-        unimplemented!()
-    }
-
-    /// Return Traversable child in self.
-    #[allow(unused)]
-    fn __truediv__(&self, child: &PyAny) -> PyResult<&PyAny> {
-        unimplemented!()
-    }
-
-    /// mode may be 'r' or 'rb' to open as text or binary. Return a handle
-    /// suitable for reading (same as pathlib.Path.open).
-    ///
-    /// When opening as text, accepts encoding parameters such as those
-    /// accepted by io.TextIOWrapper.
-    #[allow(unused)]
-    #[pyo3(signature=(*py_args, **py_kwargs))]
-    fn open(&self, py_args: &PyTuple, py_kwargs: Option<&PyDict>) -> PyResult<&PyAny> {
-        unimplemented!()
-    }
-}
-
 /// Replace all meta path importers with an OxidizedFinder instance and return it.
 ///
 /// This is called after PyInit_* to finish the initialization of the
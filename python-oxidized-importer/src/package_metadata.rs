@@ -9,10 +9,11 @@ use {
         python_resources::{name_at_package_hierarchy, name_within_package_hierarchy},
     },
     pyo3::{
-        exceptions::{PyIOError, PyNotImplementedError, PyValueError},
+        exceptions::{PyIOError, PyValueError},
         prelude::*,
         types::{PyBytes, PyDict, PyList, PyString, PyTuple, PyType},
     },
+    python_packaging::package_metadata::normalize_distribution_name,
     std::{collections::BTreeMap, sync::Arc},
 };
 
@@ -47,6 +48,43 @@ impl OxidizedDistribution {
     }
 }
 
+impl OxidizedDistribution {
+    /// Obtain rows describing files in this distribution.
+    ///
+    /// Rows are parsed from `RECORD` if available. Otherwise the egg-info
+    /// `SOURCES.txt` is used and rows only contain a path.
+    fn file_rows(&self, py: Python) -> PyResult<Option<Vec<Vec<String>>>> {
+        let text = self.read_text(py, "RECORD".into())?;
+
+        if !text.is_none() {
+            let reader = py
+                .import("csv")?
+                .call_method1("reader", (text.call_method0("splitlines")?,))?;
+
+            return Ok(Some(
+                reader
+                    .iter()?
+                    .map(|row| row?.extract::<Vec<String>>())
+                    .collect::<PyResult<Vec<_>>>()?,
+            ));
+        }
+
+        let text = self.read_text(py, "SOURCES.txt".into())?;
+
+        if text.is_none() {
+            return Ok(None);
+        }
+
+        Ok(Some(
+            text.extract::<String>()?
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(|line| vec![line.to_string()])
+                .collect::<Vec<_>>(),
+        ))
+    }
+}
+
 #[pymethods]
 impl OxidizedDistribution {
     #[allow(unused)]
@@ -227,9 +265,70 @@ impl OxidizedDistribution {
         }
     }
 
+    /// Files in this distribution.
+    ///
+    /// Derived from `RECORD` or, failing that, `SOURCES.txt`. Returns `None` if
+    /// neither is present.
     #[getter]
-    fn files(&self) -> PyResult<()> {
-        Err(PyNotImplementedError::new_err(()))
+    fn files<'p>(self_: PyRef<Self>, py: Python<'p>) -> PyResult<&'p PyAny> {
+        let rows = self_.file_rows(py)?;
+
+        let rows = if let Some(rows) = rows {
+            rows
+        } else {
+            return Ok(py.None().into_ref(py));
+        };
+
+        let importlib_metadata = py.import("importlib.metadata")?;
+        let package_path_type = importlib_metadata.getattr("PackagePath")?;
+        let file_hash_type = importlib_metadata.getattr("FileHash")?;
+
+        let dist = self_.into_py(py);
+        let files = PyList::empty(py);
+
+        for row in rows {
+            let name = if let Some(name) = row.first() {
+                name
+            } else {
+                continue;
+            };
+
+            let path = package_path_type.call1((name,))?;
+
+            let hash = match row.get(1) {
+                Some(hash) if !hash.is_empty() => file_hash_type.call1((hash,))?,
+                _ => py.None().into_ref(py),
+            };
+
+            let size = match row.get(2) {
+                Some(size) if !size.is_empty() => size
+                    .parse::<u64>()
+                    .map_err(|e| PyValueError::new_err(format!("invalid file size: {}", e)))?
+                    .into_py(py),
+                _ => py.None(),
+            };
+
+            path.setattr("hash", hash)?;
+            path.setattr("size", size)?;
+            path.setattr("dist", &dist)?;
+
+            files.append(path)?;
+        }
+
+        Ok(files.into())
+    }
+
+    /// Resolve a path to a file in this distribution.
+    ///
+    /// Resources are typically held in memory. So the returned path is virtual
+    /// and anchored at the path the `OxidizedFinder` path hook responds to.
+    fn locate_file<'p>(&self, py: Python<'p>, path: &PyAny) -> PyResult<&'p PyAny> {
+        let base = py
+            .import("pathlib")?
+            .getattr("Path")?
+            .call1((self.state.get_resources_state().current_exe(),))?;
+
+        base.call_method1("joinpath", (path,))
     }
 
     #[getter]
@@ -272,17 +371,14 @@ pub(crate) fn find_distributions<'p>(
     name: Option<&PyAny>,
     _path: Option<&PyAny>,
 ) -> PyResult<&'p PyList> {
+    // Python normalizes the name. We do the same.
+    let name = name.map(|name| normalize_distribution_name(&name.to_string()));
+
     let distributions = state
         .get_resources_state()
         .package_distribution_names(|match_name| {
-            if let Some(name) = name {
-                // Python normalizes the name. We do the same.
-                let name = name.to_string();
-                let name = name.to_lowercase().replace('-', "_");
-
-                let match_name = match_name.to_lowercase().replace('-', "_");
-
-                match_name == name
+            if let Some(name) = &name {
+                normalize_distribution_name(match_name) == *name
             } else {
                 true
             }
@@ -13,7 +13,7 @@ use {
         prelude::*,
         types::{PyList, PyString},
     },
-    std::sync::Arc,
+    std::{path::PathBuf, sync::Arc},
 };
 
 #[pyclass(module = "oxidized_importer")]
@@ -104,15 +104,35 @@ impl OxidizedPkgResourcesProvider {
 
     // Begin IResourceProvider interface.
 
-    #[allow(unused)]
-    fn get_resource_filename(&self, manager: &PyAny, resource_name: &PyAny) -> PyResult<&PyAny> {
-        // Raising NotImplementedError seems allowed per the implementation of
-        // pkg_resources.ZipProvider, which also raises this error when resources
-        // aren't backed by the filesystem.
-        //
-        // We could potentially expose the filename if the resource is backed
-        // by a file. But we keep things simple for now.
-        Err(PyNotImplementedError::new_err(()))
+    fn get_resource_filename<'p>(
+        &self,
+        py: Python<'p>,
+        manager: &'p PyAny,
+        resource_name: &str,
+    ) -> PyResult<&'p PyAny> {
+        let resources_state = self.state.get_resources_state();
+
+        if let Some(path) =
+            resources_state.package_resource_filesystem_path(&self.package, resource_name)
+        {
+            return Ok(path.into_py(py).into_ref(py));
+        }
+
+        // Like pkg_resources.ZipProvider, resources not backed by a file are
+        // extracted to the resource manager's cache. Directory requests extract
+        // everything in the directory.
+        if resources_state.is_package_resource(&self.package, resource_name) {
+            self.extract_resource(py, manager, resource_name)
+        } else if resources_state.is_package_resource_directory(&self.package, resource_name) {
+            for name in resources_state.package_resources_in_directory(&self.package, resource_name)
+            {
+                self.extract_resource(py, manager, &name)?;
+            }
+
+            self.cache_path(py, manager, resource_name)
+        } else {
+            Err(PyIOError::new_err("resource does not exist"))
+        }
     }
 
     #[allow(unused)]
@@ -170,6 +190,67 @@ impl OxidizedPkgResourcesProvider {
     // End IResourceProvider interface.
 }
 
+impl OxidizedPkgResourcesProvider {
+    /// Resolve the path of a resource in the resource manager's extraction cache.
+    fn cache_path<'p>(
+        &self,
+        py: Python<'p>,
+        manager: &'p PyAny,
+        resource_name: &str,
+    ) -> PyResult<&'p PyAny> {
+        let names = resource_name
+            .split(|c| c == '/' || c == '\\')
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
+
+        manager.call_method1(
+            "get_cache_path",
+            (self.package.as_str(), PyList::new(py, names)),
+        )
+    }
+
+    /// Extract a resource to the resource manager's extraction cache.
+    ///
+    /// Returns the path of the extracted file.
+    fn extract_resource<'p>(
+        &self,
+        py: Python<'p>,
+        manager: &'p PyAny,
+        resource_name: &str,
+    ) -> PyResult<&'p PyAny> {
+        let resources_state = self.state.get_resources_state();
+
+        let data = resources_state
+            .get_package_resource_data(&self.package, resource_name)?
+            .ok_or_else(|| PyIOError::new_err("resource does not exist"))?;
+
+        let cache_path = self.cache_path(py, manager, resource_name)?;
+        let path = cache_path.extract::<PathBuf>()?;
+
+        // Files extracted by an earlier request or process may be in use. So
+        // leave them alone if their content is current.
+        if matches!(std::fs::read(&path), Ok(existing) if existing == *data) {
+            return Ok(cache_path);
+        }
+
+        // Write to a temporary file and rename so other processes never see
+        // a partially written file.
+        let temp_path = path.with_file_name(format!(
+            ".{}.{}.tmp",
+            path.file_name()
+                .map(|s| s.to_string_lossy())
+                .unwrap_or_default(),
+            std::process::id()
+        ));
+
+        std::fs::write(&temp_path, &data)?;
+        manager.call_method1("postprocess", (temp_path.as_path(), path.as_path()))?;
+        std::fs::rename(&temp_path, &path)?;
+
+        Ok(cache_path)
+    }
+}
+
 pub(crate) fn create_oxidized_pkg_resources_provider(
     state: Arc<ImporterState>,
     package: String,
@@ -13,6 +13,7 @@ use {
         pyobject_to_pathbuf_optional,
     },
     anyhow::Result,
+    once_cell::sync::OnceCell,
     pyo3::{
         buffer::PyBuffer,
         exceptions::{PyImportError, PyOSError, PyValueError},
@@ -22,10 +23,11 @@ use {
         PyTypeInfo,
     },
     python_packaging::resource::BytecodeOptimizationLevel,
-    python_packed_resources::Resource,
+    python_packed_resources::{IndexedResources, Resource},
     std::{
         borrow::Cow,
-        cell::RefCell,
+        cell::{RefCell, RefMut},
+        cmp::Ordering,
         collections::{hash_map::Entry, BTreeSet, HashMap},
         ffi::CStr,
         os::raw::c_int,
@@ -101,6 +103,59 @@ pub enum ModuleFlavor {
     Frozen,
     Extension,
     SourceBytecode,
+    /// An implicit namespace package (PEP 420) without any code.
+    Namespace,
+}
+
+/// A location `OxidizedFinder` may import modules from.
+///
+/// An ordered list of these controls which locations are consulted when
+/// importing a module and which location wins when a module is available
+/// from several.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RuntimeResourceLocation {
+    /// Module data embedded in packed resources.
+    InMemory,
+    /// Module files relative to the resources origin, as defined by packed resources.
+    FilesystemRelative,
+    /// A filesystem directory searched like a `sys.path` entry.
+    ///
+    /// Useful for layering modules installed after the application was
+    /// built, such as user-installed plugins in an application directory.
+    Filesystem(PathBuf),
+}
+
+impl TryFrom<&str> for RuntimeResourceLocation {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        if value == "in-memory" {
+            Ok(Self::InMemory)
+        } else if value == "filesystem-relative" {
+            Ok(Self::FilesystemRelative)
+        } else if let Some(path) = value.strip_prefix("filesystem:") {
+            if path.is_empty() {
+                Err("filesystem: resource location requires a path".to_string())
+            } else {
+                Ok(Self::Filesystem(PathBuf::from(path)))
+            }
+        } else {
+            Err(format!(
+                "unknown resource location: {}; expected in-memory, filesystem-relative, or filesystem:<path>",
+                value
+            ))
+        }
+    }
+}
+
+impl ToString for RuntimeResourceLocation {
+    fn to_string(&self) -> String {
+        match self {
+            Self::InMemory => "in-memory".to_string(),
+            Self::FilesystemRelative => "filesystem-relative".to_string(),
+            Self::Filesystem(path) => format!("filesystem:{}", path.display()),
+        }
+    }
 }
 
 /// Holds state for an importable Python module.
@@ -120,6 +175,9 @@ where
     /// Path from which relative paths should be interpreted.
     origin: &'a Path,
 
+    /// Whether to synthesize a virtual `__file__` for modules without one.
+    emulate_file: bool,
+
     /// The type of importable module.
     pub flavor: ModuleFlavor,
     /// Whether this module is a package.
@@ -139,7 +197,10 @@ impl<'a> ImportablePythonModule<'a, u8> {
         io_module: &PyAny,
     ) -> PyResult<Option<&'p PyAny>> {
         let bytes = if let Some(data) = &self.resource.in_memory_source {
-            Some(PyBytes::new(py, data))
+            Some(PyBytes::new(
+                py,
+                &self.resource.resolve_in_memory_data(data)?,
+            ))
         } else if let Some(relative_path) = &self.resource.relative_path_module_source {
             let path = self.origin.join(relative_path);
 
@@ -185,6 +246,14 @@ impl<'a> ImportablePythonModule<'a, u8> {
             BytecodeOptimizationLevel::One => &self.resource.in_memory_bytecode_opt1,
             BytecodeOptimizationLevel::Two => &self.resource.in_memory_bytecode_opt2,
         } {
+            // Compressed data must be decompressed into a new buffer. Otherwise
+            // we can expose the memory backing the resource directly.
+            if self.resource.is_zstd_compressed {
+                let bytecode = self.resource.resolve_in_memory_data(data)?;
+
+                return Ok(Some(PyBytes::new(py, &bytecode).into_py(py)));
+            }
+
             let ptr = unsafe {
                 pyffi::PyMemoryView_FromMemory(
                     data.as_ptr() as _,
@@ -357,7 +426,14 @@ impl<'a> ImportablePythonModule<'a, u8> {
                 .resource
                 .relative_path_module_source
                 .as_ref()
-                .map(|path| self.origin.join(path)),
+                .map(|path| self.origin.join(path))
+                .or_else(|| {
+                    if self.emulate_file {
+                        Some(self.emulated_file_path())
+                    } else {
+                        None
+                    }
+                }),
             ModuleFlavor::Extension => self
                 .resource
                 .relative_path_extension_module_shared_library
@@ -367,6 +443,26 @@ impl<'a> ImportablePythonModule<'a, u8> {
         }
     }
 
+    /// Obtain the virtual path of this module's source file.
+    ///
+    /// This is the path relative to the current executable the module would
+    /// have if the executable were a directory. e.g. `/path/to/myapp/foo/bar.py`
+    /// or `/path/to/myapp/foo/__init__.py`. This is consistent with the
+    /// `__path__` of in-memory packages and with paths understood by
+    /// `get_data()`.
+    fn emulated_file_path(&self) -> PathBuf {
+        let mut path = self.current_exe.to_path_buf();
+        path.extend(self.resource.name.split('.'));
+
+        if self.is_package {
+            path.push("__init__.py");
+        } else {
+            path.set_extension("py");
+        }
+
+        path
+    }
+
     /// Obtain the filesystem path to bytecode for this module.
     fn bytecode_path(&self, optimize_level: BytecodeOptimizationLevel) -> Option<PathBuf> {
         let bytecode_path = match optimize_level {
@@ -383,6 +479,44 @@ impl<'a> ImportablePythonModule<'a, u8> {
     pub fn in_memory_extension_module_shared_library(&self) -> &'a Option<Cow<'a, [u8]>> {
         &self.resource.in_memory_extension_module_shared_library
     }
+
+    /// Names of shared libraries this extension module depends on.
+    pub fn shared_library_dependency_names(&self) -> &'a Option<Vec<Cow<'a, str>>> {
+        &self.resource.shared_library_dependency_names
+    }
+
+    /// Whether this module can be loaded from the given packed resources location.
+    ///
+    /// Filesystem directory locations are never satisfied by packed resources.
+    pub fn is_available_at(&self, location: &RuntimeResourceLocation) -> bool {
+        let resource = self.resource;
+
+        match (&self.flavor, location) {
+            (_, RuntimeResourceLocation::Filesystem(_)) => false,
+            // Built-in and frozen modules are part of the interpreter.
+            (ModuleFlavor::Builtin | ModuleFlavor::Frozen, _) => true,
+            // Namespace packages have no data and can be served from anywhere.
+            (ModuleFlavor::Namespace, _) => true,
+            (ModuleFlavor::Extension, RuntimeResourceLocation::InMemory) => {
+                resource.in_memory_extension_module_shared_library.is_some()
+            }
+            (ModuleFlavor::Extension, RuntimeResourceLocation::FilesystemRelative) => resource
+                .relative_path_extension_module_shared_library
+                .is_some(),
+            (ModuleFlavor::SourceBytecode, RuntimeResourceLocation::InMemory) => {
+                resource.in_memory_source.is_some()
+                    || resource.in_memory_bytecode.is_some()
+                    || resource.in_memory_bytecode_opt1.is_some()
+                    || resource.in_memory_bytecode_opt2.is_some()
+            }
+            (ModuleFlavor::SourceBytecode, RuntimeResourceLocation::FilesystemRelative) => {
+                resource.relative_path_module_source.is_some()
+                    || resource.relative_path_module_bytecode.is_some()
+                    || resource.relative_path_module_bytecode_opt1.is_some()
+                    || resource.relative_path_module_bytecode_opt2.is_some()
+            }
+        }
+    }
 }
 
 /// A source for packed resources data.
@@ -402,6 +536,84 @@ impl<'a> From<&'a [u8]> for PackedResourcesSource<'a> {
     }
 }
 
+/// Version 4 packed resources data whose resources are parsed on demand.
+#[derive(Debug)]
+struct LazyIndexedResources<'a> {
+    indexed: IndexedResources<'a>,
+
+    /// Parsed resources, by index.
+    ///
+    /// `None` records a resource that failed to parse.
+    parsed: Vec<OnceCell<Option<Box<Resource<'a, u8>>>>>,
+}
+
+impl<'a> LazyIndexedResources<'a> {
+    fn new(indexed: IndexedResources<'a>) -> Self {
+        Self {
+            indexed,
+            parsed: (0..indexed.len()).map(|_| OnceCell::new()).collect(),
+        }
+    }
+
+    /// Obtain the resource at an index, parsing it if necessary.
+    fn get(&self, index: usize) -> Option<&Resource<'a, u8>> {
+        self.parsed
+            .get(index)?
+            .get_or_init(|| self.indexed.get(index).ok().map(Box::new))
+            .as_deref()
+    }
+
+    /// Find a resource by name.
+    fn find(&self, name: &str) -> Option<&Resource<'a, u8>> {
+        self.indexed
+            .candidate_indices(name)
+            .filter_map(|index| self.get(index))
+            .find(|resource| resource.name == name)
+    }
+
+    /// Iterate over all resources, parsing them if necessary.
+    fn iter(&self) -> impl Iterator<Item = &Resource<'a, u8>> {
+        (0..self.parsed.len()).filter_map(|index| self.get(index))
+    }
+}
+
+/// Find pairs of indices of resources having the same name hash in 2 data sets.
+fn colliding_indices(a: &IndexedResources, b: &IndexedResources) -> Vec<(usize, usize)> {
+    let mut a = a.hash_records().peekable();
+    let mut b = b.hash_records().peekable();
+    let mut pairs = vec![];
+
+    loop {
+        let (a_hash, b_hash) = match (a.peek(), b.peek()) {
+            (Some((a_hash, _)), Some((b_hash, _))) => (*a_hash, *b_hash),
+            _ => break,
+        };
+
+        match a_hash.cmp(&b_hash) {
+            Ordering::Less => {
+                a.next();
+            }
+            Ordering::Greater => {
+                b.next();
+            }
+            Ordering::Equal => {
+                let a_indices = std::iter::from_fn(|| a.next_if(|(hash, _)| *hash == a_hash))
+                    .map(|(_, index)| index)
+                    .collect::<Vec<_>>();
+                let b_indices = std::iter::from_fn(|| b.next_if(|(hash, _)| *hash == b_hash))
+                    .map(|(_, index)| index)
+                    .collect::<Vec<_>>();
+
+                for a_index in &a_indices {
+                    pairs.extend(b_indices.iter().map(|b_index| (*a_index, *b_index)));
+                }
+            }
+        }
+    }
+
+    pairs
+}
+
 /// Defines Python resources available for import.
 #[derive(Debug)]
 pub struct PythonResourcesState<'a, X>
@@ -417,8 +629,16 @@ where
     origin: PathBuf,
 
     /// Named resources available for loading.
+    ///
+    /// Entries take precedence over `indexed_resources`.
     resources: HashMap<Cow<'a, str>, Resource<'a, X>>,
 
+    /// Version 4 packed resources data available for loading.
+    ///
+    /// A name is defined by at most one of these. Resources defined by multiple
+    /// data sets are merged into `resources` when data is indexed.
+    indexed_resources: Vec<LazyIndexedResources<'a>>,
+
     /// List of `PyObject` that back indexed data.
     ///
     /// Holding a reference to these prevents them from being gc'd and for
@@ -427,6 +647,25 @@ where
 
     /// Holds memory mapped file instances that resources data came from.
     backing_mmaps: Vec<memmap2::Mmap>,
+
+    /// Ordered locations to import modules from.
+    resource_locations: Vec<RuntimeResourceLocation>,
+
+    /// Per-package overrides of `resource_locations`.
+    ///
+    /// Overrides apply to the named package and all its descendants.
+    package_resource_locations: HashMap<String, Vec<RuntimeResourceLocation>>,
+
+    /// Directory to extract in-memory extension modules to before loading them.
+    extension_modules_extraction_path: Option<PathBuf>,
+
+    /// Whether to synthesize `__file__` for modules not backed by a file.
+    file_emulation: bool,
+
+    /// Per-package overrides of `file_emulation`.
+    ///
+    /// Overrides apply to the named package and all its descendants.
+    package_file_emulation: HashMap<String, bool>,
 }
 
 impl<'a> Default for PythonResourcesState<'a, u8> {
@@ -435,8 +674,17 @@ impl<'a> Default for PythonResourcesState<'a, u8> {
             current_exe: PathBuf::new(),
             origin: PathBuf::new(),
             resources: HashMap::new(),
+            indexed_resources: vec![],
             backing_py_objects: vec![],
             backing_mmaps: vec![],
+            resource_locations: vec![
+                RuntimeResourceLocation::InMemory,
+                RuntimeResourceLocation::FilesystemRelative,
+            ],
+            package_resource_locations: HashMap::new(),
+            extension_modules_extraction_path: None,
+            file_emulation: false,
+            package_file_emulation: HashMap::new(),
         }
     }
 }
@@ -477,13 +725,139 @@ impl<'a> PythonResourcesState<'a, u8> {
         self.origin = path;
     }
 
+    /// Obtain the directory in-memory extension modules are extracted to.
+    pub fn extension_modules_extraction_path(&self) -> Option<&Path> {
+        self.extension_modules_extraction_path.as_deref()
+    }
+
+    /// Set the directory in-memory extension modules are extracted to.
+    ///
+    /// If set, in-memory extension modules are written to this directory and
+    /// loaded from there instead of being loaded from memory.
+    pub fn set_extension_modules_extraction_path(&mut self, path: Option<PathBuf>) {
+        self.extension_modules_extraction_path = path;
+    }
+
+    /// Obtain the default ordered locations modules are imported from.
+    pub fn resource_locations(&self) -> &[RuntimeResourceLocation] {
+        &self.resource_locations
+    }
+
+    /// Set the default ordered locations modules are imported from.
+    pub fn set_resource_locations(&mut self, locations: Vec<RuntimeResourceLocation>) {
+        self.resource_locations = locations;
+    }
+
+    /// Obtain the resource locations override for a package, if any.
+    pub fn package_resource_locations(&self, package: &str) -> Option<&[RuntimeResourceLocation]> {
+        self.package_resource_locations
+            .get(package)
+            .map(|locations| locations.as_slice())
+    }
+
+    /// Set or clear the resource locations override for a package.
+    ///
+    /// The override applies to the package and all its descendants.
+    pub fn set_package_resource_locations(
+        &mut self,
+        package: &str,
+        locations: Option<Vec<RuntimeResourceLocation>>,
+    ) {
+        if let Some(locations) = locations {
+            self.package_resource_locations
+                .insert(package.to_string(), locations);
+        } else {
+            self.package_resource_locations.remove(package);
+        }
+    }
+
+    /// Resolve the ordered resource locations to consult for a named module.
+    ///
+    /// The override of the closest package in the module's hierarchy wins.
+    /// Otherwise the default locations are returned.
+    pub fn resolve_resource_locations(&self, name: &str) -> &[RuntimeResourceLocation] {
+        if self.package_resource_locations.is_empty() {
+            return &self.resource_locations;
+        }
+
+        let mut candidate = name;
+
+        loop {
+            if let Some(locations) = self.package_resource_locations.get(candidate) {
+                return locations;
+            }
+
+            match candidate.rsplit_once('.') {
+                Some((parent, _)) => candidate = parent,
+                None => return &self.resource_locations,
+            }
+        }
+    }
+
+    /// Whether `__file__` is synthesized for modules not backed by a file.
+    pub fn file_emulation(&self) -> bool {
+        self.file_emulation
+    }
+
+    /// Set whether to synthesize `__file__` for modules not backed by a file.
+    ///
+    /// When enabled, in-memory modules have `__file__` set to a virtual path
+    /// under the current executable. e.g. `/path/to/myapp/foo/bar.py`.
+    pub fn set_file_emulation(&mut self, value: bool) {
+        self.file_emulation = value;
+    }
+
+    /// Obtain the file emulation override for a package, if any.
+    pub fn package_file_emulation(&self, package: &str) -> Option<bool> {
+        self.package_file_emulation.get(package).copied()
+    }
+
+    /// Set or clear the file emulation override for a package.
+    ///
+    /// The override applies to the package and all its descendants.
+    pub fn set_package_file_emulation(&mut self, package: &str, value: Option<bool>) {
+        if let Some(value) = value {
+            self.package_file_emulation
+                .insert(package.to_string(), value);
+        } else {
+            self.package_file_emulation.remove(package);
+        }
+    }
+
+    /// Resolve whether `__file__` is synthesized for a named module.
+    ///
+    /// The override of the closest package in the module's hierarchy wins.
+    /// Otherwise the default is returned.
+    pub fn resolve_file_emulation(&self, name: &str) -> bool {
+        let mut candidate = name;
+
+        loop {
+            if let Some(value) = self.package_file_emulation.get(candidate) {
+                return *value;
+            }
+
+            match candidate.rsplit_once('.') {
+                Some((parent, _)) => candidate = parent,
+                None => return self.file_emulation,
+            }
+        }
+    }
+
     /// Load resources by parsing a blob.
     ///
     /// If an existing entry exists, the new entry will be merged into it. Set fields
     /// on the incoming entry will overwrite fields on the existing entry.
     ///
     /// If an entry doesn't exist, the resource will be inserted as-is.
+    ///
+    /// Version 4 data isn't parsed up front. Its resources are looked up by
+    /// name and parsed on first access.
     pub fn index_data(&mut self, data: &'a [u8]) -> Result<(), &'static str> {
+        if data.starts_with(python_packed_resources::HEADER_V4) {
+            return self
+                .index_indexed_resources(python_packed_resources::load_indexed_resources(data)?);
+        }
+
         let resources = python_packed_resources::load_resources(data)?;
 
         // Reserve space for expected number of incoming items so we can avoid extra
@@ -498,14 +872,109 @@ impl<'a> PythonResourcesState<'a, u8> {
                     existing.into_mut().merge_from(resource)?;
                 }
                 Entry::Vacant(vacant) => {
-                    vacant.insert(resource);
+                    // Resources only defined by version 4 data need to be copied
+                    // so the merged entry shadows them.
+                    match self
+                        .indexed_resources
+                        .iter()
+                        .find_map(|indexed| indexed.find(&resource.name))
+                    {
+                        Some(existing) => {
+                            let mut existing = existing.clone();
+                            existing.merge_from(resource)?;
+                            vacant.insert(existing);
+                        }
+                        None => {
+                            vacant.insert(resource);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Register version 4 packed resources data for lazy lookup.
+    ///
+    /// Only resources that are also defined elsewhere are parsed, so they can be
+    /// merged into `resources`.
+    fn index_indexed_resources(
+        &mut self,
+        indexed: IndexedResources<'a>,
+    ) -> Result<(), &'static str> {
+        for existing in self.resources.values_mut() {
+            if let Some(resource) = indexed.find(&existing.name)? {
+                existing.merge_from(resource)?;
+            }
+        }
+
+        for previous in &self.indexed_resources {
+            for (previous_index, index) in colliding_indices(&previous.indexed, &indexed) {
+                let resource = indexed.get(index)?;
+
+                if self.resources.contains_key(&resource.name) {
+                    continue;
+                }
+
+                let mut merged = previous.indexed.get(previous_index)?;
+
+                if merged.name == resource.name {
+                    merged.merge_from(resource)?;
+                    self.resources.insert(merged.name.clone(), merged);
                 }
             }
         }
 
+        self.indexed_resources
+            .push(LazyIndexedResources::new(indexed));
+
         Ok(())
     }
 
+    /// Obtain a named resource.
+    fn get_resource(&self, name: &str) -> Option<&Resource<'a, u8>> {
+        self.resources.get(name).or_else(|| {
+            self.indexed_resources
+                .iter()
+                .find_map(|indexed| indexed.find(name))
+        })
+    }
+
+    /// Iterate over all resources.
+    fn iter_resources(&self) -> impl Iterator<Item = &Resource<'a, u8>> {
+        self.resources.values().chain(
+            self.indexed_resources
+                .iter()
+                .flat_map(|indexed| indexed.iter())
+                .filter(|resource| !self.resources.contains_key(&resource.name)),
+        )
+    }
+
+    /// Obtain a mutable named resource, inserting an empty one if necessary.
+    ///
+    /// Resources from `indexed_resources` are copied into `resources` so
+    /// modifications take precedence.
+    fn resource_mut(&mut self, name: &str) -> &mut Resource<'a, u8> {
+        if !self.resources.contains_key(name) {
+            let resource = self
+                .indexed_resources
+                .iter()
+                .find_map(|indexed| indexed.find(name))
+                .cloned()
+                .unwrap_or_else(|| Resource {
+                    name: Cow::Owned(name.to_string()),
+                    ..Resource::default()
+                });
+
+            self.resources.insert(resource.name.clone(), resource);
+        }
+
+        self.resources
+            .get_mut(name)
+            .expect("resource should have been inserted")
+    }
+
     /// Load resources data from a filesystem path using memory mapped I/O.
     pub fn index_path_memory_mapped(&mut self, path: impl AsRef<Path>) -> Result<(), String> {
         let path = path.as_ref();
@@ -554,25 +1023,38 @@ impl<'a> PythonResourcesState<'a, u8> {
                 }
             };
 
-            self.resources
-                .entry(name_str.into())
-                .and_modify(|r| {
-                    r.is_python_builtin_extension_module = true;
-                })
-                .or_insert_with(|| Resource {
-                    is_python_builtin_extension_module: true,
-                    name: Cow::Owned(name_str.to_string()),
-                    ..Resource::default()
-                });
+            self.resource_mut(name_str)
+                .is_python_builtin_extension_module = true;
         }
 
         Ok(())
     }
 
     /// Load `frozen` modules from the Python interpreter.
+    ///
+    /// Python 3.11 moved the frozen modules required to bootstrap the import
+    /// system out of `PyImport_FrozenModules`, which only holds custom frozen
+    /// modules and may be NULL. Frozen standard library modules are not indexed
+    /// because they can be disabled by `PyConfig.use_frozen_modules` and we
+    /// provide our own copies of standard library modules.
     pub fn index_interpreter_frozen_modules(&mut self) -> Result<(), &'static str> {
+        #[cfg(Py_3_11)]
+        self.index_frozen_modules_table(unsafe { pyffi::_PyImport_FrozenBootstrap })?;
+
+        self.index_frozen_modules_table(unsafe { pyffi::PyImport_FrozenModules })
+    }
+
+    /// Load `frozen` modules from a NULL terminated `_frozen` array.
+    fn index_frozen_modules_table(
+        &mut self,
+        table: *const pyffi::_frozen,
+    ) -> Result<(), &'static str> {
+        if table.is_null() {
+            return Ok(());
+        }
+
         for i in 0.. {
-            let record = unsafe { pyffi::PyImport_FrozenModules.offset(i) };
+            let record = unsafe { table.offset(i) };
 
             if unsafe { *record }.name.is_null() {
                 break;
@@ -582,20 +1064,11 @@ impl<'a> PythonResourcesState<'a, u8> {
             let name_str = match name.to_str() {
                 Ok(v) => v,
                 Err(_) => {
-                    return Err("unable to parse PyImport_FrozenModules");
+                    return Err("unable to parse frozen module name");
                 }
             };
 
-            self.resources
-                .entry(name_str.into())
-                .and_modify(|r| {
-                    r.is_python_frozen_module = true;
-                })
-                .or_insert_with(|| Resource {
-                    is_python_frozen_module: true,
-                    name: Cow::Owned(name_str.to_string()),
-                    ..Resource::default()
-                });
+            self.resource_mut(name_str).is_python_frozen_module = true;
         }
 
         Ok(())
@@ -615,7 +1088,7 @@ impl<'a> PythonResourcesState<'a, u8> {
 
     /// Says whether a named resource exists.
     pub fn has_resource(&self, name: &str) -> bool {
-        self.resources.contains_key(name)
+        self.get_resource(name).is_some()
     }
 
     /// Add a resource to the instance.
@@ -662,11 +1135,13 @@ impl<'a> PythonResourcesState<'a, u8> {
         // for recognizing `__init__` because Python code in the wild relies on it.
         let name = name.strip_suffix(".__init__").unwrap_or(name);
 
-        let resource = match self.resources.get(name) {
+        let resource = match self.get_resource(name) {
             Some(entry) => entry,
             None => return None,
         };
 
+        let emulate_file = self.resolve_file_emulation(name);
+
         // Since resources can exist as multiple types and it is possible
         // that a single resource will express itself as multiple types
         // (e.g. we have both bytecode and an extension module available),
@@ -698,6 +1173,7 @@ impl<'a> PythonResourcesState<'a, u8> {
                 resource,
                 current_exe: &self.current_exe,
                 origin: &self.origin,
+                emulate_file,
                 flavor: ModuleFlavor::Builtin,
                 is_package: resource.is_python_package,
             })
@@ -706,6 +1182,7 @@ impl<'a> PythonResourcesState<'a, u8> {
                 resource,
                 current_exe: &self.current_exe,
                 origin: &self.origin,
+                emulate_file,
                 flavor: ModuleFlavor::Frozen,
                 is_package: resource.is_python_package,
             })
@@ -714,15 +1191,26 @@ impl<'a> PythonResourcesState<'a, u8> {
                 resource,
                 current_exe: &self.current_exe,
                 origin: &self.origin,
+                emulate_file,
                 flavor: ModuleFlavor::Extension,
                 is_package: resource.is_python_package,
             })
+        } else if resource.is_python_module && resource.is_python_namespace_package {
+            Some(ImportablePythonModule {
+                resource,
+                current_exe: &self.current_exe,
+                origin: &self.origin,
+                emulate_file,
+                flavor: ModuleFlavor::Namespace,
+                is_package: true,
+            })
         } else if resource.is_python_module {
             if is_module_importable(resource, optimize_level) {
                 Some(ImportablePythonModule {
                     resource,
                     current_exe: &self.current_exe,
                     origin: &self.origin,
+                    emulate_file,
                     flavor: ModuleFlavor::SourceBytecode,
                     is_package: resource.is_python_package,
                 })
@@ -745,7 +1233,7 @@ impl<'a> PythonResourcesState<'a, u8> {
         package: &str,
         resource_name: &str,
     ) -> PyResult<Option<&'p PyAny>> {
-        let entry = match self.resources.get(package) {
+        let entry = match self.get_resource(package) {
             Some(entry) => entry,
             None => return Ok(None),
         };
@@ -755,7 +1243,7 @@ impl<'a> PythonResourcesState<'a, u8> {
                 let io_module = py.import("io")?;
                 let bytes_io = io_module.getattr("BytesIO")?;
 
-                let data = PyBytes::new(py, data);
+                let data = PyBytes::new(py, &entry.resolve_in_memory_data(data)?);
                 return Ok(Some(bytes_io.call((data,), None)?));
             }
         }
@@ -778,7 +1266,7 @@ impl<'a> PythonResourcesState<'a, u8> {
 
     /// Determines whether a specific package + name pair is a known Python package resource.
     pub fn is_package_resource(&self, package: &str, resource_name: &str) -> bool {
-        if let Some(entry) = self.resources.get(package) {
+        if let Some(entry) = self.get_resource(package) {
             if let Some(resources) = &entry.in_memory_package_resources {
                 if resources.contains_key(resource_name) {
                     return true;
@@ -799,7 +1287,7 @@ impl<'a> PythonResourcesState<'a, u8> {
     ///
     /// The names are returned in sorted order.
     pub fn package_resource_names<'p>(&self, py: Python<'p>, package: &str) -> PyResult<&'p PyAny> {
-        let entry = match self.resources.get(package) {
+        let entry = match self.get_resource(package) {
             Some(entry) => entry,
             None => return Ok(PyList::empty(py).into()),
         };
@@ -833,7 +1321,7 @@ impl<'a> PythonResourcesState<'a, u8> {
             format!("{}/", name)
         };
 
-        if let Some(entry) = self.resources.get(package) {
+        if let Some(entry) = self.get_resource(package) {
             if let Some(resources) = &entry.in_memory_package_resources {
                 if resources.keys().any(|path| path.starts_with(&prefix)) {
                     return true;
@@ -890,7 +1378,7 @@ impl<'a> PythonResourcesState<'a, u8> {
 
         let mut entries = BTreeSet::new();
 
-        if let Some(entry) = self.resources.get(package) {
+        if let Some(entry) = self.get_resource(package) {
             if let Some(resources) = &entry.in_memory_package_resources {
                 entries.extend(resources.keys().filter_map(filter_map_resource));
             }
@@ -903,6 +1391,84 @@ impl<'a> PythonResourcesState<'a, u8> {
         entries.into_iter().collect::<Vec<_>>()
     }
 
+    /// Resolve all package resources in a directory, including in subdirectories.
+    ///
+    /// Returned names are relative to the package, not the directory.
+    pub fn package_resources_in_directory(&self, package: &str, name: &str) -> Vec<String> {
+        let name = name.replace('\\', "/");
+
+        let prefix = if name.ends_with('/') || name.is_empty() {
+            name
+        } else {
+            format!("{}/", name)
+        };
+
+        let mut entries = BTreeSet::new();
+
+        if let Some(entry) = self.get_resource(package) {
+            if let Some(resources) = &entry.in_memory_package_resources {
+                entries.extend(
+                    resources
+                        .keys()
+                        .filter(|path| path.starts_with(&prefix))
+                        .map(|path| path.to_string()),
+                );
+            }
+
+            if let Some(resources) = &entry.relative_path_package_resources {
+                entries.extend(
+                    resources
+                        .keys()
+                        .filter(|path| path.starts_with(&prefix))
+                        .map(|path| path.to_string()),
+                );
+            }
+        }
+
+        entries.into_iter().collect::<Vec<_>>()
+    }
+
+    /// Resolve the filesystem path of a package resource backed by a file.
+    ///
+    /// Returns `None` if the resource doesn't exist or isn't backed by a file.
+    pub fn package_resource_filesystem_path(
+        &self,
+        package: &str,
+        resource_name: &str,
+    ) -> Option<PathBuf> {
+        self.get_resource(package)?
+            .relative_path_package_resources
+            .as_ref()?
+            .get(resource_name)
+            .map(|path| self.origin.join(path))
+    }
+
+    /// Obtain the data of a single named resource in a package.
+    ///
+    /// `Ok(None)` is returned if the resource does not exist.
+    pub fn get_package_resource_data(
+        &self,
+        package: &str,
+        resource_name: &str,
+    ) -> PyResult<Option<Cow<'_, [u8]>>> {
+        let entry = match self.get_resource(package) {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        if let Some(resources) = &entry.in_memory_package_resources {
+            if let Some(data) = resources.get(resource_name) {
+                return Ok(Some(entry.resolve_in_memory_data(data)?));
+            }
+        }
+
+        if let Some(path) = self.package_resource_filesystem_path(package, resource_name) {
+            return Ok(Some(Cow::Owned(std::fs::read(path)?)));
+        }
+
+        Ok(None)
+    }
+
     /// Attempt to resolve a PyBytes for resource data given a relative path.
     ///
     /// Raises OSerror on failure.
@@ -1008,11 +1574,13 @@ impl<'a> PythonResourcesState<'a, u8> {
             let resource_name = name_parts.join("/");
             let resource_name_ref: &str = &resource_name;
 
-            if let Some(entry) = self.resources.get(package_name_ref) {
+            if let Some(entry) = self.get_resource(package_name_ref) {
                 if check_in_memory {
                     if let Some(resources) = &entry.in_memory_package_resources {
                         if let Some(data) = resources.get(resource_name_ref) {
-                            return Ok(PyBytes::new(py, data).into());
+                            return Ok(
+                                PyBytes::new(py, &entry.resolve_in_memory_data(data)?).into()
+                            );
                         }
                     }
                 }
@@ -1063,11 +1631,12 @@ impl<'a> PythonResourcesState<'a, u8> {
         optimize_level: BytecodeOptimizationLevel,
     ) -> PyResult<&'p PyList> {
         let infos: PyResult<Vec<_>> = self
-            .resources
-            .values()
+            .iter_resources()
             .filter(|r| {
                 r.is_python_extension_module
-                    || (r.is_python_module && is_module_importable(r, optimize_level))
+                    || (r.is_python_module
+                        && (r.is_python_namespace_package
+                            || is_module_importable(r, optimize_level)))
             })
             .filter(|r| name_at_package_hierarchy(&r.name, package_filter))
             .map(|r| {
@@ -1092,10 +1661,80 @@ impl<'a> PythonResourcesState<'a, u8> {
         Ok(PyList::new(py, &infos))
     }
 
+    /// Resolve file resources having embedded data whose names begin with `prefix`.
+    ///
+    /// Returns tuples of the resource name, its data, and whether the file is
+    /// executable. Compressed data is decompressed.
+    pub fn resolve_embedded_files(
+        &self,
+        prefix: &str,
+    ) -> std::io::Result<Vec<(&'_ str, Cow<'_, [u8]>, bool)>> {
+        self.iter_resources()
+            .filter(|resource| resource.is_utf8_filename_data && resource.name.starts_with(prefix))
+            .filter_map(|resource| {
+                resource.file_data_embedded.as_ref().map(|data| {
+                    Ok((
+                        resource.name.as_ref(),
+                        resource.resolve_in_memory_data(data)?,
+                        resource.file_executable,
+                    ))
+                })
+            })
+            .collect()
+    }
+
+    /// Resolve the names of namespaces containing data blobs.
+    ///
+    /// Names are sorted and unique.
+    pub fn data_namespaces(&self) -> Vec<&'_ str> {
+        self.iter_resources()
+            .filter_map(|resource| resource.data_namespace.as_deref())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Resolve the paths of data blobs belonging to a namespace.
+    ///
+    /// Paths are relative to the namespace and sorted.
+    pub fn data_paths(&self, namespace: &str) -> Vec<&'_ str> {
+        let mut paths = self
+            .iter_resources()
+            .filter(|resource| resource.data_namespace.as_deref() == Some(namespace))
+            .filter_map(|resource| resource.data_path())
+            .collect::<Vec<_>>();
+
+        paths.sort_unstable();
+
+        paths
+    }
+
+    /// Resolve the content of a data blob.
+    ///
+    /// Returns `Ok(None)` if no data blob with the given path exists in the
+    /// namespace. Compressed data is decompressed.
+    pub fn resolve_data(
+        &self,
+        namespace: &str,
+        path: &str,
+    ) -> std::io::Result<Option<Cow<'_, [u8]>>> {
+        let name = format!("{}/{}", namespace, path);
+
+        match self.get_resource(name.as_str()) {
+            Some(resource) if resource.data_namespace.as_deref() == Some(namespace) => {
+                if let Some(data) = &resource.file_data_embedded {
+                    Ok(Some(resource.resolve_in_memory_data(data)?))
+                } else {
+                    Ok(None)
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+
     /// Resolve the names of package distributions matching a name filter.
     pub fn package_distribution_names(&self, filter: impl Fn(&str) -> bool) -> Vec<&'_ str> {
-        self.resources
-            .values()
+        self.iter_resources()
             .filter(|r| {
                 r.is_python_package
                     && (r.in_memory_distribution_resources.is_some()
@@ -1112,10 +1751,10 @@ impl<'a> PythonResourcesState<'a, u8> {
         package: &str,
         name: &str,
     ) -> Result<Option<Cow<'_, [u8]>>> {
-        if let Some(entry) = self.resources.get(package) {
+        if let Some(entry) = self.get_resource(package) {
             if let Some(resources) = &entry.in_memory_distribution_resources {
                 if let Some(data) = resources.get(name) {
-                    return Ok(Some(Cow::Borrowed(data.as_ref())));
+                    return Ok(Some(entry.resolve_in_memory_data(data)?));
                 }
             }
 
@@ -1148,7 +1787,7 @@ impl<'a> PythonResourcesState<'a, u8> {
             format!("{}/", name)
         };
 
-        if let Some(entry) = &self.resources.get(package) {
+        if let Some(entry) = &self.get_resource(package) {
             if let Some(resources) = &entry.in_memory_distribution_resources {
                 if resources.keys().any(|path| path.starts_with(&prefix)) {
                     return true;
@@ -1185,9 +1824,9 @@ impl<'a> PythonResourcesState<'a, u8> {
 
         let filter_map_resource = |path: &'slf Cow<'slf, str>| -> Option<&'slf str> {
             match &prefix {
-                Some(prefix) => {
-                    path.strip_prefix(prefix).filter(|&name| !name.contains('/'))
-                }
+                Some(prefix) => path
+                    .strip_prefix(prefix)
+                    .filter(|&name| !name.contains('/')),
                 None => {
                     // Empty string input matches root directory.
                     if path.contains('/') {
@@ -1201,7 +1840,7 @@ impl<'a> PythonResourcesState<'a, u8> {
 
         let mut entries = BTreeSet::new();
 
-        if let Some(entry) = self.resources.get(package) {
+        if let Some(entry) = self.get_resource(package) {
             if let Some(resources) = &entry.in_memory_distribution_resources {
                 entries.extend(resources.keys().filter_map(filter_map_resource));
             }
@@ -1216,7 +1855,7 @@ impl<'a> PythonResourcesState<'a, u8> {
 
     /// Resolve content of a shared library to load from memory.
     pub fn resolve_in_memory_shared_library_data(&self, name: &str) -> Option<&[u8]> {
-        if let Some(entry) = &self.resources.get(name) {
+        if let Some(entry) = &self.get_resource(name) {
             if let Some(library_data) = &entry.in_memory_shared_library {
                 Some(library_data.as_ref())
             } else {
@@ -1229,7 +1868,7 @@ impl<'a> PythonResourcesState<'a, u8> {
 
     /// Convert indexed resources to a [PyList].
     pub fn resources_as_py_list<'p>(&self, py: Python<'p>) -> PyResult<&'p PyList> {
-        let mut resources = self.resources.values().collect::<Vec<_>>();
+        let mut resources = self.iter_resources().collect::<Vec<_>>();
         resources.sort_by_key(|r| &r.name);
 
         let objects = resources
@@ -1250,21 +1889,20 @@ impl<'a> PythonResourcesState<'a, u8> {
         ignore_frozen: bool,
     ) -> Result<Vec<u8>> {
         let mut resources = self
-            .resources
-            .values()
+            .iter_resources()
             .filter(|resource| {
                 // This assumes builtins and frozen are mutually exclusive with other types.
                 !((resource.is_python_builtin_extension_module && ignore_builtin)
                     || (resource.is_python_frozen_module && ignore_frozen))
             })
-            .collect::<Vec<&Resource<u8>>>();
+            .collect::<Vec<_>>();
 
         // Sort so behavior is deterministic.
         resources.sort_by_key(|v| &v.name);
 
         let mut buffer = Vec::new();
 
-        python_packed_resources::write_packed_resources_v3(&resources, &mut buffer, None)?;
+        python_packed_resources::write_packed_resources_v4(&resources, &mut buffer, None)?;
 
         Ok(buffer)
     }
@@ -1275,6 +1913,22 @@ pub(crate) struct OxidizedResource {
     resource: RefCell<Resource<'static, u8>>,
 }
 
+impl OxidizedResource {
+    /// Obtain the resource for mutating in-memory data.
+    ///
+    /// Compressed resources are decompressed first so newly assigned data
+    /// isn't mixed with compressed data.
+    fn decompressed_mut(&self) -> PyResult<RefMut<Resource<'static, u8>>> {
+        let mut resource = self.resource.borrow_mut();
+
+        if resource.is_zstd_compressed {
+            *resource = resource.to_zstd_decompressed()?;
+        }
+
+        Ok(resource)
+    }
+}
+
 #[pymethods]
 impl OxidizedResource {
     fn __repr__(&self) -> String {
@@ -1390,68 +2044,72 @@ impl OxidizedResource {
     }
 
     #[getter]
-    fn get_in_memory_source<'p>(&self, py: Python<'p>) -> Option<&'p PyBytes> {
-        self.resource
-            .borrow()
-            .in_memory_source
-            .as_ref()
-            .map(|x| PyBytes::new(py, x))
+    fn get_in_memory_source<'p>(&self, py: Python<'p>) -> PyResult<Option<&'p PyBytes>> {
+        let resource = self.resource.borrow();
+
+        Ok(match &resource.in_memory_source {
+            Some(x) => Some(PyBytes::new(py, &resource.resolve_in_memory_data(x)?)),
+            None => None,
+        })
     }
 
     #[setter]
     fn set_in_memory_source(&self, value: &PyAny) -> PyResult<()> {
-        self.resource.borrow_mut().in_memory_source =
+        self.decompressed_mut()?.in_memory_source =
             pyobject_to_owned_bytes_optional(value)?.map(Cow::Owned);
 
         Ok(())
     }
 
     #[getter]
-    fn get_in_memory_bytecode<'p>(&self, py: Python<'p>) -> Option<&'p PyBytes> {
-        self.resource
-            .borrow()
-            .in_memory_bytecode
-            .as_ref()
-            .map(|x| PyBytes::new(py, x))
+    fn get_in_memory_bytecode<'p>(&self, py: Python<'p>) -> PyResult<Option<&'p PyBytes>> {
+        let resource = self.resource.borrow();
+
+        Ok(match &resource.in_memory_bytecode {
+            Some(x) => Some(PyBytes::new(py, &resource.resolve_in_memory_data(x)?)),
+            None => None,
+        })
     }
 
     #[setter]
     fn set_in_memory_bytecode(&self, value: &PyAny) -> PyResult<()> {
-        self.resource.borrow_mut().in_memory_bytecode =
+        self.decompressed_mut()?.in_memory_bytecode =
             pyobject_to_owned_bytes_optional(value)?.map(Cow::Owned);
 
         Ok(())
     }
 
     #[getter]
-    fn get_in_memory_bytecode_opt1<'p>(&self, py: Python<'p>) -> Option<&'p PyBytes> {
-        self.resource
-            .borrow()
-            .in_memory_bytecode_opt1
-            .as_ref()
-            .map(|x| PyBytes::new(py, x))
+    fn get_in_memory_bytecode_opt1<'p>(&self, py: Python<'p>) -> PyResult<Option<&'p PyBytes>> {
+        let resource = self.resource.borrow();
+
+        Ok(match &resource.in_memory_bytecode_opt1 {
+            Some(x) => Some(PyBytes::new(py, &resource.resolve_in_memory_data(x)?)),
+            None => None,
+        })
     }
 
     #[setter]
     fn set_in_memory_bytecode_opt1(&self, value: &PyAny) -> PyResult<()> {
-        self.resource.borrow_mut().in_memory_bytecode_opt1 =
+        self.decompressed_mut()?.in_memory_bytecode_opt1 =
             pyobject_to_owned_bytes_optional(value)?.map(Cow::Owned);
 
         Ok(())
     }
 
     #[getter]
-    fn get_in_memory_bytecode_opt2<'p>(&self, py: Python<'p>) -> Option<&'p PyBytes> {
-        self.resource
-            .borrow()
-            .in_memory_bytecode_opt2
-            .as_ref()
-            .map(|x| PyBytes::new(py, x))
+    fn get_in_memory_bytecode_opt2<'p>(&self, py: Python<'p>) -> PyResult<Option<&'p PyBytes>> {
+        let resource = self.resource.borrow();
+
+        Ok(match &resource.in_memory_bytecode_opt2 {
+            Some(x) => Some(PyBytes::new(py, &resource.resolve_in_memory_data(x)?)),
+            None => None,
+        })
     }
 
     #[setter]
     fn set_in_memory_bytecode_opt2(&self, value: &PyAny) -> PyResult<()> {
-        self.resource.borrow_mut().in_memory_bytecode_opt2 =
+        self.decompressed_mut()?.in_memory_bytecode_opt2 =
             pyobject_to_owned_bytes_optional(value)?.map(Cow::Owned);
 
         Ok(())
@@ -1483,21 +2141,28 @@ impl OxidizedResource {
     fn get_in_memory_package_resources<'p>(
         &self,
         py: Python<'p>,
-    ) -> Option<HashMap<String, &'p PyBytes>> {
-        self.resource
-            .borrow()
+    ) -> PyResult<Option<HashMap<String, &'p PyBytes>>> {
+        let resource = self.resource.borrow();
+
+        resource
             .in_memory_package_resources
             .as_ref()
             .map(|x| {
                 x.iter()
-                    .map(|(k, v)| (k.to_string(), PyBytes::new(py, v)))
+                    .map(|(k, v)| {
+                        Ok((
+                            k.to_string(),
+                            PyBytes::new(py, &resource.resolve_in_memory_data(v)?),
+                        ))
+                    })
                     .collect()
             })
+            .transpose()
     }
 
     #[setter]
     fn set_in_memory_package_resources(&self, value: &PyAny) -> PyResult<()> {
-        self.resource.borrow_mut().in_memory_package_resources =
+        self.decompressed_mut()?.in_memory_package_resources =
             pyobject_optional_resources_map_to_owned_bytes(value)?.map(|x| {
                 x.into_iter()
                     .map(|(k, v)| (Cow::Owned(k), Cow::Owned(v)))
@@ -1511,21 +2176,28 @@ impl OxidizedResource {
     fn get_in_memory_distribution_resources<'p>(
         &self,
         py: Python<'p>,
-    ) -> Option<HashMap<String, &'p PyBytes>> {
-        self.resource
-            .borrow()
+    ) -> PyResult<Option<HashMap<String, &'p PyBytes>>> {
+        let resource = self.resource.borrow();
+
+        resource
             .in_memory_distribution_resources
             .as_ref()
             .map(|x| {
                 x.iter()
-                    .map(|(k, v)| (k.to_string(), PyBytes::new(py, v)))
+                    .map(|(k, v)| {
+                        Ok((
+                            k.to_string(),
+                            PyBytes::new(py, &resource.resolve_in_memory_data(v)?),
+                        ))
+                    })
                     .collect()
             })
+            .transpose()
     }
 
     #[setter]
     fn set_in_memory_distribution_resources(&self, value: &PyAny) -> PyResult<()> {
-        self.resource.borrow_mut().in_memory_distribution_resources =
+        self.decompressed_mut()?.in_memory_distribution_resources =
             pyobject_optional_resources_map_to_owned_bytes(value)?.map(|x| {
                 x.into_iter()
                     .map(|(k, v)| (Cow::Owned(k), Cow::Owned(v)))
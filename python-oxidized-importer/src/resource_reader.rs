@@ -4,8 +4,12 @@
 
 use {
     crate::importer::ImporterState,
-    pyo3::{exceptions::PyFileNotFoundError, prelude::*},
-    std::sync::Arc,
+    pyo3::{
+        exceptions::{PyFileNotFoundError, PyNotADirectoryError, PyValueError},
+        prelude::*,
+        types::{PyBytes, PyDict, PyList, PyTuple},
+    },
+    std::{collections::BTreeSet, sync::Arc},
 };
 
 /// Implements in-memory reading of resource data.
@@ -80,4 +84,193 @@ impl OxidizedResourceReader {
             .get_resources_state()
             .package_resource_names(py, &self.package)
     }
+
+    /// Returns an importlib.abc.Traversable for the package's resources.
+    ///
+    /// This powers importlib.resources.files().
+    fn files(&self) -> OxidizedResourceTraversable {
+        OxidizedResourceTraversable::new(self.state.clone(), self.package.clone(), String::new())
+    }
+}
+
+/// A path to package resources.
+///
+/// Implements importlib.abc.Traversable. Resources are addressed by their
+/// `/` delimited path relative to the package. The empty path is the package
+/// itself.
+#[pyclass(module = "oxidized_importer")]
+pub(crate) struct OxidizedResourceTraversable {
+    state: Arc<ImporterState>,
+    package: String,
+    path: String,
+}
+
+impl OxidizedResourceTraversable {
+    fn new(state: Arc<ImporterState>, package: String, path: String) -> Self {
+        Self {
+            state,
+            package,
+            path,
+        }
+    }
+
+    fn join(&self, child: &str) -> Self {
+        let path = self
+            .path
+            .split('/')
+            .chain(child.replace('\\', "/").split('/'))
+            .filter(|part| !part.is_empty() && *part != ".")
+            .collect::<Vec<_>>()
+            .join("/");
+
+        Self::new(self.state.clone(), self.package.clone(), path)
+    }
+
+    fn read_data<'p>(&self, py: Python<'p>) -> PyResult<&'p PyBytes> {
+        match self
+            .state
+            .get_resources_state()
+            .get_package_resource_data(&self.package, &self.path)?
+        {
+            Some(data) => Ok(PyBytes::new(py, &data)),
+            None => Err(PyFileNotFoundError::new_err(format!(
+                "resource not found: {}",
+                self.path
+            ))),
+        }
+    }
+}
+
+#[pymethods]
+impl OxidizedResourceTraversable {
+    fn __repr__(&self) -> String {
+        format!(
+            "<OxidizedResourceTraversable package={:?} path={:?}>",
+            self.package, self.path
+        )
+    }
+
+    fn __truediv__(&self, child: &str) -> Self {
+        self.join(child)
+    }
+
+    /// The base name of this path.
+    #[getter]
+    fn name(&self) -> &str {
+        if self.path.is_empty() {
+            self.package.rsplit('.').next().unwrap_or_default()
+        } else {
+            self.path.rsplit('/').next().unwrap_or_default()
+        }
+    }
+
+    /// Whether this path is a directory containing resources.
+    fn is_dir(&self) -> bool {
+        self.path.is_empty()
+            || self
+                .state
+                .get_resources_state()
+                .is_package_resource_directory(&self.package, &self.path)
+    }
+
+    /// Whether this path is a resource.
+    fn is_file(&self) -> bool {
+        !self.path.is_empty()
+            && self
+                .state
+                .get_resources_state()
+                .is_package_resource(&self.package, &self.path)
+    }
+
+    /// Obtain an iterator over the resources and directories in this directory.
+    fn iterdir<'p>(&self, py: Python<'p>) -> PyResult<&'p PyAny> {
+        if !self.is_dir() {
+            return Err(PyNotADirectoryError::new_err(format!(
+                "not a directory: {}",
+                self.path
+            )));
+        }
+
+        let prefix = if self.path.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", self.path)
+        };
+
+        let children = self
+            .state
+            .get_resources_state()
+            .package_resources_in_directory(&self.package, &self.path)
+            .into_iter()
+            .filter_map(|name| {
+                name.strip_prefix(&prefix)
+                    .and_then(|name| name.split('/').next())
+                    .map(|name| name.to_string())
+            })
+            .collect::<BTreeSet<_>>();
+
+        let children = children
+            .iter()
+            .map(|name| Py::new(py, self.join(name)))
+            .collect::<PyResult<Vec<_>>>()?;
+
+        Ok(PyList::new(py, children).as_ref().iter()?.into())
+    }
+
+    /// Obtain a path to a descendant of this path.
+    #[pyo3(signature=(*descendants))]
+    fn joinpath(&self, descendants: &PyTuple) -> PyResult<Self> {
+        let mut res = Self::new(self.state.clone(), self.package.clone(), self.path.clone());
+
+        for descendant in descendants {
+            res = res.join(descendant.extract::<&str>()?);
+        }
+
+        Ok(res)
+    }
+
+    /// Read the content of this resource as bytes.
+    fn read_bytes<'p>(&self, py: Python<'p>) -> PyResult<&'p PyBytes> {
+        self.read_data(py)
+    }
+
+    /// Read the content of this resource as text.
+    #[pyo3(signature=(encoding=None))]
+    fn read_text<'p>(&self, py: Python<'p>, encoding: Option<&str>) -> PyResult<&'p PyAny> {
+        self.read_data(py)?
+            .call_method1("decode", (encoding.unwrap_or("utf-8"),))
+    }
+
+    /// Open this resource for reading.
+    ///
+    /// `mode` may be `r` or `rb`. Additional arguments are passed to
+    /// `io.TextIOWrapper` when opening in text mode.
+    #[pyo3(signature=(mode="r", *args, **kwargs))]
+    fn open<'p>(
+        &self,
+        py: Python<'p>,
+        mode: &str,
+        args: &PyTuple,
+        kwargs: Option<&PyDict>,
+    ) -> PyResult<&'p PyAny> {
+        let io_module = py.import("io")?;
+        let data = io_module
+            .getattr("BytesIO")?
+            .call1((self.read_data(py)?,))?;
+
+        match mode {
+            "rb" => Ok(data),
+            "r" | "rt" => {
+                let args = PyTuple::new(
+                    py,
+                    std::iter::once(data).chain(args.iter()).collect::<Vec<_>>(),
+                );
+                io_module.getattr("TextIOWrapper")?.call(args, kwargs)
+            }
+            _ => Err(PyValueError::new_err(format!(
+                "invalid mode for resource: {}",
+                mode
+            ))),
+        }
+    }
 }
@@ -178,6 +178,89 @@ impl TryFrom<String> for TerminfoResolution {
     }
 }
 
+/// Defines what a built executable runs when it starts.
+///
+/// Run modes are typically expressed as an ordered list. The first mode that
+/// applies to the current invocation is used.
+///
+/// Serialization type: `string`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serialization", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serialization", serde(try_from = "String", into = "String"))]
+pub enum RunMode {
+    /// Run a Python module as `__main__`, like `python -m <module>`.
+    ///
+    /// Applies if the module can be found by the import machinery.
+    ///
+    /// Serialized value: `module:<name>`
+    Module(String),
+
+    /// Evaluate a string of Python code, like `python -c <code>`.
+    ///
+    /// Always applies.
+    ///
+    /// Serialized value: `eval:<code>`
+    Eval(String),
+
+    /// Run the Python file named by the first command line argument, like
+    /// `python <file> [args]`.
+    ///
+    /// Applies if a command line argument is present.
+    ///
+    /// Serialized value: `file`
+    File,
+
+    /// Start an interactive Python interpreter (REPL).
+    ///
+    /// Always applies.
+    ///
+    /// Serialized value: `repl`
+    Repl,
+}
+
+impl ToString for RunMode {
+    fn to_string(&self) -> String {
+        match self {
+            Self::Module(name) => format!("module:{}", name),
+            Self::Eval(code) => format!("eval:{}", code),
+            Self::File => "file".to_string(),
+            Self::Repl => "repl".to_string(),
+        }
+    }
+}
+
+impl From<RunMode> for String {
+    fn from(v: RunMode) -> Self {
+        v.to_string()
+    }
+}
+
+impl TryFrom<&str> for RunMode {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        if value == "file" {
+            Ok(Self::File)
+        } else if value == "repl" {
+            Ok(Self::Repl)
+        } else if let Some(name) = value.strip_prefix("module:") {
+            Ok(Self::Module(name.to_string()))
+        } else if let Some(code) = value.strip_prefix("eval:") {
+            Ok(Self::Eval(code.to_string()))
+        } else {
+            Err(format!("{} is not a valid run mode value", value))
+        }
+    }
+}
+
+impl TryFrom<String> for RunMode {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::try_from(value.as_str())
+    }
+}
+
 /// Defines a backend for a memory allocator.
 ///
 /// This says which memory allocator API / library to configure the Python
@@ -918,6 +1001,14 @@ pub struct PythonInterpreterConfig {
     /// See <https://docs.python.org/3/c-api/init_config.html#c.PyConfig.run_module>.
     pub run_module: Option<String>,
 
+    /// Whether to not prepend a potentially unsafe path to `sys.path`.
+    ///
+    /// Only has an effect on Python 3.11+. The setting is ignored on older
+    /// Python versions.
+    ///
+    /// See <https://docs.python.org/3/c-api/init_config.html#c.PyConfig.safe_path>.
+    pub safe_path: Option<bool>,
+
     /// Whether to show the total reference count at exit.
     ///
     /// See <https://docs.python.org/3/c-api/init_config.html#c.PyConfig.show_ref_count>.
@@ -951,6 +1042,14 @@ pub struct PythonInterpreterConfig {
     /// See <https://docs.python.org/3/c-api/init_config.html#c.PyConfig.tracemalloc>.
     pub tracemalloc: Option<bool>,
 
+    /// Whether to import frozen modules compiled into the interpreter.
+    ///
+    /// Only has an effect on Python 3.11+. The setting is ignored on older
+    /// Python versions.
+    ///
+    /// See <https://docs.python.org/3/c-api/init_config.html#c.PyConfig.use_frozen_modules>.
+    pub use_frozen_modules: Option<bool>,
+
     /// Whether to add the user site directory to `sys.path`.
     ///
     /// See <https://docs.python.org/3/c-api/init_config.html#c.PyConfig.user_site_directory>.
@@ -22,6 +22,7 @@ pub mod module_util;
 pub mod package_metadata;
 pub mod policy;
 pub mod python_source;
+pub mod qt;
 pub mod resource;
 pub mod resource_collection;
 #[cfg(test)]
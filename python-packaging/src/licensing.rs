@@ -196,6 +196,9 @@ pub struct LicensedComponent {
     /// Type of component.
     flavor: ComponentFlavor,
 
+    /// Version of component.
+    version: Option<String>,
+
     /// The type of license.
     license: LicenseFlavor,
 
@@ -239,6 +242,7 @@ impl LicensedComponent {
     pub fn new(flavor: ComponentFlavor, license: LicenseFlavor) -> Self {
         Self {
             flavor,
+            version: None,
             license,
             source_location: SourceLocation::NotSet,
             homepage: None,
@@ -300,6 +304,16 @@ impl LicensedComponent {
         self.source_location = location;
     }
 
+    /// Obtain the version of this component.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// Set the version of this component.
+    pub fn set_version(&mut self, value: impl ToString) {
+        self.version = Some(value.to_string());
+    }
+
     /// Obtain the homepage / URL of this component.
     pub fn homepage(&self) -> Option<&str> {
         self.homepage.as_deref()
@@ -965,6 +979,9 @@ impl TryInto<LicensedComponent> for PackageLicenseInfo {
             component.add_license_text(text);
         }
 
+        if !self.version.is_empty() {
+            component.set_version(self.version);
+        }
         if let Some(value) = self.homepage {
             component.set_homepage(value);
         }
@@ -1269,6 +1286,7 @@ mod tests {
             LicenseFlavor::None,
         );
         assert_eq!(c, wanted);
+        assert_eq!(c.version(), Some("0.1"));
 
         Ok(())
     }
@@ -66,10 +66,44 @@ impl PythonPackageMetadata {
     }
 }
 
+/// Normalize a distribution name for comparison.
+///
+/// This is the PEP 503 normalization with `-` replaced by `_`, which is how
+/// `importlib.metadata` matches distribution names.
+pub fn normalize_distribution_name(name: &str) -> String {
+    let mut res = String::with_capacity(name.len());
+    let mut in_separator = false;
+
+    for c in name.chars() {
+        if matches!(c, '-' | '_' | '.') {
+            if !in_separator {
+                res.push('_');
+            }
+            in_separator = true;
+        } else {
+            res.extend(c.to_lowercase());
+            in_separator = false;
+        }
+    }
+
+    res
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_normalize_distribution_name() {
+        assert_eq!(normalize_distribution_name("black"), "black");
+        assert_eq!(normalize_distribution_name("Foo-BAR"), "foo_bar");
+        assert_eq!(
+            normalize_distribution_name("zope.interface"),
+            "zope_interface"
+        );
+        assert_eq!(normalize_distribution_name("a-_.b"), "a_b");
+    }
+
     #[test]
     fn test_parse_metadata() -> Result<()> {
         let data = concat!(
@@ -12,15 +12,24 @@ Functionality for defining how Python resources should be packaged.
 
 use {
     crate::{
-        licensing::{LicenseFlavor, SAFE_SYSTEM_LIBRARIES},
+        licensing::{LicenseFlavor, LicensedComponents, SAFE_SYSTEM_LIBRARIES},
         location::ConcreteResourceLocation,
-        resource::{PythonExtensionModule, PythonExtensionModuleVariants, PythonResource},
+        resource::{
+            BytecodeOptimizationLevel, PythonExtensionModule, PythonExtensionModuleVariants,
+            PythonResource,
+        },
         resource_collection::PythonResourceAddCollectionContext,
     },
-    anyhow::Result,
-    std::collections::{HashMap, HashSet},
+    anyhow::{anyhow, Context, Result},
+    regex::Regex,
+    std::collections::{BTreeSet, HashMap, HashSet},
 };
 
+/// License class matching all copyleft licenses.
+///
+/// Can be used in place of an SPDX license identifier in license policy rules.
+pub const LICENSE_CLASS_COPYLEFT: &str = "copyleft";
+
 /// Denotes methods to filter extension modules.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ExtensionModuleFilter {
@@ -92,6 +101,83 @@ impl AsRef<str> for ResourceHandlingMode {
     }
 }
 
+/// A pattern matching fully qualified Python resource names.
+///
+/// See [PythonResource::full_name] for how resource names are formed.
+#[derive(Clone, Debug)]
+pub struct ResourceNamePattern {
+    /// The pattern as defined by the user.
+    pattern: String,
+
+    /// Regular expression anchored to match the entire name.
+    regex: Regex,
+}
+
+impl PartialEq for ResourceNamePattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.regex.as_str() == other.regex.as_str()
+    }
+}
+
+impl Eq for ResourceNamePattern {}
+
+impl ResourceNamePattern {
+    /// Construct an instance from a glob pattern.
+    ///
+    /// `*` matches any sequence of characters, including `.`, and `?`
+    /// matches any single character. All other characters match literally.
+    pub fn new_glob(pattern: &str) -> Result<Self> {
+        let expression = pattern
+            .chars()
+            .map(|c| match c {
+                '*' => ".*".to_string(),
+                '?' => ".".to_string(),
+                c => regex::escape(&c.to_string()),
+            })
+            .collect::<String>();
+
+        Ok(Self {
+            pattern: pattern.to_string(),
+            regex: Regex::new(&format!("^(?:{})$", expression))
+                .with_context(|| format!("compiling glob pattern {}", pattern))?,
+        })
+    }
+
+    /// Construct an instance from a regular expression.
+    ///
+    /// The expression must match the entire resource name.
+    pub fn new_regex(pattern: &str) -> Result<Self> {
+        Ok(Self {
+            pattern: pattern.to_string(),
+            regex: Regex::new(&format!("^(?:{})$", pattern))
+                .with_context(|| format!("compiling regular expression {}", pattern))?,
+        })
+    }
+
+    /// The pattern this instance was constructed from.
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Whether a resource name matches this pattern.
+    pub fn matches(&self, name: &str) -> bool {
+        self.regex.is_match(name)
+    }
+}
+
+/// Verify a name is usable in license policy rules.
+fn validate_license_policy_name(name: &str) -> Result<()> {
+    if name == LICENSE_CLASS_COPYLEFT || spdx::license_id(name).is_some() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "{} is not an SPDX license identifier or \"{}\"",
+            name,
+            LICENSE_CLASS_COPYLEFT
+        ))
+    }
+}
+
 /// Defines how Python resources should be packaged.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct PythonPackagingPolicy {
@@ -178,6 +264,31 @@ pub struct PythonPackagingPolicy {
 
     /// Python modules for which bytecode should not be generated by default.
     no_bytecode_modules: HashSet<String>,
+
+    /// Bytecode optimization levels to generate for modules in specific packages.
+    ///
+    /// Overrides the `bytecode_optimize_level_*` fields for modules in the package.
+    package_bytecode_optimize_levels: HashMap<String, Vec<BytecodeOptimizationLevel>>,
+
+    /// Whether to compress in-memory data in packed resources with zstd.
+    packed_resources_zstd_compression: bool,
+
+    /// Resource names to include. If empty, all names are included.
+    resource_name_includes: Vec<ResourceNamePattern>,
+
+    /// Resource names to exclude.
+    resource_name_excludes: Vec<ResourceNamePattern>,
+
+    /// SPDX license identifiers or license classes packaged components may use.
+    ///
+    /// If empty, all licenses not in `denied_licenses` are allowed.
+    allowed_licenses: BTreeSet<String>,
+
+    /// SPDX license identifiers or license classes packaged components must not use.
+    denied_licenses: BTreeSet<String>,
+
+    /// Whether components without SPDX licensing information may be packaged.
+    allow_unknown_licenses: bool,
 }
 
 impl Default for PythonPackagingPolicy {
@@ -202,6 +313,13 @@ impl Default for PythonPackagingPolicy {
             bytecode_optimize_level_one: false,
             bytecode_optimize_level_two: false,
             no_bytecode_modules: HashSet::new(),
+            package_bytecode_optimize_levels: HashMap::new(),
+            packed_resources_zstd_compression: false,
+            resource_name_includes: vec![],
+            resource_name_excludes: vec![],
+            allowed_licenses: BTreeSet::new(),
+            denied_licenses: BTreeSet::new(),
+            allow_unknown_licenses: true,
         }
     }
 }
@@ -383,6 +501,16 @@ impl PythonPackagingPolicy {
         self.bytecode_optimize_level_two = value;
     }
 
+    /// Whether to compress in-memory data in packed resources with zstd.
+    pub fn packed_resources_zstd_compression(&self) -> bool {
+        self.packed_resources_zstd_compression
+    }
+
+    /// Set whether to compress in-memory data in packed resources with zstd.
+    pub fn set_packed_resources_zstd_compression(&mut self, value: bool) {
+        self.packed_resources_zstd_compression = value;
+    }
+
     /// Set the resource handling mode of the policy.
     ///
     /// This is a convenience function for mapping a `ResourceHandlingMode`
@@ -435,6 +563,203 @@ impl PythonPackagingPolicy {
         self.no_bytecode_modules.insert(name.to_string());
     }
 
+    /// Obtain patterns of resource names to include.
+    pub fn resource_name_includes(&self) -> &[ResourceNamePattern] {
+        &self.resource_name_includes
+    }
+
+    /// Register a pattern of resource names to include.
+    ///
+    /// Once any include pattern is registered, resources whose name doesn't
+    /// match at least one include pattern are not added by default.
+    pub fn add_resource_name_include(&mut self, pattern: ResourceNamePattern) {
+        self.resource_name_includes.push(pattern);
+    }
+
+    /// Obtain patterns of resource names to exclude.
+    pub fn resource_name_excludes(&self) -> &[ResourceNamePattern] {
+        &self.resource_name_excludes
+    }
+
+    /// Register a pattern of resource names to exclude.
+    ///
+    /// Resources whose name matches an exclude pattern are not added by
+    /// default. Excludes take precedence over includes.
+    pub fn add_resource_name_exclude(&mut self, pattern: ResourceNamePattern) {
+        self.resource_name_excludes.push(pattern);
+    }
+
+    /// Whether a resource name passes registered include and exclude patterns.
+    fn filter_resource_name(&self, name: &str) -> bool {
+        if self.resource_name_excludes.iter().any(|p| p.matches(name)) {
+            return false;
+        }
+
+        self.resource_name_includes.is_empty()
+            || self.resource_name_includes.iter().any(|p| p.matches(name))
+    }
+
+    /// Obtain bytecode optimization levels registered for specific packages.
+    pub fn package_bytecode_optimize_levels(
+        &self,
+    ) -> &HashMap<String, Vec<BytecodeOptimizationLevel>> {
+        &self.package_bytecode_optimize_levels
+    }
+
+    /// Define the bytecode optimization levels to generate for modules in a package.
+    ///
+    /// This applies to the named package and all modules within it, overriding
+    /// the `bytecode_optimize_level_*` settings. If multiple registered packages
+    /// contain a module, the most specific one is used. Modules registered
+    /// via `register_no_bytecode_module()` still don't generate bytecode.
+    pub fn set_package_bytecode_optimize_levels(
+        &mut self,
+        package: &str,
+        levels: Vec<BytecodeOptimizationLevel>,
+    ) {
+        self.package_bytecode_optimize_levels
+            .insert(package.to_string(), levels);
+    }
+
+    /// Resolve whether to generate bytecode at optimization levels 0, 1, and 2 for a module.
+    fn module_bytecode_optimize_levels(&self, name: &str) -> (bool, bool, bool) {
+        if self.no_bytecode_modules.contains(name) {
+            return (false, false, false);
+        }
+
+        let package_levels = self
+            .package_bytecode_optimize_levels
+            .iter()
+            .filter(|(package, _)| {
+                name == package.as_str()
+                    || (name.starts_with(package.as_str())
+                        && name[package.len()..].starts_with('.'))
+            })
+            .max_by_key(|(package, _)| package.len());
+
+        if let Some((_, levels)) = package_levels {
+            (
+                levels.contains(&BytecodeOptimizationLevel::Zero),
+                levels.contains(&BytecodeOptimizationLevel::One),
+                levels.contains(&BytecodeOptimizationLevel::Two),
+            )
+        } else {
+            (
+                self.bytecode_optimize_level_zero,
+                self.bytecode_optimize_level_one,
+                self.bytecode_optimize_level_two,
+            )
+        }
+    }
+
+    /// Obtain SPDX license identifiers or license classes packaged components may use.
+    pub fn allowed_licenses(&self) -> &BTreeSet<String> {
+        &self.allowed_licenses
+    }
+
+    /// Allow packaged components to use a license.
+    ///
+    /// `name` is an SPDX license identifier or [LICENSE_CLASS_COPYLEFT]. Once any
+    /// license is allowed, components using licenses that aren't allowed violate
+    /// the policy.
+    pub fn add_allowed_license(&mut self, name: &str) -> Result<()> {
+        validate_license_policy_name(name)?;
+        self.allowed_licenses.insert(name.to_string());
+
+        Ok(())
+    }
+
+    /// Obtain SPDX license identifiers or license classes packaged components must not use.
+    pub fn denied_licenses(&self) -> &BTreeSet<String> {
+        &self.denied_licenses
+    }
+
+    /// Deny packaged components from using a license.
+    ///
+    /// `name` is an SPDX license identifier or [LICENSE_CLASS_COPYLEFT]. Denied
+    /// licenses take precedence over allowed licenses.
+    pub fn add_denied_license(&mut self, name: &str) -> Result<()> {
+        validate_license_policy_name(name)?;
+        self.denied_licenses.insert(name.to_string());
+
+        Ok(())
+    }
+
+    /// Whether components without SPDX licensing information may be packaged.
+    pub fn allow_unknown_licenses(&self) -> bool {
+        self.allow_unknown_licenses
+    }
+
+    /// Set whether components without SPDX licensing information may be packaged.
+    pub fn set_allow_unknown_licenses(&mut self, value: bool) {
+        self.allow_unknown_licenses = value;
+    }
+
+    /// Whether an SPDX license requirement is allowed by the license policy.
+    fn license_requirement_allowed(&self, req: &spdx::LicenseReq) -> bool {
+        if let Some(id) = req.license.id() {
+            let matches = |names: &BTreeSet<String>| {
+                names.contains(id.name)
+                    || (id.is_copyleft() && names.contains(LICENSE_CLASS_COPYLEFT))
+            };
+
+            !matches(&self.denied_licenses)
+                && (self.allowed_licenses.is_empty() || matches(&self.allowed_licenses))
+        } else {
+            self.allow_unknown_licenses
+        }
+    }
+
+    /// Find licensed components violating the license policy.
+    ///
+    /// Returns a description of each violation.
+    pub fn license_policy_violations(&self, components: &LicensedComponents) -> Vec<String> {
+        components
+            .iter_components()
+            .filter_map(|component| match component.license() {
+                LicenseFlavor::Spdx(expression) => {
+                    if expression.evaluate(|req| self.license_requirement_allowed(req)) {
+                        None
+                    } else {
+                        Some(format!(
+                            "{}: license {} is not allowed",
+                            component.flavor(),
+                            expression
+                        ))
+                    }
+                }
+                LicenseFlavor::PublicDomain => None,
+                LicenseFlavor::None
+                | LicenseFlavor::OtherExpression(_)
+                | LicenseFlavor::Unknown(_) => {
+                    if self.allow_unknown_licenses {
+                        None
+                    } else {
+                        Some(format!(
+                            "{}: license is not a known SPDX license",
+                            component.flavor()
+                        ))
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Verify licensed components comply with the license policy.
+    pub fn check_license_policy(&self, components: &LicensedComponents) -> Result<()> {
+        let violations = self.license_policy_violations(components);
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "{} component(s) violate the license policy:\n{}",
+                violations.len(),
+                violations.join("\n")
+            ))
+        }
+    }
+
     /// Derive a `PythonResourceAddCollectionContext` for a resource using current settings.
     ///
     /// The returned object essentially says how the resource should be added
@@ -443,7 +768,8 @@ impl PythonPackagingPolicy {
         &self,
         resource: &PythonResource,
     ) -> PythonResourceAddCollectionContext {
-        let include = self.filter_python_resource(resource);
+        let include = self.filter_python_resource(resource)
+            && self.filter_resource_name(&resource.full_name());
 
         let store_source = match resource {
             PythonResource::ModuleSource(ref module) => {
@@ -459,35 +785,15 @@ impl PythonPackagingPolicy {
         let location = self.resources_location.clone();
         let location_fallback = self.resources_location_fallback.clone();
 
-        let optimize_level_zero = match resource {
-            PythonResource::ModuleSource(module) => {
-                if self.no_bytecode_modules.contains(&*module.name) {
-                    false
-                } else {
-                    self.bytecode_optimize_level_zero
-                }
-            }
-            _ => self.bytecode_optimize_level_zero,
-        };
-        let optimize_level_one = match resource {
-            PythonResource::ModuleSource(module) => {
-                if self.no_bytecode_modules.contains(&*module.name) {
-                    false
-                } else {
-                    self.bytecode_optimize_level_one
-                }
-            }
-            _ => self.bytecode_optimize_level_one,
-        };
-        let optimize_level_two = match resource {
+        let (optimize_level_zero, optimize_level_one, optimize_level_two) = match resource {
             PythonResource::ModuleSource(module) => {
-                if self.no_bytecode_modules.contains(&*module.name) {
-                    false
-                } else {
-                    self.bytecode_optimize_level_two
-                }
+                self.module_bytecode_optimize_levels(&module.name)
             }
-            _ => self.bytecode_optimize_level_two,
+            _ => (
+                self.bytecode_optimize_level_zero,
+                self.bytecode_optimize_level_one,
+                self.bytecode_optimize_level_two,
+            ),
         };
 
         PythonResourceAddCollectionContext {
@@ -683,7 +989,14 @@ impl PythonPackagingPolicy {
 
 #[cfg(test)]
 mod tests {
-    use {super::*, simple_file_manifest::File};
+    use {
+        super::*,
+        crate::{
+            licensing::{ComponentFlavor, LicensedComponent},
+            resource::PythonModuleSource,
+        },
+        simple_file_manifest::{File, FileData},
+    };
 
     #[test]
     fn test_add_collection_context_file() -> Result<()> {
@@ -703,4 +1016,145 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_package_bytecode_optimize_levels() -> Result<()> {
+        let mut policy = PythonPackagingPolicy::default();
+        policy.set_package_bytecode_optimize_levels("foo", vec![BytecodeOptimizationLevel::Two]);
+        policy.set_package_bytecode_optimize_levels(
+            "foo.debug",
+            vec![
+                BytecodeOptimizationLevel::Zero,
+                BytecodeOptimizationLevel::One,
+            ],
+        );
+        policy.register_no_bytecode_module("foo.nobytecode");
+
+        let levels = |name: &str| {
+            let context = policy.derive_add_collection_context(
+                &PythonModuleSource {
+                    name: name.to_string(),
+                    source: FileData::Memory(vec![]),
+                    is_package: false,
+                    cache_tag: "cpython-39".to_string(),
+                    is_stdlib: false,
+                    is_test: false,
+                }
+                .into(),
+            );
+
+            (
+                context.optimize_level_zero,
+                context.optimize_level_one,
+                context.optimize_level_two,
+            )
+        };
+
+        assert_eq!(levels("foo"), (false, false, true));
+        assert_eq!(levels("foo.bar"), (false, false, true));
+        assert_eq!(levels("foo.debug"), (true, true, false));
+        assert_eq!(levels("foo.debug.util"), (true, true, false));
+        assert_eq!(levels("foo.nobytecode"), (false, false, false));
+        assert_eq!(levels("foobar"), (true, false, false));
+        assert_eq!(levels("other"), (true, false, false));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_license_policy() -> Result<()> {
+        let mut components = LicensedComponents::default();
+        components.add_component(LicensedComponent::new_spdx(
+            ComponentFlavor::PythonModule("mit".into()),
+            "MIT",
+        )?);
+        components.add_component(LicensedComponent::new_spdx(
+            ComponentFlavor::Library("gpl".into()),
+            "GPL-3.0-only",
+        )?);
+        components.add_component(LicensedComponent::new_spdx(
+            ComponentFlavor::PythonModule("dual".into()),
+            "MIT OR GPL-3.0-only",
+        )?);
+        components.add_component(LicensedComponent::new(
+            ComponentFlavor::PythonModule("unknown".into()),
+            LicenseFlavor::None,
+        ));
+
+        let mut policy = PythonPackagingPolicy::default();
+        assert!(policy.license_policy_violations(&components).is_empty());
+        policy.check_license_policy(&components)?;
+
+        assert!(policy.add_denied_license("not-a-license").is_err());
+
+        policy.add_denied_license("copyleft")?;
+        assert_eq!(
+            policy.license_policy_violations(&components),
+            vec!["library gpl: license GPL-3.0-only is not allowed".to_string()]
+        );
+        assert!(policy.check_license_policy(&components).is_err());
+
+        policy.set_allow_unknown_licenses(false);
+        assert_eq!(policy.license_policy_violations(&components).len(), 2);
+
+        let mut policy = PythonPackagingPolicy::default();
+        policy.add_allowed_license("GPL-3.0-only")?;
+        assert_eq!(
+            policy.license_policy_violations(&components),
+            vec!["Python module mit: license MIT is not allowed".to_string()]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resource_name_pattern() -> Result<()> {
+        let p = ResourceNamePattern::new_glob("*.tests")?;
+        assert!(p.matches("foo.tests"));
+        assert!(p.matches("foo.bar.tests"));
+        assert!(!p.matches("foo.tests.test_foo"));
+        assert!(!p.matches("fooXtests"));
+
+        let p = ResourceNamePattern::new_glob("foo.?")?;
+        assert!(p.matches("foo.a"));
+        assert!(!p.matches("foo.ab"));
+
+        let p = ResourceNamePattern::new_regex(r"foo\.(bar|baz)")?;
+        assert!(p.matches("foo.bar"));
+        assert!(p.matches("foo.baz"));
+        assert!(!p.matches("foo.bar.x"));
+
+        assert!(ResourceNamePattern::new_regex("(").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_collection_context_resource_names() -> Result<()> {
+        let mut policy = PythonPackagingPolicy {
+            include_file_resources: true,
+            ..Default::default()
+        };
+
+        let foo = File::new("foo/__init__.py", vec![42]);
+        let bar = File::new("bar/__init__.py", vec![42]);
+
+        policy.add_resource_name_exclude(ResourceNamePattern::new_glob("foo/*")?);
+        assert!(
+            !policy
+                .derive_add_collection_context(&foo.clone().into())
+                .include
+        );
+        assert!(
+            policy
+                .derive_add_collection_context(&bar.clone().into())
+                .include
+        );
+
+        policy.add_resource_name_include(ResourceNamePattern::new_glob("foo*")?);
+        assert!(!policy.derive_add_collection_context(&foo.into()).include);
+        assert!(!policy.derive_add_collection_context(&bar.into()).include);
+
+        Ok(())
+    }
 }
@@ -8,7 +8,11 @@
 
 /*! Utility functions related to Python source code. */
 
-use {anyhow::Result, once_cell::sync::Lazy};
+use {
+    anyhow::Result,
+    once_cell::sync::Lazy,
+    std::{borrow::Cow, collections::BTreeSet},
+};
 
 static RE_CODING: Lazy<regex::bytes::Regex> = Lazy::new(|| {
     regex::bytes::Regex::new(r"^[ \t\f]*#.*?coding[:=][ \t]*([-_.a-zA-Z0-9]+)").unwrap()
@@ -39,6 +43,11 @@ pub fn has_dunder_file(source: &[u8]) -> Result<bool> {
     // We can't just look for b"__file__ because the source file may be in
     // encodings like UTF-16. So we need to decode to Unicode first then look for
     // the code points.
+    Ok(decode_source(source).contains("__file__"))
+}
+
+/// Decode Python source code to Unicode using its declared encoding.
+fn decode_source(source: &[u8]) -> Cow<str> {
     let encoding = python_source_encoding(source);
 
     let encoder = match encoding_rs::Encoding::for_label(&encoding) {
@@ -48,5 +57,472 @@ pub fn has_dunder_file(source: &[u8]) -> Result<bool> {
 
     let (source, ..) = encoder.decode(source);
 
-    Ok(source.contains("__file__"))
+    source
+}
+
+static RE_MAIN_BLOCK: Lazy<regex::Regex> =
+    Lazy::new(|| regex::Regex::new(r#"^if\s+__name__\s*==\s*['"]__main__['"]\s*:"#).unwrap());
+
+static RE_FUNCTION: Lazy<regex::Regex> =
+    Lazy::new(|| regex::Regex::new(r"^(?:async\s+)?def\s+([A-Za-z_][A-Za-z0-9_]*)").unwrap());
+
+/// A top-level statement in Python source code and the lines belonging to it.
+struct TopLevelBlock {
+    text: String,
+    is_main: bool,
+    function: Option<String>,
+}
+
+/// Remove code that only runs when a module is executed as a script.
+///
+/// Top-level `if __name__ == "__main__":` blocks are removed, as are top-level
+/// functions that are only referenced from these blocks.
+fn strip_main_blocks(source: &str) -> String {
+    let mut blocks: Vec<TopLevelBlock> = vec![];
+
+    for line in source.split_inclusive('\n') {
+        let continuation = line.trim().is_empty()
+            || line.starts_with(|c: char| c.is_whitespace() || "#)]}".contains(c));
+
+        match blocks.last_mut() {
+            Some(block) if continuation => {
+                block.text.push_str(line);
+            }
+            _ => {
+                blocks.push(TopLevelBlock {
+                    text: line.to_string(),
+                    is_main: RE_MAIN_BLOCK.is_match(line),
+                    function: RE_FUNCTION.captures(line).map(|caps| caps[1].to_string()),
+                });
+            }
+        }
+    }
+
+    if !blocks.iter().any(|block| block.is_main) {
+        return source.to_string();
+    }
+
+    // Identifiers referenced by each block.
+    let names = blocks
+        .iter()
+        .map(|block| {
+            tokenize(&block.text)
+                .into_iter()
+                .filter_map(|token| match token {
+                    Token::Name(name) => Some(name),
+                    _ => None,
+                })
+                .collect::<BTreeSet<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let main_names = blocks
+        .iter()
+        .zip(names.iter())
+        .filter(|(block, _)| block.is_main)
+        .flat_map(|(_, names)| names.iter())
+        .collect::<BTreeSet<_>>();
+
+    blocks
+        .iter()
+        .enumerate()
+        .filter(|(i, block)| {
+            if block.is_main {
+                return false;
+            }
+
+            match &block.function {
+                Some(name) if main_names.contains(name) => blocks
+                    .iter()
+                    .zip(names.iter())
+                    .enumerate()
+                    .any(|(j, (other, names))| j != *i && !other.is_main && names.contains(name)),
+                _ => true,
+            }
+        })
+        .map(|(_, block)| block.text.as_str())
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Name(String),
+    Str(String),
+    Op(char),
+}
+
+/// Split Python source code into a simplified stream of tokens.
+///
+/// Comments and whitespace are dropped. String literals are reduced to their
+/// raw contents, without processing escape sequences.
+fn tokenize(source: &str) -> Vec<Token> {
+    let chars = source.chars().collect::<Vec<_>>();
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '#' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c.is_whitespace() || c == '\\' {
+            i += 1;
+        } else if c == '"' || c == '\'' {
+            let (value, end) = read_string(&chars, i);
+            tokens.push(Token::Str(value));
+            i = end;
+        } else if c.is_alphanumeric() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let name = chars[start..i].iter().collect::<String>();
+
+            // String prefixes like r"" and b''.
+            if name.len() <= 2
+                && name.chars().all(|c| "rRbBuUfF".contains(c))
+                && i < chars.len()
+                && (chars[i] == '"' || chars[i] == '\'')
+            {
+                let (value, end) = read_string(&chars, i);
+                tokens.push(Token::Str(value));
+                i = end;
+            } else {
+                tokens.push(Token::Name(name));
+            }
+        } else {
+            tokens.push(Token::Op(c));
+            i += 1;
+        }
+    }
+
+    tokens
+}
+
+/// Read a string literal whose opening quote is at `start`.
+///
+/// Returns the contents of the string and the offset after its closing quote.
+fn read_string(chars: &[char], start: usize) -> (String, usize) {
+    let quote = chars[start];
+    let triple = chars.len() > start + 2 && chars[start + 1] == quote && chars[start + 2] == quote;
+    let quote_len = if triple { 3 } else { 1 };
+
+    let mut i = start + quote_len;
+    let mut value = String::new();
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '\\' {
+            value.push(c);
+            if let Some(next) = chars.get(i + 1) {
+                value.push(*next);
+            }
+            i += 2;
+        } else if c == quote
+            && (!triple || (chars.get(i + 1) == Some(&quote) && chars.get(i + 2) == Some(&quote)))
+        {
+            return (value, i + quote_len);
+        } else if c == '\n' && !triple {
+            // Unterminated string. Stop at the end of the line.
+            return (value, i);
+        } else {
+            value.push(c);
+            i += 1;
+        }
+    }
+
+    (value, i)
+}
+
+/// Read a dotted name like `foo.bar` starting at token `i`.
+///
+/// Returns the name and the index of the token following it.
+fn read_dotted_name(tokens: &[Token], mut i: usize) -> (String, usize) {
+    let mut parts = vec![];
+
+    while let Some(Token::Name(name)) = tokens.get(i) {
+        parts.push(name.as_str());
+        i += 1;
+
+        if tokens.get(i) == Some(&Token::Op('.'))
+            && matches!(tokens.get(i + 1), Some(Token::Name(_)))
+        {
+            i += 1;
+        } else {
+            break;
+        }
+    }
+
+    (parts.join("."), i)
+}
+
+/// Resolve the package a relative import of the given level is relative to.
+fn resolve_relative_package(module: &str, is_package: bool, level: usize) -> Option<String> {
+    let mut parts = module.split('.').collect::<Vec<_>>();
+
+    if !is_package {
+        parts.pop();
+    }
+
+    for _ in 1..level {
+        parts.pop()?;
+    }
+
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join("."))
+    }
+}
+
+/// Find the names of modules imported by Python source code.
+///
+/// `module` and `is_package` describe the module the source code belongs to
+/// and are used to resolve relative imports. `is_main` indicates whether the
+/// module is executed as `__main__`, such as by `python -m`.
+///
+/// This is a best effort scan of the source code and doesn't evaluate it.
+/// `import` and `from ... import` statements are recognized anywhere they
+/// occur, including in functions and conditional blocks. Unless `is_main` is
+/// set, top-level `if __name__ == "__main__":` blocks and functions only
+/// called from them are ignored. Calls to
+/// `importlib.import_module()` and `__import__()` are recognized when the
+/// module name is a string literal. Other forms of dynamic imports are not
+/// detected.
+///
+/// For `from foo import bar`, both `foo` and `foo.bar` are returned, as `bar`
+/// may refer to a module. Returned names may therefore not refer to modules
+/// that exist.
+pub fn find_imported_modules(
+    source: &[u8],
+    module: &str,
+    is_package: bool,
+    is_main: bool,
+) -> BTreeSet<String> {
+    let source = decode_source(source);
+    let tokens = if is_main {
+        tokenize(&source)
+    } else {
+        tokenize(&strip_main_blocks(&source))
+    };
+    let mut imports = BTreeSet::new();
+    let mut i = 0;
+
+    while i < tokens.len() {
+        match &tokens[i] {
+            Token::Name(name) if name == "import" => {
+                i += 1;
+
+                loop {
+                    let (name, next) = read_dotted_name(&tokens, i);
+                    if name.is_empty() {
+                        break;
+                    }
+                    imports.insert(name);
+                    i = next;
+
+                    if tokens.get(i) == Some(&Token::Name("as".to_string())) {
+                        i += 2;
+                    }
+
+                    if tokens.get(i) == Some(&Token::Op(',')) {
+                        i += 1;
+                    } else {
+                        break;
+                    }
+                }
+            }
+            Token::Name(name) if name == "from" => {
+                i += 1;
+
+                let mut level = 0;
+                while tokens.get(i) == Some(&Token::Op('.')) {
+                    level += 1;
+                    i += 1;
+                }
+
+                // `from . import foo` has no name.
+                let (name, next) = if tokens.get(i) == Some(&Token::Name("import".to_string())) {
+                    (String::new(), i)
+                } else {
+                    read_dotted_name(&tokens, i)
+                };
+                i = next;
+
+                // `yield from` and `raise ... from` aren't imports.
+                if tokens.get(i) != Some(&Token::Name("import".to_string()))
+                    || (level == 0 && name.is_empty())
+                {
+                    continue;
+                }
+                i += 1;
+
+                let base = if level > 0 {
+                    match resolve_relative_package(module, is_package, level) {
+                        Some(package) if name.is_empty() => package,
+                        Some(package) => format!("{}.{}", package, name),
+                        None => continue,
+                    }
+                } else {
+                    name
+                };
+
+                let parenthesized = tokens.get(i) == Some(&Token::Op('('));
+                if parenthesized {
+                    i += 1;
+                }
+
+                imports.insert(base.clone());
+
+                while let Some(Token::Name(name)) = tokens.get(i) {
+                    imports.insert(format!("{}.{}", base, name));
+                    i += 1;
+
+                    if tokens.get(i) == Some(&Token::Name("as".to_string())) {
+                        i += 2;
+                    }
+
+                    if tokens.get(i) == Some(&Token::Op(',')) {
+                        i += 1;
+                    } else {
+                        break;
+                    }
+                }
+
+                if parenthesized && tokens.get(i) == Some(&Token::Op(')')) {
+                    i += 1;
+                }
+            }
+            Token::Name(name) if name == "import_module" || name == "__import__" => {
+                if let (Some(Token::Op('(')), Some(Token::Str(value))) =
+                    (tokens.get(i + 1), tokens.get(i + 2))
+                {
+                    if !value.is_empty() && !value.starts_with('.') {
+                        imports.insert(value.clone());
+                    }
+                }
+
+                i += 1;
+            }
+            _ => {
+                i += 1;
+            }
+        }
+    }
+
+    imports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn imports(source: &str, module: &str, is_package: bool) -> Vec<String> {
+        find_imported_modules(source.as_bytes(), module, is_package, false)
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn test_find_imported_modules_absolute() {
+        assert_eq!(
+            imports(
+                "import os\nimport os.path, sys as system\n\ndef f():\n    import json\n",
+                "foo",
+                false
+            ),
+            vec!["json", "os", "os.path", "sys"]
+        );
+
+        assert_eq!(
+            imports(
+                "from email import (\n    message,\n    parser as p,\n)\nfrom a.b import *\n",
+                "foo",
+                false
+            ),
+            vec!["a.b", "email", "email.message", "email.parser"]
+        );
+    }
+
+    #[test]
+    fn test_find_imported_modules_relative() {
+        assert_eq!(
+            imports("from . import a\nfrom .b import c\n", "pkg.mod", false),
+            vec!["pkg", "pkg.a", "pkg.b", "pkg.b.c"]
+        );
+        assert_eq!(
+            imports("from . import a\n", "pkg", true),
+            vec!["pkg", "pkg.a"]
+        );
+        assert_eq!(
+            imports("from ..x import y\n", "pkg.sub.mod", false),
+            vec!["pkg.x", "pkg.x.y"]
+        );
+        assert!(imports("from .. import a\n", "mod", false).is_empty());
+    }
+
+    #[test]
+    fn test_find_imported_modules_ignored() {
+        assert!(imports(
+            "# import os\ns = 'import sys'\nt = \"\"\"\nfrom json import x\n\"\"\"\n",
+            "foo",
+            false
+        )
+        .is_empty());
+
+        assert!(imports(
+            "def f():\n    yield from g()\n    raise ValueError() from e\n",
+            "foo",
+            false
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_find_imported_modules_main_block() {
+        assert_eq!(
+            imports(
+                "import os\n\nif __name__ == '__main__':\n    import argparse\n\n    main()\nimport sys\n",
+                "foo",
+                false
+            ),
+            vec!["os", "sys"]
+        );
+        assert_eq!(
+            imports(
+                "def f():\n    if __name__ == \"__main__\":\n        import argparse\n",
+                "foo",
+                false
+            ),
+            vec!["argparse"]
+        );
+    }
+
+    #[test]
+    fn test_find_imported_modules_main_functions() {
+        let source = "import os\n\ndef _test():\n    import doctest\n\ndef helper():\n    import json\n\ndef other():\n    helper()\n\nif __name__ == '__main__':\n    _test()\n    helper()\n";
+
+        assert_eq!(imports(source, "foo", false), vec!["json", "os"]);
+        assert_eq!(
+            find_imported_modules(source.as_bytes(), "foo", false, true)
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!["doctest", "json", "os"]
+        );
+    }
+
+    #[test]
+    fn test_find_imported_modules_dynamic() {
+        assert_eq!(
+            imports(
+                "importlib.import_module('json')\n__import__(\"csv\")\nimport_module(name)\n",
+                "foo",
+                false
+            ),
+            vec!["csv", "json"]
+        );
+    }
 }
@@ -0,0 +1,256 @@
+// Copyright 2022 Gregory Szorc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*! Packaging rules for Qt Python bindings.
+
+Qt bindings like PyQt5 and PySide6 ship Qt shared libraries, plugins, and
+QML files inside their Python package directory. Qt loads these files from
+the filesystem at run-time and locates them relative to the package
+directory. So these packages need to be installed on the filesystem with
+their original layout.
+*/
+
+use crate::resource::PythonResource;
+
+/// Top-level Python packages providing Qt bindings.
+pub const QT_BINDINGS_PACKAGES: &[&str] = &["PyQt5", "PyQt6", "PySide2", "PySide6"];
+
+/// Top-level Python packages Qt bindings depend on at run-time.
+///
+/// Shared libraries in the bindings packages reference libraries in these
+/// packages via relative paths. So they must be installed alongside the
+/// bindings.
+pub const QT_SUPPORT_PACKAGES: &[&str] = &["shiboken2", "shiboken6"];
+
+/// Whether a top-level name is a Qt bindings package or a distribution of one.
+///
+/// Distribution names like `PySide6_Essentials`, `PyQt5-Qt5`, and
+/// `PySide6-6.4.0.dist-info` match in addition to exact package names.
+fn is_qt_name(name: &str, packages: &[&str]) -> bool {
+    let normalized = name.to_lowercase().replace('-', "_");
+
+    packages.iter().any(|package| {
+        let package = package.to_lowercase();
+
+        normalized == package || normalized.starts_with(&format!("{}_", package))
+    })
+}
+
+fn top_level_name(name: &str) -> &str {
+    name.split('.').next().unwrap_or(name)
+}
+
+/// Whether a resource belongs to a Qt bindings package or a package it depends on.
+pub fn is_qt_resource(resource: &PythonResource) -> bool {
+    let packages = [QT_BINDINGS_PACKAGES, QT_SUPPORT_PACKAGES].concat();
+
+    match resource {
+        PythonResource::ModuleSource(m) => is_qt_name(top_level_name(&m.name), &packages),
+        PythonResource::ModuleBytecode(m) => is_qt_name(top_level_name(&m.name), &packages),
+        PythonResource::ModuleBytecodeRequest(m) => is_qt_name(top_level_name(&m.name), &packages),
+        PythonResource::PackageResource(r) => {
+            is_qt_name(top_level_name(&r.leaf_package), &packages)
+        }
+        PythonResource::PackageDistributionResource(r) => is_qt_name(&r.package, &packages),
+        PythonResource::ExtensionModule(em) => is_qt_name(top_level_name(&em.name), &packages),
+        PythonResource::EggFile(_) => false,
+        PythonResource::PathExtension(_) => false,
+        PythonResource::File(f) => f
+            .path()
+            .components()
+            .next()
+            .map(|c| is_qt_name(&c.as_os_str().to_string_lossy(), &packages))
+            .unwrap_or(false),
+    }
+}
+
+/// Resolve the `/` delimited path of a Qt bindings data file relative to the packages root.
+///
+/// Returns `None` if the resource isn't a data file in a Qt bindings package.
+pub fn qt_resource_path(resource: &PythonResource) -> Option<String> {
+    let path = match resource {
+        PythonResource::PackageResource(r) => {
+            format!("{}/{}", r.leaf_package.replace('.', "/"), r.relative_name)
+        }
+        PythonResource::File(f) => f.path().display().to_string().replace('\\', "/"),
+        _ => return None,
+    };
+
+    if QT_BINDINGS_PACKAGES.contains(&path.split('/').next().unwrap_or("")) {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// Describes where Qt looks for run-time files within a Qt bindings package.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QtLayout {
+    /// `/` delimited path of the directory holding Qt plugins, relative to the packages root.
+    pub plugins_path: Option<String>,
+
+    /// `/` delimited path of the directory holding QML imports, relative to the packages root.
+    pub qml_path: Option<String>,
+}
+
+impl QtLayout {
+    /// Derive the layout from paths of Qt bindings data files.
+    ///
+    /// Paths are `/` delimited and relative to the packages root, as returned
+    /// by [qt_resource_path()]. The directory layout differs between bindings
+    /// and platforms (e.g. `PySide6/Qt/plugins` vs `PyQt5/Qt5/plugins` vs
+    /// `PySide6/plugins`). So we look for the shallowest `plugins` and `qml`
+    /// directories.
+    pub fn from_paths<'a>(paths: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut layout = Self::default();
+
+        for path in paths {
+            let components = path.split('/').collect::<Vec<_>>();
+
+            // The first component is the package directory and the last is
+            // the filename. Neither can be the directory we're looking for.
+            for i in 1..components.len().saturating_sub(1) {
+                let slot = match components[i] {
+                    "plugins" => &mut layout.plugins_path,
+                    "qml" => &mut layout.qml_path,
+                    _ => continue,
+                };
+
+                let candidate = components[..=i].join("/");
+
+                let replace = match slot {
+                    Some(existing) => existing.split('/').count() > i + 1,
+                    None => true,
+                };
+
+                if replace {
+                    *slot = Some(candidate);
+                }
+
+                break;
+            }
+        }
+
+        layout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::resource::{PythonModuleSource, PythonPackageResource},
+        simple_file_manifest::{File, FileData, FileEntry},
+    };
+
+    #[test]
+    fn test_is_qt_resource() {
+        let module = |name: &str| -> PythonResource {
+            PythonModuleSource {
+                name: name.to_string(),
+                source: FileData::Memory(vec![]),
+                is_package: false,
+                cache_tag: "cpython-39".to_string(),
+                is_stdlib: false,
+                is_test: false,
+            }
+            .into()
+        };
+
+        assert!(is_qt_resource(&module("PySide6")));
+        assert!(is_qt_resource(&module("PySide6.QtCore")));
+        assert!(is_qt_resource(&module("PyQt5.sip")));
+        assert!(is_qt_resource(&module("shiboken6")));
+        assert!(!is_qt_resource(&module("PySide6x")));
+        assert!(!is_qt_resource(&module("qtpy")));
+
+        let file = |path: &str| -> PythonResource {
+            File::new(path, FileEntry::new_from_data(vec![], false)).into()
+        };
+
+        assert!(is_qt_resource(&file(
+            "PySide6/Qt/plugins/platforms/libqxcb.so"
+        )));
+        assert!(is_qt_resource(&file("PySide6-6.4.0.dist-info/RECORD")));
+        assert!(is_qt_resource(&file(
+            "PySide6_Essentials-6.4.0.dist-info/RECORD"
+        )));
+        assert!(!is_qt_resource(&file("foo/PySide6/__init__.py")));
+    }
+
+    #[test]
+    fn test_qt_resource_path() {
+        let resource: PythonResource = PythonPackageResource {
+            leaf_package: "PySide6".to_string(),
+            relative_name: "Qt/plugins/platforms/libqxcb.so".to_string(),
+            data: FileData::Memory(vec![]),
+            is_stdlib: false,
+            is_test: false,
+        }
+        .into();
+
+        assert_eq!(
+            qt_resource_path(&resource),
+            Some("PySide6/Qt/plugins/platforms/libqxcb.so".to_string())
+        );
+
+        let resource: PythonResource = PythonPackageResource {
+            leaf_package: "shiboken6".to_string(),
+            relative_name: "libshiboken6.so".to_string(),
+            data: FileData::Memory(vec![]),
+            is_stdlib: false,
+            is_test: false,
+        }
+        .into();
+
+        assert_eq!(qt_resource_path(&resource), None);
+    }
+
+    #[test]
+    fn test_layout_from_paths() {
+        assert_eq!(QtLayout::from_paths(vec![]), QtLayout::default());
+
+        assert_eq!(
+            QtLayout::from_paths(vec![
+                "PySide6/Qt/lib/libQt6Core.so.6",
+                "PySide6/Qt/plugins/platforms/libqxcb.so",
+                "PySide6/Qt/qml/QtQuick/qmldir",
+                "PySide6/Qt/qml/QtQuick/plugins/foo.qml",
+            ]),
+            QtLayout {
+                plugins_path: Some("PySide6/Qt/plugins".to_string()),
+                qml_path: Some("PySide6/Qt/qml".to_string()),
+            }
+        );
+
+        assert_eq!(
+            QtLayout::from_paths(vec!["PyQt5/Qt5/plugins/platforms/libqxcb.so"]),
+            QtLayout {
+                plugins_path: Some("PyQt5/Qt5/plugins".to_string()),
+                qml_path: None,
+            }
+        );
+
+        assert_eq!(
+            QtLayout::from_paths(vec![
+                "PySide6/qml/Foo/plugins/bar.dll",
+                "PySide6/plugins/platforms/qwindows.dll",
+            ]),
+            QtLayout {
+                plugins_path: Some("PySide6/plugins".to_string()),
+                qml_path: Some("PySide6/qml".to_string()),
+            }
+        );
+
+        // A file named plugins isn't a directory.
+        assert_eq!(
+            QtLayout::from_paths(vec!["PySide6/plugins"]),
+            QtLayout::default()
+        );
+    }
+}
@@ -17,7 +17,7 @@ use {
         licensing::{LicensedComponent, LicensedComponents},
         location::{AbstractResourceLocation, ConcreteResourceLocation},
         module_util::{packages_from_module_name, resolve_path_for_module},
-        python_source::has_dunder_file,
+        python_source::{find_imported_modules, has_dunder_file},
         resource::{
             BytecodeOptimizationLevel, PythonExtensionModule, PythonModuleBytecode,
             PythonModuleBytecodeFromSource, PythonModuleSource, PythonPackageDistributionResource,
@@ -89,6 +89,7 @@ pub struct PrePackagedResource {
     pub file_executable: bool,
     pub file_data_embedded: Option<FileData>,
     pub file_data_utf8_relative_path: Option<(PathBuf, FileData)>,
+    pub data_namespace: Option<String>,
 }
 
 impl PrePackagedResource {
@@ -100,6 +101,61 @@ impl PrePackagedResource {
             || self.is_extension_module
     }
 
+    /// Whether this resource defines code for a Python module.
+    ///
+    /// Resources without source, bytecode, or an extension module are
+    /// candidates for being implicit namespace packages.
+    pub fn has_module_code(&self) -> bool {
+        self.in_memory_source.is_some()
+            || self.in_memory_bytecode.is_some()
+            || self.in_memory_bytecode_opt1.is_some()
+            || self.in_memory_bytecode_opt2.is_some()
+            || self.in_memory_extension_module_shared_library.is_some()
+            || self.relative_path_module_source.is_some()
+            || self.relative_path_bytecode.is_some()
+            || self.relative_path_bytecode_opt1.is_some()
+            || self.relative_path_bytecode_opt2.is_some()
+            || self.relative_path_extension_module_shared_library.is_some()
+            || self.is_builtin_extension_module
+            || self.is_frozen_module
+    }
+
+    /// Obtain the Python source code for this module, if available.
+    ///
+    /// Source code is resolved from any location it is stored in, including
+    /// bytecode that will be compiled from source.
+    pub fn module_source(&self) -> Option<&FileData> {
+        if let Some(source) = &self.in_memory_source {
+            return Some(source);
+        }
+
+        if let Some((_, source)) = &self.relative_path_module_source {
+            return Some(source);
+        }
+
+        [
+            &self.in_memory_bytecode,
+            &self.in_memory_bytecode_opt1,
+            &self.in_memory_bytecode_opt2,
+        ]
+        .into_iter()
+        .flatten()
+        .chain(
+            [
+                &self.relative_path_bytecode,
+                &self.relative_path_bytecode_opt1,
+                &self.relative_path_bytecode_opt2,
+            ]
+            .into_iter()
+            .flatten()
+            .map(|(_, _, provider)| provider),
+        )
+        .find_map(|provider| match provider {
+            PythonModuleBytecodeProvider::FromSource(source) => Some(source),
+            PythonModuleBytecodeProvider::Provided(_) => None,
+        })
+    }
+
     /// Convert the instance to a `Resource`.
     ///
     /// This will compile bytecode from source code using the specified compiler.
@@ -402,6 +458,11 @@ impl PrePackagedResource {
             } else {
                 None
             },
+            is_zstd_compressed: false,
+            data_namespace: self
+                .data_namespace
+                .as_ref()
+                .map(|namespace| Cow::Owned(namespace.clone())),
         };
 
         if let Some((prefix, filename, location)) = &self.relative_path_shared_library {
@@ -425,6 +486,12 @@ impl PrePackagedResource {
 /// a particular field, we populate that field in all its parent
 /// packages. If a corresponding fields is already populated, we
 /// copy its data as well.
+///
+/// Parent packages not defining any code of their own are treated as
+/// implicit namespace packages (PEP 420). No code is synthesized for them,
+/// as an empty `__init__` would turn them into regular packages and prevent
+/// portions of the namespace provided by other distributions from being
+/// found.
 pub fn populate_parent_packages(
     resources: &mut BTreeMap<String, PrePackagedResource>,
 ) -> Result<()> {
@@ -452,6 +519,11 @@ pub fn populate_parent_packages(
             entry.is_module = true;
             entry.is_package = true;
 
+            if entry.is_namespace_package || !entry.has_module_code() {
+                entry.is_namespace_package = true;
+                continue;
+            }
+
             // We want to materialize bytecode on parent packages no matter
             // what. If the original resource has a variant of bytecode in a
             // location, we materialize that variant on parents. We take
@@ -645,9 +717,9 @@ pub struct CompiledResourcesCollection<'a> {
 }
 
 impl<'a> CompiledResourcesCollection<'a> {
-    /// Write resources to packed resources data, version 1.
+    /// Write resources to packed resources data, version 4.
     pub fn write_packed_resources<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
-        python_packed_resources::write_packed_resources_v3(
+        python_packed_resources::write_packed_resources_v4(
             &self
                 .resources
                 .values()
@@ -658,6 +730,19 @@ impl<'a> CompiledResourcesCollection<'a> {
         )
     }
 
+    /// Compress in-memory data of all resources with zstd.
+    ///
+    /// See [Resource::to_zstd_compressed] for which data is compressed.
+    pub fn zstd_compress_resources(&mut self, level: i32) -> Result<()> {
+        for (name, resource) in self.resources.iter_mut() {
+            *resource = resource
+                .to_zstd_compressed(level)
+                .with_context(|| format!("compressing {}", name))?;
+        }
+
+        Ok(())
+    }
+
     /// Convert the file installs to a [FileManifest].
     pub fn extra_files_manifest(&self) -> Result<FileManifest> {
         let mut m = FileManifest::default();
@@ -1665,6 +1750,42 @@ impl PythonResourceCollector {
         )])
     }
 
+    /// Add an arbitrary data blob belonging to a named namespace.
+    ///
+    /// Data blobs are always embedded in memory. The path of the blob within
+    /// the namespace is the path of `file`.
+    pub fn add_data_file(
+        &mut self,
+        namespace: &str,
+        file: &File,
+    ) -> Result<Vec<AddResourceAction>> {
+        if namespace.is_empty() || namespace.contains('/') {
+            return Err(anyhow!(
+                "data namespace must be non-empty and cannot contain /: {}",
+                namespace
+            ));
+        }
+
+        let name = format!("{}/{}", namespace, file.path_string());
+
+        let entry = self
+            .resources
+            .entry(name.clone())
+            .or_insert_with(|| PrePackagedResource {
+                name: name.clone(),
+                ..PrePackagedResource::default()
+            });
+
+        entry.data_namespace = Some(namespace.to_string());
+        entry.file_executable = file.entry().is_executable();
+        entry.file_data_embedded = Some(file.entry().file_data().clone());
+
+        Ok(vec![AddResourceAction::Added(
+            format!("data {}", name),
+            ConcreteResourceLocation::InMemory,
+        )])
+    }
+
     pub fn add_file_data_with_context(
         &mut self,
         file: &File,
@@ -1834,6 +1955,70 @@ impl PythonResourceCollector {
         Ok(res)
     }
 
+    /// Resolve the names of Python modules reachable by imports from a set of modules.
+    ///
+    /// Starting with `roots` and `main_modules`, the source code of each reached
+    /// module is scanned for imports using [find_imported_modules()] and imported
+    /// modules present in this collection are reached in turn. Importing a module
+    /// also imports its parent packages, so these are always reached.
+    ///
+    /// `main_modules` are modules executed as `__main__`. Code only run in this
+    /// case is considered for these modules only.
+    ///
+    /// Modules without source code, such as extension modules, are reached but
+    /// not scanned. Since the scan can't see dynamic imports, the result may be
+    /// missing modules that are imported at run-time.
+    pub fn find_imported_module_closure(
+        &self,
+        roots: &[&str],
+        main_modules: &[&str],
+    ) -> Result<BTreeSet<String>> {
+        let mut reached = BTreeSet::new();
+        let mut pending = roots
+            .iter()
+            .map(|x| (x.to_string(), false))
+            .chain(main_modules.iter().map(|x| (x.to_string(), true)))
+            .collect::<Vec<_>>();
+        let mut scanned_main = BTreeSet::new();
+
+        while let Some((name, is_main)) = pending.pop() {
+            let resource = match self.resources.get(&name) {
+                Some(resource) if resource.is_python_resource() => resource,
+                _ => continue,
+            };
+
+            // A module is scanned again as `__main__` if it was reached by import first.
+            if is_main {
+                if !scanned_main.insert(name.clone()) {
+                    continue;
+                }
+                reached.insert(name.clone());
+            } else if !reached.insert(name.clone()) {
+                continue;
+            }
+
+            pending.extend(
+                packages_from_module_name(&name)
+                    .into_iter()
+                    .map(|x| (x, false)),
+            );
+
+            if let Some(source) = resource.module_source() {
+                let source = source
+                    .resolve_content()
+                    .with_context(|| format!("resolving source code of {}", name))?;
+
+                pending.extend(
+                    find_imported_modules(&source, &name, resource.is_package, is_main)
+                        .into_iter()
+                        .map(|x| (x, false)),
+                );
+            }
+        }
+
+        Ok(reached)
+    }
+
     /// Compiles resources into a finalized collection.
     ///
     /// This will take all resources collected so far and convert them into
@@ -2754,7 +2939,7 @@ mod tests {
                 is_module: true,
                 name: "root.parent".to_string(),
                 is_package: true,
-                in_memory_source: Some(FileData::Memory(vec![])),
+                is_namespace_package: true,
                 ..PrePackagedResource::default()
             })
         );
@@ -2764,7 +2949,7 @@ mod tests {
                 is_module: true,
                 name: "root".to_string(),
                 is_package: true,
-                in_memory_source: Some(FileData::Memory(vec![])),
+                is_namespace_package: true,
                 ..PrePackagedResource::default()
             })
         );
@@ -2798,7 +2983,7 @@ mod tests {
                 is_module: true,
                 name: "root.parent".to_string(),
                 is_package: true,
-                relative_path_module_source: Some(("prefix".to_string(), FileData::Memory(vec![]))),
+                is_namespace_package: true,
                 ..PrePackagedResource::default()
             })
         );
@@ -2808,7 +2993,7 @@ mod tests {
                 is_module: true,
                 name: "root".to_string(),
                 is_package: true,
-                relative_path_module_source: Some(("prefix".to_string(), FileData::Memory(vec![]))),
+                is_namespace_package: true,
                 ..PrePackagedResource::default()
             })
         );
@@ -2841,9 +3026,7 @@ mod tests {
                 is_module: true,
                 name: "root.parent".to_string(),
                 is_package: true,
-                in_memory_bytecode: Some(PythonModuleBytecodeProvider::FromSource(
-                    FileData::Memory(vec![])
-                )),
+                is_namespace_package: true,
                 ..PrePackagedResource::default()
             })
         );
@@ -2853,9 +3036,7 @@ mod tests {
                 is_module: true,
                 name: "root".to_string(),
                 is_package: true,
-                in_memory_bytecode: Some(PythonModuleBytecodeProvider::FromSource(
-                    FileData::Memory(vec![])
-                )),
+                is_namespace_package: true,
                 ..PrePackagedResource::default()
             })
         );
@@ -2887,6 +3068,7 @@ mod tests {
                 is_module: true,
                 name: "foo".to_string(),
                 is_package: true,
+                is_namespace_package: true,
                 ..PrePackagedResource::default()
             })
         );
@@ -2920,6 +3102,62 @@ mod tests {
                 is_module: true,
                 name: "foo".to_string(),
                 is_package: true,
+                is_namespace_package: true,
+                ..PrePackagedResource::default()
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_populate_parent_packages_regular_parent() -> Result<()> {
+        let mut h = BTreeMap::new();
+        h.insert(
+            "root".to_string(),
+            PrePackagedResource {
+                is_module: true,
+                name: "root".to_string(),
+                is_package: true,
+                in_memory_source: Some(FileData::Memory(vec![1])),
+                ..PrePackagedResource::default()
+            },
+        );
+        h.insert(
+            "root.parent.child".to_string(),
+            PrePackagedResource {
+                is_module: true,
+                name: "root.parent.child".to_string(),
+                in_memory_bytecode: Some(PythonModuleBytecodeProvider::FromSource(
+                    FileData::Memory(vec![42]),
+                )),
+                ..PrePackagedResource::default()
+            },
+        );
+
+        populate_parent_packages(&mut h)?;
+
+        assert_eq!(h.len(), 3);
+        assert_eq!(
+            h.get("root"),
+            Some(&PrePackagedResource {
+                is_module: true,
+                name: "root".to_string(),
+                is_package: true,
+                in_memory_source: Some(FileData::Memory(vec![1])),
+                in_memory_bytecode: Some(PythonModuleBytecodeProvider::FromSource(
+                    FileData::Memory(vec![1])
+                )),
+                ..PrePackagedResource::default()
+            })
+        );
+        assert_eq!(
+            h.get("root.parent"),
+            Some(&PrePackagedResource {
+                is_module: true,
+                name: "root.parent".to_string(),
+                is_package: true,
+                is_namespace_package: true,
                 ..PrePackagedResource::default()
             })
         );
@@ -3021,7 +3259,7 @@ mod tests {
                 is_python_module: true,
                 name: Cow::Owned("root".to_string()),
                 is_python_package: true,
-                in_memory_source: Some(Cow::Owned(vec![])),
+                is_python_namespace_package: true,
                 ..Resource::default()
             })
         );
@@ -3031,7 +3269,7 @@ mod tests {
                 is_python_module: true,
                 name: Cow::Owned("root.parent".to_string()),
                 is_python_package: true,
-                in_memory_source: Some(Cow::Owned(vec![])),
+                is_python_namespace_package: true,
                 ..Resource::default()
             })
         );
@@ -3051,6 +3289,43 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_zstd_compress_resources() -> Result<()> {
+        let mut r = PythonResourceCollector::new(
+            vec![AbstractResourceLocation::InMemory],
+            vec![],
+            false,
+            false,
+        );
+        r.add_python_module_source(
+            &PythonModuleSource {
+                name: "foo".to_string(),
+                source: FileData::Memory(b"import io".to_vec()),
+                is_package: false,
+                cache_tag: DEFAULT_CACHE_TAG.to_string(),
+                is_stdlib: false,
+                is_test: false,
+            },
+            &ConcreteResourceLocation::InMemory,
+        )?;
+
+        let mut compiler = FakeBytecodeCompiler { magic_number: 42 };
+
+        let mut resources = r.compile_resources(&mut compiler)?;
+        resources.zstd_compress_resources(3)?;
+
+        let resource = resources.resources.get("foo").unwrap();
+        assert!(resource.is_zstd_compressed);
+        assert_eq!(
+            resource
+                .resolve_in_memory_data(resource.in_memory_source.as_ref().unwrap())?
+                .as_ref(),
+            b"import io"
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_add_relative_path_source_module() -> Result<()> {
         let mut r = PythonResourceCollector::new(
@@ -3097,9 +3372,7 @@ mod tests {
                 is_python_module: true,
                 name: Cow::Owned("foo".to_string()),
                 is_python_package: true,
-                relative_path_module_source: Some(Cow::Owned(PathBuf::from(
-                    "prefix/foo/__init__.py"
-                ))),
+                is_python_namespace_package: true,
                 ..Resource::default()
             })
         );
@@ -3114,18 +3387,11 @@ mod tests {
         );
         assert_eq!(
             resources.extra_files,
-            vec![
-                (
-                    PathBuf::from("prefix/foo/__init__.py"),
-                    FileData::Memory(vec![]),
-                    false
-                ),
-                (
-                    PathBuf::from("prefix/foo/bar.py"),
-                    FileData::Memory(vec![42]),
-                    false
-                )
-            ]
+            vec![(
+                PathBuf::from("prefix/foo/bar.py"),
+                FileData::Memory(vec![42]),
+                false
+            )]
         );
 
         Ok(())
@@ -3522,7 +3788,7 @@ mod tests {
                 is_python_module: true,
                 name: Cow::Owned("root".to_string()),
                 is_python_package: true,
-                in_memory_bytecode_opt1: Some(Cow::Owned(b"bc1".to_vec())),
+                is_python_namespace_package: true,
                 ..Resource::default()
             })
         );
@@ -3532,7 +3798,7 @@ mod tests {
                 is_python_module: true,
                 name: Cow::Owned("root.parent".to_string()),
                 is_python_package: true,
-                in_memory_bytecode_opt1: Some(Cow::Owned(b"bc1".to_vec())),
+                is_python_namespace_package: true,
                 ..Resource::default()
             })
         );
@@ -4354,6 +4620,7 @@ mod tests {
                 is_python_module: true,
                 name: Cow::Owned("foo".to_string()),
                 is_python_package: true,
+                is_python_namespace_package: true,
                 ..Resource::default()
             })
         );
@@ -4514,6 +4781,52 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_add_data_file() -> Result<()> {
+        let mut r = PythonResourceCollector::new(
+            vec![AbstractResourceLocation::InMemory],
+            vec![],
+            false,
+            false,
+        );
+
+        assert!(r
+            .add_data_file("", &File::new("model.bin", vec![42]))
+            .is_err());
+        assert!(r
+            .add_data_file("models/en", &File::new("model.bin", vec![42]))
+            .is_err());
+
+        r.add_data_file("models", &File::new("en/model.bin", vec![42]))?;
+
+        assert_eq!(
+            r.resources.get("models/en/model.bin"),
+            Some(&PrePackagedResource {
+                name: "models/en/model.bin".to_string(),
+                file_data_embedded: Some(FileData::Memory(vec![42])),
+                data_namespace: Some("models".to_string()),
+                ..PrePackagedResource::default()
+            })
+        );
+
+        let mut compiler = FakeBytecodeCompiler { magic_number: 42 };
+
+        let resources = r.compile_resources(&mut compiler)?;
+
+        assert_eq!(
+            resources.resources.get("models/en/model.bin"),
+            Some(&Resource {
+                name: Cow::Owned("models/en/model.bin".to_string()),
+                file_data_embedded: Some(Cow::Owned(vec![42])),
+                data_namespace: Some(Cow::Owned("models".to_string())),
+                ..Resource::default()
+            })
+        );
+        assert!(resources.extra_files.is_empty());
+
+        Ok(())
+    }
+
     #[test]
     fn test_add_relative_path_file_data() -> Result<()> {
         let mut r = PythonResourceCollector::new(
@@ -4684,4 +4997,61 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_find_imported_module_closure() -> Result<()> {
+        let mut r = PythonResourceCollector::new(
+            vec![AbstractResourceLocation::InMemory],
+            vec![],
+            false,
+            false,
+        );
+
+        for (name, is_package, source) in [
+            ("app", false, "import pkg.a\n"),
+            ("pkg", true, "from . import b\n"),
+            (
+                "pkg.a",
+                false,
+                "import os\n\nif __name__ == '__main__':\n    import unused\n",
+            ),
+            ("pkg.b", false, ""),
+            ("pkg.c", false, "import unused\n"),
+            ("os", false, "import sys\n"),
+            ("unused", false, ""),
+        ] {
+            r.add_python_module_source(
+                &PythonModuleSource {
+                    name: name.to_string(),
+                    source: FileData::Memory(Vec::from(source)),
+                    is_package,
+                    cache_tag: DEFAULT_CACHE_TAG.to_string(),
+                    is_stdlib: false,
+                    is_test: false,
+                },
+                &ConcreteResourceLocation::InMemory,
+            )?;
+        }
+
+        assert_eq!(
+            r.find_imported_module_closure(&["app"], &[])?
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!["app", "os", "pkg", "pkg.a", "pkg.b"]
+        );
+        assert_eq!(
+            r.find_imported_module_closure(&["pkg.c", "missing"], &[])?
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!["pkg", "pkg.b", "pkg.c", "unused"]
+        );
+        assert_eq!(
+            r.find_imported_module_closure(&["app"], &["pkg.a"])?
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!["app", "os", "pkg", "pkg.a", "pkg.b", "unused"]
+        );
+
+        Ok(())
+    }
 }
@@ -0,0 +1,112 @@
+/* Copyright 2022 Gregory Szorc.
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+/* C API for reading Python packed resources data.
+ *
+ * Functions are provided by the shared and static libraries built from the
+ * python-packed-resources-capi crate. e.g.
+ *
+ *   cargo build --release -p python-packed-resources-capi
+ *
+ * Data passed to packed_resources_open() is borrowed, not copied. It must
+ * remain valid and unmodified until packed_resources_free() is called.
+ * Slices returned by accessor functions point into that data.
+ */
+
+#ifndef PYTHON_PACKED_RESOURCES_H
+#define PYTHON_PACKED_RESOURCES_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define PACKED_RESOURCES_FLAG_PYTHON_MODULE (1u << 0)
+#define PACKED_RESOURCES_FLAG_PYTHON_BUILTIN_EXTENSION_MODULE (1u << 1)
+#define PACKED_RESOURCES_FLAG_PYTHON_FROZEN_MODULE (1u << 2)
+#define PACKED_RESOURCES_FLAG_PYTHON_EXTENSION_MODULE (1u << 3)
+#define PACKED_RESOURCES_FLAG_SHARED_LIBRARY (1u << 4)
+#define PACKED_RESOURCES_FLAG_UTF8_FILENAME_DATA (1u << 5)
+#define PACKED_RESOURCES_FLAG_PYTHON_PACKAGE (1u << 6)
+#define PACKED_RESOURCES_FLAG_PYTHON_NAMESPACE_PACKAGE (1u << 7)
+#define PACKED_RESOURCES_FLAG_FILE_EXECUTABLE (1u << 8)
+#define PACKED_RESOURCES_FLAG_ZSTD_COMPRESSED (1u << 9)
+
+/* Field types accepted by packed_resources_field(). */
+#define PACKED_RESOURCES_FIELD_IN_MEMORY_SOURCE 0x06
+#define PACKED_RESOURCES_FIELD_IN_MEMORY_BYTECODE 0x07
+#define PACKED_RESOURCES_FIELD_IN_MEMORY_BYTECODE_OPT1 0x08
+#define PACKED_RESOURCES_FIELD_IN_MEMORY_BYTECODE_OPT2 0x09
+#define PACKED_RESOURCES_FIELD_IN_MEMORY_EXTENSION_MODULE_SHARED_LIBRARY 0x0a
+#define PACKED_RESOURCES_FIELD_IN_MEMORY_SHARED_LIBRARY 0x0d
+#define PACKED_RESOURCES_FIELD_FILE_DATA_EMBEDDED 0x1d
+#define PACKED_RESOURCES_FIELD_FILE_DATA_UTF8_RELATIVE_PATH 0x1e
+#define PACKED_RESOURCES_FIELD_DATA_NAMESPACE 0x20
+
+/* Field types accepted by packed_resources_map_len() and
+ * packed_resources_map_entry(). */
+#define PACKED_RESOURCES_FIELD_IN_MEMORY_PACKAGE_RESOURCES 0x0b
+#define PACKED_RESOURCES_FIELD_IN_MEMORY_DISTRIBUTION_RESOURCES 0x0c
+
+typedef struct PackedResources PackedResources;
+
+typedef struct PackedResourcesSlice {
+    const uint8_t *data;
+    size_t length;
+} PackedResourcesSlice;
+
+/* Obtain the message of the last error on the current thread, or NULL. */
+const char *packed_resources_last_error(void);
+
+/* Parse packed resources data. Returns NULL on error. */
+PackedResources *packed_resources_open(const uint8_t *data, size_t length);
+
+/* Free an instance returned by packed_resources_open(). */
+void packed_resources_free(PackedResources *resources);
+
+/* Obtain the number of resources. */
+size_t packed_resources_len(const PackedResources *resources);
+
+/* Find a resource by name. Returns 1 and writes to index if found, 0 if not. */
+int packed_resources_find(const PackedResources *resources,
+                          const uint8_t *name, size_t name_length,
+                          size_t *index);
+
+/* Obtain the UTF-8 name of a resource. Returns 1 on success, -1 if index is
+ * out of bounds. */
+int packed_resources_name(const PackedResources *resources, size_t index,
+                          PackedResourcesSlice *out);
+
+/* Obtain the PACKED_RESOURCES_FLAG_* flags of a resource. */
+uint32_t packed_resources_flags(const PackedResources *resources,
+                                size_t index);
+
+/* Obtain the data of a field of a resource. Returns 1 if present, 0 if
+ * absent, -1 if index is out of bounds or field isn't supported. */
+int packed_resources_field(const PackedResources *resources, size_t index,
+                           uint8_t field, PackedResourcesSlice *out);
+
+/* Obtain the number of entries in a mapping field of a resource. */
+size_t packed_resources_map_len(const PackedResources *resources,
+                                size_t index, uint8_t field);
+
+/* Obtain an entry, sorted by key, in a mapping field of a resource. Returns
+ * 1 on success, -1 if out of bounds or field isn't supported. */
+int packed_resources_map_entry(const PackedResources *resources,
+                               size_t index, uint8_t field, size_t entry,
+                               PackedResourcesSlice *key,
+                               PackedResourcesSlice *value);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* PYTHON_PACKED_RESOURCES_H */
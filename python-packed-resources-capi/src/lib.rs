@@ -0,0 +1,634 @@
+// Copyright 2022 Gregory Szorc.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*! C API for reading Python packed resources data.
+
+This crate exposes functions with C linkage so applications not written in
+Rust can parse data produced by the `python-packed-resources` crate. The
+declarations are mirrored in `include/python_packed_resources.h`.
+
+Data passed to [packed_resources_open] is borrowed, not copied. It must
+remain valid and unmodified until [packed_resources_free] is called.
+Slices returned by accessor functions point into that data.
+*/
+
+use {
+    python_packed_resources::{
+        load_indexed_resources, load_resources, IndexedResources, Resource, ResourceField,
+    },
+    std::{
+        borrow::Cow,
+        cell::RefCell,
+        ffi::CString,
+        os::raw::{c_char, c_int},
+    },
+};
+
+/// Resource defines a Python module or package.
+pub const PACKED_RESOURCES_FLAG_PYTHON_MODULE: u32 = 1 << 0;
+/// Resource defines a builtin extension module.
+pub const PACKED_RESOURCES_FLAG_PYTHON_BUILTIN_EXTENSION_MODULE: u32 = 1 << 1;
+/// Resource defines a frozen Python module.
+pub const PACKED_RESOURCES_FLAG_PYTHON_FROZEN_MODULE: u32 = 1 << 2;
+/// Resource defines a Python extension module.
+pub const PACKED_RESOURCES_FLAG_PYTHON_EXTENSION_MODULE: u32 = 1 << 3;
+/// Resource defines a shared library.
+pub const PACKED_RESOURCES_FLAG_SHARED_LIBRARY: u32 = 1 << 4;
+/// Resource defines data for an arbitrary file.
+pub const PACKED_RESOURCES_FLAG_UTF8_FILENAME_DATA: u32 = 1 << 5;
+/// Python module is a package.
+pub const PACKED_RESOURCES_FLAG_PYTHON_PACKAGE: u32 = 1 << 6;
+/// Python module is a namespace package.
+pub const PACKED_RESOURCES_FLAG_PYTHON_NAMESPACE_PACKAGE: u32 = 1 << 7;
+/// File data should be executable.
+pub const PACKED_RESOURCES_FLAG_FILE_EXECUTABLE: u32 = 1 << 8;
+/// In-memory data is zstd compressed.
+pub const PACKED_RESOURCES_FLAG_ZSTD_COMPRESSED: u32 = 1 << 9;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: &str) {
+    LAST_ERROR.with(|error| {
+        error.replace(CString::new(message).ok());
+    });
+}
+
+/// A borrowed sequence of bytes.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct PackedResourcesSlice {
+    pub data: *const u8,
+    pub length: usize,
+}
+
+impl PackedResourcesSlice {
+    fn new(data: &[u8]) -> Self {
+        Self {
+            data: data.as_ptr(),
+            length: data.len(),
+        }
+    }
+}
+
+/// A parsed resource whose mappings have a deterministic order.
+struct IndexedResource {
+    resource: Resource<'static, u8>,
+    package_resources: Vec<(&'static [u8], &'static [u8])>,
+    distribution_resources: Vec<(&'static [u8], &'static [u8])>,
+}
+
+/// Obtain the entries of a resources map sorted by key.
+///
+/// Entries must borrow from the parsed data so slices handed to C remain
+/// valid. Owned entries are rejected.
+fn sorted_entries(
+    map: &Option<std::collections::HashMap<Cow<'static, str>, Cow<'static, [u8]>>>,
+) -> Result<Vec<(&'static [u8], &'static [u8])>, &'static str> {
+    let mut entries = map
+        .iter()
+        .flatten()
+        .map(|(key, value)| match (key, value) {
+            (&Cow::Borrowed(key), &Cow::Borrowed(value)) => Ok((key.as_bytes(), value)),
+            _ => Err("resources map entry does not reference packed resources data"),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    entries.sort_by_key(|(key, _)| *key);
+
+    Ok(entries)
+}
+
+/// Parsed packed resources data.
+///
+/// This is an opaque type to C.
+pub struct PackedResources {
+    resources: Vec<IndexedResource>,
+    /// Hash table of version 4 data, used to find resources by name.
+    indexed: Option<IndexedResources<'static>>,
+}
+
+impl PackedResources {
+    fn get(&self, index: usize) -> Option<&IndexedResource> {
+        self.resources.get(index)
+    }
+}
+
+/// Obtain the message of the last error on the current thread.
+///
+/// Returns NULL if no error occurred. The returned string is valid until the
+/// next call into this API on the current thread.
+#[no_mangle]
+pub extern "C" fn packed_resources_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| match error.borrow().as_ref() {
+        Some(message) => message.as_ptr(),
+        None => std::ptr::null(),
+    })
+}
+
+/// Parse packed resources data.
+///
+/// Returns NULL on error. Use [packed_resources_last_error] to obtain the
+/// reason.
+///
+/// # Safety
+///
+/// `data` must point to `length` readable bytes that remain valid and
+/// unmodified until the returned instance is freed.
+#[no_mangle]
+pub unsafe extern "C" fn packed_resources_open(
+    data: *const u8,
+    length: usize,
+) -> *mut PackedResources {
+    if data.is_null() {
+        set_last_error("data is NULL");
+        return std::ptr::null_mut();
+    }
+
+    // The caller guarantees the data outlives the returned instance.
+    let data: &'static [u8] = std::slice::from_raw_parts(data, length);
+
+    let resources = match load_resources(data)
+        .and_then(|iter| iter.collect::<Result<Vec<_>, &'static str>>())
+    {
+        Ok(resources) => resources,
+        Err(e) => {
+            set_last_error(e);
+            return std::ptr::null_mut();
+        }
+    };
+
+    let resources = match resources
+        .into_iter()
+        .map(|resource| {
+            Ok(IndexedResource {
+                package_resources: sorted_entries(&resource.in_memory_package_resources)?,
+                distribution_resources: sorted_entries(&resource.in_memory_distribution_resources)?,
+                resource,
+            })
+        })
+        .collect::<Result<Vec<_>, &'static str>>()
+    {
+        Ok(resources) => resources,
+        Err(e) => {
+            set_last_error(e);
+            return std::ptr::null_mut();
+        }
+    };
+
+    Box::into_raw(Box::new(PackedResources {
+        resources,
+        indexed: load_indexed_resources(data).ok(),
+    }))
+}
+
+/// Free an instance returned by [packed_resources_open].
+///
+/// # Safety
+///
+/// `resources` must be NULL or a pointer returned by [packed_resources_open]
+/// that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn packed_resources_free(resources: *mut PackedResources) {
+    if !resources.is_null() {
+        drop(Box::from_raw(resources));
+    }
+}
+
+/// Obtain the number of resources.
+///
+/// # Safety
+///
+/// `resources` must be a valid pointer returned by [packed_resources_open].
+#[no_mangle]
+pub unsafe extern "C" fn packed_resources_len(resources: *const PackedResources) -> usize {
+    (*resources).resources.len()
+}
+
+/// Find the index of a resource by name.
+///
+/// Returns 1 and writes to `index` if found. Returns 0 otherwise. Version 4
+/// data is searched via its hash table. Version 3 data is scanned.
+///
+/// # Safety
+///
+/// `resources` must be a valid pointer returned by [packed_resources_open].
+/// `name` must point to `name_length` readable bytes. `index` must be
+/// writable.
+#[no_mangle]
+pub unsafe extern "C" fn packed_resources_find(
+    resources: *const PackedResources,
+    name: *const u8,
+    name_length: usize,
+    index: *mut usize,
+) -> c_int {
+    let name = std::slice::from_raw_parts(name, name_length);
+    let resources = &*resources;
+
+    let position = match (&resources.indexed, std::str::from_utf8(name)) {
+        // Indices in the hash table match the order resources were parsed in.
+        (Some(indexed), Ok(name)) => indexed.candidate_indices(name).find(|candidate| {
+            matches!(resources.get(*candidate), Some(entry) if entry.resource.name == name)
+        }),
+        // Resource names are UTF-8.
+        (Some(_), Err(_)) => None,
+        (None, _) => resources
+            .resources
+            .iter()
+            .position(|entry| entry.resource.name.as_bytes() == name),
+    };
+
+    match position {
+        Some(position) => {
+            *index = position;
+            1
+        }
+        None => 0,
+    }
+}
+
+/// Obtain the UTF-8 name of a resource.
+///
+/// Returns 1 on success. Returns -1 if `index` is out of bounds.
+///
+/// # Safety
+///
+/// `resources` must be a valid pointer returned by [packed_resources_open].
+/// `out` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn packed_resources_name(
+    resources: *const PackedResources,
+    index: usize,
+    out: *mut PackedResourcesSlice,
+) -> c_int {
+    match (*resources).get(index) {
+        Some(entry) => {
+            *out = PackedResourcesSlice::new(entry.resource.name.as_bytes());
+            1
+        }
+        None => -1,
+    }
+}
+
+/// Obtain the `PACKED_RESOURCES_FLAG_*` flags of a resource.
+///
+/// Returns 0 if `index` is out of bounds.
+///
+/// # Safety
+///
+/// `resources` must be a valid pointer returned by [packed_resources_open].
+#[no_mangle]
+pub unsafe extern "C" fn packed_resources_flags(
+    resources: *const PackedResources,
+    index: usize,
+) -> u32 {
+    let resource = match (*resources).get(index) {
+        Some(entry) => &entry.resource,
+        None => return 0,
+    };
+
+    [
+        (
+            resource.is_python_module,
+            PACKED_RESOURCES_FLAG_PYTHON_MODULE,
+        ),
+        (
+            resource.is_python_builtin_extension_module,
+            PACKED_RESOURCES_FLAG_PYTHON_BUILTIN_EXTENSION_MODULE,
+        ),
+        (
+            resource.is_python_frozen_module,
+            PACKED_RESOURCES_FLAG_PYTHON_FROZEN_MODULE,
+        ),
+        (
+            resource.is_python_extension_module,
+            PACKED_RESOURCES_FLAG_PYTHON_EXTENSION_MODULE,
+        ),
+        (
+            resource.is_shared_library,
+            PACKED_RESOURCES_FLAG_SHARED_LIBRARY,
+        ),
+        (
+            resource.is_utf8_filename_data,
+            PACKED_RESOURCES_FLAG_UTF8_FILENAME_DATA,
+        ),
+        (
+            resource.is_python_package,
+            PACKED_RESOURCES_FLAG_PYTHON_PACKAGE,
+        ),
+        (
+            resource.is_python_namespace_package,
+            PACKED_RESOURCES_FLAG_PYTHON_NAMESPACE_PACKAGE,
+        ),
+        (
+            resource.file_executable,
+            PACKED_RESOURCES_FLAG_FILE_EXECUTABLE,
+        ),
+        (
+            resource.is_zstd_compressed,
+            PACKED_RESOURCES_FLAG_ZSTD_COMPRESSED,
+        ),
+    ]
+    .into_iter()
+    .filter(|(set, _)| *set)
+    .fold(0, |flags, (_, flag)| flags | flag)
+}
+
+/// Obtain the data of a field of a resource.
+///
+/// `field` is a resource field type from the packed resources specification.
+/// Supported fields are in-memory source (`0x06`), bytecode (`0x07` to
+/// `0x09`), extension module shared library (`0x0a`), shared library (`0x0d`),
+/// embedded file data (`0x1d`), UTF-8 relative path file data (`0x1e`), and
+/// data namespace (`0x20`).
+///
+/// Returns 1 and writes to `out` if the resource has the field. Returns 0 if
+/// it doesn't. Returns -1 if `index` is out of bounds or `field` isn't
+/// supported.
+///
+/// # Safety
+///
+/// `resources` must be a valid pointer returned by [packed_resources_open].
+/// `out` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn packed_resources_field(
+    resources: *const PackedResources,
+    index: usize,
+    field: u8,
+    out: *mut PackedResourcesSlice,
+) -> c_int {
+    let resource = match (*resources).get(index) {
+        Some(entry) => &entry.resource,
+        None => return -1,
+    };
+
+    let data = match ResourceField::try_from(field) {
+        Ok(ResourceField::InMemorySource) => resource.in_memory_source.as_deref(),
+        Ok(ResourceField::InMemoryBytecode) => resource.in_memory_bytecode.as_deref(),
+        Ok(ResourceField::InMemoryBytecodeOpt1) => resource.in_memory_bytecode_opt1.as_deref(),
+        Ok(ResourceField::InMemoryBytecodeOpt2) => resource.in_memory_bytecode_opt2.as_deref(),
+        Ok(ResourceField::InMemoryExtensionModuleSharedLibrary) => resource
+            .in_memory_extension_module_shared_library
+            .as_deref(),
+        Ok(ResourceField::InMemorySharedLibrary) => resource.in_memory_shared_library.as_deref(),
+        Ok(ResourceField::FileDataEmbedded) => resource.file_data_embedded.as_deref(),
+        Ok(ResourceField::FileDataUtf8RelativePath) => resource
+            .file_data_utf8_relative_path
+            .as_deref()
+            .map(|value| value.as_bytes()),
+        Ok(ResourceField::DataNamespace) => resource
+            .data_namespace
+            .as_deref()
+            .map(|value| value.as_bytes()),
+        _ => {
+            set_last_error("unsupported resource field");
+            return -1;
+        }
+    };
+
+    match data {
+        Some(data) => {
+            *out = PackedResourcesSlice::new(data);
+            1
+        }
+        None => 0,
+    }
+}
+
+fn resource_map(entry: &IndexedResource, field: u8) -> Option<&[(&'static [u8], &'static [u8])]> {
+    match ResourceField::try_from(field) {
+        Ok(ResourceField::InMemoryResourcesData) => Some(&entry.package_resources),
+        Ok(ResourceField::InMemoryDistributionResource) => Some(&entry.distribution_resources),
+        _ => None,
+    }
+}
+
+/// Obtain the number of entries in a mapping field of a resource.
+///
+/// `field` is in-memory package resources (`0x0b`) or in-memory distribution
+/// resources (`0x0c`).
+///
+/// Returns 0 if the resource doesn't have the field, `index` is out of bounds,
+/// or `field` isn't supported.
+///
+/// # Safety
+///
+/// `resources` must be a valid pointer returned by [packed_resources_open].
+#[no_mangle]
+pub unsafe extern "C" fn packed_resources_map_len(
+    resources: *const PackedResources,
+    index: usize,
+    field: u8,
+) -> usize {
+    (*resources)
+        .get(index)
+        .and_then(|entry| resource_map(entry, field))
+        .map(|entries| entries.len())
+        .unwrap_or(0)
+}
+
+/// Obtain an entry in a mapping field of a resource.
+///
+/// Entries are sorted by key. Returns 1 and writes to `key` and `value` on
+/// success. Returns -1 if `index` or `entry` is out of bounds or `field`
+/// isn't supported.
+///
+/// # Safety
+///
+/// `resources` must be a valid pointer returned by [packed_resources_open].
+/// `key` and `value` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn packed_resources_map_entry(
+    resources: *const PackedResources,
+    index: usize,
+    field: u8,
+    entry: usize,
+    key: *mut PackedResourcesSlice,
+    value: *mut PackedResourcesSlice,
+) -> c_int {
+    match (*resources)
+        .get(index)
+        .and_then(|resource| resource_map(resource, field))
+        .and_then(|entries| entries.get(entry))
+    {
+        Some((k, v)) => {
+            *key = PackedResourcesSlice::new(k);
+            *value = PackedResourcesSlice::new(v);
+            1
+        }
+        None => -1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        python_packed_resources::{write_packed_resources_v3, write_packed_resources_v4},
+        std::{collections::HashMap, ffi::CStr},
+    };
+
+    unsafe fn slice<'a>(value: PackedResourcesSlice) -> &'a [u8] {
+        std::slice::from_raw_parts(value.data, value.length)
+    }
+
+    #[test]
+    fn test_open_invalid() {
+        let data = b"foo";
+
+        unsafe {
+            assert!(packed_resources_open(data.as_ptr(), data.len()).is_null());
+            assert_eq!(
+                CStr::from_ptr(packed_resources_last_error()).to_str(),
+                Ok("error reading 8 byte header")
+            );
+        }
+    }
+
+    #[test]
+    fn test_sorted_entries_owned() {
+        let mut map = HashMap::new();
+        map.insert(Cow::Borrowed("b.txt"), Cow::Borrowed(&b"b"[..]));
+        map.insert(Cow::Borrowed("a.txt"), Cow::Borrowed(&b"a"[..]));
+        assert_eq!(
+            sorted_entries(&Some(map.clone())),
+            Ok(vec![(&b"a.txt"[..], &b"a"[..]), (&b"b.txt"[..], &b"b"[..])])
+        );
+
+        map.insert(Cow::Owned("c.txt".to_string()), Cow::Borrowed(&b"c"[..]));
+        assert!(sorted_entries(&Some(map.clone())).is_err());
+
+        map.remove("c.txt");
+        map.insert(Cow::Borrowed("d.txt"), Cow::Owned(b"d".to_vec()));
+        assert!(sorted_entries(&Some(map)).is_err());
+
+        assert_eq!(sorted_entries(&None), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_read() {
+        let mut package_resources = HashMap::new();
+        package_resources.insert(Cow::from("b.txt"), Cow::from(b"b".to_vec()));
+        package_resources.insert(Cow::from("a.txt"), Cow::from(b"a".to_vec()));
+
+        let resources = vec![
+            Resource {
+                name: Cow::from("foo"),
+                is_python_module: true,
+                is_python_package: true,
+                in_memory_source: Some(Cow::from(b"import io".to_vec())),
+                in_memory_package_resources: Some(package_resources),
+                ..Resource::default()
+            },
+            Resource {
+                name: Cow::from("bar"),
+                is_python_module: true,
+                ..Resource::default()
+            },
+        ];
+
+        let mut data = Vec::new();
+        write_packed_resources_v3(&resources, &mut data, None).unwrap();
+
+        unsafe {
+            let resources = packed_resources_open(data.as_ptr(), data.len());
+            assert!(!resources.is_null());
+            assert_eq!(packed_resources_len(resources), 2);
+
+            let mut index = 0;
+            assert_eq!(
+                packed_resources_find(resources, b"baz".as_ptr(), 3, &mut index),
+                0
+            );
+            assert_eq!(
+                packed_resources_find(resources, b"foo".as_ptr(), 3, &mut index),
+                1
+            );
+
+            let mut out = PackedResourcesSlice::new(&[]);
+            assert_eq!(packed_resources_name(resources, index, &mut out), 1);
+            assert_eq!(slice(out), b"foo");
+            assert_eq!(packed_resources_name(resources, 2, &mut out), -1);
+
+            assert_eq!(
+                packed_resources_flags(resources, index),
+                PACKED_RESOURCES_FLAG_PYTHON_MODULE | PACKED_RESOURCES_FLAG_PYTHON_PACKAGE
+            );
+
+            assert_eq!(packed_resources_field(resources, index, 0x06, &mut out), 1);
+            assert_eq!(slice(out), b"import io");
+            assert_eq!(packed_resources_field(resources, index, 0x07, &mut out), 0);
+            assert_eq!(packed_resources_field(resources, index, 0x04, &mut out), -1);
+
+            assert_eq!(packed_resources_map_len(resources, index, 0x0b), 2);
+            assert_eq!(packed_resources_map_len(resources, index, 0x0c), 0);
+
+            let mut key = PackedResourcesSlice::new(&[]);
+            assert_eq!(
+                packed_resources_map_entry(resources, index, 0x0b, 0, &mut key, &mut out),
+                1
+            );
+            assert_eq!(slice(key), b"a.txt");
+            assert_eq!(slice(out), b"a");
+            assert_eq!(
+                packed_resources_map_entry(resources, index, 0x0b, 2, &mut key, &mut out),
+                -1
+            );
+
+            packed_resources_free(resources);
+        }
+    }
+
+    #[test]
+    fn test_find_v4() {
+        let names = (0..50).map(|i| format!("module{}", i)).collect::<Vec<_>>();
+        let resources = names
+            .iter()
+            .map(|name| Resource {
+                name: Cow::from(name.as_str()),
+                is_python_module: true,
+                ..Resource::default()
+            })
+            .collect::<Vec<_>>();
+
+        let mut data = Vec::new();
+        write_packed_resources_v4(&resources, &mut data, None).unwrap();
+
+        unsafe {
+            let resources = packed_resources_open(data.as_ptr(), data.len());
+            assert!(!resources.is_null());
+            assert!((*resources).indexed.is_some());
+            assert_eq!(packed_resources_len(resources), 50);
+
+            let mut index = 0;
+            let mut out = PackedResourcesSlice::new(&[]);
+
+            for (i, name) in names.iter().enumerate() {
+                assert_eq!(
+                    packed_resources_find(resources, name.as_ptr(), name.len(), &mut index),
+                    1
+                );
+                assert_eq!(index, i);
+                assert_eq!(packed_resources_name(resources, index, &mut out), 1);
+                assert_eq!(slice(out), name.as_bytes());
+            }
+
+            assert_eq!(
+                packed_resources_find(resources, b"missing".as_ptr(), 7, &mut index),
+                0
+            );
+            assert_eq!(
+                packed_resources_find(resources, b"\xff".as_ptr(), 1, &mut index),
+                0
+            );
+
+            packed_resources_free(resources);
+        }
+    }
+}
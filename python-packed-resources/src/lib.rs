@@ -35,8 +35,8 @@ mod serialization;
 mod writer;
 
 pub use crate::{
-    parser::{load_resources, ResourceParserIterator},
+    parser::{load_indexed_resources, load_resources, IndexedResources, ResourceParserIterator},
     resource::Resource,
-    serialization::HEADER_V3,
-    writer::write_packed_resources_v3,
+    serialization::{ResourceField, HEADER_V3, HEADER_V4},
+    writer::{write_packed_resources_v3, write_packed_resources_v4},
 };
@@ -11,7 +11,10 @@
 use {
     crate::{
         resource::Resource,
-        serialization::{BlobInteriorPadding, BlobSectionField, ResourceField, HEADER_V3},
+        serialization::{
+            resource_name_hash, BlobInteriorPadding, BlobSectionField, ResourceField, HEADER_V3,
+            HEADER_V4, V4_HEADER_LENGTH, V4_TABLE_RECORD_LENGTH,
+        },
     },
     byteorder::{LittleEndian, ReadBytesExt},
     std::{borrow::Cow, collections::HashMap, io::Cursor, path::Path},
@@ -48,6 +51,8 @@ pub struct ResourceParserIterator<'a> {
     blob_sections: [Option<BlobSectionReadState>; 256],
     claimed_resources_count: usize,
     read_resources_count: usize,
+    /// Version 4 data, whose entries are parsed individually.
+    indexed: Option<IndexedResources<'a>>,
 }
 
 impl<'a> ResourceParserIterator<'a> {
@@ -61,12 +66,20 @@ impl<'a> ResourceParserIterator<'a> {
     /// This accepts a reference to the original blobs payload, an array of
     /// current blob section offsets, the resource field being accessed, and the
     /// length of the blob and returns a slice to that blob.
-    fn resolve_blob_data(&mut self, resource_field: ResourceField, length: usize) -> &'a [u8] {
+    fn resolve_blob_data(
+        &mut self,
+        resource_field: ResourceField,
+        length: usize,
+    ) -> Result<&'a [u8], &'static str> {
         let mut state = self.blob_sections[resource_field as usize]
             .as_mut()
-            .expect("blob state not found");
+            .ok_or("blob state not found")?;
 
-        let blob = &self.data[state.offset..state.offset + length];
+        let blob = state
+            .offset
+            .checked_add(length)
+            .and_then(|end| self.data.get(state.offset..end))
+            .ok_or("blob data extends beyond end of data")?;
 
         let increment = match &state.interior_padding {
             BlobInteriorPadding::None => length,
@@ -75,25 +88,33 @@ impl<'a> ResourceParserIterator<'a> {
 
         state.offset += increment;
 
-        blob
+        Ok(blob)
     }
 
     #[cfg(unix)]
-    fn resolve_path(&mut self, resource_field: ResourceField, length: usize) -> Cow<'a, Path> {
-        let path_str = OsStr::from_bytes(self.resolve_blob_data(resource_field, length));
-        Cow::Borrowed(Path::new(path_str))
+    fn resolve_path(
+        &mut self,
+        resource_field: ResourceField,
+        length: usize,
+    ) -> Result<Cow<'a, Path>, &'static str> {
+        let path_str = OsStr::from_bytes(self.resolve_blob_data(resource_field, length)?);
+        Ok(Cow::Borrowed(Path::new(path_str)))
     }
 
     #[cfg(windows)]
-    fn resolve_path(&mut self, resource_field: ResourceField, length: usize) -> Cow<'a, Path> {
-        let raw = self.resolve_blob_data(resource_field, length);
+    fn resolve_path(
+        &mut self,
+        resource_field: ResourceField,
+        length: usize,
+    ) -> Result<Cow<'a, Path>, &'static str> {
+        let raw = self.resolve_blob_data(resource_field, length)?;
         let raw = unsafe { std::slice::from_raw_parts(raw.as_ptr() as *const u16, raw.len() / 2) };
 
         // There isn't an API that lets us get a OsStr from &[u16]. So we need to use
         // owned types.
         let path_string = OsString::from_wide(raw);
 
-        Cow::Owned(PathBuf::from(path_string))
+        Ok(Cow::Owned(PathBuf::from(path_string)))
     }
 
     fn parse_next(&mut self) -> Result<Option<Resource<'a, u8>>, &'static str> {
@@ -140,7 +161,7 @@ impl<'a> ResourceParserIterator<'a> {
                         as usize;
 
                     let name = unsafe {
-                        std::str::from_utf8_unchecked(self.resolve_blob_data(field_type, l))
+                        std::str::from_utf8_unchecked(self.resolve_blob_data(field_type, l)?)
                     };
 
                     current_resource_name = Some(name);
@@ -160,7 +181,7 @@ impl<'a> ResourceParserIterator<'a> {
                         as usize;
 
                     current_resource.in_memory_source =
-                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)));
+                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)?));
                 }
                 ResourceField::InMemoryBytecode => {
                     let l = self
@@ -170,7 +191,7 @@ impl<'a> ResourceParserIterator<'a> {
                         as usize;
 
                     current_resource.in_memory_bytecode =
-                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)));
+                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)?));
                 }
                 ResourceField::InMemoryBytecodeOpt1 => {
                     let l = self
@@ -180,7 +201,7 @@ impl<'a> ResourceParserIterator<'a> {
                         as usize;
 
                     current_resource.in_memory_bytecode_opt1 =
-                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)));
+                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)?));
                 }
                 ResourceField::InMemoryBytecodeOpt2 => {
                     let l = self
@@ -190,7 +211,7 @@ impl<'a> ResourceParserIterator<'a> {
                         as usize;
 
                     current_resource.in_memory_bytecode_opt2 =
-                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)));
+                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)?));
                 }
                 ResourceField::InMemoryExtensionModuleSharedLibrary => {
                     let l = self
@@ -200,7 +221,7 @@ impl<'a> ResourceParserIterator<'a> {
                         as usize;
 
                     current_resource.in_memory_extension_module_shared_library =
-                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)));
+                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)?));
                 }
 
                 ResourceField::InMemoryResourcesData => {
@@ -221,7 +242,7 @@ impl<'a> ResourceParserIterator<'a> {
 
                         let resource_name = unsafe {
                             std::str::from_utf8_unchecked(
-                                self.resolve_blob_data(field_type, resource_name_length),
+                                self.resolve_blob_data(field_type, resource_name_length)?,
                             )
                         };
 
@@ -231,7 +252,7 @@ impl<'a> ResourceParserIterator<'a> {
                             .map_err(|_| "failed reading resource length")?
                             as usize;
 
-                        let resource_data = self.resolve_blob_data(field_type, resource_length);
+                        let resource_data = self.resolve_blob_data(field_type, resource_length)?;
 
                         resources
                             .insert(Cow::Borrowed(resource_name), Cow::Borrowed(resource_data));
@@ -258,7 +279,7 @@ impl<'a> ResourceParserIterator<'a> {
 
                         let name = unsafe {
                             std::str::from_utf8_unchecked(
-                                self.resolve_blob_data(field_type, name_length),
+                                self.resolve_blob_data(field_type, name_length)?,
                             )
                         };
 
@@ -267,7 +288,7 @@ impl<'a> ResourceParserIterator<'a> {
                                 "failed reading package distribution resource length"
                             })? as usize;
 
-                        let resource_data = self.resolve_blob_data(field_type, resource_length);
+                        let resource_data = self.resolve_blob_data(field_type, resource_length)?;
 
                         resources.insert(Cow::Borrowed(name), Cow::Borrowed(resource_data));
                     }
@@ -283,7 +304,7 @@ impl<'a> ResourceParserIterator<'a> {
                         as usize;
 
                     current_resource.in_memory_shared_library =
-                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)));
+                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)?));
                 }
 
                 ResourceField::SharedLibraryDependencyNames => {
@@ -303,7 +324,7 @@ impl<'a> ResourceParserIterator<'a> {
 
                         let name = unsafe {
                             std::str::from_utf8_unchecked(
-                                self.resolve_blob_data(field_type, name_length),
+                                self.resolve_blob_data(field_type, name_length)?,
                             )
                         };
 
@@ -320,7 +341,7 @@ impl<'a> ResourceParserIterator<'a> {
                         .map_err(|_| "failed reading Python module relative path length")?
                         as usize;
 
-                    let path = self.resolve_path(field_type, path_length);
+                    let path = self.resolve_path(field_type, path_length)?;
 
                     current_resource.relative_path_module_source = Some(path);
                 }
@@ -331,7 +352,7 @@ impl<'a> ResourceParserIterator<'a> {
                             "failed reading Python module bytecode relative path length"
                         })? as usize;
 
-                    let path = self.resolve_path(field_type, path_length);
+                    let path = self.resolve_path(field_type, path_length)?;
 
                     current_resource.relative_path_module_bytecode = Some(path);
                 }
@@ -341,7 +362,7 @@ impl<'a> ResourceParserIterator<'a> {
                         "failed reading Python module bytecode opt 1 relative path length"
                     })? as usize;
 
-                    let path = self.resolve_path(field_type, path_length);
+                    let path = self.resolve_path(field_type, path_length)?;
 
                     current_resource.relative_path_module_bytecode_opt1 = Some(path);
                 }
@@ -351,7 +372,7 @@ impl<'a> ResourceParserIterator<'a> {
                         "failed reading Python module bytecode opt 2 relative path length"
                     })? as usize;
 
-                    let path = self.resolve_path(field_type, path_length);
+                    let path = self.resolve_path(field_type, path_length)?;
 
                     current_resource.relative_path_module_bytecode_opt2 = Some(path);
                 }
@@ -361,7 +382,7 @@ impl<'a> ResourceParserIterator<'a> {
                         "failed reading Python extension module shared library relative path length"
                     })? as usize;
 
-                    let path = self.resolve_path(field_type, path_length);
+                    let path = self.resolve_path(field_type, path_length)?;
 
                     current_resource.relative_path_extension_module_shared_library = Some(path);
                 }
@@ -383,7 +404,7 @@ impl<'a> ResourceParserIterator<'a> {
 
                         let resource_name = unsafe {
                             std::str::from_utf8_unchecked(
-                                self.resolve_blob_data(field_type, resource_name_length),
+                                self.resolve_blob_data(field_type, resource_name_length)?,
                             )
                         };
 
@@ -393,7 +414,7 @@ impl<'a> ResourceParserIterator<'a> {
                             .map_err(|_| "failed reading resource path length")?
                             as usize;
 
-                        let path = self.resolve_path(field_type, path_length);
+                        let path = self.resolve_path(field_type, path_length)?;
 
                         resources.insert(Cow::Borrowed(resource_name), path);
                     }
@@ -417,7 +438,7 @@ impl<'a> ResourceParserIterator<'a> {
 
                         let name = unsafe {
                             std::str::from_utf8_unchecked(
-                                self.resolve_blob_data(field_type, name_length),
+                                self.resolve_blob_data(field_type, name_length)?,
                             )
                         };
 
@@ -427,7 +448,7 @@ impl<'a> ResourceParserIterator<'a> {
                             .map_err(|_| "failed reading package distribution path length")?
                             as usize;
 
-                        let path = self.resolve_path(field_type, path_length);
+                        let path = self.resolve_path(field_type, path_length)?;
 
                         resources.insert(Cow::Borrowed(name), path);
                     }
@@ -462,6 +483,9 @@ impl<'a> ResourceParserIterator<'a> {
                 ResourceField::FileExecutable => {
                     current_resource.file_executable = true;
                 }
+                ResourceField::IsZstdCompressed => {
+                    current_resource.is_zstd_compressed = true;
+                }
 
                 ResourceField::FileDataEmbedded => {
                     let l = self
@@ -471,7 +495,7 @@ impl<'a> ResourceParserIterator<'a> {
                         as usize;
 
                     current_resource.file_data_embedded =
-                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)));
+                        Some(Cow::Borrowed(self.resolve_blob_data(field_type, l)?));
                 }
 
                 ResourceField::FileDataUtf8RelativePath => {
@@ -482,7 +506,19 @@ impl<'a> ResourceParserIterator<'a> {
                         as usize;
 
                     current_resource.file_data_utf8_relative_path = Some(Cow::Borrowed(unsafe {
-                        std::str::from_utf8_unchecked(self.resolve_blob_data(field_type, l))
+                        std::str::from_utf8_unchecked(self.resolve_blob_data(field_type, l)?)
+                    }));
+                }
+
+                ResourceField::DataNamespace => {
+                    let l = self
+                        .reader
+                        .read_u16::<LittleEndian>()
+                        .map_err(|_| "failed reading data namespace length")?
+                        as usize;
+
+                    current_resource.data_namespace = Some(Cow::Borrowed(unsafe {
+                        std::str::from_utf8_unchecked(self.resolve_blob_data(field_type, l)?)
                     }));
                 }
             }
@@ -498,6 +534,18 @@ impl<'a> Iterator for ResourceParserIterator<'a> {
             return None;
         }
 
+        if let Some(indexed) = &self.indexed {
+            if self.read_resources_count == self.claimed_resources_count {
+                self.done = true;
+                return None;
+            }
+
+            let res = indexed.get(self.read_resources_count);
+            self.read_resources_count += 1;
+
+            return Some(res);
+        }
+
         match self.parse_next() {
             Ok(res) => res.map(Ok),
             Err(e) => Some(Err(e)),
@@ -522,6 +570,18 @@ pub fn load_resources<'a>(data: &'a [u8]) -> Result<ResourceParserIterator<'a>,
 
     if header == HEADER_V3 {
         load_resources_v3(&data[8..])
+    } else if header == HEADER_V4 {
+        let indexed = load_indexed_resources(data)?;
+
+        Ok(ResourceParserIterator {
+            done: false,
+            data,
+            reader: Cursor::new(&[]),
+            blob_sections: [None; 256],
+            claimed_resources_count: indexed.len(),
+            read_resources_count: 0,
+            indexed: Some(indexed),
+        })
     } else {
         Err("unrecognized file format")
     }
@@ -637,7 +697,13 @@ fn load_resources_v3<'a>(data: &'a [u8]) -> Result<ResourceParserIterator<'a>, &
                 None => BlobInteriorPadding::None,
             },
         });
-        current_blob_offset += section.raw_payload_length;
+        current_blob_offset = current_blob_offset
+            .checked_add(section.raw_payload_length)
+            .ok_or("blob data extends beyond end of data")?;
+    }
+
+    if blob_start_offset.saturating_add(current_blob_offset) > data.len() {
+        return Err("blob data extends beyond end of data");
     }
 
     Ok(ResourceParserIterator {
@@ -647,6 +713,161 @@ fn load_resources_v3<'a>(data: &'a [u8]) -> Result<ResourceParserIterator<'a>, &
         blob_sections: blob_offsets,
         claimed_resources_count: resources_count,
         read_resources_count: 0,
+        indexed: None,
+    })
+}
+
+fn read_u32_le(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64_le(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+/// Random access to resources in version 4 packed resources data.
+///
+/// Construction only validates the header and the bounds of the lookup
+/// tables. Individual resources are parsed on access. This makes it suitable
+/// for memory mapped data, as only the pages holding accessed resources need
+/// to be read.
+#[derive(Clone, Copy, Debug)]
+pub struct IndexedResources<'a> {
+    data: &'a [u8],
+    count: usize,
+    hash_table: &'a [u8],
+    entry_table: &'a [u8],
+}
+
+impl<'a> IndexedResources<'a> {
+    /// The number of resources.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Whether there are no resources.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Parse the resource at a given index.
+    ///
+    /// Indices correspond to the order resources were written in.
+    pub fn get(&self, index: usize) -> Result<Resource<'a, u8>, &'static str> {
+        if index >= self.count {
+            return Err("resource index out of bounds");
+        }
+
+        let record = index * V4_TABLE_RECORD_LENGTH;
+        let offset = read_u64_le(self.entry_table, record) as usize;
+        let length = read_u64_le(self.entry_table, record + 8) as usize;
+
+        let entry = offset
+            .checked_add(length)
+            .and_then(|end| self.data.get(offset..end))
+            .ok_or("resource entry extends beyond end of data")?;
+
+        let mut resources = load_resources_v3(entry)?;
+
+        let resource = resources
+            .next()
+            .ok_or("resource entry does not contain a resource")??;
+
+        if resources.next().is_some() {
+            return Err("resource entry contains multiple resources");
+        }
+
+        Ok(resource)
+    }
+
+    /// Obtain the name hash and resource index of a hash table record.
+    fn hash_record(&self, record: usize) -> (u64, usize) {
+        let offset = record * V4_TABLE_RECORD_LENGTH;
+
+        (
+            read_u64_le(self.hash_table, offset),
+            read_u32_le(self.hash_table, offset + 8) as usize,
+        )
+    }
+
+    /// Iterate over `(name hash, index)` pairs in hash table order.
+    ///
+    /// Pairs are sorted by name hash. Nothing is parsed, so this can be used to
+    /// cheaply find resources that may be present in multiple instances.
+    pub fn hash_records(&self) -> impl Iterator<Item = (u64, usize)> + '_ {
+        (0..self.count).map(|record| self.hash_record(record))
+    }
+
+    /// Obtain indices of resources whose name hash matches a name.
+    ///
+    /// This performs a binary search over the hash table. Resources at the
+    /// returned indices must be parsed to confirm their name matches.
+    pub fn candidate_indices(&self, name: &str) -> impl Iterator<Item = usize> + '_ {
+        let hash = resource_name_hash(name);
+
+        // Lower bound of the records having this hash.
+        let (mut low, mut high) = (0, self.count);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.hash_record(mid).0 < hash {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+
+        (low..self.count)
+            .map(|record| self.hash_record(record))
+            .take_while(move |(record_hash, _)| *record_hash == hash)
+            .map(|(_, index)| index)
+    }
+
+    /// Find and parse a resource by name.
+    ///
+    /// Only entries whose name hash matches are parsed.
+    pub fn find(&self, name: &str) -> Result<Option<Resource<'a, u8>>, &'static str> {
+        for index in self.candidate_indices(name) {
+            let resource = self.get(index)?;
+
+            if resource.name == name {
+                return Ok(Some(resource));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Load version 4 packed resources data for random access.
+pub fn load_indexed_resources<'a>(data: &'a [u8]) -> Result<IndexedResources<'a>, &'static str> {
+    if data.len() < V4_HEADER_LENGTH {
+        return Err("error reading 16 byte header");
+    }
+
+    if &data[0..8] != HEADER_V4 {
+        return Err("unrecognized file format");
+    }
+
+    let count = read_u32_le(data, 8) as usize;
+
+    let tables_end = count
+        .checked_mul(2 * V4_TABLE_RECORD_LENGTH)
+        .and_then(|l| l.checked_add(V4_HEADER_LENGTH))
+        .ok_or("resources count too large")?;
+
+    if data.len() < tables_end {
+        return Err("lookup tables extend beyond end of data");
+    }
+
+    let table_length = count * V4_TABLE_RECORD_LENGTH;
+    let hash_table = &data[V4_HEADER_LENGTH..V4_HEADER_LENGTH + table_length];
+    let entry_table = &data[V4_HEADER_LENGTH + table_length..tables_end];
+
+    Ok(IndexedResources {
+        data,
+        count,
+        hash_table,
+        entry_table,
     })
 }
 
@@ -655,8 +876,9 @@ mod tests {
     use {
         super::*,
         crate::{
-            resource::Resource, serialization::BlobInteriorPadding,
-            writer::write_packed_resources_v3,
+            resource::Resource,
+            serialization::BlobInteriorPadding,
+            writer::{write_packed_resources_v3, write_packed_resources_v4},
         },
     };
 
@@ -674,7 +896,7 @@ mod tests {
         let res = load_resources(data);
         assert_eq!(res.err(), Some("unrecognized file format"));
 
-        let data = b"pyembed\x04";
+        let data = b"pyembed\x05";
         let res = load_resources(data);
         assert_eq!(res.err(), Some("unrecognized file format"));
     }
@@ -750,6 +972,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_truncated_blob_data() {
+        let resource = Resource {
+            name: Cow::from("foo"),
+            ..Resource::default()
+        };
+
+        let mut data = Vec::new();
+        write_packed_resources_v3(&[resource], &mut data, None).unwrap();
+        data.pop();
+
+        assert_eq!(
+            load_resources(&data).err(),
+            Some("blob data extends beyond end of data")
+        );
+    }
+
     #[test]
     fn test_multiple_resources_just_names() {
         let resource1 = Resource {
@@ -1405,6 +1644,8 @@ mod tests {
             file_executable: true,
             file_data_embedded: Some(Cow::from(b"file_data_embedded".to_vec())),
             file_data_utf8_relative_path: Some(Cow::from("file_data_utf8_relative_path")),
+            is_zstd_compressed: true,
+            data_namespace: Some(Cow::from("data_namespace")),
         };
 
         let mut data = Vec::new();
@@ -1519,6 +1760,134 @@ mod tests {
             entry.file_data_utf8_relative_path.as_ref().unwrap(),
             "file_data_utf8_relative_path"
         );
+        assert!(entry.is_zstd_compressed);
+        assert_eq!(entry.data_namespace.as_ref().unwrap(), "data_namespace");
+    }
+
+    #[test]
+    fn test_data_blob() {
+        let resource = Resource {
+            name: Cow::from("models/en/model.bin"),
+            file_data_embedded: Some(Cow::from(b"weights".to_vec())),
+            data_namespace: Some(Cow::from("models")),
+            ..Resource::default()
+        };
+
+        let mut data = Vec::new();
+        write_packed_resources_v3(&[resource], &mut data, None).unwrap();
+        let resources = load_resources(&data)
+            .unwrap()
+            .collect::<Result<Vec<Resource<u8>>, &'static str>>()
+            .unwrap();
+
+        assert_eq!(resources.len(), 1);
+
+        let entry = &resources[0];
+        assert_eq!(entry.name, "models/en/model.bin");
+        assert_eq!(entry.data_namespace.as_ref().unwrap(), "models");
+        assert_eq!(entry.data_path(), Some("en/model.bin"));
+        assert_eq!(
+            entry.file_data_embedded.as_ref().unwrap().as_ref(),
+            b"weights"
+        );
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd_compressed() {
+        let mut package_resources = HashMap::new();
+        package_resources.insert(Cow::from("data.txt"), Cow::from(b"resource data".to_vec()));
+
+        let resource = Resource {
+            name: Cow::from("foo"),
+            is_python_module: true,
+            in_memory_source: Some(Cow::from(b"import io".to_vec())),
+            in_memory_bytecode: Some(Cow::from(b"fake bytecode".to_vec())),
+            in_memory_package_resources: Some(package_resources),
+            in_memory_shared_library: Some(Cow::from(b"library".to_vec())),
+            ..Resource::default()
+        }
+        .to_zstd_compressed(3)
+        .unwrap();
+
+        assert!(resource.is_zstd_compressed);
+        assert_ne!(
+            resource.in_memory_source.as_ref().unwrap().as_ref(),
+            b"import io"
+        );
+        assert_eq!(
+            resource.in_memory_shared_library.as_ref().unwrap().as_ref(),
+            b"library"
+        );
+
+        let mut data = Vec::new();
+        write_packed_resources_v3(&[resource], &mut data, None).unwrap();
+        let resources = load_resources(&data)
+            .unwrap()
+            .collect::<Result<Vec<Resource<u8>>, &'static str>>()
+            .unwrap();
+
+        assert_eq!(resources.len(), 1);
+
+        let entry = &resources[0];
+        assert!(entry.is_zstd_compressed);
+        assert_eq!(
+            entry
+                .resolve_in_memory_data(entry.in_memory_source.as_ref().unwrap())
+                .unwrap()
+                .as_ref(),
+            b"import io"
+        );
+        assert_eq!(
+            entry
+                .resolve_in_memory_data(entry.in_memory_bytecode.as_ref().unwrap())
+                .unwrap()
+                .as_ref(),
+            b"fake bytecode"
+        );
+        assert_eq!(
+            entry
+                .resolve_in_memory_data(
+                    entry
+                        .in_memory_package_resources
+                        .as_ref()
+                        .unwrap()
+                        .get("data.txt")
+                        .unwrap()
+                )
+                .unwrap()
+                .as_ref(),
+            b"resource data"
+        );
+        assert_eq!(
+            entry.in_memory_shared_library.as_ref().unwrap().as_ref(),
+            b"library"
+        );
+
+        let decompressed = entry.to_zstd_decompressed().unwrap();
+        assert!(!decompressed.is_zstd_compressed);
+        assert_eq!(
+            decompressed.in_memory_source.as_ref().unwrap().as_ref(),
+            b"import io"
+        );
+        assert_eq!(
+            decompressed
+                .in_memory_package_resources
+                .as_ref()
+                .unwrap()
+                .get("data.txt")
+                .unwrap()
+                .as_ref(),
+            b"resource data"
+        );
+        assert_eq!(
+            decompressed
+                .in_memory_shared_library
+                .as_ref()
+                .unwrap()
+                .as_ref(),
+            b"library"
+        );
     }
 
     #[test]
@@ -1547,4 +1916,68 @@ mod tests {
 
         assert_eq!(resources, loaded);
     }
+
+    #[test]
+    fn test_v4_empty() {
+        let resources: Vec<Resource<u8>> = vec![];
+        let mut data = Vec::new();
+        write_packed_resources_v4(&resources, &mut data, None).unwrap();
+
+        let indexed = load_indexed_resources(&data).unwrap();
+        assert!(indexed.is_empty());
+        assert_eq!(indexed.find("foo").unwrap(), None);
+        assert_eq!(load_resources(&data).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_v4_truncated_tables() {
+        let mut data = b"pyembed\x04".to_vec();
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+
+        assert_eq!(
+            load_indexed_resources(&data).unwrap_err(),
+            "lookup tables extend beyond end of data"
+        );
+    }
+
+    #[test]
+    fn test_v4_find() {
+        let names = (0..100).map(|i| format!("module{}", i)).collect::<Vec<_>>();
+        let resources = names
+            .iter()
+            .map(|name| Resource {
+                name: Cow::from(name.as_str()),
+                is_python_module: true,
+                in_memory_source: Some(Cow::from(name.as_bytes())),
+                ..Resource::default()
+            })
+            .collect::<Vec<_>>();
+
+        let mut data = Vec::new();
+        write_packed_resources_v4(&resources, &mut data, Some(BlobInteriorPadding::Null)).unwrap();
+
+        let indexed = load_indexed_resources(&data).unwrap();
+        assert_eq!(indexed.len(), 100);
+
+        for (i, name) in names.iter().enumerate() {
+            let resource = indexed.find(name).unwrap().unwrap();
+            assert_eq!(&resource, &resources[i]);
+            assert_eq!(&indexed.get(i).unwrap(), &resources[i]);
+            assert!(indexed.candidate_indices(name).any(|index| index == i));
+        }
+
+        let records = indexed.hash_records().collect::<Vec<_>>();
+        assert_eq!(records.len(), 100);
+        assert!(records.windows(2).all(|w| w[0].0 <= w[1].0));
+
+        assert_eq!(indexed.find("missing").unwrap(), None);
+        assert_eq!(indexed.get(100), Err("resource index out of bounds"));
+
+        let parsed = load_resources(&data)
+            .unwrap()
+            .collect::<Result<Vec<_>, &'static str>>()
+            .unwrap();
+        assert_eq!(parsed, resources);
+    }
 }
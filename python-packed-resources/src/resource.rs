@@ -115,6 +115,20 @@ where
 
     /// Holds arbitrary file data in a relative path encoded in UTF-8.
     pub file_data_utf8_relative_path: Option<Cow<'a, str>>,
+
+    /// Whether in-memory data is zstd compressed.
+    ///
+    /// If set, in-memory source, bytecode, package resources, distribution
+    /// resources, and embedded file data are each an individual zstd frame.
+    /// Shared library data is never compressed.
+    pub is_zstd_compressed: bool,
+
+    /// Namespace of an arbitrary application data blob.
+    ///
+    /// If set, this resource is a named data blob belonging to this namespace
+    /// and `name` is `<namespace>/<path>`. The data should exist in
+    /// `file_data_embedded`.
+    pub data_namespace: Option<Cow<'a, str>>,
 }
 
 impl<'a, X> Default for Resource<'a, X>
@@ -151,6 +165,8 @@ where
             file_executable: false,
             file_data_embedded: None,
             file_data_utf8_relative_path: None,
+            is_zstd_compressed: false,
+            data_namespace: None,
         }
     }
 }
@@ -243,6 +259,10 @@ where
         if let Some(value) = other.file_data_utf8_relative_path {
             self.file_data_utf8_relative_path.replace(value);
         }
+        self.is_zstd_compressed |= other.is_zstd_compressed;
+        if let Some(value) = other.data_namespace {
+            self.data_namespace.replace(value);
+        }
 
         Ok(())
     }
@@ -303,7 +323,7 @@ where
                 },
             ),
             in_memory_shared_library: self
-                .in_memory_source
+                .in_memory_shared_library
                 .as_ref()
                 .map(|value| Cow::Owned(value.clone().into_owned())),
             shared_library_dependency_names: self.shared_library_dependency_names.as_ref().map(
@@ -370,6 +390,151 @@ where
                 .file_data_utf8_relative_path
                 .as_ref()
                 .map(|value| Cow::Owned(value.clone().into_owned())),
+            is_zstd_compressed: self.is_zstd_compressed,
+            data_namespace: self
+                .data_namespace
+                .as_ref()
+                .map(|value| Cow::Owned(value.clone().into_owned())),
+        }
+    }
+
+    /// Resolve the path of a data blob within its namespace.
+    ///
+    /// Returns `None` if this resource isn't a data blob.
+    pub fn data_path(&self) -> Option<&str> {
+        let namespace = self.data_namespace.as_ref()?;
+
+        self.name
+            .strip_prefix(namespace.as_ref())
+            .and_then(|path| path.strip_prefix('/'))
+    }
+}
+
+impl<'a> Resource<'a, u8> {
+    /// Obtain a copy of this resource with in-memory data compressed with zstd.
+    ///
+    /// Each piece of data is compressed independently so it can be
+    /// decompressed on demand without touching other data. Shared library
+    /// data is left as-is because it may be loaded directly from memory.
+    ///
+    /// Resources that are already compressed are copied verbatim.
+    ///
+    /// Requires the `zstd` feature.
+    #[cfg(feature = "zstd")]
+    pub fn to_zstd_compressed(&self, level: i32) -> std::io::Result<Resource<'static, u8>> {
+        let mut resource = self.to_owned();
+
+        if resource.is_zstd_compressed {
+            return Ok(resource);
+        }
+
+        let compress = |data: &[u8]| -> std::io::Result<Cow<'static, [u8]>> {
+            Ok(Cow::Owned(zstd::bulk::compress(data, level)?))
+        };
+
+        let mut compressed = false;
+
+        for data in [
+            &mut resource.in_memory_source,
+            &mut resource.in_memory_bytecode,
+            &mut resource.in_memory_bytecode_opt1,
+            &mut resource.in_memory_bytecode_opt2,
+            &mut resource.file_data_embedded,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *data = compress(data)?;
+            compressed = true;
+        }
+
+        for resources in [
+            &mut resource.in_memory_package_resources,
+            &mut resource.in_memory_distribution_resources,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            for data in resources.values_mut() {
+                *data = compress(data)?;
+                compressed = true;
+            }
+        }
+
+        resource.is_zstd_compressed = compressed;
+
+        Ok(resource)
+    }
+
+    /// Obtain a copy of this resource with in-memory data decompressed.
+    ///
+    /// This is the inverse of [Resource::to_zstd_compressed]. Resources that
+    /// aren't compressed are copied verbatim.
+    ///
+    /// Requires the `zstd` feature.
+    #[cfg(feature = "zstd")]
+    pub fn to_zstd_decompressed(&self) -> std::io::Result<Resource<'static, u8>> {
+        let mut resource = self.to_owned();
+
+        if !resource.is_zstd_compressed {
+            return Ok(resource);
+        }
+
+        let decompress = |data: &[u8]| -> std::io::Result<Cow<'static, [u8]>> {
+            Ok(Cow::Owned(zstd::stream::decode_all(data)?))
+        };
+
+        for data in [
+            &mut resource.in_memory_source,
+            &mut resource.in_memory_bytecode,
+            &mut resource.in_memory_bytecode_opt1,
+            &mut resource.in_memory_bytecode_opt2,
+            &mut resource.file_data_embedded,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *data = decompress(data)?;
+        }
+
+        for resources in [
+            &mut resource.in_memory_package_resources,
+            &mut resource.in_memory_distribution_resources,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            for data in resources.values_mut() {
+                *data = decompress(data)?;
+            }
+        }
+
+        resource.is_zstd_compressed = false;
+
+        Ok(resource)
+    }
+
+    /// Resolve in-memory data belonging to this resource.
+    ///
+    /// If the resource is zstd compressed, the data is decompressed. Otherwise
+    /// it is returned as-is. Decompressing requires the `zstd` feature. An
+    /// error is returned for compressed resources if it isn't enabled.
+    pub fn resolve_in_memory_data<'d>(&self, data: &'d [u8]) -> std::io::Result<Cow<'d, [u8]>> {
+        if !self.is_zstd_compressed {
+            return Ok(Cow::Borrowed(data));
+        }
+
+        #[cfg(feature = "zstd")]
+        {
+            Ok(Cow::Owned(zstd::stream::decode_all(data)?))
+        }
+
+        #[cfg(not(feature = "zstd"))]
+        {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "zstd compressed resources require the zstd feature",
+            ))
         }
     }
 }
@@ -11,6 +11,29 @@
 /// Header value for version 2 of resources payload.
 pub const HEADER_V3: &[u8] = b"pyembed\x03";
 
+/// Header value for version 4 of resources payload.
+pub const HEADER_V4: &[u8] = b"pyembed\x04";
+
+/// Alignment in bytes of tables and entries in version 4 payloads.
+pub const V4_ALIGNMENT: usize = 8;
+
+/// Length in bytes of the version 4 header, including the magic.
+pub const V4_HEADER_LENGTH: usize = 16;
+
+/// Length in bytes of a record in the version 4 hash and entry tables.
+pub const V4_TABLE_RECORD_LENGTH: usize = 16;
+
+/// Compute the hash of a resource name used by the version 4 hash table.
+///
+/// This is 64-bit FNV-1a over the UTF-8 bytes of the name.
+pub fn resource_name_hash(name: &str) -> u64 {
+    name.as_bytes()
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
 /// Defines interior padding mechanism between entries in blob sections.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum BlobInteriorPadding {
@@ -109,6 +132,8 @@ pub enum ResourceField {
     FileExecutable = 0x1c,
     FileDataEmbedded = 0x1d,
     FileDataUtf8RelativePath = 0x1e,
+    IsZstdCompressed = 0x1f,
+    DataNamespace = 0x20,
 }
 
 impl From<ResourceField> for u8 {
@@ -144,6 +169,8 @@ impl From<ResourceField> for u8 {
             ResourceField::FileExecutable => 0x1c,
             ResourceField::FileDataEmbedded => 0x1d,
             ResourceField::FileDataUtf8RelativePath => 0x1e,
+            ResourceField::IsZstdCompressed => 0x1f,
+            ResourceField::DataNamespace => 0x20,
             ResourceField::EndOfEntry => 0xff,
         }
     }
@@ -184,6 +211,8 @@ impl TryFrom<u8> for ResourceField {
             0x1c => Ok(ResourceField::FileExecutable),
             0x1d => Ok(ResourceField::FileDataEmbedded),
             0x1e => Ok(ResourceField::FileDataUtf8RelativePath),
+            0x1f => Ok(ResourceField::IsZstdCompressed),
+            0x20 => Ok(ResourceField::DataNamespace),
             0xff => Ok(ResourceField::EndOfEntry),
             _ => Err("invalid field type"),
         }
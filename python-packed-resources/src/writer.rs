@@ -11,11 +11,18 @@
 use {
     crate::{
         resource::Resource,
-        serialization::{BlobInteriorPadding, BlobSectionField, ResourceField, HEADER_V3},
+        serialization::{
+            resource_name_hash, BlobInteriorPadding, BlobSectionField, ResourceField, HEADER_V3,
+            HEADER_V4, V4_ALIGNMENT, V4_HEADER_LENGTH, V4_TABLE_RECORD_LENGTH,
+        },
     },
     anyhow::{anyhow, Context, Result},
     byteorder::{LittleEndian, WriteBytesExt},
-    std::{collections::BTreeMap, io::Write, path::Path},
+    std::{
+        collections::{BTreeMap, HashSet},
+        io::Write,
+        path::Path,
+    },
 };
 
 #[cfg(unix)]
@@ -136,6 +143,7 @@ where
             || self.relative_path_distribution_resources.is_some()
             || self.file_data_embedded.is_some()
             || self.file_data_utf8_relative_path.is_some()
+            || self.data_namespace.is_some()
     }
 
     /// Compute length of index entry for version 1 payload format.
@@ -264,6 +272,14 @@ where
             index += 5;
         }
 
+        if self.is_zstd_compressed {
+            index += 1;
+        }
+
+        if self.data_namespace.is_some() {
+            index += 3;
+        }
+
         // End of index entry.
         index += 1;
 
@@ -412,6 +428,7 @@ where
             ResourceField::IsSharedLibrary => 0,
             ResourceField::IsUtf8FilenameData => 0,
             ResourceField::FileExecutable => 0,
+            ResourceField::IsZstdCompressed => 0,
             ResourceField::FileDataEmbedded => {
                 if let Some(data) = &self.file_data_embedded {
                     data.len()
@@ -426,6 +443,13 @@ where
                     0
                 }
             }
+            ResourceField::DataNamespace => {
+                if let Some(namespace) = &self.data_namespace {
+                    namespace.as_bytes().len()
+                } else {
+                    0
+                }
+            }
         }
     }
 
@@ -561,6 +585,7 @@ where
             ResourceField::IsSharedLibrary => 0,
             ResourceField::IsUtf8FilenameData => 0,
             ResourceField::FileExecutable => 0,
+            ResourceField::IsZstdCompressed => 0,
             ResourceField::FileDataEmbedded => {
                 if self.file_data_embedded.is_some() {
                     1
@@ -575,6 +600,13 @@ where
                     0
                 }
             }
+            ResourceField::DataNamespace => {
+                if self.data_namespace.is_some() {
+                    1
+                } else {
+                    0
+                }
+            }
         };
 
         let overhead = match padding {
@@ -853,6 +885,20 @@ where
                 .context("writing file_data_utf_relative_path field")?;
         }
 
+        if self.is_zstd_compressed {
+            dest.write_u8(ResourceField::IsZstdCompressed.into())
+                .context("writing is_zstd_compressed field")?;
+        }
+
+        if let Some(namespace) = &self.data_namespace {
+            let l = u16::try_from(namespace.as_bytes().len())
+                .context("converting data namespace length to u16")?;
+            dest.write_u8(ResourceField::DataNamespace.into())
+                .context("writing data_namespace field")?;
+            dest.write_u16::<LittleEndian>(l)
+                .context("writing data_namespace length")?;
+        }
+
         dest.write_u8(ResourceField::EndOfEntry.into())
             .map_err(|_| anyhow!("error writing end of index entry"))?;
 
@@ -997,6 +1043,7 @@ pub fn write_packed_resources_v3<'a, T: AsRef<Resource<'a, u8>>, W: Write>(
             resource,
             ResourceField::FileDataUtf8RelativePath,
         );
+        process_field(&mut blob_sections, resource, ResourceField::DataNamespace);
     }
 
     for section in blob_sections.values() {
@@ -1176,6 +1223,83 @@ pub fn write_packed_resources_v3<'a, T: AsRef<Resource<'a, u8>>, W: Write>(
         }
     }
 
+    for resource in resources {
+        if let Some(namespace) = &resource.as_ref().data_namespace {
+            dest.write_all(namespace.as_bytes())?;
+            add_interior_padding(dest)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Write packed resources data, version 4.
+///
+/// Each resource is serialized to its own version 3 payload (minus the
+/// header) at an aligned offset. Tables at the beginning of the data map
+/// resource name hashes and indices to these entries so readers can look up
+/// individual resources without parsing all of them.
+///
+/// Resource names must be unique.
+pub fn write_packed_resources_v4<'a, T: AsRef<Resource<'a, u8>>, W: Write>(
+    resources: &[T],
+    dest: &mut W,
+    interior_padding: Option<BlobInteriorPadding>,
+) -> Result<()> {
+    let resources_count =
+        u32::try_from(resources.len()).context("converting resources count to u32")?;
+
+    let mut seen_names = HashSet::new();
+    let mut hashes = Vec::with_capacity(resources.len());
+    let mut entries = Vec::with_capacity(resources.len());
+
+    for (index, resource) in resources.iter().enumerate() {
+        let resource = resource.as_ref();
+
+        if !seen_names.insert(resource.name.as_ref()) {
+            return Err(anyhow!("duplicate resource name: {}", resource.name));
+        }
+
+        hashes.push((
+            resource_name_hash(&resource.name),
+            resource.name.as_ref(),
+            index as u32,
+        ));
+
+        let mut entry = Vec::new();
+        write_packed_resources_v3(&[resource], &mut entry, interior_padding)?;
+        entries.push(entry.split_off(HEADER_V3.len()));
+    }
+
+    hashes.sort();
+
+    dest.write_all(HEADER_V4)?;
+    dest.write_u32::<LittleEndian>(resources_count)?;
+    dest.write_u32::<LittleEndian>(0)?;
+
+    for (hash, _, index) in &hashes {
+        dest.write_u64::<LittleEndian>(*hash)?;
+        dest.write_u32::<LittleEndian>(*index)?;
+        dest.write_u32::<LittleEndian>(0)?;
+    }
+
+    let mut offset = V4_HEADER_LENGTH + 2 * V4_TABLE_RECORD_LENGTH * resources.len();
+    let mut paddings = Vec::with_capacity(entries.len());
+
+    for entry in &entries {
+        dest.write_u64::<LittleEndian>(offset as u64)?;
+        dest.write_u64::<LittleEndian>(entry.len() as u64)?;
+
+        let padding = (V4_ALIGNMENT - entry.len() % V4_ALIGNMENT) % V4_ALIGNMENT;
+        paddings.push(padding);
+        offset += entry.len() + padding;
+    }
+
+    for (entry, padding) in entries.iter().zip(paddings) {
+        dest.write_all(entry)?;
+        dest.write_all(&vec![0; padding])?;
+    }
+
     Ok(())
 }
 
@@ -1247,4 +1371,50 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_write_v4_alignment() -> Result<()> {
+        let resources = vec![
+            Resource {
+                name: Cow::Owned("foo".to_string()),
+                ..Resource::default()
+            },
+            Resource {
+                name: Cow::Owned("bar".to_string()),
+                in_memory_source: Some(Cow::Owned(b"import os".to_vec())),
+                ..Resource::default()
+            },
+        ];
+
+        let mut data = Vec::new();
+        write_packed_resources_v4(&resources, &mut data, None)?;
+
+        assert_eq!(&data[0..8], b"pyembed\x04");
+        assert_eq!(data.len() % V4_ALIGNMENT, 0);
+
+        let entries_table = &data[V4_HEADER_LENGTH + 2 * V4_TABLE_RECORD_LENGTH..];
+        for record in entries_table[0..2 * V4_TABLE_RECORD_LENGTH].chunks(V4_TABLE_RECORD_LENGTH) {
+            let offset = u64::from_le_bytes(record[0..8].try_into()?) as usize;
+            assert_eq!(offset % V4_ALIGNMENT, 0);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_v4_duplicate_name() {
+        let resources = vec![
+            Resource {
+                name: Cow::Owned("foo".to_string()),
+                ..Resource::default()
+            },
+            Resource {
+                name: Cow::Owned("foo".to_string()),
+                ..Resource::default()
+            },
+        ];
+
+        let mut data = Vec::new();
+        assert!(write_packed_resources_v4(&resources, &mut data, None).is_err());
+    }
 }
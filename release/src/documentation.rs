@@ -368,6 +368,10 @@ pub fn generate_sphinx_files(repo_root: &Path) -> Result<()> {
                 filename: "python-packaging/src/interpreter.rs".into(),
                 name: "TerminfoResolution".into(),
             },
+            TypeReference {
+                filename: "python-packaging/src/interpreter.rs".into(),
+                name: "RunMode".into(),
+            },
         ];
 
         lines.push("Structs:".to_string());
@@ -45,6 +45,14 @@ def format_record(record):
     return ENTRY.format(**record)
 
 
+def format_optional_record(records, key):
+    """Format a record if the release contains it, returning None otherwise."""
+    if key in records:
+        return format_record(records[key])
+    else:
+        return None
+
+
 def main():
     parser = argparse.ArgumentParser()
     parser.add_argument("--api-token", help="GitHub API token", required=True)
@@ -88,7 +96,7 @@ def main():
             flavor = parts[-1]
 
         supports_prebuilt_extension_modules = (
-            target_triple != "x86_64-unknown-linux-musl" and flavor != "static-noopt"
+            "-linux-musl" not in target_triple and flavor != "static-noopt"
         )
 
         key = "%s-%s-%s" % (major_minor, target_triple, flavor)
@@ -122,6 +130,8 @@ def main():
     )
     print("    let dists = vec![")
 
+    # 3.11 records are optional so the script works against releases
+    # predating 3.11 distributions.
     lines = [
         "// Linux glibc linked.",
         format_record(records["3.8-x86_64-unknown-linux-gnu-pgo"]),
@@ -133,6 +143,10 @@ def main():
         format_record(records["3.10-x86_64-unknown-linux-gnu-pgo"]),
         format_record(records["3.10-x86_64_v2-unknown-linux-gnu-pgo"]),
         format_record(records["3.10-x86_64_v3-unknown-linux-gnu-pgo"]),
+        format_optional_record(records, "3.11-aarch64-unknown-linux-gnu-noopt"),
+        format_optional_record(records, "3.11-x86_64-unknown-linux-gnu-pgo"),
+        format_optional_record(records, "3.11-x86_64_v2-unknown-linux-gnu-pgo"),
+        format_optional_record(records, "3.11-x86_64_v3-unknown-linux-gnu-pgo"),
         "",
         "// Linux musl.",
         format_record(records["3.8-x86_64-unknown-linux-musl-noopt"]),
@@ -142,6 +156,12 @@ def main():
         format_record(records["3.10-x86_64-unknown-linux-musl-noopt"]),
         format_record(records["3.10-x86_64_v2-unknown-linux-musl-noopt"]),
         format_record(records["3.10-x86_64_v3-unknown-linux-musl-noopt"]),
+        format_optional_record(records, "3.11-x86_64-unknown-linux-musl-noopt"),
+        format_optional_record(records, "3.11-x86_64_v2-unknown-linux-musl-noopt"),
+        format_optional_record(records, "3.11-x86_64_v3-unknown-linux-musl-noopt"),
+        # aarch64 musl distributions aren't published by every release.
+        format_optional_record(records, "3.10-aarch64-unknown-linux-musl-noopt"),
+        format_optional_record(records, "3.11-aarch64-unknown-linux-musl-noopt"),
         "",
         "// The order here is important because we will choose the",
         "// first one. We prefer shared distributions on Windows because",
@@ -154,27 +174,35 @@ def main():
         format_record(records["3.8-i686-pc-windows-msvc-shared-pgo"]),
         format_record(records["3.9-i686-pc-windows-msvc-shared-pgo"]),
         format_record(records["3.10-i686-pc-windows-msvc-shared-pgo"]),
+        format_optional_record(records, "3.11-i686-pc-windows-msvc-shared-pgo"),
         format_record(records["3.8-x86_64-pc-windows-msvc-shared-pgo"]),
         format_record(records["3.9-x86_64-pc-windows-msvc-shared-pgo"]),
         format_record(records["3.10-x86_64-pc-windows-msvc-shared-pgo"]),
+        format_optional_record(records, "3.11-x86_64-pc-windows-msvc-shared-pgo"),
         "",
         "// Windows static.",
         format_record(records["3.8-i686-pc-windows-msvc-static-noopt"]),
         format_record(records["3.9-i686-pc-windows-msvc-static-noopt"]),
         format_record(records["3.10-i686-pc-windows-msvc-static-noopt"]),
+        format_optional_record(records, "3.11-i686-pc-windows-msvc-static-noopt"),
         format_record(records["3.8-x86_64-pc-windows-msvc-static-noopt"]),
         format_record(records["3.9-x86_64-pc-windows-msvc-static-noopt"]),
         format_record(records["3.10-x86_64-pc-windows-msvc-static-noopt"]),
+        format_optional_record(records, "3.11-x86_64-pc-windows-msvc-static-noopt"),
         "",
         "// macOS.",
         format_record(records["3.8-aarch64-apple-darwin-pgo"]),
         format_record(records["3.9-aarch64-apple-darwin-pgo"]),
         format_record(records["3.10-aarch64-apple-darwin-pgo"]),
+        format_optional_record(records, "3.11-aarch64-apple-darwin-pgo"),
         format_record(records["3.8-x86_64-apple-darwin-pgo"]),
         format_record(records["3.9-x86_64-apple-darwin-pgo"]),
         format_record(records["3.10-x86_64-apple-darwin-pgo"]),
+        format_optional_record(records, "3.11-x86_64-apple-darwin-pgo"),
     ]
 
+    lines = [line for line in lines if line is not None]
+
     for line in "\n".join(lines).splitlines(False):
         if line.strip():
             print("        %s" % line)
@@ -27,6 +27,7 @@ use {
         collections::{BTreeMap, HashMap},
         os::raw::c_ulong,
         path::{Path, PathBuf},
+        sync::Arc,
     },
 };
 
@@ -105,6 +106,20 @@ pub struct Target {
     pub built_target: Option<ResolvedTarget>,
 }
 
+/// Receives notifications as targets are resolved.
+///
+/// Errors returned by an observer abort resolution of the target.
+pub trait TargetObserver: std::fmt::Debug {
+    /// Called before a target's function is called.
+    ///
+    /// `depends` holds the name and resolved value of each target this
+    /// target depends on.
+    fn before_resolve(&self, target: &str, depends: &[(String, Value)]) -> Result<(), ValueError>;
+
+    /// Called after a target's function returns `value`.
+    fn after_resolve(&self, target: &str, value: &Value) -> Result<(), ValueError>;
+}
+
 /// Holds execution context for a Starlark environment.
 #[derive(Debug)]
 pub struct EnvironmentContext {
@@ -128,6 +143,9 @@ pub struct EnvironmentContext {
     /// Name of the default target.
     default_target: Option<String>,
 
+    /// Names of targets registered as tests, in registration order.
+    test_targets: Vec<String>,
+
     /// List of targets to resolve.
     resolve_targets: Option<Vec<String>>,
 
@@ -139,6 +157,9 @@ pub struct EnvironmentContext {
     ///
     /// This will change the default target to resolve.
     pub build_script_mode: bool,
+
+    /// Observer notified as targets are resolved.
+    target_observer: Option<Arc<dyn TargetObserver>>,
 }
 
 impl EnvironmentContext {
@@ -152,12 +173,24 @@ impl EnvironmentContext {
             targets: BTreeMap::new(),
             targets_order: vec![],
             default_target: None,
+            test_targets: vec![],
             resolve_targets: None,
             default_build_script_target: None,
             build_script_mode: false,
+            target_observer: None,
         }
     }
 
+    /// Obtain the observer notified as targets are resolved.
+    pub fn target_observer(&self) -> Option<Arc<dyn TargetObserver>> {
+        self.target_observer.clone()
+    }
+
+    /// Set the observer notified as targets are resolved.
+    pub fn set_target_observer(&mut self, observer: Option<Arc<dyn TargetObserver>>) {
+        self.target_observer = observer;
+    }
+
     /// Obtain the current working directory for this context.
     pub fn cwd(&self) -> &Path {
         &self.cwd
@@ -276,6 +309,35 @@ impl EnvironmentContext {
         }
     }
 
+    /// Register a named target that runs tests.
+    ///
+    /// Test targets are regular targets except they are never made the
+    /// default target.
+    pub fn register_test(&mut self, target: String, callable: Value, depends: Vec<String>) {
+        if !self.targets.contains_key(&target) {
+            self.targets_order.push(target.clone());
+        }
+
+        if !self.test_targets.contains(&target) {
+            self.test_targets.push(target.clone());
+        }
+
+        self.targets.insert(
+            target,
+            Target {
+                callable,
+                depends,
+                resolved_value: None,
+                built_target: None,
+            },
+        );
+    }
+
+    /// Obtain the names of targets registered as tests, in registration order.
+    pub fn test_targets(&self) -> &Vec<String> {
+        &self.test_targets
+    }
+
     /// Determine what targets should be resolved.
     ///
     /// This isn't the full list of targets that will be resolved, only the main
@@ -789,6 +851,31 @@ fn starlark_register_target(
     Ok(Value::new(NoneType::None))
 }
 
+/// register_test(target, callable, depends=None)
+fn starlark_register_test(
+    type_values: &TypeValues,
+    target: String,
+    callable: Value,
+    depends: Value,
+) -> ValueResult {
+    required_type_arg("callable", "function", &callable)?;
+    optional_list_arg("depends", "string", &depends)?;
+
+    let depends = match depends.get_type() {
+        "list" => depends.iter()?.iter().map(|x| x.to_string()).collect(),
+        _ => Vec::new(),
+    };
+
+    let raw_context = get_context_value(type_values)?;
+    let mut context = raw_context
+        .downcast_mut::<EnvironmentContext>()?
+        .ok_or(ValueError::IncorrectParameterType)?;
+
+    context.register_test(target, callable, depends);
+
+    Ok(Value::new(NoneType::None))
+}
+
 /// resolve_target(target)
 ///
 /// This will return a Value returned from the called function.
@@ -806,7 +893,7 @@ fn starlark_resolve_target(
     // The block is here so the borrowed `EnvironmentContext` goes out of
     // scope before we call into another Starlark function. Without this, we
     // could get a double borrow.
-    let target_entry = {
+    let (target_entry, observer) = {
         let raw_context = get_context_value(type_values)?;
         let context = raw_context
             .downcast_ref::<EnvironmentContext>()
@@ -823,36 +910,43 @@ fn starlark_resolve_target(
 
         warn!("resolving target {}", target);
 
-        match context.get_target(&target) {
+        let target_entry = match context.get_target(&target) {
             Some(v) => Ok((*v).clone()),
             None => Err(ValueError::from(RuntimeError {
                 code: "BUILD_TARGETS",
                 message: format!("target {} does not exist", target),
                 label: "resolve_target()".to_string(),
             })),
-        }?
+        }?;
+
+        (target_entry, context.target_observer())
     };
 
     // Resolve target dependencies.
-    let mut args = Vec::new();
+    let mut depends = Vec::new();
 
     for depend_target in target_entry.depends {
-        args.push(starlark_resolve_target(
-            type_values,
-            call_stack,
-            depend_target,
-        )?);
+        let value = starlark_resolve_target(type_values, call_stack, depend_target.clone())?;
+        depends.push((depend_target, value));
+    }
+
+    if let Some(observer) = &observer {
+        observer.before_resolve(&target, &depends)?;
     }
 
     let res = target_entry.callable.call(
         call_stack,
         type_values,
-        args,
+        depends.into_iter().map(|(_, value)| value).collect(),
         LinkedHashMap::new(),
         None,
         None,
     )?;
 
+    if let Some(observer) = &observer {
+        observer.after_resolve(&target, &res)?;
+    }
+
     // TODO consider replacing the target's callable with a new function that returns the
     // resolved value. This will ensure a target function is only ever called once.
 
@@ -945,6 +1039,10 @@ starlark_module! { build_targets_module =>
         starlark_register_target(env, target, callable, depends, default, default_build_script)
     }
 
+    register_test(env env, target: String, callable, depends = NoneType::None) {
+        starlark_register_test(env, target, callable, depends)
+    }
+
     resolve_target(env env, call_stack cs, target: String) {
         starlark_resolve_target(env, cs, target)
     }
@@ -1139,4 +1237,97 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_register_test() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval("def foo(): pass")?;
+        env.eval("def test_foo(): pass")?;
+        env.eval("register_test('test_foo', test_foo, depends=['foo'])")?;
+        env.eval("register_target('foo', foo)")?;
+
+        let context_value = get_context_value(&env.type_values).unwrap();
+        let context = context_value
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)
+            .unwrap();
+
+        assert_eq!(context.targets().len(), 2);
+        assert_eq!(context.test_targets(), &vec!["test_foo".to_string()]);
+        assert_eq!(context.default_target(), Some("foo"));
+        assert_eq!(
+            &context.get_target("test_foo").unwrap().depends,
+            &vec!["foo".to_string()],
+        );
+
+        Ok(())
+    }
+
+    #[derive(Debug, Default)]
+    struct RecordingObserver {
+        events: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl TargetObserver for RecordingObserver {
+        fn before_resolve(
+            &self,
+            target: &str,
+            depends: &[(String, Value)],
+        ) -> Result<(), ValueError> {
+            self.events.lock().unwrap().push(format!(
+                "before {} {}",
+                target,
+                depends
+                    .iter()
+                    .map(|(name, value)| format!("{}={}", name, value))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ));
+
+            Ok(())
+        }
+
+        fn after_resolve(&self, target: &str, value: &Value) -> Result<(), ValueError> {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("after {} {}", target, value));
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_target_observer() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval("def foo(): return 'foo_value'")?;
+        env.eval("def bar(foo): return foo + '_bar'")?;
+        env.eval("register_target('foo', foo)")?;
+        env.eval("register_target('bar', bar, depends=['foo'], default=True)")?;
+
+        let observer = Arc::new(RecordingObserver::default());
+
+        {
+            let context_value = get_context_value(&env.type_values).unwrap();
+            let mut context = context_value
+                .downcast_mut::<EnvironmentContext>()
+                .unwrap()
+                .unwrap();
+            context.set_target_observer(Some(observer.clone()));
+        }
+
+        env.eval("resolve_target('bar')")?;
+
+        assert_eq!(
+            observer.events.lock().unwrap().as_slice(),
+            &[
+                "before foo ".to_string(),
+                "after foo foo_value".to_string(),
+                "before bar foo=foo_value".to_string(),
+                "after bar foo_value_bar".to_string(),
+            ]
+        );
+
+        Ok(())
+    }
 }
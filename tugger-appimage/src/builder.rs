@@ -0,0 +1,531 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use {
+    crate::desktop::DesktopEntry,
+    anyhow::{anyhow, Context, Result},
+    duct::cmd,
+    log::warn,
+    simple_file_manifest::{FileEntry, FileManifest},
+    std::{
+        collections::BTreeMap,
+        io::Write,
+        path::{Path, PathBuf},
+    },
+    tugger_common::file_metadata::FileMetadata,
+};
+
+/// File extensions of supported icon formats.
+pub const ICON_EXTENSIONS: &[&str] = &["png", "svg", "xpm"];
+
+/// Name of the ELF section in the runtime holding update information.
+const UPDATE_INFORMATION_SECTION: &str = ".upd_info";
+
+/// Magic bytes identifying a type 2 AppImage, at offset 8 of the runtime.
+const APPIMAGE_TYPE2_MAGIC: &[u8] = b"AI\x02";
+
+/// Resolve the AppImage architecture name for a Rust target triple.
+pub fn target_triple_to_appimage_arch(triple: &str) -> Option<&'static str> {
+    match triple {
+        "x86_64-unknown-linux-gnu" | "x86_64-unknown-linux-musl" => Some("x86_64"),
+        "i686-unknown-linux-gnu" | "i686-unknown-linux-musl" => Some("i686"),
+        "aarch64-unknown-linux-gnu" | "aarch64-unknown-linux-musl" => Some("aarch64"),
+        "armv7-unknown-linux-gnueabihf" => Some("armhf"),
+        _ => None,
+    }
+}
+
+/// Embed update information into an AppImage runtime.
+///
+/// The information is written into the runtime's `.upd_info` ELF section.
+/// e.g. `gh-releases-zsync|owner|repo|latest|myapp-*x86_64.AppImage.zsync`.
+pub fn embed_update_information(runtime: &mut [u8], information: &str) -> Result<()> {
+    let (offset, size) = {
+        let elf = goblin::elf::Elf::parse(runtime).context("parsing AppImage runtime")?;
+
+        let section = elf
+            .section_headers
+            .iter()
+            .find(|section| {
+                elf.shdr_strtab.get_at(section.sh_name) == Some(UPDATE_INFORMATION_SECTION)
+            })
+            .ok_or_else(|| {
+                anyhow!(
+                    "AppImage runtime lacks a {} section",
+                    UPDATE_INFORMATION_SECTION
+                )
+            })?;
+
+        (section.sh_offset as usize, section.sh_size as usize)
+    };
+
+    if information.len() >= size {
+        return Err(anyhow!(
+            "update information is {} bytes; must be less than {}",
+            information.len(),
+            size
+        ));
+    }
+
+    let section = runtime
+        .get_mut(offset..offset + size)
+        .ok_or_else(|| anyhow!("{} section is out of bounds", UPDATE_INFORMATION_SECTION))?;
+    section.fill(0);
+    section[0..information.len()].copy_from_slice(information.as_bytes());
+
+    Ok(())
+}
+
+/// Create a symlink at `link` pointing to `target`.
+///
+/// An existing file at `link` is replaced.
+fn create_symlink(target: &Path, link: &Path) -> Result<()> {
+    if let Some(parent) = link.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("creating {}", parent.display()))?;
+    }
+
+    if link.symlink_metadata().is_ok() {
+        std::fs::remove_file(link).with_context(|| format!("removing {}", link.display()))?;
+    }
+
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(target, link)
+            .with_context(|| format!("creating symlink {}", link.display()))
+    }
+
+    #[cfg(not(unix))]
+    {
+        Err(anyhow!(
+            "unable to create symlink {} to {}: symlinks are only supported on Unix",
+            link.display(),
+            target.display()
+        ))
+    }
+}
+
+/// Build AppImages.
+///
+/// Instances hold the content of an AppDir: files, symlinks, an `AppRun`
+/// entrypoint, a `.desktop` file, and an icon. The AppDir is turned into a
+/// squashfs filesystem using `mksquashfs` and appended to a type 2 AppImage
+/// runtime, defined by [Self::set_runtime], to produce a single executable
+/// file.
+///
+/// If not defined, `AppRun` is a shell script executing the executable
+/// specified at construction time with the arguments it was invoked with.
+#[derive(Clone, Debug)]
+pub struct AppImageBuilder {
+    name: String,
+    architecture: String,
+    executable: PathBuf,
+    desktop_entry: DesktopEntry,
+    icon: Option<(String, FileEntry)>,
+    app_run: Option<FileEntry>,
+    files: FileManifest,
+    symlinks: BTreeMap<PathBuf, PathBuf>,
+    file_metadata: BTreeMap<PathBuf, FileMetadata>,
+    runtime: Option<FileEntry>,
+    update_information: Option<String>,
+}
+
+impl AppImageBuilder {
+    /// Create a new builder.
+    ///
+    /// `name` names the application and files derived from it. `architecture`
+    /// is an AppImage architecture name like `x86_64`. `executable` is the path
+    /// within the AppDir of the program the default `AppRun` runs.
+    pub fn new(
+        name: impl ToString,
+        architecture: impl ToString,
+        executable: impl AsRef<Path>,
+    ) -> Self {
+        let name = name.to_string();
+
+        Self {
+            desktop_entry: DesktopEntry::new(&name, &name, &name),
+            name,
+            architecture: architecture.to_string(),
+            executable: executable.as_ref().to_path_buf(),
+            icon: None,
+            app_run: None,
+            files: FileManifest::default(),
+            symlinks: BTreeMap::new(),
+            file_metadata: BTreeMap::new(),
+            runtime: None,
+            update_information: None,
+        }
+    }
+
+    /// The name of the application.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Obtain the `.desktop` file entry.
+    pub fn desktop_entry(&self) -> &DesktopEntry {
+        &self.desktop_entry
+    }
+
+    /// Obtain a mutable reference to the `.desktop` file entry.
+    pub fn desktop_entry_mut(&mut self) -> &mut DesktopEntry {
+        &mut self.desktop_entry
+    }
+
+    /// Obtain files to be installed in the AppDir.
+    pub fn files(&self) -> &FileManifest {
+        &self.files
+    }
+
+    /// Add a file to the AppDir. The path is relative to the AppDir root.
+    pub fn add_file(&mut self, path: impl AsRef<Path>, entry: impl Into<FileEntry>) -> Result<()> {
+        self.files.add_file_entry(path, entry)?;
+
+        Ok(())
+    }
+
+    /// Add files to the AppDir from the content of an existing [FileManifest].
+    pub fn add_manifest(&mut self, manifest: &FileManifest) -> Result<()> {
+        self.files.add_manifest(manifest)?;
+
+        Ok(())
+    }
+
+    /// Obtain symlinks to be installed in the AppDir.
+    ///
+    /// Keys are the paths of links and values are their targets.
+    pub fn symlinks(&self) -> &BTreeMap<PathBuf, PathBuf> {
+        &self.symlinks
+    }
+
+    /// Add a symlink to the AppDir.
+    ///
+    /// The path of the link is relative to the AppDir root. The target is
+    /// stored verbatim and should be relative so it resolves when the AppImage
+    /// is mounted.
+    pub fn add_symlink(&mut self, path: impl AsRef<Path>, target: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+
+        if self.files.has_path(path) {
+            return Err(anyhow!(
+                "cannot add symlink {}: a file is installed at that path",
+                path.display()
+            ));
+        }
+
+        self.symlinks
+            .insert(path.to_path_buf(), target.as_ref().to_path_buf());
+
+        Ok(())
+    }
+
+    /// Obtain metadata of files in the AppDir, keyed by path.
+    pub fn file_metadata(&self) -> &BTreeMap<PathBuf, FileMetadata> {
+        &self.file_metadata
+    }
+
+    /// Define the metadata of a file in the AppDir.
+    ///
+    /// The mode and modified time are applied when the AppDir is
+    /// materialized. Files in the AppImage are always owned by `root`.
+    pub fn set_file_metadata(
+        &mut self,
+        path: impl AsRef<Path>,
+        metadata: FileMetadata,
+    ) -> Result<()> {
+        metadata
+            .validate()
+            .with_context(|| format!("validating metadata of {}", path.as_ref().display()))?;
+
+        self.file_metadata
+            .insert(path.as_ref().to_path_buf(), metadata);
+
+        Ok(())
+    }
+
+    /// Define the application icon.
+    ///
+    /// `extension` must be one of [ICON_EXTENSIONS]. The icon is installed as
+    /// `<name>.<extension>` and `.DirIcon` in the AppDir root.
+    pub fn set_icon(&mut self, extension: &str, entry: impl Into<FileEntry>) -> Result<()> {
+        if !ICON_EXTENSIONS.contains(&extension) {
+            return Err(anyhow!(
+                "unsupported icon extension {}; must be one of {}",
+                extension,
+                ICON_EXTENSIONS.join(", ")
+            ));
+        }
+
+        self.icon = Some((extension.to_string(), entry.into()));
+
+        Ok(())
+    }
+
+    /// Define a custom `AppRun` entrypoint.
+    pub fn set_app_run(&mut self, entry: impl Into<FileEntry>) {
+        self.app_run = Some(entry.into());
+    }
+
+    /// Define the AppImage runtime.
+    ///
+    /// This is the executable mounting the squashfs filesystem and running
+    /// `AppRun`. It must be a type 2 runtime for the target architecture.
+    pub fn set_runtime(&mut self, entry: impl Into<FileEntry>) {
+        self.runtime = Some(entry.into());
+    }
+
+    /// Define update information to embed in the AppImage runtime.
+    pub fn set_update_information(&mut self, information: impl ToString) {
+        self.update_information = Some(information.to_string());
+    }
+
+    /// Obtain the file name of the AppImage.
+    ///
+    /// This is `{name}-{architecture}.AppImage`.
+    pub fn appimage_file_name(&self) -> String {
+        format!("{}-{}.AppImage", self.name, self.architecture)
+    }
+
+    /// Obtain the content of the default `AppRun` script.
+    fn default_app_run(&self) -> String {
+        format!(
+            "#!/bin/sh\nHERE=\"$(dirname \"$(readlink -f \"$0\")\")\"\nexec \"$HERE/{}\" \"$@\"\n",
+            self.executable.display()
+        )
+    }
+
+    /// Materialize the AppDir into `dest_dir`, returning its path.
+    ///
+    /// The AppDir is named `<name>.AppDir`. An existing directory of that
+    /// name is replaced.
+    pub fn materialize_appdir(&self, dest_dir: impl AsRef<Path>) -> Result<PathBuf> {
+        let (icon_extension, icon) = self
+            .icon
+            .as_ref()
+            .ok_or_else(|| anyhow!("an icon must be defined to build an AppImage"))?;
+
+        let app_dir = dest_dir.as_ref().join(format!("{}.AppDir", self.name));
+
+        if app_dir.exists() {
+            remove_dir_all::remove_dir_all(&app_dir)
+                .with_context(|| format!("removing {}", app_dir.display()))?;
+        }
+        std::fs::create_dir_all(&app_dir)
+            .with_context(|| format!("creating {}", app_dir.display()))?;
+
+        let mut manifest = self.files.clone();
+
+        let app_run = if let Some(entry) = &self.app_run {
+            FileEntry::new_from_data(entry.resolve_content()?, true)
+        } else {
+            FileEntry::new_from_data(self.default_app_run().into_bytes(), true)
+        };
+        manifest.add_file_entry("AppRun", app_run)?;
+
+        manifest.add_file_entry(
+            format!("{}.desktop", self.name),
+            FileEntry::new_from_data(self.desktop_entry.to_string().into_bytes(), false),
+        )?;
+
+        let icon = FileEntry::new_from_data(icon.resolve_content()?, false);
+        manifest.add_file_entry(format!("{}.{}", self.name, icon_extension), icon.clone())?;
+        manifest.add_file_entry(".DirIcon", icon)?;
+
+        manifest
+            .materialize_files(&app_dir)
+            .with_context(|| format!("installing files to {}", app_dir.display()))?;
+
+        for (path, metadata) in &self.file_metadata {
+            metadata.apply_to_path(&app_dir.join(path))?;
+        }
+
+        for (path, target) in &self.symlinks {
+            if manifest.has_path(path) {
+                return Err(anyhow!(
+                    "{} is installed as both a file and a symlink",
+                    path.display()
+                ));
+            }
+
+            create_symlink(target, &app_dir.join(path))?;
+        }
+
+        Ok(app_dir)
+    }
+
+    /// Build the AppImage into `build_path`, returning its path.
+    ///
+    /// The AppDir and the squashfs filesystem are written to `build_path` as
+    /// intermediate files.
+    pub fn build(&self, build_path: impl AsRef<Path>) -> Result<PathBuf> {
+        let build_path = build_path.as_ref();
+
+        let mut runtime = self
+            .runtime
+            .as_ref()
+            .ok_or_else(|| anyhow!("a runtime must be defined to build an AppImage"))?
+            .resolve_content()?;
+
+        if runtime.get(8..11) != Some(APPIMAGE_TYPE2_MAGIC) {
+            return Err(anyhow!("runtime is not a type 2 AppImage runtime"));
+        }
+
+        if let Some(information) = &self.update_information {
+            embed_update_information(&mut runtime, information)?;
+        }
+
+        let app_dir = self.materialize_appdir(build_path)?;
+
+        let squashfs_path = build_path.join(format!("{}.squashfs", self.name));
+        warn!("creating squashfs {}", squashfs_path.display());
+        let output = cmd!(
+            "mksquashfs",
+            &app_dir,
+            &squashfs_path,
+            "-root-owned",
+            "-noappend",
+            "-no-xattrs",
+            "-comp",
+            "gzip"
+        )
+        .stderr_to_stdout()
+        .stdout_capture()
+        .unchecked()
+        .run()
+        .context("running mksquashfs")?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "error running mksquashfs: {}",
+                String::from_utf8_lossy(&output.stdout)
+            ));
+        }
+
+        let squashfs = std::fs::read(&squashfs_path)
+            .with_context(|| format!("reading {}", squashfs_path.display()))?;
+
+        let appimage_path = build_path.join(self.appimage_file_name());
+        let mut fh = std::fs::File::create(&appimage_path)
+            .with_context(|| format!("opening {} for writing", appimage_path.display()))?;
+        fh.write_all(&runtime)?;
+        fh.write_all(&squashfs)?;
+        simple_file_manifest::set_executable(&mut fh).context("making AppImage executable")?;
+
+        warn!("wrote {}", appimage_path.display());
+
+        Ok(appimage_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, tugger_common::testutil::*};
+
+    fn builder() -> Result<AppImageBuilder> {
+        let mut builder = AppImageBuilder::new("myapp", "x86_64", "usr/bin/myapp");
+        builder.add_file("usr/bin/myapp", FileEntry::new_from_data(vec![42], true))?;
+        builder.set_icon("png", FileEntry::new_from_data(b"icon".to_vec(), false))?;
+
+        Ok(builder)
+    }
+
+    #[test]
+    fn test_appimage_file_name() -> Result<()> {
+        assert_eq!(builder()?.appimage_file_name(), "myapp-x86_64.AppImage");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_icon() -> Result<()> {
+        let mut builder = builder()?;
+        assert!(builder
+            .set_icon("ico", FileEntry::new_from_data(vec![], false))
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_materialize_appdir() -> Result<()> {
+        let dest_dir = DEFAULT_TEMP_DIR.path().join("appimage-materialize-appdir");
+
+        let app_dir = builder()?.materialize_appdir(&dest_dir)?;
+        assert_eq!(app_dir, dest_dir.join("myapp.AppDir"));
+
+        let app_run = std::fs::read_to_string(app_dir.join("AppRun"))?;
+        assert!(app_run.ends_with("exec \"$HERE/usr/bin/myapp\" \"$@\"\n"));
+
+        let desktop = std::fs::read_to_string(app_dir.join("myapp.desktop"))?;
+        assert!(desktop.contains("Icon=myapp\n"));
+
+        assert_eq!(std::fs::read(app_dir.join("myapp.png"))?, b"icon");
+        assert_eq!(std::fs::read(app_dir.join(".DirIcon"))?, b"icon");
+        assert!(app_dir.join("usr/bin/myapp").exists());
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks() -> Result<()> {
+        let dest_dir = DEFAULT_TEMP_DIR.path().join("appimage-symlinks");
+
+        let mut builder = builder()?;
+        builder.add_symlink("usr/bin/myapp-link", "myapp")?;
+        assert!(builder.add_symlink("usr/bin/myapp", "other").is_err());
+
+        let app_dir = builder.materialize_appdir(&dest_dir)?;
+        let link = app_dir.join("usr/bin/myapp-link");
+        assert!(link.symlink_metadata()?.file_type().is_symlink());
+        assert_eq!(std::fs::read_link(&link)?, PathBuf::from("myapp"));
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file_metadata() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dest_dir = DEFAULT_TEMP_DIR.path().join("appimage-file-metadata");
+
+        let mut builder = builder()?;
+        builder.set_file_metadata(
+            "usr/bin/myapp",
+            FileMetadata {
+                mode: Some(0o700),
+                ..FileMetadata::default()
+            },
+        )?;
+
+        let app_dir = builder.materialize_appdir(&dest_dir)?;
+        let metadata = std::fs::metadata(app_dir.join("usr/bin/myapp"))?;
+        assert_eq!(metadata.permissions().mode() & 0o7777, 0o700);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_icon() {
+        let builder = AppImageBuilder::new("myapp", "x86_64", "myapp");
+        assert!(builder
+            .materialize_appdir(DEFAULT_TEMP_DIR.path().join("appimage-missing-icon"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_invalid_runtime() -> Result<()> {
+        let dest_dir = DEFAULT_TEMP_DIR.path().join("appimage-invalid-runtime");
+        let mut builder = builder()?;
+        assert!(builder.build(&dest_dir).is_err());
+        builder.set_runtime(FileEntry::new_from_data(b"not a runtime".to_vec(), true));
+        assert!(builder.build(&dest_dir).is_err());
+
+        let mut data = b"not an ELF".to_vec();
+        assert!(embed_update_information(&mut data, "zsync|https://example.com").is_err());
+
+        Ok(())
+    }
+}
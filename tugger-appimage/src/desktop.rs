@@ -0,0 +1,81 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::fmt::{Display, Formatter};
+
+/// A `[Desktop Entry]` group of a freedesktop.org `.desktop` file.
+///
+/// Keys are stored in insertion order and serialized as `key=value` lines.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DesktopEntry {
+    keys: Vec<(String, String)>,
+}
+
+impl DesktopEntry {
+    /// Create an entry for an application.
+    ///
+    /// `Type`, `Name`, `Exec`, `Icon`, and `Categories` keys are defined.
+    /// `Categories` defaults to `Utility;`.
+    pub fn new(name: impl ToString, exec: impl ToString, icon: impl ToString) -> Self {
+        let mut entry = Self { keys: vec![] };
+        entry.set("Type", "Application");
+        entry.set("Name", name);
+        entry.set("Exec", exec);
+        entry.set("Icon", icon);
+        entry.set("Categories", "Utility;");
+
+        entry
+    }
+
+    /// Obtain the value of a key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.keys
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Set the value of a key, replacing an existing value.
+    pub fn set(&mut self, key: impl ToString, value: impl ToString) {
+        let key = key.to_string();
+        let value = value.to_string();
+
+        if let Some(entry) = self.keys.iter_mut().find(|(k, _)| k == &key) {
+            entry.1 = value;
+        } else {
+            self.keys.push((key, value));
+        }
+    }
+}
+
+impl Display for DesktopEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "[Desktop Entry]")?;
+
+        for (key, value) in &self.keys {
+            writeln!(f, "{}={}", key, value)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize() {
+        let mut entry = DesktopEntry::new("My App", "myapp", "myapp");
+        entry.set("Categories", "Development;");
+        entry.set("Terminal", "true");
+
+        assert_eq!(entry.get("Exec"), Some("myapp"));
+        assert_eq!(
+            entry.to_string(),
+            "[Desktop Entry]\nType=Application\nName=My App\nExec=myapp\nIcon=myapp\n\
+            Categories=Development;\nTerminal=true\n"
+        );
+    }
+}
@@ -0,0 +1,15 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! Functionality for the AppImage packaging format. */
+
+mod builder;
+mod desktop;
+
+pub use {
+    builder::{
+        embed_update_information, target_triple_to_appimage_arch, AppImageBuilder, ICON_EXTENSIONS,
+    },
+    desktop::DesktopEntry,
+};
@@ -0,0 +1,503 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! Creation of DMG disk images. */
+
+use {
+    crate::{
+        ds_store::{alias_record, DsStoreBuilder},
+        udif::{add_udif_licenses, LicenseAgreement},
+    },
+    anyhow::{anyhow, Context, Result},
+    duct::cmd,
+    log::warn,
+    simple_file_manifest::{FileEntry, FileManifest},
+    std::{
+        collections::BTreeMap,
+        path::{Path, PathBuf},
+    },
+};
+
+/// Directory holding the background image in the volume.
+const BACKGROUND_DIRECTORY: &str = ".background";
+
+/// Name of the symlink to `/Applications`.
+const APPLICATIONS_SYMLINK: &str = "Applications";
+
+/// Bounds of the Finder window displaying a volume.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct WindowBounds {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for WindowBounds {
+    fn default() -> Self {
+        Self {
+            x: 100,
+            y: 100,
+            width: 640,
+            height: 480,
+        }
+    }
+}
+
+/// Build DMG disk images.
+///
+/// Files are materialized into a staging directory along with a `.DS_Store`
+/// file describing the layout of the Finder window. `hdiutil` then creates a
+/// zlib compressed (`UDZO`) UDIF image with an HFS+ filesystem from that
+/// directory, so building requires macOS. License agreements are added to the
+/// resulting image natively.
+#[derive(Clone, Debug)]
+pub struct DmgBuilder {
+    volume_name: String,
+    files: FileManifest,
+    background: Option<(String, FileEntry)>,
+    window_bounds: WindowBounds,
+    icon_size: u32,
+    text_size: u32,
+    icon_positions: BTreeMap<String, (u32, u32)>,
+    applications_symlink: bool,
+    licenses: Vec<LicenseAgreement>,
+}
+
+impl DmgBuilder {
+    /// Create a new instance for a volume name.
+    pub fn new(volume_name: impl ToString) -> Result<Self> {
+        let volume_name = volume_name.to_string();
+
+        // Volume names are recorded as Pascal strings in alias records.
+        if volume_name.is_empty() || volume_name.len() > 27 {
+            return Err(anyhow!(
+                "volume name must be between 1 and 27 bytes: {}",
+                volume_name
+            ));
+        }
+        if volume_name.contains(':') || volume_name.contains('/') {
+            return Err(anyhow!(
+                "volume name cannot contain ':' or '/': {}",
+                volume_name
+            ));
+        }
+
+        Ok(Self {
+            volume_name,
+            files: FileManifest::default(),
+            background: None,
+            window_bounds: WindowBounds::default(),
+            icon_size: 128,
+            text_size: 12,
+            icon_positions: BTreeMap::new(),
+            applications_symlink: false,
+            licenses: vec![],
+        })
+    }
+
+    /// The name of the volume.
+    pub fn volume_name(&self) -> &str {
+        &self.volume_name
+    }
+
+    /// Obtain files to be installed in the volume.
+    pub fn files(&self) -> &FileManifest {
+        &self.files
+    }
+
+    /// Add a file to the volume. The path is relative to the volume root.
+    pub fn add_file(&mut self, path: impl AsRef<Path>, entry: impl Into<FileEntry>) -> Result<()> {
+        self.files.add_file_entry(path, entry)?;
+
+        Ok(())
+    }
+
+    /// Add files to the volume from the content of an existing [FileManifest].
+    pub fn add_manifest(&mut self, manifest: &FileManifest) -> Result<()> {
+        self.files.add_manifest(manifest)?;
+
+        Ok(())
+    }
+
+    /// Define the background image of the Finder window.
+    ///
+    /// The image is installed as `.background/<filename>` in the volume.
+    /// Its dimensions should match the window size.
+    pub fn set_background(
+        &mut self,
+        filename: impl ToString,
+        entry: impl Into<FileEntry>,
+    ) -> Result<()> {
+        let filename = filename.to_string();
+
+        if filename.is_empty() || filename.contains('/') || filename.contains(':') {
+            return Err(anyhow!("invalid background filename: {}", filename));
+        }
+
+        self.background = Some((filename, entry.into()));
+
+        Ok(())
+    }
+
+    /// Obtain the bounds of the Finder window.
+    pub fn window_bounds(&self) -> WindowBounds {
+        self.window_bounds
+    }
+
+    /// Define the bounds of the Finder window.
+    pub fn set_window_bounds(&mut self, bounds: WindowBounds) {
+        self.window_bounds = bounds;
+    }
+
+    /// Define the size of icons in pixels.
+    pub fn set_icon_size(&mut self, size: u32) -> Result<()> {
+        if !(16..=512).contains(&size) {
+            return Err(anyhow!("icon size must be between 16 and 512: {}", size));
+        }
+
+        self.icon_size = size;
+
+        Ok(())
+    }
+
+    /// Define the size of icon labels in points.
+    pub fn set_text_size(&mut self, size: u32) -> Result<()> {
+        if !(10..=16).contains(&size) {
+            return Err(anyhow!("text size must be between 10 and 16: {}", size));
+        }
+
+        self.text_size = size;
+
+        Ok(())
+    }
+
+    /// Obtain the positions of icons, keyed by filename.
+    pub fn icon_positions(&self) -> &BTreeMap<String, (u32, u32)> {
+        &self.icon_positions
+    }
+
+    /// Define the position of the icon of an entry in the volume root.
+    ///
+    /// Coordinates are of the center of the icon, relative to the top left
+    /// corner of the window content.
+    pub fn set_icon_position(&mut self, filename: impl ToString, x: u32, y: u32) {
+        self.icon_positions.insert(filename.to_string(), (x, y));
+    }
+
+    /// Define whether to add an `Applications` symlink to `/Applications`.
+    ///
+    /// This allows installing applications by dragging them onto the link.
+    pub fn set_applications_symlink(&mut self, value: bool) {
+        self.applications_symlink = value;
+    }
+
+    /// Obtain the license agreements.
+    pub fn licenses(&self) -> &[LicenseAgreement] {
+        &self.licenses
+    }
+
+    /// Add a license agreement to display when the image is attached.
+    ///
+    /// The first license is the default when no license matches the
+    /// language of the user.
+    pub fn add_license(&mut self, license: LicenseAgreement) -> Result<()> {
+        if self.licenses.iter().any(|l| l.language == license.language) {
+            return Err(anyhow!(
+                "a license is already defined for {}",
+                license.language.as_str()
+            ));
+        }
+
+        self.licenses.push(license);
+
+        Ok(())
+    }
+
+    /// Obtain the file name of the disk image.
+    ///
+    /// This is `{volume_name}.dmg`.
+    pub fn dmg_file_name(&self) -> String {
+        format!("{}.dmg", self.volume_name)
+    }
+
+    /// Names of entries in the volume root.
+    fn root_entries(&self) -> Vec<String> {
+        let mut names = self
+            .files
+            .iter_entries()
+            .filter_map(|(path, _)| {
+                path.components()
+                    .next()
+                    .map(|c| c.as_os_str().to_string_lossy().to_string())
+            })
+            .collect::<Vec<_>>();
+
+        if self.applications_symlink {
+            names.push(APPLICATIONS_SYMLINK.to_string());
+        }
+
+        names.sort();
+        names.dedup();
+
+        names
+    }
+
+    /// Obtain the content of the `.DS_Store` file of the volume root.
+    pub fn ds_store(&self) -> Result<Vec<u8>> {
+        let entries = self.root_entries();
+
+        let mut store = DsStoreBuilder::default();
+
+        for (name, (x, y)) in &self.icon_positions {
+            if !entries.contains(name) {
+                return Err(anyhow!(
+                    "icon position defined for {}, which is not in the volume root",
+                    name
+                ));
+            }
+
+            store.set_icon_position(name, *x, *y);
+        }
+
+        let bounds = self.window_bounds;
+        store.set_window_bounds(bounds.x, bounds.y, bounds.width, bounds.height)?;
+
+        let alias = if let Some((filename, _)) = &self.background {
+            Some(alias_record(
+                &self.volume_name,
+                &[BACKGROUND_DIRECTORY, filename.as_str()],
+            )?)
+        } else {
+            None
+        };
+        store.set_icon_view(self.icon_size, self.text_size, alias.as_deref())?;
+
+        Ok(store.to_bytes()?)
+    }
+
+    /// Materialize the volume content into `dest_dir`.
+    ///
+    /// An existing directory is replaced.
+    pub fn materialize_volume(&self, dest_dir: impl AsRef<Path>) -> Result<()> {
+        let dest_dir = dest_dir.as_ref();
+
+        if dest_dir.exists() {
+            remove_dir_all::remove_dir_all(dest_dir)
+                .with_context(|| format!("removing {}", dest_dir.display()))?;
+        }
+        std::fs::create_dir_all(dest_dir)
+            .with_context(|| format!("creating {}", dest_dir.display()))?;
+
+        for reserved in [".DS_Store", BACKGROUND_DIRECTORY] {
+            if self
+                .files
+                .iter_entries()
+                .any(|(path, _)| path.starts_with(reserved))
+            {
+                return Err(anyhow!("{} is reserved in disk images", reserved));
+            }
+        }
+
+        if self.applications_symlink
+            && self
+                .files
+                .iter_entries()
+                .any(|(path, _)| path.starts_with(APPLICATIONS_SYMLINK))
+        {
+            return Err(anyhow!(
+                "{} is installed as both a file and a symlink",
+                APPLICATIONS_SYMLINK
+            ));
+        }
+
+        let mut manifest = self.files.clone();
+
+        manifest.add_file_entry(
+            ".DS_Store",
+            FileEntry::new_from_data(self.ds_store()?, false),
+        )?;
+
+        if let Some((filename, entry)) = &self.background {
+            manifest.add_file_entry(
+                Path::new(BACKGROUND_DIRECTORY).join(filename),
+                FileEntry::new_from_data(entry.resolve_content()?, false),
+            )?;
+        }
+
+        manifest
+            .materialize_files(dest_dir)
+            .with_context(|| format!("installing files to {}", dest_dir.display()))?;
+
+        if self.applications_symlink {
+            let link = dest_dir.join(APPLICATIONS_SYMLINK);
+
+            #[cfg(unix)]
+            std::os::unix::fs::symlink("/Applications", &link)
+                .with_context(|| format!("creating symlink {}", link.display()))?;
+
+            #[cfg(not(unix))]
+            return Err(anyhow!(
+                "unable to create symlink {}: symlinks are only supported on Unix",
+                link.display()
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Build the disk image into `build_path`, returning its path.
+    ///
+    /// The volume content is staged in a `volume` directory in `build_path`.
+    pub fn build(&self, build_path: impl AsRef<Path>) -> Result<PathBuf> {
+        let build_path = build_path.as_ref();
+
+        let volume_path = build_path.join("volume");
+        self.materialize_volume(&volume_path)?;
+
+        let dmg_path = build_path.join(self.dmg_file_name());
+        warn!("creating disk image {}", dmg_path.display());
+        let output = cmd!(
+            "hdiutil",
+            "create",
+            "-volname",
+            &self.volume_name,
+            "-srcfolder",
+            &volume_path,
+            "-fs",
+            "HFS+",
+            "-format",
+            "UDZO",
+            "-imagekey",
+            "zlib-level=9",
+            "-ov",
+            &dmg_path
+        )
+        .stderr_to_stdout()
+        .stdout_capture()
+        .unchecked()
+        .run()
+        .context("running hdiutil")?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "error running hdiutil: {}",
+                String::from_utf8_lossy(&output.stdout)
+            ));
+        }
+
+        if !self.licenses.is_empty() {
+            let data = std::fs::read(&dmg_path)
+                .with_context(|| format!("reading {}", dmg_path.display()))?;
+            let data = add_udif_licenses(&data, &self.licenses)
+                .with_context(|| format!("adding licenses to {}", dmg_path.display()))?;
+            std::fs::write(&dmg_path, data)
+                .with_context(|| format!("writing {}", dmg_path.display()))?;
+        }
+
+        warn!("wrote {}", dmg_path.display());
+
+        Ok(dmg_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::udif::LicenseLanguage, tugger_common::testutil::*};
+
+    fn builder() -> Result<DmgBuilder> {
+        let mut builder = DmgBuilder::new("My App")?;
+        builder.add_file(
+            "My App.app/Contents/MacOS/myapp",
+            FileEntry::new_from_data(b"#!/bin/sh\n".to_vec(), true),
+        )?;
+
+        Ok(builder)
+    }
+
+    #[test]
+    fn test_new() {
+        assert!(DmgBuilder::new("").is_err());
+        assert!(DmgBuilder::new("a:b").is_err());
+        assert!(DmgBuilder::new("x".repeat(28)).is_err());
+    }
+
+    #[test]
+    fn test_dmg_file_name() -> Result<()> {
+        assert_eq!(builder()?.dmg_file_name(), "My App.dmg");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_icon_positions() -> Result<()> {
+        let mut builder = builder()?;
+        builder.set_icon_position("My App.app", 150, 200);
+        builder.ds_store()?;
+
+        builder.set_icon_position("Applications", 450, 200);
+        assert!(builder.ds_store().is_err());
+        builder.set_applications_symlink(true);
+        builder.ds_store()?;
+
+        builder.set_icon_position("missing", 0, 0);
+        assert!(builder.ds_store().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_license() -> Result<()> {
+        let mut builder = builder()?;
+        builder.add_license(LicenseAgreement::new(LicenseLanguage::English, "terms"))?;
+        assert!(builder
+            .add_license(LicenseAgreement::new(LicenseLanguage::English, "other"))
+            .is_err());
+        assert_eq!(builder.licenses().len(), 1);
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_materialize_volume() -> Result<()> {
+        let dest_dir = DEFAULT_TEMP_DIR.path().join("dmg-materialize-volume");
+
+        let mut builder = builder()?;
+        builder.set_background("background.png", FileEntry::new_from_data(vec![42], false))?;
+        builder.set_applications_symlink(true);
+        builder.set_icon_position("My App.app", 150, 200);
+        builder.set_icon_position("Applications", 450, 200);
+        builder.materialize_volume(&dest_dir)?;
+
+        assert!(dest_dir.join("My App.app/Contents/MacOS/myapp").exists());
+        assert_eq!(
+            std::fs::read(dest_dir.join(".background/background.png"))?,
+            vec![42]
+        );
+        assert_eq!(
+            std::fs::read(dest_dir.join(".DS_Store"))?,
+            builder.ds_store()?
+        );
+
+        let link = dest_dir.join("Applications");
+        assert!(link.symlink_metadata()?.file_type().is_symlink());
+        assert_eq!(std::fs::read_link(&link)?, PathBuf::from("/Applications"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reserved_paths() -> Result<()> {
+        let dest_dir = DEFAULT_TEMP_DIR.path().join("dmg-reserved-paths");
+
+        let mut builder = builder()?;
+        builder.add_file(
+            ".background/other.png",
+            FileEntry::new_from_data(vec![], false),
+        )?;
+        assert!(builder.materialize_volume(&dest_dir).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,569 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! Writing of Finder `.DS_Store` files.
+
+`.DS_Store` files hold Finder view settings for a directory, such as icon
+positions and the window background. They consist of a B-tree of records
+stored in blocks managed by a buddy allocator.
+
+Only the subset of the format needed to lay out disk image windows is
+implemented.
+*/
+
+use {
+    plist::{Dictionary, Value},
+    std::collections::BTreeMap,
+    thiserror::Error,
+};
+
+#[derive(Debug, Error)]
+pub enum DsStoreError {
+    #[error("plist error: {0}")]
+    Plist(#[from] plist::Error),
+
+    #[error("too many .DS_Store records")]
+    TooManyRecords,
+
+    #[error("{0} is too long for an alias record")]
+    AliasNameTooLong(String),
+}
+
+/// Size of B-tree node blocks.
+const PAGE_SIZE: usize = 0x1000;
+
+/// Unidentified header bytes written by Finder.
+const HEADER_UNKNOWN: [u8; 16] = [
+    0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x00, 0x87, 0x00, 0x00, 0x20, 0x0b, 0x00, 0x00, 0x00, 0x00,
+];
+
+/// The value of a `.DS_Store` record.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DsStoreValue {
+    Blob(Vec<u8>),
+    Bool(bool),
+    Long(u32),
+    Type([u8; 4]),
+    UnicodeString(String),
+}
+
+impl DsStoreValue {
+    fn write(&self, dest: &mut Vec<u8>) {
+        match self {
+            Self::Blob(data) => {
+                dest.extend_from_slice(b"blob");
+                dest.extend_from_slice(&(data.len() as u32).to_be_bytes());
+                dest.extend_from_slice(data);
+            }
+            Self::Bool(v) => {
+                dest.extend_from_slice(b"bool");
+                dest.push(u8::from(*v));
+            }
+            Self::Long(v) => {
+                dest.extend_from_slice(b"long");
+                dest.extend_from_slice(&v.to_be_bytes());
+            }
+            Self::Type(v) => {
+                dest.extend_from_slice(b"type");
+                dest.extend_from_slice(v);
+            }
+            Self::UnicodeString(s) => {
+                dest.extend_from_slice(b"ustr");
+                write_utf16(dest, s);
+            }
+        }
+    }
+}
+
+/// Write a string as a 32-bit count of UTF-16 code units followed by the code units.
+fn write_utf16(dest: &mut Vec<u8>, s: &str) {
+    let units = s.encode_utf16().collect::<Vec<_>>();
+
+    dest.extend_from_slice(&(units.len() as u32).to_be_bytes());
+    for unit in units {
+        dest.extend_from_slice(&unit.to_be_bytes());
+    }
+}
+
+/// Builds `.DS_Store` files.
+///
+/// Records are keyed by a filename, `.` being the directory itself, and a
+/// 4 character code identifying the kind of record.
+#[derive(Clone, Debug, Default)]
+pub struct DsStoreBuilder {
+    records: BTreeMap<(String, String, [u8; 4]), DsStoreValue>,
+}
+
+impl DsStoreBuilder {
+    /// Set a record.
+    pub fn set(&mut self, filename: impl ToString, code: &[u8; 4], value: DsStoreValue) {
+        let filename = filename.to_string();
+
+        // Finder sorts records by case-insensitive filename, then code.
+        self.records
+            .insert((filename.to_lowercase(), filename, *code), value);
+    }
+
+    /// Set the position of the icon of a file in the window.
+    pub fn set_icon_position(&mut self, filename: impl ToString, x: u32, y: u32) {
+        let mut data = vec![];
+        data.extend_from_slice(&x.to_be_bytes());
+        data.extend_from_slice(&y.to_be_bytes());
+        data.extend_from_slice(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00]);
+
+        self.set(filename, b"Iloc", DsStoreValue::Blob(data));
+    }
+
+    /// Set the settings of the window displaying the directory.
+    ///
+    /// Toolbars and sidebars are hidden, as is customary for disk images.
+    pub fn set_window_bounds(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(), DsStoreError> {
+        let mut dict = Dictionary::new();
+        dict.insert("ContainerShowSidebar".into(), false.into());
+        dict.insert("ShowPathbar".into(), false.into());
+        dict.insert("ShowSidebar".into(), false.into());
+        dict.insert("ShowStatusBar".into(), false.into());
+        dict.insert("ShowTabView".into(), false.into());
+        dict.insert("ShowToolbar".into(), false.into());
+        dict.insert(
+            "WindowBounds".into(),
+            format!("{{{{{}, {}}}, {{{}, {}}}}}", x, y, width, height).into(),
+        );
+
+        self.set(".", b"bwsp", DsStoreValue::Blob(binary_plist(dict)?));
+
+        Ok(())
+    }
+
+    /// Set the icon view settings of the directory.
+    ///
+    /// `background_alias` is an alias record of a background image, as produced
+    /// by [alias_record].
+    pub fn set_icon_view(
+        &mut self,
+        icon_size: u32,
+        text_size: u32,
+        background_alias: Option<&[u8]>,
+    ) -> Result<(), DsStoreError> {
+        let mut dict = Dictionary::new();
+        dict.insert("arrangeBy".into(), "none".into());
+        dict.insert("backgroundColorBlue".into(), 1.0.into());
+        dict.insert("backgroundColorGreen".into(), 1.0.into());
+        dict.insert("backgroundColorRed".into(), 1.0.into());
+        if let Some(alias) = background_alias {
+            dict.insert("backgroundImageAlias".into(), Value::Data(alias.to_vec()));
+            dict.insert("backgroundType".into(), 2.into());
+        } else {
+            dict.insert("backgroundType".into(), 0.into());
+        }
+        dict.insert("gridOffsetX".into(), 0.0.into());
+        dict.insert("gridOffsetY".into(), 0.0.into());
+        dict.insert("gridSpacing".into(), 100.0.into());
+        dict.insert("iconSize".into(), f64::from(icon_size).into());
+        dict.insert("labelOnBottom".into(), true.into());
+        dict.insert("showIconPreview".into(), true.into());
+        dict.insert("showItemInfo".into(), false.into());
+        dict.insert("textSize".into(), f64::from(text_size).into());
+        dict.insert("viewOptionsVersion".into(), 1.into());
+
+        self.set(".", b"icvp", DsStoreValue::Blob(binary_plist(dict)?));
+        self.set(".", b"vSrn", DsStoreValue::Long(1));
+        self.set(".", b"vstl", DsStoreValue::Type(*b"icnv"));
+
+        Ok(())
+    }
+
+    /// Serialize the records to a `.DS_Store` file.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, DsStoreError> {
+        let records = self
+            .records
+            .iter()
+            .map(|((_, filename, code), value)| {
+                let mut data = vec![];
+                write_utf16(&mut data, filename);
+                data.extend_from_slice(code);
+                value.write(&mut data);
+                data
+            })
+            .collect::<Vec<_>>();
+
+        // Blocks 0 and 1 hold allocator metadata and the B-tree header.
+        let mut blocks = vec![vec![], vec![]];
+        let (root, levels, node_count) = write_tree(&records, &mut blocks)?;
+
+        let mut header = vec![];
+        for v in [
+            root,
+            levels,
+            records.len() as u32,
+            node_count,
+            PAGE_SIZE as u32,
+        ] {
+            header.extend_from_slice(&v.to_be_bytes());
+        }
+        blocks[1] = header;
+
+        // The allocator metadata block is large enough for a table of 256 block
+        // addresses, so its size is known before addresses are assigned.
+        let metadata_size = 0x800;
+        let mut sizes = vec![metadata_size];
+        sizes.extend(
+            blocks[1..]
+                .iter()
+                .map(|b| b.len().max(32).next_power_of_two()),
+        );
+
+        // The first 32 bytes are occupied by the file header.
+        let mut offset = 32;
+        let mut offsets = vec![];
+        for size in &sizes {
+            offset = (offset + size - 1) / size * size;
+            offsets.push(offset);
+            offset += size;
+        }
+        let total_size = offset;
+
+        let mut metadata = vec![];
+        metadata.extend_from_slice(&(blocks.len() as u32).to_be_bytes());
+        metadata.extend_from_slice(&0u32.to_be_bytes());
+        for (offset, size) in offsets.iter().zip(sizes.iter()) {
+            let address = *offset as u32 | size.trailing_zeros();
+            metadata.extend_from_slice(&address.to_be_bytes());
+        }
+        metadata.resize(8 + 256 * 4, 0);
+        // Directory of named blocks.
+        metadata.extend_from_slice(&1u32.to_be_bytes());
+        metadata.push(4);
+        metadata.extend_from_slice(b"DSDB");
+        metadata.extend_from_slice(&1u32.to_be_bytes());
+        // Empty free lists.
+        metadata.resize(metadata.len() + 32 * 4, 0);
+        blocks[0] = metadata;
+
+        let mut data = vec![0u8; total_size];
+        for (block, offset) in blocks.iter().zip(offsets.iter()) {
+            data[*offset..*offset + block.len()].copy_from_slice(block);
+        }
+
+        let mut file = vec![0, 0, 0, 1];
+        file.extend_from_slice(b"Bud1");
+        file.extend_from_slice(&(offsets[0] as u32).to_be_bytes());
+        file.extend_from_slice(&(metadata_size as u32).to_be_bytes());
+        file.extend_from_slice(&(offsets[0] as u32).to_be_bytes());
+        file.extend_from_slice(&HEADER_UNKNOWN);
+        file.extend_from_slice(&data[file.len() - 4..]);
+
+        Ok(file)
+    }
+}
+
+/// Write B-tree nodes holding records, returning the root node block, tree height, and node count.
+fn write_tree(
+    records: &[Vec<u8>],
+    blocks: &mut Vec<Vec<u8>>,
+) -> Result<(u32, u32, u32), DsStoreError> {
+    let node = |pointer: u32, entries: &[Vec<u8>]| {
+        let mut data = vec![];
+        data.extend_from_slice(&pointer.to_be_bytes());
+        data.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+        for entry in entries {
+            data.extend_from_slice(entry);
+        }
+        data
+    };
+
+    // Partition records into leaves. The record following each leaf
+    // becomes a separator in the parent node.
+    let mut leaves: Vec<&[Vec<u8>]> = vec![];
+    let mut separators = vec![];
+    let mut start = 0;
+    let mut size = 8;
+    for (i, record) in records.iter().enumerate() {
+        if size + record.len() > PAGE_SIZE {
+            if i == start {
+                return Err(DsStoreError::TooManyRecords);
+            }
+            leaves.push(&records[start..i]);
+            separators.push(record);
+            start = i + 1;
+            size = 8;
+        } else {
+            size += record.len();
+        }
+    }
+    if start == records.len() && !leaves.is_empty() {
+        // The last record became a separator. Use the last record of the
+        // preceding leaf as the separator instead so the final leaf isn't empty.
+        let previous = leaves.pop().unwrap();
+        if previous.len() < 2 {
+            return Err(DsStoreError::TooManyRecords);
+        }
+        let end = records.len() - 2;
+        leaves.push(&previous[..previous.len() - 1]);
+        separators.pop();
+        separators.push(&records[end]);
+        start = end + 1;
+    }
+    leaves.push(&records[start..]);
+
+    if leaves.len() == 1 {
+        blocks.push(node(0, leaves[0]));
+        return Ok(((blocks.len() - 1) as u32, 0, 1));
+    }
+
+    let mut children = vec![];
+    for leaf in &leaves {
+        blocks.push(node(0, leaf));
+        children.push((blocks.len() - 1) as u32);
+    }
+
+    let entries = children
+        .iter()
+        .zip(separators.iter())
+        .map(|(child, separator)| {
+            let mut entry = child.to_be_bytes().to_vec();
+            entry.extend_from_slice(separator);
+            entry
+        })
+        .collect::<Vec<_>>();
+
+    let internal = node(*children.last().unwrap(), &entries);
+    if internal.len() > PAGE_SIZE {
+        return Err(DsStoreError::TooManyRecords);
+    }
+    blocks.push(internal);
+
+    Ok(((blocks.len() - 1) as u32, 1, children.len() as u32 + 1))
+}
+
+fn binary_plist(dict: Dictionary) -> Result<Vec<u8>, DsStoreError> {
+    let mut data = vec![];
+    Value::Dictionary(dict).to_writer_binary(&mut data)?;
+
+    Ok(data)
+}
+
+/// Write a tagged extra field of an alias record.
+fn write_alias_extra(dest: &mut Vec<u8>, tag: i16, data: &[u8]) {
+    dest.extend_from_slice(&tag.to_be_bytes());
+    dest.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    dest.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        dest.push(0);
+    }
+}
+
+/// Encode a string as a 16-bit character count followed by UTF-16 code units.
+fn alias_unicode(s: &str) -> Vec<u8> {
+    let units = s.encode_utf16().collect::<Vec<_>>();
+
+    let mut data = (units.len() as u16).to_be_bytes().to_vec();
+    for unit in units {
+        data.extend_from_slice(&unit.to_be_bytes());
+    }
+
+    data
+}
+
+/// Write a fixed size Pascal string.
+fn write_pascal(dest: &mut Vec<u8>, s: &str, size: usize) -> Result<(), DsStoreError> {
+    if s.len() >= size {
+        return Err(DsStoreError::AliasNameTooLong(s.to_string()));
+    }
+
+    dest.push(s.len() as u8);
+    dest.extend_from_slice(s.as_bytes());
+    dest.resize(dest.len() + size - 1 - s.len(), 0);
+
+    Ok(())
+}
+
+/// Produce a version 2 alias record for a file on an HFS+ disk image volume.
+///
+/// `path` is the path of the file relative to the volume root. Since the volume
+/// doesn't exist yet, identifiers and dates are unset and Finder resolves the
+/// alias from the recorded volume name and paths.
+pub fn alias_record(volume_name: &str, path: &[&str]) -> Result<Vec<u8>, DsStoreError> {
+    let filename = path.last().copied().unwrap_or_default();
+    let parent = if path.len() > 1 {
+        path[path.len() - 2]
+    } else {
+        ""
+    };
+
+    let mut data = vec![];
+    // Application specific information.
+    data.extend_from_slice(&[0; 4]);
+    // Record size, filled in below.
+    data.extend_from_slice(&[0; 2]);
+    // Version.
+    data.extend_from_slice(&2u16.to_be_bytes());
+    // Kind: file.
+    data.extend_from_slice(&0u16.to_be_bytes());
+    write_pascal(&mut data, volume_name, 28)?;
+    // Volume creation date.
+    data.extend_from_slice(&0u32.to_be_bytes());
+    // Filesystem type.
+    data.extend_from_slice(b"H+");
+    // Disk type: ejectable.
+    data.extend_from_slice(&5u16.to_be_bytes());
+    // Parent directory ID.
+    data.extend_from_slice(&0u32.to_be_bytes());
+    write_pascal(&mut data, filename, 64)?;
+    // File ID, creation date, type, and creator.
+    data.extend_from_slice(&[0; 16]);
+    // Levels from and to the common ancestor of the alias and target.
+    data.extend_from_slice(&(-1i16).to_be_bytes());
+    data.extend_from_slice(&(-1i16).to_be_bytes());
+    // Volume attributes, filesystem ID, and reserved.
+    data.extend_from_slice(&[0; 16]);
+
+    write_alias_extra(&mut data, 0, parent.as_bytes());
+    write_alias_extra(
+        &mut data,
+        2,
+        std::iter::once(volume_name)
+            .chain(path.iter().copied())
+            .collect::<Vec<_>>()
+            .join(":")
+            .as_bytes(),
+    );
+    write_alias_extra(&mut data, 14, &alias_unicode(filename));
+    write_alias_extra(&mut data, 15, &alias_unicode(volume_name));
+    write_alias_extra(&mut data, 18, format!("/{}", path.join("/")).as_bytes());
+    write_alias_extra(
+        &mut data,
+        19,
+        format!("/Volumes/{}", volume_name).as_bytes(),
+    );
+    write_alias_extra(&mut data, -1, &[]);
+
+    let size = data.len() as u16;
+    data[4..6].copy_from_slice(&size.to_be_bytes());
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_u32(data: &[u8], offset: usize) -> u32 {
+        u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    /// Resolve the offset and size of a block from its number.
+    fn block(data: &[u8], number: usize) -> (usize, usize) {
+        let root = read_u32(data, 8) as usize + 4;
+        let address = read_u32(data, root + 8 + number * 4) as usize;
+
+        ((address & !0x1f) + 4, 1 << (address & 0x1f))
+    }
+
+    #[test]
+    fn test_write() -> Result<(), DsStoreError> {
+        let mut builder = DsStoreBuilder::default();
+        builder.set_icon_position("b.app", 100, 200);
+        builder.set_icon_position("Applications", 300, 200);
+        builder.set_window_bounds(10, 20, 640, 480)?;
+        builder.set_icon_view(128, 12, None)?;
+
+        let data = builder.to_bytes()?;
+        assert_eq!(&data[0..8], b"\x00\x00\x00\x01Bud1");
+        assert_eq!(read_u32(&data, 8), read_u32(&data, 16));
+        assert_eq!(read_u32(&data, 12), 0x800);
+
+        let root = read_u32(&data, 8) as usize + 4;
+        // 3 blocks: metadata, B-tree header, leaf.
+        assert_eq!(read_u32(&data, root), 3);
+        let directory = root + 8 + 256 * 4;
+        assert_eq!(read_u32(&data, directory), 1);
+        assert_eq!(&data[directory + 4..directory + 9], b"\x04DSDB");
+        assert_eq!(read_u32(&data, directory + 9), 1);
+
+        let (header, _) = block(&data, 1);
+        let leaf_number = read_u32(&data, header) as usize;
+        assert_eq!(read_u32(&data, header + 4), 0);
+        assert_eq!(read_u32(&data, header + 8), 6);
+        assert_eq!(read_u32(&data, header + 12), 1);
+
+        let (leaf, size) = block(&data, leaf_number);
+        assert_eq!(leaf % size, 4);
+        assert_eq!(read_u32(&data, leaf), 0);
+        assert_eq!(read_u32(&data, leaf + 4), 6);
+        // Records sort case-insensitively, with "." first.
+        assert_eq!(read_u32(&data, leaf + 8), 1);
+        assert_eq!(&data[leaf + 12..leaf + 14], b"\x00.");
+        assert_eq!(&data[leaf + 14..leaf + 18], b"bwsp");
+
+        let names = [".", ".", ".", ".", "Applications", "b.app"];
+        let mut offset = leaf + 8;
+        for name in names {
+            let len = read_u32(&data, offset) as usize;
+            let actual = String::from_utf16(
+                &data[offset + 4..offset + 4 + len * 2]
+                    .chunks(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect::<Vec<_>>(),
+            )
+            .unwrap();
+            assert_eq!(actual, name);
+
+            offset += 4 + len * 2 + 4;
+            let value_size = match &data[offset..offset + 4] {
+                b"blob" => 4 + read_u32(&data, offset + 4) as usize,
+                b"long" | b"type" => 4,
+                t => panic!("unexpected type {:?}", t),
+            };
+            offset += 4 + value_size;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_many_records() -> Result<(), DsStoreError> {
+        let mut builder = DsStoreBuilder::default();
+        for i in 0..500 {
+            builder.set_icon_position(format!("file{:03}", i), i, i);
+        }
+
+        let data = builder.to_bytes()?;
+        let (header, _) = block(&data, 1);
+        assert_eq!(read_u32(&data, header + 4), 1);
+        assert_eq!(read_u32(&data, header + 8), 500);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_alias_record() -> Result<(), DsStoreError> {
+        let alias = alias_record("My App", &[".background", "background.png"])?;
+
+        assert_eq!(
+            u16::from_be_bytes([alias[4], alias[5]]) as usize,
+            alias.len()
+        );
+        assert_eq!(&alias[6..8], &[0, 2]);
+        assert_eq!(&alias[10..17], b"\x06My App");
+        assert_eq!(&alias[42..44], b"H+");
+        assert_eq!(&alias[50..65], b"\x0ebackground.png");
+        assert_eq!(&alias[alias.len() - 4..], &[0xff, 0xff, 0, 0]);
+
+        let alias = String::from_utf8_lossy(&alias);
+        assert!(alias.contains("My App:.background:background.png"));
+        assert!(alias.contains("/.background/background.png"));
+        assert!(alias.contains("/Volumes/My App"));
+
+        assert!(alias_record(&"x".repeat(28), &["a"]).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,888 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! Typed representation of bundle `Info.plist` files.
+
+See <https://developer.apple.com/documentation/bundleresources/information_property_list>
+for the meaning of the keys.
+*/
+
+use {
+    plist::{Dictionary, Value},
+    std::{collections::BTreeMap, io::Write, path::Path},
+    thiserror::Error,
+};
+
+#[derive(Debug, Error)]
+pub enum InfoPlistError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("plist error: {0}")]
+    Plist(#[from] plist::Error),
+
+    #[error("{0} is set by a dedicated field and cannot be set as an extra key")]
+    TypedKey(String),
+
+    #[error("unknown {0}: {1}")]
+    UnknownName(&'static str, String),
+
+    #[error("invalid Info.plist:\n{}", format_problems(.0))]
+    Invalid(Vec<String>),
+}
+
+fn format_problems(problems: &[String]) -> String {
+    problems
+        .iter()
+        .map(|p| format!("  {}", p))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Keys populated from dedicated [InfoPlist] fields.
+const TYPED_KEYS: &[&str] = &[
+    "CFBundleDevelopmentRegion",
+    "CFBundleDisplayName",
+    "CFBundleDocumentTypes",
+    "CFBundleExecutable",
+    "CFBundleIconFile",
+    "CFBundleIdentifier",
+    "CFBundleInfoDictionaryVersion",
+    "CFBundleName",
+    "CFBundlePackageType",
+    "CFBundleShortVersionString",
+    "CFBundleSignature",
+    "CFBundleURLTypes",
+    "CFBundleVersion",
+    "LSApplicationCategoryType",
+    "LSMinimumSystemVersion",
+    "LSUIElement",
+    "NSHighResolutionCapable",
+    "NSHumanReadableCopyright",
+    "NSPrincipalClass",
+];
+
+/// Serialization format of a property list.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PlistFormat {
+    Xml,
+    Binary,
+}
+
+impl TryFrom<&str> for PlistFormat {
+    type Error = InfoPlistError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "xml" => Ok(Self::Xml),
+            "binary" => Ok(Self::Binary),
+            _ => Err(InfoPlistError::UnknownName("plist format", s.to_string())),
+        }
+    }
+}
+
+/// The role of an app with respect to a document or URL type.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BundleTypeRole {
+    Editor,
+    Viewer,
+    Shell,
+    None,
+}
+
+impl BundleTypeRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Editor => "Editor",
+            Self::Viewer => "Viewer",
+            Self::Shell => "Shell",
+            Self::None => "None",
+        }
+    }
+}
+
+impl TryFrom<&str> for BundleTypeRole {
+    type Error = InfoPlistError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s.to_ascii_lowercase().as_str() {
+            "editor" => Ok(Self::Editor),
+            "viewer" => Ok(Self::Viewer),
+            "shell" => Ok(Self::Shell),
+            "none" => Ok(Self::None),
+            _ => Err(InfoPlistError::UnknownName("role", s.to_string())),
+        }
+    }
+}
+
+/// How strongly an app claims a document type.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HandlerRank {
+    Owner,
+    Default,
+    Alternate,
+    None,
+}
+
+impl HandlerRank {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Owner => "Owner",
+            Self::Default => "Default",
+            Self::Alternate => "Alternate",
+            Self::None => "None",
+        }
+    }
+}
+
+impl TryFrom<&str> for HandlerRank {
+    type Error = InfoPlistError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s.to_ascii_lowercase().as_str() {
+            "owner" => Ok(Self::Owner),
+            "default" => Ok(Self::Default),
+            "alternate" => Ok(Self::Alternate),
+            "none" => Ok(Self::None),
+            _ => Err(InfoPlistError::UnknownName("handler rank", s.to_string())),
+        }
+    }
+}
+
+/// Protected resources whose access requires a usage description.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum UsageDescription {
+    AppleEvents,
+    Bluetooth,
+    Calendars,
+    Camera,
+    Contacts,
+    DesktopFolder,
+    DocumentsFolder,
+    DownloadsFolder,
+    LocalNetwork,
+    Location,
+    Microphone,
+    NetworkVolumes,
+    PhotoLibrary,
+    Reminders,
+    RemovableVolumes,
+    SpeechRecognition,
+    SystemAdministration,
+}
+
+impl UsageDescription {
+    /// All variants, with the names accepted by [UsageDescription::try_from].
+    pub const ALL: &'static [(&'static str, Self)] = &[
+        ("apple_events", Self::AppleEvents),
+        ("bluetooth", Self::Bluetooth),
+        ("calendars", Self::Calendars),
+        ("camera", Self::Camera),
+        ("contacts", Self::Contacts),
+        ("desktop_folder", Self::DesktopFolder),
+        ("documents_folder", Self::DocumentsFolder),
+        ("downloads_folder", Self::DownloadsFolder),
+        ("local_network", Self::LocalNetwork),
+        ("location", Self::Location),
+        ("microphone", Self::Microphone),
+        ("network_volumes", Self::NetworkVolumes),
+        ("photo_library", Self::PhotoLibrary),
+        ("reminders", Self::Reminders),
+        ("removable_volumes", Self::RemovableVolumes),
+        ("speech_recognition", Self::SpeechRecognition),
+        ("system_administration", Self::SystemAdministration),
+    ];
+
+    /// The `Info.plist` key holding this usage description.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::AppleEvents => "NSAppleEventsUsageDescription",
+            Self::Bluetooth => "NSBluetoothAlwaysUsageDescription",
+            Self::Calendars => "NSCalendarsUsageDescription",
+            Self::Camera => "NSCameraUsageDescription",
+            Self::Contacts => "NSContactsUsageDescription",
+            Self::DesktopFolder => "NSDesktopFolderUsageDescription",
+            Self::DocumentsFolder => "NSDocumentsFolderUsageDescription",
+            Self::DownloadsFolder => "NSDownloadsFolderUsageDescription",
+            Self::LocalNetwork => "NSLocalNetworkUsageDescription",
+            Self::Location => "NSLocationUsageDescription",
+            Self::Microphone => "NSMicrophoneUsageDescription",
+            Self::NetworkVolumes => "NSNetworkVolumesUsageDescription",
+            Self::PhotoLibrary => "NSPhotoLibraryUsageDescription",
+            Self::Reminders => "NSRemindersUsageDescription",
+            Self::RemovableVolumes => "NSRemovableVolumesUsageDescription",
+            Self::SpeechRecognition => "NSSpeechRecognitionUsageDescription",
+            Self::SystemAdministration => "NSSystemAdministrationUsageDescription",
+        }
+    }
+}
+
+impl TryFrom<&str> for UsageDescription {
+    type Error = InfoPlistError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        Self::ALL
+            .iter()
+            .find(|(name, _)| *name == s)
+            .map(|(_, v)| *v)
+            .ok_or_else(|| InfoPlistError::UnknownName("usage description", s.to_string()))
+    }
+}
+
+/// A document type an app can open, as defined in `CFBundleDocumentTypes`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DocumentType {
+    /// Name of the document type (`CFBundleTypeName`).
+    pub name: String,
+    /// The app's role with respect to the type (`CFBundleTypeRole`).
+    pub role: BundleTypeRole,
+    /// How strongly the app claims the type (`LSHandlerRank`).
+    pub handler_rank: Option<HandlerRank>,
+    /// Uniform type identifiers of the type (`LSItemContentTypes`).
+    pub content_types: Vec<String>,
+    /// Filename extensions of the type (`CFBundleTypeExtensions`).
+    pub extensions: Vec<String>,
+    /// Icon file for documents of the type (`CFBundleTypeIconFile`).
+    pub icon_file: Option<String>,
+}
+
+impl DocumentType {
+    fn to_dictionary(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+
+        dict.insert("CFBundleTypeName".into(), self.name.clone().into());
+        dict.insert("CFBundleTypeRole".into(), self.role.as_str().into());
+        if let Some(rank) = &self.handler_rank {
+            dict.insert("LSHandlerRank".into(), rank.as_str().into());
+        }
+        if !self.content_types.is_empty() {
+            dict.insert(
+                "LSItemContentTypes".into(),
+                string_array(&self.content_types),
+            );
+        }
+        if !self.extensions.is_empty() {
+            dict.insert(
+                "CFBundleTypeExtensions".into(),
+                string_array(&self.extensions),
+            );
+        }
+        if let Some(icon) = &self.icon_file {
+            dict.insert("CFBundleTypeIconFile".into(), icon.clone().into());
+        }
+
+        dict
+    }
+}
+
+/// URL schemes an app handles, as defined in `CFBundleURLTypes`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UrlType {
+    /// Abstract name of the URL type (`CFBundleURLName`).
+    pub name: String,
+    /// URL schemes handled (`CFBundleURLSchemes`).
+    pub schemes: Vec<String>,
+    /// The app's role with respect to the type (`CFBundleTypeRole`).
+    pub role: Option<BundleTypeRole>,
+}
+
+impl UrlType {
+    fn to_dictionary(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+
+        dict.insert("CFBundleURLName".into(), self.name.clone().into());
+        dict.insert("CFBundleURLSchemes".into(), string_array(&self.schemes));
+        if let Some(role) = &self.role {
+            dict.insert("CFBundleTypeRole".into(), role.as_str().into());
+        }
+
+        dict
+    }
+}
+
+fn string_array(values: &[String]) -> Value {
+    Value::Array(values.iter().map(|v| Value::from(v.as_str())).collect())
+}
+
+/// Whether a string is 1 to `max` period-delimited integers.
+fn is_dotted_integers(s: &str, max: usize) -> bool {
+    let parts = s.split('.').collect::<Vec<_>>();
+
+    parts.len() <= max
+        && parts
+            .iter()
+            .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Whether a string is a valid bundle identifier.
+///
+/// Identifiers consist of alphanumerics, hyphens, and periods, with periods
+/// delimiting non-empty components.
+pub fn is_valid_bundle_identifier(s: &str) -> bool {
+    !s.is_empty()
+        && s.split('.').all(|component| {
+            !component.is_empty()
+                && component
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+/// Whether a string is a valid URL scheme, per RFC 3986.
+fn is_valid_url_scheme(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic())
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+}
+
+/// The content of an `Info.plist` file in a bundle.
+///
+/// Commonly used keys have dedicated fields. Other keys can be set via
+/// [InfoPlist::set_extra_key].
+#[derive(Clone, Debug, PartialEq)]
+pub struct InfoPlist {
+    /// Unique identifier of the bundle in reverse-DNS form (`CFBundleIdentifier`).
+    pub bundle_identifier: String,
+    /// Short name of the bundle (`CFBundleName`).
+    pub bundle_name: String,
+    /// Name of the main executable in `Contents/MacOS` (`CFBundleExecutable`).
+    pub executable: String,
+    /// Build version (`CFBundleVersion`).
+    pub bundle_version: String,
+    /// User visible release version (`CFBundleShortVersionString`).
+    pub short_version: Option<String>,
+    /// User visible name (`CFBundleDisplayName`).
+    pub display_name: Option<String>,
+    /// Type of the bundle (`CFBundlePackageType`). `APPL` for applications.
+    pub package_type: String,
+    /// Four character creator code (`CFBundleSignature`).
+    pub signature: Option<String>,
+    /// Icon file in `Contents/Resources` (`CFBundleIconFile`).
+    pub icon_file: Option<String>,
+    /// Default language and region (`CFBundleDevelopmentRegion`).
+    pub development_region: Option<String>,
+    /// Minimum macOS version the bundle runs on (`LSMinimumSystemVersion`).
+    pub minimum_system_version: Option<String>,
+    /// App Store category (`LSApplicationCategoryType`).
+    pub application_category: Option<String>,
+    /// Copyright notice (`NSHumanReadableCopyright`).
+    pub copyright: Option<String>,
+    /// Whether the app supports high resolution displays (`NSHighResolutionCapable`).
+    pub high_resolution_capable: Option<bool>,
+    /// Whether the app runs without a Dock icon or menu bar (`LSUIElement`).
+    pub ui_element: Option<bool>,
+    /// Name of the principal class (`NSPrincipalClass`).
+    pub principal_class: Option<String>,
+    /// Document types the app opens (`CFBundleDocumentTypes`).
+    pub document_types: Vec<DocumentType>,
+    /// URL schemes the app handles (`CFBundleURLTypes`).
+    pub url_types: Vec<UrlType>,
+    /// Explanations of why the app accesses protected resources.
+    pub usage_descriptions: BTreeMap<UsageDescription, String>,
+    /// Keys without a dedicated field.
+    extra: Dictionary,
+}
+
+impl InfoPlist {
+    /// Construct an instance for an application with required keys.
+    pub fn new(
+        bundle_identifier: impl ToString,
+        bundle_name: impl ToString,
+        executable: impl ToString,
+        bundle_version: impl ToString,
+    ) -> Self {
+        Self {
+            bundle_identifier: bundle_identifier.to_string(),
+            bundle_name: bundle_name.to_string(),
+            executable: executable.to_string(),
+            bundle_version: bundle_version.to_string(),
+            short_version: None,
+            display_name: None,
+            package_type: "APPL".to_string(),
+            signature: None,
+            icon_file: None,
+            development_region: None,
+            minimum_system_version: None,
+            application_category: None,
+            copyright: None,
+            high_resolution_capable: None,
+            ui_element: None,
+            principal_class: None,
+            document_types: vec![],
+            url_types: vec![],
+            usage_descriptions: BTreeMap::new(),
+            extra: Dictionary::new(),
+        }
+    }
+
+    /// Register a document type the app opens.
+    pub fn add_document_type(&mut self, document_type: DocumentType) {
+        self.document_types.push(document_type);
+    }
+
+    /// Register URL schemes the app handles.
+    pub fn add_url_type(&mut self, url_type: UrlType) {
+        self.url_types.push(url_type);
+    }
+
+    /// Set the description shown when the app requests access to a resource.
+    pub fn set_usage_description(&mut self, resource: UsageDescription, text: impl ToString) {
+        self.usage_descriptions.insert(resource, text.to_string());
+    }
+
+    /// Set a key not having a dedicated field.
+    ///
+    /// Errors if the key is populated from a dedicated field.
+    pub fn set_extra_key(
+        &mut self,
+        key: impl ToString,
+        value: impl Into<Value>,
+    ) -> Result<(), InfoPlistError> {
+        let key = key.to_string();
+
+        if TYPED_KEYS.contains(&key.as_str())
+            || UsageDescription::ALL
+                .iter()
+                .any(|(_, usage)| usage.key() == key)
+        {
+            return Err(InfoPlistError::TypedKey(key));
+        }
+
+        self.extra.insert(key, value.into());
+
+        Ok(())
+    }
+
+    /// Obtain keys not having a dedicated field.
+    pub fn extra_keys(&self) -> &Dictionary {
+        &self.extra
+    }
+
+    /// Validate the content of this instance.
+    ///
+    /// All problems are reported in the returned error.
+    pub fn validate(&self) -> Result<(), InfoPlistError> {
+        let mut problems = vec![];
+
+        if !is_valid_bundle_identifier(&self.bundle_identifier) {
+            problems.push(format!(
+                "CFBundleIdentifier {} must consist of alphanumerics, hyphens, and periods",
+                self.bundle_identifier
+            ));
+        }
+
+        if self.bundle_name.is_empty() {
+            problems.push("CFBundleName must not be empty".to_string());
+        } else if self.bundle_name.chars().count() > 15 {
+            problems.push(format!(
+                "CFBundleName {} must be at most 15 characters",
+                self.bundle_name
+            ));
+        }
+
+        if self.executable.is_empty() || self.executable.contains('/') {
+            problems.push(format!(
+                "CFBundleExecutable {} must be a file name",
+                self.executable
+            ));
+        }
+
+        if !is_dotted_integers(&self.bundle_version, 3) {
+            problems.push(format!(
+                "CFBundleVersion {} must be 1 to 3 period-delimited integers",
+                self.bundle_version
+            ));
+        }
+
+        if let Some(version) = &self.short_version {
+            if !is_dotted_integers(version, 3) {
+                problems.push(format!(
+                    "CFBundleShortVersionString {} must be 1 to 3 period-delimited integers",
+                    version
+                ));
+            }
+        }
+
+        if self.package_type.len() != 4 {
+            problems.push(format!(
+                "CFBundlePackageType {} must be 4 characters",
+                self.package_type
+            ));
+        }
+
+        if let Some(signature) = &self.signature {
+            if signature.len() != 4 || !signature.is_ascii() {
+                problems.push(format!(
+                    "CFBundleSignature {} must be 4 ASCII characters",
+                    signature
+                ));
+            }
+        }
+
+        if let Some(version) = &self.minimum_system_version {
+            if !version.contains('.') || !is_dotted_integers(version, 3) {
+                problems.push(format!(
+                    "LSMinimumSystemVersion {} must be a version like 10.13 or 11.0",
+                    version
+                ));
+            }
+        }
+
+        if let Some(category) = &self.application_category {
+            if !category.starts_with("public.app-category.") {
+                problems.push(format!(
+                    "LSApplicationCategoryType {} must begin with public.app-category.",
+                    category
+                ));
+            }
+        }
+
+        for document_type in &self.document_types {
+            if document_type.name.is_empty() {
+                problems.push("document types must have a name".to_string());
+            }
+
+            if document_type.content_types.is_empty() && document_type.extensions.is_empty() {
+                problems.push(format!(
+                    "document type {} must define content types or extensions",
+                    document_type.name
+                ));
+            }
+
+            for extension in &document_type.extensions {
+                if extension.starts_with('.') {
+                    problems.push(format!(
+                        "document type {} extension {} must not begin with a period",
+                        document_type.name, extension
+                    ));
+                }
+            }
+        }
+
+        for url_type in &self.url_types {
+            if url_type.schemes.is_empty() {
+                problems.push(format!(
+                    "URL type {} must define at least one scheme",
+                    url_type.name
+                ));
+            }
+
+            for scheme in &url_type.schemes {
+                if !is_valid_url_scheme(scheme) {
+                    problems.push(format!(
+                        "URL type {} has invalid scheme {}",
+                        url_type.name, scheme
+                    ));
+                }
+            }
+        }
+
+        for (resource, text) in &self.usage_descriptions {
+            if text.trim().is_empty() {
+                problems.push(format!("{} must not be empty", resource.key()));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(InfoPlistError::Invalid(problems))
+        }
+    }
+
+    /// Convert this instance to a plist dictionary.
+    pub fn to_dictionary(&self) -> Dictionary {
+        let mut dict = self.extra.clone();
+
+        dict.insert("CFBundleInfoDictionaryVersion".into(), "6.0".into());
+        dict.insert(
+            "CFBundleIdentifier".into(),
+            self.bundle_identifier.clone().into(),
+        );
+        dict.insert("CFBundleName".into(), self.bundle_name.clone().into());
+        dict.insert("CFBundleExecutable".into(), self.executable.clone().into());
+        dict.insert("CFBundleVersion".into(), self.bundle_version.clone().into());
+        dict.insert(
+            "CFBundlePackageType".into(),
+            self.package_type.clone().into(),
+        );
+
+        for (key, value) in [
+            ("CFBundleShortVersionString", &self.short_version),
+            ("CFBundleDisplayName", &self.display_name),
+            ("CFBundleSignature", &self.signature),
+            ("CFBundleIconFile", &self.icon_file),
+            ("CFBundleDevelopmentRegion", &self.development_region),
+            ("LSMinimumSystemVersion", &self.minimum_system_version),
+            ("LSApplicationCategoryType", &self.application_category),
+            ("NSHumanReadableCopyright", &self.copyright),
+            ("NSPrincipalClass", &self.principal_class),
+        ] {
+            if let Some(value) = value {
+                dict.insert(key.into(), value.clone().into());
+            }
+        }
+
+        for (key, value) in [
+            ("NSHighResolutionCapable", self.high_resolution_capable),
+            ("LSUIElement", self.ui_element),
+        ] {
+            if let Some(value) = value {
+                dict.insert(key.into(), value.into());
+            }
+        }
+
+        if !self.document_types.is_empty() {
+            dict.insert(
+                "CFBundleDocumentTypes".into(),
+                Value::Array(
+                    self.document_types
+                        .iter()
+                        .map(|t| Value::Dictionary(t.to_dictionary()))
+                        .collect(),
+                ),
+            );
+        }
+
+        if !self.url_types.is_empty() {
+            dict.insert(
+                "CFBundleURLTypes".into(),
+                Value::Array(
+                    self.url_types
+                        .iter()
+                        .map(|t| Value::Dictionary(t.to_dictionary()))
+                        .collect(),
+                ),
+            );
+        }
+
+        for (resource, text) in &self.usage_descriptions {
+            dict.insert(resource.key().into(), text.clone().into());
+        }
+
+        dict.sort_keys();
+
+        dict
+    }
+
+    /// Write this instance to a writer in the given format.
+    ///
+    /// The instance is validated first.
+    pub fn write(&self, writer: impl Write, format: PlistFormat) -> Result<(), InfoPlistError> {
+        self.validate()?;
+
+        let value = Value::Dictionary(self.to_dictionary());
+
+        match format {
+            PlistFormat::Xml => value.to_writer_xml(writer)?,
+            PlistFormat::Binary => value.to_writer_binary(writer)?,
+        }
+
+        Ok(())
+    }
+
+    /// Serialize this instance to XML.
+    pub fn to_xml(&self) -> Result<Vec<u8>, InfoPlistError> {
+        let mut data = vec![];
+        self.write(&mut data, PlistFormat::Xml)?;
+
+        Ok(data)
+    }
+
+    /// Serialize this instance to the binary plist format.
+    pub fn to_binary(&self) -> Result<Vec<u8>, InfoPlistError> {
+        let mut data = vec![];
+        self.write(&mut data, PlistFormat::Binary)?;
+
+        Ok(data)
+    }
+
+    /// Write this instance to a file in the given format.
+    pub fn write_file(
+        &self,
+        path: impl AsRef<Path>,
+        format: PlistFormat,
+    ) -> Result<(), InfoPlistError> {
+        let fh = std::fs::File::create(path.as_ref())?;
+
+        self.write(std::io::BufWriter::new(fh), format)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plist() -> InfoPlist {
+        let mut plist = InfoPlist::new("com.example.myapp", "My App", "myapp", "1.2.3");
+        plist.short_version = Some("1.2".into());
+        plist.minimum_system_version = Some("10.13".into());
+        plist.high_resolution_capable = Some(true);
+
+        plist
+    }
+
+    #[test]
+    fn test_dictionary() -> Result<(), InfoPlistError> {
+        let mut plist = plist();
+        plist.add_document_type(DocumentType {
+            name: "My Document".into(),
+            role: BundleTypeRole::Editor,
+            handler_rank: Some(HandlerRank::Owner),
+            content_types: vec!["com.example.myapp.document".into()],
+            extensions: vec!["mydoc".into()],
+            icon_file: None,
+        });
+        plist.add_url_type(UrlType {
+            name: "com.example.myapp".into(),
+            schemes: vec!["myapp".into()],
+            role: None,
+        });
+        plist.set_usage_description(UsageDescription::Camera, "Takes photos.");
+        plist.set_extra_key("LSBackgroundOnly", false)?;
+
+        let dict = plist.to_dictionary();
+        let keys = dict.keys().map(|k| k.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            vec![
+                "CFBundleDocumentTypes",
+                "CFBundleExecutable",
+                "CFBundleIdentifier",
+                "CFBundleInfoDictionaryVersion",
+                "CFBundleName",
+                "CFBundlePackageType",
+                "CFBundleShortVersionString",
+                "CFBundleURLTypes",
+                "CFBundleVersion",
+                "LSBackgroundOnly",
+                "LSMinimumSystemVersion",
+                "NSCameraUsageDescription",
+                "NSHighResolutionCapable",
+            ]
+        );
+        assert_eq!(
+            dict.get("CFBundlePackageType").and_then(|v| v.as_string()),
+            Some("APPL")
+        );
+
+        let document = dict
+            .get("CFBundleDocumentTypes")
+            .and_then(|v| v.as_array())
+            .unwrap()[0]
+            .as_dictionary()
+            .unwrap();
+        assert_eq!(
+            document.get("CFBundleTypeRole").and_then(|v| v.as_string()),
+            Some("Editor")
+        );
+        assert_eq!(
+            document.get("LSHandlerRank").and_then(|v| v.as_string()),
+            Some("Owner")
+        );
+
+        let url = dict
+            .get("CFBundleURLTypes")
+            .and_then(|v| v.as_array())
+            .unwrap()[0]
+            .as_dictionary()
+            .unwrap();
+        assert_eq!(
+            url.get("CFBundleURLSchemes"),
+            Some(&Value::Array(vec!["myapp".into()]))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize() -> Result<(), InfoPlistError> {
+        let plist = plist();
+
+        let xml = plist.to_xml()?;
+        assert!(xml.starts_with(b"<?xml"));
+        let parsed = Value::from_reader_xml(xml.as_slice())?;
+        assert_eq!(parsed, Value::Dictionary(plist.to_dictionary()));
+
+        let binary = plist.to_binary()?;
+        assert!(binary.starts_with(b"bplist00"));
+        let parsed = Value::from_reader(std::io::Cursor::new(binary))?;
+        assert_eq!(parsed, Value::Dictionary(plist.to_dictionary()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_typed_key() {
+        let mut plist = plist();
+
+        assert!(matches!(
+            plist.set_extra_key("CFBundleIdentifier", "com.example.other"),
+            Err(InfoPlistError::TypedKey(_))
+        ));
+        assert!(matches!(
+            plist.set_extra_key("NSCameraUsageDescription", "text"),
+            Err(InfoPlistError::TypedKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut plist = InfoPlist::new(
+            "com.example/myapp",
+            "My Application Name",
+            "bin/myapp",
+            "1.0b1",
+        );
+        plist.short_version = Some("1.2.3.4".into());
+        plist.signature = Some("abc".into());
+        plist.minimum_system_version = Some("11".into());
+        plist.application_category = Some("games".into());
+        plist.add_document_type(DocumentType {
+            name: "Doc".into(),
+            role: BundleTypeRole::Viewer,
+            handler_rank: None,
+            content_types: vec![],
+            extensions: vec![],
+            icon_file: None,
+        });
+        plist.add_url_type(UrlType {
+            name: "url".into(),
+            schemes: vec!["1http".into()],
+            role: None,
+        });
+        plist.set_usage_description(UsageDescription::Microphone, " ");
+
+        let err = plist.to_xml().unwrap_err().to_string();
+        for message in [
+            "CFBundleIdentifier com.example/myapp",
+            "CFBundleName My Application Name must be at most 15 characters",
+            "CFBundleExecutable bin/myapp must be a file name",
+            "CFBundleVersion 1.0b1",
+            "CFBundleShortVersionString 1.2.3.4",
+            "CFBundleSignature abc",
+            "LSMinimumSystemVersion 11",
+            "LSApplicationCategoryType games",
+            "document type Doc must define content types or extensions",
+            "URL type url has invalid scheme 1http",
+            "NSMicrophoneUsageDescription must not be empty",
+        ] {
+            assert!(err.contains(message), "{} not in {}", message, err);
+        }
+    }
+
+    #[test]
+    fn test_names() -> Result<(), InfoPlistError> {
+        assert_eq!(PlistFormat::try_from("binary")?, PlistFormat::Binary);
+        assert_eq!(BundleTypeRole::try_from("Editor")?, BundleTypeRole::Editor);
+        assert_eq!(HandlerRank::try_from("alternate")?, HandlerRank::Alternate);
+        assert_eq!(
+            UsageDescription::try_from("photo_library")?,
+            UsageDescription::PhotoLibrary
+        );
+        assert!(UsageDescription::try_from("NSCameraUsageDescription").is_err());
+
+        Ok(())
+    }
+}
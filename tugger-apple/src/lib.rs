@@ -2,5 +2,13 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+mod dmg;
+pub use dmg::*;
+mod ds_store;
+pub use ds_store::*;
+mod info_plist;
+pub use info_plist::*;
 mod macho;
 pub use macho::*;
+mod udif;
+pub use udif::*;
@@ -0,0 +1,445 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! Manipulation of UDIF disk images.
+
+UDIF images (`.dmg` files) end with a 512 byte trailer, the `koly` block,
+which locates an XML property list describing the image. Besides the
+partition table, this property list holds classic Mac OS resources. The
+license agreement displayed when an image is attached is defined by
+`LPic`, `STR#`, and `TEXT` resources.
+*/
+
+use {
+    plist::{Dictionary, Value},
+    thiserror::Error,
+};
+
+#[derive(Debug, Error)]
+pub enum UdifError {
+    #[error("plist error: {0}")]
+    Plist(#[from] plist::Error),
+
+    #[error("not a UDIF image: {0}")]
+    BadImage(&'static str),
+
+    #[error("unknown license language: {0}")]
+    UnknownLanguage(String),
+
+    #[error("character {0:?} cannot be encoded as MacRoman")]
+    Encoding(char),
+
+    #[error("string too long for a resource: {0}")]
+    StringTooLong(String),
+
+    #[error("at most one license per language can be defined: {0}")]
+    DuplicateLanguage(&'static str),
+}
+
+/// Magic of the UDIF trailer.
+const KOLY_MAGIC: &[u8; 4] = b"koly";
+
+/// Size of the UDIF trailer.
+const KOLY_SIZE: usize = 512;
+
+/// Offset of the property list offset in the UDIF trailer.
+const KOLY_XML_OFFSET: usize = 216;
+
+/// Offset of the property list length in the UDIF trailer.
+const KOLY_XML_LENGTH: usize = 224;
+
+/// Resource ID of the `LPic` resource. `STR#` and `TEXT` resources follow.
+const LPIC_ID: u16 = 5000;
+
+/// Characters of the MacRoman encoding from 0x80 to 0xff.
+const MAC_ROMAN_HIGH: [char; 128] = [
+    'Ä', 'Å', 'Ç', 'É', 'Ñ', 'Ö', 'Ü', 'á', 'à', 'â', 'ä', 'ã', 'å', 'ç', 'é', 'è', //
+    'ê', 'ë', 'í', 'ì', 'î', 'ï', 'ñ', 'ó', 'ò', 'ô', 'ö', 'õ', 'ú', 'ù', 'û', 'ü', //
+    '†', '°', '¢', '£', '§', '•', '¶', 'ß', '®', '©', '™', '´', '¨', '≠', 'Æ', 'Ø', //
+    '∞', '±', '≤', '≥', '¥', 'µ', '∂', '∑', '∏', 'π', '∫', 'ª', 'º', 'Ω', 'æ', 'ø', //
+    '¿', '¡', '¬', '√', 'ƒ', '≈', '∆', '«', '»', '…', '\u{a0}', 'À', 'Ã', 'Õ', 'Œ', 'œ', //
+    '–', '—', '“', '”', '‘', '’', '÷', '◊', 'ÿ', 'Ÿ', '⁄', '€', '‹', '›', 'ﬁ', 'ﬂ', //
+    '‡', '·', '‚', '„', '‰', 'Â', 'Ê', 'Á', 'Ë', 'È', 'Í', 'Î', 'Ï', 'Ì', 'Ó', 'Ô', //
+    '\u{f8ff}', 'Ò', 'Ú', 'Û', 'Ù', 'ı', 'ˆ', '˜', '¯', '˘', '˙', '˚', '¸', '˝', '˛', 'ˇ',
+];
+
+/// Encode a string as MacRoman.
+///
+/// Line endings are converted to carriage returns.
+pub fn encode_mac_roman(s: &str) -> Result<Vec<u8>, UdifError> {
+    s.replace("\r\n", "\r")
+        .replace('\n', "\r")
+        .chars()
+        .map(|c| {
+            if c.is_ascii() {
+                Ok(c as u8)
+            } else {
+                MAC_ROMAN_HIGH
+                    .iter()
+                    .position(|x| *x == c)
+                    .map(|i| 0x80 + i as u8)
+                    .ok_or(UdifError::Encoding(c))
+            }
+        })
+        .collect()
+}
+
+/// Languages of license agreements.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LicenseLanguage {
+    Danish,
+    Dutch,
+    English,
+    Finnish,
+    French,
+    German,
+    Italian,
+    Norwegian,
+    Portuguese,
+    Spanish,
+    Swedish,
+}
+
+impl LicenseLanguage {
+    /// All languages.
+    pub const ALL: &'static [Self] = &[
+        Self::Danish,
+        Self::Dutch,
+        Self::English,
+        Self::Finnish,
+        Self::French,
+        Self::German,
+        Self::Italian,
+        Self::Norwegian,
+        Self::Portuguese,
+        Self::Spanish,
+        Self::Swedish,
+    ];
+
+    /// The name of the language.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Danish => "Danish",
+            Self::Dutch => "Dutch",
+            Self::English => "English",
+            Self::Finnish => "Finnish",
+            Self::French => "French",
+            Self::German => "German",
+            Self::Italian => "Italian",
+            Self::Norwegian => "Norwegian",
+            Self::Portuguese => "Portuguese",
+            Self::Spanish => "Spanish",
+            Self::Swedish => "Swedish",
+        }
+    }
+
+    /// The Mac OS region code of the language.
+    pub fn region_code(&self) -> u16 {
+        match self {
+            Self::English => 0,
+            Self::French => 1,
+            Self::German => 3,
+            Self::Italian => 4,
+            Self::Dutch => 5,
+            Self::Swedish => 7,
+            Self::Spanish => 8,
+            Self::Danish => 9,
+            Self::Portuguese => 10,
+            Self::Norwegian => 12,
+            Self::Finnish => 17,
+        }
+    }
+}
+
+impl TryFrom<&str> for LicenseLanguage {
+    type Error = UdifError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        Self::ALL
+            .iter()
+            .find(|l| l.as_str() == s)
+            .copied()
+            .ok_or_else(|| UdifError::UnknownLanguage(s.to_string()))
+    }
+}
+
+/// A license agreement displayed when a disk image is attached.
+#[derive(Clone, Debug)]
+pub struct LicenseAgreement {
+    /// The language of the license.
+    pub language: LicenseLanguage,
+    /// The text of the license.
+    pub text: String,
+    /// Label of the button accepting the license.
+    pub agree: String,
+    /// Label of the button declining the license.
+    pub disagree: String,
+    /// Label of the button printing the license.
+    pub print: String,
+    /// Label of the button saving the license.
+    pub save: String,
+    /// Message explaining how to accept the license.
+    pub message: String,
+}
+
+impl LicenseAgreement {
+    /// Construct a new instance with English button labels.
+    pub fn new(language: LicenseLanguage, text: impl ToString) -> Self {
+        Self {
+            language,
+            text: text.to_string(),
+            agree: "Agree".to_string(),
+            disagree: "Disagree".to_string(),
+            print: "Print".to_string(),
+            save: "Save...".to_string(),
+            message: "If you agree with the terms of this license, press \"Agree\" to \
+                install the software. If you do not agree, press \"Disagree\"."
+                .to_string(),
+        }
+    }
+
+    /// Obtain the content of the `STR#` resource holding the localized strings.
+    fn strings_resource(&self) -> Result<Vec<u8>, UdifError> {
+        let strings = [
+            self.language.as_str(),
+            self.agree.as_str(),
+            self.disagree.as_str(),
+            self.print.as_str(),
+            self.save.as_str(),
+            self.message.as_str(),
+        ];
+
+        let mut data = (strings.len() as u16).to_be_bytes().to_vec();
+        for s in strings {
+            let encoded = encode_mac_roman(s)?;
+            if encoded.len() > 255 {
+                return Err(UdifError::StringTooLong(s.to_string()));
+            }
+            data.push(encoded.len() as u8);
+            data.extend(encoded);
+        }
+
+        Ok(data)
+    }
+}
+
+fn resource(id: u16, data: Vec<u8>) -> Value {
+    let mut dict = Dictionary::new();
+    dict.insert("Attributes".into(), "0x0000".into());
+    dict.insert("Data".into(), Value::Data(data));
+    dict.insert("ID".into(), id.to_string().into());
+    dict.insert("Name".into(), "".into());
+
+    Value::Dictionary(dict)
+}
+
+/// Obtain the license resources, keyed by resource type.
+fn license_resources(
+    licenses: &[LicenseAgreement],
+) -> Result<Vec<(&'static str, Value)>, UdifError> {
+    let mut lpic = vec![];
+    lpic.extend_from_slice(&licenses[0].language.region_code().to_be_bytes());
+    lpic.extend_from_slice(&(licenses.len() as u16).to_be_bytes());
+
+    let mut strings = vec![];
+    let mut texts = vec![];
+
+    for (i, license) in licenses.iter().enumerate() {
+        if licenses[..i].iter().any(|l| l.language == license.language) {
+            return Err(UdifError::DuplicateLanguage(license.language.as_str()));
+        }
+
+        let id = LPIC_ID + 2 + i as u16;
+
+        lpic.extend_from_slice(&license.language.region_code().to_be_bytes());
+        lpic.extend_from_slice(&(id - LPIC_ID).to_be_bytes());
+        // Whether the strings use a multibyte encoding.
+        lpic.extend_from_slice(&0u16.to_be_bytes());
+
+        strings.push(resource(id, license.strings_resource()?));
+        texts.push(resource(id, encode_mac_roman(&license.text)?));
+    }
+
+    Ok(vec![
+        ("LPic", Value::Array(vec![resource(LPIC_ID, lpic)])),
+        ("STR#", Value::Array(strings)),
+        ("TEXT", Value::Array(texts)),
+    ])
+}
+
+/// Add license agreements to a UDIF image.
+///
+/// `data` is the content of the image. Returns the content of the modified image.
+///
+/// The property list must immediately precede the trailer, as is the case for
+/// images produced by `hdiutil`. Existing license resources are replaced.
+pub fn add_udif_licenses(data: &[u8], licenses: &[LicenseAgreement]) -> Result<Vec<u8>, UdifError> {
+    if licenses.is_empty() {
+        return Ok(data.to_vec());
+    }
+
+    if data.len() < KOLY_SIZE {
+        return Err(UdifError::BadImage("file too small"));
+    }
+
+    let koly_offset = data.len() - KOLY_SIZE;
+    let mut koly = data[koly_offset..].to_vec();
+    if &koly[0..4] != KOLY_MAGIC {
+        return Err(UdifError::BadImage("koly trailer not found"));
+    }
+
+    let read_u64 =
+        |offset: usize| u64::from_be_bytes(koly[offset..offset + 8].try_into().unwrap()) as usize;
+    let xml_offset = read_u64(KOLY_XML_OFFSET);
+    let xml_length = read_u64(KOLY_XML_LENGTH);
+
+    if xml_offset.checked_add(xml_length) != Some(koly_offset) {
+        return Err(UdifError::BadImage(
+            "property list does not precede koly trailer",
+        ));
+    }
+
+    let mut plist = Value::from_reader_xml(&data[xml_offset..koly_offset])?;
+    let root = plist
+        .as_dictionary_mut()
+        .ok_or(UdifError::BadImage("property list is not a dictionary"))?;
+
+    if !matches!(root.get("resource-fork"), Some(Value::Dictionary(_))) {
+        root.insert("resource-fork".into(), Value::Dictionary(Dictionary::new()));
+    }
+    let resources = root
+        .get_mut("resource-fork")
+        .and_then(|v| v.as_dictionary_mut())
+        .expect("resource-fork dictionary defined above");
+
+    for (key, value) in license_resources(licenses)? {
+        resources.insert(key.into(), value);
+    }
+
+    let mut xml = vec![];
+    plist.to_writer_xml(&mut xml)?;
+
+    koly[KOLY_XML_LENGTH..KOLY_XML_LENGTH + 8].copy_from_slice(&(xml.len() as u64).to_be_bytes());
+
+    let mut res = data[..xml_offset].to_vec();
+    res.extend(xml);
+    res.extend(koly);
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Produce an image with a trailer and a minimal property list.
+    fn image() -> Vec<u8> {
+        let mut dict = Dictionary::new();
+        dict.insert("resource-fork".into(), Value::Dictionary(Dictionary::new()));
+        let mut xml = vec![];
+        Value::Dictionary(dict).to_writer_xml(&mut xml).unwrap();
+
+        let mut data = vec![0x42; 1024];
+        let xml_offset = data.len() as u64;
+        data.extend_from_slice(&xml);
+
+        let mut koly = vec![0; KOLY_SIZE];
+        koly[0..4].copy_from_slice(KOLY_MAGIC);
+        koly[KOLY_XML_OFFSET..KOLY_XML_OFFSET + 8].copy_from_slice(&xml_offset.to_be_bytes());
+        koly[KOLY_XML_LENGTH..KOLY_XML_LENGTH + 8]
+            .copy_from_slice(&(xml.len() as u64).to_be_bytes());
+        data.extend(koly);
+
+        data
+    }
+
+    #[test]
+    fn test_encode_mac_roman() -> Result<(), UdifError> {
+        assert_eq!(encode_mac_roman("a\nb\r\nc")?, b"a\rb\rc");
+        assert_eq!(encode_mac_roman("Ä©€")?, vec![0x80, 0xa9, 0xdb]);
+        assert!(encode_mac_roman("\u{4e2d}").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_language() -> Result<(), UdifError> {
+        assert_eq!(
+            LicenseLanguage::try_from("German")?,
+            LicenseLanguage::German
+        );
+        assert_eq!(LicenseLanguage::German.region_code(), 3);
+        assert!(LicenseLanguage::try_from("Klingon").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_licenses() -> Result<(), UdifError> {
+        let source = image();
+        let data = add_udif_licenses(
+            &source,
+            &[
+                LicenseAgreement::new(LicenseLanguage::English, "Terms\nMore terms"),
+                LicenseAgreement::new(LicenseLanguage::French, "Conditions"),
+            ],
+        )?;
+
+        assert_eq!(&data[0..1024], &source[0..1024]);
+        let koly = &data[data.len() - KOLY_SIZE..];
+        assert_eq!(&koly[0..4], KOLY_MAGIC);
+        let xml_length = u64::from_be_bytes(
+            koly[KOLY_XML_LENGTH..KOLY_XML_LENGTH + 8]
+                .try_into()
+                .unwrap(),
+        );
+        assert_eq!(xml_length as usize, data.len() - KOLY_SIZE - 1024);
+
+        let plist = Value::from_reader_xml(&data[1024..data.len() - KOLY_SIZE])?;
+        let resources = plist
+            .as_dictionary()
+            .and_then(|d| d.get("resource-fork"))
+            .and_then(|v| v.as_dictionary())
+            .unwrap();
+
+        let data_of = |key: &str, index: usize| {
+            resources
+                .get(key)
+                .and_then(|v| v.as_array())
+                .and_then(|a| a.get(index))
+                .and_then(|v| v.as_dictionary())
+                .and_then(|d| d.get("Data"))
+                .and_then(|v| v.as_data())
+                .unwrap()
+                .to_vec()
+        };
+
+        assert_eq!(
+            data_of("LPic", 0),
+            vec![0, 0, 0, 2, 0, 0, 0, 2, 0, 0, 0, 1, 0, 3, 0, 0]
+        );
+        assert_eq!(data_of("TEXT", 0), b"Terms\rMore terms");
+        assert_eq!(data_of("TEXT", 1), b"Conditions");
+
+        let strings = data_of("STR#", 1);
+        assert_eq!(&strings[0..2], &[0, 6]);
+        assert_eq!(&strings[2..9], b"\x06French");
+
+        assert!(add_udif_licenses(
+            &source,
+            &[
+                LicenseAgreement::new(LicenseLanguage::English, "a"),
+                LicenseAgreement::new(LicenseLanguage::English, "b"),
+            ],
+        )
+        .is_err());
+        assert!(add_udif_licenses(
+            &[0; 600],
+            &[LicenseAgreement::new(LicenseLanguage::English, "a")]
+        )
+        .is_err());
+
+        Ok(())
+    }
+}
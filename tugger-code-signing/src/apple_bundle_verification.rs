@@ -0,0 +1,611 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Verification of Apple bundle sealed resources.
+//!
+//! Signed Apple bundles have a `_CodeSignature/CodeResources` plist file
+//! that *seals* the resources in the bundle: it records the digests of
+//! every non-code file and the code directory hashes (*cdhashes*) of
+//! nested code. This module implements verifying that the content of a
+//! bundle on disk matches what was sealed. It is the equivalent of the
+//! resource checking performed by `codesign --verify --deep`.
+//!
+//! Verification of the CMS signature of the main executable is not
+//! performed here.
+
+use {
+    crate::SigningError,
+    apple_codesign::{AppleCodesignError, Blob, EmbeddedSignature, MachFile},
+    regex::Regex,
+    sha1::Sha1,
+    sha2::{Digest, Sha256},
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        fmt::{Display, Formatter},
+        path::{Path, PathBuf},
+    },
+};
+
+/// Length of a cdhash, in bytes.
+const CDHASH_LENGTH: usize = 20;
+
+/// Describes a problem found when verifying sealed resources.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SealedResourceProblem {
+    /// A file exists in the bundle but isn't sealed.
+    Added(String),
+
+    /// A sealed, non-optional file is missing from the bundle.
+    Removed(String),
+
+    /// The content of a sealed file doesn't match its recorded digest.
+    Modified(String),
+
+    /// A sealed symlink doesn't point to its recorded target.
+    SymlinkMismatch(String),
+
+    /// Nested code has a cdhash not matching the sealed value.
+    NestedCodeMismatch(String),
+
+    /// Nested code could not be read or isn't signed.
+    NestedCodeUnsigned(String),
+}
+
+impl Display for SealedResourceProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Added(path) => f.write_fmt(format_args!("file added: {}", path)),
+            Self::Removed(path) => f.write_fmt(format_args!("file missing: {}", path)),
+            Self::Modified(path) => f.write_fmt(format_args!("file modified: {}", path)),
+            Self::SymlinkMismatch(path) => {
+                f.write_fmt(format_args!("symlink target modified: {}", path))
+            }
+            Self::NestedCodeMismatch(path) => {
+                f.write_fmt(format_args!("nested code cdhash mismatch: {}", path))
+            }
+            Self::NestedCodeUnsigned(path) => {
+                f.write_fmt(format_args!("nested code not signed: {}", path))
+            }
+        }
+    }
+}
+
+/// The result of verifying an Apple bundle's sealed resources.
+#[derive(Clone, Debug, Default)]
+pub struct AppleBundleVerification {
+    /// Path of the bundle that was verified.
+    pub bundle_path: PathBuf,
+
+    /// Number of sealed resources that were checked.
+    pub resources_checked: usize,
+
+    /// Problems found in this bundle.
+    pub problems: Vec<SealedResourceProblem>,
+
+    /// Verification results of nested bundles.
+    pub nested: Vec<AppleBundleVerification>,
+}
+
+impl AppleBundleVerification {
+    /// Whether this bundle and all nested bundles verified without problems.
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty() && self.nested.iter().all(|x| x.is_valid())
+    }
+
+    /// Obtain all problems in this and nested bundles.
+    ///
+    /// Paths of problems in nested bundles are prefixed with the path of
+    /// the nested bundle.
+    pub fn all_problems(&self) -> Vec<(PathBuf, SealedResourceProblem)> {
+        let mut res = self
+            .problems
+            .iter()
+            .map(|p| (self.bundle_path.clone(), p.clone()))
+            .collect::<Vec<_>>();
+
+        for nested in &self.nested {
+            res.extend(nested.all_problems());
+        }
+
+        res
+    }
+}
+
+/// A sealed resource rule from the `rules2` dictionary.
+struct ResourceRule {
+    pattern: Regex,
+    omit: bool,
+    nested: bool,
+    weight: f64,
+}
+
+/// A sealed resource from the `files2` dictionary.
+#[derive(Default)]
+struct SealedResource {
+    sha1: Option<Vec<u8>>,
+    sha256: Option<Vec<u8>>,
+    optional: bool,
+    symlink: Option<String>,
+    cdhash: Option<Vec<u8>>,
+}
+
+/// Resolve the directory that sealed resource paths are relative to.
+///
+/// macOS application bundles store content in `Contents/`. Versioned framework
+/// bundles store content in `Versions/Current/`. Shallow bundles store content
+/// in the root directory.
+fn resolve_bundle_content_dir(bundle_dir: &Path) -> PathBuf {
+    let contents = bundle_dir.join("Contents");
+    let current = bundle_dir.join("Versions").join("Current");
+
+    if contents.is_dir() {
+        contents
+    } else if current.is_dir() {
+        current
+    } else {
+        bundle_dir.to_path_buf()
+    }
+}
+
+/// Resolve the path of the main executable relative to the content directory.
+fn resolve_main_executable(content_dir: &Path) -> Result<Option<String>, SigningError> {
+    let info_plist = ["Info.plist", "Resources/Info.plist"]
+        .iter()
+        .map(|p| content_dir.join(p))
+        .find(|p| p.is_file());
+
+    let info_plist = if let Some(p) = info_plist {
+        p
+    } else {
+        return Ok(None);
+    };
+
+    let value = plist::Value::from_file(&info_plist)?;
+
+    let executable = value
+        .as_dictionary()
+        .and_then(|d| d.get("CFBundleExecutable"))
+        .and_then(|v| v.as_string());
+
+    Ok(executable.map(|exe| {
+        if content_dir.join("MacOS").is_dir() {
+            format!("MacOS/{}", exe)
+        } else {
+            exe.to_string()
+        }
+    }))
+}
+
+fn parse_rules(value: Option<&plist::Value>) -> Result<Vec<ResourceRule>, SigningError> {
+    let dict = if let Some(dict) = value.and_then(|v| v.as_dictionary()) {
+        dict
+    } else {
+        return Ok(vec![]);
+    };
+
+    let mut rules = vec![];
+
+    for (pattern, value) in dict {
+        let pattern = Regex::new(pattern).map_err(|e| {
+            SigningError::BundleVerification(format!("invalid resource rule {}: {}", pattern, e))
+        })?;
+
+        let mut rule = ResourceRule {
+            pattern,
+            omit: false,
+            nested: false,
+            weight: 1.0,
+        };
+
+        if let Some(d) = value.as_dictionary() {
+            rule.omit = d.get("omit").and_then(|v| v.as_boolean()).unwrap_or(false);
+            rule.nested = d
+                .get("nested")
+                .and_then(|v| v.as_boolean())
+                .unwrap_or(false);
+            rule.weight = d.get("weight").and_then(|v| v.as_real()).unwrap_or(1.0);
+        }
+
+        rules.push(rule);
+    }
+
+    Ok(rules)
+}
+
+fn parse_sealed_resources(
+    value: Option<&plist::Value>,
+) -> Result<BTreeMap<String, SealedResource>, SigningError> {
+    let dict = value.and_then(|v| v.as_dictionary()).ok_or_else(|| {
+        SigningError::BundleVerification("CodeResources lacks a files dictionary".to_string())
+    })?;
+
+    let mut res = BTreeMap::new();
+
+    for (path, value) in dict {
+        let resource = match value {
+            plist::Value::Data(data) => SealedResource {
+                sha1: Some(data.clone()),
+                ..Default::default()
+            },
+            plist::Value::Dictionary(d) => SealedResource {
+                sha1: d.get("hash").and_then(|v| v.as_data()).map(|x| x.to_vec()),
+                sha256: d.get("hash2").and_then(|v| v.as_data()).map(|x| x.to_vec()),
+                optional: d
+                    .get("optional")
+                    .and_then(|v| v.as_boolean())
+                    .unwrap_or(false),
+                symlink: d
+                    .get("symlink")
+                    .and_then(|v| v.as_string())
+                    .map(|x| x.to_string()),
+                cdhash: d
+                    .get("cdhash")
+                    .and_then(|v| v.as_data())
+                    .map(|x| x.to_vec()),
+            },
+            _ => {
+                return Err(SigningError::BundleVerification(format!(
+                    "unexpected value type for sealed resource {}",
+                    path
+                )));
+            }
+        };
+
+        res.insert(path.clone(), resource);
+    }
+
+    Ok(res)
+}
+
+/// Find the highest weighted rule matching a path.
+fn matching_rule<'a>(rules: &'a [ResourceRule], path: &str) -> Option<&'a ResourceRule> {
+    rules
+        .iter()
+        .filter(|rule| rule.pattern.is_match(path))
+        .fold(None, |best: Option<&ResourceRule>, rule| match best {
+            Some(best) if best.weight >= rule.weight => Some(best),
+            _ => Some(rule),
+        })
+}
+
+/// Obtain the cdhashes of all code directories in an embedded signature.
+///
+/// A cdhash is the digest of a code directory using the code directory's own
+/// digest type, truncated to 20 bytes.
+fn embedded_signature_cdhashes(
+    signature: &EmbeddedSignature,
+) -> Result<Vec<Vec<u8>>, AppleCodesignError> {
+    let mut code_directories = signature.code_directory()?.into_iter().collect::<Vec<_>>();
+    code_directories.extend(
+        signature
+            .alternate_code_directories()?
+            .into_iter()
+            .map(|(_, cd)| cd),
+    );
+
+    code_directories
+        .into_iter()
+        .map(|cd| {
+            let mut digest = cd.digest_with(cd.digest_type)?;
+            digest.truncate(CDHASH_LENGTH);
+
+            Ok(digest)
+        })
+        .collect()
+}
+
+/// Obtain the cdhashes of all code directories in Mach-O data.
+///
+/// Universal binaries will have cdhashes for every architecture. Data that
+/// isn't Mach-O or lacks a valid signature has no cdhashes.
+fn macho_cdhashes(data: &[u8]) -> Vec<Vec<u8>> {
+    let mach = if let Ok(mach) = MachFile::parse(data) {
+        mach
+    } else {
+        return vec![];
+    };
+
+    mach.iter_macho()
+        .filter_map(|macho| macho.code_signature().ok().flatten())
+        .filter_map(|signature| embedded_signature_cdhashes(&signature).ok())
+        .flatten()
+        .collect()
+}
+
+/// Verify nested code against its sealed cdhash.
+fn verify_nested_code(
+    path: &Path,
+    rel_path: &str,
+    expected: &[u8],
+    problems: &mut Vec<SealedResourceProblem>,
+    nested: &mut Vec<AppleBundleVerification>,
+) -> Result<(), SigningError> {
+    let executable = if path.is_dir() {
+        nested.push(verify_apple_bundle_resources(path)?);
+
+        let content_dir = resolve_bundle_content_dir(path);
+
+        resolve_main_executable(&content_dir)?.map(|exe| content_dir.join(exe))
+    } else {
+        Some(path.to_path_buf())
+    };
+
+    let cdhashes = if let Some(executable) = executable {
+        match std::fs::read(&executable) {
+            Ok(data) => macho_cdhashes(&data),
+            Err(_) => vec![],
+        }
+    } else {
+        vec![]
+    };
+
+    if cdhashes.is_empty() {
+        problems.push(SealedResourceProblem::NestedCodeUnsigned(
+            rel_path.to_string(),
+        ));
+    } else if !cdhashes.iter().any(|h| h == expected) {
+        problems.push(SealedResourceProblem::NestedCodeMismatch(
+            rel_path.to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Verify the sealed resources of an Apple bundle.
+///
+/// This reads the bundle's `_CodeSignature/CodeResources` file and verifies
+/// that every sealed file is present with matching content, that no unsealed
+/// files were added, and that nested code has the sealed cdhash. Nested
+/// bundles are verified recursively.
+///
+/// Problems with the bundle content are reported in the returned
+/// [AppleBundleVerification]. `Err` is only returned if the bundle cannot be
+/// read or lacks a `CodeResources` file.
+pub fn verify_apple_bundle_resources(
+    bundle_dir: impl AsRef<Path>,
+) -> Result<AppleBundleVerification, SigningError> {
+    let bundle_dir = bundle_dir.as_ref();
+    let content_dir = resolve_bundle_content_dir(bundle_dir);
+
+    let code_resources_path = content_dir.join("_CodeSignature").join("CodeResources");
+    if !code_resources_path.is_file() {
+        return Err(SigningError::BundleVerification(format!(
+            "{} does not exist; is the bundle signed?",
+            code_resources_path.display()
+        )));
+    }
+
+    let code_resources = plist::Value::from_file(&code_resources_path)?;
+    let code_resources = code_resources.as_dictionary().ok_or_else(|| {
+        SigningError::BundleVerification("CodeResources is not a dictionary".to_string())
+    })?;
+
+    // Prefer the version 2 rules and files, falling back to version 1 for
+    // bundles signed by ancient tools.
+    let (rules, sealed) = if code_resources.contains_key("files2") {
+        (
+            parse_rules(code_resources.get("rules2"))?,
+            parse_sealed_resources(code_resources.get("files2"))?,
+        )
+    } else {
+        (
+            parse_rules(code_resources.get("rules"))?,
+            parse_sealed_resources(code_resources.get("files"))?,
+        )
+    };
+
+    let main_executable = resolve_main_executable(&content_dir)?;
+
+    let mut verification = AppleBundleVerification {
+        bundle_path: bundle_dir.to_path_buf(),
+        ..Default::default()
+    };
+
+    let mut seen = BTreeSet::new();
+
+    let mut walker = walkdir::WalkDir::new(&content_dir)
+        .sort_by_file_name()
+        .into_iter();
+
+    while let Some(entry) = walker.next() {
+        let entry = entry?;
+
+        let rel_path = crate::bundle_relative_path(&content_dir, entry.path());
+        if rel_path.is_empty() {
+            continue;
+        }
+
+        if rel_path == "_CodeSignature" || rel_path == "CodeResources" {
+            if entry.file_type().is_dir() {
+                walker.skip_current_dir();
+            }
+            continue;
+        }
+
+        if Some(&rel_path) == main_executable.as_ref() {
+            continue;
+        }
+
+        if let Some(resource) = sealed.get(&rel_path) {
+            seen.insert(rel_path.clone());
+            verification.resources_checked += 1;
+
+            if let Some(expected) = &resource.cdhash {
+                if entry.file_type().is_dir() {
+                    walker.skip_current_dir();
+                }
+
+                verify_nested_code(
+                    entry.path(),
+                    &rel_path,
+                    expected,
+                    &mut verification.problems,
+                    &mut verification.nested,
+                )?;
+            } else if let Some(target) = &resource.symlink {
+                let actual = std::fs::read_link(entry.path()).ok();
+
+                if actual.as_deref() != Some(Path::new(target)) {
+                    verification
+                        .problems
+                        .push(SealedResourceProblem::SymlinkMismatch(rel_path));
+                }
+            } else if entry.file_type().is_dir() {
+                verification
+                    .problems
+                    .push(SealedResourceProblem::Modified(rel_path));
+            } else {
+                let data = std::fs::read(entry.path())?;
+
+                let matches = if let Some(expected) = &resource.sha256 {
+                    Sha256::digest(&data).as_slice() == expected.as_slice()
+                } else if let Some(expected) = &resource.sha1 {
+                    Sha1::digest(&data).as_slice() == expected.as_slice()
+                } else {
+                    false
+                };
+
+                if !matches {
+                    verification
+                        .problems
+                        .push(SealedResourceProblem::Modified(rel_path));
+                }
+            }
+
+            continue;
+        }
+
+        let rule = matching_rule(&rules, &rel_path);
+
+        if entry.file_type().is_dir() {
+            // Nested bundles not recorded in the seal are an addition. But there's
+            // no need to descend into them.
+            let is_bundle = entry.file_name().to_string_lossy().contains('.');
+
+            if is_bundle && matches!(rule, Some(rule) if rule.nested && !rule.omit) {
+                walker.skip_current_dir();
+                verification
+                    .problems
+                    .push(SealedResourceProblem::Added(rel_path));
+            }
+
+            continue;
+        }
+
+        // Files not covered by any rule or covered by an omit rule aren't sealed.
+        if matches!(rule, Some(rule) if !rule.omit) {
+            verification
+                .problems
+                .push(SealedResourceProblem::Added(rel_path));
+        }
+    }
+
+    for (path, resource) in &sealed {
+        if !seen.contains(path) && !resource.optional {
+            verification
+                .problems
+                .push(SealedResourceProblem::Removed(path.clone()));
+        }
+    }
+
+    Ok(verification)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_code_resources(
+        content_dir: &Path,
+        files: &[(&str, &[u8])],
+    ) -> Result<(), SigningError> {
+        let mut rules = plist::Dictionary::new();
+        rules.insert("^.*".to_string(), plist::Value::Boolean(true));
+        let mut omit = plist::Dictionary::new();
+        omit.insert("omit".to_string(), plist::Value::Boolean(true));
+        omit.insert("weight".to_string(), plist::Value::Real(20.0));
+        rules.insert("^Info\\.plist$".to_string(), plist::Value::Dictionary(omit));
+
+        let mut files2 = plist::Dictionary::new();
+        for (path, data) in files {
+            let mut entry = plist::Dictionary::new();
+            entry.insert(
+                "hash2".to_string(),
+                plist::Value::Data(Sha256::digest(data).to_vec()),
+            );
+            files2.insert(path.to_string(), plist::Value::Dictionary(entry));
+        }
+
+        let mut root = plist::Dictionary::new();
+        root.insert("rules2".to_string(), plist::Value::Dictionary(rules));
+        root.insert("files2".to_string(), plist::Value::Dictionary(files2));
+
+        std::fs::create_dir_all(content_dir.join("_CodeSignature"))?;
+        plist::Value::Dictionary(root)
+            .to_file_xml(content_dir.join("_CodeSignature").join("CodeResources"))?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn verify_resources() -> Result<(), SigningError> {
+        let td = tempfile::Builder::new()
+            .prefix("tugger-code-signing-test")
+            .tempdir()?;
+        let bundle = td.path().join("App.app");
+        let contents = bundle.join("Contents");
+        std::fs::create_dir_all(contents.join("Resources"))?;
+        plist::Value::Dictionary(plist::Dictionary::new())
+            .to_file_xml(contents.join("Info.plist"))?;
+        std::fs::write(contents.join("Resources/a.txt"), b"a")?;
+        std::fs::write(contents.join("Resources/b.txt"), b"b")?;
+
+        write_code_resources(
+            &contents,
+            &[("Resources/a.txt", b"a"), ("Resources/b.txt", b"b")],
+        )?;
+
+        let res = verify_apple_bundle_resources(&bundle)?;
+        assert!(res.is_valid());
+        assert_eq!(res.resources_checked, 2);
+
+        std::fs::write(contents.join("Resources/a.txt"), b"modified")?;
+        std::fs::remove_file(contents.join("Resources/b.txt"))?;
+        std::fs::write(contents.join("Resources/c.txt"), b"c")?;
+
+        let res = verify_apple_bundle_resources(&bundle)?;
+        assert!(!res.is_valid());
+        assert_eq!(
+            res.problems,
+            vec![
+                SealedResourceProblem::Modified("Resources/a.txt".to_string()),
+                SealedResourceProblem::Added("Resources/c.txt".to_string()),
+                SealedResourceProblem::Removed("Resources/b.txt".to_string()),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn unsigned_bundle() -> Result<(), SigningError> {
+        let td = tempfile::Builder::new()
+            .prefix("tugger-code-signing-test")
+            .tempdir()?;
+
+        assert!(matches!(
+            verify_apple_bundle_resources(td.path()),
+            Err(SigningError::BundleVerification(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn non_macho_cdhashes() {
+        assert!(macho_cdhashes(b"garbage").is_empty());
+        assert!(macho_cdhashes(&[]).is_empty());
+    }
+}
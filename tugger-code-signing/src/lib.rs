@@ -55,15 +55,31 @@
 //! callback functions can be registered on [Signer] instances to allow customization
 //! of the low-level signing primitives used for signing individual [Signable]. See
 //! [Signer::apple_settings_callback] and [Signer::windows_settings_callback].
+//! Progress of signing operations can be observed by registering a callback
+//! via [Signer::event_callback].
 //!
 //! Finally, a signing operation can be performed via [SignableSigner::sign].
 //! This hides away all the complexity of mapping different signable entities
 //! to different signing *backends* and gives you a relatively clean interface
 //! to attempt code signing. If signing was successful, you'll get a
 //! [SignedOutput] describing where the signed content lives.
+//!
+//! Signed Apple bundles can additionally be notarized by registering
+//! [AppleNotarizationSettings] via [Signer::apple_notarization].
+//!
+//! # Verification
+//!
+//! [verify_apple_bundle_resources] can be used to verify that the content
+//! of a signed Apple bundle matches its sealed resources.
+
+mod apple_bundle_verification;
+pub use apple_bundle_verification::*;
 
 use {
-    apple_codesign::{cryptography::InMemoryPrivateKey, AppleCodesignError, MachOSigner},
+    apple_codesign::{
+        cryptography::InMemoryPrivateKey, AppleCodesignError, CodeSignatureFlags, DigestType,
+        MachOSigner, SettingsScope,
+    },
     cryptographic_message_syntax::CmsError,
     log::warn,
     reqwest::{IntoUrl, Url},
@@ -137,12 +153,18 @@ pub enum SigningError {
     #[error("error signing Apple bundle: {0}")]
     AppleBundleSigningError(AppleCodesignError),
 
+    #[error("error notarizing: {0}")]
+    AppleNotarizationError(AppleCodesignError),
+
     #[error("error running settings callback: {0}")]
     SettingsCallback(anyhow::Error),
 
     #[error("error running signtool: {0}")]
     SigntoolError(anyhow::Error),
 
+    #[error("error creating Authenticode signature: {0}")]
+    AuthenticodeError(anyhow::Error),
+
     #[error("incompatible signing destination: {0}")]
     IncompatibleSigningDestination(&'static str),
 
@@ -151,6 +173,52 @@ pub enum SigningError {
 
     #[error("X.509 certificate handling error: {0}")]
     X509Certificate(#[from] X509CertificateError),
+
+    #[error("unknown code signature flag: {0}")]
+    UnknownCodeSignatureFlag(String),
+
+    #[error("invalid path pattern: {0}")]
+    BadPathPattern(#[from] glob::PatternError),
+
+    #[error("error walking directory: {0}")]
+    WalkDir(#[from] walkdir::Error),
+
+    #[error("plist error: {0}")]
+    Plist(#[from] plist::Error),
+
+    #[error("bundle verification error: {0}")]
+    BundleVerification(String),
+}
+
+/// Resolve an Apple code signature flag from its symbolic name.
+///
+/// Names match those accepted by Apple's `codesign --options` argument
+/// where possible. e.g. `runtime` enables the hardened runtime.
+pub fn apple_code_signature_flag_from_name(name: &str) -> Result<CodeSignatureFlags, SigningError> {
+    match name {
+        "host" => Ok(CodeSignatureFlags::HOST),
+        "hard" => Ok(CodeSignatureFlags::FORCE_HARD),
+        "kill" => Ok(CodeSignatureFlags::FORCE_KILL),
+        "expires" => Ok(CodeSignatureFlags::FORCE_EXPIRES),
+        "restrict" => Ok(CodeSignatureFlags::RESTRICT),
+        "enforcement" => Ok(CodeSignatureFlags::ENFORCEMENT),
+        "library" | "library-validation" => Ok(CodeSignatureFlags::LIBRARY_VALIDATION),
+        "runtime" => Ok(CodeSignatureFlags::RUNTIME),
+        _ => Err(SigningError::UnknownCodeSignatureFlag(name.to_string())),
+    }
+}
+
+/// Resolve Apple code signature flags from an iterable of symbolic names.
+///
+/// See [apple_code_signature_flag_from_name] for the list of recognized names.
+pub fn apple_code_signature_flags_from_names<'a>(
+    names: impl IntoIterator<Item = &'a str>,
+) -> Result<CodeSignatureFlags, SigningError> {
+    names
+        .into_iter()
+        .try_fold(CodeSignatureFlags::empty(), |flags, name| {
+            Ok(flags | apple_code_signature_flag_from_name(name)?)
+        })
 }
 
 /// Represents a location where signed data should be written.
@@ -392,6 +460,17 @@ impl Signable {
         !self.signing_methods().is_empty()
     }
 
+    /// Obtain a short human readable description of this entity.
+    pub fn description(&self) -> String {
+        match self {
+            Self::WindowsFile(p) => format!("Windows file {}", p.display()),
+            Self::WindowsData(data) => format!("Windows data ({} bytes)", data.len()),
+            Self::MachOFile(p, _) => format!("Mach-O file {}", p.display()),
+            Self::MachOData(data) => format!("Mach-O data ({} bytes)", data.len()),
+            Self::AppleBundle(p) => format!("Apple bundle {}", p.display()),
+        }
+    }
+
     /// Obtain the filesystem path of the signable entity, if it is backed by a file.
     pub fn source_file(&self) -> Option<&Path> {
         match self {
@@ -514,17 +593,9 @@ pub fn path_signable(path: impl AsRef<Path>) -> Result<Signability, SigningError
     if path.is_file() {
         match tugger_windows_codesign::is_file_signable(path) {
             Ok(true) => {
-                // But we can only sign Windows binaries on Windows since we call out to
-                // signtool.exe.
-                return if cfg!(target_family = "windows") {
-                    Ok(Signability::Signable(Signable::WindowsFile(
-                        path.to_path_buf(),
-                    )))
-                } else {
-                    Ok(Signability::PlatformUnsupported(
-                        "Windows signing requires running on Windows",
-                    ))
-                };
+                return Ok(Signability::Signable(Signable::WindowsFile(
+                    path.to_path_buf(),
+                )));
             }
             Ok(false) => {}
             Err(e) => return Err(SigningError::SignableTestError(format!("{:?}", e))),
@@ -551,17 +622,7 @@ pub fn path_signable(path: impl AsRef<Path>) -> Result<Signability, SigningError
 /// Resolve signability information given a data slice.
 pub fn data_signable(data: &[u8]) -> Result<Signability, SigningError> {
     if tugger_windows_codesign::is_signable_binary_header(data) {
-        // But we can only sign Windows binaries on Windows since we call out to
-        // signtool.exe.
-        return if cfg!(target_family = "windows") {
-            Ok(Signability::Signable(Signable::WindowsData(
-                data.as_ref().to_vec(),
-            )))
-        } else {
-            Ok(Signability::PlatformUnsupported(
-                "Windows signing requires running on Windows",
-            ))
-        };
+        return Ok(Signability::Signable(Signable::WindowsData(data.to_vec())));
     }
 
     if goblin::mach::Mach::parse(data).is_ok() {
@@ -714,6 +775,147 @@ impl SigningCertificate {
     }
 }
 
+/// Describes which macOS versions Apple signatures should be compatible with.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AppleSigningCompatibility {
+    /// Use modern defaults.
+    ///
+    /// Code directories use SHA-256 digests.
+    Modern,
+
+    /// Emit signatures compatible with macOS 10.11 and older.
+    ///
+    /// The primary code directory uses SHA-1 digests, as older operating
+    /// systems do not understand SHA-256 code directories.
+    LegacySha1,
+}
+
+impl Default for AppleSigningCompatibility {
+    fn default() -> Self {
+        Self::Modern
+    }
+}
+
+impl TryFrom<&str> for AppleSigningCompatibility {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "modern" => Ok(Self::Modern),
+            "legacy-sha1" => Ok(Self::LegacySha1),
+            _ => Err(format!(
+                "{} is not a valid signing compatibility profile; use modern or legacy-sha1",
+                value
+            )),
+        }
+    }
+}
+
+/// Apple signing settings that apply to a subset of paths within a bundle.
+///
+/// Instances are registered on a [Signer] with a path pattern via
+/// [Signer::apple_path_settings]. When signing an Apple bundle, every
+/// nested file and directory whose bundle-relative path matches the
+/// pattern is signed with these settings instead of the defaults.
+#[derive(Clone, Debug, Default)]
+pub struct ApplePathSigningSettings {
+    /// Code signature flags to use for matching entities.
+    pub code_signature_flags: Option<CodeSignatureFlags>,
+
+    /// Entitlements plist XML to use for matching entities.
+    pub entitlements_xml: Option<String>,
+}
+
+/// Settings for notarizing signed Apple entities.
+///
+/// Registered on a [Signer] via [Signer::apple_notarization]. When present,
+/// signed Apple bundles are uploaded to Apple's Notary API after signing.
+/// Authentication uses an App Store Connect API key, which is resolved from
+/// its key ID in the same locations as Apple's `notarytool`.
+#[derive(Clone, Debug)]
+pub struct AppleNotarizationSettings {
+    /// App Store Connect API issuer ID.
+    pub api_issuer: String,
+
+    /// App Store Connect API key ID.
+    pub api_key: String,
+
+    /// Whether to staple the notarization ticket to the signed entity.
+    pub staple: bool,
+}
+
+/// Describes an event that occurred while signing a [Signable].
+///
+/// Events are delivered to the callback registered via
+/// [Signer::event_callback] and can be used to report progress of
+/// long-running signing operations.
+#[derive(Clone, Copy, Debug)]
+pub enum SigningEvent<'a> {
+    /// Signing of an entity is about to begin.
+    SigningStarted(&'a Signable, &'a SigningDestination),
+
+    /// A file or directory of an Apple bundle was copied to the signing
+    /// destination.
+    ///
+    /// The value is the `/` delimited path relative to the bundle root.
+    AppleBundleFileCopied(&'a str),
+
+    /// Content is being written to a temporary file to facilitate signing.
+    TemporaryFileCreated(&'a Path),
+
+    /// A Time-Stamp Protocol server will be used to time-stamp the signature.
+    TimeStampRequest(&'a Url),
+
+    /// The signing backend is about to create the cryptographic signature.
+    SignatureCreationStarted(&'a Signable),
+
+    /// A signed entity is being submitted to Apple for notarization.
+    AppleNotarizationStarted(&'a Path),
+
+    /// Signing of an entity finished successfully.
+    SigningFinished(&'a Signable, &'a SignedOutput),
+
+    /// Signing of an entity failed.
+    SigningFailed(&'a Signable, &'a SigningError),
+}
+
+impl<'a> std::fmt::Display for SigningEvent<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SigningStarted(signable, destination) => f.write_fmt(format_args!(
+                "signing started: {} -> {:?}",
+                signable.description(),
+                destination
+            )),
+            Self::AppleBundleFileCopied(path) => {
+                f.write_fmt(format_args!("bundle file copied: {}", path))
+            }
+            Self::TemporaryFileCreated(path) => {
+                f.write_fmt(format_args!("temporary file created: {}", path.display()))
+            }
+            Self::TimeStampRequest(url) => f.write_fmt(format_args!("time-stamp server: {}", url)),
+            Self::SignatureCreationStarted(signable) => f.write_fmt(format_args!(
+                "creating signature for {}",
+                signable.description()
+            )),
+            Self::AppleNotarizationStarted(path) => {
+                f.write_fmt(format_args!("notarizing {}", path.display()))
+            }
+            Self::SigningFinished(signable, _) => {
+                f.write_fmt(format_args!("signing finished: {}", signable.description()))
+            }
+            Self::SigningFailed(signable, err) => f.write_fmt(format_args!(
+                "signing failed: {}: {}",
+                signable.description(),
+                err
+            )),
+        }
+    }
+}
+
+/// A callback receiving [SigningEvent] as signing operations progress.
+pub type SigningEventFn = dyn Fn(&SigningEvent) + Send + Sync;
+
 /// A callback for influencing the creation of [apple_codesign::SigningSettings]
 /// instances for a given [Signable].
 pub type AppleSigningSettingsFn =
@@ -741,13 +943,37 @@ pub struct Signer {
     /// URL of Time-Stamp Protocol server to use.
     time_stamp_url: Option<Url>,
 
+    /// Compatibility profile to use when signing Apple signables.
+    apple_compatibility: AppleSigningCompatibility,
+
+    /// Code signature flags to apply when signing Apple signables.
+    apple_code_signature_flags: Option<CodeSignatureFlags>,
+
+    /// Entitlements plist XML to apply when signing Apple signables.
+    apple_entitlements_xml: Option<String>,
+
+    /// Binary designated requirement to apply when signing Apple signables.
+    apple_designated_requirement: Option<Vec<u8>>,
+
+    /// Notarization to perform after signing Apple bundles.
+    apple_notarization: Option<AppleNotarizationSettings>,
+
+    /// Apple signing settings scoped to bundle paths matching a pattern.
+    apple_path_settings: Vec<(glob::Pattern, ApplePathSigningSettings)>,
+
     /// Optional function to influence creation of [apple_codesign::SigningSettings]
     /// used for signing Apple signables.
     apple_signing_settings_fn: Option<Arc<AppleSigningSettingsFn>>,
 
+    /// Description of signed content to record in Windows signatures.
+    windows_description: Option<String>,
+
     /// Optional function to influence creation of [tugger_windows_codesign::SigntoolSign]
     /// used for signing Windows signables.
     windows_signer_fn: Option<Arc<WindowsSignerFn>>,
+
+    /// Optional function receiving events as signing progresses.
+    event_fn: Option<Arc<SigningEventFn>>,
 }
 
 impl From<SigningCertificate> for Signer {
@@ -763,8 +989,16 @@ impl Signer {
             signing_certificate,
             certificate_chain: vec![],
             time_stamp_url: None,
+            apple_compatibility: AppleSigningCompatibility::default(),
+            apple_code_signature_flags: None,
+            apple_entitlements_xml: None,
+            apple_designated_requirement: None,
+            apple_notarization: None,
+            apple_path_settings: vec![],
             apple_signing_settings_fn: None,
+            windows_description: None,
             windows_signer_fn: None,
+            event_fn: None,
         }
     }
 
@@ -894,16 +1128,97 @@ impl Signer {
         Ok(())
     }
 
+    /// Set the compatibility profile to use when signing Apple signables.
+    ///
+    /// The default is [AppleSigningCompatibility::Modern]. Only use
+    /// [AppleSigningCompatibility::LegacySha1] if you need to support
+    /// macOS 10.11 or older.
+    pub fn apple_signing_compatibility(&mut self, compatibility: AppleSigningCompatibility) {
+        self.apple_compatibility = compatibility;
+    }
+
+    /// Set the code signature flags to use when signing Apple signables.
+    ///
+    /// Flags control behavior such as the hardened runtime (required for
+    /// notarization) and library validation. The flags apply to the main
+    /// signing scope of every Apple entity signed by this instance. Use
+    /// [SignableSigner::set_apple_code_signature_flags] to customize flags
+    /// for an individual entity.
+    pub fn apple_code_signature_flags(&mut self, flags: CodeSignatureFlags) {
+        self.apple_code_signature_flags = Some(flags);
+    }
+
+    /// Set the entitlements to use when signing Apple signables.
+    ///
+    /// The value is an entitlements plist XML document. Like
+    /// [Self::apple_code_signature_flags], it applies to the main signing
+    /// scope. Use [Self::apple_path_settings] to grant entitlements to
+    /// nested bundle entities.
+    pub fn apple_entitlements_xml(&mut self, xml: impl ToString) {
+        self.apple_entitlements_xml = Some(xml.to_string());
+    }
+
+    /// Set the designated requirement to use when signing Apple signables.
+    ///
+    /// The value is a binary code requirement expression, as produced by
+    /// `csreq -b`. If not set, the designated requirement is derived from
+    /// the signing certificate.
+    pub fn apple_designated_requirement(&mut self, data: impl Into<Vec<u8>>) {
+        self.apple_designated_requirement = Some(data.into());
+    }
+
+    /// Notarize signed Apple bundles using the given settings.
+    pub fn apple_notarization(&mut self, settings: AppleNotarizationSettings) {
+        self.apple_notarization = Some(settings);
+    }
+
+    /// Register Apple signing settings for bundle paths matching a pattern.
+    ///
+    /// `pattern` is a glob pattern (e.g. `Contents/Helpers/*.app`) matched
+    /// against the `/` delimited path of nested entities relative to the
+    /// root of the bundle being signed. `*` does not match across directories
+    /// but `**` does. When multiple patterns match the same path, their
+    /// settings are merged field by field, with each field taken from the
+    /// latest registration defining it.
+    ///
+    /// This allows e.g. granting JIT entitlements to an embedded helper
+    /// without granting them to the main executable.
+    pub fn apple_path_settings(
+        &mut self,
+        pattern: &str,
+        settings: ApplePathSigningSettings,
+    ) -> Result<(), SigningError> {
+        self.apple_path_settings
+            .push((glob::Pattern::new(pattern)?, settings));
+
+        Ok(())
+    }
+
     /// Set a callback function to be called to influence settings for signing individual Apple signables.
     pub fn apple_settings_callback(&mut self, cb: AppleSigningSettingsFn) {
         self.apple_signing_settings_fn = Some(Arc::new(cb));
     }
 
+    /// Set the description of signed content to record in Windows signatures.
+    ///
+    /// Windows displays this description in User Account Control prompts.
+    pub fn windows_description(&mut self, description: impl ToString) {
+        self.windows_description = Some(description.to_string());
+    }
+
     /// Set a callback function to be called to influence settings for signing individual Windows signables.
     pub fn windows_settings_callback(&mut self, cb: WindowsSignerFn) {
         self.windows_signer_fn = Some(Arc::new(cb));
     }
 
+    /// Set a callback function to be called as signing operations progress.
+    ///
+    /// The callback receives a [SigningEvent] describing what is happening.
+    /// This can be used to display progress or emit structured logs.
+    pub fn event_callback(&mut self, cb: Arc<SigningEventFn>) {
+        self.event_fn = Some(cb);
+    }
+
     /// Determine the *signability* of a potentially signable entity.
     pub fn resolve_signability(
         &self,
@@ -915,15 +1230,21 @@ impl Signer {
             SignableCandidate::Forced(signable) => Ok(Signability::Signable(signable.clone())),
         }?;
 
-        // We don't yet support exporting the key back to PFX for Windows signing.
+        // Certificates in the Windows store can only be used via signtool.exe,
+        // which requires Windows. Other certificates are used to sign natively.
         if matches!(
             signability,
             Signability::Signable(Signable::WindowsFile(_))
                 | Signability::Signable(Signable::WindowsData(_))
-        ) && matches!(self.signing_certificate, SigningCertificate::Memory(_, _))
+        ) && matches!(
+            self.signing_certificate,
+            SigningCertificate::WindowsStoreSubject(_, _)
+                | SigningCertificate::WindowsStoreSha1Thumbprint(_, _)
+                | SigningCertificate::WindowsStoreAuto
+        ) && !cfg!(target_family = "windows")
         {
             Ok(Signability::PlatformUnsupported(
-                "do not support PFX key re-export on Windows",
+                "Windows certificate store signing requires running on Windows",
             ))
         } else {
             Ok(signability)
@@ -972,13 +1293,37 @@ pub struct SignableSigner<'a> {
     /// URL of Time-Stamp Protocol server to use.
     time_stamp_url: Option<Url>,
 
+    /// Compatibility profile to use when signing Apple signables.
+    apple_compatibility: AppleSigningCompatibility,
+
+    /// Code signature flags to apply when signing Apple signables.
+    apple_code_signature_flags: Option<CodeSignatureFlags>,
+
+    /// Entitlements plist XML to apply when signing Apple signables.
+    apple_entitlements_xml: Option<String>,
+
+    /// Binary designated requirement to apply when signing Apple signables.
+    apple_designated_requirement: Option<Vec<u8>>,
+
+    /// Notarization to perform after signing Apple bundles.
+    apple_notarization: Option<AppleNotarizationSettings>,
+
+    /// Apple signing settings scoped to bundle paths matching a pattern.
+    apple_path_settings: Vec<(glob::Pattern, ApplePathSigningSettings)>,
+
     /// Optional function to influence creation of [apple_codesign::SigningSettings]
     /// used for signing Apple signables.
     apple_signing_settings_fn: Option<Arc<AppleSigningSettingsFn>>,
 
+    /// Description of signed content to record in Windows signatures.
+    windows_description: Option<String>,
+
     /// Optional function to influence creation of [tugger_windows_codesign::SigntoolSign]
     /// used for signing Windows signables.
     windows_signer_fn: Option<Arc<WindowsSignerFn>>,
+
+    /// Optional function receiving events as signing progresses.
+    event_fn: Option<Arc<SigningEventFn>>,
 }
 
 impl<'a> SignableSigner<'a> {
@@ -992,8 +1337,23 @@ impl<'a> SignableSigner<'a> {
             signable,
             certificate_chain,
             time_stamp_url,
+            apple_compatibility: signer.apple_compatibility,
+            apple_code_signature_flags: signer.apple_code_signature_flags,
+            apple_entitlements_xml: signer.apple_entitlements_xml.clone(),
+            apple_designated_requirement: signer.apple_designated_requirement.clone(),
+            apple_notarization: signer.apple_notarization.clone(),
+            apple_path_settings: signer.apple_path_settings.clone(),
             apple_signing_settings_fn: signer.apple_signing_settings_fn.clone(),
+            windows_description: signer.windows_description.clone(),
             windows_signer_fn: signer.windows_signer_fn.clone(),
+            event_fn: signer.event_fn.clone(),
+        }
+    }
+
+    /// Deliver a [SigningEvent] to the registered callback, if any.
+    fn emit(&self, event: SigningEvent) {
+        if let Some(cb) = &self.event_fn {
+            cb(&event);
         }
     }
 
@@ -1002,6 +1362,14 @@ impl<'a> SignableSigner<'a> {
         &self.signable
     }
 
+    /// Set the code signature flags to use when signing this entity.
+    ///
+    /// This overrides any flags inherited from the [Signer]. The flags are
+    /// ignored for non-Apple signables.
+    pub fn set_apple_code_signature_flags(&mut self, flags: CodeSignatureFlags) {
+        self.apple_code_signature_flags = Some(flags);
+    }
+
     /// Obtain a [SigningDestination] that is the same as the input.
     pub fn in_place_destination(&self) -> SigningDestination {
         match &self.signable {
@@ -1040,15 +1408,54 @@ impl<'a> SignableSigner<'a> {
         }
 
         if let Some(url) = &self.time_stamp_url {
+            self.emit(SigningEvent::TimeStampRequest(url));
             settings
                 .set_time_stamp_url(url.clone())
                 .expect("shouldn't have failed for already parsed URL");
         } else {
+            let url = Url::parse(APPLE_TIMESTAMP_URL).expect("constant URL should parse");
+            self.emit(SigningEvent::TimeStampRequest(&url));
             settings
-                .set_time_stamp_url(APPLE_TIMESTAMP_URL)
+                .set_time_stamp_url(url)
                 .expect("shouldn't have failed for constant URL");
         }
 
+        match self.apple_compatibility {
+            AppleSigningCompatibility::Modern => {}
+            AppleSigningCompatibility::LegacySha1 => {
+                settings.set_digest_type(DigestType::Sha1);
+            }
+        }
+
+        if let Some(flags) = self.apple_code_signature_flags {
+            settings.set_code_signature_flags(SettingsScope::Main, flags);
+        }
+
+        if let Some(xml) = &self.apple_entitlements_xml {
+            settings
+                .set_entitlements_xml(SettingsScope::Main, xml)
+                .map_err(SigningError::AppleBundleSigningError)?;
+        }
+
+        if let Some(data) = &self.apple_designated_requirement {
+            settings
+                .set_designated_requirement_bytes(SettingsScope::Main, data)
+                .map_err(SigningError::AppleBundleSigningError)?;
+        }
+
+        if let Signable::AppleBundle(bundle_dir) = &self.signable {
+            for (path, path_settings) in self.resolve_apple_path_settings(bundle_dir)? {
+                if let Some(flags) = path_settings.code_signature_flags {
+                    settings.set_code_signature_flags(SettingsScope::Path(path.clone()), flags);
+                }
+                if let Some(xml) = &path_settings.entitlements_xml {
+                    settings
+                        .set_entitlements_xml(SettingsScope::Path(path.clone()), xml)
+                        .map_err(SigningError::AppleBundleSigningError)?;
+                }
+            }
+        }
+
         if let Some(cb) = &self.apple_signing_settings_fn {
             cb(&self.signable, &mut settings).map_err(SigningError::SettingsCallback)?;
         }
@@ -1056,6 +1463,108 @@ impl<'a> SignableSigner<'a> {
         Ok(settings)
     }
 
+    /// Resolve bundle-relative paths having registered [ApplePathSigningSettings].
+    ///
+    /// Walks the bundle directory and returns the merged settings of all
+    /// registered patterns matching each path, in traversal order.
+    fn resolve_apple_path_settings(
+        &self,
+        bundle_dir: &Path,
+    ) -> Result<Vec<(String, ApplePathSigningSettings)>, SigningError> {
+        if self.apple_path_settings.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut res = vec![];
+
+        for entry in walkdir::WalkDir::new(bundle_dir).sort_by_file_name() {
+            let entry = entry?;
+
+            let rel_path = bundle_relative_path(bundle_dir, entry.path());
+
+            if rel_path.is_empty() {
+                continue;
+            }
+
+            let mut merged: Option<ApplePathSigningSettings> = None;
+
+            for (pattern, settings) in &self.apple_path_settings {
+                if !pattern.matches_with(
+                    &rel_path,
+                    glob::MatchOptions {
+                        require_literal_separator: true,
+                        ..glob::MatchOptions::new()
+                    },
+                ) {
+                    continue;
+                }
+
+                let merged = merged.get_or_insert_with(ApplePathSigningSettings::default);
+
+                if settings.code_signature_flags.is_some() {
+                    merged.code_signature_flags = settings.code_signature_flags;
+                }
+                if settings.entitlements_xml.is_some() {
+                    merged.entitlements_xml = settings.entitlements_xml.clone();
+                }
+            }
+
+            if let Some(settings) = merged {
+                res.push((rel_path, settings));
+            }
+        }
+
+        Ok(res)
+    }
+
+    /// Whether Windows signables are signed without `signtool.exe`.
+    ///
+    /// Certificates held in memory always sign natively. PFX file
+    /// based certificates use `signtool.exe` when running on Windows so
+    /// [Signer::windows_settings_callback] is honored. Certificates in the
+    /// Windows store always require `signtool.exe`.
+    pub fn uses_native_windows_signing(&self) -> bool {
+        match self.signing_certificate {
+            SigningCertificate::Memory(_, _) => true,
+            SigningCertificate::PfxFile(_, _, _, _) => !cfg!(target_family = "windows"),
+            SigningCertificate::WindowsStoreSubject(_, _)
+            | SigningCertificate::WindowsStoreSha1Thumbprint(_, _)
+            | SigningCertificate::WindowsStoreAuto => false,
+        }
+    }
+
+    /// Obtain a [tugger_windows_codesign::AuthenticodeSigner] from this instance.
+    pub fn as_authenticode_signer(
+        &self,
+    ) -> Result<tugger_windows_codesign::AuthenticodeSigner<'_>, SigningError> {
+        let (cert, key) = match self.signing_certificate {
+            SigningCertificate::Memory(cert, key)
+            | SigningCertificate::PfxFile(_, _, cert, key) => (cert, key),
+            SigningCertificate::WindowsStoreSubject(_, _)
+            | SigningCertificate::WindowsStoreSha1Thumbprint(_, _)
+            | SigningCertificate::WindowsStoreAuto => {
+                return Err(SigningError::CertificateNotUsable("certificates in the Windows store can only be used via signtool.exe; try using a PFX file-based certificate instead".to_string()));
+            }
+        };
+
+        let mut signer = tugger_windows_codesign::AuthenticodeSigner::new(key, cert.clone());
+
+        for cert in &self.certificate_chain {
+            signer.chain_certificate(cert.clone());
+        }
+
+        if let Some(url) = &self.time_stamp_url {
+            self.emit(SigningEvent::TimeStampRequest(url));
+            signer.time_stamp_url(url);
+        }
+
+        if let Some(description) = &self.windows_description {
+            signer.description(description);
+        }
+
+        Ok(signer)
+    }
+
     /// Obtain a [tugger_windows_codesign::SigntoolSign] from this instance.
     pub fn as_windows_signer(&self) -> Result<tugger_windows_codesign::SigntoolSign, SigningError> {
         let cert = self
@@ -1065,6 +1574,7 @@ impl<'a> SignableSigner<'a> {
         let mut signer = tugger_windows_codesign::SigntoolSign::new(cert);
 
         if let Some(url) = &self.time_stamp_url {
+            self.emit(SigningEvent::TimeStampRequest(url));
             signer.timestamp_server(tugger_windows_codesign::TimestampServer::Rfc3161(
                 url.to_string(),
                 "SHA256".to_string(),
@@ -1073,6 +1583,10 @@ impl<'a> SignableSigner<'a> {
 
         signer.file_digest_algorithm("SHA256");
 
+        if let Some(description) = &self.windows_description {
+            signer.description(description);
+        }
+
         if let Some(cb) = &self.windows_signer_fn {
             cb(&self.signable, &mut signer).map_err(SigningError::SettingsCallback)?;
         }
@@ -1112,6 +1626,27 @@ impl<'a> SignableSigner<'a> {
             return Err(SigningError::IncompatibleSigningDestination(reason));
         }
 
+        self.emit(SigningEvent::SigningStarted(&self.signable, destination));
+
+        match self.sign_inner(temp_dir, destination) {
+            Ok(output) => {
+                self.emit(SigningEvent::SigningFinished(&self.signable, &output));
+
+                Ok(output)
+            }
+            Err(err) => {
+                self.emit(SigningEvent::SigningFailed(&self.signable, &err));
+
+                Err(err)
+            }
+        }
+    }
+
+    fn sign_inner(
+        &self,
+        temp_dir: Option<&Path>,
+        destination: &SigningDestination,
+    ) -> Result<SignedOutput, SigningError> {
         let temp_dir = if self.requires_temporary_files(destination) {
             let mut builder = tempfile::Builder::new();
             builder.prefix("tugger-code-sign-");
@@ -1126,6 +1661,17 @@ impl<'a> SignableSigner<'a> {
         };
 
         match &self.signable {
+            Signable::WindowsData(data) if self.uses_native_windows_signing() => {
+                warn!(
+                    "signing Windows binary from in-memory data of size {} bytes",
+                    data.len()
+                );
+                self.sign_windows_native(data, destination)
+            }
+            Signable::WindowsFile(source_file) if self.uses_native_windows_signing() => {
+                warn!("signing {}", source_file.display());
+                self.sign_windows_native(&std::fs::read(source_file)?, destination)
+            }
             Signable::WindowsData(data) => {
                 let mut signer = self.as_windows_signer()?;
 
@@ -1141,8 +1687,10 @@ impl<'a> SignableSigner<'a> {
                     sign_path.display()
                 );
                 std::fs::write(&sign_path, data)?;
+                self.emit(SigningEvent::TemporaryFileCreated(&sign_path));
 
                 signer.sign_file(&sign_path);
+                self.emit(SigningEvent::SignatureCreationStarted(&self.signable));
                 signer.run().map_err(SigningError::SigntoolError)?;
 
                 match destination {
@@ -1188,6 +1736,7 @@ impl<'a> SignableSigner<'a> {
                         sign_path.display()
                     );
                     std::fs::copy(source_file, &sign_path)?;
+                    self.emit(SigningEvent::TemporaryFileCreated(&sign_path));
 
                     sign_path
                 } else {
@@ -1196,6 +1745,7 @@ impl<'a> SignableSigner<'a> {
                 };
 
                 signer.sign_file(&sign_path);
+                self.emit(SigningEvent::SignatureCreationStarted(&self.signable));
                 signer.run().map_err(SigningError::SigntoolError)?;
 
                 match destination {
@@ -1232,6 +1782,7 @@ impl<'a> SignableSigner<'a> {
                     .map_err(SigningError::MachOSigningError)?;
 
                 let mut dest = Vec::<u8>::with_capacity(macho_data.len() + 2_usize.pow(17));
+                self.emit(SigningEvent::SignatureCreationStarted(&self.signable));
                 signer
                     .write_signed_binary(&settings, &mut dest)
                     .map_err(SigningError::MachOSigningError)?;
@@ -1260,6 +1811,7 @@ impl<'a> SignableSigner<'a> {
                     .map_err(SigningError::MachOSigningError)?;
 
                 let mut dest = Vec::<u8>::with_capacity(macho_data.len() + 2_usize.pow(17));
+                self.emit(SigningEvent::SignatureCreationStarted(&self.signable));
                 signer
                     .write_signed_binary(&settings, &mut dest)
                     .map_err(SigningError::MachOSigningError)?;
@@ -1294,18 +1846,133 @@ impl<'a> SignableSigner<'a> {
                     dest_dir.display()
                 );
 
-                let signer = apple_codesign::BundleSigner::new_from_path(source_dir)
+                // Copy the bundle ourselves and sign the copy in place so
+                // progress can be reported as files are processed.
+                if copy_file_needed(source_dir, dest_dir)? {
+                    self.copy_apple_bundle(source_dir, dest_dir)?;
+                }
+
+                let signer = apple_codesign::BundleSigner::new_from_path(dest_dir)
                     .map_err(SigningError::AppleBundleSigningError)?;
 
+                self.emit(SigningEvent::SignatureCreationStarted(&self.signable));
                 signer
                     .write_signed_bundle(dest_dir, &settings)
                     .map_err(SigningError::AppleBundleSigningError)?;
 
+                if let Some(notarization) = &self.apple_notarization {
+                    self.notarize_apple_path(notarization, dest_dir)?;
+                }
+
                 Ok(SignedOutput::Directory(dest_dir.clone()))
             }
         }
     }
 
+    /// Copy an Apple bundle to a directory, emitting an event for every entry.
+    ///
+    /// Symlinks are copied as symlinks.
+    fn copy_apple_bundle(&self, source_dir: &Path, dest_dir: &Path) -> Result<(), SigningError> {
+        for entry in walkdir::WalkDir::new(source_dir).sort_by_file_name() {
+            let entry = entry?;
+
+            let rel_path = bundle_relative_path(source_dir, entry.path());
+            let dest_path = dest_dir.join(
+                entry
+                    .path()
+                    .strip_prefix(source_dir)
+                    .expect("path should be relative to bundle directory"),
+            );
+
+            if entry.file_type().is_dir() {
+                std::fs::create_dir_all(&dest_path)?;
+            } else if entry.file_type().is_symlink() {
+                if dest_path.symlink_metadata().is_ok() {
+                    std::fs::remove_file(&dest_path)?;
+                }
+
+                create_symlink(
+                    &std::fs::read_link(entry.path())?,
+                    &dest_path,
+                    entry.path().is_dir(),
+                )?;
+            } else {
+                std::fs::copy(entry.path(), &dest_path)?;
+            }
+
+            if !rel_path.is_empty() {
+                self.emit(SigningEvent::AppleBundleFileCopied(&rel_path));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Notarize a signed Apple entity and optionally staple the ticket to it.
+    ///
+    /// Blocks until the Notary API has reached a verdict.
+    fn notarize_apple_path(
+        &self,
+        notarization: &AppleNotarizationSettings,
+        path: &Path,
+    ) -> Result<(), SigningError> {
+        self.emit(SigningEvent::AppleNotarizationStarted(path));
+
+        let mut notarizer = apple_codesign::notarization::Notarizer::new()
+            .map_err(SigningError::AppleNotarizationError)?;
+        notarizer
+            .set_api_key(&notarization.api_issuer, &notarization.api_key)
+            .map_err(SigningError::AppleNotarizationError)?;
+
+        warn!("notarizing {}", path.display());
+        notarizer
+            .notarize_path(path, Some(std::time::Duration::from_secs(600)))
+            .map_err(SigningError::AppleNotarizationError)?;
+
+        if notarization.staple {
+            warn!("stapling notarization ticket to {}", path.display());
+            apple_codesign::stapling::Stapler::new()
+                .map_err(SigningError::AppleNotarizationError)?
+                .staple_path(path)
+                .map_err(SigningError::AppleNotarizationError)?;
+        }
+
+        Ok(())
+    }
+
+    /// Sign Windows data with [tugger_windows_codesign::AuthenticodeSigner].
+    fn sign_windows_native(
+        &self,
+        data: &[u8],
+        destination: &SigningDestination,
+    ) -> Result<SignedOutput, SigningError> {
+        let signer = self.as_authenticode_signer()?;
+
+        self.emit(SigningEvent::SignatureCreationStarted(&self.signable));
+        let signed = signer
+            .sign_data(data)
+            .map_err(SigningError::AuthenticodeError)?;
+
+        match destination {
+            SigningDestination::Memory => {
+                warn!("signing success");
+                Ok(SignedOutput::Memory(signed))
+            }
+            SigningDestination::File(dest_path) => {
+                warn!(
+                    "signing success; writing signed file to {}",
+                    dest_path.display()
+                );
+                std::fs::write(dest_path, &signed)?;
+
+                Ok(SignedOutput::File(dest_path.clone()))
+            }
+            SigningDestination::Directory(_) => {
+                panic!("illegal signing combination: Windows signable -> Directory");
+            }
+        }
+    }
+
     /// Whether signing to the specified [SigningDestination] will require temporary files.
     ///
     /// Temporary files are used when:
@@ -1317,8 +1984,17 @@ impl<'a> SignableSigner<'a> {
     ///   to play it safe and only write to the destination after signing success.
     ///   By going through a temporary directory, we prevent polluting the destination
     ///   with corrupted results.
+    ///
+    /// Native Authenticode signing operates in memory and never requires
+    /// temporary files.
     pub fn requires_temporary_files(&self, destination: &SigningDestination) -> bool {
         match &self.signable {
+            // Native Authenticode signing does everything in memory.
+            Signable::WindowsData(_) | Signable::WindowsFile(_)
+                if self.uses_native_windows_signing() =>
+            {
+                false
+            }
             // signtool only supports signing files. We'll have to persist data to a file.
             Signable::WindowsData(_) => true,
             Signable::WindowsFile(source_file) => match destination {
@@ -1343,6 +2019,36 @@ impl<'a> SignableSigner<'a> {
     }
 }
 
+/// Obtain the `/` delimited path of a bundle member relative to the bundle root.
+fn bundle_relative_path(bundle_dir: &Path, path: &Path) -> String {
+    path.strip_prefix(bundle_dir)
+        .expect("path should be relative to bundle directory")
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Create a symlink at `link` pointing to `target`.
+///
+/// `is_dir` says whether the target is a directory, which matters on Windows.
+fn create_symlink(target: &Path, link: &Path, is_dir: bool) -> Result<(), std::io::Error> {
+    #[cfg(unix)]
+    {
+        let _ = is_dir;
+        std::os::unix::fs::symlink(target, link)
+    }
+
+    #[cfg(windows)]
+    {
+        if is_dir {
+            std::os::windows::fs::symlink_dir(target, link)
+        } else {
+            std::os::windows::fs::symlink_file(target, link)
+        }
+    }
+}
+
 /// Whether a request to copy between 2 paths needs to be fulfilled.
 ///
 /// We can run into cases where we are writing to the input file but we don't
@@ -1387,6 +2093,292 @@ mod tests {
         SigningCertificate::from_pfx_data(&pfx_data, "password").unwrap();
     }
 
+    #[test]
+    fn code_signature_flags_from_names() -> Result<(), SigningError> {
+        assert_eq!(
+            apple_code_signature_flags_from_names(vec![])?,
+            CodeSignatureFlags::empty()
+        );
+        assert_eq!(
+            apple_code_signature_flags_from_names(vec!["runtime", "library-validation"])?,
+            CodeSignatureFlags::RUNTIME | CodeSignatureFlags::LIBRARY_VALIDATION
+        );
+        assert_eq!(
+            apple_code_signature_flags_from_names(vec!["restrict", "kill"])?,
+            CodeSignatureFlags::RESTRICT | CodeSignatureFlags::FORCE_KILL
+        );
+        assert!(matches!(
+            apple_code_signature_flags_from_names(vec!["bogus"]),
+            Err(SigningError::UnknownCodeSignatureFlag(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn apple_path_settings_resolution() -> Result<(), SigningError> {
+        let td = tempfile::Builder::new()
+            .prefix("tugger-code-signing-test")
+            .tempdir()?;
+        let bundle = td.path().join("App.app");
+        std::fs::create_dir_all(bundle.join("Contents/MacOS"))?;
+        std::fs::create_dir_all(bundle.join("Contents/Helpers/Helper.app/Contents/MacOS"))?;
+        std::fs::write(bundle.join("Contents/MacOS/app"), b"main")?;
+        std::fs::write(
+            bundle.join("Contents/Helpers/Helper.app/Contents/MacOS/helper"),
+            b"helper",
+        )?;
+
+        let mut signer = Signer::new(SigningCertificate::from_pfx_data(
+            APPLE_P12_DATA,
+            "password123",
+        )?);
+        signer.apple_path_settings(
+            "Contents/Helpers/*.app",
+            ApplePathSigningSettings {
+                code_signature_flags: Some(CodeSignatureFlags::RUNTIME),
+                ..Default::default()
+            },
+        )?;
+        signer.apple_path_settings(
+            "Contents/Helpers/Helper.app",
+            ApplePathSigningSettings {
+                entitlements_xml: Some("<plist/>".to_string()),
+                ..Default::default()
+            },
+        )?;
+        assert!(signer
+            .apple_path_settings("[", ApplePathSigningSettings::default())
+            .is_err());
+
+        let signable_signer = SignableSigner::new(&signer, Signable::AppleBundle(bundle.clone()));
+        let resolved = signable_signer.resolve_apple_path_settings(&bundle)?;
+
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].0, "Contents/Helpers/Helper.app");
+        assert_eq!(
+            resolved[0].1.code_signature_flags,
+            Some(CodeSignatureFlags::RUNTIME)
+        );
+        assert_eq!(resolved[0].1.entitlements_xml, Some("<plist/>".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn apple_path_settings_overlapping() -> Result<(), SigningError> {
+        let td = tempfile::Builder::new()
+            .prefix("tugger-code-signing-test")
+            .tempdir()?;
+        let bundle = td.path().join("App.app");
+        std::fs::create_dir_all(bundle.join("Contents/Helpers/A.app"))?;
+        std::fs::create_dir_all(bundle.join("Contents/Helpers/B.app"))?;
+
+        let mut signer = Signer::new(SigningCertificate::from_pfx_data(
+            APPLE_P12_DATA,
+            "password123",
+        )?);
+        signer.apple_path_settings(
+            "Contents/Helpers/*.app",
+            ApplePathSigningSettings {
+                code_signature_flags: Some(CodeSignatureFlags::RUNTIME),
+                entitlements_xml: Some("<plist>all</plist>".to_string()),
+            },
+        )?;
+        signer.apple_path_settings(
+            "Contents/Helpers/A.app",
+            ApplePathSigningSettings {
+                code_signature_flags: Some(CodeSignatureFlags::HARD),
+                ..Default::default()
+            },
+        )?;
+        signer.apple_path_settings(
+            "Contents/Helpers/B.app",
+            ApplePathSigningSettings {
+                entitlements_xml: Some("<plist>b</plist>".to_string()),
+                ..Default::default()
+            },
+        )?;
+
+        let signable_signer = SignableSigner::new(&signer, Signable::AppleBundle(bundle.clone()));
+        let resolved = signable_signer.resolve_apple_path_settings(&bundle)?;
+
+        assert_eq!(resolved.len(), 2);
+
+        // Later patterns win for the fields they define. Other fields are
+        // retained from earlier patterns.
+        assert_eq!(resolved[0].0, "Contents/Helpers/A.app");
+        assert_eq!(
+            resolved[0].1.code_signature_flags,
+            Some(CodeSignatureFlags::HARD)
+        );
+        assert_eq!(
+            resolved[0].1.entitlements_xml,
+            Some("<plist>all</plist>".to_string())
+        );
+
+        assert_eq!(resolved[1].0, "Contents/Helpers/B.app");
+        assert_eq!(
+            resolved[1].1.code_signature_flags,
+            Some(CodeSignatureFlags::RUNTIME)
+        );
+        assert_eq!(
+            resolved[1].1.entitlements_xml,
+            Some("<plist>b</plist>".to_string())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn event_callback() -> Result<(), SigningError> {
+        let events = Arc::new(std::sync::Mutex::new(Vec::<String>::new()));
+
+        let mut signer = Signer::new(SigningCertificate::from_pfx_data(
+            APPLE_P12_DATA,
+            "password123",
+        )?);
+
+        let events_cb = events.clone();
+        signer.event_callback(Arc::new(move |event| {
+            events_cb.lock().unwrap().push(format!("{}", event));
+        }));
+
+        let signable_signer = SignableSigner::new(&signer, Signable::MachOData(vec![0; 4]));
+        signable_signer.as_apple_signing_settings()?;
+
+        assert_eq!(
+            events.lock().unwrap().as_slice(),
+            &["time-stamp server: http://timestamp.apple.com/ts01".to_string()]
+        );
+
+        // Failures are reported after signing started.
+        events.lock().unwrap().clear();
+        assert!(signable_signer
+            .sign(None, &SigningDestination::Memory)
+            .is_err());
+
+        let events = events.lock().unwrap();
+        assert_eq!(
+            events[0],
+            "signing started: Mach-O data (4 bytes) -> Memory"
+        );
+        assert!(events
+            .last()
+            .unwrap()
+            .starts_with("signing failed: Mach-O data (4 bytes): "));
+        assert!(!events.iter().any(|e| e.starts_with("signing finished")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn copy_apple_bundle_events() -> Result<(), SigningError> {
+        let td = tempfile::Builder::new()
+            .prefix("tugger-code-signing-test")
+            .tempdir()?;
+        let source = td.path().join("source/App.app");
+        let dest = td.path().join("dest/App.app");
+        std::fs::create_dir_all(source.join("Contents/MacOS"))?;
+        std::fs::write(source.join("Contents/Info.plist"), b"plist")?;
+        std::fs::write(source.join("Contents/MacOS/app"), b"main")?;
+
+        let events = Arc::new(std::sync::Mutex::new(Vec::<String>::new()));
+
+        let mut signer = Signer::new(SigningCertificate::from_pfx_data(
+            APPLE_P12_DATA,
+            "password123",
+        )?);
+
+        let events_cb = events.clone();
+        signer.event_callback(Arc::new(move |event| {
+            events_cb.lock().unwrap().push(format!("{}", event));
+        }));
+
+        let signable_signer = SignableSigner::new(&signer, Signable::AppleBundle(source.clone()));
+        signable_signer.copy_apple_bundle(&source, &dest)?;
+
+        assert_eq!(std::fs::read(dest.join("Contents/MacOS/app"))?, b"main");
+        assert_eq!(
+            events.lock().unwrap().as_slice(),
+            &[
+                "bundle file copied: Contents".to_string(),
+                "bundle file copied: Contents/Info.plist".to_string(),
+                "bundle file copied: Contents/MacOS".to_string(),
+                "bundle file copied: Contents/MacOS/app".to_string(),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn apple_signing_compatibility() -> Result<(), SigningError> {
+        assert_eq!(
+            AppleSigningCompatibility::try_from("modern"),
+            Ok(AppleSigningCompatibility::Modern)
+        );
+        assert_eq!(
+            AppleSigningCompatibility::try_from("legacy-sha1"),
+            Ok(AppleSigningCompatibility::LegacySha1)
+        );
+        assert!(AppleSigningCompatibility::try_from("sha1").is_err());
+
+        let mut signer = Signer::new(SigningCertificate::from_pfx_data(
+            APPLE_P12_DATA,
+            "password123",
+        )?);
+
+        let signable_signer = SignableSigner::new(&signer, Signable::MachOData(vec![]));
+        let settings = signable_signer.as_apple_signing_settings()?;
+        assert_eq!(settings.digest_type(), &DigestType::Sha256);
+
+        signer.apple_signing_compatibility(AppleSigningCompatibility::LegacySha1);
+        let signable_signer = SignableSigner::new(&signer, Signable::MachOData(vec![]));
+        let settings = signable_signer.as_apple_signing_settings()?;
+        assert_eq!(settings.digest_type(), &DigestType::Sha1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn sign_windows_native() -> Result<(), SigningError> {
+        // A minimal PE32+ file having no sections.
+        let mut pe = vec![0u8; 0x400];
+        pe[0..2].copy_from_slice(b"MZ");
+        pe[0x3c..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        pe[0x40..0x44].copy_from_slice(b"PE\0\0");
+        pe[0x44..0x46].copy_from_slice(&0x8664u16.to_le_bytes());
+        pe[0x54..0x56].copy_from_slice(&0xf0u16.to_le_bytes());
+        pe[0x58..0x5a].copy_from_slice(&0x20bu16.to_le_bytes());
+        pe[0xc4..0xc8].copy_from_slice(&16u32.to_le_bytes());
+
+        let mut signer = Signer::new(SigningCertificate::from_pfx_data(
+            WINDOWS_PFX_DEFAULT_DATA,
+            "password123",
+        )?);
+        signer.windows_description("test binary");
+
+        let signable_signer = signer
+            .resolve_signer(&SignableCandidate::Data(pe.clone().into()))?
+            .expect("PE data should be signable");
+        assert!(signable_signer.uses_native_windows_signing());
+        assert!(!signable_signer.requires_temporary_files(&SigningDestination::Memory));
+
+        let signed = match signable_signer.sign(None, &SigningDestination::Memory)? {
+            SignedOutput::Memory(data) => data,
+            _ => panic!("expected in-memory output"),
+        };
+
+        assert!(signed.len() > pe.len());
+        assert_eq!(
+            &signed[pe.len() + 4..pe.len() + 8],
+            &[0x00, 0x02, 0x02, 0x00]
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn windows_store_with_subject() {
         let cert = SigningCertificate::windows_store_with_subject("my", "test user").unwrap();
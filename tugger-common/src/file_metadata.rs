@@ -0,0 +1,109 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use {
+    anyhow::{anyhow, Context, Result},
+    std::path::Path,
+};
+
+/// Metadata for a file beyond its content and executable bit.
+///
+/// All fields are optional. Consumers fall back to their own defaults
+/// (typically `root` ownership, `0o755` or `0o644` permissions depending on
+/// the executable bit, and a fixed modified time) for undefined fields.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FileMetadata {
+    /// Unix permission bits, including setuid, setgid, and sticky bits.
+    ///
+    /// e.g. `0o644` or `0o4755`.
+    pub mode: Option<u32>,
+
+    /// Numeric ID of the owning user.
+    pub uid: Option<u64>,
+
+    /// Numeric ID of the owning group.
+    pub gid: Option<u64>,
+
+    /// Name of the owning user. e.g. `root`.
+    pub user: Option<String>,
+
+    /// Name of the owning group. e.g. `root`.
+    pub group: Option<String>,
+
+    /// Modified time, in seconds since the UNIX epoch.
+    pub mtime: Option<u64>,
+}
+
+impl FileMetadata {
+    /// Whether no metadata is defined.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Ensure defined metadata is valid.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(mode) = self.mode {
+            if mode & !0o7777 != 0 {
+                return Err(anyhow!(
+                    "mode {:#o} contains bits other than permission bits",
+                    mode
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Resolve the permission bits of a file.
+    ///
+    /// If no mode is defined, this is `0o755` for executable files and
+    /// `0o644` otherwise.
+    pub fn resolve_mode(&self, executable: bool) -> u32 {
+        self.mode.unwrap_or(if executable { 0o755 } else { 0o644 })
+    }
+
+    /// Overlay fields defined by another instance onto this one.
+    pub fn merge(&mut self, other: &Self) {
+        if other.mode.is_some() {
+            self.mode = other.mode;
+        }
+        if other.uid.is_some() {
+            self.uid = other.uid;
+        }
+        if other.gid.is_some() {
+            self.gid = other.gid;
+        }
+        if other.user.is_some() {
+            self.user = other.user.clone();
+        }
+        if other.group.is_some() {
+            self.group = other.group.clone();
+        }
+        if other.mtime.is_some() {
+            self.mtime = other.mtime;
+        }
+    }
+
+    /// Apply metadata to a file on the filesystem.
+    ///
+    /// The mode and modified time are applied. Ownership is not, as changing
+    /// it typically requires elevated privileges. Permission bits are only
+    /// applied on Unix.
+    pub fn apply_to_path(&self, path: &Path) -> Result<()> {
+        #[cfg(unix)]
+        if let Some(mode) = self.mode {
+            use std::os::unix::fs::PermissionsExt;
+
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+                .with_context(|| format!("setting mode of {}", path.display()))?;
+        }
+
+        if let Some(mtime) = self.mtime {
+            filetime::set_file_mtime(path, filetime::FileTime::from_unix_time(mtime as i64, 0))
+                .with_context(|| format!("setting modified time of {}", path.display()))?;
+        }
+
+        Ok(())
+    }
+}
@@ -106,7 +106,14 @@ pub fn download_to_path<P: AsRef<Path>>(entry: &RemoteContent, dest_path: P) ->
 
     let expected_hash = hex::decode(&entry.sha256)?;
 
-    let lock_path = dest_path.with_extension("lock");
+    let file_name = dest_path
+        .file_name()
+        .ok_or_else(|| anyhow!("unable to obtain file name"))?
+        .to_string_lossy();
+
+    // Derive from the full file name so files differing only by extension
+    // don't share a lock.
+    let lock_path = dest_path.with_file_name(format!("{}.lock", file_name));
     let lock = std::fs::File::create(&lock_path)
         .with_context(|| format!("creating {}", lock_path.display()))?;
     lock.lock_exclusive().context("obtaining lock")?;
@@ -124,13 +131,7 @@ pub fn download_to_path<P: AsRef<Path>>(entry: &RemoteContent, dest_path: P) ->
     }
 
     let data = download_and_verify(entry).context("downloading with verification")?;
-    let temp_path = dest_path.with_file_name(format!(
-        "{}.tmp",
-        dest_path
-            .file_name()
-            .ok_or_else(|| anyhow!("unable to obtain file name"))?
-            .to_string_lossy()
-    ));
+    let temp_path = dest_path.with_file_name(format!("{}.tmp", file_name));
 
     std::fs::write(&temp_path, data).context("writing data to temporary file")?;
     std::fs::rename(&temp_path, dest_path).with_context(|| {
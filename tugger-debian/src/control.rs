@@ -0,0 +1,181 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use {
+    anyhow::{anyhow, Result},
+    std::fmt::{Display, Formatter},
+};
+
+/// A paragraph in a Debian control file.
+///
+/// A paragraph is an ordered series of fields. Field names are matched
+/// case-insensitively but retain the case they were defined with.
+///
+/// Values spanning multiple lines (such as `Description`) are stored with
+/// `\n` delimiting lines. Continuation line prefixes are added and removed
+/// during serialization and parsing.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ControlParagraph {
+    fields: Vec<(String, String)>,
+}
+
+impl ControlParagraph {
+    /// Parse a paragraph from its serialized form.
+    pub fn parse(data: &str) -> Result<Self> {
+        let mut paragraph = Self::default();
+        let mut current: Option<(String, String)> = None;
+
+        for line in data.lines() {
+            if line.trim().is_empty() {
+                break;
+            }
+
+            if line.starts_with(' ') || line.starts_with('\t') {
+                let (_, value) = current
+                    .as_mut()
+                    .ok_or_else(|| anyhow!("continuation line without a field: {}", line))?;
+
+                let line = line[1..].trim_end();
+                value.push('\n');
+                if line != "." {
+                    value.push_str(line);
+                }
+            } else {
+                if let Some((name, value)) = current.take() {
+                    paragraph.set_field(name, value);
+                }
+
+                let (name, value) = line
+                    .split_once(':')
+                    .ok_or_else(|| anyhow!("malformed control file line: {}", line))?;
+
+                current = Some((name.to_string(), value.trim().to_string()));
+            }
+        }
+
+        if let Some((name, value)) = current {
+            paragraph.set_field(name, value);
+        }
+
+        Ok(paragraph)
+    }
+
+    /// Obtain the value of a field.
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Set the value of a field.
+    ///
+    /// An existing field with the same name is replaced in place. Otherwise
+    /// the field is appended.
+    pub fn set_field(&mut self, name: impl ToString, value: impl ToString) {
+        let name = name.to_string();
+        let value = value.to_string();
+
+        if let Some(entry) = self
+            .fields
+            .iter_mut()
+            .find(|(k, _)| k.eq_ignore_ascii_case(&name))
+        {
+            entry.1 = value;
+        } else {
+            self.fields.push((name, value));
+        }
+    }
+
+    /// Remove a field, returning its value if it was present.
+    pub fn remove_field(&mut self, name: &str) -> Option<String> {
+        let index = self
+            .fields
+            .iter()
+            .position(|(k, _)| k.eq_ignore_ascii_case(name))?;
+
+        Some(self.fields.remove(index).1)
+    }
+
+    /// Iterate over fields in this paragraph.
+    pub fn iter_fields(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.fields.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
+
+impl Display for ControlParagraph {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (name, value) in &self.fields {
+            let mut lines = value.split('\n');
+
+            writeln!(f, "{}: {}", name, lines.next().unwrap_or_default())?;
+
+            for line in lines {
+                if line.is_empty() {
+                    writeln!(f, " .")?;
+                } else {
+                    writeln!(f, " {}", line)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_field() {
+        let mut p = ControlParagraph::default();
+        p.set_field("Package", "foo");
+        p.set_field("Version", "1.0");
+        p.set_field("package", "bar");
+
+        assert_eq!(p.field("PACKAGE"), Some("bar"));
+        assert_eq!(
+            p.iter_fields().collect::<Vec<_>>(),
+            vec![("Package", "bar"), ("Version", "1.0")]
+        );
+
+        assert_eq!(p.remove_field("version"), Some("1.0".to_string()));
+        assert_eq!(p.field("Version"), None);
+    }
+
+    #[test]
+    fn test_serialize() {
+        let mut p = ControlParagraph::default();
+        p.set_field("Package", "foo");
+        p.set_field(
+            "Description",
+            "summary\nfirst paragraph\n\nsecond paragraph",
+        );
+
+        assert_eq!(
+            p.to_string(),
+            "Package: foo\nDescription: summary\n first paragraph\n .\n second paragraph\n"
+        );
+    }
+
+    #[test]
+    fn test_parse() -> Result<()> {
+        let p = ControlParagraph::parse(
+            "Package: foo\nDescription: summary\n first paragraph\n .\n second paragraph\n",
+        )?;
+
+        assert_eq!(p.field("Package"), Some("foo"));
+        assert_eq!(
+            p.field("Description"),
+            Some("summary\nfirst paragraph\n\nsecond paragraph")
+        );
+        assert_eq!(ControlParagraph::parse(&p.to_string())?, p);
+
+        assert!(ControlParagraph::parse(" continuation").is_err());
+        assert!(ControlParagraph::parse("no separator").is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,979 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use {
+    crate::{
+        control::ControlParagraph,
+        shlibs::{find_needed_shared_libraries, merge_relations, ShlibsDatabase},
+    },
+    anyhow::{anyhow, Context, Result},
+    md5::{Digest, Md5},
+    simple_file_manifest::{FileEntry, FileManifest},
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        io::Write,
+        path::{Path, PathBuf},
+    },
+    tugger_common::file_metadata::FileMetadata,
+};
+
+/// Names of maintainer scripts that can be present in a `.deb`.
+///
+/// Scripts are written to `control.tar` in this order.
+pub const MAINTAINER_SCRIPTS: &[&str] = &["preinst", "postinst", "prerm", "postrm"];
+
+/// Directives that can appear in a `triggers` control file.
+pub const TRIGGER_DIRECTIVES: &[&str] = &[
+    "interest",
+    "interest-await",
+    "interest-noawait",
+    "activate",
+    "activate-await",
+    "activate-noawait",
+];
+
+/// Control fields that must be defined to build a `.deb`.
+const REQUIRED_FIELDS: &[&str] = &[
+    "Package",
+    "Version",
+    "Architecture",
+    "Maintainer",
+    "Description",
+];
+
+/// Build `.deb` files.
+///
+/// Instances are bound to a [ControlParagraph] defining the binary package's
+/// `control` file, files and symlinks to install, and optional maintainer
+/// scripts, conffiles, and triggers.
+///
+/// `.deb` files are assembled in-process, so building doesn't require
+/// `dpkg-deb` or a Debian-based system. Archive content is deterministic:
+/// by default, files are owned by `root` and have the modified time set by
+/// [Self::set_mtime]. [Self::set_file_metadata] overrides the mode,
+/// ownership, and modified time of individual files.
+///
+/// If not defined, the `Installed-Size` control field is derived from the
+/// size of installed files.
+#[derive(Clone, Debug)]
+pub struct DebBuilder {
+    control: ControlParagraph,
+    install_files: FileManifest,
+    symlinks: BTreeMap<PathBuf, PathBuf>,
+    file_metadata: BTreeMap<PathBuf, FileMetadata>,
+    conffiles: BTreeSet<PathBuf>,
+    maintainer_scripts: BTreeMap<String, Vec<u8>>,
+    triggers: BTreeSet<(String, String)>,
+    mtime: u64,
+}
+
+impl DebBuilder {
+    /// Create a new builder using the specified `control` file paragraph.
+    pub fn new(control: ControlParagraph) -> Self {
+        Self {
+            control,
+            install_files: FileManifest::default(),
+            symlinks: BTreeMap::new(),
+            file_metadata: BTreeMap::new(),
+            conffiles: BTreeSet::new(),
+            maintainer_scripts: BTreeMap::new(),
+            triggers: BTreeSet::new(),
+            mtime: 0,
+        }
+    }
+
+    /// Obtain the `control` file paragraph.
+    pub fn control(&self) -> &ControlParagraph {
+        &self.control
+    }
+
+    /// Obtain a mutable reference to the `control` file paragraph.
+    pub fn control_mut(&mut self) -> &mut ControlParagraph {
+        &mut self.control
+    }
+
+    /// Obtain the files to be installed by the package.
+    pub fn install_files(&self) -> &FileManifest {
+        &self.install_files
+    }
+
+    /// Add a file to install.
+    ///
+    /// The path is relative to the filesystem root. e.g. `usr/bin/foo`.
+    pub fn install_file(
+        &mut self,
+        path: impl AsRef<Path>,
+        entry: impl Into<FileEntry>,
+    ) -> Result<()> {
+        self.install_files
+            .add_file_entry(normalize_path(path.as_ref()), entry)?;
+
+        Ok(())
+    }
+
+    /// Add files to install from the content of an existing [FileManifest].
+    ///
+    /// Paths are relative to the filesystem root. e.g. `usr/bin/foo`.
+    pub fn install_manifest(&mut self, manifest: &FileManifest) -> Result<()> {
+        self.install_files.add_manifest(manifest)?;
+
+        Ok(())
+    }
+
+    /// Obtain symlinks to be installed by the package.
+    ///
+    /// Keys are the paths of links and values are their targets.
+    pub fn symlinks(&self) -> &BTreeMap<PathBuf, PathBuf> {
+        &self.symlinks
+    }
+
+    /// Add a symlink to install.
+    ///
+    /// The path of the link is relative to the filesystem root. e.g.
+    /// `usr/bin/foo`. The target is stored verbatim and can be relative to the
+    /// link's directory or absolute.
+    pub fn install_symlink(
+        &mut self,
+        path: impl AsRef<Path>,
+        target: impl AsRef<Path>,
+    ) -> Result<()> {
+        let path = normalize_path(path.as_ref());
+
+        if self.install_files.has_path(path) {
+            return Err(anyhow!(
+                "cannot add symlink {}: a file is installed at that path",
+                path.display()
+            ));
+        }
+
+        self.symlinks
+            .insert(path.to_path_buf(), target.as_ref().to_path_buf());
+
+        Ok(())
+    }
+
+    /// Obtain metadata of installed files, keyed by path.
+    pub fn file_metadata(&self) -> &BTreeMap<PathBuf, FileMetadata> {
+        &self.file_metadata
+    }
+
+    /// Define the metadata of an installed file.
+    ///
+    /// The path is relative to the filesystem root. Defined fields override
+    /// the default mode, `root` ownership, and modified time of the file in
+    /// the package.
+    pub fn set_file_metadata(
+        &mut self,
+        path: impl AsRef<Path>,
+        metadata: FileMetadata,
+    ) -> Result<()> {
+        metadata
+            .validate()
+            .with_context(|| format!("validating metadata of {}", path.as_ref().display()))?;
+
+        self.file_metadata
+            .insert(normalize_path(path.as_ref()).to_path_buf(), metadata);
+
+        Ok(())
+    }
+
+    /// Mark an installed file as a conffile.
+    ///
+    /// dpkg preserves local modifications to conffiles during upgrades. The
+    /// path is relative to the filesystem root and must be installed by the
+    /// package at build time.
+    pub fn add_conffile(&mut self, path: impl AsRef<Path>) {
+        self.conffiles
+            .insert(normalize_path(path.as_ref()).to_path_buf());
+    }
+
+    /// Define the content of a maintainer script.
+    ///
+    /// `name` must be one of [MAINTAINER_SCRIPTS]. dpkg executes scripts
+    /// directly, so content must begin with a `#!` interpreter line.
+    pub fn set_maintainer_script(&mut self, name: &str, content: impl Into<Vec<u8>>) -> Result<()> {
+        if !MAINTAINER_SCRIPTS.contains(&name) {
+            return Err(anyhow!(
+                "invalid maintainer script {}; must be one of {}",
+                name,
+                MAINTAINER_SCRIPTS.join(", ")
+            ));
+        }
+
+        let content = content.into();
+        if !content.starts_with(b"#!") {
+            return Err(anyhow!(
+                "maintainer script {} must begin with a #! interpreter line",
+                name
+            ));
+        }
+
+        self.maintainer_scripts.insert(name.to_string(), content);
+
+        Ok(())
+    }
+
+    /// Declare a dpkg trigger.
+    ///
+    /// `directive` must be one of [TRIGGER_DIRECTIVES]. `name` is the trigger
+    /// name or, for file triggers, an absolute path. e.g.
+    /// `interest-noawait /usr/share/icons` runs the package's `postinst`
+    /// with `triggered` when another package installs files under that path.
+    pub fn add_trigger(&mut self, directive: &str, name: &str) -> Result<()> {
+        if !TRIGGER_DIRECTIVES.contains(&directive) {
+            return Err(anyhow!(
+                "invalid trigger directive {}; must be one of {}",
+                directive,
+                TRIGGER_DIRECTIVES.join(", ")
+            ));
+        }
+
+        if name.is_empty() || name.chars().any(|c| c.is_whitespace()) {
+            return Err(anyhow!(
+                "trigger name must be non-empty and not contain whitespace"
+            ));
+        }
+
+        self.triggers
+            .insert((directive.to_string(), name.to_string()));
+
+        Ok(())
+    }
+
+    /// Resolve the package relations needed by ELF files to be installed.
+    ///
+    /// Shared libraries needed by installed ELF files are looked up in
+    /// `shlibs`. Libraries provided by the package itself, either as files or
+    /// symlinks, are ignored. An error occurs if a library can't be resolved.
+    pub fn shlibs_depends(&self, shlibs: &ShlibsDatabase) -> Result<Vec<String>> {
+        let provided = self
+            .symlinks
+            .keys()
+            .filter_map(|path| path.file_name().and_then(|x| x.to_str()))
+            .collect::<BTreeSet<_>>();
+
+        let mut relations = vec![];
+        let mut unresolved = vec![];
+
+        for library in find_needed_shared_libraries(&self.install_files)? {
+            if provided.contains(library.as_str()) {
+                continue;
+            }
+
+            match shlibs.resolve(&library) {
+                Some(relation) => relations.push(relation),
+                None => unresolved.push(library),
+            }
+        }
+
+        if !unresolved.is_empty() {
+            return Err(anyhow!(
+                "unable to resolve packages providing shared libraries: {}",
+                unresolved.join(", ")
+            ));
+        }
+
+        Ok(merge_relations(relations))
+    }
+
+    /// Add relations from [Self::shlibs_depends] to the `Depends` field.
+    ///
+    /// Relations are merged with any existing `Depends` value.
+    pub fn add_shlibs_depends(&mut self, shlibs: &ShlibsDatabase) -> Result<()> {
+        let relations = self.shlibs_depends(shlibs)?;
+
+        let depends = merge_relations(
+            self.control
+                .field("Depends")
+                .into_iter()
+                .chain(relations.iter().map(|x| x.as_str())),
+        );
+
+        if !depends.is_empty() {
+            self.control.set_field("Depends", depends.join(", "));
+        }
+
+        Ok(())
+    }
+
+    /// Set the modified time of archive members, in seconds since the UNIX epoch.
+    pub fn set_mtime(&mut self, mtime: u64) {
+        self.mtime = mtime;
+    }
+
+    /// Obtain the file name of the `.deb` per Debian conventions.
+    ///
+    /// This is `{package}_{version}_{architecture}.deb`, with the epoch
+    /// removed from the version.
+    pub fn deb_file_name(&self) -> Result<String> {
+        let field = |name: &str| {
+            self.control
+                .field(name)
+                .ok_or_else(|| anyhow!("control file does not define {}", name))
+        };
+
+        let version = field("Version")?;
+        let version = version.split_once(':').map(|(_, v)| v).unwrap_or(version);
+
+        Ok(format!(
+            "{}_{}_{}.deb",
+            field("Package")?,
+            version,
+            field("Architecture")?
+        ))
+    }
+
+    /// Derive the content of the `control` file.
+    pub fn control_file(&self) -> Result<String> {
+        for name in REQUIRED_FIELDS {
+            if self.control.field(name).is_none() {
+                return Err(anyhow!("control file does not define {}", name));
+            }
+        }
+
+        let mut control = self.control.clone();
+
+        if control.field("Installed-Size").is_none() {
+            let mut size = 0;
+            for (path, entry) in self.install_files.iter_entries() {
+                size += entry
+                    .resolve_content()
+                    .with_context(|| format!("resolving content of {}", path.display()))?
+                    .len() as u64;
+            }
+
+            control.set_field("Installed-Size", (size + 1023) / 1024);
+        }
+
+        Ok(control.to_string())
+    }
+
+    /// Write the `control.tar.gz` archive.
+    fn write_control_tar(&self, writer: impl Write) -> Result<()> {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            writer,
+            flate2::Compression::default(),
+        ));
+
+        append_file(
+            &mut builder,
+            "control",
+            self.control_file()?.as_bytes(),
+            0o644,
+            self.mtime,
+        )?;
+
+        let mut md5sums = vec![];
+        for (path, entry) in self.install_files.iter_entries() {
+            let content = entry
+                .resolve_content()
+                .with_context(|| format!("resolving content of {}", path.display()))?;
+
+            md5sums.push(format!(
+                "{:x}  {}\n",
+                Md5::digest(&content),
+                archive_path(path)
+            ));
+        }
+        append_file(
+            &mut builder,
+            "md5sums",
+            md5sums.concat().as_bytes(),
+            0o644,
+            self.mtime,
+        )?;
+
+        if !self.conffiles.is_empty() {
+            let mut conffiles = vec![];
+
+            for path in &self.conffiles {
+                if !self.install_files.has_path(path) {
+                    return Err(anyhow!(
+                        "conffile {} is not installed by the package",
+                        path.display()
+                    ));
+                }
+
+                conffiles.push(format!("/{}\n", archive_path(path)));
+            }
+
+            append_file(
+                &mut builder,
+                "conffiles",
+                conffiles.concat().as_bytes(),
+                0o644,
+                self.mtime,
+            )?;
+        }
+
+        if !self.triggers.is_empty() {
+            let triggers = self
+                .triggers
+                .iter()
+                .map(|(directive, name)| format!("{} {}\n", directive, name))
+                .collect::<Vec<_>>();
+
+            append_file(
+                &mut builder,
+                "triggers",
+                triggers.concat().as_bytes(),
+                0o644,
+                self.mtime,
+            )?;
+        }
+
+        for name in MAINTAINER_SCRIPTS {
+            if let Some(content) = self.maintainer_scripts.get(*name) {
+                append_file(&mut builder, name, content, 0o755, self.mtime)?;
+            }
+        }
+
+        builder.into_inner()?.finish()?;
+
+        Ok(())
+    }
+
+    /// Write the `data.tar.gz` archive.
+    fn write_data_tar(&self, writer: impl Write) -> Result<()> {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            writer,
+            flate2::Compression::default(),
+        ));
+
+        // Directories must precede the files within them.
+        let mut directories = BTreeSet::new();
+        for path in self
+            .install_files
+            .iter_entries()
+            .map(|(path, _)| path)
+            .chain(self.symlinks.keys())
+        {
+            for ancestor in path.ancestors().skip(1) {
+                if ancestor.as_os_str().is_empty() {
+                    break;
+                }
+
+                directories.insert(ancestor.to_path_buf());
+            }
+        }
+
+        for directory in directories {
+            append_directory(&mut builder, &archive_path(&directory), self.mtime)?;
+        }
+
+        for (path, entry) in self.install_files.iter_entries() {
+            let content = entry
+                .resolve_content()
+                .with_context(|| format!("resolving content of {}", path.display()))?;
+
+            let metadata = self.file_metadata.get(path).cloned().unwrap_or_default();
+
+            let mut header = new_header(
+                tar::EntryType::Regular,
+                content.len() as u64,
+                metadata.resolve_mode(entry.is_executable()),
+                metadata.mtime.unwrap_or(self.mtime),
+            )?;
+            set_ownership(&mut header, &metadata)?;
+            builder.append_data(&mut header, archive_path(path), content.as_slice())?;
+        }
+
+        for (path, target) in &self.symlinks {
+            if self.install_files.has_path(path) {
+                return Err(anyhow!(
+                    "{} is installed as both a file and a symlink",
+                    path.display()
+                ));
+            }
+
+            append_symlink(&mut builder, &archive_path(path), target, self.mtime)?;
+        }
+
+        builder.into_inner()?.finish()?;
+
+        Ok(())
+    }
+
+    /// Write the `.deb` to a writer.
+    pub fn write_deb(&self, writer: impl Write) -> Result<()> {
+        let mut control_tar = vec![];
+        self.write_control_tar(&mut control_tar)
+            .context("writing control.tar.gz")?;
+
+        let mut data_tar = vec![];
+        self.write_data_tar(&mut data_tar)
+            .context("writing data.tar.gz")?;
+
+        let mut builder = ar::Builder::new(writer);
+
+        for (name, data) in [
+            ("debian-binary", b"2.0\n".as_ref()),
+            ("control.tar.gz", control_tar.as_slice()),
+            ("data.tar.gz", data_tar.as_slice()),
+        ] {
+            let mut header = ar::Header::new(name.as_bytes().to_vec(), data.len() as u64);
+            header.set_mode(0o100644);
+            header.set_mtime(self.mtime);
+
+            builder
+                .append(&header, data)
+                .with_context(|| format!("writing {} to ar archive", name))?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the `.deb` into a directory, using [Self::deb_file_name()] as its name.
+    ///
+    /// Returns the path of the written file.
+    pub fn write_deb_into_directory(&self, directory: impl AsRef<Path>) -> Result<PathBuf> {
+        let path = directory.as_ref().join(self.deb_file_name()?);
+
+        let fh = std::fs::File::create(&path)
+            .with_context(|| format!("opening {} for writing", path.display()))?;
+        self.write_deb(std::io::BufWriter::new(fh))?;
+
+        Ok(path)
+    }
+}
+
+/// Resolve the Debian architecture name for a Rust target triple.
+pub fn target_triple_to_debian_arch(triple: &str) -> Option<&'static str> {
+    match triple {
+        "x86_64-unknown-linux-gnu" | "x86_64-unknown-linux-musl" => Some("amd64"),
+        "i686-unknown-linux-gnu" | "i686-unknown-linux-musl" => Some("i386"),
+        "aarch64-unknown-linux-gnu" | "aarch64-unknown-linux-musl" => Some("arm64"),
+        "armv7-unknown-linux-gnueabihf" => Some("armhf"),
+        "powerpc64le-unknown-linux-gnu" => Some("ppc64el"),
+        "s390x-unknown-linux-gnu" => Some("s390x"),
+        _ => None,
+    }
+}
+
+/// Strip a leading `/` from a path.
+fn normalize_path(path: &Path) -> &Path {
+    path.strip_prefix("/").unwrap_or(path)
+}
+
+/// Render a path for use in an archive or metadata file.
+fn archive_path(path: &Path) -> String {
+    normalize_path(path)
+        .display()
+        .to_string()
+        .replace('\\', "/")
+}
+
+fn new_header(entry_type: tar::EntryType, size: u64, mode: u32, mtime: u64) -> Result<tar::Header> {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(entry_type);
+    header.set_size(size);
+    header.set_mode(mode);
+    header.set_uid(0);
+    header.set_gid(0);
+    header.set_username("root")?;
+    header.set_groupname("root")?;
+    header.set_mtime(mtime);
+
+    Ok(header)
+}
+
+/// Apply ownership defined by file metadata to a tar header.
+///
+/// A numeric ID without a name clears the default `root` name so the two
+/// don't disagree.
+fn set_ownership(header: &mut tar::Header, metadata: &FileMetadata) -> Result<()> {
+    if let Some(uid) = metadata.uid {
+        header.set_uid(uid);
+        header.set_username(metadata.user.as_deref().unwrap_or(""))?;
+    } else if let Some(user) = &metadata.user {
+        header.set_username(user)?;
+    }
+
+    if let Some(gid) = metadata.gid {
+        header.set_gid(gid);
+        header.set_groupname(metadata.group.as_deref().unwrap_or(""))?;
+    } else if let Some(group) = &metadata.group {
+        header.set_groupname(group)?;
+    }
+
+    Ok(())
+}
+
+fn append_directory(builder: &mut tar::Builder<impl Write>, path: &str, mtime: u64) -> Result<()> {
+    let mut header = new_header(tar::EntryType::Directory, 0, 0o755, mtime)?;
+    builder.append_data(&mut header, path, std::io::empty())?;
+
+    Ok(())
+}
+
+fn append_file(
+    builder: &mut tar::Builder<impl Write>,
+    path: &str,
+    data: &[u8],
+    mode: u32,
+    mtime: u64,
+) -> Result<()> {
+    let mut header = new_header(tar::EntryType::Regular, data.len() as u64, mode, mtime)?;
+    builder.append_data(&mut header, path, data)?;
+
+    Ok(())
+}
+
+fn append_symlink(
+    builder: &mut tar::Builder<impl Write>,
+    path: &str,
+    target: &Path,
+    mtime: u64,
+) -> Result<()> {
+    let mut header = new_header(tar::EntryType::Symlink, 0, 0o777, mtime)?;
+    builder.append_link(&mut header, path, target)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::io::Read};
+
+    fn control() -> ControlParagraph {
+        let mut control = ControlParagraph::default();
+        control.set_field("Package", "myapp");
+        control.set_field("Version", "1:1.0-1");
+        control.set_field("Architecture", "amd64");
+        control.set_field("Maintainer", "Jane Doe <jane@example.com>");
+        control.set_field("Description", "My app\nDoes things.");
+
+        control
+    }
+
+    fn builder() -> Result<DebBuilder> {
+        let mut manifest = FileManifest::default();
+        manifest.add_file_entry(
+            "usr/bin/myapp",
+            FileEntry::new_from_data(vec![42; 2000], true),
+        )?;
+        manifest.add_file_entry(
+            "etc/myapp.conf",
+            FileEntry::new_from_data(b"a = 1\n".to_vec(), false),
+        )?;
+
+        let mut builder = DebBuilder::new(control());
+        builder.install_manifest(&manifest)?;
+
+        Ok(builder)
+    }
+
+    /// Obtain the names and contents of members of an ar archive.
+    fn ar_members(data: &[u8]) -> Result<Vec<(String, Vec<u8>)>> {
+        let mut archive = ar::Archive::new(data);
+        let mut members = vec![];
+
+        while let Some(entry) = archive.next_entry() {
+            let mut entry = entry?;
+            let name = String::from_utf8(entry.header().identifier().to_vec())?;
+            let mut content = vec![];
+            entry.read_to_end(&mut content)?;
+            members.push((name, content));
+        }
+
+        Ok(members)
+    }
+
+    /// Obtain the paths and modes of entries in a gzipped tar archive.
+    fn tar_entries(data: &[u8]) -> Result<Vec<(String, u32)>> {
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(data));
+
+        archive
+            .entries()?
+            .map(|entry| {
+                let entry = entry?;
+                Ok((entry.path()?.display().to_string(), entry.header().mode()?))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_deb_file_name() -> Result<()> {
+        assert_eq!(builder()?.deb_file_name()?, "myapp_1.0-1_amd64.deb");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_target_triple_to_debian_arch() {
+        assert_eq!(
+            target_triple_to_debian_arch("x86_64-unknown-linux-gnu"),
+            Some("amd64")
+        );
+        assert_eq!(
+            target_triple_to_debian_arch("aarch64-unknown-linux-musl"),
+            Some("arm64")
+        );
+        assert_eq!(target_triple_to_debian_arch("x86_64-apple-darwin"), None);
+    }
+
+    #[test]
+    fn test_control_file() -> Result<()> {
+        let control = builder()?.control_file()?;
+        assert!(control.contains("Description: My app\n Does things.\n"));
+        assert!(control.ends_with("Installed-Size: 2\n"));
+
+        let mut builder = DebBuilder::new(ControlParagraph::default());
+        assert!(builder.control_file().is_err());
+        builder.control_mut().set_field("Package", "foo");
+        assert!(builder.control_file().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_maintainer_script_names() -> Result<()> {
+        let mut builder = builder()?;
+        builder.set_maintainer_script("postinst", "#!/bin/sh\n")?;
+        assert!(builder
+            .set_maintainer_script("bogus", "#!/bin/sh\n")
+            .is_err());
+        assert!(builder.set_maintainer_script("prerm", "exit 0\n").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_triggers() -> Result<()> {
+        let mut builder = builder()?;
+        builder.add_trigger("interest-noawait", "/usr/share/icons")?;
+        builder.add_trigger("activate-noawait", "ldconfig")?;
+        assert!(builder.add_trigger("bogus", "foo").is_err());
+        assert!(builder.add_trigger("activate", "foo bar").is_err());
+        assert!(builder.add_trigger("activate", "").is_err());
+
+        let mut data = vec![];
+        builder.write_deb(&mut data)?;
+        let members = ar_members(&data)?;
+
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(members[1].1.as_slice()));
+        let mut triggers = None;
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if entry.path()?.to_str() == Some("triggers") {
+                let mut content = String::new();
+                entry.read_to_string(&mut content)?;
+                triggers = Some(content);
+            }
+        }
+
+        assert_eq!(
+            triggers.as_deref(),
+            Some("activate-noawait ldconfig\ninterest-noawait /usr/share/icons\n")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_shlibs_depends() -> Result<()> {
+        let mut builder = builder()?;
+        builder.install_file(
+            "usr/lib/myapp/myapp-bin",
+            FileEntry::new_from_data(
+                crate::shlibs::tests::elf_library(
+                    &["libc.so.6", "libssl.so.3", "libbundled.so.1"],
+                    None,
+                ),
+                true,
+            ),
+        )?;
+        builder.install_symlink("usr/lib/myapp/libbundled.so.1", "libbundled.so.1.0.0")?;
+        builder
+            .control_mut()
+            .set_field("Depends", "libc6 (>= 2.17), python3");
+
+        let mut shlibs = ShlibsDatabase::default();
+        shlibs.add_shlibs("libc 6 libc6 (>= 2.34)\n")?;
+
+        assert!(builder.shlibs_depends(&shlibs).is_err());
+
+        shlibs.add_shlibs("libssl 3 libssl3 (>= 3.0.0)\n")?;
+        assert_eq!(
+            builder.shlibs_depends(&shlibs)?,
+            vec![
+                "libc6 (>= 2.34)".to_string(),
+                "libssl3 (>= 3.0.0)".to_string()
+            ]
+        );
+
+        builder.add_shlibs_depends(&shlibs)?;
+        assert_eq!(
+            builder.control().field("Depends"),
+            Some("libc6 (>= 2.34), libssl3 (>= 3.0.0), python3")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_deb() -> Result<()> {
+        let mut builder = builder()?;
+        builder.add_conffile("/etc/myapp.conf");
+        builder.add_trigger("interest-noawait", "/usr/share/icons")?;
+        builder.set_maintainer_script("postrm", "#!/bin/sh\nset -e\n")?;
+        builder.set_maintainer_script("postinst", "#!/bin/sh\nset -e\n")?;
+        builder.set_maintainer_script("preinst", "#!/bin/sh\nset -e\n")?;
+
+        let mut data = vec![];
+        builder.write_deb(&mut data)?;
+
+        let members = ar_members(&data)?;
+        assert_eq!(
+            members
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["debian-binary", "control.tar.gz", "data.tar.gz"]
+        );
+        assert_eq!(members[0].1, b"2.0\n");
+
+        assert_eq!(
+            tar_entries(&members[1].1)?,
+            vec![
+                ("control".to_string(), 0o644),
+                ("md5sums".to_string(), 0o644),
+                ("conffiles".to_string(), 0o644),
+                ("triggers".to_string(), 0o644),
+                ("preinst".to_string(), 0o755),
+                ("postinst".to_string(), 0o755),
+                ("postrm".to_string(), 0o755),
+            ]
+        );
+        assert_eq!(
+            tar_entries(&members[2].1)?,
+            vec![
+                ("etc".to_string(), 0o755),
+                ("usr".to_string(), 0o755),
+                ("usr/bin".to_string(), 0o755),
+                ("etc/myapp.conf".to_string(), 0o644),
+                ("usr/bin/myapp".to_string(), 0o755),
+            ]
+        );
+
+        // Output is deterministic.
+        let mut data2 = vec![];
+        builder.write_deb(&mut data2)?;
+        assert_eq!(data, data2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_symlinks() -> Result<()> {
+        let mut builder = builder()?;
+        builder.install_symlink("/usr/lib/myapp/current", "1.0")?;
+        builder.install_symlink("usr/bin/myapp-link", "/usr/bin/myapp")?;
+        assert!(builder.install_symlink("usr/bin/myapp", "other").is_err());
+
+        let mut data = vec![];
+        builder.write_deb(&mut data)?;
+
+        let members = ar_members(&data)?;
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(members[2].1.as_slice()));
+        let links = archive
+            .entries()?
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                if entry.header().entry_type() == tar::EntryType::Symlink {
+                    Some((
+                        entry.path().ok()?.display().to_string(),
+                        entry.link_name().ok()??.display().to_string(),
+                    ))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            links,
+            vec![
+                (
+                    "usr/bin/myapp-link".to_string(),
+                    "/usr/bin/myapp".to_string()
+                ),
+                ("usr/lib/myapp/current".to_string(), "1.0".to_string()),
+            ]
+        );
+
+        // Symlinks aren't recorded in md5sums.
+        let mut control_tar = vec![];
+        flate2::read::GzDecoder::new(members[1].1.as_slice()).read_to_end(&mut control_tar)?;
+        assert!(!String::from_utf8_lossy(&control_tar).contains("myapp-link"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_metadata() -> Result<()> {
+        let mut builder = builder()?;
+        builder.set_file_metadata(
+            "/usr/bin/myapp",
+            FileMetadata {
+                mode: Some(0o4750),
+                uid: Some(0),
+                gid: Some(1000),
+                group: Some("myapp".into()),
+                mtime: Some(42),
+                ..FileMetadata::default()
+            },
+        )?;
+        assert!(builder
+            .set_file_metadata(
+                "etc/myapp.conf",
+                FileMetadata {
+                    mode: Some(0o100644),
+                    ..FileMetadata::default()
+                },
+            )
+            .is_err());
+
+        let mut data = vec![];
+        builder.write_deb(&mut data)?;
+
+        let members = ar_members(&data)?;
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(members[2].1.as_slice()));
+        let mut found = false;
+        for entry in archive.entries()? {
+            let entry = entry?;
+            let header = entry.header();
+
+            match entry.path()?.display().to_string().as_str() {
+                "usr/bin/myapp" => {
+                    assert_eq!(header.mode()?, 0o4750);
+                    assert_eq!(header.uid()?, 0);
+                    assert_eq!(header.gid()?, 1000);
+                    assert_eq!(header.username()?, Some("root"));
+                    assert_eq!(header.groupname()?, Some("myapp"));
+                    assert_eq!(header.mtime()?, 42);
+                    found = true;
+                }
+                "etc/myapp.conf" => {
+                    assert_eq!(header.mode()?, 0o644);
+                    assert_eq!(header.groupname()?, Some("root"));
+                    assert_eq!(header.mtime()?, 0);
+                }
+                _ => {}
+            }
+        }
+        assert!(found);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_conffile() -> Result<()> {
+        let mut builder = builder()?;
+        builder.add_conffile("etc/missing.conf");
+
+        assert!(builder.write_deb(std::io::sink()).is_err());
+
+        Ok(())
+    }
+}
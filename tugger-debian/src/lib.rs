@@ -0,0 +1,18 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! Functionality for the Debian packaging format. */
+
+mod control;
+mod deb;
+mod shlibs;
+
+pub use {
+    control::ControlParagraph,
+    deb::{target_triple_to_debian_arch, DebBuilder, MAINTAINER_SCRIPTS, TRIGGER_DIRECTIVES},
+    shlibs::{
+        compare_versions, find_needed_shared_libraries, merge_relations, parse_soname,
+        ShlibsDatabase,
+    },
+};
@@ -0,0 +1,488 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! Shared library dependency resolution.
+
+This implements the `shlibs` mechanism used by `dpkg-shlibdeps`: ELF files
+declare the shared libraries they need by SONAME, and `shlibs` files map
+each library name and version to the package relation providing it.
+*/
+
+use {
+    anyhow::{anyhow, Context, Result},
+    simple_file_manifest::FileManifest,
+    std::{
+        cmp::Ordering,
+        collections::{BTreeMap, BTreeSet},
+        path::Path,
+    },
+};
+
+/// Directory holding dpkg's metadata for installed packages.
+const DPKG_INFO_DIR: &str = "/var/lib/dpkg/info";
+
+/// System shlibs file taking precedence over package shlibs files.
+const SHLIBS_OVERRIDE_PATH: &str = "/etc/dpkg/shlibs.override";
+
+/// System shlibs file used when no package shlibs file has an entry.
+const SHLIBS_DEFAULT_PATH: &str = "/etc/dpkg/shlibs.default";
+
+/// Split a SONAME into its library name and version.
+///
+/// Both the `libfoo.so.1` and `libfoo-1.so` forms are recognized. Returns
+/// `None` if the SONAME is in neither form.
+pub fn parse_soname(soname: &str) -> Option<(&str, &str)> {
+    if let Some((name, version)) = soname.split_once(".so.") {
+        if !name.is_empty() && !version.is_empty() {
+            return Some((name, version));
+        }
+    }
+
+    let stem = soname.strip_suffix(".so")?;
+    let (name, version) = stem.rsplit_once('-')?;
+
+    if !name.is_empty() && version.starts_with(|c: char| c.is_ascii_digit()) {
+        Some((name, version))
+    } else {
+        None
+    }
+}
+
+/// A mapping of shared libraries to the package relations providing them.
+///
+/// Entries are keyed by library name and version, as parsed from a SONAME
+/// by [parse_soname]. When the same library is defined multiple times, the
+/// first definition wins. So higher precedence sources should be added first.
+#[derive(Clone, Debug, Default)]
+pub struct ShlibsDatabase {
+    entries: BTreeMap<(String, String), String>,
+}
+
+impl ShlibsDatabase {
+    /// Construct an instance from the shlibs files of the running system.
+    ///
+    /// See [Self::add_system_shlibs].
+    pub fn from_system() -> Result<Self> {
+        let mut db = Self::default();
+        db.add_system_shlibs()?;
+
+        Ok(db)
+    }
+
+    /// Add entries from the shlibs files of the running system.
+    ///
+    /// This consults `/etc/dpkg/shlibs.override`, the shlibs files of
+    /// installed packages, and `/etc/dpkg/shlibs.default`, in that order of
+    /// precedence. Missing files are ignored, so this adds nothing on
+    /// systems without dpkg.
+    pub fn add_system_shlibs(&mut self) -> Result<()> {
+        if Path::new(SHLIBS_OVERRIDE_PATH).is_file() {
+            self.add_shlibs_file(SHLIBS_OVERRIDE_PATH)?;
+        }
+
+        if Path::new(DPKG_INFO_DIR).is_dir() {
+            let mut paths = std::fs::read_dir(DPKG_INFO_DIR)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<std::io::Result<Vec<_>>>()?;
+            paths.sort();
+
+            for path in paths {
+                if path.extension().and_then(|x| x.to_str()) == Some("shlibs") {
+                    self.add_shlibs_file(&path)?;
+                }
+            }
+        }
+
+        if Path::new(SHLIBS_DEFAULT_PATH).is_file() {
+            self.add_shlibs_file(SHLIBS_DEFAULT_PATH)?;
+        }
+
+        Ok(())
+    }
+
+    /// Define the relation providing a library, if not already defined.
+    pub fn add_entry(&mut self, library: &str, version: &str, relation: &str) {
+        self.entries
+            .entry((library.to_string(), version.to_string()))
+            .or_insert_with(|| relation.to_string());
+    }
+
+    /// Add entries from the content of a shlibs file.
+    ///
+    /// Each line has the form `[type:] library version relations`. Lines
+    /// having a type (e.g. `udeb:`) apply to other package types and are
+    /// ignored.
+    pub fn add_shlibs(&mut self, content: &str) -> Result<()> {
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.splitn(3, char::is_whitespace);
+            let library = parts.next().unwrap_or_default();
+            if library.ends_with(':') {
+                continue;
+            }
+
+            let version = parts
+                .next()
+                .ok_or_else(|| anyhow!("malformed shlibs line {}: {}", i + 1, line))?;
+            let relation = parts.next().unwrap_or_default().trim();
+
+            self.add_entry(library, version, relation);
+        }
+
+        Ok(())
+    }
+
+    /// Add entries from a shlibs file.
+    pub fn add_shlibs_file(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+
+        let content =
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+
+        self.add_shlibs(&content)
+            .with_context(|| format!("parsing {}", path.display()))
+    }
+
+    /// Resolve the package relation providing a SONAME.
+    ///
+    /// An empty relation means the library is known to need no dependency.
+    pub fn resolve(&self, soname: &str) -> Option<&str> {
+        let (library, version) = parse_soname(soname)?;
+
+        self.entries
+            .get(&(library.to_string(), version.to_string()))
+            .map(|x| x.as_str())
+    }
+}
+
+/// Find shared libraries needed by ELF files in a manifest.
+///
+/// Libraries provided by the manifest itself, either by SONAME or by file
+/// name, are excluded.
+pub fn find_needed_shared_libraries(manifest: &FileManifest) -> Result<BTreeSet<String>> {
+    let mut needed = BTreeSet::new();
+    let mut provided = BTreeSet::new();
+
+    for (path, entry) in manifest.iter_entries() {
+        if let Some(name) = path.file_name().and_then(|x| x.to_str()) {
+            provided.insert(name.to_string());
+        }
+
+        let data = entry
+            .resolve_content()
+            .with_context(|| format!("resolving content of {}", path.display()))?;
+
+        if !data.starts_with(b"\x7fELF") {
+            continue;
+        }
+
+        let elf = goblin::elf::Elf::parse(&data)
+            .with_context(|| format!("parsing ELF file {}", path.display()))?;
+
+        if let Some(soname) = elf.soname {
+            provided.insert(soname.to_string());
+        }
+
+        needed.extend(elf.libraries.iter().map(|x| x.to_string()));
+    }
+
+    Ok(needed.difference(&provided).cloned().collect())
+}
+
+/// Compare Debian package versions.
+///
+/// This implements the algorithm used by `dpkg --compare-versions`.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn split(version: &str) -> (u64, &str, &str) {
+        let (epoch, rest) = match version.split_once(':') {
+            Some((epoch, rest)) => (epoch.parse().unwrap_or(0), rest),
+            None => (0, version),
+        };
+
+        match rest.rsplit_once('-') {
+            Some((upstream, revision)) => (epoch, upstream, revision),
+            None => (epoch, rest, ""),
+        }
+    }
+
+    fn order(c: Option<u8>) -> i32 {
+        match c {
+            Some(b'~') => -1,
+            Some(c) if c.is_ascii_digit() => 0,
+            Some(c) if c.is_ascii_alphabetic() => c as i32,
+            Some(c) => c as i32 + 256,
+            None => 0,
+        }
+    }
+
+    fn compare_part(a: &str, b: &str) -> Ordering {
+        let (mut a, mut b) = (a.as_bytes(), b.as_bytes());
+
+        while !a.is_empty() || !b.is_empty() {
+            // Compare non-digit prefixes.
+            while a.first().map_or(false, |c| !c.is_ascii_digit())
+                || b.first().map_or(false, |c| !c.is_ascii_digit())
+            {
+                let (ac, bc) = (order(a.first().copied()), order(b.first().copied()));
+                if ac != bc {
+                    return ac.cmp(&bc);
+                }
+                a = a.get(1..).unwrap_or_default();
+                b = b.get(1..).unwrap_or_default();
+            }
+
+            // Compare numeric runs.
+            let a_len = a.iter().take_while(|c| c.is_ascii_digit()).count();
+            let b_len = b.iter().take_while(|c| c.is_ascii_digit()).count();
+            let parse = |digits: &[u8]| {
+                digits
+                    .iter()
+                    .fold(0u128, |acc, c| acc * 10 + (c - b'0') as u128)
+            };
+
+            match parse(&a[..a_len]).cmp(&parse(&b[..b_len])) {
+                Ordering::Equal => {}
+                ordering => return ordering,
+            }
+
+            a = &a[a_len..];
+            b = &b[b_len..];
+        }
+
+        Ordering::Equal
+    }
+
+    let (a_epoch, a_upstream, a_revision) = split(a);
+    let (b_epoch, b_upstream, b_revision) = split(b);
+
+    a_epoch
+        .cmp(&b_epoch)
+        .then_with(|| compare_part(a_upstream, b_upstream))
+        .then_with(|| compare_part(a_revision, b_revision))
+}
+
+/// Merge comma delimited package relations.
+///
+/// Relations on the same package having no or `>=` version constraints are
+/// reduced to the strictest one. Other relations are deduplicated. The
+/// result is sorted.
+pub fn merge_relations<'a>(relations: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut minimums: BTreeMap<String, Option<String>> = BTreeMap::new();
+    let mut others = BTreeSet::new();
+
+    for relation in relations
+        .into_iter()
+        .flat_map(|x| x.split(','))
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty())
+    {
+        let (name, constraint) = match relation.split_once('(') {
+            Some((name, constraint)) => (name.trim(), Some(constraint.trim())),
+            None => (relation, None),
+        };
+
+        if name.contains(|c: char| c.is_whitespace() || c == '|') {
+            others.insert(relation.to_string());
+            continue;
+        }
+
+        let minimum = match constraint {
+            None => None,
+            Some(constraint) => match constraint
+                .strip_prefix(">=")
+                .and_then(|x| x.strip_suffix(')'))
+            {
+                Some(version) => Some(version.trim().to_string()),
+                None => {
+                    others.insert(relation.to_string());
+                    continue;
+                }
+            },
+        };
+
+        let current = minimums.entry(name.to_string()).or_default();
+        if let Some(minimum) = minimum {
+            if current
+                .as_deref()
+                .map_or(true, |x| compare_versions(&minimum, x) == Ordering::Greater)
+            {
+                *current = Some(minimum);
+            }
+        }
+    }
+
+    let mut res = minimums
+        .into_iter()
+        .map(|(name, minimum)| match minimum {
+            Some(version) => format!("{} (>= {})", name, version),
+            None => name,
+        })
+        .chain(others)
+        .collect::<Vec<_>>();
+    res.sort();
+    res.dedup();
+
+    res
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use {super::*, simple_file_manifest::FileEntry};
+
+    /// Construct a minimal 64-bit ELF shared library with a dynamic section.
+    pub(crate) fn elf_library(needed: &[&str], soname: Option<&str>) -> Vec<u8> {
+        let mut strtab = vec![0u8];
+        let mut dynamic = vec![];
+        for name in needed {
+            dynamic.push((1u64, strtab.len() as u64));
+            strtab.extend_from_slice(name.as_bytes());
+            strtab.push(0);
+        }
+        if let Some(soname) = soname {
+            dynamic.push((14, strtab.len() as u64));
+            strtab.extend_from_slice(soname.as_bytes());
+            strtab.push(0);
+        }
+        strtab.resize((strtab.len() + 7) / 8 * 8, 0);
+
+        let strtab_offset = 64 + 2 * 56;
+        let dynamic_offset = strtab_offset + strtab.len();
+        dynamic.push((5, strtab_offset as u64));
+        dynamic.push((10, strtab.len() as u64));
+        dynamic.push((0, 0));
+        let dynamic_size = dynamic.len() * 16;
+        let total = dynamic_offset + dynamic_size;
+
+        let mut data = vec![];
+        data.extend_from_slice(b"\x7fELF\x02\x01\x01\0\0\0\0\0\0\0\0\0");
+        data.extend_from_slice(&3u16.to_le_bytes());
+        data.extend_from_slice(&0x3eu16.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.extend_from_slice(&64u64.to_le_bytes());
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        for value in [64u16, 56, 2, 64, 0, 0] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+
+        for (kind, flags, offset, size, align) in [
+            (1u32, 4u32, 0usize, total, 0x1000u64),
+            (2, 6, dynamic_offset, dynamic_size, 8),
+        ] {
+            data.extend_from_slice(&kind.to_le_bytes());
+            data.extend_from_slice(&flags.to_le_bytes());
+            data.extend_from_slice(&(offset as u64).to_le_bytes());
+            data.extend_from_slice(&(offset as u64).to_le_bytes());
+            data.extend_from_slice(&(offset as u64).to_le_bytes());
+            data.extend_from_slice(&(size as u64).to_le_bytes());
+            data.extend_from_slice(&(size as u64).to_le_bytes());
+            data.extend_from_slice(&align.to_le_bytes());
+        }
+
+        data.extend_from_slice(&strtab);
+        for (tag, value) in dynamic {
+            data.extend_from_slice(&tag.to_le_bytes());
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+
+        data
+    }
+
+    #[test]
+    fn test_parse_soname() {
+        assert_eq!(parse_soname("libc.so.6"), Some(("libc", "6")));
+        assert_eq!(parse_soname("libssl.so.1.1"), Some(("libssl", "1.1")));
+        assert_eq!(
+            parse_soname("libpython3.10-1.0.so"),
+            Some(("libpython3.10", "1.0"))
+        );
+        assert_eq!(parse_soname("libfoo.so"), None);
+        assert_eq!(parse_soname("foo"), None);
+    }
+
+    #[test]
+    fn test_shlibs_database() -> Result<()> {
+        let mut db = ShlibsDatabase::default();
+        db.add_shlibs(
+            "# comment\n\
+             libfoo 1 libfoo1 (>= 1.2)\n\
+             udeb: libfoo 1 libfoo1-udeb\n\
+             libbar 2\n",
+        )?;
+        db.add_shlibs("libfoo 1 libfoo1-override\n")?;
+
+        assert_eq!(db.resolve("libfoo.so.1"), Some("libfoo1 (>= 1.2)"));
+        assert_eq!(db.resolve("libbar.so.2"), Some(""));
+        assert_eq!(db.resolve("libbaz.so.1"), None);
+        assert!(db.add_shlibs("libfoo\n").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        for (a, b, expected) in [
+            ("1.0", "1.0", Ordering::Equal),
+            ("1.0", "1.1", Ordering::Less),
+            ("1.10", "1.9", Ordering::Greater),
+            ("1.0~rc1", "1.0", Ordering::Less),
+            ("1:0.1", "2.0", Ordering::Greater),
+            ("2.31-13", "2.31-2", Ordering::Greater),
+            ("1.0a", "1.0+", Ordering::Less),
+        ] {
+            assert_eq!(compare_versions(a, b), expected, "{} vs {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_merge_relations() {
+        assert_eq!(
+            merge_relations(vec![
+                "libc6 (>= 2.14)",
+                "libc6 (>= 2.34), libgcc-s1 (>= 3.0)",
+                "libc6",
+                "libfoo1 | libfoo2",
+                "libbar (= 1.0)",
+                "",
+            ]),
+            vec![
+                "libbar (= 1.0)".to_string(),
+                "libc6 (>= 2.34)".to_string(),
+                "libfoo1 | libfoo2".to_string(),
+                "libgcc-s1 (>= 3.0)".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_needed_shared_libraries() -> Result<()> {
+        let mut manifest = FileManifest::default();
+        manifest.add_file_entry(
+            "usr/bin/myapp",
+            FileEntry::new_from_data(elf_library(&["libc.so.6", "libinternal.so.1"], None), true),
+        )?;
+        manifest.add_file_entry(
+            "usr/lib/myapp/libinternal.so.1",
+            FileEntry::new_from_data(elf_library(&["libm.so.6"], Some("libinternal.so.1")), false),
+        )?;
+        manifest.add_file_entry(
+            "usr/share/doc/myapp/README",
+            FileEntry::new_from_data(b"hello".to_vec(), false),
+        )?;
+
+        assert_eq!(
+            find_needed_shared_libraries(&manifest)?
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!["libc.so.6".to_string(), "libm.so.6".to_string()]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,449 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use {
+    crate::manifest::{FlatpakManifest, FlatpakModule, FlatpakSource},
+    anyhow::{anyhow, Context, Result},
+    duct::cmd,
+    log::warn,
+    simple_file_manifest::{FileEntry, FileManifest},
+    std::{
+        collections::BTreeMap,
+        io::{BufRead, BufReader},
+        path::{Path, PathBuf},
+    },
+    tugger_common::file_metadata::FileMetadata,
+};
+
+/// Directory relative to the build path holding files installed into `/app`.
+const SOURCE_DIRECTORY: &str = "files";
+
+/// File extensions of supported icon formats.
+const ICON_EXTENSIONS: &[&str] = &["png", "svg"];
+
+/// Run a command in a directory, logging its output.
+fn run_command(program: &str, args: Vec<String>, dir: &Path) -> Result<()> {
+    warn!("invoking {} with args: {:?}", program, args);
+    let command = cmd(program, &args)
+        .dir(dir)
+        .stderr_to_stdout()
+        .reader()
+        .with_context(|| format!("running {}", program))?;
+    {
+        let reader = BufReader::new(&command);
+        for line in reader.lines() {
+            warn!("{}", line?);
+        }
+    }
+
+    let output = command
+        .try_wait()?
+        .ok_or_else(|| anyhow!("unable to wait on command"))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(anyhow!("error running {}", program))
+    }
+}
+
+/// Create a symlink at `link` pointing to `target`.
+///
+/// An existing file at `link` is replaced.
+fn create_symlink(target: &Path, link: &Path) -> Result<()> {
+    if let Some(parent) = link.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("creating {}", parent.display()))?;
+    }
+
+    if link.symlink_metadata().is_ok() {
+        std::fs::remove_file(link).with_context(|| format!("removing {}", link.display()))?;
+    }
+
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(target, link)
+            .with_context(|| format!("creating symlink {}", link.display()))
+    }
+
+    #[cfg(not(unix))]
+    {
+        Err(anyhow!(
+            "unable to create symlink {} to {}: symlinks are only supported on Unix",
+            link.display(),
+            target.display()
+        ))
+    }
+}
+
+/// Build Flatpak applications.
+///
+/// Instances are bound to a [FlatpakManifest] and a set of files and symlinks
+/// to install into the application's `/app` prefix. Building writes the files and a
+/// manifest whose final module copies them into `/app`. `flatpak-builder` and
+/// `flatpak build-bundle` can then be invoked to produce a `.flatpak` bundle.
+#[derive(Clone, Debug)]
+pub struct FlatpakBuilder {
+    manifest: FlatpakManifest,
+    install_files: FileManifest,
+    symlinks: BTreeMap<PathBuf, PathBuf>,
+    file_metadata: BTreeMap<PathBuf, FileMetadata>,
+}
+
+impl FlatpakBuilder {
+    /// Create a new builder for a manifest.
+    pub fn new(manifest: FlatpakManifest) -> Self {
+        Self {
+            manifest,
+            install_files: FileManifest::default(),
+            symlinks: BTreeMap::new(),
+            file_metadata: BTreeMap::new(),
+        }
+    }
+
+    /// Obtain the manifest.
+    pub fn manifest(&self) -> &FlatpakManifest {
+        &self.manifest
+    }
+
+    /// Obtain a mutable reference to the manifest.
+    pub fn manifest_mut(&mut self) -> &mut FlatpakManifest {
+        &mut self.manifest
+    }
+
+    /// Obtain files to be installed into `/app`.
+    pub fn install_files(&self) -> &FileManifest {
+        &self.install_files
+    }
+
+    /// Add a file to install. The path is relative to `/app`. e.g. `bin/foo`.
+    pub fn install_file(
+        &mut self,
+        path: impl AsRef<Path>,
+        entry: impl Into<FileEntry>,
+    ) -> Result<()> {
+        self.install_files.add_file_entry(path, entry)?;
+
+        Ok(())
+    }
+
+    /// Add files to install from the content of an existing [FileManifest].
+    pub fn install_manifest(&mut self, manifest: &FileManifest) -> Result<()> {
+        self.install_files.add_manifest(manifest)?;
+
+        Ok(())
+    }
+
+    /// Obtain symlinks to be installed into `/app`.
+    ///
+    /// Keys are the paths of links and values are their targets.
+    pub fn symlinks(&self) -> &BTreeMap<PathBuf, PathBuf> {
+        &self.symlinks
+    }
+
+    /// Add a symlink to install. The path is relative to `/app`. e.g. `bin/foo`.
+    ///
+    /// The target is stored verbatim and can be relative to the link's
+    /// directory or absolute.
+    pub fn install_symlink(
+        &mut self,
+        path: impl AsRef<Path>,
+        target: impl AsRef<Path>,
+    ) -> Result<()> {
+        let path = path.as_ref();
+
+        if self.install_files.has_path(path) {
+            return Err(anyhow!(
+                "cannot add symlink {}: a file is installed at that path",
+                path.display()
+            ));
+        }
+
+        self.symlinks
+            .insert(path.to_path_buf(), target.as_ref().to_path_buf());
+
+        Ok(())
+    }
+
+    /// Obtain metadata of installed files, keyed by path.
+    pub fn file_metadata(&self) -> &BTreeMap<PathBuf, FileMetadata> {
+        &self.file_metadata
+    }
+
+    /// Define the metadata of an installed file. The path is relative to `/app`.
+    ///
+    /// The mode and modified time are applied when files are written.
+    /// Ownership is defined by Flatpak and can't be changed.
+    pub fn set_file_metadata(
+        &mut self,
+        path: impl AsRef<Path>,
+        metadata: FileMetadata,
+    ) -> Result<()> {
+        metadata
+            .validate()
+            .with_context(|| format!("validating metadata of {}", path.as_ref().display()))?;
+
+        self.file_metadata
+            .insert(path.as_ref().to_path_buf(), metadata);
+
+        Ok(())
+    }
+
+    /// Install a `.desktop` file exported to the host.
+    ///
+    /// The file is installed as `share/applications/<app-id>.desktop`.
+    pub fn set_desktop_file(&mut self, entry: impl Into<FileEntry>) -> Result<()> {
+        self.install_file(
+            format!("share/applications/{}.desktop", self.manifest.app_id),
+            entry,
+        )
+    }
+
+    /// Install an icon exported to the host.
+    ///
+    /// `size` is `scalable` or a size like `128x128`. `extension` is `png` or
+    /// `svg`. The icon is installed as
+    /// `share/icons/hicolor/<size>/apps/<app-id>.<extension>`.
+    pub fn add_icon(
+        &mut self,
+        size: &str,
+        extension: &str,
+        entry: impl Into<FileEntry>,
+    ) -> Result<()> {
+        let valid_size = size == "scalable"
+            || size
+                .split_once('x')
+                .map(|(w, h)| w == h && w.parse::<u32>().is_ok())
+                .unwrap_or(false);
+        if !valid_size {
+            return Err(anyhow!(
+                "invalid icon size {}; must be scalable or like 128x128",
+                size
+            ));
+        }
+
+        if !ICON_EXTENSIONS.contains(&extension) {
+            return Err(anyhow!(
+                "unsupported icon extension {}; must be one of {}",
+                extension,
+                ICON_EXTENSIONS.join(", ")
+            ));
+        }
+
+        self.install_file(
+            format!(
+                "share/icons/hicolor/{}/apps/{}.{}",
+                size, self.manifest.app_id, extension
+            ),
+            entry,
+        )
+    }
+
+    /// Obtain the file name of the `.flatpak` bundle.
+    pub fn bundle_file_name(&self) -> String {
+        format!("{}.flatpak", self.manifest.app_id)
+    }
+
+    /// Obtain the file name of the manifest.
+    pub fn manifest_file_name(&self) -> String {
+        format!("{}.json", self.manifest.app_id)
+    }
+
+    /// Obtain the manifest written by this builder.
+    ///
+    /// This is the manifest with a final module copying installed files into
+    /// `/app`.
+    pub fn resolved_manifest(&self) -> FlatpakManifest {
+        let mut manifest = self.manifest.clone();
+
+        manifest.modules.push(FlatpakModule {
+            name: manifest.app_id.clone(),
+            buildsystem: Some("simple".to_string()),
+            build_commands: vec!["cp -a . /app/".to_string()],
+            sources: vec![FlatpakSource {
+                source_type: "dir".to_string(),
+                path: Some(SOURCE_DIRECTORY.to_string()),
+                ..FlatpakSource::default()
+            }],
+        });
+
+        manifest
+    }
+
+    /// Write the manifest and installed files into `dest_dir`.
+    ///
+    /// Returns the path of the written manifest.
+    pub fn write_manifest(&self, dest_dir: impl AsRef<Path>) -> Result<PathBuf> {
+        let dest_dir = dest_dir.as_ref();
+
+        let files_path = dest_dir.join(SOURCE_DIRECTORY);
+        if files_path.exists() {
+            remove_dir_all::remove_dir_all(&files_path)
+                .with_context(|| format!("removing {}", files_path.display()))?;
+        }
+        std::fs::create_dir_all(&files_path)
+            .with_context(|| format!("creating {}", files_path.display()))?;
+
+        self.install_files
+            .materialize_files(&files_path)
+            .with_context(|| format!("installing files to {}", files_path.display()))?;
+
+        for (path, metadata) in &self.file_metadata {
+            metadata.apply_to_path(&files_path.join(path))?;
+        }
+
+        // `cp -a` in the final module preserves symlinks when copying into `/app`.
+        for (path, target) in &self.symlinks {
+            if self.install_files.has_path(path) {
+                return Err(anyhow!(
+                    "{} is installed as both a file and a symlink",
+                    path.display()
+                ));
+            }
+
+            create_symlink(target, &files_path.join(path))?;
+        }
+
+        let manifest_path = dest_dir.join(self.manifest_file_name());
+        let fh = std::fs::File::create(&manifest_path)
+            .with_context(|| format!("opening {} for writing", manifest_path.display()))?;
+        serde_json::to_writer_pretty(fh, &self.resolved_manifest())
+            .context("serializing Flatpak manifest")?;
+
+        Ok(manifest_path)
+    }
+
+    /// Build a `.flatpak` bundle in `build_path`, returning its path.
+    ///
+    /// This writes the manifest, invokes `flatpak-builder` to build the
+    /// application into a repository, then invokes `flatpak build-bundle` to
+    /// export it as a single file. The runtime and SDK must be installed.
+    pub fn build_bundle(&self, build_path: impl AsRef<Path>) -> Result<PathBuf> {
+        let build_path = build_path.as_ref();
+
+        self.write_manifest(build_path)?;
+
+        run_command(
+            "flatpak-builder",
+            vec![
+                "--force-clean".to_string(),
+                "--repo=repo".to_string(),
+                "build-dir".to_string(),
+                self.manifest_file_name(),
+            ],
+            build_path,
+        )?;
+
+        run_command(
+            "flatpak",
+            vec![
+                "build-bundle".to_string(),
+                "repo".to_string(),
+                self.bundle_file_name(),
+                self.manifest.app_id.clone(),
+            ],
+            build_path,
+        )?;
+
+        Ok(build_path.join(self.bundle_file_name()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, tugger_common::testutil::*};
+
+    fn builder() -> FlatpakBuilder {
+        FlatpakBuilder::new(FlatpakManifest::new(
+            "org.example.MyApp",
+            "org.freedesktop.Platform",
+            "22.08",
+            "org.freedesktop.Sdk",
+            "myapp",
+        ))
+    }
+
+    #[test]
+    fn test_add_icon() -> Result<()> {
+        let mut builder = builder();
+
+        builder.add_icon("128x128", "png", FileEntry::new_from_data(vec![42], false))?;
+        builder.add_icon("scalable", "svg", FileEntry::new_from_data(vec![42], false))?;
+        assert!(builder
+            .install_files()
+            .get("share/icons/hicolor/128x128/apps/org.example.MyApp.png")
+            .is_some());
+        assert!(builder
+            .install_files()
+            .get("share/icons/hicolor/scalable/apps/org.example.MyApp.svg")
+            .is_some());
+
+        assert!(builder
+            .add_icon("128x64", "png", FileEntry::new_from_data(vec![], false))
+            .is_err());
+        assert!(builder
+            .add_icon("64x64", "ico", FileEntry::new_from_data(vec![], false))
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_manifest() -> Result<()> {
+        let dest_dir = DEFAULT_TEMP_DIR.path().join("flatpak-write-manifest");
+
+        let mut builder = builder();
+        builder
+            .manifest_mut()
+            .finish_args
+            .push("--socket=wayland".into());
+        builder.install_file("bin/myapp", FileEntry::new_from_data(vec![42], true))?;
+        builder.set_desktop_file(FileEntry::new_from_data(
+            b"[Desktop Entry]\n".to_vec(),
+            false,
+        ))?;
+
+        let manifest_path = builder.write_manifest(&dest_dir)?;
+        assert_eq!(manifest_path, dest_dir.join("org.example.MyApp.json"));
+
+        let manifest: FlatpakManifest = serde_json::from_slice(&std::fs::read(&manifest_path)?)?;
+        assert_eq!(manifest.finish_args, vec!["--socket=wayland".to_string()]);
+        assert_eq!(manifest.modules.len(), 1);
+        assert_eq!(manifest.modules[0].name, "org.example.MyApp");
+        assert_eq!(
+            manifest.modules[0].sources[0].path,
+            Some("files".to_string())
+        );
+
+        assert!(dest_dir.join("files/bin/myapp").exists());
+        assert!(dest_dir
+            .join("files/share/applications/org.example.MyApp.desktop")
+            .exists());
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks() -> Result<()> {
+        let dest_dir = DEFAULT_TEMP_DIR.path().join("flatpak-symlinks");
+
+        let mut builder = builder();
+        builder.install_file(
+            "lib/myapp/1.0/myapp",
+            FileEntry::new_from_data(vec![42], true),
+        )?;
+        builder.install_symlink("lib/myapp/current", "1.0")?;
+        assert!(builder
+            .install_symlink("lib/myapp/1.0/myapp", "other")
+            .is_err());
+
+        builder.write_manifest(&dest_dir)?;
+        let link = dest_dir.join("files/lib/myapp/current");
+        assert!(link.symlink_metadata()?.file_type().is_symlink());
+        assert_eq!(std::fs::read_link(&link)?, PathBuf::from("1.0"));
+        assert!(link.join("myapp").exists());
+
+        Ok(())
+    }
+}
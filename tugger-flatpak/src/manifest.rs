@@ -0,0 +1,128 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use serde::{Deserialize, Serialize};
+
+/// Represents a source of a module.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct FlatpakSource {
+    /// The source type. e.g. `dir`, `file`, or `archive`.
+    #[serde(rename = "type")]
+    pub source_type: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+/// Represents an entry in the `modules` field.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct FlatpakModule {
+    pub name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub buildsystem: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub build_commands: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<FlatpakSource>,
+}
+
+/// Represents a `flatpak-builder` manifest.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct FlatpakManifest {
+    /// The application ID. e.g. `org.example.MyApp`.
+    pub app_id: String,
+
+    /// The runtime the application runs in. e.g. `org.freedesktop.Platform`.
+    pub runtime: String,
+
+    /// The version of the runtime. e.g. `22.08`.
+    pub runtime_version: String,
+
+    /// The SDK the application is built with. e.g. `org.freedesktop.Sdk`.
+    pub sdk: String,
+
+    /// The command run when the application is launched.
+    pub command: String,
+
+    /// Arguments to `flatpak build-finish`, defining sandbox permissions.
+    ///
+    /// e.g. `--share=network` or `--socket=wayland`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub finish_args: Vec<String>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modules: Vec<FlatpakModule>,
+}
+
+impl FlatpakManifest {
+    /// Create a new manifest with required fields.
+    pub fn new(
+        app_id: impl ToString,
+        runtime: impl ToString,
+        runtime_version: impl ToString,
+        sdk: impl ToString,
+        command: impl ToString,
+    ) -> Self {
+        Self {
+            app_id: app_id.to_string(),
+            runtime: runtime.to_string(),
+            runtime_version: runtime_version.to_string(),
+            sdk: sdk.to_string(),
+            command: command.to_string(),
+            ..Self::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize() -> anyhow::Result<()> {
+        let mut manifest = FlatpakManifest::new(
+            "org.example.MyApp",
+            "org.freedesktop.Platform",
+            "22.08",
+            "org.freedesktop.Sdk",
+            "myapp",
+        );
+        manifest.finish_args.push("--share=network".into());
+        manifest.modules.push(FlatpakModule {
+            name: "myapp".into(),
+            buildsystem: Some("simple".into()),
+            build_commands: vec!["cp -a . /app/".into()],
+            sources: vec![FlatpakSource {
+                source_type: "dir".into(),
+                path: Some("files".into()),
+                ..FlatpakSource::default()
+            }],
+        });
+
+        let value = serde_json::to_value(&manifest)?;
+        assert_eq!(value["app-id"], "org.example.MyApp");
+        assert_eq!(value["runtime-version"], "22.08");
+        assert_eq!(value["finish-args"][0], "--share=network");
+        assert_eq!(value["modules"][0]["build-commands"][0], "cp -a . /app/");
+        assert_eq!(value["modules"][0]["sources"][0]["type"], "dir");
+        assert!(value["modules"][0]["sources"][0].get("url").is_none());
+
+        let parsed: FlatpakManifest = serde_json::from_value(value)?;
+        assert_eq!(parsed, manifest);
+
+        Ok(())
+    }
+}
@@ -3,111 +3,1421 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use {
+    crate::{
+        cpio::{CpioEntry, CpioWriter},
+        header::*,
+    },
     anyhow::{anyhow, Context, Result},
-    std::path::{Path, PathBuf},
-    tugger_file_manifest::FileManifest,
+    md5::Md5,
+    pgp::{
+        crypto::hash::HashAlgorithm,
+        packet::{write_packet, SignatureConfig, SignatureType, SignatureVersion, Subpacket},
+        types::KeyTrait,
+        Deserializable, SignedSecretKey,
+    },
+    sha1::Sha1,
+    sha2::{Digest, Sha256},
+    simple_file_manifest::{FileEntry, FileManifest},
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        io::Write,
+        path::{Path, PathBuf},
+    },
+    tugger_common::file_metadata::FileMetadata,
 };
 
-#[cfg(target_family = "unix")]
-use rpm::{RPMFileOptions, RPMPackage};
+/// Names of scriptlets that can be present in an `.rpm`.
+pub const SCRIPTLETS: &[&str] = &["pretrans", "pre", "post", "preun", "postun", "posttrans"];
 
-/// Create RPMs.
-///
-/// This is a thin wrapper around rpm::RPMBuilder which provides some
-/// minor quality of life improvements, such as handling of
-/// `FileManifest` instances.
-pub struct RpmBuilder {
-    inner: rpm::RPMBuilder,
+/// Dependency flag: relation is satisfied by lower versions.
+const RPMSENSE_LESS: u32 = 1 << 1;
+/// Dependency flag: relation is satisfied by higher versions.
+const RPMSENSE_GREATER: u32 = 1 << 2;
+/// Dependency flag: relation is satisfied by the same version.
+const RPMSENSE_EQUAL: u32 = 1 << 3;
+/// Dependency flag: dependency is the interpreter of a scriptlet.
+const RPMSENSE_INTERP: u32 = 1 << 8;
+/// Dependency flag: dependency is on a feature of rpm itself.
+const RPMSENSE_RPMLIB: u32 = 1 << 24;
+
+/// File flag: file is a configuration file.
+const RPMFILE_CONFIG: u32 = 1 << 0;
+/// File flag: modified configuration files are not replaced on upgrade.
+const RPMFILE_NOREPLACE: u32 = 1 << 4;
+
+/// `RPMTAG_FILEDIGESTALGO` value for SHA-256.
+const PGPHASHALGO_SHA256: u32 = 8;
+
+/// Features of rpm required to install packages we produce.
+const RPMLIB_FEATURES: &[(&str, &str)] = &[
+    ("rpmlib(CompressedFileNames)", "3.0.4-1"),
+    ("rpmlib(FileDigests)", "4.6.0-1"),
+    ("rpmlib(PayloadFilesHavePrefix)", "4.0-1"),
+];
+
+/// The kind of a package relation.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum RelationKind {
+    Provides,
+    Requires,
+    Conflicts,
+    Obsoletes,
+}
+
+impl RelationKind {
+    /// Resolve an instance from its lowercase name. e.g. `requires`.
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "provides" => Ok(Self::Provides),
+            "requires" => Ok(Self::Requires),
+            "conflicts" => Ok(Self::Conflicts),
+            "obsoletes" => Ok(Self::Obsoletes),
+            _ => Err(anyhow!(
+                "invalid relation kind {}; must be one of provides, requires, conflicts, obsoletes",
+                name
+            )),
+        }
+    }
+
+    /// Tags holding the names, flags, and versions of relations of this kind.
+    fn tags(&self) -> (u32, u32, u32) {
+        match self {
+            Self::Provides => (
+                RPMTAG_PROVIDENAME,
+                RPMTAG_PROVIDEFLAGS,
+                RPMTAG_PROVIDEVERSION,
+            ),
+            Self::Requires => (
+                RPMTAG_REQUIRENAME,
+                RPMTAG_REQUIREFLAGS,
+                RPMTAG_REQUIREVERSION,
+            ),
+            Self::Conflicts => (
+                RPMTAG_CONFLICTNAME,
+                RPMTAG_CONFLICTFLAGS,
+                RPMTAG_CONFLICTVERSION,
+            ),
+            Self::Obsoletes => (
+                RPMTAG_OBSOLETENAME,
+                RPMTAG_OBSOLETEFLAGS,
+                RPMTAG_OBSOLETEVERSION,
+            ),
+        }
+    }
+}
 
-    build_path: PathBuf,
-    files: FileManifest,
+/// A relation on a package or capability, optionally constrained by version.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Relation {
+    pub name: String,
+    pub flags: u32,
+    pub version: String,
 }
 
-impl AsMut<rpm::RPMBuilder> for RpmBuilder {
-    fn as_mut(&mut self) -> &mut rpm::RPMBuilder {
-        &mut self.inner
+impl Relation {
+    /// Parse a relation in the form `name [operator version]`.
+    ///
+    /// e.g. `python3`, `glibc >= 2.17`, or `myapp-data = 1.0-1`. The
+    /// operator is one of `<`, `<=`, `=`, `>=`, or `>`.
+    pub fn parse(relation: &str) -> Result<Self> {
+        let parts = relation.split_whitespace().collect::<Vec<_>>();
+
+        match parts.as_slice() {
+            [name] => Ok(Self {
+                name: name.to_string(),
+                flags: 0,
+                version: "".to_string(),
+            }),
+            [name, operator, version] => {
+                let flags = match *operator {
+                    "<" => RPMSENSE_LESS,
+                    "<=" => RPMSENSE_LESS | RPMSENSE_EQUAL,
+                    "=" | "==" => RPMSENSE_EQUAL,
+                    ">=" => RPMSENSE_GREATER | RPMSENSE_EQUAL,
+                    ">" => RPMSENSE_GREATER,
+                    _ => return Err(anyhow!("invalid version operator in {}", relation)),
+                };
+
+                Ok(Self {
+                    name: name.to_string(),
+                    flags,
+                    version: version.to_string(),
+                })
+            }
+            _ => Err(anyhow!(
+                "invalid relation {}; expected `name [operator version]`",
+                relation
+            )),
+        }
     }
 }
 
+/// An entry in the package changelog.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct ChangelogEntry {
+    time: u64,
+    author: String,
+    text: String,
+}
+
+/// A file or symlink in the package payload.
+struct PayloadEntry<'a> {
+    path: &'a Path,
+    data: Vec<u8>,
+    mode: u32,
+    link_target: Option<&'a Path>,
+    metadata: FileMetadata,
+    flags: u32,
+}
+
+/// Build `.rpm` files.
+///
+/// Instances define the package's metadata, files and symlinks to install,
+/// relations on other packages, scriptlets, and changelog.
+///
+/// `.rpm` files are assembled in-process, so building doesn't require
+/// `rpmbuild` or an RPM-based system. Output is deterministic unless
+/// signed: by default, files are owned by `root` and have the modified time
+/// set by [Self::set_mtime], which is also used as the build time.
+/// [Self::set_file_metadata] overrides the mode, ownership, and modified
+/// time of individual files.
+///
+/// The payload is a gzip compressed cpio archive. File digests are SHA-256.
+/// If a signing key is set, the header and payload are signed with OpenPGP
+/// signatures, as `rpmsign` would.
+#[derive(Clone, Debug)]
+pub struct RpmBuilder {
+    name: String,
+    version: String,
+    release: String,
+    epoch: Option<u32>,
+    arch: String,
+    summary: Option<String>,
+    description: Option<String>,
+    license: Option<String>,
+    url: Option<String>,
+    vendor: Option<String>,
+    packager: Option<String>,
+    group: Option<String>,
+    install_files: FileManifest,
+    symlinks: BTreeMap<PathBuf, PathBuf>,
+    file_metadata: BTreeMap<PathBuf, FileMetadata>,
+    config_files: BTreeMap<PathBuf, bool>,
+    relations: BTreeSet<(RelationKind, Relation)>,
+    scriptlets: BTreeMap<String, String>,
+    changelog: Vec<ChangelogEntry>,
+    signing_key: Option<(SignedSecretKey, String)>,
+    mtime: u64,
+}
+
 impl RpmBuilder {
-    /// Create a new instance from required fields.
-    pub fn new<P: AsRef<Path>>(
-        build_path: P,
-        name: &str,
-        version: &str,
-        license: &str,
-        arch: &str,
-        description: &str,
+    /// Create a new builder from the package's identity.
+    ///
+    /// `arch` is an RPM architecture name. e.g. `x86_64` or `noarch`.
+    pub fn new(
+        name: impl ToString,
+        version: impl ToString,
+        release: impl ToString,
+        arch: impl ToString,
     ) -> Self {
-        let inner = rpm::RPMBuilder::new(name, version, license, arch, description);
-
         Self {
-            inner,
-            build_path: build_path.as_ref().to_path_buf(),
-            files: FileManifest::default(),
+            name: name.to_string(),
+            version: version.to_string(),
+            release: release.to_string(),
+            epoch: None,
+            arch: arch.to_string(),
+            summary: None,
+            description: None,
+            license: None,
+            url: None,
+            vendor: None,
+            packager: None,
+            group: None,
+            install_files: FileManifest::default(),
+            symlinks: BTreeMap::new(),
+            file_metadata: BTreeMap::new(),
+            config_files: BTreeMap::new(),
+            relations: BTreeSet::new(),
+            scriptlets: BTreeMap::new(),
+            changelog: vec![],
+            signing_key: None,
+            mtime: 0,
+        }
+    }
+
+    /// The package name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The package version.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// The package release.
+    pub fn release(&self) -> &str {
+        &self.release
+    }
+
+    /// The package architecture.
+    pub fn arch(&self) -> &str {
+        &self.arch
+    }
+
+    /// Set the package epoch.
+    pub fn set_epoch(&mut self, epoch: u32) {
+        self.epoch = Some(epoch);
+    }
+
+    /// Set the one line summary of the package. Required.
+    pub fn set_summary(&mut self, value: impl ToString) {
+        self.summary = Some(value.to_string());
+    }
+
+    /// Set the description of the package.
+    ///
+    /// Defaults to the summary if not set.
+    pub fn set_description(&mut self, value: impl ToString) {
+        self.description = Some(value.to_string());
+    }
+
+    /// Set the license of the package. Required.
+    pub fn set_license(&mut self, value: impl ToString) {
+        self.license = Some(value.to_string());
+    }
+
+    /// Set the URL of the project the package is derived from.
+    pub fn set_url(&mut self, value: impl ToString) {
+        self.url = Some(value.to_string());
+    }
+
+    /// Set the vendor of the package.
+    pub fn set_vendor(&mut self, value: impl ToString) {
+        self.vendor = Some(value.to_string());
+    }
+
+    /// Set the packager of the package. e.g. `Jane Doe <jane@example.com>`.
+    pub fn set_packager(&mut self, value: impl ToString) {
+        self.packager = Some(value.to_string());
+    }
+
+    /// Set the group of the package.
+    ///
+    /// Defaults to `Unspecified`.
+    pub fn set_group(&mut self, value: impl ToString) {
+        self.group = Some(value.to_string());
+    }
+
+    /// Obtain the files to be installed by the package.
+    pub fn install_files(&self) -> &FileManifest {
+        &self.install_files
+    }
+
+    /// Add a file to install.
+    ///
+    /// The path is relative to the filesystem root. e.g. `usr/bin/foo`.
+    pub fn install_file(
+        &mut self,
+        path: impl AsRef<Path>,
+        entry: impl Into<FileEntry>,
+    ) -> Result<()> {
+        self.install_files
+            .add_file_entry(normalize_path(path.as_ref()), entry)?;
+
+        Ok(())
+    }
+
+    /// Add files to install from the content of an existing [FileManifest].
+    ///
+    /// Paths are relative to the filesystem root. e.g. `usr/bin/foo`.
+    pub fn install_manifest(&mut self, manifest: &FileManifest) -> Result<()> {
+        self.install_files.add_manifest(manifest)?;
+
+        Ok(())
+    }
+
+    /// Obtain symlinks to be installed by the package.
+    ///
+    /// Keys are the paths of links and values are their targets.
+    pub fn symlinks(&self) -> &BTreeMap<PathBuf, PathBuf> {
+        &self.symlinks
+    }
+
+    /// Add a symlink to install.
+    ///
+    /// The path of the link is relative to the filesystem root. e.g.
+    /// `usr/bin/foo`. The target is stored verbatim and can be relative to the
+    /// link's directory or absolute.
+    pub fn install_symlink(
+        &mut self,
+        path: impl AsRef<Path>,
+        target: impl AsRef<Path>,
+    ) -> Result<()> {
+        let path = normalize_path(path.as_ref());
+
+        if self.install_files.has_path(path) {
+            return Err(anyhow!(
+                "cannot add symlink {}: a file is installed at that path",
+                path.display()
+            ));
         }
+
+        self.symlinks
+            .insert(path.to_path_buf(), target.as_ref().to_path_buf());
+
+        Ok(())
+    }
+
+    /// Obtain metadata of installed files, keyed by path.
+    pub fn file_metadata(&self) -> &BTreeMap<PathBuf, FileMetadata> {
+        &self.file_metadata
+    }
+
+    /// Define the metadata of an installed file.
+    ///
+    /// The path is relative to the filesystem root. Defined fields override
+    /// the default mode, `root` ownership, and modified time of the file in
+    /// the package.
+    pub fn set_file_metadata(
+        &mut self,
+        path: impl AsRef<Path>,
+        metadata: FileMetadata,
+    ) -> Result<()> {
+        metadata
+            .validate()
+            .with_context(|| format!("validating metadata of {}", path.as_ref().display()))?;
+
+        self.file_metadata
+            .insert(normalize_path(path.as_ref()).to_path_buf(), metadata);
+
+        Ok(())
+    }
+
+    /// Mark an installed file as a configuration file.
+    ///
+    /// rpm preserves local modifications to configuration files during
+    /// upgrades. If `noreplace` is true, the new version of a modified file is
+    /// installed alongside it with an `.rpmnew` suffix. Otherwise the
+    /// modified file is moved aside with an `.rpmsave` suffix. The path is
+    /// relative to the filesystem root and must be installed by the package at
+    /// build time.
+    pub fn add_config_file(&mut self, path: impl AsRef<Path>, noreplace: bool) {
+        self.config_files
+            .insert(normalize_path(path.as_ref()).to_path_buf(), noreplace);
     }
 
-    /// Populate registered files with the internal RPMBuilder.
-    pub fn populate_files(mut self) -> Result<Self> {
-        self.files
-            .materialize_files(&self.build_path)
-            .context("writing RPM data files")?;
+    /// Add a relation on another package or capability.
+    ///
+    /// See [Relation::parse] for the syntax of `relation`.
+    pub fn add_relation(&mut self, kind: RelationKind, relation: &str) -> Result<()> {
+        self.relations.insert((kind, Relation::parse(relation)?));
 
-        for (rel_path, content) in self.files.iter_entries() {
-            let real_path = self.build_path.join(rel_path);
+        Ok(())
+    }
+
+    /// Define the content of a scriptlet.
+    ///
+    /// `name` must be one of [SCRIPTLETS]. Content must begin with a `#!`
+    /// line naming the interpreter, which is recorded as a dependency of the
+    /// package.
+    pub fn set_scriptlet(&mut self, name: &str, content: impl ToString) -> Result<()> {
+        if !SCRIPTLETS.contains(&name) {
+            return Err(anyhow!(
+                "invalid scriptlet {}; must be one of {}",
+                name,
+                SCRIPTLETS.join(", ")
+            ));
+        }
+
+        let content = content.to_string();
+        if scriptlet_interpreter(&content).is_none() {
+            return Err(anyhow!(
+                "scriptlet {} must begin with a #! interpreter line",
+                name
+            ));
+        }
 
-            let mut options = RPMFileOptions::new(rel_path.display().to_string());
+        self.scriptlets.insert(name.to_string(), content);
 
-            if content.is_executable() {
-                options = options.mode(0o100_775);
+        Ok(())
+    }
+
+    /// Add an entry to the package changelog.
+    ///
+    /// `time` is in seconds since the UNIX epoch. `author` is conventionally
+    /// `Name <email> - version-release`. Entries are written newest first.
+    pub fn add_changelog_entry(&mut self, time: u64, author: impl ToString, text: impl ToString) {
+        self.changelog.push(ChangelogEntry {
+            time,
+            author: author.to_string(),
+            text: text.to_string(),
+        });
+    }
+
+    /// Sign the package with an OpenPGP secret key.
+    ///
+    /// `passphrase` unlocks the key, if it is protected.
+    pub fn set_signing_key(&mut self, key: SignedSecretKey, passphrase: impl ToString) {
+        self.signing_key = Some((key, passphrase.to_string()));
+    }
+
+    /// Sign the package with an ASCII armored OpenPGP secret key.
+    ///
+    /// See [Self::set_signing_key].
+    pub fn set_signing_key_armored(
+        &mut self,
+        armored: &str,
+        passphrase: impl ToString,
+    ) -> Result<()> {
+        let (key, _) = SignedSecretKey::from_string(armored)
+            .context("parsing ASCII armored OpenPGP secret key")?;
+        key.verify().context("verifying OpenPGP secret key")?;
+
+        self.set_signing_key(key, passphrase);
+
+        Ok(())
+    }
+
+    /// Set the modified time of files and the build time, in seconds since the UNIX epoch.
+    pub fn set_mtime(&mut self, mtime: u64) {
+        self.mtime = mtime;
+    }
+
+    /// Obtain the file name of the `.rpm` per RPM conventions.
+    ///
+    /// This is `{name}-{version}-{release}.{arch}.rpm`.
+    pub fn rpm_file_name(&self) -> String {
+        format!(
+            "{}-{}-{}.{}.rpm",
+            self.name, self.version, self.release, self.arch
+        )
+    }
+
+    /// The full version of the package, including any epoch.
+    fn full_version(&self) -> String {
+        match self.epoch {
+            Some(epoch) => format!("{}:{}-{}", epoch, self.version, self.release),
+            None => format!("{}-{}", self.version, self.release),
+        }
+    }
+
+    /// Resolve files and symlinks in the payload.
+    ///
+    /// Entries are sorted byte-wise by their archive path, as rpm does.
+    fn payload_entries(&self) -> Result<Vec<PayloadEntry<'_>>> {
+        for path in self.config_files.keys() {
+            if !self.install_files.has_path(path) {
+                return Err(anyhow!(
+                    "config file {} is not installed by the package",
+                    path.display()
+                ));
             }
+        }
+
+        let mut entries = BTreeMap::new();
+
+        for (path, entry) in self.install_files.iter_entries() {
+            let data = entry
+                .resolve_content()
+                .with_context(|| format!("resolving content of {}", path.display()))?;
+            let metadata = self.file_metadata.get(path).cloned().unwrap_or_default();
 
-            // TODO support additional attributes, such as owner/group.
-            // TODO make deterministic by modifying upstream to allow control
-            // over what add_data() does.
+            let flags = match self.config_files.get(path) {
+                Some(true) => RPMFILE_CONFIG | RPMFILE_NOREPLACE,
+                Some(false) => RPMFILE_CONFIG,
+                None => 0,
+            };
 
-            self.inner = self
-                .inner
-                .with_file(&real_path, options)
-                .map_err(|e| anyhow!("error registering file with RPMBuilder: {}", e))
-                .context("registering file with RPM")?;
+            entries.insert(
+                archive_path(path),
+                PayloadEntry {
+                    path: path.as_path(),
+                    data,
+                    mode: 0o100000 | metadata.resolve_mode(entry.is_executable()),
+                    link_target: None,
+                    metadata,
+                    flags,
+                },
+            );
         }
 
-        Ok(self)
+        for (path, target) in &self.symlinks {
+            if self.install_files.has_path(path) {
+                return Err(anyhow!(
+                    "{} is installed as both a file and a symlink",
+                    path.display()
+                ));
+            }
+
+            let metadata = self.file_metadata.get(path).cloned().unwrap_or_default();
+
+            entries.insert(
+                archive_path(path),
+                PayloadEntry {
+                    path: path.as_path(),
+                    data: archive_path(target).into_bytes(),
+                    mode: 0o120777,
+                    link_target: Some(target.as_path()),
+                    metadata,
+                    flags: 0,
+                },
+            );
+        }
+
+        Ok(entries.into_values().collect())
     }
 
-    /// Build the RPM, consuming self.
-    pub fn build(mut self) -> Result<RPMPackage> {
-        self = self
-            .populate_files()
-            .context("populating files with builder")?;
+    /// Write the compressed cpio payload, returning its uncompressed size.
+    fn write_payload(&self, entries: &[PayloadEntry], writer: impl Write) -> Result<usize> {
+        let mut cpio = CpioWriter::new(CountingWriter {
+            inner: flate2::write::GzEncoder::new(writer, flate2::Compression::best()),
+            count: 0,
+        });
 
-        let package = self
-            .inner
-            .build()
-            .map_err(|e| anyhow!("error building RPM: {}", e))
-            .context("building RPM")?;
+        for (i, entry) in entries.iter().enumerate() {
+            let name = format!("./{}", archive_path(entry.path));
+
+            cpio.append(&CpioEntry {
+                name: &name,
+                inode: i as u32 + 1,
+                mode: entry.mode,
+                uid: entry.metadata.uid.unwrap_or(0) as u32,
+                gid: entry.metadata.gid.unwrap_or(0) as u32,
+                mtime: int32(entry.metadata.mtime.unwrap_or(self.mtime), "mtime")?,
+                data: &entry.data,
+            })
+            .with_context(|| format!("adding {} to payload", entry.path.display()))?;
+        }
 
-        Ok(package)
+        let writer = cpio.finish()?;
+        writer.inner.finish()?;
+
+        Ok(writer.count)
     }
 
-    /// Build the RPM, writing it to a filesystem path, consuming self.
-    pub fn build_to_path<P: AsRef<Path>>(self, dest_path: P) -> Result<()> {
-        let package = self.build()?;
+    /// Derive the main header.
+    fn main_header(&self, entries: &[PayloadEntry]) -> Result<Header> {
+        let summary = self
+            .summary
+            .as_ref()
+            .ok_or_else(|| anyhow!("package summary is not defined"))?;
+        let license = self
+            .license
+            .as_ref()
+            .ok_or_else(|| anyhow!("package license is not defined"))?;
 
-        let dest_path = dest_path.as_ref();
+        let mut header = Header::default();
+        let string = |s: &str| IndexValue::String(s.to_string());
+        let i18n = |s: &str| IndexValue::I18nString(s.to_string());
 
-        if let Some(parent) = dest_path.parent() {
-            if !parent.exists() {
-                std::fs::create_dir_all(parent)?;
+        header.set(
+            RPMTAG_HEADERI18NTABLE,
+            IndexValue::StringArray(vec!["C".to_string()]),
+        );
+        header.set(RPMTAG_NAME, string(&self.name));
+        header.set(RPMTAG_VERSION, string(&self.version));
+        header.set(RPMTAG_RELEASE, string(&self.release));
+        if let Some(epoch) = self.epoch {
+            header.set(RPMTAG_EPOCH, IndexValue::Int32(vec![epoch]));
+        }
+        header.set(RPMTAG_SUMMARY, i18n(summary));
+        header.set(
+            RPMTAG_DESCRIPTION,
+            i18n(self.description.as_deref().unwrap_or(summary)),
+        );
+        header.set(
+            RPMTAG_BUILDTIME,
+            IndexValue::Int32(vec![int32(self.mtime, "build time")?]),
+        );
+        header.set(RPMTAG_BUILDHOST, string("localhost"));
+        header.set(RPMTAG_LICENSE, string(license));
+        header.set(
+            RPMTAG_GROUP,
+            i18n(self.group.as_deref().unwrap_or("Unspecified")),
+        );
+        for (tag, value) in [
+            (RPMTAG_URL, &self.url),
+            (RPMTAG_VENDOR, &self.vendor),
+            (RPMTAG_PACKAGER, &self.packager),
+        ] {
+            if let Some(value) = value {
+                header.set(tag, string(value));
             }
         }
+        header.set(RPMTAG_OS, string("linux"));
+        header.set(RPMTAG_ARCH, string(&self.arch));
+        header.set(
+            RPMTAG_SOURCERPM,
+            string(&format!(
+                "{}-{}-{}.src.rpm",
+                self.name, self.version, self.release
+            )),
+        );
+        header.set(RPMTAG_RPMVERSION, string("4.14.3"));
+        header.set(RPMTAG_PAYLOADFORMAT, string("cpio"));
+        header.set(RPMTAG_PAYLOADCOMPRESSOR, string("gzip"));
+        header.set(RPMTAG_PAYLOADFLAGS, string("9"));
+
+        self.add_file_tags(&mut header, entries)?;
+        self.add_relation_tags(&mut header);
+        self.add_scriptlet_tags(&mut header);
+        self.add_changelog_tags(&mut header)?;
+
+        Ok(header)
+    }
+
+    fn add_file_tags(&self, header: &mut Header, entries: &[PayloadEntry]) -> Result<()> {
+        let mut size = 0u64;
+        let mut dirnames: Vec<String> = vec![];
+        let mut dirindexes = vec![];
+        let mut basenames = vec![];
+
+        for entry in entries {
+            size += entry.data.len() as u64;
+
+            let path = archive_path(entry.path);
+            let (dirname, basename) = match path.rsplit_once('/') {
+                Some((dirname, basename)) => (format!("/{}/", dirname), basename.to_string()),
+                None => ("/".to_string(), path.clone()),
+            };
+
+            let index = match dirnames.iter().position(|x| x == &dirname) {
+                Some(index) => index,
+                None => {
+                    dirnames.push(dirname);
+                    dirnames.len() - 1
+                }
+            };
+
+            dirindexes.push(index as u32);
+            basenames.push(basename);
+        }
+
+        let count = entries.len();
+        let strings = |f: &dyn Fn(&PayloadEntry) -> String| {
+            IndexValue::StringArray(entries.iter().map(f).collect())
+        };
+
+        header.set(
+            RPMTAG_SIZE,
+            IndexValue::Int32(vec![int32(size, "package size")?]),
+        );
+
+        if count == 0 {
+            return Ok(());
+        }
+
+        header.set(
+            RPMTAG_FILESIZES,
+            IndexValue::Int32(entries.iter().map(|x| x.data.len() as u32).collect()),
+        );
+        header.set(
+            RPMTAG_FILEMODES,
+            IndexValue::Int16(entries.iter().map(|x| x.mode as u16).collect()),
+        );
+        header.set(RPMTAG_FILERDEVS, IndexValue::Int16(vec![0; count]));
+        header.set(
+            RPMTAG_FILEMTIMES,
+            IndexValue::Int32(
+                entries
+                    .iter()
+                    .map(|x| int32(x.metadata.mtime.unwrap_or(self.mtime), "mtime"))
+                    .collect::<Result<Vec<_>>>()?,
+            ),
+        );
+        header.set(
+            RPMTAG_FILEDIGESTS,
+            strings(&|x| {
+                if x.link_target.is_some() {
+                    "".to_string()
+                } else {
+                    hex::encode(Sha256::digest(&x.data))
+                }
+            }),
+        );
+        header.set(
+            RPMTAG_FILELINKTOS,
+            strings(&|x| x.link_target.map(archive_path).unwrap_or_default()),
+        );
+        header.set(
+            RPMTAG_FILEFLAGS,
+            IndexValue::Int32(entries.iter().map(|x| x.flags).collect()),
+        );
+        header.set(
+            RPMTAG_FILEUSERNAME,
+            strings(&|x| x.metadata.user.clone().unwrap_or_else(|| "root".into())),
+        );
+        header.set(
+            RPMTAG_FILEGROUPNAME,
+            strings(&|x| x.metadata.group.clone().unwrap_or_else(|| "root".into())),
+        );
+        header.set(
+            RPMTAG_FILEVERIFYFLAGS,
+            IndexValue::Int32(vec![u32::MAX; count]),
+        );
+        header.set(RPMTAG_FILEDEVICES, IndexValue::Int32(vec![1; count]));
+        header.set(
+            RPMTAG_FILEINODES,
+            IndexValue::Int32((1..=count as u32).collect()),
+        );
+        header.set(RPMTAG_FILELANGS, strings(&|_| "".to_string()));
+        header.set(RPMTAG_DIRINDEXES, IndexValue::Int32(dirindexes));
+        header.set(RPMTAG_BASENAMES, IndexValue::StringArray(basenames));
+        header.set(RPMTAG_DIRNAMES, IndexValue::StringArray(dirnames));
+        header.set(
+            RPMTAG_FILEDIGESTALGO,
+            IndexValue::Int32(vec![PGPHASHALGO_SHA256]),
+        );
+
+        Ok(())
+    }
+
+    fn add_relation_tags(&self, header: &mut Header) {
+        let mut relations = self.relations.clone();
+
+        // Packages provide themselves.
+        relations.insert((
+            RelationKind::Provides,
+            Relation {
+                name: self.name.clone(),
+                flags: RPMSENSE_EQUAL,
+                version: self.full_version(),
+            },
+        ));
+
+        for (name, version) in RPMLIB_FEATURES {
+            relations.insert((
+                RelationKind::Requires,
+                Relation {
+                    name: name.to_string(),
+                    flags: RPMSENSE_RPMLIB | RPMSENSE_LESS | RPMSENSE_EQUAL,
+                    version: version.to_string(),
+                },
+            ));
+        }
+
+        for content in self.scriptlets.values() {
+            if let Some(interpreter) = scriptlet_interpreter(content) {
+                relations.insert((
+                    RelationKind::Requires,
+                    Relation {
+                        name: interpreter[0].to_string(),
+                        flags: RPMSENSE_INTERP,
+                        version: "".to_string(),
+                    },
+                ));
+            }
+        }
+
+        for kind in [
+            RelationKind::Provides,
+            RelationKind::Requires,
+            RelationKind::Conflicts,
+            RelationKind::Obsoletes,
+        ] {
+            let relations = relations
+                .iter()
+                .filter(|(k, _)| *k == kind)
+                .map(|(_, r)| r)
+                .collect::<Vec<_>>();
+
+            if relations.is_empty() {
+                continue;
+            }
+
+            let (name_tag, flags_tag, version_tag) = kind.tags();
+            header.set(
+                name_tag,
+                IndexValue::StringArray(relations.iter().map(|r| r.name.clone()).collect()),
+            );
+            header.set(
+                flags_tag,
+                IndexValue::Int32(relations.iter().map(|r| r.flags).collect()),
+            );
+            header.set(
+                version_tag,
+                IndexValue::StringArray(relations.iter().map(|r| r.version.clone()).collect()),
+            );
+        }
+    }
+
+    fn add_scriptlet_tags(&self, header: &mut Header) {
+        for (name, content) in &self.scriptlets {
+            let (script_tag, program_tag) = match name.as_str() {
+                "pretrans" => (RPMTAG_PRETRANS, RPMTAG_PRETRANSPROG),
+                "pre" => (RPMTAG_PREIN, RPMTAG_PREINPROG),
+                "post" => (RPMTAG_POSTIN, RPMTAG_POSTINPROG),
+                "preun" => (RPMTAG_PREUN, RPMTAG_PREUNPROG),
+                "postun" => (RPMTAG_POSTUN, RPMTAG_POSTUNPROG),
+                "posttrans" => (RPMTAG_POSTTRANS, RPMTAG_POSTTRANSPROG),
+                _ => unreachable!("scriptlet names are validated when set"),
+            };
+
+            if let Some(interpreter) = scriptlet_interpreter(content) {
+                header.set(script_tag, IndexValue::String(content.clone()));
+                header.set(
+                    program_tag,
+                    IndexValue::StringArray(interpreter.iter().map(|x| x.to_string()).collect()),
+                );
+            }
+        }
+    }
+
+    fn add_changelog_tags(&self, header: &mut Header) -> Result<()> {
+        if self.changelog.is_empty() {
+            return Ok(());
+        }
+
+        let mut entries = self.changelog.iter().collect::<Vec<_>>();
+        entries.sort_by(|a, b| b.time.cmp(&a.time));
+
+        header.set(
+            RPMTAG_CHANGELOGTIME,
+            IndexValue::Int32(
+                entries
+                    .iter()
+                    .map(|x| int32(x.time, "changelog time"))
+                    .collect::<Result<Vec<_>>>()?,
+            ),
+        );
+        header.set(
+            RPMTAG_CHANGELOGNAME,
+            IndexValue::StringArray(entries.iter().map(|x| x.author.clone()).collect()),
+        );
+        header.set(
+            RPMTAG_CHANGELOGTEXT,
+            IndexValue::StringArray(entries.iter().map(|x| x.text.clone()).collect()),
+        );
+
+        Ok(())
+    }
+
+    /// Produce an OpenPGP signature of data using the signing key.
+    fn sign(&self, data: &[u8]) -> Result<Option<Vec<u8>>> {
+        let (key, passphrase) = match &self.signing_key {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+
+        let now = chrono::Utc::now();
+        let config = SignatureConfig::new_v4(
+            SignatureVersion::V4,
+            SignatureType::Binary,
+            key.algorithm(),
+            HashAlgorithm::SHA2_256,
+            vec![
+                Subpacket::SignatureCreationTime(now),
+                Subpacket::Issuer(key.key_id()),
+            ],
+            vec![],
+        );
+
+        let signature = config
+            .sign(key, || passphrase.clone(), data)
+            .context("creating OpenPGP signature")?;
+
+        let mut res = vec![];
+        write_packet(&mut res, &signature).context("serializing OpenPGP signature")?;
+
+        Ok(Some(res))
+    }
+
+    /// Write the `.rpm` to a writer.
+    pub fn write_rpm(&self, mut writer: impl Write) -> Result<()> {
+        let entries = self.payload_entries()?;
+
+        let mut payload = vec![];
+        let payload_size = self
+            .write_payload(&entries, &mut payload)
+            .context("writing payload")?;
+
+        let header = self
+            .main_header(&entries)?
+            .to_bytes(RPMTAG_HEADERIMMUTABLE)
+            .context("serializing header")?;
+
+        let mut signature = Header::default();
+        signature.set(
+            RPMSIGTAG_SIZE,
+            IndexValue::Int32(vec![int32(
+                (header.len() + payload.len()) as u64,
+                "package size",
+            )?]),
+        );
+        signature.set(
+            RPMSIGTAG_PAYLOADSIZE,
+            IndexValue::Int32(vec![int32(payload_size as u64, "payload size")?]),
+        );
+        signature.set(
+            RPMSIGTAG_MD5,
+            IndexValue::Bin(
+                Md5::new()
+                    .chain_update(&header)
+                    .chain_update(&payload)
+                    .finalize()
+                    .to_vec(),
+            ),
+        );
+        signature.set(
+            RPMSIGTAG_SHA1,
+            IndexValue::String(hex::encode(Sha1::digest(&header))),
+        );
+        signature.set(
+            RPMSIGTAG_SHA256,
+            IndexValue::String(hex::encode(Sha256::digest(&header))),
+        );
+        if let Some(data) = self.sign(&header).context("signing header")? {
+            signature.set(RPMSIGTAG_RSA, IndexValue::Bin(data));
+        }
+        if let Some(data) = self
+            .sign(&[header.as_slice(), payload.as_slice()].concat())
+            .context("signing header and payload")?
+        {
+            signature.set(RPMSIGTAG_PGP, IndexValue::Bin(data));
+        }
+
+        let mut signature = signature
+            .to_bytes(RPMTAG_HEADERSIGNATURES)
+            .context("serializing signature header")?;
+        // The signature header is padded to an 8 byte boundary.
+        signature.resize((signature.len() + 7) / 8 * 8, 0);
+
+        writer.write_all(&self.lead())?;
+        writer.write_all(&signature)?;
+        writer.write_all(&header)?;
+        writer.write_all(&payload)?;
+
+        Ok(())
+    }
+
+    /// Write the `.rpm` into a directory, using [Self::rpm_file_name()] as its name.
+    ///
+    /// Returns the path of the written file.
+    pub fn write_rpm_into_directory(&self, directory: impl AsRef<Path>) -> Result<PathBuf> {
+        let path = directory.as_ref().join(self.rpm_file_name());
+
+        let fh = std::fs::File::create(&path)
+            .with_context(|| format!("opening {} for writing", path.display()))?;
+        self.write_rpm(std::io::BufWriter::new(fh))?;
+
+        Ok(path)
+    }
+
+    /// Derive the legacy lead preceding the signature header.
+    fn lead(&self) -> Vec<u8> {
+        let name = format!("{}-{}-{}", self.name, self.version, self.release);
+
+        let mut lead = vec![0xed, 0xab, 0xee, 0xdb, 3, 0];
+        // Binary package.
+        lead.extend_from_slice(&0u16.to_be_bytes());
+        lead.extend_from_slice(&lead_arch_number(&self.arch).to_be_bytes());
+
+        let mut name_field = [0u8; 66];
+        let len = name.len().min(65);
+        name_field[..len].copy_from_slice(&name.as_bytes()[..len]);
+        lead.extend_from_slice(&name_field);
+
+        // Linux.
+        lead.extend_from_slice(&1u16.to_be_bytes());
+        // Signature is a header structure.
+        lead.extend_from_slice(&5u16.to_be_bytes());
+        lead.extend_from_slice(&[0u8; 16]);
+
+        lead
+    }
+}
+
+/// Resolve the RPM architecture name for a Rust target triple.
+pub fn target_triple_to_rpm_arch(triple: &str) -> Option<&'static str> {
+    match triple {
+        "x86_64-unknown-linux-gnu" | "x86_64-unknown-linux-musl" => Some("x86_64"),
+        "i686-unknown-linux-gnu" | "i686-unknown-linux-musl" => Some("i686"),
+        "aarch64-unknown-linux-gnu" | "aarch64-unknown-linux-musl" => Some("aarch64"),
+        "armv7-unknown-linux-gnueabihf" => Some("armv7hl"),
+        "powerpc64le-unknown-linux-gnu" => Some("ppc64le"),
+        "s390x-unknown-linux-gnu" => Some("s390x"),
+        _ => None,
+    }
+}
+
+/// Resolve the architecture number recorded in the lead.
+fn lead_arch_number(arch: &str) -> u16 {
+    match arch {
+        "x86_64" | "i386" | "i486" | "i586" | "i686" => 1,
+        "armv7hl" => 12,
+        "s390x" => 15,
+        "ppc64le" => 16,
+        "aarch64" => 19,
+        _ => 0,
+    }
+}
+
+/// Parse the interpreter and its arguments from the `#!` line of a scriptlet.
+fn scriptlet_interpreter(content: &str) -> Option<Vec<&str>> {
+    let line = content.lines().next()?.strip_prefix("#!")?;
+    let parts = line.split_whitespace().collect::<Vec<_>>();
+
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts)
+    }
+}
+
+/// Convert a value to the 32-bit integer stored in headers.
+fn int32(value: u64, what: &str) -> Result<u32> {
+    u32::try_from(value).map_err(|_| anyhow!("{} {} does not fit in 32 bits", what, value))
+}
+
+/// Strip a leading `/` from a path.
+fn normalize_path(path: &Path) -> &Path {
+    path.strip_prefix("/").unwrap_or(path)
+}
+
+/// Render a path for use in the payload or header.
+fn archive_path(path: &Path) -> String {
+    path.display().to_string().replace('\\', "/")
+}
+
+/// A writer counting the bytes written through it.
+struct CountingWriter<W: Write> {
+    inner: W,
+    count: usize,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count += written;
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::io::Read};
+
+    fn builder() -> Result<RpmBuilder> {
+        let mut builder = RpmBuilder::new("myapp", "1.0", "1", "x86_64");
+        builder.set_summary("My app");
+        builder.set_license("MPL-2.0");
+        builder.install_file(
+            "usr/bin/myapp",
+            FileEntry::new_from_data(vec![42; 2000], true),
+        )?;
+        builder.install_file(
+            "etc/myapp.conf",
+            FileEntry::new_from_data(b"a = 1\n".to_vec(), false),
+        )?;
+
+        Ok(builder)
+    }
+
+    /// Resolve the signature header and the offsets of the main header and payload.
+    fn split_rpm(data: &[u8]) -> Result<(Header, usize, usize)> {
+        assert_eq!(&data[0..4], &[0xed, 0xab, 0xee, 0xdb]);
+
+        let (signature, size) = Header::parse(&data[96..])?;
+        let header_offset = 96 + (size + 7) / 8 * 8;
+        let (_, size) = Header::parse(&data[header_offset..])?;
+
+        Ok((signature, header_offset, header_offset + size))
+    }
+
+    /// Parse an `.rpm` into its signature header, main header, and decompressed payload.
+    fn parse_rpm(data: &[u8]) -> Result<(Header, Header, Vec<u8>)> {
+        let (signature, header_offset, payload_offset) = split_rpm(data)?;
+        let (header, _) = Header::parse(&data[header_offset..])?;
+
+        let mut payload = vec![];
+        flate2::read::GzDecoder::new(&data[payload_offset..]).read_to_end(&mut payload)?;
+
+        Ok((signature, header, payload))
+    }
+
+    #[test]
+    fn test_rpm_file_name() {
+        let builder = RpmBuilder::new("myapp", "1.0", "1", "x86_64");
+        assert_eq!(builder.rpm_file_name(), "myapp-1.0-1.x86_64.rpm");
+    }
+
+    #[test]
+    fn test_relation_parse() -> Result<()> {
+        assert_eq!(
+            Relation::parse("glibc >= 2.17")?,
+            Relation {
+                name: "glibc".into(),
+                flags: RPMSENSE_GREATER | RPMSENSE_EQUAL,
+                version: "2.17".into()
+            }
+        );
+        assert_eq!(Relation::parse("python3")?.flags, 0);
+        assert!(Relation::parse("foo ~ 1").is_err());
+        assert!(Relation::parse("foo >=").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_required_fields() -> Result<()> {
+        let mut builder = RpmBuilder::new("myapp", "1.0", "1", "x86_64");
+        assert!(builder.write_rpm(vec![]).is_err());
+        builder.set_summary("My app");
+        assert!(builder.write_rpm(vec![]).is_err());
+        builder.set_license("MPL-2.0");
+        builder.write_rpm(vec![])?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_rpm() -> Result<()> {
+        let mut builder = builder()?;
+        builder.install_symlink("usr/bin/myapp-link", "myapp")?;
+        builder.add_config_file("etc/myapp.conf", true);
+        builder.set_file_metadata(
+            "usr/bin/myapp",
+            FileMetadata {
+                mode: Some(0o4750),
+                user: Some("myapp".into()),
+                ..Default::default()
+            },
+        )?;
+
+        let mut data = vec![];
+        builder.write_rpm(&mut data)?;
+
+        let (signature, header, payload) = parse_rpm(&data)?;
+
+        assert_eq!(header.get_string(RPMTAG_NAME), Some("myapp"));
+        assert_eq!(header.get_string(RPMTAG_DESCRIPTION), Some("My app"));
+        assert_eq!(
+            header.get(RPMTAG_DIRNAMES),
+            Some(&IndexValue::StringArray(vec![
+                "/etc/".into(),
+                "/usr/bin/".into()
+            ]))
+        );
+        assert_eq!(
+            header.get(RPMTAG_BASENAMES),
+            Some(&IndexValue::StringArray(vec![
+                "myapp.conf".into(),
+                "myapp".into(),
+                "myapp-link".into()
+            ]))
+        );
+        assert_eq!(
+            header.get(RPMTAG_FILEMODES),
+            Some(&IndexValue::Int16(vec![0o100644, 0o104750, 0o120777]))
+        );
+        assert_eq!(
+            header.get(RPMTAG_FILEFLAGS),
+            Some(&IndexValue::Int32(vec![
+                RPMFILE_CONFIG | RPMFILE_NOREPLACE,
+                0,
+                0
+            ]))
+        );
+        assert_eq!(
+            header.get(RPMTAG_FILEUSERNAME),
+            Some(&IndexValue::StringArray(vec![
+                "root".into(),
+                "myapp".into(),
+                "root".into()
+            ]))
+        );
+        assert_eq!(
+            header.get(RPMTAG_FILELINKTOS),
+            Some(&IndexValue::StringArray(vec![
+                "".into(),
+                "".into(),
+                "myapp".into()
+            ]))
+        );
+        assert_eq!(
+            header.get(RPMTAG_PROVIDENAME),
+            Some(&IndexValue::StringArray(vec!["myapp".into()]))
+        );
+        assert_eq!(
+            header.get(RPMTAG_PROVIDEVERSION),
+            Some(&IndexValue::StringArray(vec!["1.0-1".into()]))
+        );
+
+        assert_eq!(
+            signature.get(RPMSIGTAG_PAYLOADSIZE),
+            Some(&IndexValue::Int32(vec![payload.len() as u32]))
+        );
+        assert!(signature.get(RPMSIGTAG_RSA).is_none());
+
+        let payload_s = String::from_utf8_lossy(&payload);
+        assert!(payload_s.contains("./etc/myapp.conf\0"));
+        assert!(payload_s.contains("./usr/bin/myapp-link\0"));
+        assert!(payload_s.contains("TRAILER!!!\0"));
+
+        // Output is deterministic.
+        let mut data2 = vec![];
+        builder.write_rpm(&mut data2)?;
+        assert_eq!(data, data2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_payload_order() -> Result<()> {
+        let mut builder = builder()?;
+        builder.install_file("usr/foo/x", FileEntry::new_from_data(vec![], false))?;
+        builder.install_file("usr/foo-bar", FileEntry::new_from_data(vec![], false))?;
+        builder.install_symlink("usr/foo.link", "foo-bar")?;
+
+        let paths = builder
+            .payload_entries()?
+            .iter()
+            .map(|e| archive_path(e.path))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                "etc/myapp.conf",
+                "usr/bin/myapp",
+                "usr/foo-bar",
+                "usr/foo.link",
+                "usr/foo/x"
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_file_not_installed() -> Result<()> {
+        let mut builder = builder()?;
+        builder.add_config_file("etc/missing.conf", false);
+
+        assert!(builder.write_rpm(vec![]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_relations_and_scriptlets() -> Result<()> {
+        let mut builder = builder()?;
+        builder.set_epoch(2);
+        builder.add_relation(RelationKind::Requires, "glibc >= 2.17")?;
+        builder.add_relation(RelationKind::Conflicts, "otherapp")?;
+        builder.add_relation(RelationKind::Obsoletes, "myapp-legacy < 1.0")?;
+        builder.set_scriptlet("post", "#!/bin/sh -e\nldconfig\n")?;
+        assert!(builder.set_scriptlet("postinst", "#!/bin/sh\n").is_err());
+        assert!(builder.set_scriptlet("pre", "ldconfig\n").is_err());
+
+        let mut data = vec![];
+        builder.write_rpm(&mut data)?;
+        let (_, header, _) = parse_rpm(&data)?;
+
+        assert_eq!(
+            header.get(RPMTAG_PROVIDEVERSION),
+            Some(&IndexValue::StringArray(vec!["2:1.0-1".into()]))
+        );
+        assert_eq!(
+            header.get(RPMTAG_REQUIRENAME),
+            Some(&IndexValue::StringArray(vec![
+                "/bin/sh".into(),
+                "glibc".into(),
+                "rpmlib(CompressedFileNames)".into(),
+                "rpmlib(FileDigests)".into(),
+                "rpmlib(PayloadFilesHavePrefix)".into(),
+            ]))
+        );
+        assert_eq!(
+            header.get(RPMTAG_CONFLICTNAME),
+            Some(&IndexValue::StringArray(vec!["otherapp".into()]))
+        );
+        assert_eq!(
+            header.get(RPMTAG_OBSOLETEFLAGS),
+            Some(&IndexValue::Int32(vec![RPMSENSE_LESS]))
+        );
+        assert_eq!(
+            header.get_string(RPMTAG_POSTIN),
+            Some("#!/bin/sh -e\nldconfig\n")
+        );
+        assert_eq!(
+            header.get(RPMTAG_POSTINPROG),
+            Some(&IndexValue::StringArray(vec![
+                "/bin/sh".into(),
+                "-e".into()
+            ]))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_changelog() -> Result<()> {
+        let mut builder = builder()?;
+        builder.add_changelog_entry(1000, "Jane Doe <jane@example.com> - 0.9-1", "- Initial");
+        builder.add_changelog_entry(2000, "Jane Doe <jane@example.com> - 1.0-1", "- Update");
+
+        let mut data = vec![];
+        builder.write_rpm(&mut data)?;
+        let (_, header, _) = parse_rpm(&data)?;
+
+        assert_eq!(
+            header.get(RPMTAG_CHANGELOGTIME),
+            Some(&IndexValue::Int32(vec![2000, 1000]))
+        );
+        assert_eq!(
+            header.get(RPMTAG_CHANGELOGTEXT),
+            Some(&IndexValue::StringArray(vec![
+                "- Update".into(),
+                "- Initial".into()
+            ]))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_signing() -> Result<()> {
+        use pgp::{
+            composed::{KeyType, SecretKeyParamsBuilder},
+            StandaloneSignature,
+        };
+
+        let key = SecretKeyParamsBuilder::default()
+            .key_type(KeyType::EdDSA)
+            .can_sign(true)
+            .primary_user_id("Jane Doe <jane@example.com>".into())
+            .build()
+            .map_err(|e| anyhow!("{}", e))?
+            .generate()?
+            .sign(String::new)?;
+
+        let mut builder = builder()?;
+        assert!(builder.set_signing_key_armored("garbage", "").is_err());
+        builder.set_signing_key_armored(&key.to_armored_string(None)?, "")?;
+
+        let mut data = vec![];
+        builder.write_rpm(&mut data)?;
+
+        let (signature, header_offset, payload_offset) = split_rpm(&data)?;
+        let header = &data[header_offset..payload_offset];
+
+        let rsa = match signature.get(RPMSIGTAG_RSA) {
+            Some(IndexValue::Bin(data)) => data.clone(),
+            _ => return Err(anyhow!("header signature missing")),
+        };
+        StandaloneSignature::from_bytes(rsa.as_slice())?.verify(&key, header)?;
 
-        let mut f = std::fs::File::create(dest_path)?;
-        package
-            .write(&mut f)
-            .map_err(|e| anyhow!("error writing RPM: {}", e))?;
+        let pgp = match signature.get(RPMSIGTAG_PGP) {
+            Some(IndexValue::Bin(data)) => data.clone(),
+            _ => return Err(anyhow!("header and payload signature missing")),
+        };
+        StandaloneSignature::from_bytes(pgp.as_slice())?.verify(&key, &data[header_offset..])?;
 
         Ok(())
     }
@@ -0,0 +1,139 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! Writing of `newc` cpio archives, as used for RPM payloads. */
+
+use {
+    anyhow::{anyhow, Result},
+    std::io::Write,
+};
+
+/// Magic starting each `newc` archive member header.
+const NEWC_MAGIC: &[u8] = b"070701";
+
+/// Name of the member terminating an archive.
+const TRAILER_NAME: &str = "TRAILER!!!";
+
+/// A member in a cpio archive.
+#[derive(Clone, Debug, Default)]
+pub struct CpioEntry<'a> {
+    pub name: &'a str,
+    pub inode: u32,
+    /// Full mode, including the file type bits.
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub mtime: u32,
+    pub data: &'a [u8],
+}
+
+/// Write `newc` cpio archives.
+pub struct CpioWriter<W: Write> {
+    writer: W,
+    offset: usize,
+}
+
+impl<W: Write> CpioWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer, offset: 0 }
+    }
+
+    /// Write raw data, tracking the offset for alignment purposes.
+    fn write_raw(&mut self, data: &[u8]) -> Result<()> {
+        self.writer.write_all(data)?;
+        self.offset += data.len();
+
+        Ok(())
+    }
+
+    /// Pad the archive to a 4 byte boundary.
+    fn pad(&mut self) -> Result<()> {
+        let padding = (4 - self.offset % 4) % 4;
+        self.write_raw(&[0u8; 3][..padding])
+    }
+
+    /// Append a member to the archive.
+    pub fn append(&mut self, entry: &CpioEntry) -> Result<()> {
+        if entry.name.contains('\0') {
+            return Err(anyhow!("cpio member names cannot contain NUL"));
+        }
+
+        let size = u32::try_from(entry.data.len())
+            .map_err(|_| anyhow!("{} is too large for a cpio archive", entry.name))?;
+
+        let mut header = NEWC_MAGIC.to_vec();
+        for value in [
+            entry.inode,
+            entry.mode,
+            entry.uid,
+            entry.gid,
+            // nlink
+            1,
+            entry.mtime,
+            size,
+            // devmajor, devminor, rdevmajor, rdevminor
+            0,
+            0,
+            0,
+            0,
+            (entry.name.len() + 1) as u32,
+            // check
+            0,
+        ] {
+            header.extend_from_slice(format!("{:08x}", value).as_bytes());
+        }
+
+        self.write_raw(&header)?;
+        self.write_raw(entry.name.as_bytes())?;
+        self.write_raw(&[0])?;
+        self.pad()?;
+        self.write_raw(entry.data)?;
+        self.pad()?;
+
+        Ok(())
+    }
+
+    /// Write the archive trailer and return the inner writer.
+    pub fn finish(mut self) -> Result<W> {
+        self.append(&CpioEntry {
+            name: TRAILER_NAME,
+            ..Default::default()
+        })?;
+
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write() -> Result<()> {
+        let mut writer = CpioWriter::new(vec![]);
+        writer.append(&CpioEntry {
+            name: "./a",
+            inode: 1,
+            mode: 0o100644,
+            data: b"hello",
+            ..Default::default()
+        })?;
+        let data = writer.finish()?;
+
+        // 110 byte header + "./a\0" = 114, padded to 116.
+        assert_eq!(&data[0..6], b"070701");
+        assert_eq!(&data[6..14], b"00000001");
+        assert_eq!(&data[14..22], b"000081a4");
+        assert_eq!(&data[54..62], b"00000005");
+        assert_eq!(&data[110..114], b"./a\0");
+        assert_eq!(&data[116..121], b"hello");
+
+        // Data is padded to 124, where the trailer begins.
+        assert_eq!(&data[124..130], b"070701");
+        assert_eq!(&data[234..245], b"TRAILER!!!\0");
+        assert_eq!(data.len() % 4, 0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,421 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! RPM header structures.
+
+Both the signature and the main metadata of an RPM are stored in headers:
+an index of `(tag, type, offset, count)` entries followed by a data store
+holding the values. Each header is wrapped in a region, whose trailer
+records which entries are covered by digests and signatures.
+*/
+
+use {
+    anyhow::{anyhow, Result},
+    std::collections::BTreeMap,
+};
+
+/// Magic bytes starting every header.
+const HEADER_MAGIC: [u8; 8] = [0x8e, 0xad, 0xe8, 0x01, 0, 0, 0, 0];
+
+/// Size of an index entry, in bytes.
+const INDEX_ENTRY_SIZE: usize = 16;
+
+/// Region tag of the signature header.
+pub const RPMTAG_HEADERSIGNATURES: u32 = 62;
+/// Region tag of the main header.
+pub const RPMTAG_HEADERIMMUTABLE: u32 = 63;
+pub const RPMTAG_HEADERI18NTABLE: u32 = 100;
+
+pub const RPMSIGTAG_RSA: u32 = 268;
+pub const RPMSIGTAG_SHA1: u32 = 269;
+pub const RPMSIGTAG_SHA256: u32 = 273;
+pub const RPMSIGTAG_SIZE: u32 = 1000;
+pub const RPMSIGTAG_PGP: u32 = 1002;
+pub const RPMSIGTAG_MD5: u32 = 1004;
+pub const RPMSIGTAG_PAYLOADSIZE: u32 = 1007;
+
+pub const RPMTAG_NAME: u32 = 1000;
+pub const RPMTAG_VERSION: u32 = 1001;
+pub const RPMTAG_RELEASE: u32 = 1002;
+pub const RPMTAG_EPOCH: u32 = 1003;
+pub const RPMTAG_SUMMARY: u32 = 1004;
+pub const RPMTAG_DESCRIPTION: u32 = 1005;
+pub const RPMTAG_BUILDTIME: u32 = 1006;
+pub const RPMTAG_BUILDHOST: u32 = 1007;
+pub const RPMTAG_SIZE: u32 = 1009;
+pub const RPMTAG_VENDOR: u32 = 1011;
+pub const RPMTAG_LICENSE: u32 = 1014;
+pub const RPMTAG_PACKAGER: u32 = 1015;
+pub const RPMTAG_GROUP: u32 = 1016;
+pub const RPMTAG_URL: u32 = 1020;
+pub const RPMTAG_OS: u32 = 1021;
+pub const RPMTAG_ARCH: u32 = 1022;
+pub const RPMTAG_PREIN: u32 = 1023;
+pub const RPMTAG_POSTIN: u32 = 1024;
+pub const RPMTAG_PREUN: u32 = 1025;
+pub const RPMTAG_POSTUN: u32 = 1026;
+pub const RPMTAG_FILESIZES: u32 = 1028;
+pub const RPMTAG_FILEMODES: u32 = 1030;
+pub const RPMTAG_FILERDEVS: u32 = 1033;
+pub const RPMTAG_FILEMTIMES: u32 = 1034;
+pub const RPMTAG_FILEDIGESTS: u32 = 1035;
+pub const RPMTAG_FILELINKTOS: u32 = 1036;
+pub const RPMTAG_FILEFLAGS: u32 = 1037;
+pub const RPMTAG_FILEUSERNAME: u32 = 1039;
+pub const RPMTAG_FILEGROUPNAME: u32 = 1040;
+pub const RPMTAG_SOURCERPM: u32 = 1044;
+pub const RPMTAG_FILEVERIFYFLAGS: u32 = 1045;
+pub const RPMTAG_PROVIDENAME: u32 = 1047;
+pub const RPMTAG_REQUIREFLAGS: u32 = 1048;
+pub const RPMTAG_REQUIRENAME: u32 = 1049;
+pub const RPMTAG_REQUIREVERSION: u32 = 1050;
+pub const RPMTAG_CONFLICTFLAGS: u32 = 1053;
+pub const RPMTAG_CONFLICTNAME: u32 = 1054;
+pub const RPMTAG_CONFLICTVERSION: u32 = 1055;
+pub const RPMTAG_RPMVERSION: u32 = 1064;
+pub const RPMTAG_CHANGELOGTIME: u32 = 1080;
+pub const RPMTAG_CHANGELOGNAME: u32 = 1081;
+pub const RPMTAG_CHANGELOGTEXT: u32 = 1082;
+pub const RPMTAG_PREINPROG: u32 = 1085;
+pub const RPMTAG_POSTINPROG: u32 = 1086;
+pub const RPMTAG_PREUNPROG: u32 = 1087;
+pub const RPMTAG_POSTUNPROG: u32 = 1088;
+pub const RPMTAG_OBSOLETENAME: u32 = 1090;
+pub const RPMTAG_FILEDEVICES: u32 = 1095;
+pub const RPMTAG_FILEINODES: u32 = 1096;
+pub const RPMTAG_FILELANGS: u32 = 1097;
+pub const RPMTAG_PROVIDEFLAGS: u32 = 1112;
+pub const RPMTAG_PROVIDEVERSION: u32 = 1113;
+pub const RPMTAG_OBSOLETEFLAGS: u32 = 1114;
+pub const RPMTAG_OBSOLETEVERSION: u32 = 1115;
+pub const RPMTAG_DIRINDEXES: u32 = 1116;
+pub const RPMTAG_BASENAMES: u32 = 1117;
+pub const RPMTAG_DIRNAMES: u32 = 1118;
+pub const RPMTAG_PAYLOADFORMAT: u32 = 1124;
+pub const RPMTAG_PAYLOADCOMPRESSOR: u32 = 1125;
+pub const RPMTAG_PAYLOADFLAGS: u32 = 1126;
+pub const RPMTAG_PRETRANS: u32 = 1151;
+pub const RPMTAG_POSTTRANS: u32 = 1152;
+pub const RPMTAG_PRETRANSPROG: u32 = 1153;
+pub const RPMTAG_POSTTRANSPROG: u32 = 1154;
+pub const RPMTAG_FILEDIGESTALGO: u32 = 5011;
+
+/// A value stored in a header.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum IndexValue {
+    Int16(Vec<u16>),
+    Int32(Vec<u32>),
+    String(String),
+    Bin(Vec<u8>),
+    StringArray(Vec<String>),
+    I18nString(String),
+}
+
+impl IndexValue {
+    /// The numeric type of the value in the header index.
+    fn type_id(&self) -> u32 {
+        match self {
+            Self::Int16(_) => 3,
+            Self::Int32(_) => 4,
+            Self::String(_) => 6,
+            Self::Bin(_) => 7,
+            Self::StringArray(_) => 8,
+            Self::I18nString(_) => 9,
+        }
+    }
+
+    /// The number of items in the value, as recorded in the header index.
+    fn count(&self) -> usize {
+        match self {
+            Self::Int16(v) => v.len(),
+            Self::Int32(v) => v.len(),
+            Self::String(_) | Self::I18nString(_) => 1,
+            Self::Bin(v) => v.len(),
+            Self::StringArray(v) => v.len(),
+        }
+    }
+
+    /// Required alignment of the value in the data store.
+    fn alignment(&self) -> usize {
+        match self {
+            Self::Int16(_) => 2,
+            Self::Int32(_) => 4,
+            _ => 1,
+        }
+    }
+
+    fn write(&self, data: &mut Vec<u8>) -> Result<()> {
+        fn write_string(data: &mut Vec<u8>, s: &str) -> Result<()> {
+            if s.contains('\0') {
+                return Err(anyhow!("header strings cannot contain NUL"));
+            }
+
+            data.extend_from_slice(s.as_bytes());
+            data.push(0);
+
+            Ok(())
+        }
+
+        match self {
+            Self::Int16(v) => {
+                for x in v {
+                    data.extend_from_slice(&x.to_be_bytes());
+                }
+            }
+            Self::Int32(v) => {
+                for x in v {
+                    data.extend_from_slice(&x.to_be_bytes());
+                }
+            }
+            Self::String(s) | Self::I18nString(s) => write_string(data, s)?,
+            Self::Bin(v) => data.extend_from_slice(v),
+            Self::StringArray(v) => {
+                for s in v {
+                    write_string(data, s)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read(type_id: u32, count: usize, data: &[u8]) -> Result<Self> {
+        let take = |size: usize| {
+            count
+                .checked_mul(size)
+                .and_then(|len| data.get(..len))
+                .ok_or_else(|| anyhow!("header value extends past data store"))
+        };
+
+        let strings = || -> Result<Vec<String>> {
+            data.split(|b| *b == 0)
+                .take(count)
+                .map(|s| String::from_utf8(s.to_vec()).map_err(|e| anyhow!(e)))
+                .collect::<Result<Vec<_>>>()
+                .and_then(|v| {
+                    if v.len() == count {
+                        Ok(v)
+                    } else {
+                        Err(anyhow!("header value extends past data store"))
+                    }
+                })
+        };
+
+        Ok(match type_id {
+            6 | 9 if count != 1 => {
+                return Err(anyhow!("string header values must have a count of 1"))
+            }
+            3 => Self::Int16(
+                take(2)?
+                    .chunks_exact(2)
+                    .map(|x| u16::from_be_bytes([x[0], x[1]]))
+                    .collect(),
+            ),
+            4 => Self::Int32(
+                take(4)?
+                    .chunks_exact(4)
+                    .map(|x| u32::from_be_bytes([x[0], x[1], x[2], x[3]]))
+                    .collect(),
+            ),
+            6 => Self::String(strings()?.remove(0)),
+            7 => Self::Bin(take(1)?.to_vec()),
+            8 => Self::StringArray(strings()?),
+            9 => Self::I18nString(strings()?.remove(0)),
+            _ => return Err(anyhow!("unsupported header value type {}", type_id)),
+        })
+    }
+}
+
+/// An RPM header.
+///
+/// Entries are keyed by tag. The region entry is managed automatically.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Header {
+    entries: BTreeMap<u32, IndexValue>,
+}
+
+impl Header {
+    /// Obtain the value of a tag.
+    pub fn get(&self, tag: u32) -> Option<&IndexValue> {
+        self.entries.get(&tag)
+    }
+
+    /// Set the value of a tag, replacing any existing value.
+    pub fn set(&mut self, tag: u32, value: IndexValue) {
+        self.entries.insert(tag, value);
+    }
+
+    /// Obtain the value of a string tag.
+    pub fn get_string(&self, tag: u32) -> Option<&str> {
+        match self.entries.get(&tag) {
+            Some(IndexValue::String(s)) | Some(IndexValue::I18nString(s)) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Serialize the header, wrapping entries in the region having `region_tag`.
+    pub fn to_bytes(&self, region_tag: u32) -> Result<Vec<u8>> {
+        let mut index = vec![];
+        let mut data = vec![];
+
+        for (tag, value) in &self.entries {
+            while data.len() % value.alignment() != 0 {
+                data.push(0);
+            }
+
+            index.push((
+                *tag,
+                value.type_id(),
+                data.len() as u32,
+                value.count() as u32,
+            ));
+            value.write(&mut data)?;
+        }
+
+        // The region trailer is an index entry whose offset is the negated
+        // size of the index entries in the region.
+        let region_size = (index.len() + 1) * INDEX_ENTRY_SIZE;
+        let trailer_offset = data.len() as u32;
+        for value in [
+            region_tag,
+            7,
+            (-(region_size as i32)) as u32,
+            INDEX_ENTRY_SIZE as u32,
+        ] {
+            data.extend_from_slice(&value.to_be_bytes());
+        }
+        index.insert(0, (region_tag, 7, trailer_offset, INDEX_ENTRY_SIZE as u32));
+
+        let mut res = HEADER_MAGIC.to_vec();
+        res.extend_from_slice(&(index.len() as u32).to_be_bytes());
+        res.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        for (tag, type_id, offset, count) in index {
+            for value in [tag, type_id, offset, count] {
+                res.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+        res.extend(data);
+
+        Ok(res)
+    }
+
+    /// Parse a header from the beginning of data.
+    ///
+    /// Returns the header and the number of bytes it occupies. Region entries
+    /// are not retained.
+    pub fn parse(data: &[u8]) -> Result<(Self, usize)> {
+        if data.len() < 16 || data[0..8] != HEADER_MAGIC {
+            return Err(anyhow!("data does not begin with an RPM header"));
+        }
+
+        let read_u32 = |offset: usize| {
+            u32::from_be_bytes([
+                data[offset],
+                data[offset + 1],
+                data[offset + 2],
+                data[offset + 3],
+            ])
+        };
+
+        let index_count = read_u32(8) as usize;
+        let data_size = read_u32(12) as usize;
+        let store_offset = 16 + index_count * INDEX_ENTRY_SIZE;
+        let end = store_offset + data_size;
+
+        if data.len() < end {
+            return Err(anyhow!("RPM header is truncated"));
+        }
+
+        let store = &data[store_offset..end];
+        let mut entries = BTreeMap::new();
+
+        for i in 0..index_count {
+            let entry = 16 + i * INDEX_ENTRY_SIZE;
+            let tag = read_u32(entry);
+
+            if tag == RPMTAG_HEADERSIGNATURES || tag == RPMTAG_HEADERIMMUTABLE {
+                continue;
+            }
+
+            let offset = read_u32(entry + 8) as usize;
+            let value = IndexValue::read(
+                read_u32(entry + 4),
+                read_u32(entry + 12) as usize,
+                store
+                    .get(offset..)
+                    .ok_or_else(|| anyhow!("header value offset out of bounds"))?,
+            )?;
+
+            entries.insert(tag, value);
+        }
+
+        Ok((Self { entries }, end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() -> Result<()> {
+        let mut header = Header::default();
+        header.set(RPMTAG_NAME, IndexValue::String("myapp".into()));
+        header.set(RPMTAG_SUMMARY, IndexValue::I18nString("My app".into()));
+        header.set(
+            RPMTAG_FILEMODES,
+            IndexValue::Int16(vec![0o100644, 0o120777]),
+        );
+        header.set(RPMTAG_FILESIZES, IndexValue::Int32(vec![1, 70000]));
+        header.set(
+            RPMTAG_BASENAMES,
+            IndexValue::StringArray(vec!["a".into(), "".into(), "c".into()]),
+        );
+        header.set(RPMSIGTAG_MD5, IndexValue::Bin(vec![1, 2, 3]));
+
+        let data = header.to_bytes(RPMTAG_HEADERIMMUTABLE)?;
+
+        // Region entry comes first and its trailer is at the end of the data.
+        assert_eq!(&data[16..20], &RPMTAG_HEADERIMMUTABLE.to_be_bytes());
+        assert_eq!(
+            &data[data.len() - 16..data.len() - 12],
+            &63u32.to_be_bytes()
+        );
+        assert_eq!(
+            &data[data.len() - 8..data.len() - 4],
+            &(-(7 * 16i32)).to_be_bytes()
+        );
+
+        let (parsed, size) = Header::parse(&data)?;
+        assert_eq!(size, data.len());
+        assert_eq!(parsed, header);
+        assert_eq!(parsed.get_string(RPMTAG_SUMMARY), Some("My app"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_alignment() -> Result<()> {
+        let mut header = Header::default();
+        header.set(RPMTAG_NAME, IndexValue::String("x".into()));
+        header.set(RPMTAG_SIZE, IndexValue::Int32(vec![42]));
+
+        let data = header.to_bytes(RPMTAG_HEADERIMMUTABLE)?;
+
+        // "x\0" occupies 2 bytes, so the integer is padded to offset 4.
+        let size_entry = &data[16 + 32..16 + 48];
+        assert_eq!(&size_entry[0..4], &RPMTAG_SIZE.to_be_bytes());
+        assert_eq!(&size_entry[8..12], &4u32.to_be_bytes());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_nul_in_string() {
+        let mut header = Header::default();
+        header.set(RPMTAG_NAME, IndexValue::String("a\0b".into()));
+
+        assert!(header.to_bytes(RPMTAG_HEADERIMMUTABLE).is_err());
+    }
+}
@@ -2,6 +2,10 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+/*! Functionality for the RPM packaging format. */
+
 mod builder;
+mod cpio;
+pub mod header;
 
-pub use builder::RpmBuilder;
+pub use builder::{target_triple_to_rpm_arch, Relation, RelationKind, RpmBuilder, SCRIPTLETS};
@@ -128,10 +128,11 @@ impl<'a> SnapcraftBuilder<'a> {
     ///
     /// This will perform the following actions:
     ///
-    /// 1. Potentially purge `build_path`.
-    /// 2. Materialize registered files into `build_path`.
-    /// 3. Materialize `snapcraft.yaml` into `build_path/snap/snapcraft.yaml`.
-    /// 4. Invoke `snapcraft` with the specified arguments.
+    /// 1. Validate the `snapcraft.yaml` content.
+    /// 2. Potentially purge `build_path`.
+    /// 3. Materialize registered files into `build_path`.
+    /// 4. Materialize `snapcraft.yaml` into `build_path/snap/snapcraft.yaml`.
+    /// 5. Invoke `snapcraft` with the specified arguments.
     pub fn build_invocation<P: AsRef<Path>>(
         &self,
         build_path: P,
@@ -139,6 +140,8 @@ impl<'a> SnapcraftBuilder<'a> {
     ) -> Result<()> {
         let build_path = build_path.as_ref();
 
+        self.snap.validate()?;
+
         if invocation.purge_build && build_path.exists() {
             warn!("purging {}", build_path.display());
             remove_dir_all::remove_dir_all(build_path)
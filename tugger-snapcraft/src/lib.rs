@@ -5,12 +5,18 @@
 /*! Functionality for the Snapcraft packaging format. */
 
 mod builder;
+mod validate;
 mod yaml;
 
 pub use {
     builder::{SnapcraftBuilder, SnapcraftInvocation},
+    validate::{
+        is_valid_app_name, is_valid_hook_name, is_valid_interface_name, is_valid_part_name,
+        is_valid_snap_name, is_valid_version,
+    },
     yaml::{
         Adapter, Architecture, Architectures, BuildAttribute, Confinement, Daemon, Grade,
-        RestartCondition, SnapApp, SnapPart, Snapcraft, SourceType, Type,
+        LayoutType, RestartCondition, SnapApp, SnapHook, SnapInterface, SnapLayout, SnapPart,
+        Snapcraft, SourceType, Type,
     },
 };
@@ -0,0 +1,525 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! Validation of `snapcraft.yaml` content.
+
+The rules here mirror the JSON schema shipped with `snapcraft` as well as
+constraints enforced by `snapd` at install time. This allows catching errors
+before spending time on a build.
+*/
+
+use {
+    crate::yaml::{Confinement, SnapInterface, SnapLayout, Snapcraft},
+    anyhow::{anyhow, Result},
+};
+
+/// Maximum length of the `name` field.
+const MAX_NAME_LENGTH: usize = 40;
+
+/// Maximum length of the `title` field.
+const MAX_TITLE_LENGTH: usize = 40;
+
+/// Maximum length of the `summary` field.
+const MAX_SUMMARY_LENGTH: usize = 78;
+
+/// Maximum length of the `version` field.
+const MAX_VERSION_LENGTH: usize = 32;
+
+/// Hooks with fixed names.
+const HOOKS: &[&str] = &[
+    "check-health",
+    "configure",
+    "default-configure",
+    "fde-setup",
+    "gate-auto-refresh",
+    "install",
+    "install-device",
+    "post-refresh",
+    "pre-refresh",
+    "remove",
+];
+
+/// Prefixes of hooks acting on a named plug or slot.
+const INTERFACE_HOOK_PREFIXES: &[&str] = &[
+    "connect-plug-",
+    "connect-slot-",
+    "disconnect-plug-",
+    "disconnect-slot-",
+    "prepare-plug-",
+    "prepare-slot-",
+    "unprepare-plug-",
+    "unprepare-slot-",
+];
+
+/// Paths that `snapd` refuses to apply layouts at or below.
+const RESERVED_LAYOUT_PATHS: &[&str] = &[
+    "/boot",
+    "/dev",
+    "/home",
+    "/lib/firmware",
+    "/lib/modules",
+    "/lost+found",
+    "/media",
+    "/proc",
+    "/run",
+    "/snap",
+    "/sys",
+    "/tmp",
+    "/var/lib/snapd",
+    "/var/snap",
+];
+
+/// Variables that layout sources must be relative to.
+const LAYOUT_SOURCE_PREFIXES: &[&str] = &["$SNAP", "$SNAP_COMMON", "$SNAP_DATA"];
+
+/// Whether a string is a valid snap name.
+///
+/// Names consist of lowercase letters, digits, and non-consecutive hyphens
+/// not at the start or end. At least one letter is required.
+pub fn is_valid_snap_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && name.chars().any(|c| c.is_ascii_lowercase())
+        && !name.starts_with('-')
+        && !name.ends_with('-')
+        && !name.contains("--")
+}
+
+/// Whether a string is a valid app name.
+pub fn is_valid_app_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        && !name.starts_with('-')
+        && !name.ends_with('-')
+        && !name.contains("--")
+}
+
+/// Whether a string is a valid plug, slot, or hook name.
+pub fn is_valid_interface_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !name.ends_with('-')
+        && !name.contains("--")
+}
+
+/// Whether a string is a valid part name.
+pub fn is_valid_part_name(name: &str) -> bool {
+    name != "plugins"
+        && name.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '+')
+}
+
+/// Whether a string is a valid snap version.
+pub fn is_valid_version(version: &str) -> bool {
+    !version.is_empty()
+        && version.len() <= MAX_VERSION_LENGTH
+        && version.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && version.ends_with(|c: char| c.is_ascii_alphanumeric() || c == '+' || c == '~')
+        && version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || ":.+~-".contains(c))
+}
+
+/// Whether a string is a valid hook name.
+pub fn is_valid_hook_name(name: &str) -> bool {
+    if HOOKS.contains(&name) {
+        return true;
+    }
+
+    INTERFACE_HOOK_PREFIXES.iter().any(|prefix| {
+        name.strip_prefix(prefix)
+            .map(is_valid_interface_name)
+            .unwrap_or(false)
+    })
+}
+
+fn validate_interface(
+    problems: &mut Vec<String>,
+    kind: &str,
+    name: &str,
+    interface: &SnapInterface,
+) {
+    if !is_valid_interface_name(name) {
+        problems.push(format!("invalid {} name: {}", kind, name));
+    }
+
+    if interface.interface_name(name) == "content" {
+        if kind == "plug" {
+            if interface.target.is_none() {
+                problems.push(format!("content plug {} must define target", name));
+            }
+        } else if interface.read.is_empty() && interface.write.is_empty() {
+            problems.push(format!(
+                "content slot {} must define read or write paths",
+                name
+            ));
+        }
+    }
+}
+
+fn validate_layout(problems: &mut Vec<String>, path: &str, layout: &SnapLayout) {
+    if !path.starts_with('/') && !path.starts_with("$SNAP") {
+        problems.push(format!("layout path must be absolute: {}", path));
+    }
+
+    if let Some(reserved) = RESERVED_LAYOUT_PATHS
+        .iter()
+        .find(|p| path == **p || path.starts_with(&format!("{}/", p)))
+    {
+        problems.push(format!(
+            "layout path {} conflicts with reserved path {}",
+            path, reserved
+        ));
+    }
+
+    let sources = [&layout.bind, &layout.bind_file, &layout.symlink];
+    let count =
+        sources.iter().filter(|s| s.is_some()).count() + usize::from(layout.layout_type.is_some());
+
+    if count != 1 {
+        problems.push(format!(
+            "layout {} must define exactly one of bind, bind-file, symlink, or type",
+            path
+        ));
+    }
+
+    for source in sources.iter().filter_map(|s| s.as_deref()) {
+        let relative = LAYOUT_SOURCE_PREFIXES.iter().any(|prefix| {
+            source == *prefix
+                || source.starts_with(&format!("{}/", prefix))
+                || source.starts_with(&format!("${{{}}}/", &prefix[1..]))
+        });
+
+        if !relative {
+            problems.push(format!(
+                "layout {} source must begin with $SNAP, $SNAP_DATA, or $SNAP_COMMON: {}",
+                path, source
+            ));
+        }
+    }
+}
+
+impl<'a> Snapcraft<'a> {
+    /// Validate the content of this instance.
+    ///
+    /// This checks the constraints `snapcraft` and `snapd` impose on fields
+    /// and on the relationships between them. All violations are reported in
+    /// the returned error.
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = vec![];
+
+        if !is_valid_snap_name(&self.name) {
+            problems.push(format!(
+                "invalid snap name {}: must be at most {} lowercase letters, digits, and hyphens",
+                self.name, MAX_NAME_LENGTH
+            ));
+        }
+
+        // Fields can be left empty if they are populated by adopt-info.
+        let adopted = self.adopt_info.is_some();
+
+        if !(adopted && self.version.is_empty()) && !is_valid_version(&self.version) {
+            problems.push(format!(
+                "invalid version {}: must be at most {} characters of letters, digits, and :.+~-",
+                self.version, MAX_VERSION_LENGTH
+            ));
+        }
+
+        if self.summary.is_empty() && !adopted {
+            problems.push("summary must be defined".to_string());
+        }
+        if self.summary.chars().count() > MAX_SUMMARY_LENGTH {
+            problems.push(format!(
+                "summary must be at most {} characters",
+                MAX_SUMMARY_LENGTH
+            ));
+        }
+
+        if self.description.is_empty() && !adopted {
+            problems.push("description must be defined".to_string());
+        }
+
+        if let Some(title) = &self.title {
+            if title.chars().count() > MAX_TITLE_LENGTH {
+                problems.push(format!(
+                    "title must be at most {} characters",
+                    MAX_TITLE_LENGTH
+                ));
+            }
+        }
+
+        if let Some(adopt_info) = &self.adopt_info {
+            if !self.parts.contains_key(adopt_info) {
+                problems.push(format!("adopt-info references unknown part {}", adopt_info));
+            }
+        }
+
+        if self.parts.is_empty() {
+            problems.push("at least one part must be defined".to_string());
+        }
+
+        let mut part_names = self.parts.keys().collect::<Vec<_>>();
+        part_names.sort();
+
+        for name in part_names {
+            let part = &self.parts[name];
+
+            if !is_valid_part_name(name) {
+                problems.push(format!("invalid part name: {}", name));
+            }
+
+            for after in &part.after {
+                if !self.parts.contains_key(after) {
+                    problems.push(format!("part {} is after unknown part {}", name, after));
+                }
+            }
+        }
+
+        let mut app_names = self.apps.keys().collect::<Vec<_>>();
+        app_names.sort();
+
+        for name in app_names {
+            let app = &self.apps[name];
+
+            if !is_valid_app_name(name) {
+                problems.push(format!("invalid app name: {}", name));
+            }
+
+            if app.command.is_none() {
+                problems.push(format!("app {} must define command", name));
+            }
+
+            if app.daemon.is_none()
+                && (app.stop_command.is_some()
+                    || app.post_stop_command.is_some()
+                    || app.stop_timeout.is_some()
+                    || app.restart_condition.is_some())
+            {
+                problems.push(format!(
+                    "app {} defines daemon settings but is not a daemon",
+                    name
+                ));
+            }
+
+            for plug in &app.plugs {
+                if !is_valid_interface_name(plug) {
+                    problems.push(format!("app {} has invalid plug name: {}", name, plug));
+                }
+            }
+            for slot in &app.slots {
+                if !is_valid_interface_name(slot) {
+                    problems.push(format!("app {} has invalid slot name: {}", name, slot));
+                }
+            }
+        }
+
+        let mut plug_names = self.plugs.keys().collect::<Vec<_>>();
+        plug_names.sort();
+        for name in plug_names {
+            validate_interface(&mut problems, "plug", name, &self.plugs[name]);
+        }
+
+        let mut slot_names = self.slots.keys().collect::<Vec<_>>();
+        slot_names.sort();
+        for name in slot_names {
+            validate_interface(&mut problems, "slot", name, &self.slots[name]);
+        }
+
+        let mut hook_names = self.hooks.keys().collect::<Vec<_>>();
+        hook_names.sort();
+
+        for name in hook_names {
+            if !is_valid_hook_name(name) {
+                problems.push(format!("unknown hook: {}", name));
+            }
+
+            for plug in &self.hooks[name].plugs {
+                if !is_valid_interface_name(plug) {
+                    problems.push(format!("hook {} has invalid plug name: {}", name, plug));
+                }
+            }
+        }
+
+        if !self.layout.is_empty() && self.confinement == Some(Confinement::Classic) {
+            problems.push("layouts are not supported with classic confinement".to_string());
+        }
+
+        let mut layout_paths = self.layout.keys().collect::<Vec<_>>();
+        layout_paths.sort();
+        for path in layout_paths {
+            validate_layout(&mut problems, path, &self.layout[path]);
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "invalid snapcraft.yaml:\n{}",
+                problems
+                    .iter()
+                    .map(|p| format!("  {}", p))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::yaml::{SnapApp, SnapHook, SnapPart},
+    };
+
+    fn valid_snap() -> Snapcraft<'static> {
+        let mut snap = Snapcraft::new(
+            "my-app".into(),
+            "1.0.2+git1".into(),
+            "summary".into(),
+            "description".into(),
+        );
+        snap.add_part(
+            "my-app".into(),
+            SnapPart {
+                plugin: Some("dump".into()),
+                ..SnapPart::default()
+            },
+        );
+        snap.add_app(
+            "my-app".into(),
+            SnapApp {
+                command: Some("bin/my-app".into()),
+                plugs: vec!["network".into(), "themes".into()],
+                ..SnapApp::default()
+            },
+        );
+
+        snap
+    }
+
+    #[test]
+    fn test_names() {
+        assert!(is_valid_snap_name("foo"));
+        assert!(is_valid_snap_name("foo-2"));
+        assert!(is_valid_snap_name("0ad"));
+        assert!(!is_valid_snap_name("123"));
+        assert!(!is_valid_snap_name("Foo"));
+        assert!(!is_valid_snap_name("foo_bar"));
+        assert!(!is_valid_snap_name("-foo"));
+        assert!(!is_valid_snap_name("foo--bar"));
+        assert!(!is_valid_snap_name(&"a".repeat(41)));
+
+        assert!(is_valid_app_name("Foo-2"));
+        assert!(!is_valid_app_name("foo.bar"));
+
+        assert!(is_valid_part_name("libfoo+2"));
+        assert!(!is_valid_part_name("plugins"));
+
+        assert!(is_valid_version("1.0"));
+        assert!(is_valid_version("2:1.0~rc1"));
+        assert!(!is_valid_version("1.0-"));
+        assert!(!is_valid_version("v 1"));
+        assert!(!is_valid_version(&"1".repeat(33)));
+
+        assert!(is_valid_hook_name("configure"));
+        assert!(is_valid_hook_name("connect-plug-themes"));
+        assert!(!is_valid_hook_name("connect-plug-"));
+        assert!(!is_valid_hook_name("post-install"));
+    }
+
+    #[test]
+    fn test_valid() -> Result<()> {
+        let mut snap = valid_snap();
+        snap.add_plug(
+            "themes".into(),
+            SnapInterface {
+                interface: Some("content".into()),
+                target: Some("$SNAP/themes".into()),
+                ..SnapInterface::default()
+            },
+        );
+        snap.add_hook(
+            "configure".into(),
+            SnapHook {
+                plugs: vec!["network".into()],
+                ..SnapHook::default()
+            },
+        );
+        snap.add_layout(
+            "/usr/share/foo".into(),
+            SnapLayout::bind("$SNAP/usr/share/foo"),
+        );
+        snap.add_layout(
+            "/etc/foo.conf".into(),
+            SnapLayout::bind_file("$SNAP_DATA/foo.conf"),
+        );
+        snap.add_layout("/var/cache/foo".into(), SnapLayout::tmpfs());
+
+        snap.validate()
+    }
+
+    #[test]
+    fn test_invalid() {
+        let mut snap = valid_snap();
+        snap.name = "My_App".into();
+        snap.summary = "s".repeat(79).into();
+        snap.parts.get_mut("my-app").unwrap().after = vec!["missing".into()];
+        snap.apps.get_mut("my-app").unwrap().stop_timeout = Some("10s".into());
+        snap.add_plug("themes".into(), SnapInterface::new("content"));
+        snap.add_slot(
+            "data".into(),
+            SnapInterface {
+                interface: Some("content".into()),
+                ..SnapInterface::default()
+            },
+        );
+        snap.add_hook("post-install".into(), SnapHook::default());
+        snap.add_layout("/proc/foo".into(), SnapLayout::symlink("/usr/lib/foo"));
+        snap.add_layout(
+            "/usr/lib/foo".into(),
+            SnapLayout {
+                bind: Some("$SNAP/foo".into()),
+                ..SnapLayout::tmpfs()
+            },
+        );
+
+        let err = snap.validate().unwrap_err().to_string();
+        for message in [
+            "invalid snap name My_App",
+            "summary must be at most 78 characters",
+            "part my-app is after unknown part missing",
+            "app my-app defines daemon settings but is not a daemon",
+            "content plug themes must define target",
+            "content slot data must define read or write paths",
+            "unknown hook: post-install",
+            "layout path /proc/foo conflicts with reserved path /proc",
+            "layout /proc/foo source must begin with $SNAP",
+            "layout /usr/lib/foo must define exactly one of",
+        ] {
+            assert!(err.contains(message), "{} not in {}", message, err);
+        }
+    }
+
+    #[test]
+    fn test_classic_layout() {
+        let mut snap = valid_snap();
+        snap.confinement = Some(Confinement::Classic);
+        snap.validate().unwrap();
+
+        snap.add_layout("/usr/share/foo".into(), SnapLayout::bind("$SNAP/foo"));
+        assert!(snap
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("layouts are not supported with classic confinement"));
+    }
+}
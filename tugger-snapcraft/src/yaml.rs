@@ -178,6 +178,21 @@ impl TryFrom<&str> for BuildAttribute {
     }
 }
 
+/// Represents the value of a `layout.<path>.type` field.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LayoutType {
+    Tmpfs,
+}
+
+impl TryFrom<&str> for LayoutType {
+    type Error = serde_yaml::Error;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        serde_yaml::from_str(s)
+    }
+}
+
 /// Represents the value of an `architecture` field.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -289,6 +304,109 @@ pub struct SnapPart<'a> {
     pub build_attributes: Vec<BuildAttribute>,
 }
 
+/// Represents the `plugs.<plug-name>` and `slots.<slot-name>` entries in a `snapcraft.yaml`.
+///
+/// If `interface` isn't defined, the interface is the name of the plug or slot.
+/// Attributes of the `content` interface have dedicated fields. Attributes of other
+/// interfaces go in `attributes`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SnapInterface<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interface: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_provider: Option<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub read: Vec<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub write: Vec<Cow<'a, str>>,
+    #[serde(flatten)]
+    pub attributes: HashMap<Cow<'a, str>, Cow<'a, str>>,
+}
+
+impl<'a> SnapInterface<'a> {
+    /// Construct an instance for the named interface.
+    pub fn new(interface: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            interface: Some(interface.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Resolve the name of the interface used by a plug or slot having the given name.
+    pub fn interface_name<'b>(&'b self, name: &'b str) -> &'b str {
+        self.interface.as_deref().unwrap_or(name)
+    }
+}
+
+/// Represents the `hooks.<hook-name>` entries in a `snapcraft.yaml`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SnapHook<'a> {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub command_chain: Vec<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub environment: HashMap<Cow<'a, str>, Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub plugs: Vec<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub passthrough: HashMap<Cow<'a, str>, Cow<'a, str>>,
+}
+
+/// Represents the `layout.<path>` entries in a `snapcraft.yaml`.
+///
+/// Exactly one field should be defined.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SnapLayout<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bind: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bind_file: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symlink: Option<Cow<'a, str>>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub layout_type: Option<LayoutType>,
+}
+
+impl<'a> SnapLayout<'a> {
+    /// A layout bind mounting a directory.
+    pub fn bind(source: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            bind: Some(source.into()),
+            ..Self::default()
+        }
+    }
+
+    /// A layout bind mounting a file.
+    pub fn bind_file(source: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            bind_file: Some(source.into()),
+            ..Self::default()
+        }
+    }
+
+    /// A layout creating a symlink.
+    pub fn symlink(source: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            symlink: Some(source.into()),
+            ..Self::default()
+        }
+    }
+
+    /// A layout mounting a tmpfs.
+    pub fn tmpfs() -> Self {
+        Self {
+            layout_type: Some(LayoutType::Tmpfs),
+            ..Self::default()
+        }
+    }
+}
+
 /// Represents a `snapcraft.yaml` file content.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -324,9 +442,13 @@ pub struct Snapcraft<'a> {
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub parts: HashMap<Cow<'a, str>, SnapPart<'a>>,
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
-    pub plugs: HashMap<Cow<'a, str>, HashMap<Cow<'a, str>, Cow<'a, str>>>,
+    pub plugs: HashMap<Cow<'a, str>, SnapInterface<'a>>,
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
-    pub slots: HashMap<Cow<'a, str>, HashMap<Cow<'a, str>, Cow<'a, str>>>,
+    pub slots: HashMap<Cow<'a, str>, SnapInterface<'a>>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub hooks: HashMap<Cow<'a, str>, SnapHook<'a>>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub layout: HashMap<Cow<'a, str>, SnapLayout<'a>>,
 }
 
 impl<'a> Snapcraft<'a> {
@@ -356,6 +478,8 @@ impl<'a> Snapcraft<'a> {
             parts: HashMap::new(),
             plugs: HashMap::new(),
             slots: HashMap::new(),
+            hooks: HashMap::new(),
+            layout: HashMap::new(),
         }
     }
 
@@ -368,6 +492,26 @@ impl<'a> Snapcraft<'a> {
     pub fn add_part(&mut self, name: Cow<'a, str>, part: SnapPart<'a>) {
         self.parts.insert(name, part);
     }
+
+    /// Add a named plug to this instance.
+    pub fn add_plug(&mut self, name: Cow<'a, str>, plug: SnapInterface<'a>) {
+        self.plugs.insert(name, plug);
+    }
+
+    /// Add a named slot to this instance.
+    pub fn add_slot(&mut self, name: Cow<'a, str>, slot: SnapInterface<'a>) {
+        self.slots.insert(name, slot);
+    }
+
+    /// Add a named hook to this instance.
+    pub fn add_hook(&mut self, name: Cow<'a, str>, hook: SnapHook<'a>) {
+        self.hooks.insert(name, hook);
+    }
+
+    /// Add a layout for the given path to this instance.
+    pub fn add_layout(&mut self, path: Cow<'a, str>, layout: SnapLayout<'a>) {
+        self.layout.insert(path, layout);
+    }
 }
 
 #[cfg(test)]
@@ -391,6 +535,49 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_serialize_extended() -> Result<(), serde_yaml::Error> {
+        let mut snap = Snapcraft::new(
+            "myapp".into(),
+            "1.0".into(),
+            "summary".into(),
+            "description".into(),
+        );
+        snap.add_plug(
+            "themes".into(),
+            SnapInterface {
+                interface: Some("content".into()),
+                target: Some("$SNAP/data-dir/themes".into()),
+                default_provider: Some("gtk-common-themes".into()),
+                ..SnapInterface::default()
+            },
+        );
+        snap.add_hook(
+            "configure".into(),
+            SnapHook {
+                plugs: vec!["network".into()],
+                ..SnapHook::default()
+            },
+        );
+        snap.add_layout(
+            "/usr/share/myapp".into(),
+            SnapLayout::bind("$SNAP/usr/share/myapp"),
+        );
+        snap.add_layout("/var/cache/myapp".into(), SnapLayout::tmpfs());
+
+        let yaml = serde_yaml::to_string(&snap)?;
+        assert!(yaml.contains("    interface: content\n"));
+        assert!(yaml.contains("    default-provider: gtk-common-themes\n"));
+        assert!(yaml.contains("hooks:\n  configure:\n    plugs:\n    - network\n"));
+        assert!(yaml.contains("  /usr/share/myapp:\n    bind: $SNAP/usr/share/myapp\n"));
+        assert!(yaml.contains("  /var/cache/myapp:\n    type: tmpfs\n"));
+
+        let parsed: Snapcraft = serde_yaml::from_str(&yaml)?;
+        assert_eq!(parsed, snap);
+
+        Ok(())
+    }
+
     #[test]
     fn test_source_type_from_str() -> Result<(), serde_yaml::Error> {
         assert_eq!(SourceType::try_from("7z")?, SourceType::SevenZip);
@@ -0,0 +1,861 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! Authenticode signing implemented in pure Rust.
+
+This allows signing PE binaries (`.exe`, `.dll`, etc) and MSI installers
+without `signtool.exe`, and therefore from non-Windows machines.
+
+Authenticode signatures are PKCS #7 `SignedData` structures whose content is
+an `SpcIndirectDataContent` holding a digest of the signed file. For PE files,
+the digest covers the file minus the checksum field and the certificate table
+and the signature is stored in a `WIN_CERTIFICATE` structure appended to the
+file. For MSI files, the digest covers the streams of the compound file and
+the signature is stored in the `\u{5}DigitalSignature` stream.
+*/
+
+use {
+    crate::der,
+    anyhow::{anyhow, Context, Result},
+    sha2::{digest::DynDigest, Digest, Sha256, Sha384, Sha512},
+    std::{
+        io::{Cursor, Read, Seek, Write},
+        path::Path,
+    },
+    x509_certificate::{CapturedX509Certificate, Sign, SignatureAlgorithm},
+};
+
+pub(crate) const OID_SIGNED_DATA: &[u64] = &[1, 2, 840, 113549, 1, 7, 2];
+pub(crate) const OID_CONTENT_TYPE: &[u64] = &[1, 2, 840, 113549, 1, 9, 3];
+pub(crate) const OID_MESSAGE_DIGEST: &[u64] = &[1, 2, 840, 113549, 1, 9, 4];
+pub(crate) const OID_SIGNING_TIME: &[u64] = &[1, 2, 840, 113549, 1, 9, 5];
+pub(crate) const OID_COUNTERSIGNATURE: &[u64] = &[1, 2, 840, 113549, 1, 9, 6];
+pub(crate) const OID_TST_INFO: &[u64] = &[1, 2, 840, 113549, 1, 9, 16, 1, 4];
+pub(crate) const OID_SHA1: &[u64] = &[1, 3, 14, 3, 2, 26];
+pub(crate) const OID_SHA256: &[u64] = &[2, 16, 840, 1, 101, 3, 4, 2, 1];
+pub(crate) const OID_SHA384: &[u64] = &[2, 16, 840, 1, 101, 3, 4, 2, 2];
+pub(crate) const OID_SHA512: &[u64] = &[2, 16, 840, 1, 101, 3, 4, 2, 3];
+pub(crate) const OID_RSA_ENCRYPTION: &[u64] = &[1, 2, 840, 113549, 1, 1, 1];
+pub(crate) const OID_ECDSA_WITH_SHA256: &[u64] = &[1, 2, 840, 10045, 4, 3, 2];
+pub(crate) const OID_SPC_INDIRECT_DATA: &[u64] = &[1, 3, 6, 1, 4, 1, 311, 2, 1, 4];
+pub(crate) const OID_SPC_STATEMENT_TYPE: &[u64] = &[1, 3, 6, 1, 4, 1, 311, 2, 1, 11];
+pub(crate) const OID_SPC_SP_OPUS_INFO: &[u64] = &[1, 3, 6, 1, 4, 1, 311, 2, 1, 12];
+pub(crate) const OID_SPC_PE_IMAGE_DATA: &[u64] = &[1, 3, 6, 1, 4, 1, 311, 2, 1, 15];
+pub(crate) const OID_SPC_INDIVIDUAL_SP_KEY_PURPOSE: &[u64] = &[1, 3, 6, 1, 4, 1, 311, 2, 1, 21];
+pub(crate) const OID_SPC_SIPINFO: &[u64] = &[1, 3, 6, 1, 4, 1, 311, 2, 1, 30];
+pub(crate) const OID_MS_RFC3161_TIMESTAMP: &[u64] = &[1, 3, 6, 1, 4, 1, 311, 3, 3, 1];
+
+/// Encoded `SpcPeImageData` with an empty `SpcLink` file, as emitted by signtool.
+const SPC_PE_IMAGE_DATA: [u8; 11] = [
+    0x30, 0x09, 0x03, 0x01, 0x00, 0xa0, 0x04, 0xa2, 0x02, 0x80, 0x00,
+];
+
+/// GUID of the MSI subject interface package, in its on-disk byte order.
+const MSI_SIP_GUID: [u8; 16] = [
+    0xf1, 0x10, 0x0c, 0x00, 0x00, 0x00, 0x00, 0x00, 0xc0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
+];
+
+/// Name of the MSI stream holding the Authenticode signature.
+pub const MSI_DIGITAL_SIGNATURE_STREAM: &str = "\u{5}DigitalSignature";
+
+/// Name of the MSI stream holding the extended (metadata) digest.
+pub const MSI_DIGITAL_SIGNATURE_EX_STREAM: &str = "\u{5}MsiDigitalSignatureEx";
+
+const WIN_CERT_REVISION_2_0: u16 = 0x0200;
+pub(crate) const WIN_CERT_TYPE_PKCS_SIGNED_DATA: u16 = 0x0002;
+
+/// The kinds of files that can be signed with Authenticode.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AuthenticodeFileType {
+    /// A PE binary (`.exe`, `.dll`, etc).
+    Pe,
+    /// An MSI installer (or other file using the compound file binary format).
+    Msi,
+}
+
+impl AuthenticodeFileType {
+    /// Resolve the file type from file content.
+    pub fn from_data(data: &[u8]) -> Option<Self> {
+        if data.starts_with(b"MZ") {
+            Some(Self::Pe)
+        } else if data.starts_with(&crate::signing::CFB_MAGIC_NUMBER) {
+            Some(Self::Msi)
+        } else {
+            None
+        }
+    }
+}
+
+/// Digest algorithms that can appear in Authenticode signatures.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum DigestAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    /// Resolve an instance from an encoded `AlgorithmIdentifier`.
+    pub fn from_algorithm_identifier(element: &der::Element) -> Result<Self> {
+        let algorithm = element
+            .expect(der::TAG_SEQUENCE)?
+            .children()?
+            .first()
+            .copied()
+            .ok_or_else(|| anyhow!("empty AlgorithmIdentifier"))?
+            .expect(der::TAG_OID)?;
+
+        [
+            (OID_SHA1, Self::Sha1),
+            (OID_SHA256, Self::Sha256),
+            (OID_SHA384, Self::Sha384),
+            (OID_SHA512, Self::Sha512),
+        ]
+        .into_iter()
+        .find(|(oid, _)| algorithm.raw == der::oid(oid).as_slice())
+        .map(|(_, alg)| alg)
+        .ok_or_else(|| anyhow!("unsupported digest algorithm"))
+    }
+
+    /// Obtain a new hasher for this algorithm.
+    pub fn hasher(&self) -> Box<dyn DynDigest> {
+        match self {
+            Self::Sha1 => Box::new(sha1::Sha1::new()),
+            Self::Sha256 => Box::new(Sha256::new()),
+            Self::Sha384 => Box::new(Sha384::new()),
+            Self::Sha512 => Box::new(Sha512::new()),
+        }
+    }
+
+    /// Digest a slice of data.
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        let mut hasher = self.hasher();
+        hasher.update(data);
+
+        hasher.finalize().to_vec()
+    }
+}
+
+/// Offsets of Authenticode relevant fields in a PE file.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct PeLayout {
+    /// Offset of the `CheckSum` field of the optional header.
+    pub checksum_offset: usize,
+    /// Offset of the certificate table (security) data directory entry.
+    pub security_entry_offset: usize,
+    /// File offset and size of the certificate table, if present.
+    pub certificate_table: Option<(usize, usize)>,
+}
+
+pub(crate) fn read_u16(data: &[u8], offset: usize) -> Result<u16> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| anyhow!("PE file truncated at offset {}", offset))
+}
+
+pub(crate) fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| anyhow!("PE file truncated at offset {}", offset))
+}
+
+impl PeLayout {
+    /// Resolve the layout of a PE file.
+    pub fn from_data(data: &[u8]) -> Result<Self> {
+        if !data.starts_with(b"MZ") {
+            return Err(anyhow!("not a PE file: missing MZ header"));
+        }
+
+        let pe_offset = read_u32(data, 0x3c)? as usize;
+        if data.get(pe_offset..pe_offset + 4) != Some(b"PE\0\0".as_slice()) {
+            return Err(anyhow!("not a PE file: missing PE signature"));
+        }
+
+        let coff_offset = pe_offset + 4;
+        let optional_header_size = read_u16(data, coff_offset + 16)? as usize;
+        let optional_offset = coff_offset + 20;
+
+        let directories_offset = match read_u16(data, optional_offset)? {
+            0x10b => optional_offset + 96,
+            0x20b => optional_offset + 112,
+            magic => return Err(anyhow!("unsupported PE optional header magic {:#x}", magic)),
+        };
+
+        let directory_count = read_u32(data, directories_offset - 4)? as usize;
+        if directory_count < 5
+            || directories_offset + 5 * 8 > optional_offset + optional_header_size
+        {
+            return Err(anyhow!("PE file lacks a certificate table data directory"));
+        }
+
+        let security_entry_offset = directories_offset + 4 * 8;
+        let table_offset = read_u32(data, security_entry_offset)? as usize;
+        let table_size = read_u32(data, security_entry_offset + 4)? as usize;
+
+        let certificate_table = if table_offset == 0 || table_size == 0 {
+            None
+        } else if table_offset
+            .checked_add(table_size)
+            .map(|end| end > data.len())
+            .unwrap_or(true)
+        {
+            return Err(anyhow!("PE certificate table extends beyond end of file"));
+        } else {
+            Some((table_offset, table_size))
+        };
+
+        Ok(Self {
+            checksum_offset: optional_offset + 64,
+            security_entry_offset,
+            certificate_table,
+        })
+    }
+
+    /// Compute the Authenticode digest of the PE file having this layout.
+    ///
+    /// The digest covers all file content except the checksum, the
+    /// certificate table data directory entry, and the certificate table
+    /// itself.
+    pub fn digest(&self, data: &[u8], algorithm: DigestAlgorithm) -> Vec<u8> {
+        let end = self
+            .certificate_table
+            .map(|(offset, _)| offset)
+            .unwrap_or(data.len());
+
+        let mut hasher = algorithm.hasher();
+        hasher.update(&data[..self.checksum_offset]);
+        hasher.update(&data[self.checksum_offset + 4..self.security_entry_offset]);
+        hasher.update(&data[self.security_entry_offset + 8..end]);
+
+        hasher.finalize().to_vec()
+    }
+}
+
+/// Compute the checksum of a PE file, as stored in its optional header.
+pub(crate) fn pe_checksum(data: &[u8], checksum_offset: usize) -> u32 {
+    let mut sum = 0u64;
+
+    for (i, chunk) in data.chunks(2).enumerate() {
+        let offset = i * 2;
+        if offset == checksum_offset || offset == checksum_offset + 2 {
+            continue;
+        }
+
+        let word = if chunk.len() == 2 {
+            u16::from_le_bytes([chunk[0], chunk[1]])
+        } else {
+            chunk[0] as u16
+        };
+
+        sum += word as u64;
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+
+    sum = (sum & 0xffff) + (sum >> 16);
+
+    (sum as u32).wrapping_add(data.len() as u32)
+}
+
+/// Compute the Authenticode digest of an MSI compound file.
+///
+/// Streams are hashed in sorted order of their UTF-16 names, recursing into
+/// storages, followed by the CLSID of each storage. Existing signature
+/// streams are excluded. `prehash` is the content of the
+/// `MsiDigitalSignatureEx` stream, if any, and is hashed first.
+pub(crate) fn msi_digest<F: Read + Seek>(
+    compound: &mut cfb::CompoundFile<F>,
+    algorithm: DigestAlgorithm,
+    prehash: &[u8],
+) -> Result<Vec<u8>> {
+    let mut hasher = algorithm.hasher();
+    hasher.update(prehash);
+    hash_msi_storage(compound, Path::new("/"), true, hasher.as_mut())?;
+
+    Ok(hasher.finalize().to_vec())
+}
+
+fn hash_msi_storage<F: Read + Seek>(
+    compound: &mut cfb::CompoundFile<F>,
+    path: &Path,
+    is_root: bool,
+    hasher: &mut dyn DynDigest,
+) -> Result<()> {
+    let mut entries = compound
+        .read_storage(path)?
+        .filter(|entry| {
+            !(is_root
+                && (entry.name() == MSI_DIGITAL_SIGNATURE_STREAM
+                    || entry.name() == MSI_DIGITAL_SIGNATURE_EX_STREAM))
+        })
+        .map(|entry| {
+            (
+                entry
+                    .name()
+                    .encode_utf16()
+                    .flat_map(|c| c.to_le_bytes())
+                    .collect::<Vec<_>>(),
+                entry.path().to_path_buf(),
+                entry.is_stream(),
+            )
+        })
+        .collect::<Vec<_>>();
+    entries.sort();
+
+    for (_, entry_path, is_stream) in entries {
+        if is_stream {
+            let mut data = vec![];
+            compound
+                .open_stream(&entry_path)?
+                .read_to_end(&mut data)
+                .with_context(|| format!("reading MSI stream {}", entry_path.display()))?;
+            hasher.update(&data);
+        } else {
+            hash_msi_storage(compound, &entry_path, false, hasher)?;
+        }
+    }
+
+    hasher.update(&compound.entry(path)?.clsid().to_bytes_le());
+
+    Ok(())
+}
+
+fn sha256_algorithm_identifier() -> Vec<u8> {
+    der::algorithm_identifier(OID_SHA256, Some(&der::null()))
+}
+
+/// Construct the `SpcIndirectDataContent` for a file digest.
+fn spc_indirect_data_content(file_type: AuthenticodeFileType, digest: &[u8]) -> Vec<u8> {
+    let data = match file_type {
+        AuthenticodeFileType::Pe => {
+            der::sequence(&[&der::oid(OID_SPC_PE_IMAGE_DATA), &SPC_PE_IMAGE_DATA])
+        }
+        AuthenticodeFileType::Msi => {
+            let zero = der::integer(0);
+            let sip_info = der::sequence(&[
+                &der::integer(1),
+                &der::octet_string(&MSI_SIP_GUID),
+                &zero,
+                &zero,
+                &zero,
+                &zero,
+                &zero,
+            ]);
+
+            der::sequence(&[&der::oid(OID_SPC_SIPINFO), &sip_info])
+        }
+    };
+
+    let digest_info = der::sequence(&[&sha256_algorithm_identifier(), &der::octet_string(digest)]);
+
+    der::sequence(&[&data, &digest_info])
+}
+
+/// Construct an authenticated attribute.
+fn attribute(oid: &[u64], value: Vec<u8>) -> Vec<u8> {
+    der::sequence(&[&der::oid(oid), &der::set_of(vec![value])])
+}
+
+/// Obtain the encoded issuer name and serial number of a certificate.
+pub(crate) fn issuer_and_serial(cert: &CapturedX509Certificate) -> Result<(Vec<u8>, Vec<u8>)> {
+    let fields = tbs_certificate_fields(cert)?;
+
+    let serial = fields[0].expect(der::TAG_INTEGER)?;
+    let issuer = fields[2].expect(der::TAG_SEQUENCE)?;
+
+    Ok((issuer.raw.to_vec(), serial.raw.to_vec()))
+}
+
+/// Decode the fields of a certificate's `TBSCertificate`.
+///
+/// The optional version is skipped, so the returned fields start with the
+/// serial number and are followed by the signature algorithm, issuer,
+/// validity, and subject.
+pub(crate) fn tbs_certificate_fields(
+    cert: &CapturedX509Certificate,
+) -> Result<Vec<der::Element<'_>>> {
+    let certificate = der::parse_single(cert.constructed_data())?.expect(der::TAG_SEQUENCE)?;
+    let tbs = certificate
+        .children()?
+        .first()
+        .copied()
+        .ok_or_else(|| anyhow!("certificate lacks TBSCertificate"))?
+        .expect(der::TAG_SEQUENCE)?;
+
+    let mut fields = tbs.children()?;
+    // Skip the optional [0] EXPLICIT version.
+    if fields.first().map(|x| x.tag) == Some(0xa0) {
+        fields.remove(0);
+    }
+
+    if fields.len() < 5 {
+        return Err(anyhow!("certificate has too few TBSCertificate fields"));
+    }
+
+    Ok(fields)
+}
+
+/// Obtain an RFC 3161 time-stamp token for a signature.
+fn request_time_stamp_token(url: &str, signature: &[u8]) -> Result<Vec<u8>> {
+    let message_imprint = der::sequence(&[
+        &sha256_algorithm_identifier(),
+        &der::octet_string(&Sha256::digest(signature)),
+    ]);
+    let request = der::sequence(&[
+        &der::integer(1),
+        &message_imprint,
+        &der::encode(der::TAG_BOOLEAN, &[0xff]),
+    ]);
+
+    let response = tugger_common::http::get_http_client()?
+        .post(url)
+        .header("Content-Type", "application/timestamp-query")
+        .body(request)
+        .send()
+        .with_context(|| format!("sending time-stamp request to {}", url))?
+        .error_for_status()?
+        .bytes()?;
+
+    let response = der::parse_single(&response)
+        .context("parsing time-stamp response")?
+        .expect(der::TAG_SEQUENCE)?;
+    let mut fields = response.children()?.into_iter();
+
+    let status = fields
+        .next()
+        .ok_or_else(|| anyhow!("time-stamp response lacks status"))?
+        .children()?
+        .first()
+        .ok_or_else(|| anyhow!("time-stamp response lacks status"))?
+        .to_u64()?;
+
+    // 0 is granted and 1 is granted with modifications.
+    if status > 1 {
+        return Err(anyhow!(
+            "time-stamp server {} rejected request with status {}",
+            url,
+            status
+        ));
+    }
+
+    let token = fields
+        .next()
+        .ok_or_else(|| anyhow!("time-stamp response lacks token"))?
+        .expect(der::TAG_SEQUENCE)?;
+
+    Ok(token.raw.to_vec())
+}
+
+/// Produces Authenticode signatures without `signtool.exe`.
+///
+/// Instances are bound to a signing key and certificate. Additional
+/// certificates can be registered to be embedded in signatures so
+/// verifiers can build a chain to a trusted root.
+pub struct AuthenticodeSigner<'a> {
+    signing_key: &'a dyn Sign,
+    signing_certificate: CapturedX509Certificate,
+    certificate_chain: Vec<CapturedX509Certificate>,
+    description: Option<String>,
+    description_url: Option<String>,
+    time_stamp_url: Option<String>,
+}
+
+impl<'a> AuthenticodeSigner<'a> {
+    /// Construct a new instance from a signing key and its certificate.
+    pub fn new(signing_key: &'a dyn Sign, signing_certificate: CapturedX509Certificate) -> Self {
+        Self {
+            signing_key,
+            signing_certificate,
+            certificate_chain: vec![],
+            description: None,
+            description_url: None,
+            time_stamp_url: None,
+        }
+    }
+
+    /// Add a certificate to embed in signatures.
+    ///
+    /// This is typically used to embed intermediate certificates.
+    pub fn chain_certificate(&mut self, cert: CapturedX509Certificate) -> &mut Self {
+        self.certificate_chain.push(cert);
+        self
+    }
+
+    /// Set the description of the signed content.
+    ///
+    /// This is the equivalent of `signtool sign /d`.
+    pub fn description(&mut self, description: impl ToString) -> &mut Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// Set a URL with more information about the signed content.
+    ///
+    /// This is the equivalent of `signtool sign /du`.
+    pub fn description_url(&mut self, url: impl ToString) -> &mut Self {
+        self.description_url = Some(url.to_string());
+        self
+    }
+
+    /// Set the URL of an RFC 3161 time-stamp server.
+    ///
+    /// This is the equivalent of `signtool sign /tr`.
+    pub fn time_stamp_url(&mut self, url: impl ToString) -> &mut Self {
+        self.time_stamp_url = Some(url.to_string());
+        self
+    }
+
+    /// Sign data, resolving its file type from its content.
+    pub fn sign_data(&self, data: &[u8]) -> Result<Vec<u8>> {
+        match AuthenticodeFileType::from_data(data) {
+            Some(AuthenticodeFileType::Pe) => self.sign_pe(data),
+            Some(AuthenticodeFileType::Msi) => self.sign_msi(data),
+            None => Err(anyhow!("data is not a PE or MSI file")),
+        }
+    }
+
+    /// Sign a file in place, resolving its file type from its content.
+    pub fn sign_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+
+        let data = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+        let signed = self
+            .sign_data(&data)
+            .with_context(|| format!("signing {}", path.display()))?;
+        std::fs::write(path, signed).with_context(|| format!("writing {}", path.display()))?;
+
+        Ok(())
+    }
+
+    /// Sign a PE file, returning the signed file content.
+    ///
+    /// Any existing signature is replaced.
+    pub fn sign_pe(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let layout = PeLayout::from_data(data)?;
+
+        // Strip any existing signature and clear the data directory entry.
+        let mut res = data[..layout
+            .certificate_table
+            .map(|(offset, _)| offset)
+            .unwrap_or(data.len())]
+            .to_vec();
+        res[layout.security_entry_offset..layout.security_entry_offset + 8].fill(0);
+
+        // The certificate table must be 8 byte aligned. Padding is covered by
+        // the digest.
+        res.resize(res.len() + (8 - res.len() % 8) % 8, 0);
+
+        let layout = PeLayout {
+            certificate_table: None,
+            ..layout
+        };
+        let signature = self.create_signature(
+            AuthenticodeFileType::Pe,
+            &layout.digest(&res, DigestAlgorithm::Sha256),
+        )?;
+
+        let length = 8 + signature.len();
+        let padding = (8 - length % 8) % 8;
+        let table_offset = res.len();
+        let table_size = length + padding;
+
+        res.extend_from_slice(&(table_size as u32).to_le_bytes());
+        res.extend_from_slice(&WIN_CERT_REVISION_2_0.to_le_bytes());
+        res.extend_from_slice(&WIN_CERT_TYPE_PKCS_SIGNED_DATA.to_le_bytes());
+        res.extend_from_slice(&signature);
+        res.resize(res.len() + padding, 0);
+
+        res[layout.security_entry_offset..layout.security_entry_offset + 4]
+            .copy_from_slice(&(table_offset as u32).to_le_bytes());
+        res[layout.security_entry_offset + 4..layout.security_entry_offset + 8]
+            .copy_from_slice(&(table_size as u32).to_le_bytes());
+
+        let checksum = pe_checksum(&res, layout.checksum_offset);
+        res[layout.checksum_offset..layout.checksum_offset + 4]
+            .copy_from_slice(&checksum.to_le_bytes());
+
+        Ok(res)
+    }
+
+    /// Sign an MSI file, returning the signed file content.
+    ///
+    /// Any existing signature is replaced.
+    pub fn sign_msi(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut compound =
+            cfb::CompoundFile::open(Cursor::new(data.to_vec())).context("opening MSI file")?;
+
+        // We don't emit the extended digest. So remove any stale one, as it
+        // would otherwise fail verification.
+        let ex_path = format!("/{}", MSI_DIGITAL_SIGNATURE_EX_STREAM);
+        if compound.exists(&ex_path) {
+            compound.remove_stream(&ex_path)?;
+        }
+
+        let digest = msi_digest(&mut compound, DigestAlgorithm::Sha256, &[])?;
+        let signature = self.create_signature(AuthenticodeFileType::Msi, &digest)?;
+
+        compound
+            .create_stream(format!("/{}", MSI_DIGITAL_SIGNATURE_STREAM))?
+            .write_all(&signature)?;
+        compound.flush()?;
+
+        Ok(compound.into_inner().into_inner())
+    }
+
+    /// Create the DER encoded PKCS #7 `ContentInfo` for a file digest.
+    fn create_signature(&self, file_type: AuthenticodeFileType, digest: &[u8]) -> Result<Vec<u8>> {
+        let content = spc_indirect_data_content(file_type, digest);
+
+        // The message digest covers the content octets, excluding the
+        // SEQUENCE tag and length.
+        let message_digest = Sha256::digest(der::parse_single(&content)?.content);
+
+        let mut opus_info = vec![];
+        if let Some(description) = &self.description {
+            let bmp = description
+                .encode_utf16()
+                .flat_map(|c| c.to_be_bytes())
+                .collect::<Vec<_>>();
+            // programName [0] EXPLICIT SpcString, SpcString unicode [0] IMPLICIT.
+            opus_info.extend(der::encode(0xa0, &der::encode(0x80, &bmp)));
+        }
+        if let Some(url) = &self.description_url {
+            // moreInfo [1] EXPLICIT SpcLink, SpcLink url [0] IMPLICIT IA5String.
+            opus_info.extend(der::encode(0xa1, &der::encode(0x80, url.as_bytes())));
+        }
+
+        let signed_attributes = der::set_of(vec![
+            attribute(OID_CONTENT_TYPE, der::oid(OID_SPC_INDIRECT_DATA)),
+            attribute(
+                OID_SPC_SP_OPUS_INFO,
+                der::encode(der::TAG_SEQUENCE, &opus_info),
+            ),
+            attribute(
+                OID_SPC_STATEMENT_TYPE,
+                der::sequence(&[&der::oid(OID_SPC_INDIVIDUAL_SP_KEY_PURPOSE)]),
+            ),
+            attribute(OID_MESSAGE_DIGEST, der::octet_string(&message_digest)),
+        ]);
+
+        // The signature is over the attributes encoded as a SET OF.
+        let (signature, algorithm) = self
+            .signing_key
+            .sign(&signed_attributes)
+            .map_err(|e| anyhow!("signing failed: {}", e))?;
+
+        let signature_algorithm = match algorithm {
+            SignatureAlgorithm::RsaSha256 => {
+                der::algorithm_identifier(OID_RSA_ENCRYPTION, Some(&der::null()))
+            }
+            SignatureAlgorithm::EcdsaSha256 => {
+                der::algorithm_identifier(OID_ECDSA_WITH_SHA256, None)
+            }
+            algorithm => {
+                return Err(anyhow!(
+                    "signature algorithm {:?} is not supported for Authenticode",
+                    algorithm
+                ))
+            }
+        };
+
+        let (issuer, serial) = issuer_and_serial(&self.signing_certificate)?;
+
+        let mut signer_info = vec![
+            der::integer(1),
+            der::sequence(&[&issuer, &serial]),
+            sha256_algorithm_identifier(),
+            der::implicit(0xa0, signed_attributes),
+            signature_algorithm,
+            der::octet_string(&signature),
+        ];
+
+        if let Some(url) = &self.time_stamp_url {
+            let token = request_time_stamp_token(url, &signature)?;
+
+            signer_info.push(der::implicit(
+                0xa1,
+                der::set_of(vec![attribute(OID_MS_RFC3161_TIMESTAMP, token)]),
+            ));
+        }
+
+        let signer_info =
+            der::sequence(&signer_info.iter().map(|x| x.as_slice()).collect::<Vec<_>>());
+
+        let certificates = der::implicit(
+            0xa0,
+            der::set_of(
+                std::iter::once(&self.signing_certificate)
+                    .chain(self.certificate_chain.iter())
+                    .map(|cert| cert.constructed_data().to_vec())
+                    .collect::<Vec<_>>(),
+            ),
+        );
+
+        let signed_data = der::sequence(&[
+            &der::integer(1),
+            &der::set_of(vec![sha256_algorithm_identifier()]),
+            &der::sequence(&[
+                &der::oid(OID_SPC_INDIRECT_DATA),
+                &der::encode(0xa0, &content),
+            ]),
+            &certificates,
+            &der::set_of(vec![signer_info]),
+        ]);
+
+        Ok(der::sequence(&[
+            &der::oid(OID_SIGNED_DATA),
+            &der::encode(0xa0, &signed_data),
+        ]))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use {
+        super::*, crate::create_self_signed_code_signing_certificate,
+        x509_certificate::InMemorySigningKeyPair,
+    };
+
+    /// Construct a minimal PE32+ file having no sections.
+    pub(crate) fn minimal_pe() -> Vec<u8> {
+        let mut data = vec![0u8; 0x400];
+
+        data[0..2].copy_from_slice(b"MZ");
+        data[0x3c..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        data[0x40..0x44].copy_from_slice(b"PE\0\0");
+        // Machine: AMD64.
+        data[0x44..0x46].copy_from_slice(&0x8664u16.to_le_bytes());
+        // SizeOfOptionalHeader.
+        data[0x54..0x56].copy_from_slice(&0xf0u16.to_le_bytes());
+        // Optional header magic.
+        data[0x58..0x5a].copy_from_slice(&0x20bu16.to_le_bytes());
+        // NumberOfRvaAndSizes.
+        data[0x58 + 108..0x58 + 112].copy_from_slice(&16u32.to_le_bytes());
+        // Some content to hash.
+        data[0x200..0x20b].copy_from_slice(b"hello world");
+
+        data
+    }
+
+    pub(crate) fn test_signing_key() -> Result<(InMemorySigningKeyPair, CapturedX509Certificate)> {
+        let cert = create_self_signed_code_signing_certificate("tugger@example.com")?;
+
+        let key = InMemorySigningKeyPair::from_pkcs8_der(cert.serialize_private_key_der())?;
+        let cert = CapturedX509Certificate::from_der(cert.serialize_der()?)?;
+
+        Ok((key, cert))
+    }
+
+    /// Extract the `SpcIndirectDataContent` digest from a `ContentInfo`.
+    fn embedded_digest(signature: &[u8]) -> Result<Vec<u8>> {
+        let content_info = der::parse_single(signature)?.children()?;
+        assert_eq!(content_info[0].raw, der::oid(OID_SIGNED_DATA));
+
+        let signed_data = der::parse_single(content_info[1].content)?.children()?;
+        let content = signed_data[2].children()?;
+        assert_eq!(content[0].raw, der::oid(OID_SPC_INDIRECT_DATA));
+
+        let indirect = der::parse_single(content[1].content)?.children()?;
+        let digest_info = indirect[1].children()?;
+
+        Ok(digest_info[1].content.to_vec())
+    }
+
+    #[test]
+    fn test_file_type() {
+        assert_eq!(
+            AuthenticodeFileType::from_data(&minimal_pe()),
+            Some(AuthenticodeFileType::Pe)
+        );
+        assert_eq!(
+            AuthenticodeFileType::from_data(&crate::signing::CFB_MAGIC_NUMBER),
+            Some(AuthenticodeFileType::Msi)
+        );
+        assert_eq!(AuthenticodeFileType::from_data(b"foo"), None);
+    }
+
+    #[test]
+    fn test_sign_pe() -> Result<()> {
+        let (key, cert) = test_signing_key()?;
+        let mut signer = AuthenticodeSigner::new(&key, cert);
+        signer.description("tugger test");
+
+        let unsigned = minimal_pe();
+        let signed = signer.sign_pe(&unsigned)?;
+
+        assert_eq!(signed.len() % 8, 0);
+
+        let layout = PeLayout::from_data(&signed)?;
+        let (offset, size) = layout.certificate_table.unwrap();
+        assert_eq!(offset, unsigned.len());
+        assert_eq!(offset + size, signed.len());
+        assert_eq!(read_u16(&signed, offset + 4)?, WIN_CERT_REVISION_2_0);
+        assert_eq!(
+            read_u16(&signed, offset + 6)?,
+            WIN_CERT_TYPE_PKCS_SIGNED_DATA
+        );
+
+        assert_eq!(
+            read_u32(&signed, layout.checksum_offset)?,
+            pe_checksum(&signed, layout.checksum_offset)
+        );
+
+        let (signature, _) = der::parse(&signed[offset + 8..])?;
+        assert_eq!(
+            embedded_digest(signature.raw)?,
+            layout.digest(&signed, DigestAlgorithm::Sha256)
+        );
+        assert_eq!(
+            layout.digest(&signed, DigestAlgorithm::Sha256),
+            layout.digest(&unsigned, DigestAlgorithm::Sha256)
+        );
+
+        // Re-signing replaces the existing signature.
+        let resigned = signer.sign_pe(&signed)?;
+        assert_eq!(
+            PeLayout::from_data(&resigned)?.certificate_table.unwrap().0,
+            unsigned.len()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sign_msi() -> Result<()> {
+        let (key, cert) = test_signing_key()?;
+        let signer = AuthenticodeSigner::new(&key, cert);
+
+        let mut compound = cfb::CompoundFile::create(Cursor::new(vec![]))?;
+        compound.create_stream("/foo")?.write_all(b"foo")?;
+        compound.create_storage("/bar")?;
+        compound.create_stream("/bar/baz")?.write_all(b"baz")?;
+        compound.flush()?;
+        let unsigned = compound.into_inner().into_inner();
+
+        let signed = signer.sign_data(&unsigned)?;
+
+        let mut compound = cfb::CompoundFile::open(Cursor::new(signed))?;
+        let mut signature = vec![];
+        compound
+            .open_stream(format!("/{}", MSI_DIGITAL_SIGNATURE_STREAM))?
+            .read_to_end(&mut signature)?;
+
+        assert_eq!(
+            embedded_digest(&signature)?,
+            msi_digest(&mut compound, DigestAlgorithm::Sha256, &[])?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_unknown() -> Result<()> {
+        let (key, cert) = test_signing_key()?;
+        let signer = AuthenticodeSigner::new(&key, cert);
+
+        assert!(signer.sign_data(b"not a binary").is_err());
+
+        Ok(())
+    }
+}
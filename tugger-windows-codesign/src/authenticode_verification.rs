@@ -0,0 +1,854 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! Verification of Authenticode signatures.
+
+This verifies that the digest recorded in a signature matches the content of
+a PE or MSI file, that the signature was produced by the embedded signing
+certificate, that any time-stamp (RFC 3161 or legacy countersignature) covers
+the signature, and that the embedded certificate chain is internally
+consistent and was valid at signing time.
+
+Whether the root of the chain is trusted is not evaluated, as that requires
+a trust store. For MSI files having an `MsiDigitalSignatureEx` stream, the
+stored metadata digest is incorporated into the content digest but is not
+itself recomputed.
+*/
+
+use {
+    crate::{
+        authenticode::{
+            issuer_and_serial, msi_digest, read_u16, read_u32, tbs_certificate_fields,
+            AuthenticodeFileType, DigestAlgorithm, PeLayout, MSI_DIGITAL_SIGNATURE_EX_STREAM,
+            MSI_DIGITAL_SIGNATURE_STREAM, OID_COUNTERSIGNATURE, OID_MESSAGE_DIGEST,
+            OID_MS_RFC3161_TIMESTAMP, OID_SIGNED_DATA, OID_SIGNING_TIME, OID_SPC_INDIRECT_DATA,
+            OID_TST_INFO, WIN_CERT_TYPE_PKCS_SIGNED_DATA,
+        },
+        der,
+    },
+    anyhow::{anyhow, Context, Result},
+    std::{
+        fmt::{Display, Formatter},
+        io::{Cursor, Read, Seek},
+        path::Path,
+    },
+    x509_certificate::{CapturedX509Certificate, KeyAlgorithm, SignatureAlgorithm},
+};
+
+/// Describes a problem found when verifying an Authenticode signature.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AuthenticodeProblem {
+    /// The file content doesn't match the digest recorded in the signature.
+    DigestMismatch,
+
+    /// The signature uses a digest algorithm we don't support.
+    UnsupportedDigestAlgorithm,
+
+    /// The signed content doesn't match the `messageDigest` signed attribute.
+    MessageDigestMismatch,
+
+    /// The certificate that produced the signature isn't embedded in it.
+    SigningCertificateMissing,
+
+    /// The cryptographic signature doesn't verify against the signing certificate.
+    BadSignature,
+
+    /// A certificate in the chain isn't validly signed by its issuer.
+    BadCertificateSignature(String),
+
+    /// A certificate in the chain wasn't valid at the time of signing.
+    CertificateNotValid(String),
+
+    /// The time-stamp doesn't cover the signature.
+    TimeStampMismatch,
+
+    /// The time-stamp signature could not be verified.
+    BadTimeStamp(String),
+}
+
+impl Display for AuthenticodeProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DigestMismatch => f.write_str("file digest does not match signature"),
+            Self::UnsupportedDigestAlgorithm => f.write_str("unsupported digest algorithm"),
+            Self::MessageDigestMismatch => {
+                f.write_str("signed content does not match message digest attribute")
+            }
+            Self::SigningCertificateMissing => f.write_str("signing certificate not found"),
+            Self::BadSignature => f.write_str("signature does not verify"),
+            Self::BadCertificateSignature(subject) => f.write_fmt(format_args!(
+                "certificate not signed by its issuer: {}",
+                subject
+            )),
+            Self::CertificateNotValid(subject) => f.write_fmt(format_args!(
+                "certificate not valid at signing time: {}",
+                subject
+            )),
+            Self::TimeStampMismatch => f.write_str("time-stamp does not cover signature"),
+            Self::BadTimeStamp(reason) => f.write_fmt(format_args!("bad time-stamp: {}", reason)),
+        }
+    }
+}
+
+/// A time-stamp attached to an Authenticode signature.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AuthenticodeTimeStamp {
+    /// Whether this is an RFC 3161 time-stamp as opposed to a legacy countersignature.
+    pub rfc3161: bool,
+
+    /// The time-stamped time, as an RFC 3339 UTC string.
+    pub time: String,
+
+    /// Common name of the time-stamp signing certificate, if found.
+    pub signer: Option<String>,
+}
+
+/// The result of verifying an Authenticode signature.
+#[derive(Clone, Debug)]
+pub struct AuthenticodeVerification {
+    /// The kind of file that was verified.
+    pub file_type: AuthenticodeFileType,
+
+    /// Common name of the signing certificate, if found.
+    pub signer: Option<String>,
+
+    /// Common names of certificates in the chain, starting at the signing certificate.
+    pub certificate_chain: Vec<String>,
+
+    /// Whether the chain ends with a self-signed certificate.
+    ///
+    /// Signatures commonly omit the root certificate, as verifiers are
+    /// expected to have it in their trust store. So an incomplete chain isn't
+    /// considered a problem.
+    pub chain_complete: bool,
+
+    /// The time-stamp attached to the signature, if any.
+    pub time_stamp: Option<AuthenticodeTimeStamp>,
+
+    /// Problems found with the signature.
+    pub problems: Vec<AuthenticodeProblem>,
+}
+
+impl AuthenticodeVerification {
+    /// Whether the signature verified without problems.
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// A decoded PKCS #7 `SignedData`.
+struct SignedData<'a> {
+    content_type: der::Element<'a>,
+    /// The signed content, without the `[0] EXPLICIT` wrapper.
+    content: Option<der::Element<'a>>,
+    certificates: Vec<CapturedX509Certificate>,
+    signer_infos: Vec<der::Element<'a>>,
+}
+
+impl<'a> SignedData<'a> {
+    /// Decode from a `ContentInfo` holding `SignedData`.
+    fn parse(content_info: der::Element<'a>) -> Result<Self> {
+        let parts = content_info.expect(der::TAG_SEQUENCE)?.children()?;
+        if parts.len() != 2 || parts[0].raw != der::oid(OID_SIGNED_DATA).as_slice() {
+            return Err(anyhow!("not a PKCS #7 SignedData structure"));
+        }
+
+        let fields = der::parse_single(parts[1].expect(0xa0)?.content)?
+            .expect(der::TAG_SEQUENCE)?
+            .children()?;
+        if fields.len() < 4 {
+            return Err(anyhow!("SignedData has too few fields"));
+        }
+
+        let content_info = fields[2].expect(der::TAG_SEQUENCE)?.children()?;
+        let content_type = content_info
+            .first()
+            .copied()
+            .ok_or_else(|| anyhow!("SignedData lacks content type"))?
+            .expect(der::TAG_OID)?;
+        let content = content_info
+            .get(1)
+            .map(|x| der::parse_single(x.expect(0xa0)?.content))
+            .transpose()?;
+
+        let mut certificates = vec![];
+        for field in &fields[3..fields.len() - 1] {
+            if field.tag == 0xa0 {
+                for cert in field.children()? {
+                    certificates.push(CapturedX509Certificate::from_der(cert.raw.to_vec())?);
+                }
+            }
+        }
+
+        let signer_infos = fields[fields.len() - 1].expect(der::TAG_SET)?.children()?;
+
+        Ok(Self {
+            content_type,
+            content,
+            certificates,
+            signer_infos,
+        })
+    }
+}
+
+/// A decoded PKCS #7 `SignerInfo`.
+struct SignerInfo<'a> {
+    issuer: &'a [u8],
+    serial: &'a [u8],
+    digest_algorithm: der::Element<'a>,
+    signed_attributes: Option<der::Element<'a>>,
+    signature: &'a [u8],
+    unsigned_attributes: Option<der::Element<'a>>,
+}
+
+fn next_field<'a>(
+    fields: &mut impl Iterator<Item = der::Element<'a>>,
+    name: &str,
+) -> Result<der::Element<'a>> {
+    fields
+        .next()
+        .ok_or_else(|| anyhow!("SignerInfo lacks {}", name))
+}
+
+impl<'a> SignerInfo<'a> {
+    fn parse(element: der::Element<'a>) -> Result<Self> {
+        let mut fields = element.expect(der::TAG_SEQUENCE)?.children()?.into_iter();
+
+        next_field(&mut fields, "version")?;
+
+        let sid = next_field(&mut fields, "signer identifier")?;
+        if sid.tag != der::TAG_SEQUENCE {
+            return Err(anyhow!(
+                "only issuer and serial number signer identifiers are supported"
+            ));
+        }
+        let sid = sid.children()?;
+        if sid.len() != 2 {
+            return Err(anyhow!("malformed issuer and serial number"));
+        }
+
+        let digest_algorithm = next_field(&mut fields, "digest algorithm")?;
+
+        let mut field = next_field(&mut fields, "signature algorithm")?;
+        let signed_attributes = if field.tag == 0xa0 {
+            let attributes = field;
+            field = next_field(&mut fields, "signature algorithm")?;
+            Some(attributes)
+        } else {
+            None
+        };
+
+        let signature = next_field(&mut fields, "signature")?
+            .expect(der::TAG_OCTET_STRING)?
+            .content;
+        let unsigned_attributes = fields.next().filter(|x| x.tag == 0xa1);
+
+        Ok(Self {
+            issuer: sid[0].raw,
+            serial: sid[1].raw,
+            digest_algorithm,
+            signed_attributes,
+            signature,
+            unsigned_attributes,
+        })
+    }
+}
+
+/// Find the first value of an attribute.
+fn attribute_value<'a>(
+    attributes: Option<der::Element<'a>>,
+    oid: &[u64],
+) -> Result<Option<der::Element<'a>>> {
+    let attributes = match attributes {
+        Some(attributes) => attributes,
+        None => return Ok(None),
+    };
+
+    let oid = der::oid(oid);
+
+    for attribute in attributes.children()? {
+        let parts = attribute.children()?;
+
+        if parts.len() == 2 && parts[0].raw == oid.as_slice() {
+            return Ok(parts[1].children()?.first().copied());
+        }
+    }
+
+    Ok(None)
+}
+
+/// Obtain the name of a certificate for reporting.
+fn certificate_name(cert: &CapturedX509Certificate) -> String {
+    cert.subject_common_name()
+        .unwrap_or_else(|| "<unknown>".to_string())
+}
+
+/// Decode a `UTCTime` or `GeneralizedTime` to a `YYYYMMDDHHMMSS` string.
+///
+/// This form allows times to be compared lexically.
+fn der_time(element: &der::Element) -> Result<String> {
+    let value = std::str::from_utf8(element.content).context("decoding time")?;
+
+    let res = match element.tag {
+        // UTCTime: YYMMDDHHMMSSZ.
+        0x17 if value.len() >= 12 => {
+            let century = if &value[0..2] >= "50" { "19" } else { "20" };
+            format!("{}{}", century, &value[0..12])
+        }
+        // GeneralizedTime: YYYYMMDDHHMMSS[.fff]Z.
+        0x18 if value.len() >= 14 => value[0..14].to_string(),
+        _ => return Err(anyhow!("invalid time value: {}", value)),
+    };
+
+    if res.bytes().all(|b| b.is_ascii_digit()) {
+        Ok(res)
+    } else {
+        Err(anyhow!("invalid time value: {}", value))
+    }
+}
+
+/// Format a `YYYYMMDDHHMMSS` string as RFC 3339.
+fn rfc3339_time(time: &str) -> String {
+    format!(
+        "{}-{}-{}T{}:{}:{}Z",
+        &time[0..4],
+        &time[4..6],
+        &time[6..8],
+        &time[8..10],
+        &time[10..12],
+        &time[12..14]
+    )
+}
+
+/// The current time as a `YYYYMMDDHHMMSS` string.
+fn now_time() -> String {
+    let now = time::OffsetDateTime::now_utc();
+
+    format!(
+        "{:04}{:02}{:02}{:02}{:02}{:02}",
+        now.year(),
+        now.month() as u8,
+        now.day(),
+        now.hour(),
+        now.minute(),
+        now.second()
+    )
+}
+
+/// Verify data was signed by a certificate's key.
+fn verify_signed_data(
+    cert: &CapturedX509Certificate,
+    digest_algorithm: DigestAlgorithm,
+    data: &[u8],
+    signature: &[u8],
+) -> Result<()> {
+    let key_algorithm = cert
+        .key_algorithm()
+        .ok_or_else(|| anyhow!("unknown certificate key algorithm"))?;
+
+    let signature_algorithm = match (key_algorithm, digest_algorithm) {
+        (KeyAlgorithm::Rsa, DigestAlgorithm::Sha1) => SignatureAlgorithm::RsaSha1,
+        (KeyAlgorithm::Rsa, DigestAlgorithm::Sha256) => SignatureAlgorithm::RsaSha256,
+        (KeyAlgorithm::Rsa, DigestAlgorithm::Sha384) => SignatureAlgorithm::RsaSha384,
+        (KeyAlgorithm::Rsa, DigestAlgorithm::Sha512) => SignatureAlgorithm::RsaSha512,
+        (KeyAlgorithm::Ecdsa(_), DigestAlgorithm::Sha256) => SignatureAlgorithm::EcdsaSha256,
+        (KeyAlgorithm::Ecdsa(_), DigestAlgorithm::Sha384) => SignatureAlgorithm::EcdsaSha384,
+        (KeyAlgorithm::Ed25519, _) => SignatureAlgorithm::Ed25519,
+        _ => {
+            return Err(anyhow!(
+                "unsupported combination of {:?} key and {:?} digest",
+                key_algorithm,
+                digest_algorithm
+            ))
+        }
+    };
+
+    let verify_algorithm = signature_algorithm.resolve_verification_algorithm(key_algorithm)?;
+    cert.verify_signed_data_with_algorithm(data, signature, verify_algorithm)?;
+
+    Ok(())
+}
+
+/// Verify a `SignerInfo` over content.
+///
+/// Problems are recorded in `problems`. Returns the index of the signing
+/// certificate, if found.
+fn verify_signer_info(
+    info: &SignerInfo,
+    certificates: &[CapturedX509Certificate],
+    content: &[u8],
+    problems: &mut Vec<AuthenticodeProblem>,
+) -> Result<Option<usize>> {
+    let index = match certificates.iter().position(|cert| {
+        issuer_and_serial(cert)
+            .map(|(issuer, serial)| issuer == info.issuer && serial == info.serial)
+            .unwrap_or(false)
+    }) {
+        Some(index) => index,
+        None => {
+            problems.push(AuthenticodeProblem::SigningCertificateMissing);
+            return Ok(None);
+        }
+    };
+
+    let digest_algorithm = match DigestAlgorithm::from_algorithm_identifier(&info.digest_algorithm)
+    {
+        Ok(algorithm) => algorithm,
+        Err(_) => {
+            problems.push(AuthenticodeProblem::UnsupportedDigestAlgorithm);
+            return Ok(Some(index));
+        }
+    };
+
+    let signed_data = if let Some(attributes) = info.signed_attributes {
+        match attribute_value(Some(attributes), OID_MESSAGE_DIGEST)? {
+            Some(digest)
+                if digest.tag == der::TAG_OCTET_STRING
+                    && digest.content == digest_algorithm.digest(content).as_slice() => {}
+            _ => problems.push(AuthenticodeProblem::MessageDigestMismatch),
+        }
+
+        // The signature is over the attributes encoded as a SET OF.
+        der::implicit(der::TAG_SET, attributes.raw.to_vec())
+    } else {
+        content.to_vec()
+    };
+
+    if verify_signed_data(
+        &certificates[index],
+        digest_algorithm,
+        &signed_data,
+        info.signature,
+    )
+    .is_err()
+    {
+        problems.push(AuthenticodeProblem::BadSignature);
+    }
+
+    Ok(Some(index))
+}
+
+/// Verify an RFC 3161 time-stamp token covering a signature.
+///
+/// Returns the time-stamped time as a `YYYYMMDDHHMMSS` string.
+fn verify_rfc3161_time_stamp(
+    token: der::Element,
+    signature: &[u8],
+    report: &mut AuthenticodeVerification,
+) -> Result<String> {
+    let signed_data = SignedData::parse(token).context("parsing time-stamp token")?;
+    if signed_data.content_type.raw != der::oid(OID_TST_INFO).as_slice() {
+        return Err(anyhow!("time-stamp token does not contain TSTInfo"));
+    }
+
+    let tst_info = signed_data
+        .content
+        .ok_or_else(|| anyhow!("time-stamp token lacks TSTInfo"))?
+        .expect(der::TAG_OCTET_STRING)?
+        .content;
+    let fields = der::parse_single(tst_info)?
+        .expect(der::TAG_SEQUENCE)?
+        .children()?;
+    if fields.len() < 5 {
+        return Err(anyhow!("TSTInfo has too few fields"));
+    }
+
+    let imprint = fields[2].expect(der::TAG_SEQUENCE)?.children()?;
+    if imprint.len() != 2 {
+        return Err(anyhow!("malformed time-stamp message imprint"));
+    }
+    match DigestAlgorithm::from_algorithm_identifier(&imprint[0]) {
+        Ok(algorithm) if imprint[1].content == algorithm.digest(signature).as_slice() => {}
+        _ => report.problems.push(AuthenticodeProblem::TimeStampMismatch),
+    }
+
+    let time = der_time(&fields[4])?;
+
+    let info = SignerInfo::parse(
+        signed_data
+            .signer_infos
+            .first()
+            .copied()
+            .ok_or_else(|| anyhow!("time-stamp token has no signers"))?,
+    )?;
+
+    let mut problems = vec![];
+    let index = verify_signer_info(&info, &signed_data.certificates, tst_info, &mut problems)?;
+
+    report.problems.extend(
+        problems
+            .into_iter()
+            .map(|p| AuthenticodeProblem::BadTimeStamp(p.to_string())),
+    );
+    report.time_stamp = Some(AuthenticodeTimeStamp {
+        rfc3161: true,
+        time: rfc3339_time(&time),
+        signer: index.map(|i| certificate_name(&signed_data.certificates[i])),
+    });
+
+    Ok(time)
+}
+
+/// Verify a legacy countersignature covering a signature.
+///
+/// Returns the signing time as a `YYYYMMDDHHMMSS` string.
+fn verify_countersignature(
+    countersignature: der::Element,
+    signature: &[u8],
+    certificates: &[CapturedX509Certificate],
+    report: &mut AuthenticodeVerification,
+) -> Result<String> {
+    let info = SignerInfo::parse(countersignature).context("parsing countersignature")?;
+
+    let time = der_time(
+        &attribute_value(info.signed_attributes, OID_SIGNING_TIME)?
+            .ok_or_else(|| anyhow!("countersignature lacks signing time"))?,
+    )?;
+
+    let mut problems = vec![];
+    let index = verify_signer_info(&info, certificates, signature, &mut problems)?;
+
+    report
+        .problems
+        .extend(problems.into_iter().map(|p| match p {
+            AuthenticodeProblem::MessageDigestMismatch => AuthenticodeProblem::TimeStampMismatch,
+            p => AuthenticodeProblem::BadTimeStamp(p.to_string()),
+        }));
+    report.time_stamp = Some(AuthenticodeTimeStamp {
+        rfc3161: false,
+        time: rfc3339_time(&time),
+        signer: index.map(|i| certificate_name(&certificates[i])),
+    });
+
+    Ok(time)
+}
+
+/// Verify the certificate chain of the signing certificate at the given time.
+fn verify_chain(
+    certificates: &[CapturedX509Certificate],
+    signer_index: usize,
+    time: &str,
+    report: &mut AuthenticodeVerification,
+) -> Result<()> {
+    let mut visited = vec![];
+    let mut current = signer_index;
+
+    loop {
+        let cert = &certificates[current];
+        let name = certificate_name(cert);
+        let fields = tbs_certificate_fields(cert)?;
+
+        report.certificate_chain.push(name.clone());
+        visited.push(current);
+
+        let validity = fields[3].expect(der::TAG_SEQUENCE)?.children()?;
+        if validity.len() != 2 {
+            return Err(anyhow!("malformed certificate validity"));
+        }
+        if time < der_time(&validity[0])?.as_str() || time > der_time(&validity[1])?.as_str() {
+            report
+                .problems
+                .push(AuthenticodeProblem::CertificateNotValid(name.clone()));
+        }
+
+        let issuer = fields[2].raw;
+        if issuer == fields[4].raw {
+            report.chain_complete = true;
+            break;
+        }
+
+        let issuer_index = certificates.iter().enumerate().position(|(i, candidate)| {
+            !visited.contains(&i)
+                && tbs_certificate_fields(candidate)
+                    .map(|fields| fields[4].raw == issuer)
+                    .unwrap_or(false)
+        });
+
+        if let Some(issuer_index) = issuer_index {
+            if cert
+                .verify_signed_by_certificate(&certificates[issuer_index])
+                .is_err()
+            {
+                report
+                    .problems
+                    .push(AuthenticodeProblem::BadCertificateSignature(name));
+            }
+
+            current = issuer_index;
+        } else {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Verify an encoded Authenticode signature.
+///
+/// `file_digest` computes the digest of the signed file with a given algorithm.
+fn verify_signature(
+    file_type: AuthenticodeFileType,
+    signature: &[u8],
+    file_digest: impl FnOnce(DigestAlgorithm) -> Result<Vec<u8>>,
+) -> Result<AuthenticodeVerification> {
+    let (content_info, _) = der::parse(signature).context("parsing signature")?;
+    let signed_data = SignedData::parse(content_info)?;
+
+    if signed_data.content_type.raw != der::oid(OID_SPC_INDIRECT_DATA).as_slice() {
+        return Err(anyhow!("signature does not contain SpcIndirectDataContent"));
+    }
+
+    let content = signed_data
+        .content
+        .ok_or_else(|| anyhow!("signature lacks SpcIndirectDataContent"))?
+        .expect(der::TAG_SEQUENCE)?;
+    let digest_info = content
+        .children()?
+        .get(1)
+        .copied()
+        .ok_or_else(|| anyhow!("SpcIndirectDataContent lacks digest"))?
+        .expect(der::TAG_SEQUENCE)?
+        .children()?;
+    if digest_info.len() != 2 {
+        return Err(anyhow!("malformed SpcIndirectDataContent digest"));
+    }
+
+    let mut report = AuthenticodeVerification {
+        file_type,
+        signer: None,
+        certificate_chain: vec![],
+        chain_complete: false,
+        time_stamp: None,
+        problems: vec![],
+    };
+
+    match DigestAlgorithm::from_algorithm_identifier(&digest_info[0]) {
+        Ok(algorithm) => {
+            if file_digest(algorithm)? != digest_info[1].content {
+                report.problems.push(AuthenticodeProblem::DigestMismatch);
+            }
+        }
+        Err(_) => report
+            .problems
+            .push(AuthenticodeProblem::UnsupportedDigestAlgorithm),
+    }
+
+    let info = SignerInfo::parse(
+        signed_data
+            .signer_infos
+            .first()
+            .copied()
+            .ok_or_else(|| anyhow!("signature has no signers"))?,
+    )?;
+
+    let signer_index = verify_signer_info(
+        &info,
+        &signed_data.certificates,
+        content.content,
+        &mut report.problems,
+    )?;
+
+    let time =
+        if let Some(token) = attribute_value(info.unsigned_attributes, OID_MS_RFC3161_TIMESTAMP)? {
+            Some(verify_rfc3161_time_stamp(
+                token,
+                info.signature,
+                &mut report,
+            )?)
+        } else if let Some(countersignature) =
+            attribute_value(info.unsigned_attributes, OID_COUNTERSIGNATURE)?
+        {
+            Some(verify_countersignature(
+                countersignature,
+                info.signature,
+                &signed_data.certificates,
+                &mut report,
+            )?)
+        } else {
+            None
+        };
+
+    if let Some(index) = signer_index {
+        report.signer = Some(certificate_name(&signed_data.certificates[index]));
+
+        // Without a time-stamp, certificates must be valid now.
+        let time = time.unwrap_or_else(now_time);
+        verify_chain(&signed_data.certificates, index, &time, &mut report)?;
+    }
+
+    Ok(report)
+}
+
+fn read_msi_stream<F: Read + Seek>(
+    compound: &mut cfb::CompoundFile<F>,
+    name: &str,
+) -> Result<Option<Vec<u8>>> {
+    let path = format!("/{}", name);
+
+    if !compound.exists(&path) {
+        return Ok(None);
+    }
+
+    let mut data = vec![];
+    compound.open_stream(&path)?.read_to_end(&mut data)?;
+
+    Ok(Some(data))
+}
+
+/// Verify the Authenticode signature of PE or MSI file content.
+///
+/// Problems with the signature are reported in the returned
+/// [AuthenticodeVerification]. `Err` is only returned if the data cannot be
+/// parsed or isn't signed.
+pub fn verify_authenticode_data(data: &[u8]) -> Result<AuthenticodeVerification> {
+    match AuthenticodeFileType::from_data(data) {
+        Some(AuthenticodeFileType::Pe) => {
+            let layout = PeLayout::from_data(data)?;
+            let (offset, size) = layout
+                .certificate_table
+                .ok_or_else(|| anyhow!("PE file is not signed"))?;
+
+            let length = read_u32(data, offset)? as usize;
+            if length < 8
+                || length > size
+                || read_u16(data, offset + 6)? != WIN_CERT_TYPE_PKCS_SIGNED_DATA
+            {
+                return Err(anyhow!("unsupported PE certificate table entry"));
+            }
+
+            verify_signature(
+                AuthenticodeFileType::Pe,
+                &data[offset + 8..offset + length],
+                |algorithm| Ok(layout.digest(data, algorithm)),
+            )
+        }
+        Some(AuthenticodeFileType::Msi) => {
+            let mut compound =
+                cfb::CompoundFile::open(Cursor::new(data)).context("opening MSI file")?;
+
+            let signature = read_msi_stream(&mut compound, MSI_DIGITAL_SIGNATURE_STREAM)?
+                .ok_or_else(|| anyhow!("MSI file is not signed"))?;
+            let prehash = read_msi_stream(&mut compound, MSI_DIGITAL_SIGNATURE_EX_STREAM)?
+                .unwrap_or_default();
+
+            verify_signature(AuthenticodeFileType::Msi, &signature, |algorithm| {
+                msi_digest(&mut compound, algorithm, &prehash)
+            })
+        }
+        None => Err(anyhow!("data is not a PE or MSI file")),
+    }
+}
+
+/// Verify the Authenticode signature of a PE or MSI file.
+///
+/// See [verify_authenticode_data] for details.
+pub fn verify_authenticode_file(path: impl AsRef<Path>) -> Result<AuthenticodeVerification> {
+    let path = path.as_ref();
+
+    let data = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+
+    verify_authenticode_data(&data).with_context(|| format!("verifying {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            authenticode::tests::{minimal_pe, test_signing_key},
+            AuthenticodeSigner,
+        },
+        std::io::Write,
+    };
+
+    #[test]
+    fn test_der_time() -> Result<()> {
+        assert_eq!(
+            der_time(&der::parse_single(&der::encode(0x17, b"230102030405Z"))?)?,
+            "20230102030405"
+        );
+        assert_eq!(
+            der_time(&der::parse_single(&der::encode(0x17, b"991231235959Z"))?)?,
+            "19991231235959"
+        );
+        assert_eq!(
+            der_time(&der::parse_single(&der::encode(
+                0x18,
+                b"20230102030405.123Z"
+            ))?)?,
+            "20230102030405"
+        );
+        assert!(der_time(&der::parse_single(&der::encode(0x18, b"bad"))?).is_err());
+        assert_eq!(rfc3339_time("20230102030405"), "2023-01-02T03:04:05Z");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_pe() -> Result<()> {
+        let (key, cert) = test_signing_key()?;
+        let signer = AuthenticodeSigner::new(&key, cert.clone());
+
+        let unsigned = minimal_pe();
+        assert!(verify_authenticode_data(&unsigned).is_err());
+
+        let signed = signer.sign_pe(&unsigned)?;
+        let res = verify_authenticode_data(&signed)?;
+        assert!(res.is_valid(), "{:?}", res.problems);
+        assert_eq!(res.file_type, AuthenticodeFileType::Pe);
+        assert_eq!(res.signer.as_deref(), Some("tugger@example.com"));
+        assert_eq!(
+            res.certificate_chain,
+            vec!["tugger@example.com".to_string()]
+        );
+        assert!(res.chain_complete);
+        assert!(res.time_stamp.is_none());
+
+        // Modifying content is detected.
+        let mut modified = signed.clone();
+        modified[0x200] = b'j';
+        assert_eq!(
+            verify_authenticode_data(&modified)?.problems,
+            vec![AuthenticodeProblem::DigestMismatch]
+        );
+
+        // A signature from a key not matching the certificate is detected.
+        let (other_key, _) = test_signing_key()?;
+        let signed = AuthenticodeSigner::new(&other_key, cert).sign_pe(&unsigned)?;
+        assert_eq!(
+            verify_authenticode_data(&signed)?.problems,
+            vec![AuthenticodeProblem::BadSignature]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_msi() -> Result<()> {
+        let (key, cert) = test_signing_key()?;
+        let signer = AuthenticodeSigner::new(&key, cert);
+
+        let mut compound = cfb::CompoundFile::create(Cursor::new(vec![]))?;
+        compound.create_stream("/foo")?.write_all(b"foo")?;
+        compound.flush()?;
+        let unsigned = compound.into_inner().into_inner();
+        assert!(verify_authenticode_data(&unsigned).is_err());
+
+        let signed = signer.sign_msi(&unsigned)?;
+        let res = verify_authenticode_data(&signed)?;
+        assert!(res.is_valid(), "{:?}", res.problems);
+        assert_eq!(res.file_type, AuthenticodeFileType::Msi);
+
+        let mut compound = cfb::CompoundFile::open(Cursor::new(signed))?;
+        compound.create_stream("/foo")?.write_all(b"bar")?;
+        compound.flush()?;
+        let modified = compound.into_inner().into_inner();
+        assert_eq!(
+            verify_authenticode_data(&modified)?.problems,
+            vec![AuthenticodeProblem::DigestMismatch]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,275 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+/*! Minimal DER encoding and decoding for Authenticode structures.
+
+Authenticode signatures are PKCS #7 structures which predate CMS and differ
+from it in subtle ways (e.g. the signed content isn't wrapped in an
+`OCTET STRING`). So we construct and parse them directly rather than going
+through a CMS implementation.
+*/
+
+use anyhow::{anyhow, Result};
+
+pub const TAG_BOOLEAN: u8 = 0x01;
+pub const TAG_INTEGER: u8 = 0x02;
+pub const TAG_OCTET_STRING: u8 = 0x04;
+pub const TAG_NULL: u8 = 0x05;
+pub const TAG_OID: u8 = 0x06;
+pub const TAG_SEQUENCE: u8 = 0x30;
+pub const TAG_SET: u8 = 0x31;
+
+/// Encode a tag-length-value element.
+pub fn encode(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut res = Vec::with_capacity(content.len() + 6);
+    res.push(tag);
+
+    let len = content.len();
+    if len < 0x80 {
+        res.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|b| **b == 0).count();
+        res.push(0x80 | (bytes.len() - skip) as u8);
+        res.extend_from_slice(&bytes[skip..]);
+    }
+
+    res.extend_from_slice(content);
+    res
+}
+
+/// Encode a `SEQUENCE` of already encoded elements.
+pub fn sequence(elements: &[&[u8]]) -> Vec<u8> {
+    encode(TAG_SEQUENCE, &elements.concat())
+}
+
+/// Encode a `SET OF` already encoded elements.
+///
+/// Elements are sorted as required by DER.
+pub fn set_of(mut elements: Vec<Vec<u8>>) -> Vec<u8> {
+    elements.sort();
+    encode(TAG_SET, &elements.concat())
+}
+
+/// Replace the tag of an encoded element, as is done for `IMPLICIT` tagging.
+pub fn implicit(tag: u8, mut element: Vec<u8>) -> Vec<u8> {
+    element[0] = tag;
+    element
+}
+
+/// Encode an `OBJECT IDENTIFIER` from its components.
+pub fn oid(components: &[u64]) -> Vec<u8> {
+    let mut content = vec![];
+
+    let mut encode_component = |mut value: u64| {
+        let mut bytes = vec![(value & 0x7f) as u8];
+        value >>= 7;
+        while value > 0 {
+            bytes.push(0x80 | (value & 0x7f) as u8);
+            value >>= 7;
+        }
+        bytes.reverse();
+        content.extend(bytes);
+    };
+
+    encode_component(components[0] * 40 + components[1]);
+    for component in &components[2..] {
+        encode_component(*component);
+    }
+
+    encode(TAG_OID, &content)
+}
+
+/// Encode a non-negative `INTEGER`.
+pub fn integer(value: u64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let skip = bytes
+        .iter()
+        .take_while(|b| **b == 0)
+        .count()
+        .min(bytes.len() - 1);
+
+    let mut content = bytes[skip..].to_vec();
+    if content[0] & 0x80 != 0 {
+        content.insert(0, 0);
+    }
+
+    encode(TAG_INTEGER, &content)
+}
+
+/// Encode an `OCTET STRING`.
+pub fn octet_string(data: &[u8]) -> Vec<u8> {
+    encode(TAG_OCTET_STRING, data)
+}
+
+/// Encode a `NULL`.
+pub fn null() -> Vec<u8> {
+    encode(TAG_NULL, &[])
+}
+
+/// Encode an `AlgorithmIdentifier`.
+pub fn algorithm_identifier(algorithm: &[u64], parameters: Option<&[u8]>) -> Vec<u8> {
+    if let Some(parameters) = parameters {
+        sequence(&[&oid(algorithm), parameters])
+    } else {
+        sequence(&[&oid(algorithm)])
+    }
+}
+
+/// A decoded tag-length-value element.
+#[derive(Clone, Copy, Debug)]
+pub struct Element<'a> {
+    /// The element's tag.
+    pub tag: u8,
+    /// The element's content.
+    pub content: &'a [u8],
+    /// The entire encoded element, including tag and length.
+    pub raw: &'a [u8],
+}
+
+impl<'a> Element<'a> {
+    /// Decode the children of a constructed element.
+    pub fn children(&self) -> Result<Vec<Element<'a>>> {
+        parse_all(self.content)
+    }
+
+    /// Ensure the element has the given tag.
+    pub fn expect(self, tag: u8) -> Result<Self> {
+        if self.tag == tag {
+            Ok(self)
+        } else {
+            Err(anyhow!(
+                "expected DER tag {:#04x}; got {:#04x}",
+                tag,
+                self.tag
+            ))
+        }
+    }
+
+    /// Interpret the content as an unsigned integer.
+    pub fn to_u64(&self) -> Result<u64> {
+        if self.tag != TAG_INTEGER || self.content.is_empty() || self.content.len() > 9 {
+            return Err(anyhow!("DER element is not a small integer"));
+        }
+
+        Ok(self
+            .content
+            .iter()
+            .fold(0u64, |acc, b| (acc << 8) | *b as u64))
+    }
+}
+
+/// Decode the first element in data, returning it and the remaining data.
+pub fn parse(data: &[u8]) -> Result<(Element<'_>, &[u8])> {
+    if data.len() < 2 {
+        return Err(anyhow!("truncated DER element"));
+    }
+
+    let tag = data[0];
+    if tag & 0x1f == 0x1f {
+        return Err(anyhow!("multi-byte DER tags are not supported"));
+    }
+
+    let (len, header_len) = if data[1] < 0x80 {
+        (data[1] as usize, 2)
+    } else {
+        let count = (data[1] & 0x7f) as usize;
+        if count == 0 {
+            return Err(anyhow!("indefinite length encoding is not allowed in DER"));
+        }
+        if count > std::mem::size_of::<usize>() || data.len() < 2 + count {
+            return Err(anyhow!("invalid DER length"));
+        }
+
+        (
+            data[2..2 + count]
+                .iter()
+                .fold(0usize, |acc, b| (acc << 8) | *b as usize),
+            2 + count,
+        )
+    };
+
+    let end = header_len
+        .checked_add(len)
+        .filter(|end| *end <= data.len())
+        .ok_or_else(|| anyhow!("truncated DER element"))?;
+
+    Ok((
+        Element {
+            tag,
+            content: &data[header_len..end],
+            raw: &data[..end],
+        },
+        &data[end..],
+    ))
+}
+
+/// Decode a single element, ensuring no data follows it.
+pub fn parse_single(data: &[u8]) -> Result<Element<'_>> {
+    let (element, rest) = parse(data)?;
+
+    if rest.is_empty() {
+        Ok(element)
+    } else {
+        Err(anyhow!("trailing data after DER element"))
+    }
+}
+
+/// Decode all elements in data.
+pub fn parse_all(mut data: &[u8]) -> Result<Vec<Element<'_>>> {
+    let mut res = vec![];
+
+    while !data.is_empty() {
+        let (element, rest) = parse(data)?;
+        res.push(element);
+        data = rest;
+    }
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_length() {
+        assert_eq!(encode(TAG_OCTET_STRING, &[1, 2]), vec![0x04, 0x02, 1, 2]);
+
+        let long = encode(TAG_OCTET_STRING, &[0; 300]);
+        assert_eq!(&long[0..4], &[0x04, 0x82, 0x01, 0x2c]);
+
+        let (element, rest) = parse(&long).unwrap();
+        assert_eq!(element.content.len(), 300);
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn test_oid() {
+        // SHA-256.
+        assert_eq!(
+            oid(&[2, 16, 840, 1, 101, 3, 4, 2, 1]),
+            vec![0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01]
+        );
+    }
+
+    #[test]
+    fn test_integer() {
+        assert_eq!(integer(0), vec![0x02, 0x01, 0x00]);
+        assert_eq!(integer(1), vec![0x02, 0x01, 0x01]);
+        assert_eq!(integer(128), vec![0x02, 0x02, 0x00, 0x80]);
+        assert_eq!(
+            parse_single(&integer(0x1234)).unwrap().to_u64().unwrap(),
+            0x1234
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse(&[0x30]).is_err());
+        assert!(parse(&[0x30, 0x80, 0x00, 0x00]).is_err());
+        assert!(parse(&[0x30, 0x05, 0x00]).is_err());
+        assert!(parse_single(&[0x05, 0x00, 0x05, 0x00]).is_err());
+    }
+}
@@ -4,6 +4,11 @@
 
 /*! Code signing on Windows. */
 
+mod authenticode;
+pub use authenticode::*;
+mod authenticode_verification;
+pub use authenticode_verification::*;
+mod der;
 mod signing;
 pub use signing::*;
 mod signtool;
@@ -142,7 +142,7 @@ pub fn certificate_to_pfx(
 }
 
 /// MSI file magic.
-const CFB_MAGIC_NUMBER: [u8; 8] = [0xd0, 0xcf, 0x11, 0xe0, 0xa1, 0xb1, 0x1a, 0xe1];
+pub(crate) const CFB_MAGIC_NUMBER: [u8; 8] = [0xd0, 0xcf, 0x11, 0xe0, 0xa1, 0xb1, 0x1a, 0xe1];
 
 /// Whether the bytes passed in look like a file header for a format that is signable.
 ///
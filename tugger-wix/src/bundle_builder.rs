@@ -3,16 +3,156 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use {
-    crate::*,
+    crate::{common::normalize_guid, *},
     anyhow::{anyhow, Context, Result},
     log::warn,
-    std::{borrow::Cow, collections::BTreeMap, io::Write, ops::Deref, path::Path},
+    std::{
+        borrow::Cow,
+        collections::BTreeMap,
+        io::Write,
+        ops::Deref,
+        path::{Path, PathBuf},
+    },
     tugger_common::http::download_to_path,
     tugger_windows::{VcRedistributablePlatform, VC_REDIST_ARM64, VC_REDIST_X64, VC_REDIST_X86},
     uuid::Uuid,
     xml::{common::XmlVersion, writer::XmlEvent, EmitterConfig, EventWriter},
 };
 
+/// Settings for the standard bootstrapper application providing the bundle's UI.
+#[derive(Clone, Debug)]
+pub struct BootstrapperUi {
+    /// The theme of the standard bootstrapper application.
+    ///
+    /// One of `HyperlinkLicense`, `HyperlinkLargeLicense`,
+    /// `HyperlinkSidebarLicense`, `RtfLicense`, `RtfLargeLicense`, or
+    /// `Foundation`.
+    pub theme: String,
+
+    /// URL of the license displayed by `Hyperlink*` themes.
+    ///
+    /// If not defined, no license link is displayed.
+    pub license_url: Option<String>,
+
+    /// Path of the RTF license displayed by `Rtf*` themes.
+    pub license_file: Option<PathBuf>,
+
+    /// Path of the logo image displayed by the UI.
+    pub logo_file: Option<PathBuf>,
+
+    /// Whether to hide the options button allowing the install location to be changed.
+    pub suppress_options_ui: bool,
+
+    /// Whether to hide the repair button when run for an installed bundle.
+    pub suppress_repair: bool,
+
+    /// Whether to display the bundle version in the UI.
+    pub show_version: bool,
+}
+
+impl Default for BootstrapperUi {
+    fn default() -> Self {
+        Self {
+            theme: "HyperlinkLicense".to_string(),
+            license_url: None,
+            license_file: None,
+            logo_file: None,
+            suppress_options_ui: true,
+            suppress_repair: false,
+            show_version: false,
+        }
+    }
+}
+
+impl BootstrapperUi {
+    /// Ensure settings are valid.
+    pub fn validate(&self) -> Result<()> {
+        match self.theme.as_str() {
+            "HyperlinkLicense" | "HyperlinkLargeLicense" | "HyperlinkSidebarLicense" => {
+                if self.license_file.is_some() {
+                    return Err(anyhow!(
+                        "{} theme uses a license URL, not a license file",
+                        self.theme
+                    ));
+                }
+            }
+            "RtfLicense" | "RtfLargeLicense" => {
+                if self.license_file.is_none() {
+                    return Err(anyhow!("{} theme requires a license file", self.theme));
+                }
+                if self.license_url.is_some() {
+                    return Err(anyhow!(
+                        "{} theme uses a license file, not a license URL",
+                        self.theme
+                    ));
+                }
+            }
+            "Foundation" => {
+                if self.license_file.is_some() || self.license_url.is_some() {
+                    return Err(anyhow!("Foundation theme does not display a license"));
+                }
+            }
+            theme => {
+                return Err(anyhow!("invalid bootstrapper theme: {}", theme));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_xml<W: Write>(&self, writer: &mut EventWriter<W>) -> Result<()> {
+        writer.write(XmlEvent::start_element("BootstrapperApplicationRef").attr(
+            "Id",
+            &format!("WixStandardBootstrapperApplication.{}", self.theme),
+        ))?;
+
+        let license_file = self.license_file.as_ref().map(|p| p.display().to_string());
+        let logo_file = self.logo_file.as_ref().map(|p| p.display().to_string());
+
+        let e = XmlEvent::start_element("bal:WixStandardBootstrapperApplication");
+
+        let e = if let Some(path) = &license_file {
+            e.attr("LicenseFile", path)
+        } else if self.theme.starts_with("Hyperlink") {
+            e.attr("LicenseUrl", self.license_url.as_deref().unwrap_or(""))
+        } else {
+            e
+        };
+
+        let e = if let Some(path) = &logo_file {
+            e.attr("LogoFile", path)
+        } else {
+            e
+        };
+
+        let e = if self.suppress_options_ui {
+            e.attr("SuppressOptionsUI", "yes")
+        } else {
+            e
+        };
+
+        let e = if self.suppress_repair {
+            e.attr("SuppressRepair", "yes")
+        } else {
+            e
+        };
+
+        let e = if self.show_version {
+            e.attr("ShowVersion", "yes")
+        } else {
+            e
+        };
+
+        writer.write(e)?;
+        writer.write(XmlEvent::end_element())?;
+
+        // </BootstrapperApplicationRef>
+        writer.write(XmlEvent::end_element())?;
+
+        Ok(())
+    }
+}
+
 /// Entity used to build a WiX bundle installer.
 ///
 /// Bundle installers have multiple components in them.
@@ -32,6 +172,12 @@ pub struct WiXBundleInstallerBuilder<'a> {
     /// UUID upgrade code.
     upgrade_code: Option<String>,
 
+    /// Path of the icon of the bundle executable and its Add/Remove Programs entry.
+    icon_file: Option<PathBuf>,
+
+    /// Settings of the UI.
+    bootstrapper_ui: BootstrapperUi,
+
     /// Conditions that must be met to perform the install.
     conditions: Vec<(String, String)>,
 
@@ -65,6 +211,28 @@ impl<'a> WiXBundleInstallerBuilder<'a> {
         }
     }
 
+    /// Set the `<Bundle UpgradeCode` attribute value.
+    ///
+    /// If not called, a deterministic value will be derived from the bundle name.
+    pub fn set_upgrade_code(&mut self, value: &str) -> Result<()> {
+        self.upgrade_code = Some(normalize_guid(value)?);
+
+        Ok(())
+    }
+
+    /// Set the path of the icon of the bundle.
+    pub fn set_icon_path(&mut self, path: impl AsRef<Path>) {
+        self.icon_file = Some(path.as_ref().to_path_buf());
+    }
+
+    /// Set the settings of the bundle's UI.
+    pub fn set_bootstrapper_ui(&mut self, ui: BootstrapperUi) -> Result<()> {
+        ui.validate()?;
+        self.bootstrapper_ui = ui;
+
+        Ok(())
+    }
+
     /// Define a `<bal:Condition>` that must be satisfied to run this installer.
     ///
     /// `message` is the message that will be displayed if the condition is not met.
@@ -82,10 +250,11 @@ impl<'a> WiXBundleInstallerBuilder<'a> {
         let (entry, install_condition) = match platform {
             VcRedistributablePlatform::X86 => (VC_REDIST_X86.deref(), "Not VersionNT64"),
             VcRedistributablePlatform::X64 => (VC_REDIST_X64.deref(), "VersionNT64"),
-            VcRedistributablePlatform::Arm64 => {
-                // TODO define proper Arm64 install condition.
-                (VC_REDIST_ARM64.deref(), "VersionNT64 And Not VersionNT64")
-            }
+            // Burn doesn't expose the native machine architecture in WiX 3.11. ARM64
+            // Windows is 64-bit, so VersionNT64 is the best approximation. This means an
+            // x64 bundle will also install the x64 runtime on ARM64, which is what x64
+            // binaries running under emulation need anyway.
+            VcRedistributablePlatform::Arm64 => (VC_REDIST_ARM64.deref(), "VersionNT64"),
         };
 
         let url = url::Url::parse(&entry.url)?;
@@ -202,22 +371,16 @@ impl<'a> WiXBundleInstallerBuilder<'a> {
             bundle
         };
 
-        writer.write(bundle)?;
-
-        writer.write(
-            XmlEvent::start_element("BootstrapperApplicationRef")
-                .attr("Id", "WixStandardBootstrapperApplication.HyperlinkLicense"),
-        )?;
+        let icon_file = self.icon_file.as_ref().map(|p| p.display().to_string());
+        let bundle = if let Some(value) = &icon_file {
+            bundle.attr("IconSourceFile", value)
+        } else {
+            bundle
+        };
 
-        writer.write(
-            XmlEvent::start_element("bal:WixStandardBootstrapperApplication")
-                .attr("LicenseUrl", "")
-                .attr("SuppressOptionsUI", "yes"),
-        )?;
-        writer.write(XmlEvent::end_element())?;
+        writer.write(bundle)?;
 
-        // </BootstrapperApplicationRef>
-        writer.write(XmlEvent::end_element())?;
+        self.bootstrapper_ui.write_xml(writer)?;
 
         for (message, condition) in &self.conditions {
             writer.write(XmlEvent::start_element("bal:Condition").attr("Message", message))?;
@@ -274,6 +437,81 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_write_xml() -> Result<()> {
+        let mut bundle = WiXBundleInstallerBuilder::new(
+            "myapp".to_string(),
+            "0.1".to_string(),
+            "author".to_string(),
+        );
+
+        let mut writer = EventWriter::new(vec![]);
+        bundle.write_xml(&mut writer)?;
+        let xml = String::from_utf8(writer.into_inner())?;
+        assert!(xml.contains(
+            "<BootstrapperApplicationRef Id=\"WixStandardBootstrapperApplication.HyperlinkLicense\">"
+        ));
+        assert!(xml.contains("LicenseUrl=\"\" SuppressOptionsUI=\"yes\""));
+
+        bundle.set_upgrade_code("0c1d2e3f-4a5b-6c7d-8e9f-a0b1c2d3e4f5")?;
+        bundle.set_icon_path("app.ico");
+        bundle.set_bootstrapper_ui(BootstrapperUi {
+            theme: "RtfLicense".to_string(),
+            license_file: Some(PathBuf::from("license.rtf")),
+            logo_file: Some(PathBuf::from("logo.png")),
+            show_version: true,
+            ..BootstrapperUi::default()
+        })?;
+        assert!(bundle
+            .set_bootstrapper_ui(BootstrapperUi {
+                theme: "RtfLicense".to_string(),
+                ..BootstrapperUi::default()
+            })
+            .is_err());
+        assert!(bundle
+            .set_bootstrapper_ui(BootstrapperUi {
+                theme: "Bogus".to_string(),
+                ..BootstrapperUi::default()
+            })
+            .is_err());
+        assert!(bundle.set_upgrade_code("bogus").is_err());
+
+        bundle.chain(
+            ExePackage {
+                source_file: Some("prereq.exe".into()),
+                detect_condition: Some("PrereqInstalled".into()),
+                install_command: Some("/quiet".into()),
+                vital: Some("yes".into()),
+                ..ExePackage::default()
+            }
+            .into(),
+        );
+        bundle.chain(
+            MsiPackage {
+                source_file: Some("app.msi".into()),
+                vital: Some("yes".into()),
+                properties: vec![("INSTALLDESKTOPSHORTCUT".into(), "1".into())],
+                ..MsiPackage::default()
+            }
+            .into(),
+        );
+
+        let mut writer = EventWriter::new(vec![]);
+        bundle.write_xml(&mut writer)?;
+        let xml = String::from_utf8(writer.into_inner())?;
+        assert!(xml.contains("UpgradeCode=\"0C1D2E3F-4A5B-6C7D-8E9F-A0B1C2D3E4F5\""));
+        assert!(xml.contains("IconSourceFile=\"app.ico\""));
+        assert!(xml.contains("WixStandardBootstrapperApplication.RtfLicense"));
+        assert!(xml.contains(
+            "LicenseFile=\"license.rtf\" LogoFile=\"logo.png\" SuppressOptionsUI=\"yes\" ShowVersion=\"yes\""
+        ));
+        assert!(xml.contains("DetectCondition=\"PrereqInstalled\""));
+        assert!(xml.contains("<MsiPackage SourceFile=\"app.msi\" Vital=\"yes\">"));
+        assert!(xml.contains("<MsiProperty Name=\"INSTALLDESKTOPSHORTCUT\" Value=\"1\""));
+
+        Ok(())
+    }
+
     #[cfg(windows)]
     #[test]
     fn test_vc_redistributable_build() -> Result<()> {
@@ -99,6 +99,17 @@ pub fn component_group_id(prefix: &str, path: &Path) -> String {
     )
 }
 
+/// Validate a GUID and normalize it to the upper case hyphenated form.
+pub(crate) fn normalize_guid(value: &str) -> Result<String> {
+    let guid = Uuid::parse_str(value.trim_start_matches('{').trim_end_matches('}'))
+        .map_err(|e| anyhow!("{} is not a valid GUID: {}", value, e))?;
+
+    Ok(guid
+        .as_hyphenated()
+        .encode_upper(&mut Uuid::encode_buffer())
+        .to_string())
+}
+
 /// Convert a `FileManifest` to WiX XML defining those files.
 ///
 /// The generated XML contains `<Fragment>` and `<DirectoryRef>` for every
@@ -458,6 +469,23 @@ mod tests {
         xml::EmitterConfig,
     };
 
+    #[test]
+    fn test_target_triple_to_wix_arch() {
+        assert_eq!(
+            target_triple_to_wix_arch("i686-pc-windows-msvc"),
+            Some("x86")
+        );
+        assert_eq!(
+            target_triple_to_wix_arch("x86_64-pc-windows-msvc"),
+            Some("x64")
+        );
+        assert_eq!(
+            target_triple_to_wix_arch("aarch64-pc-windows-msvc"),
+            Some("arm64")
+        );
+        assert_eq!(target_triple_to_wix_arch("x86_64-unknown-linux-gnu"), None);
+    }
+
     #[test]
     fn test_wix_download() -> Result<()> {
         extract_wix(DEFAULT_DOWNLOAD_DIR.as_path())?;
@@ -4,7 +4,7 @@
 
 use {
     crate::ChainElement,
-    anyhow::Result,
+    anyhow::{anyhow, Result},
     std::{borrow::Cow, io::Write},
     xml::writer::{EventWriter, XmlEvent},
 };
@@ -37,6 +37,23 @@ impl ToString for Behavior {
     }
 }
 
+impl TryFrom<&str> for Behavior {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "success" => Ok(Self::Success),
+            "error" => Ok(Self::Error),
+            "scheduleReboot" => Ok(Self::ScheduleReboot),
+            "forceReboot" => Ok(Self::ForceReboot),
+            _ => Err(anyhow!(
+                "{} is not a valid exit code behavior; use 'success', 'error', 'scheduleReboot', or 'forceReboot'",
+                value
+            )),
+        }
+    }
+}
+
 /// Defines a mapping of an exit code to behavior.
 #[derive(Clone, Debug)]
 pub struct ExitCode {
@@ -63,6 +80,7 @@ pub struct ExePackage<'a> {
     pub install_command: Option<Cow<'a, str>>,
     pub repair_command: Option<Cow<'a, str>>,
     pub uninstall_command: Option<Cow<'a, str>>,
+    pub vital: Option<Cow<'a, str>>,
     pub exit_codes: Vec<ExitCode>,
 }
 
@@ -154,6 +172,12 @@ impl<'a> ExePackage<'a> {
             e
         };
 
+        let e = if let Some(value) = &self.vital {
+            e.attr("Vital", value)
+        } else {
+            e
+        };
+
         writer.write(e)?;
 
         for exit_code in &self.exit_codes {
@@ -12,12 +12,15 @@ mod simple_msi_builder;
 mod wxs_builder;
 
 pub use {
-    bundle_builder::WiXBundleInstallerBuilder,
+    bundle_builder::{BootstrapperUi, WiXBundleInstallerBuilder},
     chain::ChainElement,
     common::{run_candle, run_light, target_triple_to_wix_arch, write_file_manifest_to_wix},
     exe_package::{Behavior, ExePackage, ExitCode},
     installer_builder::WiXInstallerBuilder,
     msi_package::MsiPackage,
-    simple_msi_builder::WiXSimpleMsiBuilder,
+    simple_msi_builder::{
+        WiXCustomAction, WiXCustomActionKind, WiXEnvironmentVariable, WiXInstallScope,
+        WiXProductCode, WiXProperty, WiXRegistryValue, WiXSimpleMsiBuilder,
+    },
     wxs_builder::WxsBuilder,
 };
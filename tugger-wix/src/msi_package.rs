@@ -19,6 +19,12 @@ pub struct MsiPackage<'a> {
     pub source_file: Option<Cow<'a, str>>,
     pub display_internal_ui: Option<Cow<'a, str>>,
     pub install_condition: Option<Cow<'a, str>>,
+    pub cache: Option<Cow<'a, str>>,
+    pub permanent: Option<Cow<'a, str>>,
+    pub vital: Option<Cow<'a, str>>,
+    pub visible: Option<Cow<'a, str>>,
+    /// Properties to pass to the MSI, as `<MsiProperty>` children.
+    pub properties: Vec<(Cow<'a, str>, Cow<'a, str>)>,
 }
 
 impl<'a> From<MsiPackage<'a>> for ChainElement<'a> {
@@ -73,7 +79,41 @@ impl<'a> MsiPackage<'a> {
             e
         };
 
+        let e = if let Some(value) = &self.cache {
+            e.attr("Cache", value)
+        } else {
+            e
+        };
+
+        let e = if let Some(value) = &self.permanent {
+            e.attr("Permanent", value)
+        } else {
+            e
+        };
+
+        let e = if let Some(value) = &self.vital {
+            e.attr("Vital", value)
+        } else {
+            e
+        };
+
+        let e = if let Some(value) = &self.visible {
+            e.attr("Visible", value)
+        } else {
+            e
+        };
+
         writer.write(e)?;
+
+        for (name, value) in &self.properties {
+            writer.write(
+                XmlEvent::start_element("MsiProperty")
+                    .attr("Name", name)
+                    .attr("Value", value),
+            )?;
+            writer.write(XmlEvent::end_element().name("MsiProperty"))?;
+        }
+
         writer.write(XmlEvent::end_element().name("MsiPackage"))?;
 
         Ok(())
@@ -3,7 +3,7 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use {
-    crate::{WiXInstallerBuilder, WxsBuilder},
+    crate::{common::normalize_guid, WiXInstallerBuilder, WxsBuilder},
     anyhow::{anyhow, Result},
     simple_file_manifest::FileManifest,
     std::{
@@ -20,6 +20,243 @@ use {
     },
 };
 
+/// A registry value to write during installation.
+///
+/// Registry values are installed with the main program feature and are
+/// removed on uninstall.
+#[derive(Clone, Debug)]
+pub struct WiXRegistryValue {
+    /// Registry root. One of `HKLM`, `HKCU`, `HKCR`, `HKU`, or `HKMU`.
+    pub root: String,
+    /// Registry key path under the root.
+    pub key: String,
+    /// Name of the value. `None` sets the key's default value.
+    pub name: Option<String>,
+    /// The value to write. May reference installer properties, e.g. `[APPLICATIONFOLDER]`.
+    pub value: String,
+    /// WiX value type. One of `string`, `integer`, `expandable`, `multiString`, or `binary`.
+    pub value_type: String,
+}
+
+/// An environment variable modification to perform during installation.
+///
+/// Modifications are reverted on uninstall.
+#[derive(Clone, Debug)]
+pub struct WiXEnvironmentVariable {
+    /// Name of the environment variable.
+    pub name: String,
+    /// Value to apply. May reference installer properties, e.g. `[APPLICATIONFOLDER]`.
+    pub value: String,
+    /// WiX action. One of `set`, `create`, or `remove`.
+    pub action: String,
+    /// Which part of an existing value to modify. One of `all`, `first`, or `last`.
+    pub part: String,
+    /// Whether to modify the system environment instead of the user environment.
+    pub system: bool,
+}
+
+/// What a custom action runs.
+#[derive(Clone, Debug)]
+pub enum WiXCustomActionKind {
+    /// Run a command line, typically referencing an installed executable.
+    ///
+    /// The command runs in the installation directory and may reference
+    /// installer properties, e.g. `"[APPLICATIONFOLDER]myapp.exe" --register`.
+    Exe { command: String },
+
+    /// Call an exported function of a DLL embedded in the installer.
+    ///
+    /// The function must have the signature `UINT __stdcall f(MSIHANDLE)`.
+    Dll {
+        /// Path of the DLL to embed.
+        source: PathBuf,
+        /// Name of the exported function to call.
+        entry_point: String,
+    },
+
+    /// Run a script embedded in the installer.
+    Script {
+        /// Path of the script to embed.
+        source: PathBuf,
+        /// Script language. One of `vbscript` or `jscript`.
+        language: String,
+        /// Function to call. `None` runs the script's global code.
+        function: Option<String>,
+    },
+}
+
+/// A custom action to run during installation.
+///
+/// Custom actions are scheduled in the `InstallExecuteSequence` table.
+#[derive(Clone, Debug)]
+pub struct WiXCustomAction {
+    /// Identifier of the action. Must be unique within the installer.
+    pub id: String,
+    /// What the action runs.
+    pub kind: WiXCustomActionKind,
+    /// Standard or custom action to run after. e.g. `InstallFiles`.
+    ///
+    /// If neither this nor `before` is defined, the action runs after
+    /// `InstallFiles`.
+    pub after: Option<String>,
+    /// Standard or custom action to run before. e.g. `InstallFinalize`.
+    pub before: Option<String>,
+    /// Condition controlling whether the action runs. e.g. `NOT Installed`.
+    ///
+    /// `None` always runs the action.
+    pub condition: Option<String>,
+    /// When the action executes. One of `immediate`, `deferred`, `commit`,
+    /// `rollback`, `oncePerProcess`, `firstSequence`, or `secondSequence`.
+    ///
+    /// Deferred actions run in the installation script and are required to
+    /// change the system.
+    pub execute: String,
+    /// Whether the action runs as the installing user.
+    ///
+    /// If false, deferred actions run as `LocalSystem`.
+    pub impersonate: bool,
+    /// How the action's result is handled. One of `check`, `ignore`,
+    /// `asyncWait`, or `asyncNoWait`.
+    pub return_type: String,
+}
+
+/// A property to define in the installer.
+#[derive(Clone, Debug)]
+pub struct WiXProperty {
+    /// Identifier of the property. Public properties, which can be set on
+    /// the command line, are all uppercase.
+    pub id: String,
+    /// Default value of the property.
+    pub value: String,
+    /// Whether the property can be passed to the server side of the install.
+    ///
+    /// Public properties read by deferred actions or conditions must be
+    /// secure.
+    pub secure: bool,
+}
+
+/// How the `<Product Id` (the product code) is chosen.
+///
+/// Windows Installer identifies an installed product by its product code.
+/// Products sharing an upgrade code but having different product codes are
+/// upgraded via the `<MajorUpgrade>` rules. Reinstalling a product code that
+/// is already installed is a maintenance operation instead.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum WiXProductCode {
+    /// Generate a new product code every time the installer is built.
+    #[default]
+    Auto,
+
+    /// Derive a stable product code from the upgrade code and product version.
+    ///
+    /// Rebuilding the same version yields the same product code, so an
+    /// installer for a given version can be rebuilt without it being treated
+    /// as a different product.
+    PerVersion,
+
+    /// Use an explicit GUID.
+    Fixed(String),
+}
+
+impl TryFrom<&str> for WiXProductCode {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "*" | "auto" => Ok(Self::Auto),
+            "version" => Ok(Self::PerVersion),
+            _ => Ok(Self::Fixed(normalize_guid(value)?)),
+        }
+    }
+}
+
+/// Who an installer installs the application for.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum WiXInstallScope {
+    /// Install for all users of the machine.
+    ///
+    /// Files are installed in `Program Files` and installing requires
+    /// elevation.
+    #[default]
+    PerMachine,
+
+    /// Install for the installing user only.
+    ///
+    /// Files are installed in `%LOCALAPPDATA%\Programs` and installing does
+    /// not require elevation. Registry values and environment variables must
+    /// be written to the current user's hive.
+    PerUser,
+}
+
+impl TryFrom<&str> for WiXInstallScope {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "perMachine" => Ok(Self::PerMachine),
+            "perUser" => Ok(Self::PerUser),
+            _ => Err(anyhow!(
+                "{} is not a valid install scope; use 'perMachine' or 'perUser'",
+                value
+            )),
+        }
+    }
+}
+
+/// Ensure a version is usable as a `ProductVersion`.
+///
+/// Product versions have the form `major.minor.build[.revision]`, where
+/// `major` and `minor` are at most 255 and `build` is at most 65535. Windows
+/// Installer ignores `revision` when comparing versions.
+fn validate_product_version(version: &str) -> Result<()> {
+    let parts = version.split('.').collect::<Vec<_>>();
+
+    if parts.is_empty() || parts.len() > 4 {
+        return Err(anyhow!(
+            "product version {} must have between 1 and 4 components",
+            version
+        ));
+    }
+
+    for (i, part) in parts.iter().enumerate() {
+        let value = part.parse::<u32>().map_err(|_| {
+            anyhow!(
+                "product version {} contains non-numeric component {}",
+                version,
+                part
+            )
+        })?;
+
+        let max = match i {
+            0 | 1 => 255,
+            _ => 65535,
+        };
+
+        if value > max {
+            return Err(anyhow!(
+                "product version {} component {} exceeds maximum value {}",
+                version,
+                part,
+                max
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether a string is a valid WiX identifier.
+fn is_valid_identifier(value: &str) -> bool {
+    let mut chars = value.chars();
+
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
 /// Entity used to emit a simple `.wxs` for building an msi installer.
 ///
 /// Instances are constructed with mandatory fields, such as the
@@ -46,6 +283,15 @@ pub struct WiXSimpleMsiBuilder {
     program_files_manifest: FileManifest,
 
     upgrade_code: Option<String>,
+    product_code: WiXProductCode,
+    install_scope: WiXInstallScope,
+
+    /// When existing versions are removed during a major upgrade.
+    upgrade_schedule: Option<String>,
+    allow_downgrades: bool,
+    allow_same_version_upgrades: bool,
+    downgrade_error_message: Option<String>,
+
     package_keywords: Option<String>,
     package_description: Option<String>,
     license_source: Option<PathBuf>,
@@ -62,6 +308,24 @@ pub struct WiXSimpleMsiBuilder {
     /// Dimensions are 493 x 312.
     dialog_bmp: Option<PathBuf>,
 
+    /// Registry values to write.
+    registry_values: Vec<WiXRegistryValue>,
+
+    /// Environment variable modifications to perform.
+    environment_variables: Vec<WiXEnvironmentVariable>,
+
+    /// Custom actions to run.
+    custom_actions: Vec<WiXCustomAction>,
+
+    /// Properties to define.
+    properties: Vec<WiXProperty>,
+
+    /// Additional `.wxs` files to compile and link into the installer.
+    extra_wxs: Vec<WxsBuilder>,
+
+    /// `<ComponentGroup>` ids from extra `.wxs` files to install with the main feature.
+    extra_component_groups: Vec<String>,
+
     /// Signtool settings to use to auto sign binaries and the installer.
     auto_sign_signtool_settings: Option<SigntoolSign>,
 }
@@ -117,6 +381,136 @@ impl WiXSimpleMsiBuilder {
         Ok(())
     }
 
+    /// Register a registry value to be written by the installer.
+    pub fn add_registry_value(&mut self, value: WiXRegistryValue) -> Result<()> {
+        if !matches!(
+            value.root.as_str(),
+            "HKLM" | "HKCU" | "HKCR" | "HKU" | "HKMU"
+        ) {
+            return Err(anyhow!("invalid registry root: {}", value.root));
+        }
+
+        if !matches!(
+            value.value_type.as_str(),
+            "string" | "integer" | "expandable" | "multiString" | "binary"
+        ) {
+            return Err(anyhow!("invalid registry value type: {}", value.value_type));
+        }
+
+        self.registry_values.push(value);
+
+        Ok(())
+    }
+
+    /// Register an environment variable modification to be performed by the installer.
+    pub fn add_environment_variable(&mut self, variable: WiXEnvironmentVariable) -> Result<()> {
+        if !matches!(variable.action.as_str(), "set" | "create" | "remove") {
+            return Err(anyhow!(
+                "invalid environment variable action: {}",
+                variable.action
+            ));
+        }
+
+        if !matches!(variable.part.as_str(), "all" | "first" | "last") {
+            return Err(anyhow!(
+                "invalid environment variable part: {}",
+                variable.part
+            ));
+        }
+
+        self.environment_variables.push(variable);
+
+        Ok(())
+    }
+
+    /// Register a custom action to be run by the installer.
+    pub fn add_custom_action(&mut self, action: WiXCustomAction) -> Result<()> {
+        if !is_valid_identifier(&action.id) {
+            return Err(anyhow!("invalid custom action id: {}", action.id));
+        }
+
+        if self.custom_actions.iter().any(|a| a.id == action.id) {
+            return Err(anyhow!("custom action {} already defined", action.id));
+        }
+
+        if action.after.is_some() && action.before.is_some() {
+            return Err(anyhow!(
+                "custom action {} cannot define both after and before",
+                action.id
+            ));
+        }
+
+        if !matches!(
+            action.execute.as_str(),
+            "immediate"
+                | "deferred"
+                | "commit"
+                | "rollback"
+                | "oncePerProcess"
+                | "firstSequence"
+                | "secondSequence"
+        ) {
+            return Err(anyhow!(
+                "invalid custom action execute value: {}",
+                action.execute
+            ));
+        }
+
+        if !matches!(
+            action.return_type.as_str(),
+            "check" | "ignore" | "asyncWait" | "asyncNoWait"
+        ) {
+            return Err(anyhow!(
+                "invalid custom action return value: {}",
+                action.return_type
+            ));
+        }
+
+        if let WiXCustomActionKind::Script { language, .. } = &action.kind {
+            if !matches!(language.as_str(), "vbscript" | "jscript") {
+                return Err(anyhow!(
+                    "invalid custom action script language: {}",
+                    language
+                ));
+            }
+        }
+
+        self.custom_actions.push(action);
+
+        Ok(())
+    }
+
+    /// Register a property to be defined by the installer.
+    ///
+    /// Properties can be referenced by custom action commands and conditions
+    /// and set on the `msiexec` command line.
+    pub fn add_property(&mut self, property: WiXProperty) -> Result<()> {
+        if !is_valid_identifier(&property.id) {
+            return Err(anyhow!("invalid property id: {}", property.id));
+        }
+
+        if property.id == "DiskPrompt" || self.properties.iter().any(|p| p.id == property.id) {
+            return Err(anyhow!("property {} already defined", property.id));
+        }
+
+        self.properties.push(property);
+
+        Ok(())
+    }
+
+    /// Add an additional `.wxs` file to the installer.
+    ///
+    /// WiX only links fragments that are referenced. If `component_group` is
+    /// defined, the `<ComponentGroup>` having that id will be installed as part
+    /// of the main program feature.
+    pub fn add_wxs(&mut self, wxs: WxsBuilder, component_group: Option<String>) {
+        self.extra_wxs.push(wxs);
+
+        if let Some(group) = component_group {
+            self.extra_component_groups.push(group);
+        }
+    }
+
     /// Set the `<Product UpgradeCode` attribute value.
     ///
     /// If not called, a deterministic value will be derived from the product name.
@@ -126,6 +520,122 @@ impl WiXSimpleMsiBuilder {
         self
     }
 
+    /// Set the `<Product UpgradeCode` attribute value, validating it.
+    ///
+    /// The upgrade code identifies the product across versions and must never
+    /// change once an installer has shipped. Otherwise new versions are
+    /// installed side-by-side with old ones instead of replacing them.
+    pub fn set_upgrade_code(&mut self, value: &str) -> Result<()> {
+        self.upgrade_code = Some(normalize_guid(value)?);
+
+        Ok(())
+    }
+
+    /// Set how the `<Product Id` attribute value is chosen.
+    ///
+    /// Defaults to [WiXProductCode::Auto].
+    pub fn set_product_code(&mut self, value: WiXProductCode) -> Result<()> {
+        if let WiXProductCode::Fixed(guid) = &value {
+            self.product_code = WiXProductCode::Fixed(normalize_guid(guid)?);
+        } else {
+            self.product_code = value;
+        }
+
+        Ok(())
+    }
+
+    /// Set when previous versions are removed during an upgrade.
+    ///
+    /// This is the `<MajorUpgrade Schedule` attribute. One of
+    /// `afterInstallValidate`, `afterInstallInitialize` (the default),
+    /// `afterInstallExecute`, `afterInstallExecuteAgain`, or
+    /// `afterInstallFinalize`.
+    pub fn set_upgrade_schedule(&mut self, value: &str) -> Result<()> {
+        if !matches!(
+            value,
+            "afterInstallValidate"
+                | "afterInstallInitialize"
+                | "afterInstallExecute"
+                | "afterInstallExecuteAgain"
+                | "afterInstallFinalize"
+        ) {
+            return Err(anyhow!("invalid upgrade schedule: {}", value));
+        }
+
+        self.upgrade_schedule = Some(value.to_string());
+
+        Ok(())
+    }
+
+    /// Set whether installing an older version over a newer one is allowed.
+    ///
+    /// By default, downgrades are refused with an error message.
+    #[must_use]
+    pub fn allow_downgrades(mut self, value: bool) -> Self {
+        self.allow_downgrades = value;
+        self
+    }
+
+    /// Set whether installing the same version upgrades an existing install.
+    ///
+    /// Windows Installer ignores the 4th version component when comparing
+    /// versions. If enabled, installers differing only by that component
+    /// replace each other instead of installing side-by-side.
+    #[must_use]
+    pub fn allow_same_version_upgrades(mut self, value: bool) -> Self {
+        self.allow_same_version_upgrades = value;
+        self
+    }
+
+    /// Set the message displayed when a downgrade is refused.
+    #[must_use]
+    pub fn downgrade_error_message(mut self, value: String) -> Self {
+        self.downgrade_error_message = Some(value);
+        self
+    }
+
+    /// Set who the application is installed for.
+    ///
+    /// Defaults to [WiXInstallScope::PerMachine].
+    ///
+    /// Per-user installers use Windows Installer's single package authoring:
+    /// the well-known `Program Files` directories resolve to the user's
+    /// `Programs` directory, `HKMU` registry values are written to `HKCU`, and
+    /// no elevation is requested.
+    #[must_use]
+    pub fn install_scope(mut self, value: WiXInstallScope) -> Self {
+        self.install_scope = value;
+        self
+    }
+
+    /// Ensure registered content is compatible with the install scope.
+    fn validate_install_scope(&self) -> Result<()> {
+        if self.install_scope != WiXInstallScope::PerUser {
+            return Ok(());
+        }
+
+        for value in &self.registry_values {
+            if matches!(value.root.as_str(), "HKLM" | "HKCR" | "HKU") {
+                return Err(anyhow!(
+                    "registry root {} of {} cannot be written by per-user installers; use HKCU or HKMU",
+                    value.root,
+                    value.key
+                ));
+            }
+        }
+
+        for variable in &self.environment_variables {
+            if variable.system {
+                return Err(anyhow!(
+                    "environment variable {} cannot modify the system environment in per-user installers",
+                    variable.name
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
     /// Set the `<Package Keywords` attribute value.
     #[must_use]
     pub fn package_keywords(mut self, value: String) -> Self {
@@ -211,6 +721,10 @@ impl WiXSimpleMsiBuilder {
             emitter.into_inner().into_inner()?,
         ));
 
+        for wxs in &self.extra_wxs {
+            builder.add_wxs(wxs.clone());
+        }
+
         builder
             .install_files_mut()
             .add_manifest(&self.program_files_manifest)?;
@@ -290,9 +804,16 @@ impl WiXSimpleMsiBuilder {
             XmlEvent::start_element("Wix").default_ns("http://schemas.microsoft.com/wix/2006/wi"),
         )?;
 
+        validate_product_version(&self.product_version)?;
+        self.validate_install_scope()?;
+
+        let per_user = self.install_scope == WiXInstallScope::PerUser;
+
+        let product_code = self.get_product_code();
+
         writer.write(
             XmlEvent::start_element("Product")
-                .attr("Id", "*")
+                .attr("Id", &product_code)
                 .attr("Name", &self.product_name)
                 .attr("Version", &self.product_version)
                 .attr("Manufacturer", &self.product_manufacturer)
@@ -307,10 +828,18 @@ impl WiXSimpleMsiBuilder {
             .attr("InstallerVersion", &self.package_installer_version)
             .attr("Languages", &self.package_languages)
             .attr("Compressed", "yes")
-            .attr("InstallScope", "perMachine")
             .attr("SummaryCodepage", "1252")
             .attr("Platform", "$(sys.BUILDARCH)");
 
+        // Per-user installs don't set InstallScope, as that would resolve
+        // Program Files to the machine-wide directory. ALLUSERS and
+        // MSIINSTALLPERUSER below select the per-user directories instead.
+        let package = if per_user {
+            package.attr("InstallPrivileges", "limited")
+        } else {
+            package.attr("InstallScope", "perMachine")
+        };
+
         let package = if let Some(keywords) = &self.package_keywords {
             package.attr("Keywords", keywords)
         } else {
@@ -325,14 +854,31 @@ impl WiXSimpleMsiBuilder {
         writer.write(package)?;
         writer.write(XmlEvent::end_element().name("Package"))?;
 
-        writer.write(
-            XmlEvent::start_element("MajorUpgrade")
-                .attr("Schedule", "afterInstallInitialize")
-                .attr(
-                    "DowngradeErrorMessage",
+        let major_upgrade = XmlEvent::start_element("MajorUpgrade").attr(
+            "Schedule",
+            self.upgrade_schedule
+                .as_deref()
+                .unwrap_or("afterInstallInitialize"),
+        );
+
+        let major_upgrade = if self.allow_downgrades {
+            major_upgrade.attr("AllowDowngrades", "yes")
+        } else {
+            major_upgrade.attr(
+                "DowngradeErrorMessage",
+                self.downgrade_error_message.as_deref().unwrap_or(
                     "A newer version of [ProductName] is already installed. Setup will now exit.",
                 ),
-        )?;
+            )
+        };
+
+        let major_upgrade = if self.allow_same_version_upgrades {
+            major_upgrade.attr("AllowSameVersionUpgrades", "yes")
+        } else {
+            major_upgrade
+        };
+
+        writer.write(major_upgrade)?;
         writer.write(XmlEvent::end_element().name("MajorUpgrade"))?;
 
         writer.write(
@@ -351,6 +897,36 @@ impl WiXSimpleMsiBuilder {
         )?;
         writer.write(XmlEvent::end_element().name("Property"))?;
 
+        if per_user {
+            writer.write(
+                XmlEvent::start_element("Property")
+                    .attr("Id", "ALLUSERS")
+                    .attr("Value", "2"),
+            )?;
+            writer.write(XmlEvent::end_element().name("Property"))?;
+            writer.write(
+                XmlEvent::start_element("Property")
+                    .attr("Id", "MSIINSTALLPERUSER")
+                    .attr("Value", "1"),
+            )?;
+            writer.write(XmlEvent::end_element().name("Property"))?;
+        }
+
+        for property in &self.properties {
+            let element = XmlEvent::start_element("Property")
+                .attr("Id", &property.id)
+                .attr("Value", &property.value);
+
+            let element = if property.secure {
+                element.attr("Secure", "yes")
+            } else {
+                element
+            };
+
+            writer.write(element)?;
+            writer.write(XmlEvent::end_element().name("Property"))?;
+        }
+
         writer.write(
             XmlEvent::start_element("Directory")
                 .attr("Id", "TARGETDIR")
@@ -382,7 +958,7 @@ impl WiXSimpleMsiBuilder {
                 .attr("Permanent", "no")
                 .attr("Part", "last")
                 .attr("Action", "set")
-                .attr("System", "yes"),
+                .attr("System", if per_user { "no" } else { "yes" }),
         )?;
         writer.write(XmlEvent::end_element().name("Environment"))?;
         writer.write(XmlEvent::end_element().name("Component"))?;
@@ -408,6 +984,58 @@ impl WiXSimpleMsiBuilder {
             writer.write(XmlEvent::end_element().name("Component"))?;
         }
 
+        for (i, registry_value) in self.registry_values.iter().enumerate() {
+            writer.write(
+                XmlEvent::start_element("Component")
+                    .attr("Id", &format!("Registry{}", i))
+                    .attr("Guid", "*")
+                    .attr("Win64", "$(var.Win64)"),
+            )?;
+
+            let element = XmlEvent::start_element("RegistryValue")
+                .attr("Root", &registry_value.root)
+                .attr("Key", &registry_value.key)
+                .attr("Type", &registry_value.value_type)
+                .attr("Value", &registry_value.value)
+                .attr("KeyPath", "yes");
+
+            let element = if let Some(name) = &registry_value.name {
+                element.attr("Name", name)
+            } else {
+                element
+            };
+
+            writer.write(element)?;
+            writer.write(XmlEvent::end_element().name("RegistryValue"))?;
+            writer.write(XmlEvent::end_element().name("Component"))?;
+        }
+
+        if !self.environment_variables.is_empty() {
+            writer.write(
+                XmlEvent::start_element("Component")
+                    .attr("Id", "EnvironmentVariables")
+                    .attr("Guid", &self.environment_component_guid())
+                    .attr("Win64", "$(var.Win64)")
+                    .attr("KeyPath", "yes"),
+            )?;
+
+            for (i, variable) in self.environment_variables.iter().enumerate() {
+                writer.write(
+                    XmlEvent::start_element("Environment")
+                        .attr("Id", &format!("EnvironmentVariable{}", i))
+                        .attr("Name", &variable.name)
+                        .attr("Value", &variable.value)
+                        .attr("Permanent", "no")
+                        .attr("Part", &variable.part)
+                        .attr("Action", &variable.action)
+                        .attr("System", if variable.system { "yes" } else { "no" }),
+                )?;
+                writer.write(XmlEvent::end_element().name("Environment"))?;
+            }
+
+            writer.write(XmlEvent::end_element().name("Component"))?;
+        }
+
         writer.write(XmlEvent::end_element().name("Directory"))?;
         writer.write(XmlEvent::end_element().name("Directory"))?;
         writer.write(XmlEvent::end_element().name("Directory"))?;
@@ -436,13 +1064,36 @@ impl WiXSimpleMsiBuilder {
             writer.write(XmlEvent::end_element().name("ComponentRef"))?;
         }
 
+        for i in 0..self.registry_values.len() {
+            writer.write(
+                XmlEvent::start_element("ComponentRef").attr("Id", &format!("Registry{}", i)),
+            )?;
+            writer.write(XmlEvent::end_element().name("ComponentRef"))?;
+        }
+
+        if !self.environment_variables.is_empty() {
+            writer.write(
+                XmlEvent::start_element("ComponentRef").attr("Id", "EnvironmentVariables"),
+            )?;
+            writer.write(XmlEvent::end_element().name("ComponentRef"))?;
+        }
+
+        for group in &self.extra_component_groups {
+            writer.write(XmlEvent::start_element("ComponentGroupRef").attr("Id", group))?;
+            writer.write(XmlEvent::end_element().name("ComponentGroupRef"))?;
+        }
+
         writer.write(
             XmlEvent::start_element("Feature")
                 .attr("Id", "Environment")
                 .attr("Title", "PATH Environment Variable")
                 .attr(
                     "Description",
-                    "Add the install location to the PATH system environment variable",
+                    if per_user {
+                        "Add the install location to the PATH user environment variable"
+                    } else {
+                        "Add the install location to the PATH system environment variable"
+                    },
                 )
                 .attr("Level", "1")
                 .attr("Absent", "allow"),
@@ -461,6 +1112,8 @@ impl WiXSimpleMsiBuilder {
         )?;
         writer.write(XmlEvent::end_element().name("SetProperty"))?;
 
+        self.write_custom_actions_xml(writer)?;
+
         if let Some(icon_path) = &self.product_icon {
             writer.write(
                 XmlEvent::start_element("Icon")
@@ -548,6 +1201,84 @@ impl WiXSimpleMsiBuilder {
         Ok(())
     }
 
+    /// Write XML defining and scheduling custom actions.
+    fn write_custom_actions_xml<W: Write>(&self, writer: &mut EventWriter<W>) -> Result<()> {
+        if self.custom_actions.is_empty() {
+            return Ok(());
+        }
+
+        for action in &self.custom_actions {
+            let binary_id = format!("{}.Binary", action.id);
+
+            let source = match &action.kind {
+                WiXCustomActionKind::Exe { .. } => None,
+                WiXCustomActionKind::Dll { source, .. }
+                | WiXCustomActionKind::Script { source, .. } => Some(source.display().to_string()),
+            };
+
+            if let Some(source) = &source {
+                writer.write(
+                    XmlEvent::start_element("Binary")
+                        .attr("Id", &binary_id)
+                        .attr("SourceFile", source),
+                )?;
+                writer.write(XmlEvent::end_element().name("Binary"))?;
+            }
+
+            let element = XmlEvent::start_element("CustomAction")
+                .attr("Id", &action.id)
+                .attr("Execute", &action.execute)
+                .attr("Impersonate", if action.impersonate { "yes" } else { "no" })
+                .attr("Return", &action.return_type);
+
+            let element = match &action.kind {
+                WiXCustomActionKind::Exe { command } => element
+                    .attr("Directory", "APPLICATIONFOLDER")
+                    .attr("ExeCommand", command),
+                WiXCustomActionKind::Dll { entry_point, .. } => element
+                    .attr("BinaryKey", &binary_id)
+                    .attr("DllEntry", entry_point),
+                WiXCustomActionKind::Script {
+                    language, function, ..
+                } => {
+                    let element = element.attr("BinaryKey", &binary_id);
+
+                    match (language.as_str(), function) {
+                        ("vbscript", Some(function)) => element.attr("VBScriptCall", function),
+                        ("jscript", Some(function)) => element.attr("JScriptCall", function),
+                        ("vbscript", None) => element.attr("VBScriptCall", ""),
+                        _ => element.attr("JScriptCall", ""),
+                    }
+                }
+            };
+
+            writer.write(element)?;
+            writer.write(XmlEvent::end_element().name("CustomAction"))?;
+        }
+
+        writer.write(XmlEvent::start_element("InstallExecuteSequence"))?;
+
+        for action in &self.custom_actions {
+            let element = XmlEvent::start_element("Custom").attr("Action", &action.id);
+
+            let element = if let Some(before) = &action.before {
+                element.attr("Before", before)
+            } else {
+                element.attr("After", action.after.as_deref().unwrap_or("InstallFiles"))
+            };
+
+            writer.write(element)?;
+            if let Some(condition) = &action.condition {
+                writer.write(XmlEvent::Characters(condition))?;
+            }
+            writer.write(XmlEvent::end_element().name("Custom"))?;
+        }
+
+        writer.write(XmlEvent::end_element().name("InstallExecuteSequence"))?;
+
+        Ok(())
+    }
+
     fn get_upgrade_code(&self) -> Cow<'_, str> {
         if let Some(v) = &self.upgrade_code {
             Cow::Borrowed(v)
@@ -562,6 +1293,37 @@ impl WiXSimpleMsiBuilder {
         }
     }
 
+    fn get_product_code(&self) -> Cow<'_, str> {
+        match &self.product_code {
+            WiXProductCode::Auto => Cow::Borrowed("*"),
+            WiXProductCode::Fixed(v) => Cow::Borrowed(v),
+            WiXProductCode::PerVersion => Cow::Owned(
+                Uuid::new_v5(
+                    &Uuid::NAMESPACE_DNS,
+                    format!(
+                        "tugger.product_code.{}.{}",
+                        self.get_upgrade_code().to_uppercase(),
+                        self.product_version
+                    )
+                    .as_bytes(),
+                )
+                .as_hyphenated()
+                .encode_upper(&mut Uuid::encode_buffer())
+                .to_string(),
+            ),
+        }
+    }
+
+    fn environment_component_guid(&self) -> String {
+        Uuid::new_v5(
+            &Uuid::NAMESPACE_DNS,
+            format!("tugger.environment_component.{}", self.product_name).as_bytes(),
+        )
+        .as_hyphenated()
+        .encode_upper(&mut Uuid::encode_buffer())
+        .to_string()
+    }
+
     fn path_component_guid(&self) -> String {
         Uuid::new_v5(
             &Uuid::NAMESPACE_DNS,
@@ -600,6 +1362,248 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_registry_and_environment() -> Result<()> {
+        let mut builder = WiXSimpleMsiBuilder::new("prefix", "myapp", "0.1", "author");
+
+        builder.add_registry_value(WiXRegistryValue {
+            root: "HKLM".to_string(),
+            key: "Software\\myapp".to_string(),
+            name: Some("InstallDir".to_string()),
+            value: "[APPLICATIONFOLDER]".to_string(),
+            value_type: "string".to_string(),
+        })?;
+        builder.add_environment_variable(WiXEnvironmentVariable {
+            name: "MYAPP_HOME".to_string(),
+            value: "[APPLICATIONFOLDER]".to_string(),
+            action: "set".to_string(),
+            part: "all".to_string(),
+            system: true,
+        })?;
+        builder.add_wxs(
+            WxsBuilder::from_data("extra.wxs", b"<Wix/>".to_vec()),
+            Some("ExtraGroup".to_string()),
+        );
+
+        assert!(builder
+            .add_registry_value(WiXRegistryValue {
+                root: "HKBOGUS".to_string(),
+                key: "Software".to_string(),
+                name: None,
+                value: "".to_string(),
+                value_type: "string".to_string(),
+            })
+            .is_err());
+        assert!(builder
+            .add_environment_variable(WiXEnvironmentVariable {
+                name: "FOO".to_string(),
+                value: "".to_string(),
+                action: "bogus".to_string(),
+                part: "all".to_string(),
+                system: false,
+            })
+            .is_err());
+
+        let mut writer = EventWriter::new(vec![]);
+        builder.write_xml(&mut writer)?;
+        let xml = String::from_utf8(writer.into_inner())?;
+
+        assert!(xml.contains("<ComponentRef Id=\"Registry0\""));
+        assert!(xml.contains("Name=\"InstallDir\""));
+        assert!(xml.contains("<ComponentRef Id=\"EnvironmentVariables\""));
+        assert!(xml.contains("Name=\"MYAPP_HOME\""));
+        assert!(xml.contains("<ComponentGroupRef Id=\"ExtraGroup\""));
+
+        let builder = builder.to_installer_builder("x64", DEFAULT_TEMP_DIR.path())?;
+        assert!(builder
+            .wxs_files()
+            .contains_key(&PathBuf::from("extra.wxs")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_actions_and_properties() -> Result<()> {
+        let mut builder = WiXSimpleMsiBuilder::new("prefix", "myapp", "0.1", "author");
+
+        let action = WiXCustomAction {
+            id: "RegisterService".to_string(),
+            kind: WiXCustomActionKind::Exe {
+                command: "\"[APPLICATIONFOLDER]myapp.exe\" --register".to_string(),
+            },
+            after: None,
+            before: None,
+            condition: Some("NOT Installed".to_string()),
+            execute: "deferred".to_string(),
+            impersonate: false,
+            return_type: "check".to_string(),
+        };
+        builder.add_custom_action(action.clone())?;
+        builder.add_custom_action(WiXCustomAction {
+            id: "Configure".to_string(),
+            kind: WiXCustomActionKind::Dll {
+                source: PathBuf::from("helpers.dll"),
+                entry_point: "Configure".to_string(),
+            },
+            before: Some("InstallFinalize".to_string()),
+            condition: None,
+            ..action.clone()
+        })?;
+        builder.add_property(WiXProperty {
+            id: "SERVICEPORT".to_string(),
+            value: "8080".to_string(),
+            secure: true,
+        })?;
+
+        assert!(builder.add_custom_action(action.clone()).is_err());
+        assert!(builder
+            .add_custom_action(WiXCustomAction {
+                id: "Bogus".to_string(),
+                execute: "bogus".to_string(),
+                ..action.clone()
+            })
+            .is_err());
+        assert!(builder
+            .add_custom_action(WiXCustomAction {
+                id: "Both".to_string(),
+                after: Some("InstallFiles".to_string()),
+                before: Some("InstallFinalize".to_string()),
+                ..action.clone()
+            })
+            .is_err());
+        assert!(builder
+            .add_custom_action(WiXCustomAction {
+                id: "Script".to_string(),
+                kind: WiXCustomActionKind::Script {
+                    source: PathBuf::from("script.py"),
+                    language: "python".to_string(),
+                    function: None,
+                },
+                ..action
+            })
+            .is_err());
+        assert!(builder
+            .add_property(WiXProperty {
+                id: "1BAD".to_string(),
+                value: "".to_string(),
+                secure: false,
+            })
+            .is_err());
+
+        let mut writer = EventWriter::new(vec![]);
+        builder.write_xml(&mut writer)?;
+        let xml = String::from_utf8(writer.into_inner())?;
+
+        assert!(xml.contains("<Property Id=\"SERVICEPORT\" Value=\"8080\" Secure=\"yes\""));
+        assert!(xml.contains("<CustomAction Id=\"RegisterService\" Execute=\"deferred\" Impersonate=\"no\" Return=\"check\" Directory=\"APPLICATIONFOLDER\""));
+        assert!(xml.contains("<Binary Id=\"Configure.Binary\" SourceFile=\"helpers.dll\""));
+        assert!(xml.contains("BinaryKey=\"Configure.Binary\" DllEntry=\"Configure\""));
+        assert!(xml.contains(
+            "<Custom Action=\"RegisterService\" After=\"InstallFiles\">NOT Installed</Custom>"
+        ));
+        assert!(xml.contains("<Custom Action=\"Configure\" Before=\"InstallFinalize\""));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_upgrade_policy() -> Result<()> {
+        let mut builder = WiXSimpleMsiBuilder::new("prefix", "myapp", "1.2.3", "author");
+
+        let mut writer = EventWriter::new(vec![]);
+        builder.write_xml(&mut writer)?;
+        let xml = String::from_utf8(writer.into_inner())?;
+        assert!(xml.contains("<Product Id=\"*\""));
+        assert!(xml
+            .contains("<MajorUpgrade Schedule=\"afterInstallInitialize\" DowngradeErrorMessage="));
+
+        builder.set_upgrade_code("{0c1d2e3f-4a5b-6c7d-8e9f-a0b1c2d3e4f5}")?;
+        builder.set_product_code(WiXProductCode::PerVersion)?;
+        builder.set_upgrade_schedule("afterInstallExecute")?;
+        let builder = builder
+            .allow_downgrades(true)
+            .allow_same_version_upgrades(true);
+
+        assert!(builder.clone().set_upgrade_code("bogus").is_err());
+        assert!(builder.clone().set_upgrade_schedule("bogus").is_err());
+
+        let mut writer = EventWriter::new(vec![]);
+        builder.write_xml(&mut writer)?;
+        let xml = String::from_utf8(writer.into_inner())?;
+        assert!(xml.contains("UpgradeCode=\"0C1D2E3F-4A5B-6C7D-8E9F-A0B1C2D3E4F5\""));
+        assert!(!xml.contains("<Product Id=\"*\""));
+        assert!(xml.contains(
+            "<MajorUpgrade Schedule=\"afterInstallExecute\" AllowDowngrades=\"yes\" AllowSameVersionUpgrades=\"yes\""
+        ));
+
+        // The product code is stable for a given version.
+        let product_code = builder.get_product_code().to_string();
+        assert_eq!(builder.get_product_code(), product_code);
+        let other = WiXSimpleMsiBuilder {
+            product_version: "1.2.4".to_string(),
+            ..builder.clone()
+        };
+        assert_ne!(other.get_product_code(), product_code);
+
+        assert_eq!(
+            WiXProductCode::try_from("version")?,
+            WiXProductCode::PerVersion
+        );
+        assert!(WiXProductCode::try_from("bogus").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_install_scope() -> Result<()> {
+        let builder = WiXSimpleMsiBuilder::new("prefix", "myapp", "0.1", "author");
+
+        let mut writer = EventWriter::new(vec![]);
+        builder.write_xml(&mut writer)?;
+        let xml = String::from_utf8(writer.into_inner())?;
+        assert!(xml.contains("InstallScope=\"perMachine\""));
+        assert!(!xml.contains("MSIINSTALLPERUSER"));
+
+        let mut builder = builder.install_scope(WiXInstallScope::PerUser);
+
+        let mut writer = EventWriter::new(vec![]);
+        builder.write_xml(&mut writer)?;
+        let xml = String::from_utf8(writer.into_inner())?;
+        assert!(!xml.contains("InstallScope="));
+        assert!(xml.contains("InstallPrivileges=\"limited\""));
+        assert!(xml.contains("<Property Id=\"ALLUSERS\" Value=\"2\""));
+        assert!(xml.contains("<Property Id=\"MSIINSTALLPERUSER\" Value=\"1\""));
+        assert!(xml.contains("Action=\"set\" System=\"no\""));
+
+        builder.add_registry_value(WiXRegistryValue {
+            root: "HKLM".to_string(),
+            key: "Software\\myapp".to_string(),
+            name: None,
+            value: "1".to_string(),
+            value_type: "string".to_string(),
+        })?;
+        let mut writer = EventWriter::new(vec![]);
+        assert!(builder.write_xml(&mut writer).is_err());
+
+        assert_eq!(
+            WiXInstallScope::try_from("perUser")?,
+            WiXInstallScope::PerUser
+        );
+        assert!(WiXInstallScope::try_from("bogus").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_product_version() {
+        assert!(validate_product_version("1").is_ok());
+        assert!(validate_product_version("255.255.65535.65535").is_ok());
+        assert!(validate_product_version("256.0.0").is_err());
+        assert!(validate_product_version("1.2.3.4.5").is_err());
+        assert!(validate_product_version("1.2.3a").is_err());
+        assert!(validate_product_version("").is_err());
+    }
+
     #[cfg(target_family = "windows")]
     #[test]
     fn test_simple_msi_builder_build() -> Result<()> {
@@ -0,0 +1,388 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use {
+    crate::starlark::{file_content::FileContentValue, file_manifest::FileManifestValue},
+    anyhow::{anyhow, Context},
+    starlark::{
+        environment::TypeValues,
+        values::{
+            error::{RuntimeError, UnsupportedOperation, ValueError},
+            none::NoneType,
+            Mutable, TypedValue, Value, ValueResult,
+        },
+        {
+            starlark_fun, starlark_module, starlark_parse_param_type, starlark_signature,
+            starlark_signature_extraction, starlark_signatures,
+        },
+    },
+    starlark_dialect_build_targets::{
+        get_context_value, optional_str_arg, EnvironmentContext, ResolvedTarget,
+        ResolvedTargetValue, RunMode,
+    },
+    std::{
+        path::{Path, PathBuf},
+        sync::{Arc, Mutex, MutexGuard},
+    },
+    tugger_appimage::AppImageBuilder,
+};
+
+fn error_context<F, T>(label: &str, f: F) -> Result<T, ValueError>
+where
+    F: FnOnce() -> anyhow::Result<T>,
+{
+    f().map_err(|e| {
+        ValueError::Runtime(RuntimeError {
+            code: "TUGGER_APPIMAGE",
+            message: format!("{:?}", e),
+            label: label.to_string(),
+        })
+    })
+}
+
+#[derive(Clone)]
+pub struct AppImageBuilderValue {
+    inner: Arc<Mutex<AppImageBuilder>>,
+}
+
+impl TypedValue for AppImageBuilderValue {
+    type Holder = Mutable<AppImageBuilderValue>;
+    const TYPE: &'static str = "AppImageBuilder";
+
+    fn values_for_descendant_check_and_freeze(&self) -> Box<dyn Iterator<Item = Value>> {
+        Box::new(std::iter::empty())
+    }
+
+    fn get_attr(&self, attribute: &str) -> ValueResult {
+        let builder = self.inner()?;
+
+        Ok(match attribute {
+            "appimage_file_name" => Value::from(builder.appimage_file_name()),
+            _ => {
+                return Err(ValueError::OperationNotSupported {
+                    op: UnsupportedOperation::GetAttr(attribute.to_string()),
+                    left: Self::TYPE.to_string(),
+                    right: None,
+                })
+            }
+        })
+    }
+
+    fn has_attr(&self, attribute: &str) -> Result<bool, ValueError> {
+        Ok(matches!(attribute, "appimage_file_name"))
+    }
+}
+
+impl AppImageBuilderValue {
+    fn inner(&self) -> Result<MutexGuard<'_, AppImageBuilder>, ValueError> {
+        self.inner.try_lock().map_err(|e| {
+            ValueError::Runtime(RuntimeError {
+                code: "TUGGER_APPIMAGE",
+                message: format!("unable to obtain AppImage builder lock: {:?}", e),
+                label: "AppImageBuilder".to_string(),
+            })
+        })
+    }
+
+    pub fn new_from_args(name: String, architecture: String, executable: String) -> ValueResult {
+        Self::new_from_builder(AppImageBuilder::new(name, architecture, executable))
+    }
+
+    /// Construct a value from an existing [AppImageBuilder].
+    pub fn new_from_builder(builder: AppImageBuilder) -> ValueResult {
+        Ok(Value::new(Self {
+            inner: Arc::new(Mutex::new(builder)),
+        }))
+    }
+
+    pub fn add_file(&self, content: FileContentValue, path: Value) -> ValueResult {
+        const LABEL: &str = "AppImageBuilder.add_file()";
+
+        let path = optional_str_arg("path", &path)?;
+
+        let mut inner = self.inner()?;
+        let content_inner = content.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            let path = if let Some(path) = path {
+                PathBuf::from(path)
+            } else {
+                PathBuf::from(&content_inner.filename)
+            };
+
+            inner.add_file(path, content_inner.content.clone())
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_manifest(&self, manifest: FileManifestValue) -> ValueResult {
+        const LABEL: &str = "AppImageBuilder.add_manifest()";
+
+        let mut inner = self.inner()?;
+        let symlinks = manifest.symlinks(LABEL)?;
+        let metadata = manifest.file_metadata(LABEL)?;
+        let manifest = manifest.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            inner.add_manifest(&manifest)?;
+
+            for (path, target) in symlinks.iter() {
+                inner.add_symlink(path, target)?;
+            }
+            for (path, metadata) in metadata.iter() {
+                inner.set_file_metadata(path, metadata.clone())?;
+            }
+
+            Ok(())
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn set_icon(&self, content: FileContentValue) -> ValueResult {
+        const LABEL: &str = "AppImageBuilder.set_icon()";
+
+        let mut inner = self.inner()?;
+        let content_inner = content.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            let extension = Path::new(&content_inner.filename)
+                .extension()
+                .and_then(|x| x.to_str())
+                .ok_or_else(|| {
+                    anyhow!(
+                        "unable to resolve icon format from filename {}",
+                        content_inner.filename
+                    )
+                })?;
+
+            inner.set_icon(extension, content_inner.content.clone())
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn set_app_run(&self, content: FileContentValue) -> ValueResult {
+        const LABEL: &str = "AppImageBuilder.set_app_run()";
+
+        let mut inner = self.inner()?;
+        let content_inner = content.inner(LABEL)?;
+
+        inner.set_app_run(content_inner.content.clone());
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn set_desktop_entry_key(&self, key: String, value: String) -> ValueResult {
+        self.inner()?.desktop_entry_mut().set(key, value);
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn set_runtime(&self, content: FileContentValue) -> ValueResult {
+        const LABEL: &str = "AppImageBuilder.set_runtime()";
+
+        let mut inner = self.inner()?;
+        let content_inner = content.inner(LABEL)?;
+
+        inner.set_runtime(content_inner.content.clone());
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn set_update_information(&self, information: String) -> ValueResult {
+        self.inner()?.set_update_information(information);
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn write_app_dir(&self, type_values: &TypeValues, path: String) -> ValueResult {
+        const LABEL: &str = "AppImageBuilder.write_app_dir()";
+
+        let inner = self.inner()?;
+
+        let context_value = get_context_value(type_values)?;
+        let context = context_value
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let path = context.resolve_path(path);
+
+        let app_dir = error_context(LABEL, || {
+            inner
+                .materialize_appdir(&path)
+                .context("materializing AppDir")
+        })?;
+
+        Ok(Value::from(format!("{}", app_dir.display())))
+    }
+
+    pub fn write_to_directory(&self, type_values: &TypeValues, path: String) -> ValueResult {
+        const LABEL: &str = "AppImageBuilder.write_to_directory()";
+
+        let inner = self.inner()?;
+
+        let context_value = get_context_value(type_values)?;
+        let context = context_value
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let path = context.resolve_path(path);
+
+        let appimage_path = error_context(LABEL, || {
+            std::fs::create_dir_all(&path)
+                .with_context(|| format!("creating directory {}", path.display()))?;
+
+            inner.build(&path).context("building AppImage")
+        })?;
+
+        Ok(Value::from(format!("{}", appimage_path.display())))
+    }
+
+    fn build(&self, type_values: &TypeValues, target: String) -> ValueResult {
+        const LABEL: &str = "AppImageBuilder.build()";
+
+        let inner = self.inner()?;
+
+        let context_value = get_context_value(type_values)?;
+        let context = context_value
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let output_path = context.target_build_path(&target);
+
+        let appimage_path = error_context(LABEL, || {
+            std::fs::create_dir_all(&output_path)
+                .with_context(|| format!("creating directory {}", output_path.display()))?;
+
+            inner.build(&output_path).context("building AppImage")
+        })?;
+
+        Ok(Value::new(ResolvedTargetValue {
+            inner: ResolvedTarget {
+                run_mode: RunMode::Path {
+                    path: appimage_path,
+                },
+                output_path,
+            },
+        }))
+    }
+}
+
+starlark_module! { appimage_builder_module =>
+    #[allow(non_snake_case)]
+    AppImageBuilder(name: String, architecture: String, executable: String) {
+        AppImageBuilderValue::new_from_args(name, architecture, executable)
+    }
+
+    AppImageBuilder.add_file(this, file: FileContentValue, path = NoneType::None) {
+        let this = this.downcast_ref::<AppImageBuilderValue>().unwrap();
+        this.add_file(file, path)
+    }
+
+    AppImageBuilder.add_manifest(this, manifest: FileManifestValue) {
+        let this = this.downcast_ref::<AppImageBuilderValue>().unwrap();
+        this.add_manifest(manifest)
+    }
+
+    AppImageBuilder.set_icon(this, file: FileContentValue) {
+        let this = this.downcast_ref::<AppImageBuilderValue>().unwrap();
+        this.set_icon(file)
+    }
+
+    AppImageBuilder.set_app_run(this, file: FileContentValue) {
+        let this = this.downcast_ref::<AppImageBuilderValue>().unwrap();
+        this.set_app_run(file)
+    }
+
+    AppImageBuilder.set_desktop_entry_key(this, key: String, value: String) {
+        let this = this.downcast_ref::<AppImageBuilderValue>().unwrap();
+        this.set_desktop_entry_key(key, value)
+    }
+
+    AppImageBuilder.set_runtime(this, file: FileContentValue) {
+        let this = this.downcast_ref::<AppImageBuilderValue>().unwrap();
+        this.set_runtime(file)
+    }
+
+    AppImageBuilder.set_update_information(this, information: String) {
+        let this = this.downcast_ref::<AppImageBuilderValue>().unwrap();
+        this.set_update_information(information)
+    }
+
+    AppImageBuilder.write_app_dir(env env, this, path: String) {
+        let this = this.downcast_ref::<AppImageBuilderValue>().unwrap();
+        this.write_app_dir(env, path)
+    }
+
+    AppImageBuilder.write_to_directory(env env, this, path: String) {
+        let this = this.downcast_ref::<AppImageBuilderValue>().unwrap();
+        this.write_to_directory(env, path)
+    }
+
+    AppImageBuilder.build(env env, this, target: String) {
+        let this = this.downcast_ref::<AppImageBuilderValue>().unwrap();
+        this.build(env, target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::starlark::testutil::*, anyhow::Result, tugger_common::testutil::*};
+
+    #[test]
+    fn type_info() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("a = AppImageBuilder('myapp', 'x86_64', 'usr/bin/myapp')")?;
+        let raw = env.eval("a")?;
+        assert_eq!(raw.get_type(), AppImageBuilderValue::TYPE);
+
+        let v = env.eval("a.appimage_file_name")?;
+        assert_eq!(v.to_string(), "myapp-x86_64.AppImage");
+
+        Ok(())
+    }
+
+    #[test]
+    fn set_icon() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("a = AppImageBuilder('myapp', 'x86_64', 'usr/bin/myapp')")?;
+        env.eval("a.set_icon(FileContent(filename = 'icon.png', content = 'png'))")?;
+        assert!(env
+            .eval("a.set_icon(FileContent(filename = 'icon', content = 'png'))")
+            .is_err());
+        assert!(env
+            .eval("a.set_icon(FileContent(filename = 'icon.ico', content = 'ico'))")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_app_dir() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        let temp_dir_s = format!("{}", DEFAULT_TEMP_DIR.path().display()).replace('\\', "/");
+
+        env.eval("a = AppImageBuilder('myapp', 'x86_64', 'usr/bin/myapp')")?;
+        env.eval(
+            "a.add_file(FileContent(filename = 'myapp', content = 'foo'), path = 'usr/bin/myapp')",
+        )?;
+        env.eval("a.set_icon(FileContent(filename = 'icon.svg', content = '<svg/>'))")?;
+        env.eval("a.set_desktop_entry_key('Categories', 'Development;')")?;
+        let path = env.eval(&format!("a.write_app_dir('{}')", temp_dir_s))?;
+
+        assert_eq!(path.get_type(), "string");
+        let path = PathBuf::from(path.to_string());
+        assert!(path.join("AppRun").exists());
+        assert!(path.join("myapp.svg").exists());
+        assert!(std::fs::read_to_string(path.join("myapp.desktop"))?
+            .contains("Categories=Development;\n"));
+
+        Ok(())
+    }
+}
@@ -21,15 +21,16 @@ use {
             starlark_signature_extraction, starlark_signatures,
         },
     },
-    starlark_dialect_build_targets::required_type_arg,
+    starlark_dialect_build_targets::{optional_str_arg, required_type_arg, TryToOptional},
     std::{
         fmt::{Display, Formatter},
         path::{Path, PathBuf},
         sync::{Arc, Mutex},
     },
     tugger_code_signing::{
-        SignableCandidate, SignedOutput, Signer, SigningCertificate, SigningDestination,
-        SigningError,
+        apple_code_signature_flags_from_names, AppleNotarizationSettings, ApplePathSigningSettings,
+        AppleSigningCompatibility, SignableCandidate, SignedOutput, Signer, SigningCertificate,
+        SigningDestination, SigningError,
     },
 };
 
@@ -43,6 +44,11 @@ pub struct SigningSettings {
 
     /// Whether to prevent signing of this entity.
     pub prevent_signing: bool,
+
+    /// Apple code signature flags to use for this entity.
+    ///
+    /// Overrides flags defined on the [Signer].
+    pub apple_code_signature_flags: Option<Vec<String>>,
 }
 
 /// Represents a request for a [Signer] to sign something.
@@ -107,8 +113,11 @@ impl TypedValue for CodeSignerValue {
 
 impl From<SigningCertificate> for CodeSignerValue {
     fn from(cert: SigningCertificate) -> Self {
+        let mut signer = Signer::new(cert);
+        signer.event_callback(Arc::new(|event| debug!("{}", event)));
+
         Self {
-            inner: Arc::new(Mutex::new(Signer::new(cert))),
+            inner: Arc::new(Mutex::new(signer)),
             signing_callback: None,
         }
     }
@@ -210,6 +219,133 @@ impl CodeSignerValue {
         })
     }
 
+    fn set_windows_description(&self, description: String) -> ValueResult {
+        let mut signer = self.signer("set_windows_description()")?;
+
+        signer.windows_description(description);
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    fn set_apple_signing_compatibility(&self, profile: String) -> ValueResult {
+        let label = "set_apple_signing_compatibility()";
+
+        let mut signer = self.signer(label)?;
+
+        let compatibility = AppleSigningCompatibility::try_from(profile.as_str()).map_err(|e| {
+            ValueError::Runtime(RuntimeError {
+                code: "TUGGER_CODE_SIGNING",
+                message: e,
+                label: label.to_string(),
+            })
+        })?;
+
+        signer.apple_signing_compatibility(compatibility);
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    fn set_apple_code_signature_flags(&self, flags: Vec<String>) -> ValueResult {
+        let label = "set_apple_code_signature_flags()";
+
+        let mut signer = self.signer(label)?;
+
+        error_context(label, || {
+            let flags = apple_code_signature_flags_from_names(flags.iter().map(|x| x.as_str()))?;
+            signer.apple_code_signature_flags(flags);
+
+            Ok(Value::new(NoneType::None))
+        })
+    }
+
+    fn set_apple_entitlements_path(&self, path: String) -> ValueResult {
+        let label = "set_apple_entitlements_path()";
+
+        let mut signer = self.signer(label)?;
+
+        error_context(label, || {
+            let xml = std::fs::read_to_string(&path)
+                .with_context(|| format!("reading entitlements file {}", path))?;
+            signer.apple_entitlements_xml(xml);
+
+            Ok(Value::new(NoneType::None))
+        })
+    }
+
+    fn set_apple_designated_requirement_path(&self, path: String) -> ValueResult {
+        let label = "set_apple_designated_requirement_path()";
+
+        let mut signer = self.signer(label)?;
+
+        error_context(label, || {
+            let data = std::fs::read(&path)
+                .with_context(|| format!("reading designated requirement file {}", path))?;
+            signer.apple_designated_requirement(data);
+
+            Ok(Value::new(NoneType::None))
+        })
+    }
+
+    fn set_apple_notarization(
+        &self,
+        api_issuer: String,
+        api_key: String,
+        staple: bool,
+    ) -> ValueResult {
+        let mut signer = self.signer("set_apple_notarization()")?;
+
+        signer.apple_notarization(AppleNotarizationSettings {
+            api_issuer,
+            api_key,
+            staple,
+        });
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    fn add_apple_path_settings(
+        &self,
+        pattern: String,
+        code_signature_flags: Value,
+        entitlements_path: Value,
+    ) -> ValueResult {
+        let label = "add_apple_path_settings()";
+
+        let code_signature_flags: Option<Vec<String>> = code_signature_flags.try_to_optional()?;
+        let entitlements_path = optional_str_arg("entitlements_path", &entitlements_path)?;
+
+        let mut signer = self.signer(label)?;
+
+        error_context(label, || {
+            let code_signature_flags = if let Some(flags) = code_signature_flags {
+                Some(apple_code_signature_flags_from_names(
+                    flags.iter().map(|x| x.as_str()),
+                )?)
+            } else {
+                None
+            };
+
+            let entitlements_xml = if let Some(path) = entitlements_path {
+                Some(
+                    std::fs::read_to_string(&path)
+                        .with_context(|| format!("reading entitlements file {}", path))?,
+                )
+            } else {
+                None
+            };
+
+            signer.apple_path_settings(
+                &pattern,
+                ApplePathSigningSettings {
+                    code_signature_flags,
+                    entitlements_xml,
+                },
+            )?;
+
+            Ok(Value::new(NoneType::None))
+        })
+    }
+
     fn set_signing_callback(&mut self, func: Value) -> ValueResult {
         required_type_arg("func", "function", &func)?;
 
@@ -262,6 +398,14 @@ impl TypedValue for CodeSigningRequestValue {
         match attribute {
             "defer" => self.settings.defer = value.to_bool(),
             "prevent_signing" => self.settings.prevent_signing = value.to_bool(),
+            "apple_code_signature_flags" => {
+                let mut flags = vec![];
+                for v in &value.iter()? {
+                    flags.push(v.to_str());
+                }
+
+                self.settings.apple_code_signature_flags = Some(flags);
+            }
             _ => {
                 return Err(ValueError::OperationNotSupported {
                     op: UnsupportedOperation::SetAttr(attribute.to_string()),
@@ -439,7 +583,7 @@ pub fn handle_signable_event(
 
         let signer = signer_value.signer(request_context.label)?;
 
-        if let Some(signable_signer) = error_context(request_context.label, || {
+        if let Some(mut signable_signer) = error_context(request_context.label, || {
             Ok(signer.resolve_signer(request_context.candidate)?)
         })? {
             info!("CodeSigner #{} is capable of signing {}", i, request);
@@ -472,6 +616,15 @@ pub fn handle_signable_event(
                 continue;
             }
 
+            if let Some(flags) = &settings.apple_code_signature_flags {
+                let flags = error_context(request_context.label, || {
+                    Ok(apple_code_signature_flags_from_names(
+                        flags.iter().map(|x| x.as_str()),
+                    )?)
+                })?;
+                signable_signer.set_apple_code_signature_flags(flags);
+            }
+
             let destination = request_context
                 .destination
                 .unwrap_or_else(|| signable_signer.in_place_destination());
@@ -621,6 +774,51 @@ starlark_module! { code_signing_module =>
         this.set_time_stamp_server(url)
     }
 
+    CodeSigner.set_windows_description(this, description: String) {
+        let this = this.downcast_ref::<CodeSignerValue>().unwrap();
+        this.set_windows_description(description)
+    }
+
+    CodeSigner.set_apple_signing_compatibility(this, profile: String) {
+        let this = this.downcast_ref::<CodeSignerValue>().unwrap();
+        this.set_apple_signing_compatibility(profile)
+    }
+
+    CodeSigner.set_apple_code_signature_flags(this, flags: Vec<String>) {
+        let this = this.downcast_ref::<CodeSignerValue>().unwrap();
+        this.set_apple_code_signature_flags(flags)
+    }
+
+    CodeSigner.set_apple_entitlements_path(this, path: String) {
+        let this = this.downcast_ref::<CodeSignerValue>().unwrap();
+        this.set_apple_entitlements_path(path)
+    }
+
+    CodeSigner.set_apple_designated_requirement_path(this, path: String) {
+        let this = this.downcast_ref::<CodeSignerValue>().unwrap();
+        this.set_apple_designated_requirement_path(path)
+    }
+
+    CodeSigner.set_apple_notarization(
+        this,
+        api_issuer: String,
+        api_key: String,
+        staple: bool = true
+    ) {
+        let this = this.downcast_ref::<CodeSignerValue>().unwrap();
+        this.set_apple_notarization(api_issuer, api_key, staple)
+    }
+
+    CodeSigner.add_apple_path_settings(
+        this,
+        pattern: String,
+        code_signature_flags = NoneType::None,
+        entitlements_path = NoneType::None
+    ) {
+        let this = this.downcast_ref::<CodeSignerValue>().unwrap();
+        this.add_apple_path_settings(pattern, code_signature_flags, entitlements_path)
+    }
+
     CodeSigner.set_signing_callback(this, func) {
         let mut this = this.downcast_mut::<CodeSignerValue>().unwrap().unwrap();
         this.set_signing_callback(func)
@@ -826,6 +1024,134 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn set_windows_description() -> Result<()> {
+        let mut env = env_with_pfx_signer()?;
+
+        env.eval("signer.set_windows_description('My Application')")?;
+        assert!(env.eval("signer.set_windows_description()").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn set_apple_signing_compatibility() -> Result<()> {
+        let mut env = env_with_pfx_signer()?;
+
+        env.eval("signer.set_apple_signing_compatibility('legacy-sha1')")?;
+        env.eval("signer.set_apple_signing_compatibility('modern')")?;
+        assert!(env
+            .eval("signer.set_apple_signing_compatibility('bogus')")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn set_apple_code_signature_flags() -> Result<()> {
+        let mut env = env_with_pfx_signer()?;
+
+        env.eval("signer.set_apple_code_signature_flags(['runtime', 'library-validation'])")?;
+        assert!(env
+            .eval("signer.set_apple_code_signature_flags(['bogus'])")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn set_apple_entitlements_path() -> Result<()> {
+        let mut env = env_with_pfx_signer()?;
+
+        let entitlements_path = DEFAULT_TEMP_DIR
+            .path()
+            .join("set_apple_entitlements_path.entitlements");
+        let entitlements_path_str = format!("{}", entitlements_path.display()).replace('\\', "/");
+        std::fs::write(&entitlements_path, "<plist/>")?;
+
+        env.eval(&format!(
+            "signer.set_apple_entitlements_path('{}')",
+            entitlements_path_str
+        ))?;
+        assert!(env
+            .eval("signer.set_apple_entitlements_path('/does/not/exist')")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn set_apple_designated_requirement_path() -> Result<()> {
+        let mut env = env_with_pfx_signer()?;
+
+        assert!(env
+            .eval("signer.set_apple_designated_requirement_path('/does/not/exist')")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn set_apple_notarization() -> Result<()> {
+        let mut env = env_with_pfx_signer()?;
+
+        env.eval("signer.set_apple_notarization('issuer', 'key')")?;
+        env.eval("signer.set_apple_notarization('issuer', 'key', staple = False)")?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn add_apple_path_settings() -> Result<()> {
+        let mut env = env_with_pfx_signer()?;
+
+        let entitlements_path = DEFAULT_TEMP_DIR
+            .path()
+            .join("add_apple_path_settings.entitlements");
+        let entitlements_path_str = format!("{}", entitlements_path.display()).replace('\\', "/");
+        std::fs::write(&entitlements_path, "<plist/>")?;
+
+        env.eval("signer.add_apple_path_settings('Contents/Helpers/*.app', code_signature_flags = ['runtime'])")?;
+        env.eval(&format!(
+            "signer.add_apple_path_settings('Contents/MacOS/helper', entitlements_path = '{}')",
+            entitlements_path_str
+        ))?;
+        assert!(env
+            .eval("signer.add_apple_path_settings('[', code_signature_flags = ['runtime'])")
+            .is_err());
+        assert!(env
+            .eval("signer.add_apple_path_settings('*', code_signature_flags = ['bogus'])")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn callback_apple_code_signature_flags() -> Result<()> {
+        let mut env = env_with_pfx_signer()?;
+
+        env.eval("def callback(request):\n    request.apple_code_signature_flags = ['runtime']\n")?;
+        env.eval("signer.set_signing_callback(callback)")?;
+        env.eval("signer.activate()")?;
+        env.eval("SIGNING_EVENT.run()")?;
+
+        let event_value = env.eval("SIGNING_EVENT")?;
+        let event = event_value.downcast_ref::<TestSigningEventValue>().unwrap();
+        let response = event.response.as_ref().unwrap();
+        assert_eq!(response.signed_index, Some(0));
+
+        let mut env = env_with_pfx_signer()?;
+
+        env.eval(
+            "def bad_callback(request):\n    request.apple_code_signature_flags = ['bogus']\n",
+        )?;
+        env.eval("signer.set_signing_callback(bad_callback)")?;
+        env.eval("signer.activate()")?;
+        assert!(env.eval("SIGNING_EVENT.run()").is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn set_signing_callback() -> Result<()> {
         let mut env = env_with_pfx_signer()?;
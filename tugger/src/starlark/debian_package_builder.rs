@@ -0,0 +1,523 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use {
+    crate::starlark::{
+        file_content::{FileContentValue, FileContentWrapper},
+        file_manifest::FileManifestValue,
+    },
+    anyhow::Context,
+    log::warn,
+    starlark::{
+        environment::TypeValues,
+        values::{
+            error::{RuntimeError, UnsupportedOperation, ValueError},
+            none::NoneType,
+            Mutable, TypedValue, Value, ValueResult,
+        },
+        {
+            starlark_fun, starlark_module, starlark_parse_param_type, starlark_signature,
+            starlark_signature_extraction, starlark_signatures,
+        },
+    },
+    starlark_dialect_build_targets::{
+        get_context_value, optional_list_arg, optional_str_arg, EnvironmentContext, ResolvedTarget,
+        ResolvedTargetValue, RunMode,
+    },
+    std::{
+        path::PathBuf,
+        sync::{Arc, Mutex, MutexGuard},
+    },
+    tugger_debian::{ControlParagraph, DebBuilder, ShlibsDatabase},
+};
+
+fn error_context<F, T>(label: &str, f: F) -> Result<T, ValueError>
+where
+    F: FnOnce() -> anyhow::Result<T>,
+{
+    f().map_err(|e| {
+        ValueError::Runtime(RuntimeError {
+            code: "TUGGER_DEBIAN",
+            message: format!("{:?}", e),
+            label: label.to_string(),
+        })
+    })
+}
+
+#[derive(Clone)]
+pub struct DebianPackageBuilderValue {
+    inner: Arc<Mutex<DebBuilder>>,
+}
+
+impl TypedValue for DebianPackageBuilderValue {
+    type Holder = Mutable<DebianPackageBuilderValue>;
+    const TYPE: &'static str = "DebianPackageBuilder";
+
+    fn values_for_descendant_check_and_freeze(&self) -> Box<dyn Iterator<Item = Value>> {
+        Box::new(std::iter::empty())
+    }
+
+    fn get_attr(&self, attribute: &str) -> ValueResult {
+        let builder = self.inner()?;
+
+        Ok(match attribute {
+            "deb_file_name" => {
+                Value::from(error_context("DebianPackageBuilder.deb_file_name", || {
+                    builder.deb_file_name()
+                })?)
+            }
+            _ => {
+                return Err(ValueError::OperationNotSupported {
+                    op: UnsupportedOperation::GetAttr(attribute.to_string()),
+                    left: Self::TYPE.to_string(),
+                    right: None,
+                })
+            }
+        })
+    }
+
+    fn has_attr(&self, attribute: &str) -> Result<bool, ValueError> {
+        Ok(matches!(attribute, "deb_file_name"))
+    }
+}
+
+impl DebianPackageBuilderValue {
+    fn inner(&self) -> Result<MutexGuard<'_, DebBuilder>, ValueError> {
+        self.inner.try_lock().map_err(|e| {
+            ValueError::Runtime(RuntimeError {
+                code: "TUGGER_DEBIAN",
+                message: format!("unable to obtain Debian package builder lock: {:?}", e),
+                label: "DebianPackageBuilder".to_string(),
+            })
+        })
+    }
+
+    pub fn new_from_args(
+        package: String,
+        version: String,
+        architecture: String,
+        maintainer: String,
+        description: String,
+    ) -> ValueResult {
+        let mut control = ControlParagraph::default();
+        control.set_field("Package", package);
+        control.set_field("Version", version);
+        control.set_field("Architecture", architecture);
+        control.set_field("Maintainer", maintainer);
+        control.set_field("Description", description);
+
+        Self::new_from_builder(DebBuilder::new(control))
+    }
+
+    /// Construct a value from an existing [DebBuilder].
+    pub fn new_from_builder(builder: DebBuilder) -> ValueResult {
+        Ok(Value::new(Self {
+            inner: Arc::new(Mutex::new(builder)),
+        }))
+    }
+
+    pub fn set_control_field(&self, name: String, value: String) -> ValueResult {
+        self.inner()?.control_mut().set_field(name, value);
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_file(&self, content: FileContentValue, path: Value) -> ValueResult {
+        const LABEL: &str = "DebianPackageBuilder.add_file()";
+
+        let path = optional_str_arg("path", &path)?;
+
+        let mut inner = self.inner()?;
+        let content_inner = content.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            let path = if let Some(path) = path {
+                PathBuf::from(path)
+            } else {
+                PathBuf::from(&content_inner.filename)
+            };
+
+            inner.install_file(path, content_inner.content.clone())
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_manifest(&self, manifest: FileManifestValue) -> ValueResult {
+        const LABEL: &str = "DebianPackageBuilder.add_manifest()";
+
+        let mut inner = self.inner()?;
+        let symlinks = manifest.symlinks(LABEL)?;
+        let metadata = manifest.file_metadata(LABEL)?;
+        let manifest = manifest.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            inner.install_manifest(&manifest)?;
+
+            for (path, target) in symlinks.iter() {
+                inner.install_symlink(path, target)?;
+            }
+            for (path, metadata) in metadata.iter() {
+                inner.set_file_metadata(path, metadata.clone())?;
+            }
+
+            Ok(())
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_conffile(&self, path: String) -> ValueResult {
+        self.inner()?.add_conffile(path);
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn set_maintainer_script(&self, name: String, content: String) -> ValueResult {
+        const LABEL: &str = "DebianPackageBuilder.set_maintainer_script()";
+
+        let mut inner = self.inner()?;
+
+        error_context(LABEL, || inner.set_maintainer_script(&name, content))?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_trigger(&self, directive: String, name: String) -> ValueResult {
+        const LABEL: &str = "DebianPackageBuilder.add_trigger()";
+
+        let mut inner = self.inner()?;
+
+        error_context(LABEL, || inner.add_trigger(&directive, &name))?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_shlibs_depends(
+        &self,
+        type_values: &TypeValues,
+        shlibs_files: &Value,
+        system: bool,
+    ) -> ValueResult {
+        const LABEL: &str = "DebianPackageBuilder.add_shlibs_depends()";
+
+        optional_list_arg("shlibs_files", "string", shlibs_files)?;
+
+        let context_value = get_context_value(type_values)?;
+        let context = context_value
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let shlibs_files = match shlibs_files.get_type() {
+            "list" => shlibs_files
+                .iter()?
+                .iter()
+                .map(|x| context.resolve_path(x.to_string()))
+                .collect::<Vec<_>>(),
+            _ => vec![],
+        };
+
+        let mut inner = self.inner()?;
+
+        error_context(LABEL, || {
+            let mut shlibs = ShlibsDatabase::default();
+
+            // Explicit files take precedence over the system database.
+            for path in shlibs_files {
+                shlibs.add_shlibs_file(path)?;
+            }
+
+            if system {
+                shlibs
+                    .add_system_shlibs()
+                    .context("loading system shlibs database")?;
+            }
+
+            inner.add_shlibs_depends(&shlibs)
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn to_file_content(&self) -> ValueResult {
+        const LABEL: &str = "DebianPackageBuilder.to_file_content()";
+
+        let inner = self.inner()?;
+
+        let (data, filename) = error_context(LABEL, || {
+            let mut data = vec![];
+            inner.write_deb(&mut data).context("writing .deb")?;
+
+            Ok((data, inner.deb_file_name()?))
+        })?;
+
+        Ok(FileContentWrapper {
+            content: data.into(),
+            filename,
+        }
+        .into())
+    }
+
+    pub fn write_to_directory(&self, type_values: &TypeValues, path: String) -> ValueResult {
+        const LABEL: &str = "DebianPackageBuilder.write_to_directory()";
+
+        let inner = self.inner()?;
+
+        let context_value = get_context_value(type_values)?;
+        let context = context_value
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let path = context.resolve_path(path);
+
+        let deb_path = error_context(LABEL, || {
+            std::fs::create_dir_all(&path)
+                .with_context(|| format!("creating directory {}", path.display()))?;
+
+            inner
+                .write_deb_into_directory(&path)
+                .context("writing .deb to directory")
+        })?;
+
+        Ok(Value::from(format!("{}", deb_path.display())))
+    }
+
+    fn build(&self, type_values: &TypeValues, target: String) -> ValueResult {
+        const LABEL: &str = "DebianPackageBuilder.build()";
+
+        let inner = self.inner()?;
+
+        let context_value = get_context_value(type_values)?;
+        let context = context_value
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let output_directory = context.target_build_path(&target);
+
+        let deb_path = error_context(LABEL, || {
+            warn!("writing .deb to {}", output_directory.display());
+
+            std::fs::create_dir_all(&output_directory)
+                .with_context(|| format!("creating directory {}", output_directory.display()))?;
+
+            inner
+                .write_deb_into_directory(&output_directory)
+                .context("writing .deb to directory")
+        })?;
+
+        warn!("wrote {}", deb_path.display());
+
+        Ok(Value::new(ResolvedTargetValue {
+            inner: ResolvedTarget {
+                run_mode: RunMode::None,
+                output_path: output_directory,
+            },
+        }))
+    }
+}
+
+starlark_module! { debian_package_builder_module =>
+    #[allow(non_snake_case)]
+    DebianPackageBuilder(
+        package: String,
+        version: String,
+        architecture: String,
+        maintainer: String,
+        description: String
+    ) {
+        DebianPackageBuilderValue::new_from_args(
+            package,
+            version,
+            architecture,
+            maintainer,
+            description,
+        )
+    }
+
+    DebianPackageBuilder.set_control_field(this, name: String, value: String) {
+        let this = this.downcast_ref::<DebianPackageBuilderValue>().unwrap();
+        this.set_control_field(name, value)
+    }
+
+    DebianPackageBuilder.add_file(this, file: FileContentValue, path = NoneType::None) {
+        let this = this.downcast_ref::<DebianPackageBuilderValue>().unwrap();
+        this.add_file(file, path)
+    }
+
+    DebianPackageBuilder.add_manifest(this, manifest: FileManifestValue) {
+        let this = this.downcast_ref::<DebianPackageBuilderValue>().unwrap();
+        this.add_manifest(manifest)
+    }
+
+    DebianPackageBuilder.add_conffile(this, path: String) {
+        let this = this.downcast_ref::<DebianPackageBuilderValue>().unwrap();
+        this.add_conffile(path)
+    }
+
+    DebianPackageBuilder.set_maintainer_script(this, name: String, content: String) {
+        let this = this.downcast_ref::<DebianPackageBuilderValue>().unwrap();
+        this.set_maintainer_script(name, content)
+    }
+
+    DebianPackageBuilder.add_trigger(this, directive: String, name: String) {
+        let this = this.downcast_ref::<DebianPackageBuilderValue>().unwrap();
+        this.add_trigger(directive, name)
+    }
+
+    DebianPackageBuilder.add_shlibs_depends(
+        env env,
+        this,
+        shlibs_files = NoneType::None,
+        system: bool = true
+    ) {
+        let this = this.downcast_ref::<DebianPackageBuilderValue>().unwrap();
+        this.add_shlibs_depends(env, &shlibs_files, system)
+    }
+
+    DebianPackageBuilder.to_file_content(this) {
+        let this = this.downcast_ref::<DebianPackageBuilderValue>().unwrap();
+        this.to_file_content()
+    }
+
+    DebianPackageBuilder.write_to_directory(env env, this, path: String) {
+        let this = this.downcast_ref::<DebianPackageBuilderValue>().unwrap();
+        this.write_to_directory(env, path)
+    }
+
+    DebianPackageBuilder.build(env env, this, target: String) {
+        let this = this.downcast_ref::<DebianPackageBuilderValue>().unwrap();
+        this.build(env, target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::starlark::testutil::*, anyhow::Result, tugger_common::testutil::*};
+
+    const NEW_BUILDER: &str =
+        "d = DebianPackageBuilder('myapp', '1.0-1', 'amd64', 'Jane Doe <jane@example.com>', 'My app')";
+
+    #[test]
+    fn type_info() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval(NEW_BUILDER)?;
+        let raw = env.eval("d")?;
+        assert_eq!(raw.get_type(), DebianPackageBuilderValue::TYPE);
+
+        let v = env.eval("d.deb_file_name")?;
+        assert_eq!(v.to_string(), "myapp_1.0-1_amd64.deb");
+
+        Ok(())
+    }
+
+    #[test]
+    fn control_fields() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval(NEW_BUILDER)?;
+        env.eval("d.set_control_field('Version', '2.0-1')")?;
+        env.eval("d.set_control_field('Depends', 'libc6')")?;
+
+        let raw = env.eval("d")?;
+        let value = raw.downcast_ref::<DebianPackageBuilderValue>().unwrap();
+        let inner = value.inner().unwrap();
+        assert_eq!(inner.control().field("Version"), Some("2.0-1"));
+        assert_eq!(inner.control().field("Depends"), Some("libc6"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn set_maintainer_script() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval(NEW_BUILDER)?;
+        env.eval("d.set_maintainer_script('postinst', '#!/bin/sh\\nexit 0\\n')")?;
+        assert!(env
+            .eval("d.set_maintainer_script('config', '#!/bin/sh\\n')")
+            .is_err());
+        assert!(env
+            .eval("d.set_maintainer_script('prerm', 'exit 0\\n')")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn add_trigger() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval(NEW_BUILDER)?;
+        env.eval("d.add_trigger('interest-noawait', '/usr/share/icons')")?;
+        assert!(env.eval("d.add_trigger('bogus', 'foo')").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn add_shlibs_depends() -> Result<()> {
+        let shlibs_path = DEFAULT_TEMP_DIR.path().join("debian-shlibs");
+        std::fs::write(&shlibs_path, "libc 6 libc6 (>= 2.34)\n")?;
+        let shlibs_path_s = format!("{}", shlibs_path.display()).replace('\\', "/");
+
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval(NEW_BUILDER)?;
+        env.eval("d.set_control_field('Depends', 'python3')")?;
+        env.eval(&format!(
+            "d.add_shlibs_depends(shlibs_files = ['{}'], system = False)",
+            shlibs_path_s
+        ))?;
+        assert!(env
+            .eval("d.add_shlibs_depends(shlibs_files = 'foo')")
+            .is_err());
+        assert!(env
+            .eval("d.add_shlibs_depends(shlibs_files = ['/does/not/exist'], system = False)")
+            .is_err());
+
+        let raw = env.eval("d")?;
+        let value = raw.downcast_ref::<DebianPackageBuilderValue>().unwrap();
+        let inner = value.inner().unwrap();
+        assert_eq!(inner.control().field("Depends"), Some("python3"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn to_file_content() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval(NEW_BUILDER)?;
+        env.eval("m = FileManifest()")?;
+        env.eval("m.add_file(FileContent(filename = 'myapp.conf', content = 'a = 1'), directory = 'etc')")?;
+        env.eval("d.add_manifest(m)")?;
+        env.eval("d.add_conffile('/etc/myapp.conf')")?;
+
+        let f = env.eval("d.to_file_content()")?;
+        assert_eq!(f.get_type(), "FileContent");
+        let value = f.downcast_ref::<FileContentValue>().unwrap();
+        let inner = value.inner("ignored").unwrap();
+        assert_eq!(inner.filename, "myapp_1.0-1_amd64.deb");
+        assert!(inner.content.resolve_content()?.starts_with(b"!<arch>\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_to_directory() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        let temp_dir_s = format!("{}", DEFAULT_TEMP_DIR.path().display()).replace('\\', "/");
+
+        env.eval(NEW_BUILDER)?;
+        let path = env.eval(&format!("d.write_to_directory('{}')", temp_dir_s))?;
+
+        assert_eq!(path.get_type(), "string");
+        let path = PathBuf::from(path.to_string());
+        assert!(path.exists());
+
+        Ok(())
+    }
+}
@@ -3,11 +3,14 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use {
-    crate::starlark::{
-        code_signing::{handle_signable_event, SigningAction, SigningContext},
-        file_content::{FileContentValue, FileContentWrapper},
+    crate::{
+        starlark::{
+            code_signing::{handle_signable_event, SigningAction, SigningContext},
+            file_content::{FileContentValue, FileContentWrapper},
+        },
+        tarball::write_tarball_from_directory,
     },
-    anyhow::anyhow,
+    anyhow::{anyhow, Context},
     log::warn,
     simple_file_manifest::FileManifest,
     starlark::{
@@ -24,14 +27,17 @@ use {
         },
     },
     starlark_dialect_build_targets::{
-        get_context_value, optional_str_arg, EnvironmentContext, ResolvedTarget,
+        get_context_value, optional_int_arg, optional_str_arg, EnvironmentContext, ResolvedTarget,
         ResolvedTargetValue, RunMode,
     },
     std::{
-        path::{Path, PathBuf},
+        collections::BTreeMap,
+        io::Write,
+        path::{Component, Path, PathBuf},
         sync::{Arc, Mutex, MutexGuard},
     },
     tugger_code_signing::SigningDestination,
+    tugger_common::file_metadata::FileMetadata,
 };
 
 fn error_context<F, T>(label: &str, f: F) -> Result<T, ValueError>
@@ -76,9 +82,72 @@ fn post_materialize_signing_checks(
     Ok(())
 }
 
+/// Create a symlink at `link` pointing to `target`.
+///
+/// An existing file at `link` is replaced.
+fn create_symlink(target: &Path, link: &Path) -> anyhow::Result<()> {
+    if let Some(parent) = link.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("creating {}", parent.display()))?;
+    }
+
+    if link.symlink_metadata().is_ok() {
+        std::fs::remove_file(link).with_context(|| format!("removing {}", link.display()))?;
+    }
+
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(target, link)
+            .with_context(|| format!("creating symlink {}", link.display()))
+    }
+
+    #[cfg(not(unix))]
+    {
+        Err(anyhow!(
+            "unable to create symlink {} to {}: symlinks are only supported on Unix",
+            link.display(),
+            target.display()
+        ))
+    }
+}
+
+/// Create symlinks relative to a destination directory.
+///
+/// Keys of `symlinks` are link paths relative to `dest_path` and values are
+/// link targets, which are written verbatim. Existing files at link paths
+/// are replaced.
+pub fn materialize_symlinks(
+    dest_path: &Path,
+    symlinks: &BTreeMap<PathBuf, PathBuf>,
+) -> anyhow::Result<()> {
+    for (path, target) in symlinks {
+        create_symlink(target, &dest_path.join(path))?;
+    }
+
+    Ok(())
+}
+
+/// Apply file metadata to materialized files.
+///
+/// Keys of `metadata` are file paths relative to `dest_path`.
+pub fn apply_file_metadata(
+    dest_path: &Path,
+    metadata: &BTreeMap<PathBuf, FileMetadata>,
+) -> anyhow::Result<()> {
+    for (path, metadata) in metadata {
+        metadata.apply_to_path(&dest_path.join(path))?;
+    }
+
+    Ok(())
+}
+
 #[derive(Clone, Debug)]
 pub struct FileManifestValue {
     inner: Arc<Mutex<FileManifest>>,
+    /// Symlinks to install, keyed by link path.
+    symlinks: Arc<Mutex<BTreeMap<PathBuf, PathBuf>>>,
+    /// Metadata of files, keyed by path.
+    metadata: Arc<Mutex<BTreeMap<PathBuf, FileMetadata>>>,
     /// Optional path to be the default run target.
     pub run_path: Option<PathBuf>,
 }
@@ -102,6 +171,8 @@ impl FileManifestValue {
     pub fn new_from_manifest(manifest: FileManifest) -> ValueResult {
         Ok(Value::new(Self {
             inner: Arc::new(Mutex::new(manifest)),
+            symlinks: Arc::new(Mutex::new(BTreeMap::new())),
+            metadata: Arc::new(Mutex::new(BTreeMap::new())),
             run_path: None,
         }))
     }
@@ -116,6 +187,64 @@ impl FileManifestValue {
         })
     }
 
+    /// Obtain symlinks in this manifest.
+    ///
+    /// Keys are the paths of links and values are their targets.
+    pub fn symlinks(
+        &self,
+        label: &str,
+    ) -> Result<MutexGuard<BTreeMap<PathBuf, PathBuf>>, ValueError> {
+        self.symlinks.try_lock().map_err(|e| {
+            ValueError::Runtime(RuntimeError {
+                code: "SIMPLE_FILE_MANIFEST",
+                message: format!("error obtaining lock: {}", e),
+                label: label.to_string(),
+            })
+        })
+    }
+
+    /// Obtain metadata of files in this manifest, keyed by path.
+    pub fn file_metadata(
+        &self,
+        label: &str,
+    ) -> Result<MutexGuard<BTreeMap<PathBuf, FileMetadata>>, ValueError> {
+        self.metadata.try_lock().map_err(|e| {
+            ValueError::Runtime(RuntimeError {
+                code: "SIMPLE_FILE_MANIFEST",
+                message: format!("error obtaining lock: {}", e),
+                label: label.to_string(),
+            })
+        })
+    }
+
+    /// Write an uncompressed tarball of this manifest's files and symlinks.
+    ///
+    /// Symlinks are archived as symlinks. Archive paths are relative to
+    /// `archive_prefix` if set.
+    pub fn write_tarball(
+        &self,
+        label: &str,
+        writer: &mut impl Write,
+        archive_prefix: Option<&Path>,
+    ) -> Result<(), ValueError> {
+        let inner = self.inner(label)?;
+        let symlinks = self.symlinks(label)?;
+        let metadata = self.file_metadata(label)?;
+
+        error_context(label, || {
+            let temp_dir = tempfile::Builder::new()
+                .prefix("tugger-file-manifest-")
+                .tempdir()?;
+            let dest_path = temp_dir.path();
+
+            inner.materialize_files(dest_path)?;
+            apply_file_metadata(dest_path, &metadata)?;
+            materialize_symlinks(dest_path, &symlinks)?;
+
+            write_tarball_from_directory(writer, dest_path, archive_prefix)
+        })
+    }
+
     fn build(
         &self,
         type_values: &TypeValues,
@@ -132,12 +261,16 @@ impl FileManifestValue {
         let output_path = context.target_build_path(&target);
 
         let inner = self.inner(LABEL)?;
+        let symlinks = self.symlinks(LABEL)?;
+        let metadata = self.file_metadata(LABEL)?;
 
         let installed_paths = error_context(LABEL, || {
             warn!("installing files to {}", output_path.display());
-            inner
-                .materialize_files_with_replace(&output_path)
-                .map_err(anyhow::Error::new)
+            let installed_paths = inner.materialize_files_with_replace(&output_path)?;
+            apply_file_metadata(&output_path, &metadata)?;
+            materialize_symlinks(&output_path, &symlinks)?;
+
+            Ok(installed_paths)
         })?;
 
         post_materialize_signing_checks(
@@ -184,9 +317,50 @@ impl FileManifestValue {
 
         let mut inner = self.inner(LABEL)?;
         let other_inner = other.inner(LABEL)?;
+        let mut symlinks = self.symlinks(LABEL)?;
+        let other_symlinks = other.symlinks(LABEL)?;
+        let mut metadata = self.file_metadata(LABEL)?;
+        let other_metadata = other.file_metadata(LABEL)?;
 
         error_context(LABEL, || {
-            inner.add_manifest(&other_inner).map_err(anyhow::Error::new)
+            for (path, target) in other_symlinks.iter() {
+                if inner.has_path(path) {
+                    return Err(anyhow!(
+                        "cannot add symlink {}: a file exists at that path",
+                        path.display()
+                    ));
+                }
+                if let Some(existing) = symlinks.get(path) {
+                    if existing != target {
+                        return Err(anyhow!(
+                            "symlink {} already exists with target {}",
+                            path.display(),
+                            existing.display()
+                        ));
+                    }
+                }
+            }
+
+            for (path, _) in other_inner.iter_entries() {
+                if symlinks.contains_key(path) {
+                    return Err(anyhow!(
+                        "cannot add file {}: a symlink exists at that path",
+                        path.display()
+                    ));
+                }
+            }
+
+            inner.add_manifest(&other_inner)?;
+            symlinks.extend(
+                other_symlinks
+                    .iter()
+                    .map(|(path, target)| (path.clone(), target.clone())),
+            );
+            for (path, other) in other_metadata.iter() {
+                metadata.entry(path.clone()).or_default().merge(other);
+            }
+
+            Ok(())
         })?;
 
         Ok(Value::new(NoneType::None))
@@ -205,6 +379,7 @@ impl FileManifestValue {
         let directory = optional_str_arg("directory", &directory)?;
 
         let mut inner = self.inner(LABEL)?;
+        let symlinks = self.symlinks(LABEL)?;
         let content_inner = content.inner(LABEL)?;
 
         error_context(LABEL, || {
@@ -222,6 +397,13 @@ impl FileManifestValue {
                 PathBuf::from(&content_inner.filename)
             };
 
+            if symlinks.contains_key(&path) {
+                return Err(anyhow!(
+                    "cannot add file {}: a symlink exists at that path",
+                    path.display()
+                ));
+            }
+
             inner.add_file_entry(path, content_inner.content.clone())?;
 
             Ok(())
@@ -256,6 +438,101 @@ impl FileManifestValue {
         Ok(Value::new(NoneType::None))
     }
 
+    /// FileManifest.add_symlink(path, target)
+    pub fn add_symlink(&mut self, path: String, target: String) -> ValueResult {
+        const LABEL: &str = "FileManifest.add_symlink()";
+
+        let inner = self.inner(LABEL)?;
+        let mut symlinks = self.symlinks(LABEL)?;
+
+        error_context(LABEL, || {
+            let path = PathBuf::from(path);
+
+            if path.is_absolute() {
+                return Err(anyhow!("symlink path must be relative: {}", path.display()));
+            }
+            if path.components().any(|c| c == Component::ParentDir) {
+                return Err(anyhow!(
+                    "symlink path cannot contain '..': {}",
+                    path.display()
+                ));
+            }
+            if inner.has_path(&path) {
+                return Err(anyhow!(
+                    "cannot add symlink {}: a file exists at that path",
+                    path.display()
+                ));
+            }
+
+            symlinks.insert(path, PathBuf::from(target));
+
+            Ok(())
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// FileManifest.set_metadata(path, mode=None, uid=None, gid=None, user=None, group=None, mtime=None)
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_metadata(
+        &mut self,
+        path: String,
+        mode: Value,
+        uid: Value,
+        gid: Value,
+        user: Value,
+        group: Value,
+        mtime: Value,
+    ) -> ValueResult {
+        const LABEL: &str = "FileManifest.set_metadata()";
+
+        let mode = optional_int_arg("mode", &mode)?;
+        let uid = optional_int_arg("uid", &uid)?;
+        let gid = optional_int_arg("gid", &gid)?;
+        let user = optional_str_arg("user", &user)?;
+        let group = optional_str_arg("group", &group)?;
+        let mtime = optional_int_arg("mtime", &mtime)?;
+
+        let inner = self.inner(LABEL)?;
+        let mut metadata = self.file_metadata(LABEL)?;
+
+        error_context(LABEL, || {
+            let path = PathBuf::from(path);
+
+            if !inner.has_path(&path) {
+                return Err(anyhow!("{} is not in the manifest", path.display()));
+            }
+
+            let new = FileMetadata {
+                mode: mode
+                    .map(u32::try_from)
+                    .transpose()
+                    .context("resolving mode")?,
+                uid: uid
+                    .map(u64::try_from)
+                    .transpose()
+                    .context("resolving uid")?,
+                gid: gid
+                    .map(u64::try_from)
+                    .transpose()
+                    .context("resolving gid")?,
+                user,
+                group,
+                mtime: mtime
+                    .map(u64::try_from)
+                    .transpose()
+                    .context("resolving mtime")?,
+            };
+            new.validate()?;
+
+            metadata.entry(path).or_default().merge(&new);
+
+            Ok(())
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
     /// FileManifest.get_file(path) -> FileContent
     pub fn get_file(&self, path: String) -> ValueResult {
         const LABEL: &str = "FileManifest.get_file()";
@@ -302,16 +579,21 @@ impl FileManifestValue {
             .ok_or(ValueError::IncorrectParameterType)?;
 
         let inner = self.inner(LABEL)?;
+        let symlinks = self.symlinks(LABEL)?;
+        let metadata = self.file_metadata(LABEL)?;
 
         let installed_paths = error_context(LABEL, || {
             let dest_path = context.build_path().join(path);
 
-            if replace {
+            let installed_paths = if replace {
                 inner.materialize_files_with_replace(&dest_path)
             } else {
                 inner.materialize_files(&dest_path)
-            }
-            .map_err(anyhow::Error::new)
+            }?;
+            apply_file_metadata(&dest_path, &metadata)?;
+            materialize_symlinks(&dest_path, &symlinks)?;
+
+            Ok(installed_paths)
         })?;
 
         post_materialize_signing_checks(
@@ -356,6 +638,8 @@ impl FileManifestValue {
         })?;
 
         let mut inner = self.inner(LABEL)?;
+        self.symlinks(LABEL)?.remove(&path);
+        self.file_metadata(LABEL)?.remove(&path);
 
         if let Some(entry) = inner.remove(path) {
             Ok(FileContentWrapper {
@@ -395,6 +679,11 @@ starlark_module! { file_manifest_module =>
         this.add_path(path, strip_prefix, force_read)
     }
 
+    FileManifest.add_symlink(this, path: String, target: String) {
+        let mut this = this.downcast_mut::<FileManifestValue>().unwrap().unwrap();
+        this.add_symlink(path, target)
+    }
+
     FileManifest.build(env env, call_stack cs, this, target: String) {
         let this = this.downcast_ref::<FileManifestValue>().unwrap();
         this.build(env, cs, target)
@@ -410,6 +699,20 @@ starlark_module! { file_manifest_module =>
         this.install(env, cs, path, replace)
     }
 
+    FileManifest.set_metadata(
+        this,
+        path: String,
+        mode = NoneType::None,
+        uid = NoneType::None,
+        gid = NoneType::None,
+        user = NoneType::None,
+        group = NoneType::None,
+        mtime = NoneType::None
+    ) {
+        let mut this = this.downcast_mut::<FileManifestValue>().unwrap().unwrap();
+        this.set_metadata(path, mode, uid, gid, user, group, mtime)
+    }
+
     FileManifest.paths(this) {
         let this = this.downcast_ref::<FileManifestValue>().unwrap();
         this.paths()
@@ -550,6 +853,210 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn add_symlink() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("m = FileManifest()")?;
+        env.eval(
+            "m.add_file(FileContent(filename = 'lib', content = 'foo'), directory = 'Versions/A')",
+        )?;
+        env.eval("m.add_symlink('Versions/Current', 'A')")?;
+        env.eval("m.add_symlink('lib', 'Versions/Current/lib')")?;
+
+        assert!(env
+            .eval("m.add_symlink('Versions/A/lib', 'other')")
+            .is_err());
+        assert!(env.eval("m.add_symlink('/absolute', 'other')").is_err());
+        assert!(env.eval("m.add_symlink('../escape', 'other')").is_err());
+        assert!(env
+            .eval("m.add_symlink('Versions/../../escape', 'other')")
+            .is_err());
+        assert!(env
+            .eval("m.add_file(FileContent(filename = 'lib', content = 'foo'))")
+            .is_err());
+
+        let raw = env.eval("m")?;
+        let manifest = raw.downcast_ref::<FileManifestValue>().unwrap();
+        assert_eq!(
+            manifest
+                .symlinks("ignored")
+                .unwrap()
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (PathBuf::from("Versions/Current"), PathBuf::from("A")),
+                (PathBuf::from("lib"), PathBuf::from("Versions/Current/lib")),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn add_manifest_symlinks() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("m1 = FileManifest()")?;
+        env.eval("m2 = FileManifest()")?;
+        env.eval("m2.add_symlink('current', '1.0')")?;
+        env.eval("m1.add_manifest(m2)")?;
+
+        let raw = env.eval("m1")?;
+        let manifest = raw.downcast_ref::<FileManifestValue>().unwrap();
+        assert_eq!(
+            manifest
+                .symlinks("ignored")
+                .unwrap()
+                .get(Path::new("current")),
+            Some(&PathBuf::from("1.0"))
+        );
+
+        env.eval("m3 = FileManifest()")?;
+        env.eval("m3.add_file(FileContent(filename = 'current', content = 'foo'))")?;
+        assert!(env.eval("m1.add_manifest(m3)").is_err());
+
+        env.eval("m4 = FileManifest()")?;
+        env.eval("m4.add_symlink('current', '2.0')")?;
+        assert!(env.eval("m1.add_manifest(m4)").is_err());
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn install_symlinks() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("m = FileManifest()")?;
+        env.eval(
+            "m.add_file(FileContent(filename = 'lib', content = 'foo'), directory = 'Versions/A')",
+        )?;
+        env.eval("m.add_symlink('Versions/Current', 'A')")?;
+        env.eval("m.install('install_symlinks')")?;
+
+        let context_value = get_context_value(&env.type_values).unwrap();
+        let context = context_value.downcast_ref::<EnvironmentContext>().unwrap();
+        let dest_path = context.build_path().join("install_symlinks");
+
+        let link = dest_path.join("Versions/Current");
+        assert!(link.symlink_metadata()?.file_type().is_symlink());
+        assert_eq!(std::fs::read_link(&link)?, PathBuf::from("A"));
+        assert_eq!(std::fs::read(link.join("lib"))?, b"foo");
+
+        // Installing again replaces the existing symlink.
+        env.eval("m.install('install_symlinks')")?;
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_tarball_symlinks() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("m = FileManifest()")?;
+        env.eval(
+            "m.add_file(FileContent(filename = 'lib', content = 'foo'), directory = 'Versions/A')",
+        )?;
+        env.eval("m.add_symlink('Versions/Current', 'A')")?;
+
+        let raw = env.eval("m")?;
+        let manifest = raw.downcast_ref::<FileManifestValue>().unwrap();
+
+        let mut data = vec![];
+        manifest
+            .write_tarball("ignored", &mut data, Some(Path::new("app")))
+            .unwrap();
+
+        let mut archive = tar::Archive::new(std::io::Cursor::new(data));
+        let mut symlinks = vec![];
+        for entry in archive.entries()? {
+            let entry = entry?;
+
+            if entry.header().entry_type() == tar::EntryType::Symlink {
+                symlinks.push((
+                    entry.path()?.to_path_buf(),
+                    entry.link_name()?.map(|p| p.to_path_buf()),
+                ));
+            }
+        }
+
+        assert_eq!(
+            symlinks,
+            vec![(
+                PathBuf::from("app/Versions/Current"),
+                Some(PathBuf::from("A"))
+            )]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn set_metadata() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("m = FileManifest()")?;
+        env.eval("m.add_file(FileContent(filename = 'file', content = 'foo'))")?;
+        assert!(env.eval("m.set_metadata('missing', mode = 0o644)").is_err());
+        assert!(env.eval("m.set_metadata('file', mode = 0o170000)").is_err());
+        assert!(env.eval("m.set_metadata('file', uid = -1)").is_err());
+
+        env.eval("m.set_metadata('file', mode = 0o600, user = 'app')")?;
+        env.eval("m.set_metadata('file', mtime = 42)")?;
+
+        env.eval("m2 = FileManifest()")?;
+        env.eval("m2.add_manifest(m)")?;
+
+        let raw = env.eval("m2")?;
+        let manifest = raw.downcast_ref::<FileManifestValue>().unwrap();
+        assert_eq!(
+            manifest
+                .file_metadata("ignored")
+                .unwrap()
+                .get(Path::new("file")),
+            Some(&FileMetadata {
+                mode: Some(0o600),
+                user: Some("app".into()),
+                mtime: Some(42),
+                ..FileMetadata::default()
+            })
+        );
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn install_metadata() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("m = FileManifest()")?;
+        env.eval("m.add_file(FileContent(filename = 'file', content = 'foo'))")?;
+        env.eval("m.set_metadata('file', mode = 0o600, mtime = 1000000000)")?;
+        env.eval("m.install('install_metadata')")?;
+
+        let context_value = get_context_value(&env.type_values).unwrap();
+        let context = context_value.downcast_ref::<EnvironmentContext>().unwrap();
+        let path = context.build_path().join("install_metadata").join("file");
+
+        let metadata = std::fs::metadata(&path)?;
+        assert_eq!(metadata.permissions().mode() & 0o7777, 0o600);
+        assert_eq!(
+            metadata
+                .modified()?
+                .duration_since(std::time::UNIX_EPOCH)?
+                .as_secs(),
+            1000000000
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn get_file() -> Result<()> {
         let mut env = StarlarkEnvironment::new()?;
@@ -597,6 +1104,10 @@ mod tests {
         );
         assert_eq!(env.eval("m.remove('file')")?.get_type(), "NoneType");
 
+        env.eval("m.add_symlink('link', 'file')")?;
+        env.eval("m.remove('link')")?;
+        env.eval("m.add_file(FileContent(filename = 'link', content = 'foo'))")?;
+
         Ok(())
     }
 }
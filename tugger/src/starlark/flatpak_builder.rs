@@ -0,0 +1,394 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use {
+    crate::starlark::{file_content::FileContentValue, file_manifest::FileManifestValue},
+    anyhow::{anyhow, Context},
+    log::warn,
+    starlark::{
+        environment::TypeValues,
+        values::{
+            error::{RuntimeError, UnsupportedOperation, ValueError},
+            none::NoneType,
+            Mutable, TypedValue, Value, ValueResult,
+        },
+        {
+            starlark_fun, starlark_module, starlark_parse_param_type, starlark_signature,
+            starlark_signature_extraction, starlark_signatures,
+        },
+    },
+    starlark_dialect_build_targets::{
+        get_context_value, optional_str_arg, EnvironmentContext, ResolvedTarget,
+        ResolvedTargetValue, RunMode,
+    },
+    std::{
+        path::{Path, PathBuf},
+        sync::{Arc, Mutex, MutexGuard},
+    },
+    tugger_flatpak::{FlatpakBuilder, FlatpakManifest},
+};
+
+fn error_context<F, T>(label: &str, f: F) -> Result<T, ValueError>
+where
+    F: FnOnce() -> anyhow::Result<T>,
+{
+    f().map_err(|e| {
+        ValueError::Runtime(RuntimeError {
+            code: "TUGGER_FLATPAK",
+            message: format!("{:?}", e),
+            label: label.to_string(),
+        })
+    })
+}
+
+#[derive(Clone)]
+pub struct FlatpakBuilderValue {
+    inner: Arc<Mutex<FlatpakBuilder>>,
+    /// Whether building produces a `.flatpak` bundle.
+    build_bundle: bool,
+}
+
+impl TypedValue for FlatpakBuilderValue {
+    type Holder = Mutable<FlatpakBuilderValue>;
+    const TYPE: &'static str = "FlatpakBuilder";
+
+    fn values_for_descendant_check_and_freeze(&self) -> Box<dyn Iterator<Item = Value>> {
+        Box::new(std::iter::empty())
+    }
+
+    fn get_attr(&self, attribute: &str) -> ValueResult {
+        let builder = self.inner()?;
+
+        Ok(match attribute {
+            "app_id" => Value::from(builder.manifest().app_id.clone()),
+            "build_bundle" => Value::from(self.build_bundle),
+            "bundle_file_name" => Value::from(builder.bundle_file_name()),
+            "finish_args" => Value::from(builder.manifest().finish_args.clone()),
+            _ => {
+                return Err(ValueError::OperationNotSupported {
+                    op: UnsupportedOperation::GetAttr(attribute.to_string()),
+                    left: Self::TYPE.to_string(),
+                    right: None,
+                })
+            }
+        })
+    }
+
+    fn has_attr(&self, attribute: &str) -> Result<bool, ValueError> {
+        Ok(matches!(
+            attribute,
+            "app_id" | "build_bundle" | "bundle_file_name" | "finish_args"
+        ))
+    }
+
+    fn set_attr(&mut self, attribute: &str, value: Value) -> Result<(), ValueError> {
+        match attribute {
+            "build_bundle" => {
+                self.build_bundle = value.to_bool();
+            }
+            attr => {
+                return Err(ValueError::OperationNotSupported {
+                    op: UnsupportedOperation::SetAttr(attr.to_string()),
+                    left: Self::TYPE.to_string(),
+                    right: None,
+                })
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FlatpakBuilderValue {
+    fn inner(&self) -> Result<MutexGuard<'_, FlatpakBuilder>, ValueError> {
+        self.inner.try_lock().map_err(|e| {
+            ValueError::Runtime(RuntimeError {
+                code: "TUGGER_FLATPAK",
+                message: format!("unable to obtain Flatpak builder lock: {:?}", e),
+                label: "FlatpakBuilder".to_string(),
+            })
+        })
+    }
+
+    pub fn new_from_args(
+        app_id: String,
+        runtime: String,
+        runtime_version: String,
+        sdk: String,
+        command: String,
+    ) -> ValueResult {
+        Self::new_from_builder(FlatpakBuilder::new(FlatpakManifest::new(
+            app_id,
+            runtime,
+            runtime_version,
+            sdk,
+            command,
+        )))
+    }
+
+    /// Construct a value from an existing [FlatpakBuilder].
+    pub fn new_from_builder(builder: FlatpakBuilder) -> ValueResult {
+        Ok(Value::new(Self {
+            inner: Arc::new(Mutex::new(builder)),
+            build_bundle: true,
+        }))
+    }
+
+    pub fn add_finish_arg(&self, arg: String) -> ValueResult {
+        self.inner()?.manifest_mut().finish_args.push(arg);
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_file(&self, content: FileContentValue, path: Value) -> ValueResult {
+        const LABEL: &str = "FlatpakBuilder.add_file()";
+
+        let path = optional_str_arg("path", &path)?;
+
+        let mut inner = self.inner()?;
+        let content_inner = content.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            let path = if let Some(path) = path {
+                PathBuf::from(path)
+            } else {
+                PathBuf::from(&content_inner.filename)
+            };
+
+            inner.install_file(path, content_inner.content.clone())
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_manifest(&self, manifest: FileManifestValue) -> ValueResult {
+        const LABEL: &str = "FlatpakBuilder.add_manifest()";
+
+        let mut inner = self.inner()?;
+        let symlinks = manifest.symlinks(LABEL)?;
+        let metadata = manifest.file_metadata(LABEL)?;
+        let manifest = manifest.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            inner.install_manifest(&manifest)?;
+
+            for (path, target) in symlinks.iter() {
+                inner.install_symlink(path, target)?;
+            }
+            for (path, metadata) in metadata.iter() {
+                inner.set_file_metadata(path, metadata.clone())?;
+            }
+
+            Ok(())
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn set_desktop_file(&self, content: FileContentValue) -> ValueResult {
+        const LABEL: &str = "FlatpakBuilder.set_desktop_file()";
+
+        let mut inner = self.inner()?;
+        let content_inner = content.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            inner.set_desktop_file(content_inner.content.clone())
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_icon(&self, content: FileContentValue, size: String) -> ValueResult {
+        const LABEL: &str = "FlatpakBuilder.add_icon()";
+
+        let mut inner = self.inner()?;
+        let content_inner = content.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            let extension = Path::new(&content_inner.filename)
+                .extension()
+                .and_then(|x| x.to_str())
+                .ok_or_else(|| {
+                    anyhow!(
+                        "unable to resolve icon format from filename {}",
+                        content_inner.filename
+                    )
+                })?;
+
+            inner.add_icon(&size, extension, content_inner.content.clone())
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn write_manifest(&self, type_values: &TypeValues, path: String) -> ValueResult {
+        const LABEL: &str = "FlatpakBuilder.write_manifest()";
+
+        let inner = self.inner()?;
+
+        let context_value = get_context_value(type_values)?;
+        let context = context_value
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let path = context.resolve_path(path);
+
+        let manifest_path = error_context(LABEL, || {
+            std::fs::create_dir_all(&path)
+                .with_context(|| format!("creating directory {}", path.display()))?;
+
+            inner.write_manifest(&path).context("writing manifest")
+        })?;
+
+        Ok(Value::from(format!("{}", manifest_path.display())))
+    }
+
+    fn build(&self, type_values: &TypeValues, target: String) -> ValueResult {
+        const LABEL: &str = "FlatpakBuilder.build()";
+
+        let inner = self.inner()?;
+
+        let context_value = get_context_value(type_values)?;
+        let context = context_value
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let output_path = context.target_build_path(&target);
+
+        error_context(LABEL, || {
+            std::fs::create_dir_all(&output_path)
+                .with_context(|| format!("creating directory {}", output_path.display()))?;
+
+            if self.build_bundle {
+                let bundle_path = inner
+                    .build_bundle(&output_path)
+                    .context("building Flatpak bundle")?;
+                warn!("wrote {}", bundle_path.display());
+            } else {
+                let manifest_path = inner
+                    .write_manifest(&output_path)
+                    .context("writing manifest")?;
+                warn!("wrote {}", manifest_path.display());
+            }
+
+            Ok(())
+        })?;
+
+        Ok(Value::new(ResolvedTargetValue {
+            inner: ResolvedTarget {
+                run_mode: RunMode::None,
+                output_path,
+            },
+        }))
+    }
+}
+
+starlark_module! { flatpak_builder_module =>
+    #[allow(non_snake_case)]
+    FlatpakBuilder(
+        app_id: String,
+        runtime: String,
+        runtime_version: String,
+        sdk: String,
+        command: String
+    ) {
+        FlatpakBuilderValue::new_from_args(app_id, runtime, runtime_version, sdk, command)
+    }
+
+    FlatpakBuilder.add_finish_arg(this, arg: String) {
+        let this = this.downcast_ref::<FlatpakBuilderValue>().unwrap();
+        this.add_finish_arg(arg)
+    }
+
+    FlatpakBuilder.add_file(this, file: FileContentValue, path = NoneType::None) {
+        let this = this.downcast_ref::<FlatpakBuilderValue>().unwrap();
+        this.add_file(file, path)
+    }
+
+    FlatpakBuilder.add_manifest(this, manifest: FileManifestValue) {
+        let this = this.downcast_ref::<FlatpakBuilderValue>().unwrap();
+        this.add_manifest(manifest)
+    }
+
+    FlatpakBuilder.set_desktop_file(this, file: FileContentValue) {
+        let this = this.downcast_ref::<FlatpakBuilderValue>().unwrap();
+        this.set_desktop_file(file)
+    }
+
+    FlatpakBuilder.add_icon(this, file: FileContentValue, size: String) {
+        let this = this.downcast_ref::<FlatpakBuilderValue>().unwrap();
+        this.add_icon(file, size)
+    }
+
+    FlatpakBuilder.write_manifest(env env, this, path: String) {
+        let this = this.downcast_ref::<FlatpakBuilderValue>().unwrap();
+        this.write_manifest(env, path)
+    }
+
+    FlatpakBuilder.build(env env, this, target: String) {
+        let this = this.downcast_ref::<FlatpakBuilderValue>().unwrap();
+        this.build(env, target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::starlark::testutil::*, anyhow::Result, tugger_common::testutil::*};
+
+    const NEW_BUILDER: &str = "f = FlatpakBuilder('org.example.MyApp', 'org.freedesktop.Platform', '22.08', 'org.freedesktop.Sdk', 'myapp')";
+
+    #[test]
+    fn attributes() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval(NEW_BUILDER)?;
+        let raw = env.eval("f")?;
+        assert_eq!(raw.get_type(), FlatpakBuilderValue::TYPE);
+
+        let v = env.eval("f.app_id")?;
+        assert_eq!(v.to_string(), "org.example.MyApp");
+
+        let v = env.eval("f.bundle_file_name")?;
+        assert_eq!(v.to_string(), "org.example.MyApp.flatpak");
+
+        let v = env.eval("f.build_bundle")?;
+        assert!(v.to_bool());
+        env.eval("f.build_bundle = False")?;
+        let v = env.eval("f.build_bundle")?;
+        assert!(!v.to_bool());
+
+        env.eval("f.add_finish_arg('--share=network')")?;
+        let v = env.eval("f.finish_args")?;
+        assert_eq!(v.to_string(), "[\"--share=network\"]");
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_manifest() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        let temp_dir_s = format!("{}", DEFAULT_TEMP_DIR.path().display()).replace('\\', "/");
+
+        env.eval(NEW_BUILDER)?;
+        env.eval(
+            "f.add_file(FileContent(filename = 'myapp', content = 'foo'), path = 'bin/myapp')",
+        )?;
+        env.eval("f.add_icon(FileContent(filename = 'icon.png', content = 'png'), '64x64')")?;
+        assert!(env
+            .eval("f.add_icon(FileContent(filename = 'icon.png', content = 'png'), 'large')")
+            .is_err());
+
+        let path = env.eval(&format!("f.write_manifest('{}')", temp_dir_s))?;
+        assert_eq!(path.get_type(), "string");
+        let path = PathBuf::from(path.to_string());
+        assert!(path.exists());
+        assert!(path
+            .with_file_name("files")
+            .join("share/icons/hicolor/64x64/apps/org.example.MyApp.png")
+            .exists());
+
+        Ok(())
+    }
+}
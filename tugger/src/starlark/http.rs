@@ -0,0 +1,232 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use {
+    crate::starlark::{
+        file_content::FileContentWrapper, get_context_value as get_tugger_context_value,
+        TuggerContextValue,
+    },
+    anyhow::{anyhow, Context},
+    simple_file_manifest::FileEntry,
+    starlark::{
+        environment::TypeValues,
+        values::{
+            error::{RuntimeError, ValueError},
+            none::NoneType,
+            Value, ValueResult,
+        },
+        {
+            starlark_fun, starlark_module, starlark_parse_param_type, starlark_signature,
+            starlark_signature_extraction, starlark_signatures,
+        },
+    },
+    starlark_dialect_build_targets::{
+        get_context_value, optional_bool_arg, optional_str_arg, EnvironmentContext,
+    },
+    tugger_common::http::{download_to_path, RemoteContent},
+};
+
+fn error_context<F, T>(label: &str, f: F) -> Result<T, ValueError>
+where
+    F: FnOnce() -> anyhow::Result<T>,
+{
+    f().map_err(|e| {
+        ValueError::Runtime(RuntimeError {
+            code: "TUGGER_HTTP",
+            message: format!("{:?}", e),
+            label: label.to_string(),
+        })
+    })
+}
+
+/// http_fetch(url, sha256, filename=None, executable=None)
+fn starlark_http_fetch(
+    type_values: &TypeValues,
+    url: String,
+    sha256: String,
+    filename: &Value,
+    executable: &Value,
+) -> ValueResult {
+    const LABEL: &str = "http_fetch()";
+
+    let filename = optional_str_arg("filename", filename)?;
+    let executable = optional_bool_arg("executable", executable)?;
+
+    let cache_dir = {
+        let tugger_context_raw = get_tugger_context_value(type_values)?;
+        let tugger_context = tugger_context_raw
+            .downcast_ref::<TuggerContextValue>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        if let Some(path) = &tugger_context.http_cache_dir {
+            path.clone()
+        } else {
+            let context_value = get_context_value(type_values)?;
+            let context = context_value
+                .downcast_ref::<EnvironmentContext>()
+                .ok_or(ValueError::IncorrectParameterType)?;
+
+            context.build_path().join("http-cache")
+        }
+    };
+
+    let file_content = error_context(LABEL, || {
+        let sha256 = sha256.to_lowercase();
+
+        if sha256.len() != 64 || !sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(anyhow!(
+                "sha256 must be a 64 character hex digest; got {}",
+                sha256
+            ));
+        }
+
+        let filename = if let Some(filename) = filename {
+            filename
+        } else {
+            url.split(&['?', '#'][..])
+                .next()
+                .and_then(|s| s.rsplit('/').next())
+                .filter(|s| !s.is_empty())
+                .ok_or_else(|| {
+                    anyhow!(
+                        "unable to resolve file name from URL {}; specify filename",
+                        url
+                    )
+                })?
+                .to_string()
+        };
+
+        if filename.contains('/') || filename.contains('\\') {
+            return Err(anyhow!(
+                "directory separators aren't allowed in file names: {}",
+                filename
+            ));
+        }
+
+        // Cache entries are keyed by content digest so the same URL can
+        // never satisfy a different pin.
+        let dest_path = cache_dir.join(&sha256).join(&filename);
+
+        let remote = RemoteContent {
+            name: format!("HTTP_FETCH_{}", sha256),
+            url: url.clone(),
+            sha256,
+        };
+
+        download_to_path(&remote, &dest_path).with_context(|| format!("fetching {}", url))?;
+
+        let mut content = FileEntry::try_from(dest_path.as_path())?;
+        content.set_executable(executable.unwrap_or(false));
+
+        Ok(FileContentWrapper { content, filename })
+    })?;
+
+    Ok(file_content.into())
+}
+
+starlark_module! { http_module =>
+    http_fetch(
+        env env,
+        url: String,
+        sha256: String,
+        filename = NoneType::None,
+        executable = NoneType::None
+    ) {
+        starlark_http_fetch(env, url, sha256, &filename, &executable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::starlark::{file_content::FileContentValue, testutil::*},
+        anyhow::Result,
+        starlark::values::TypedValue,
+        tugger_common::testutil::*,
+    };
+
+    const FOO_SHA256: &str = "2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae";
+
+    fn file_url(filename: &str, content: &[u8]) -> Result<String> {
+        let path = DEFAULT_TEMP_DIR.path().join(filename);
+        std::fs::write(&path, content)?;
+
+        let normalized = path.display().to_string().replace('\\', "/");
+
+        Ok(if normalized.starts_with('/') {
+            format!("file://{}", normalized)
+        } else {
+            format!("file:///{}", normalized)
+        })
+    }
+
+    #[test]
+    fn fetch() -> Result<()> {
+        let url = file_url("tugger_http_fetch.txt", b"foo")?;
+
+        let mut env = StarlarkEnvironment::new()?;
+
+        let raw = env.eval(&format!("http_fetch('{}', '{}')", url, FOO_SHA256))?;
+        assert_eq!(raw.get_type(), FileContentValue::TYPE);
+
+        let v = raw.downcast_ref::<FileContentValue>().unwrap();
+        let inner = v.inner("ignored").unwrap();
+
+        assert_eq!(inner.filename, "tugger_http_fetch.txt");
+        assert!(!inner.content.is_executable());
+        assert_eq!(inner.content.resolve_content()?, b"foo".to_vec());
+
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_filename_executable() -> Result<()> {
+        let url = file_url("tugger_http_fetch_exe", b"foo")?;
+
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval(&format!(
+            "c = http_fetch('{}', '{}', filename = 'tool', executable = True)",
+            url,
+            FOO_SHA256.to_uppercase()
+        ))?;
+
+        assert_eq!(env.eval("c.filename")?.to_string(), "tool");
+        assert!(env.eval("c.executable")?.to_bool());
+
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_hash_mismatch() -> Result<()> {
+        let url = file_url("tugger_http_fetch_mismatch.txt", b"bar")?;
+
+        let mut env = StarlarkEnvironment::new()?;
+        assert!(env
+            .eval(&format!("http_fetch('{}', '{}')", url, FOO_SHA256))
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_bad_args() -> Result<()> {
+        let url = file_url("tugger_http_fetch_bad_args.txt", b"foo")?;
+
+        let mut env = StarlarkEnvironment::new()?;
+        assert!(env.eval(&format!("http_fetch('{}')", url)).is_err());
+        assert!(env
+            .eval(&format!("http_fetch('{}', 'deadbeef')", url))
+            .is_err());
+        assert!(env
+            .eval(&format!(
+                "http_fetch('{}', '{}', filename = 'foo/bar')",
+                url, FOO_SHA256
+            ))
+            .is_err());
+
+        Ok(())
+    }
+}
@@ -6,7 +6,8 @@ use {
     crate::starlark::{
         code_signing::{handle_signable_event, SigningAction, SigningContext},
         file_content::FileContentValue,
-        file_manifest::FileManifestValue,
+        file_manifest::{apply_file_metadata, materialize_symlinks, FileManifestValue},
+        macos_info_plist::{value_to_plist, MacOsInfoPlistValue},
     },
     anyhow::{anyhow, Context},
     apple_bundles::MacOsApplicationBundleBuilder,
@@ -15,7 +16,7 @@ use {
         environment::TypeValues,
         eval::call_stack::CallStack,
         values::{
-            error::{RuntimeError, ValueError, INCORRECT_PARAMETER_TYPE_ERROR_CODE},
+            error::{RuntimeError, ValueError},
             none::NoneType,
             {Mutable, TypedValue, Value, ValueResult},
         },
@@ -28,8 +29,12 @@ use {
         get_context_value, optional_str_arg, EnvironmentContext, ResolvedTarget,
         ResolvedTargetValue, RunMode,
     },
-    std::path::{Path, PathBuf},
+    std::{
+        collections::BTreeMap,
+        path::{Path, PathBuf},
+    },
     tugger_code_signing::SigningDestination,
+    tugger_common::file_metadata::FileMetadata,
 };
 
 fn error_context<F, T>(label: &str, f: F) -> Result<T, ValueError>
@@ -48,6 +53,10 @@ where
 #[derive(Debug)]
 pub struct MacOsApplicationBundleBuilderValue {
     pub inner: MacOsApplicationBundleBuilder,
+    /// Symlinks to create in the bundle, keyed by path relative to the bundle root.
+    pub symlinks: BTreeMap<PathBuf, PathBuf>,
+    /// Metadata of files in the bundle, keyed by path relative to the bundle root.
+    pub file_metadata: BTreeMap<PathBuf, FileMetadata>,
 }
 
 impl TypedValue for MacOsApplicationBundleBuilderValue {
@@ -65,7 +74,11 @@ impl MacOsApplicationBundleBuilderValue {
             MacOsApplicationBundleBuilder::new(bundle_name)
         })?;
 
-        Ok(Value::new(MacOsApplicationBundleBuilderValue { inner }))
+        Ok(Value::new(MacOsApplicationBundleBuilderValue {
+            inner,
+            symlinks: BTreeMap::new(),
+            file_metadata: BTreeMap::new(),
+        }))
     }
 
     pub fn add_icon(&mut self, path: String) -> ValueResult {
@@ -77,9 +90,30 @@ impl MacOsApplicationBundleBuilderValue {
         Ok(Value::new(NoneType::None))
     }
 
+    /// Record symlinks and file metadata from a manifest to be applied under
+    /// `prefix` in the bundle.
+    fn add_manifest_extras(
+        &mut self,
+        prefix: &Path,
+        manifest: &FileManifestValue,
+        label: &str,
+    ) -> Result<(), ValueError> {
+        for (path, target) in manifest.symlinks(label)?.iter() {
+            self.symlinks.insert(prefix.join(path), target.clone());
+        }
+
+        for (path, metadata) in manifest.file_metadata(label)?.iter() {
+            self.file_metadata
+                .insert(prefix.join(path), metadata.clone());
+        }
+
+        Ok(())
+    }
+
     pub fn add_manifest(&mut self, manifest: FileManifestValue) -> ValueResult {
         const LABEL: &str = "MacOsApplicationBundleBuilder.add_manifest()";
 
+        self.add_manifest_extras(Path::new("Contents"), &manifest, LABEL)?;
         let manifest = manifest.inner(LABEL)?;
 
         error_context(LABEL, || {
@@ -120,6 +154,7 @@ impl MacOsApplicationBundleBuilderValue {
     pub fn add_macos_manifest(&mut self, manifest: FileManifestValue) -> ValueResult {
         const LABEL: &str = "MacOsApplicationBundleBuilder.add_macos_manifest()";
 
+        self.add_manifest_extras(Path::new("Contents/MacOS"), &manifest, LABEL)?;
         let manifest = manifest.inner(LABEL)?;
 
         error_context(LABEL, || {
@@ -160,6 +195,7 @@ impl MacOsApplicationBundleBuilderValue {
     pub fn add_resources_manifest(&mut self, manifest: FileManifestValue) -> ValueResult {
         const LABEL: &str = "MacOsApplicationBundleBuilder.add_resources_manifest()";
 
+        self.add_manifest_extras(Path::new("Contents/Resources"), &manifest, LABEL)?;
         let manifest = manifest.inner(LABEL)?;
 
         error_context(LABEL, || {
@@ -176,18 +212,7 @@ impl MacOsApplicationBundleBuilderValue {
     }
 
     pub fn set_info_plist_key(&mut self, key: String, value: Value) -> ValueResult {
-        let value: plist::Value = match value.get_type() {
-            "bool" => value.to_bool().into(),
-            "int" => value.to_int()?.into(),
-            "string" => value.to_string().into(),
-            t => {
-                return Err(ValueError::from(RuntimeError {
-                    code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
-                    message: format!("function expects a bool, int, or string; got {}", t),
-                    label: "set_info_plist_key()".to_string(),
-                }))
-            }
-        };
+        let value = value_to_plist("set_info_plist_key()", &value)?;
 
         error_context("MacOsApplicationBundleBuilder.set_info_plist_key()", || {
             self.inner
@@ -198,6 +223,60 @@ impl MacOsApplicationBundleBuilderValue {
         Ok(Value::new(NoneType::None))
     }
 
+    pub fn set_info_plist_from_path(
+        &mut self,
+        type_values: &TypeValues,
+        path: String,
+    ) -> ValueResult {
+        const LABEL: &str = "MacOsApplicationBundleBuilder.set_info_plist_from_path()";
+
+        let context_value = get_context_value(type_values)?;
+        let context = context_value
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let path = context.resolve_path(path);
+
+        error_context(LABEL, || {
+            let value = plist::Value::from_file(&path)
+                .with_context(|| format!("parsing {}", path.display()))?;
+
+            let dict = value
+                .into_dictionary()
+                .ok_or_else(|| anyhow!("{} does not contain a dictionary", path.display()))?;
+
+            for (key, value) in dict {
+                self.inner
+                    .set_info_plist_key(key.clone(), value)
+                    .with_context(|| format!("setting info plist key {}", key))?;
+            }
+
+            Ok(())
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn set_info_plist(&mut self, plist: MacOsInfoPlistValue) -> ValueResult {
+        const LABEL: &str = "MacOsApplicationBundleBuilder.set_info_plist()";
+
+        let plist = plist.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            plist.validate().context("validating Info.plist")?;
+
+            for (key, value) in plist.to_dictionary() {
+                self.inner
+                    .set_info_plist_key(key.clone(), value)
+                    .with_context(|| format!("setting info plist key {}", key))?;
+            }
+
+            Ok(())
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
     pub fn set_info_plist_required_keys(
         &mut self,
         display_name: String,
@@ -236,6 +315,10 @@ impl MacOsApplicationBundleBuilderValue {
                 .inner
                 .materialize_bundle(dest_dir)
                 .context("materializing bundle")?;
+            apply_file_metadata(&bundle_path, &self.file_metadata)
+                .context("applying file metadata in bundle")?;
+            materialize_symlinks(&bundle_path, &self.symlinks)
+                .context("creating symlinks in bundle")?;
 
             let filename = bundle_path
                 .file_name()
@@ -356,6 +439,16 @@ starlark_module! { macos_application_bundle_builder_module =>
         this.set_info_plist_key(key, value)
     }
 
+    MacOsApplicationBundleBuilder.set_info_plist_from_path(env env, this, path: String) {
+        let mut this = this.downcast_mut::<MacOsApplicationBundleBuilderValue>().unwrap().unwrap();
+        this.set_info_plist_from_path(env, path)
+    }
+
+    MacOsApplicationBundleBuilder.set_info_plist(this, plist: MacOsInfoPlistValue) {
+        let mut this = this.downcast_mut::<MacOsApplicationBundleBuilderValue>().unwrap().unwrap();
+        this.set_info_plist(plist)
+    }
+
     MacOsApplicationBundleBuilder.set_info_plist_required_keys(
         this,
         display_name: String,
@@ -429,6 +522,81 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn set_info_plist() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("builder = MacOsApplicationBundleBuilder('myapp')")?;
+        env.eval("p = MacOsInfoPlist('com.example.myapp', 'My App', 'myapp', '0.1')")?;
+        env.eval("p.add_url_type('com.example.myapp', ['myapp'])")?;
+        env.eval("builder.set_info_plist(p)")?;
+
+        let builder_value = env.eval("builder")?;
+        let builder = builder_value
+            .downcast_ref::<MacOsApplicationBundleBuilderValue>()
+            .unwrap();
+
+        assert_eq!(
+            builder.inner.get_info_plist_key("CFBundleIdentifier")?,
+            Some("com.example.myapp".into())
+        );
+        assert_eq!(
+            builder.inner.get_info_plist_key("CFBundlePackageType")?,
+            Some("APPL".into())
+        );
+        assert!(builder
+            .inner
+            .get_info_plist_key("CFBundleURLTypes")?
+            .is_some());
+
+        env.eval("p.bundle_version = 'latest'")?;
+        assert!(env.eval("builder.set_info_plist(p)").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn set_info_plist_from_path() -> Result<()> {
+        let temp_dir = DEFAULT_TEMP_DIR
+            .path()
+            .join("macos-application-bundle-builder-info-plist");
+        std::fs::create_dir_all(&temp_dir)?;
+        let plist_path = temp_dir.join("Info.plist");
+
+        let mut dict = plist::Dictionary::new();
+        dict.insert("CFBundleIdentifier".into(), "com.example.my_app".into());
+        dict.insert("LSUIElement".into(), true.into());
+        plist::Value::from(dict).to_file_xml(&plist_path)?;
+
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("builder = MacOsApplicationBundleBuilder('myapp')")?;
+        env.eval(&format!(
+            "builder.set_info_plist_from_path('{}')",
+            plist_path.display().to_string().replace('\\', "/")
+        ))?;
+
+        let builder_value = env.eval("builder")?;
+        let builder = builder_value
+            .downcast_ref::<MacOsApplicationBundleBuilderValue>()
+            .unwrap();
+
+        assert_eq!(
+            builder.inner.get_info_plist_key("CFBundleIdentifier")?,
+            Some("com.example.my_app".into())
+        );
+        assert_eq!(
+            builder.inner.get_info_plist_key("LSUIElement")?,
+            Some(true.into())
+        );
+
+        assert!(env
+            .eval("builder.set_info_plist_from_path('does-not-exist.plist')")
+            .is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn add_macos_file() -> Result<()> {
         let mut env = StarlarkEnvironment::new()?;
@@ -487,6 +655,44 @@ mod tests {
         let path = PathBuf::from(path_value.to_string());
         assert!(path.is_dir());
 
+        Ok(())
+    }
+    #[cfg(unix)]
+    #[test]
+    fn manifest_symlinks() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("builder = MacOsApplicationBundleBuilder('myapp')")?;
+        env.eval("m = FileManifest()")?;
+        env.eval(
+            "m.add_file(FileContent(filename = 'file', content = 'content'), directory = 'A')",
+        )?;
+        env.eval("m.add_symlink('Current', 'A')")?;
+        env.eval("builder.add_resources_manifest(m)")?;
+
+        let value = env.eval("builder")?;
+        {
+            let builder = value
+                .downcast_ref::<MacOsApplicationBundleBuilderValue>()
+                .unwrap();
+            assert_eq!(
+                builder
+                    .symlinks
+                    .get(Path::new("Contents/Resources/Current")),
+                Some(&PathBuf::from("A"))
+            );
+        }
+
+        let dest_dir = DEFAULT_TEMP_DIR
+            .path()
+            .join("macos-application-bundle-builder-symlinks");
+        let dest_dir_s = dest_dir.to_string_lossy().replace('\\', "/");
+
+        let path_value = env.eval(&format!("builder.write_to_directory('{}')", dest_dir_s))?;
+        let link = PathBuf::from(path_value.to_string()).join("Contents/Resources/Current");
+        assert!(link.symlink_metadata()?.file_type().is_symlink());
+        assert_eq!(std::fs::read(link.join("file"))?, b"content");
+
         Ok(())
     }
 }
@@ -0,0 +1,550 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use {
+    crate::starlark::{file_content::FileContentValue, file_manifest::FileManifestValue},
+    anyhow::{anyhow, Context},
+    starlark::{
+        environment::TypeValues,
+        values::{
+            error::{RuntimeError, UnsupportedOperation, ValueError},
+            none::NoneType,
+            Mutable, TypedValue, Value, ValueResult,
+        },
+        {
+            starlark_fun, starlark_module, starlark_parse_param_type, starlark_signature,
+            starlark_signature_extraction, starlark_signatures,
+        },
+    },
+    starlark_dialect_build_targets::{
+        get_context_value, optional_str_arg, optional_type_arg, required_type_arg,
+        EnvironmentContext, ResolvedTarget, ResolvedTargetValue, RunMode,
+    },
+    std::{
+        path::PathBuf,
+        sync::{Arc, Mutex, MutexGuard},
+    },
+    tugger_apple::{DmgBuilder, LicenseAgreement, LicenseLanguage, WindowBounds},
+};
+
+fn error_context<F, T>(label: &str, f: F) -> Result<T, ValueError>
+where
+    F: FnOnce() -> anyhow::Result<T>,
+{
+    f().map_err(|e| {
+        ValueError::Runtime(RuntimeError {
+            code: "TUGGER_MACOS_DMG",
+            message: format!("{:?}", e),
+            label: label.to_string(),
+        })
+    })
+}
+
+/// Resolve a non-negative integer argument.
+fn u32_arg(label: &str, name: &str, value: &Value) -> Result<u32, ValueError> {
+    required_type_arg(name, "int", value)?;
+    let value = value.to_int()?;
+
+    error_context(label, || {
+        u32::try_from(value).with_context(|| format!("{} must not be negative", name))
+    })
+}
+
+#[derive(Clone)]
+pub struct MacOsDmgBuilderValue {
+    inner: Arc<Mutex<DmgBuilder>>,
+}
+
+impl TypedValue for MacOsDmgBuilderValue {
+    type Holder = Mutable<MacOsDmgBuilderValue>;
+    const TYPE: &'static str = "MacOsDmgBuilder";
+
+    fn values_for_descendant_check_and_freeze(&self) -> Box<dyn Iterator<Item = Value>> {
+        Box::new(std::iter::empty())
+    }
+
+    fn get_attr(&self, attribute: &str) -> ValueResult {
+        let builder = self.inner()?;
+
+        Ok(match attribute {
+            "dmg_file_name" => Value::from(builder.dmg_file_name()),
+            "volume_name" => Value::from(builder.volume_name()),
+            _ => {
+                return Err(ValueError::OperationNotSupported {
+                    op: UnsupportedOperation::GetAttr(attribute.to_string()),
+                    left: Self::TYPE.to_string(),
+                    right: None,
+                })
+            }
+        })
+    }
+
+    fn has_attr(&self, attribute: &str) -> Result<bool, ValueError> {
+        Ok(matches!(attribute, "dmg_file_name" | "volume_name"))
+    }
+}
+
+impl MacOsDmgBuilderValue {
+    fn inner(&self) -> Result<MutexGuard<'_, DmgBuilder>, ValueError> {
+        self.inner.try_lock().map_err(|e| {
+            ValueError::Runtime(RuntimeError {
+                code: "TUGGER_MACOS_DMG",
+                message: format!("unable to obtain DMG builder lock: {:?}", e),
+                label: "MacOsDmgBuilder".to_string(),
+            })
+        })
+    }
+
+    pub fn new_from_args(volume_name: String) -> ValueResult {
+        const LABEL: &str = "MacOsDmgBuilder()";
+
+        let builder = error_context(LABEL, || DmgBuilder::new(volume_name))?;
+
+        Ok(Value::new(Self {
+            inner: Arc::new(Mutex::new(builder)),
+        }))
+    }
+
+    /// Construct an instance with common settings.
+    pub fn new_from_settings(
+        volume_name: String,
+        manifest: Value,
+        background: Value,
+        applications_symlink: bool,
+        license: Value,
+    ) -> ValueResult {
+        optional_type_arg("manifest", "FileManifest", &manifest)?;
+        optional_type_arg("background", "FileContent", &background)?;
+        let license = optional_str_arg("license", &license)?;
+
+        let value = Self::new_from_args(volume_name)?;
+        let builder = value.downcast_ref::<MacOsDmgBuilderValue>().unwrap();
+
+        if let Some(manifest) = manifest.downcast_ref::<FileManifestValue>() {
+            builder.add_manifest(manifest.clone())?;
+        }
+        if let Some(background) = background.downcast_ref::<FileContentValue>() {
+            builder.set_background(background.clone())?;
+        }
+        builder
+            .inner()?
+            .set_applications_symlink(applications_symlink);
+        if let Some(license) = license {
+            builder.add_license(
+                license,
+                "English".to_string(),
+                Value::new(NoneType::None),
+                Value::new(NoneType::None),
+            )?;
+        }
+
+        Ok(value.clone())
+    }
+
+    pub fn add_file(&self, content: FileContentValue, path: Value) -> ValueResult {
+        const LABEL: &str = "MacOsDmgBuilder.add_file()";
+
+        let path = optional_str_arg("path", &path)?;
+
+        let mut inner = self.inner()?;
+        let content_inner = content.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            let path = if let Some(path) = path {
+                PathBuf::from(path)
+            } else {
+                PathBuf::from(&content_inner.filename)
+            };
+
+            inner.add_file(path, content_inner.content.clone())
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_manifest(&self, manifest: FileManifestValue) -> ValueResult {
+        const LABEL: &str = "MacOsDmgBuilder.add_manifest()";
+
+        let mut inner = self.inner()?;
+        let symlinks = manifest.symlinks(LABEL)?;
+        let manifest = manifest.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            if let Some(path) = symlinks.keys().next() {
+                return Err(anyhow!(
+                    "symlinks are not supported in disk images: {}",
+                    path.display()
+                ));
+            }
+
+            inner.add_manifest(&manifest)
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn set_background(&self, content: FileContentValue) -> ValueResult {
+        const LABEL: &str = "MacOsDmgBuilder.set_background()";
+
+        let mut inner = self.inner()?;
+        let content_inner = content.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            inner.set_background(&content_inner.filename, content_inner.content.clone())
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn set_window_bounds(
+        &self,
+        x: Value,
+        y: Value,
+        width: Value,
+        height: Value,
+    ) -> ValueResult {
+        const LABEL: &str = "MacOsDmgBuilder.set_window_bounds()";
+
+        let bounds = WindowBounds {
+            x: u32_arg(LABEL, "x", &x)?,
+            y: u32_arg(LABEL, "y", &y)?,
+            width: u32_arg(LABEL, "width", &width)?,
+            height: u32_arg(LABEL, "height", &height)?,
+        };
+
+        self.inner()?.set_window_bounds(bounds);
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn set_icon_size(&self, size: Value) -> ValueResult {
+        const LABEL: &str = "MacOsDmgBuilder.set_icon_size()";
+
+        let size = u32_arg(LABEL, "size", &size)?;
+        let mut inner = self.inner()?;
+
+        error_context(LABEL, || inner.set_icon_size(size))?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn set_text_size(&self, size: Value) -> ValueResult {
+        const LABEL: &str = "MacOsDmgBuilder.set_text_size()";
+
+        let size = u32_arg(LABEL, "size", &size)?;
+        let mut inner = self.inner()?;
+
+        error_context(LABEL, || inner.set_text_size(size))?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn set_icon_position(&self, name: String, x: Value, y: Value) -> ValueResult {
+        const LABEL: &str = "MacOsDmgBuilder.set_icon_position()";
+
+        let x = u32_arg(LABEL, "x", &x)?;
+        let y = u32_arg(LABEL, "y", &y)?;
+
+        self.inner()?.set_icon_position(name, x, y);
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_applications_symlink(&self, x: Value, y: Value) -> ValueResult {
+        const LABEL: &str = "MacOsDmgBuilder.add_applications_symlink()";
+
+        let position = match (x.get_type(), y.get_type()) {
+            ("NoneType", "NoneType") => None,
+            _ => Some((u32_arg(LABEL, "x", &x)?, u32_arg(LABEL, "y", &y)?)),
+        };
+
+        let mut inner = self.inner()?;
+        inner.set_applications_symlink(true);
+        if let Some((x, y)) = position {
+            inner.set_icon_position("Applications", x, y);
+        }
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_license(
+        &self,
+        text: String,
+        language: String,
+        agree: Value,
+        disagree: Value,
+    ) -> ValueResult {
+        const LABEL: &str = "MacOsDmgBuilder.add_license()";
+
+        let agree = optional_str_arg("agree", &agree)?;
+        let disagree = optional_str_arg("disagree", &disagree)?;
+
+        let mut inner = self.inner()?;
+
+        error_context(LABEL, || {
+            let language = LicenseLanguage::try_from(language.as_str())?;
+
+            let mut license = LicenseAgreement::new(language, text);
+            if let Some(agree) = agree {
+                license.agree = agree;
+            }
+            if let Some(disagree) = disagree {
+                license.disagree = disagree;
+            }
+
+            inner.add_license(license)
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn write_volume(&self, type_values: &TypeValues, path: String) -> ValueResult {
+        const LABEL: &str = "MacOsDmgBuilder.write_volume()";
+
+        let inner = self.inner()?;
+
+        let context_value = get_context_value(type_values)?;
+        let context = context_value
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let path = context.resolve_path(path);
+
+        error_context(LABEL, || {
+            inner
+                .materialize_volume(&path)
+                .context("materializing volume")
+        })?;
+
+        Ok(Value::from(format!("{}", path.display())))
+    }
+
+    pub fn write_to_directory(&self, type_values: &TypeValues, path: String) -> ValueResult {
+        const LABEL: &str = "MacOsDmgBuilder.write_to_directory()";
+
+        let inner = self.inner()?;
+
+        let context_value = get_context_value(type_values)?;
+        let context = context_value
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let path = context.resolve_path(path);
+
+        let dmg_path = error_context(LABEL, || {
+            std::fs::create_dir_all(&path)
+                .with_context(|| format!("creating directory {}", path.display()))?;
+
+            inner.build(&path).context("building DMG")
+        })?;
+
+        Ok(Value::from(format!("{}", dmg_path.display())))
+    }
+
+    fn build(&self, type_values: &TypeValues, target: String) -> ValueResult {
+        const LABEL: &str = "MacOsDmgBuilder.build()";
+
+        let inner = self.inner()?;
+
+        let context_value = get_context_value(type_values)?;
+        let context = context_value
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let output_path = context.target_build_path(&target);
+
+        let dmg_path = error_context(LABEL, || {
+            std::fs::create_dir_all(&output_path)
+                .with_context(|| format!("creating directory {}", output_path.display()))?;
+
+            inner.build(&output_path).context("building DMG")
+        })?;
+
+        Ok(Value::new(ResolvedTargetValue {
+            inner: ResolvedTarget {
+                run_mode: RunMode::Path { path: dmg_path },
+                output_path,
+            },
+        }))
+    }
+}
+
+starlark_module! { macos_dmg_builder_module =>
+    #[allow(non_snake_case)]
+    MacOsDmgBuilder(volume_name: String) {
+        MacOsDmgBuilderValue::new_from_args(volume_name)
+    }
+
+    macos_dmg(
+        volume_name: String,
+        manifest = NoneType::None,
+        background = NoneType::None,
+        applications_symlink: bool = true,
+        license = NoneType::None
+    ) {
+        MacOsDmgBuilderValue::new_from_settings(
+            volume_name,
+            manifest,
+            background,
+            applications_symlink,
+            license,
+        )
+    }
+
+    MacOsDmgBuilder.add_file(this, file: FileContentValue, path = NoneType::None) {
+        let this = this.downcast_ref::<MacOsDmgBuilderValue>().unwrap();
+        this.add_file(file, path)
+    }
+
+    MacOsDmgBuilder.add_manifest(this, manifest: FileManifestValue) {
+        let this = this.downcast_ref::<MacOsDmgBuilderValue>().unwrap();
+        this.add_manifest(manifest)
+    }
+
+    MacOsDmgBuilder.set_background(this, file: FileContentValue) {
+        let this = this.downcast_ref::<MacOsDmgBuilderValue>().unwrap();
+        this.set_background(file)
+    }
+
+    MacOsDmgBuilder.set_window_bounds(this, x, y, width, height) {
+        let this = this.downcast_ref::<MacOsDmgBuilderValue>().unwrap();
+        this.set_window_bounds(x, y, width, height)
+    }
+
+    MacOsDmgBuilder.set_icon_size(this, size) {
+        let this = this.downcast_ref::<MacOsDmgBuilderValue>().unwrap();
+        this.set_icon_size(size)
+    }
+
+    MacOsDmgBuilder.set_text_size(this, size) {
+        let this = this.downcast_ref::<MacOsDmgBuilderValue>().unwrap();
+        this.set_text_size(size)
+    }
+
+    MacOsDmgBuilder.set_icon_position(this, name: String, x, y) {
+        let this = this.downcast_ref::<MacOsDmgBuilderValue>().unwrap();
+        this.set_icon_position(name, x, y)
+    }
+
+    MacOsDmgBuilder.add_applications_symlink(this, x = NoneType::None, y = NoneType::None) {
+        let this = this.downcast_ref::<MacOsDmgBuilderValue>().unwrap();
+        this.add_applications_symlink(x, y)
+    }
+
+    MacOsDmgBuilder.add_license(
+        this,
+        text: String,
+        language: String = "English".to_string(),
+        agree = NoneType::None,
+        disagree = NoneType::None
+    ) {
+        let this = this.downcast_ref::<MacOsDmgBuilderValue>().unwrap();
+        this.add_license(text, language, agree, disagree)
+    }
+
+    MacOsDmgBuilder.write_volume(env env, this, path: String) {
+        let this = this.downcast_ref::<MacOsDmgBuilderValue>().unwrap();
+        this.write_volume(env, path)
+    }
+
+    MacOsDmgBuilder.write_to_directory(env env, this, path: String) {
+        let this = this.downcast_ref::<MacOsDmgBuilderValue>().unwrap();
+        this.write_to_directory(env, path)
+    }
+
+    MacOsDmgBuilder.build(env env, this, target: String) {
+        let this = this.downcast_ref::<MacOsDmgBuilderValue>().unwrap();
+        this.build(env, target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::starlark::testutil::*, anyhow::Result};
+
+    #[test]
+    fn type_info() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("d = MacOsDmgBuilder('My App')")?;
+        let raw = env.eval("d")?;
+        assert_eq!(raw.get_type(), MacOsDmgBuilderValue::TYPE);
+
+        assert_eq!(env.eval("d.volume_name")?.to_string(), "My App");
+        assert_eq!(env.eval("d.dmg_file_name")?.to_string(), "My App.dmg");
+
+        assert!(env.eval("MacOsDmgBuilder('a:b')").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn settings() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("d = MacOsDmgBuilder('My App')")?;
+        env.eval("d.set_window_bounds(100, 100, 600, 400)")?;
+        assert!(env.eval("d.set_window_bounds(-1, 100, 600, 400)").is_err());
+        assert!(env.eval("d.set_window_bounds('a', 100, 600, 400)").is_err());
+        env.eval("d.set_icon_size(96)")?;
+        assert!(env.eval("d.set_icon_size(8)").is_err());
+        env.eval("d.set_text_size(14)")?;
+        env.eval("d.add_license('terms')")?;
+        env.eval("d.add_license('conditions', language = 'French', agree = 'Accepter')")?;
+        assert!(env
+            .eval("d.add_license('terms', language = 'English')")
+            .is_err());
+        assert!(env
+            .eval("d.add_license('terms', language = 'Klingon')")
+            .is_err());
+
+        let raw = env.eval("d")?;
+        let builder = raw.downcast_ref::<MacOsDmgBuilderValue>().unwrap();
+        let inner = builder.inner()?;
+        assert_eq!(inner.window_bounds().width, 600);
+        assert_eq!(inner.licenses().len(), 2);
+        assert_eq!(inner.licenses()[1].agree, "Accepter");
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_volume() -> Result<()> {
+        use tugger_common::testutil::DEFAULT_TEMP_DIR;
+
+        let mut env = StarlarkEnvironment::new()?;
+
+        let temp_dir_s = format!(
+            "{}",
+            DEFAULT_TEMP_DIR.path().join("macos-dmg-volume").display()
+        )
+        .replace('\\', "/");
+
+        env.eval("m = FileManifest()")?;
+        env.eval(
+            "m.add_file(FileContent(filename = 'myapp', content = 'foo'), directory = 'My App.app/Contents/MacOS')",
+        )?;
+        env.eval(
+            "d = macos_dmg('My App', manifest = m, background = FileContent(filename = 'bg.png', content = 'png'))",
+        )?;
+        env.eval("d.set_icon_position('My App.app', 150, 200)")?;
+        env.eval("d.add_applications_symlink(450, 200)")?;
+        let path = env.eval(&format!("d.write_volume('{}')", temp_dir_s))?;
+
+        assert_eq!(path.get_type(), "string");
+        let path = PathBuf::from(path.to_string());
+        assert!(path.join("My App.app/Contents/MacOS/myapp").exists());
+        assert!(path.join(".background/bg.png").exists());
+        assert!(path.join(".DS_Store").exists());
+        assert!(path.join("Applications").symlink_metadata().is_ok());
+
+        env.eval("d.set_icon_position('missing', 0, 0)")?;
+        assert!(env
+            .eval(&format!("d.write_volume('{}')", temp_dir_s))
+            .is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,499 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use {
+    crate::starlark::file_content::FileContentWrapper,
+    starlark::{
+        values::{
+            error::{
+                RuntimeError, UnsupportedOperation, ValueError, INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+            },
+            none::NoneType,
+            Mutable, TypedValue, Value, ValueResult,
+        },
+        {
+            starlark_fun, starlark_module, starlark_parse_param_type, starlark_signature,
+            starlark_signature_extraction, starlark_signatures,
+        },
+    },
+    starlark_dialect_build_targets::{optional_bool_arg, optional_list_arg, optional_str_arg},
+    std::sync::{Arc, Mutex, MutexGuard},
+    tugger_apple::{
+        BundleTypeRole, DocumentType, HandlerRank, InfoPlist, InfoPlistError, PlistFormat, UrlType,
+        UsageDescription,
+    },
+};
+
+fn error_context<F, T>(label: &str, f: F) -> Result<T, ValueError>
+where
+    F: FnOnce() -> Result<T, InfoPlistError>,
+{
+    f().map_err(|e| {
+        ValueError::Runtime(RuntimeError {
+            code: "TUGGER_MACOS_INFO_PLIST",
+            message: format!("{}", e),
+            label: label.to_string(),
+        })
+    })
+}
+
+fn optional_str_list(name: &str, value: &Value) -> Result<Vec<String>, ValueError> {
+    optional_list_arg(name, "string", value)?;
+
+    match value.get_type() {
+        "list" => Ok(value.iter()?.iter().map(|v| v.to_string()).collect()),
+        _ => Ok(vec![]),
+    }
+}
+
+/// Convert a Starlark value to a plist value.
+pub fn value_to_plist(label: &str, value: &Value) -> Result<plist::Value, ValueError> {
+    Ok(match value.get_type() {
+        "bool" => value.to_bool().into(),
+        "int" => value.to_int()?.into(),
+        "string" => value.to_string().into(),
+        "list" => plist::Value::Array(
+            value
+                .iter()?
+                .iter()
+                .map(|v| value_to_plist(label, &v))
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+        t => {
+            return Err(ValueError::from(RuntimeError {
+                code: INCORRECT_PARAMETER_TYPE_ERROR_CODE,
+                message: format!("function expects a bool, int, string, or list; got {}", t),
+                label: label.to_string(),
+            }))
+        }
+    })
+}
+
+#[derive(Clone, Debug)]
+pub struct MacOsInfoPlistValue {
+    inner: Arc<Mutex<InfoPlist>>,
+}
+
+impl TypedValue for MacOsInfoPlistValue {
+    type Holder = Mutable<MacOsInfoPlistValue>;
+    const TYPE: &'static str = "MacOsInfoPlist";
+
+    fn values_for_descendant_check_and_freeze(&self) -> Box<dyn Iterator<Item = Value>> {
+        Box::new(std::iter::empty())
+    }
+
+    fn get_attr(&self, attribute: &str) -> ValueResult {
+        let inner = self.inner(&format!("{}.{}", Self::TYPE, attribute))?;
+
+        let optional_str = |v: &Option<String>| match v {
+            Some(v) => Value::from(v.as_str()),
+            None => Value::new(NoneType::None),
+        };
+        let optional_bool = |v: Option<bool>| match v {
+            Some(v) => Value::from(v),
+            None => Value::new(NoneType::None),
+        };
+
+        Ok(match attribute {
+            "application_category" => optional_str(&inner.application_category),
+            "bundle_identifier" => Value::from(inner.bundle_identifier.as_str()),
+            "bundle_name" => Value::from(inner.bundle_name.as_str()),
+            "bundle_version" => Value::from(inner.bundle_version.as_str()),
+            "copyright" => optional_str(&inner.copyright),
+            "development_region" => optional_str(&inner.development_region),
+            "display_name" => optional_str(&inner.display_name),
+            "executable" => Value::from(inner.executable.as_str()),
+            "high_resolution_capable" => optional_bool(inner.high_resolution_capable),
+            "icon_file" => optional_str(&inner.icon_file),
+            "minimum_system_version" => optional_str(&inner.minimum_system_version),
+            "package_type" => Value::from(inner.package_type.as_str()),
+            "principal_class" => optional_str(&inner.principal_class),
+            "short_version" => optional_str(&inner.short_version),
+            "signature" => optional_str(&inner.signature),
+            "ui_element" => optional_bool(inner.ui_element),
+            _ => {
+                return Err(ValueError::OperationNotSupported {
+                    op: UnsupportedOperation::GetAttr(attribute.to_string()),
+                    left: Self::TYPE.to_string(),
+                    right: None,
+                })
+            }
+        })
+    }
+
+    fn has_attr(&self, attribute: &str) -> Result<bool, ValueError> {
+        Ok(matches!(
+            attribute,
+            "application_category"
+                | "bundle_identifier"
+                | "bundle_name"
+                | "bundle_version"
+                | "copyright"
+                | "development_region"
+                | "display_name"
+                | "executable"
+                | "high_resolution_capable"
+                | "icon_file"
+                | "minimum_system_version"
+                | "package_type"
+                | "principal_class"
+                | "short_version"
+                | "signature"
+                | "ui_element"
+        ))
+    }
+
+    fn set_attr(&mut self, attribute: &str, value: Value) -> Result<(), ValueError> {
+        let label = format!("{}.{}", Self::TYPE, attribute);
+        let mut inner = self.inner(&label)?;
+
+        match attribute {
+            "application_category" => {
+                inner.application_category = optional_str_arg(attribute, &value)?;
+            }
+            "bundle_identifier" => {
+                inner.bundle_identifier = value.to_string();
+            }
+            "bundle_name" => {
+                inner.bundle_name = value.to_string();
+            }
+            "bundle_version" => {
+                inner.bundle_version = value.to_string();
+            }
+            "copyright" => {
+                inner.copyright = optional_str_arg(attribute, &value)?;
+            }
+            "development_region" => {
+                inner.development_region = optional_str_arg(attribute, &value)?;
+            }
+            "display_name" => {
+                inner.display_name = optional_str_arg(attribute, &value)?;
+            }
+            "executable" => {
+                inner.executable = value.to_string();
+            }
+            "high_resolution_capable" => {
+                inner.high_resolution_capable = optional_bool_arg(attribute, &value)?;
+            }
+            "icon_file" => {
+                inner.icon_file = optional_str_arg(attribute, &value)?;
+            }
+            "minimum_system_version" => {
+                inner.minimum_system_version = optional_str_arg(attribute, &value)?;
+            }
+            "package_type" => {
+                inner.package_type = value.to_string();
+            }
+            "principal_class" => {
+                inner.principal_class = optional_str_arg(attribute, &value)?;
+            }
+            "short_version" => {
+                inner.short_version = optional_str_arg(attribute, &value)?;
+            }
+            "signature" => {
+                inner.signature = optional_str_arg(attribute, &value)?;
+            }
+            "ui_element" => {
+                inner.ui_element = optional_bool_arg(attribute, &value)?;
+            }
+            attr => {
+                return Err(ValueError::OperationNotSupported {
+                    op: UnsupportedOperation::SetAttr(attr.to_string()),
+                    left: Self::TYPE.to_string(),
+                    right: None,
+                })
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl MacOsInfoPlistValue {
+    pub fn inner(&self, label: &str) -> Result<MutexGuard<InfoPlist>, ValueError> {
+        self.inner.try_lock().map_err(|e| {
+            ValueError::Runtime(RuntimeError {
+                code: "TUGGER_MACOS_INFO_PLIST",
+                message: format!("error obtaining lock: {}", e),
+                label: label.to_string(),
+            })
+        })
+    }
+
+    pub fn new_from_args(
+        bundle_identifier: String,
+        bundle_name: String,
+        executable: String,
+        bundle_version: String,
+    ) -> ValueResult {
+        Ok(Value::new(MacOsInfoPlistValue {
+            inner: Arc::new(Mutex::new(InfoPlist::new(
+                bundle_identifier,
+                bundle_name,
+                executable,
+                bundle_version,
+            ))),
+        }))
+    }
+
+    pub fn add_document_type(
+        &self,
+        name: String,
+        role: String,
+        extensions: &Value,
+        content_types: &Value,
+        handler_rank: &Value,
+        icon_file: &Value,
+    ) -> ValueResult {
+        const LABEL: &str = "MacOsInfoPlist.add_document_type()";
+
+        let extensions = optional_str_list("extensions", extensions)?;
+        let content_types = optional_str_list("content_types", content_types)?;
+        let handler_rank = optional_str_arg("handler_rank", handler_rank)?;
+        let icon_file = optional_str_arg("icon_file", icon_file)?;
+
+        let mut inner = self.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            inner.add_document_type(DocumentType {
+                name,
+                role: BundleTypeRole::try_from(role.as_str())?,
+                handler_rank: handler_rank
+                    .map(|rank| HandlerRank::try_from(rank.as_str()))
+                    .transpose()?,
+                content_types,
+                extensions,
+                icon_file,
+            });
+
+            Ok(())
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_url_type(&self, name: String, schemes: &Value, role: &Value) -> ValueResult {
+        const LABEL: &str = "MacOsInfoPlist.add_url_type()";
+
+        let schemes = optional_str_list("schemes", schemes)?;
+        let role = optional_str_arg("role", role)?;
+
+        let mut inner = self.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            inner.add_url_type(UrlType {
+                name,
+                schemes,
+                role: role
+                    .map(|role| BundleTypeRole::try_from(role.as_str()))
+                    .transpose()?,
+            });
+
+            Ok(())
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn set_usage_description(&self, resource: String, description: String) -> ValueResult {
+        const LABEL: &str = "MacOsInfoPlist.set_usage_description()";
+
+        let mut inner = self.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            inner
+                .set_usage_description(UsageDescription::try_from(resource.as_str())?, description);
+
+            Ok(())
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn set_key(&self, key: String, value: &Value) -> ValueResult {
+        const LABEL: &str = "MacOsInfoPlist.set_key()";
+
+        let value = value_to_plist(LABEL, value)?;
+
+        let mut inner = self.inner(LABEL)?;
+
+        error_context(LABEL, || inner.set_extra_key(key, value))?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn validate(&self) -> ValueResult {
+        const LABEL: &str = "MacOsInfoPlist.validate()";
+
+        let inner = self.inner(LABEL)?;
+
+        error_context(LABEL, || inner.validate())?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn to_file_content(&self, format: String) -> ValueResult {
+        const LABEL: &str = "MacOsInfoPlist.to_file_content()";
+
+        let inner = self.inner(LABEL)?;
+
+        let data = error_context(LABEL, || {
+            let mut data = vec![];
+            inner.write(&mut data, PlistFormat::try_from(format.as_str())?)?;
+
+            Ok(data)
+        })?;
+
+        Ok(FileContentWrapper {
+            content: data.into(),
+            filename: "Info.plist".to_string(),
+        }
+        .into())
+    }
+}
+
+starlark_module! { macos_info_plist_module =>
+    #[allow(non_snake_case)]
+    MacOsInfoPlist(
+        bundle_identifier: String,
+        bundle_name: String,
+        executable: String,
+        bundle_version: String
+    ) {
+        MacOsInfoPlistValue::new_from_args(bundle_identifier, bundle_name, executable, bundle_version)
+    }
+
+    MacOsInfoPlist.add_document_type(
+        this,
+        name: String,
+        role: String,
+        extensions = NoneType::None,
+        content_types = NoneType::None,
+        handler_rank = NoneType::None,
+        icon_file = NoneType::None
+    ) {
+        let this = this.downcast_ref::<MacOsInfoPlistValue>().unwrap();
+        this.add_document_type(name, role, &extensions, &content_types, &handler_rank, &icon_file)
+    }
+
+    MacOsInfoPlist.add_url_type(this, name: String, schemes, role = NoneType::None) {
+        let this = this.downcast_ref::<MacOsInfoPlistValue>().unwrap();
+        this.add_url_type(name, &schemes, &role)
+    }
+
+    MacOsInfoPlist.set_usage_description(this, resource: String, description: String) {
+        let this = this.downcast_ref::<MacOsInfoPlistValue>().unwrap();
+        this.set_usage_description(resource, description)
+    }
+
+    MacOsInfoPlist.set_key(this, key: String, value) {
+        let this = this.downcast_ref::<MacOsInfoPlistValue>().unwrap();
+        this.set_key(key, &value)
+    }
+
+    MacOsInfoPlist.validate(this) {
+        let this = this.downcast_ref::<MacOsInfoPlistValue>().unwrap();
+        this.validate()
+    }
+
+    MacOsInfoPlist.to_file_content(this, format: String = "xml".to_string()) {
+        let this = this.downcast_ref::<MacOsInfoPlistValue>().unwrap();
+        this.to_file_content(format)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::starlark::{file_content::FileContentValue, testutil::*},
+        anyhow::Result,
+    };
+
+    const NEW_PLIST: &str = "p = MacOsInfoPlist('com.example.myapp', 'My App', 'myapp', '1.0')";
+
+    #[test]
+    fn constructor() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        let p = env.eval(&format!("{}; p", NEW_PLIST))?;
+        assert_eq!(p.get_type(), MacOsInfoPlistValue::TYPE);
+
+        assert_eq!(
+            env.eval("p.bundle_identifier")?.to_string(),
+            "com.example.myapp"
+        );
+        assert_eq!(env.eval("p.package_type")?.to_string(), "APPL");
+        assert_eq!(env.eval("p.short_version")?.get_type(), "NoneType");
+
+        Ok(())
+    }
+
+    #[test]
+    fn configure() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        let p = env.eval(&format!("{}; p", NEW_PLIST))?;
+        env.eval("p.short_version = '1.0.0'")?;
+        env.eval("p.minimum_system_version = '10.13'")?;
+        env.eval("p.high_resolution_capable = True")?;
+        env.eval("p.add_document_type('Text', 'viewer', extensions = ['txt'], handler_rank = 'alternate')")?;
+        env.eval("p.add_url_type('com.example.myapp', ['myapp'])")?;
+        env.eval("p.set_usage_description('camera', 'Scans documents.')")?;
+        env.eval("p.set_key('LSEnvironment', ['a', 'b'])")?;
+        env.eval("p.validate()")?;
+
+        assert!(env.eval("p.set_key('CFBundleName', 'Other')").is_err());
+        assert!(env
+            .eval("p.set_usage_description('radio', 'text')")
+            .is_err());
+        assert!(env.eval("p.add_document_type('Doc', 'owner')").is_err());
+
+        let value = p.downcast_ref::<MacOsInfoPlistValue>().unwrap();
+        let inner = value.inner("ignored").unwrap();
+        assert_eq!(inner.short_version, Some("1.0.0".into()));
+        assert_eq!(inner.high_resolution_capable, Some(true));
+        assert_eq!(
+            inner.document_types,
+            vec![DocumentType {
+                name: "Text".into(),
+                role: BundleTypeRole::Viewer,
+                handler_rank: Some(HandlerRank::Alternate),
+                content_types: vec![],
+                extensions: vec!["txt".into()],
+                icon_file: None,
+            }]
+        );
+        assert_eq!(inner.url_types[0].schemes, vec!["myapp".to_string()]);
+        assert_eq!(
+            inner.usage_descriptions.get(&UsageDescription::Camera),
+            Some(&"Scans documents.".to_string())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn to_file_content() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval(NEW_PLIST)?;
+
+        let f = env.eval("p.to_file_content()")?;
+        let value = f.downcast_ref::<FileContentValue>().unwrap();
+        let inner = value.inner("ignored").unwrap();
+        assert_eq!(inner.filename, "Info.plist");
+        assert!(inner.content.resolve_content()?.starts_with(b"<?xml"));
+
+        let f = env.eval("p.to_file_content('binary')")?;
+        let value = f.downcast_ref::<FileContentValue>().unwrap();
+        let inner = value.inner("ignored").unwrap();
+        assert!(inner.content.resolve_content()?.starts_with(b"bplist00"));
+
+        env.eval("p.bundle_version = '1.0-beta'")?;
+        assert!(env.eval("p.to_file_content()").is_err());
+
+        Ok(())
+    }
+}
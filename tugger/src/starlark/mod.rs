@@ -8,13 +8,20 @@ The `starlark` module and related sub-modules define the
 Tugger.
 */
 
+pub mod appimage_builder;
 pub mod apple_universal_binary;
 pub mod code_signing;
+pub mod debian_package_builder;
 pub mod file_content;
 pub mod file_manifest;
 pub mod file_resource;
+pub mod flatpak_builder;
+pub mod http;
 pub mod macos_application_bundle_builder;
+pub mod macos_dmg_builder;
+pub mod macos_info_plist;
 pub mod python_wheel_builder;
+pub mod rpm_package_builder;
 pub mod snapcraft;
 pub mod terminal;
 #[cfg(test)]
@@ -32,7 +39,10 @@ use {
             Mutable, TypedValue, Value, ValueResult,
         },
     },
-    std::ops::{Deref, DerefMut},
+    std::{
+        ops::{Deref, DerefMut},
+        path::PathBuf,
+    },
 };
 
 /// Holds global context for Tugger Starlark evaluation.
@@ -44,6 +54,10 @@ pub struct TuggerContext {
     ///
     /// Setting to true causes [Self::can_prompt] to always return false.
     pub disable_interaction: bool,
+    /// Directory in which to cache files fetched by `http_fetch()`.
+    ///
+    /// If not set, a directory under the build path is used.
+    pub http_cache_dir: Option<PathBuf>,
 }
 
 impl TuggerContext {
@@ -55,6 +69,7 @@ impl TuggerContext {
             term_stderr: Term::stderr(),
             code_signers: vec![],
             disable_interaction: false,
+            http_cache_dir: None,
         }
     }
 
@@ -125,13 +140,20 @@ pub fn register_starlark_dialect(
     env: &mut Environment,
     type_values: &mut TypeValues,
 ) -> Result<(), EnvironmentError> {
+    appimage_builder::appimage_builder_module(env, type_values);
     apple_universal_binary::apple_universal_binary_module(env, type_values);
     code_signing::code_signing_module(env, type_values);
+    debian_package_builder::debian_package_builder_module(env, type_values);
     file_content::file_content_module(env, type_values);
     file_manifest::file_manifest_module(env, type_values);
     file_resource::file_resource_module(env, type_values);
+    flatpak_builder::flatpak_builder_module(env, type_values);
+    http::http_module(env, type_values);
     macos_application_bundle_builder::macos_application_bundle_builder_module(env, type_values);
+    macos_dmg_builder::macos_dmg_builder_module(env, type_values);
+    macos_info_plist::macos_info_plist_module(env, type_values);
     python_wheel_builder::python_wheel_builder_module(env, type_values);
+    rpm_package_builder::rpm_package_builder_module(env, type_values);
     snapcraft::snapcraft_module(env, type_values);
     terminal::terminal_module(env, type_values);
     wix_bundle_builder::wix_bundle_builder_module(env, type_values);
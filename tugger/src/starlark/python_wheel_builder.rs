@@ -162,8 +162,13 @@ impl PythonWheelBuilderValue {
     }
 
     pub fn new_from_args(distribution: String, version: String) -> ValueResult {
+        Self::new_from_builder(WheelBuilder::new(distribution, version))
+    }
+
+    /// Construct a value from an existing [WheelBuilder].
+    pub fn new_from_builder(builder: WheelBuilder) -> ValueResult {
         Ok(Value::new(Self {
-            inner: Arc::new(Mutex::new(WheelBuilder::new(distribution, version))),
+            inner: Arc::new(Mutex::new(builder)),
         }))
     }
 
@@ -0,0 +1,503 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use {
+    crate::starlark::{
+        file_content::{FileContentValue, FileContentWrapper},
+        file_manifest::FileManifestValue,
+    },
+    anyhow::Context,
+    log::warn,
+    starlark::{
+        environment::TypeValues,
+        values::{
+            error::{RuntimeError, UnsupportedOperation, ValueError},
+            none::NoneType,
+            Mutable, TypedValue, Value, ValueResult,
+        },
+        {
+            starlark_fun, starlark_module, starlark_parse_param_type, starlark_signature,
+            starlark_signature_extraction, starlark_signatures,
+        },
+    },
+    starlark_dialect_build_targets::{
+        get_context_value, optional_str_arg, required_type_arg, EnvironmentContext, ResolvedTarget,
+        ResolvedTargetValue, RunMode,
+    },
+    std::{
+        path::PathBuf,
+        sync::{Arc, Mutex, MutexGuard},
+    },
+    tugger_rpm::{RelationKind, RpmBuilder},
+};
+
+fn error_context<F, T>(label: &str, f: F) -> Result<T, ValueError>
+where
+    F: FnOnce() -> anyhow::Result<T>,
+{
+    f().map_err(|e| {
+        ValueError::Runtime(RuntimeError {
+            code: "TUGGER_RPM",
+            message: format!("{:?}", e),
+            label: label.to_string(),
+        })
+    })
+}
+
+#[derive(Clone)]
+pub struct RpmPackageBuilderValue {
+    inner: Arc<Mutex<RpmBuilder>>,
+}
+
+impl TypedValue for RpmPackageBuilderValue {
+    type Holder = Mutable<RpmPackageBuilderValue>;
+    const TYPE: &'static str = "RpmPackageBuilder";
+
+    fn values_for_descendant_check_and_freeze(&self) -> Box<dyn Iterator<Item = Value>> {
+        Box::new(std::iter::empty())
+    }
+
+    fn get_attr(&self, attribute: &str) -> ValueResult {
+        let builder = self.inner()?;
+
+        Ok(match attribute {
+            "rpm_file_name" => Value::from(builder.rpm_file_name()),
+            _ => {
+                return Err(ValueError::OperationNotSupported {
+                    op: UnsupportedOperation::GetAttr(attribute.to_string()),
+                    left: Self::TYPE.to_string(),
+                    right: None,
+                })
+            }
+        })
+    }
+
+    fn has_attr(&self, attribute: &str) -> Result<bool, ValueError> {
+        Ok(matches!(attribute, "rpm_file_name"))
+    }
+}
+
+impl RpmPackageBuilderValue {
+    fn inner(&self) -> Result<MutexGuard<'_, RpmBuilder>, ValueError> {
+        self.inner.try_lock().map_err(|e| {
+            ValueError::Runtime(RuntimeError {
+                code: "TUGGER_RPM",
+                message: format!("unable to obtain RPM package builder lock: {:?}", e),
+                label: "RpmPackageBuilder".to_string(),
+            })
+        })
+    }
+
+    pub fn new_from_args(
+        name: String,
+        version: String,
+        release: String,
+        architecture: String,
+        summary: String,
+        license: String,
+    ) -> ValueResult {
+        let mut builder = RpmBuilder::new(name, version, release, architecture);
+        builder.set_summary(summary);
+        builder.set_license(license);
+
+        Self::new_from_builder(builder)
+    }
+
+    /// Construct a value from an existing [RpmBuilder].
+    pub fn new_from_builder(builder: RpmBuilder) -> ValueResult {
+        Ok(Value::new(Self {
+            inner: Arc::new(Mutex::new(builder)),
+        }))
+    }
+
+    pub fn set_metadata(&self, name: String, value: String) -> ValueResult {
+        const LABEL: &str = "RpmPackageBuilder.set_metadata()";
+
+        let mut inner = self.inner()?;
+
+        error_context(LABEL, || {
+            match name.as_str() {
+                "epoch" => inner.set_epoch(value.parse().context("parsing epoch")?),
+                "summary" => inner.set_summary(value),
+                "description" => inner.set_description(value),
+                "license" => inner.set_license(value),
+                "url" => inner.set_url(value),
+                "vendor" => inner.set_vendor(value),
+                "packager" => inner.set_packager(value),
+                "group" => inner.set_group(value),
+                _ => return Err(anyhow::anyhow!("unknown metadata field {}", name)),
+            }
+
+            Ok(())
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_file(&self, content: FileContentValue, path: Value) -> ValueResult {
+        const LABEL: &str = "RpmPackageBuilder.add_file()";
+
+        let path = optional_str_arg("path", &path)?;
+
+        let mut inner = self.inner()?;
+        let content_inner = content.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            let path = if let Some(path) = path {
+                PathBuf::from(path)
+            } else {
+                PathBuf::from(&content_inner.filename)
+            };
+
+            inner.install_file(path, content_inner.content.clone())
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_manifest(&self, manifest: FileManifestValue) -> ValueResult {
+        const LABEL: &str = "RpmPackageBuilder.add_manifest()";
+
+        let mut inner = self.inner()?;
+        let symlinks = manifest.symlinks(LABEL)?;
+        let metadata = manifest.file_metadata(LABEL)?;
+        let manifest = manifest.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            inner.install_manifest(&manifest)?;
+
+            for (path, target) in symlinks.iter() {
+                inner.install_symlink(path, target)?;
+            }
+            for (path, metadata) in metadata.iter() {
+                inner.set_file_metadata(path, metadata.clone())?;
+            }
+
+            Ok(())
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_config_file(&self, path: String, noreplace: bool) -> ValueResult {
+        self.inner()?.add_config_file(path, noreplace);
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_relation(&self, kind: String, relation: String) -> ValueResult {
+        const LABEL: &str = "RpmPackageBuilder.add_relation()";
+
+        let mut inner = self.inner()?;
+
+        error_context(LABEL, || {
+            inner.add_relation(RelationKind::from_name(&kind)?, &relation)
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn set_scriptlet(&self, name: String, content: String) -> ValueResult {
+        const LABEL: &str = "RpmPackageBuilder.set_scriptlet()";
+
+        let mut inner = self.inner()?;
+
+        error_context(LABEL, || inner.set_scriptlet(&name, content))?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_changelog_entry(&self, time: Value, author: String, text: String) -> ValueResult {
+        const LABEL: &str = "RpmPackageBuilder.add_changelog_entry()";
+
+        required_type_arg("time", "int", &time)?;
+        let time = time.to_int()?;
+
+        let mut inner = self.inner()?;
+
+        error_context(LABEL, || {
+            let time = u64::try_from(time).context("changelog time must not be negative")?;
+            inner.add_changelog_entry(time, author, text);
+
+            Ok(())
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn set_signing_key(
+        &self,
+        type_values: &TypeValues,
+        path: String,
+        passphrase: Value,
+    ) -> ValueResult {
+        const LABEL: &str = "RpmPackageBuilder.set_signing_key()";
+
+        let passphrase = optional_str_arg("passphrase", &passphrase)?;
+
+        let context_value = get_context_value(type_values)?;
+        let context = context_value
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let path = context.resolve_path(path);
+
+        let mut inner = self.inner()?;
+
+        error_context(LABEL, || {
+            let armored = std::fs::read_to_string(&path)
+                .with_context(|| format!("reading {}", path.display()))?;
+
+            inner.set_signing_key_armored(&armored, passphrase.unwrap_or_default())
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn to_file_content(&self) -> ValueResult {
+        const LABEL: &str = "RpmPackageBuilder.to_file_content()";
+
+        let inner = self.inner()?;
+
+        let data = error_context(LABEL, || {
+            let mut data = vec![];
+            inner.write_rpm(&mut data).context("writing .rpm")?;
+
+            Ok(data)
+        })?;
+
+        Ok(FileContentWrapper {
+            content: data.into(),
+            filename: inner.rpm_file_name(),
+        }
+        .into())
+    }
+
+    pub fn write_to_directory(&self, type_values: &TypeValues, path: String) -> ValueResult {
+        const LABEL: &str = "RpmPackageBuilder.write_to_directory()";
+
+        let inner = self.inner()?;
+
+        let context_value = get_context_value(type_values)?;
+        let context = context_value
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let path = context.resolve_path(path);
+
+        let rpm_path = error_context(LABEL, || {
+            std::fs::create_dir_all(&path)
+                .with_context(|| format!("creating directory {}", path.display()))?;
+
+            inner
+                .write_rpm_into_directory(&path)
+                .context("writing .rpm to directory")
+        })?;
+
+        Ok(Value::from(format!("{}", rpm_path.display())))
+    }
+
+    fn build(&self, type_values: &TypeValues, target: String) -> ValueResult {
+        const LABEL: &str = "RpmPackageBuilder.build()";
+
+        let inner = self.inner()?;
+
+        let context_value = get_context_value(type_values)?;
+        let context = context_value
+            .downcast_ref::<EnvironmentContext>()
+            .ok_or(ValueError::IncorrectParameterType)?;
+
+        let output_directory = context.target_build_path(&target);
+
+        let rpm_path = error_context(LABEL, || {
+            warn!("writing .rpm to {}", output_directory.display());
+
+            std::fs::create_dir_all(&output_directory)
+                .with_context(|| format!("creating directory {}", output_directory.display()))?;
+
+            inner
+                .write_rpm_into_directory(&output_directory)
+                .context("writing .rpm to directory")
+        })?;
+
+        warn!("wrote {}", rpm_path.display());
+
+        Ok(Value::new(ResolvedTargetValue {
+            inner: ResolvedTarget {
+                run_mode: RunMode::None,
+                output_path: output_directory,
+            },
+        }))
+    }
+}
+
+starlark_module! { rpm_package_builder_module =>
+    #[allow(non_snake_case)]
+    RpmPackageBuilder(
+        name: String,
+        version: String,
+        release: String,
+        architecture: String,
+        summary: String,
+        license: String
+    ) {
+        RpmPackageBuilderValue::new_from_args(
+            name,
+            version,
+            release,
+            architecture,
+            summary,
+            license,
+        )
+    }
+
+    RpmPackageBuilder.set_metadata(this, name: String, value: String) {
+        let this = this.downcast_ref::<RpmPackageBuilderValue>().unwrap();
+        this.set_metadata(name, value)
+    }
+
+    RpmPackageBuilder.add_file(this, file: FileContentValue, path = NoneType::None) {
+        let this = this.downcast_ref::<RpmPackageBuilderValue>().unwrap();
+        this.add_file(file, path)
+    }
+
+    RpmPackageBuilder.add_manifest(this, manifest: FileManifestValue) {
+        let this = this.downcast_ref::<RpmPackageBuilderValue>().unwrap();
+        this.add_manifest(manifest)
+    }
+
+    RpmPackageBuilder.add_config_file(this, path: String, noreplace: bool = false) {
+        let this = this.downcast_ref::<RpmPackageBuilderValue>().unwrap();
+        this.add_config_file(path, noreplace)
+    }
+
+    RpmPackageBuilder.add_relation(this, kind: String, relation: String) {
+        let this = this.downcast_ref::<RpmPackageBuilderValue>().unwrap();
+        this.add_relation(kind, relation)
+    }
+
+    RpmPackageBuilder.set_scriptlet(this, name: String, content: String) {
+        let this = this.downcast_ref::<RpmPackageBuilderValue>().unwrap();
+        this.set_scriptlet(name, content)
+    }
+
+    RpmPackageBuilder.add_changelog_entry(this, time, author: String, text: String) {
+        let this = this.downcast_ref::<RpmPackageBuilderValue>().unwrap();
+        this.add_changelog_entry(time, author, text)
+    }
+
+    RpmPackageBuilder.set_signing_key(env env, this, path: String, passphrase = NoneType::None) {
+        let this = this.downcast_ref::<RpmPackageBuilderValue>().unwrap();
+        this.set_signing_key(env, path, passphrase)
+    }
+
+    RpmPackageBuilder.to_file_content(this) {
+        let this = this.downcast_ref::<RpmPackageBuilderValue>().unwrap();
+        this.to_file_content()
+    }
+
+    RpmPackageBuilder.write_to_directory(env env, this, path: String) {
+        let this = this.downcast_ref::<RpmPackageBuilderValue>().unwrap();
+        this.write_to_directory(env, path)
+    }
+
+    RpmPackageBuilder.build(env env, this, target: String) {
+        let this = this.downcast_ref::<RpmPackageBuilderValue>().unwrap();
+        this.build(env, target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::starlark::testutil::*, anyhow::Result, tugger_common::testutil::*};
+
+    const NEW_BUILDER: &str =
+        "r = RpmPackageBuilder('myapp', '1.0', '1', 'x86_64', 'My app', 'MPL-2.0')";
+
+    #[test]
+    fn type_info() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval(NEW_BUILDER)?;
+        let raw = env.eval("r")?;
+        assert_eq!(raw.get_type(), RpmPackageBuilderValue::TYPE);
+
+        let v = env.eval("r.rpm_file_name")?;
+        assert_eq!(v.to_string(), "myapp-1.0-1.x86_64.rpm");
+
+        Ok(())
+    }
+
+    #[test]
+    fn set_metadata() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval(NEW_BUILDER)?;
+        env.eval("r.set_metadata('url', 'https://example.com')")?;
+        env.eval("r.set_metadata('epoch', '2')")?;
+        assert!(env.eval("r.set_metadata('epoch', 'foo')").is_err());
+        assert!(env.eval("r.set_metadata('bogus', 'foo')").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn relations_and_scriptlets() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval(NEW_BUILDER)?;
+        env.eval("r.add_relation('requires', 'glibc >= 2.17')")?;
+        assert!(env.eval("r.add_relation('recommends', 'foo')").is_err());
+        assert!(env.eval("r.add_relation('requires', 'foo ~ 1')").is_err());
+        env.eval("r.set_scriptlet('post', '#!/bin/sh\\nldconfig\\n')")?;
+        assert!(env.eval("r.set_scriptlet('post', 'ldconfig\\n')").is_err());
+        env.eval(
+            "r.add_changelog_entry(1600000000, 'Jane Doe <jane@example.com> - 1.0-1', '- Initial')",
+        )?;
+        assert!(env
+            .eval("r.add_changelog_entry(-1, 'Jane Doe', '- Initial')")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn to_file_content() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval(NEW_BUILDER)?;
+        env.eval("m = FileManifest()")?;
+        env.eval("m.add_file(FileContent(filename = 'myapp.conf', content = 'a = 1'), directory = 'etc')")?;
+        env.eval("r.add_manifest(m)")?;
+        env.eval("r.add_config_file('/etc/myapp.conf', noreplace = True)")?;
+
+        let f = env.eval("r.to_file_content()")?;
+        assert_eq!(f.get_type(), "FileContent");
+        let value = f.downcast_ref::<FileContentValue>().unwrap();
+        let inner = value.inner("ignored").unwrap();
+        assert_eq!(inner.filename, "myapp-1.0-1.x86_64.rpm");
+        assert!(inner
+            .content
+            .resolve_content()?
+            .starts_with(&[0xed, 0xab, 0xee, 0xdb]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_to_directory() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        let temp_dir_s = format!("{}", DEFAULT_TEMP_DIR.path().display()).replace('\\', "/");
+
+        env.eval(NEW_BUILDER)?;
+        let path = env.eval(&format!("r.write_to_directory('{}')", temp_dir_s))?;
+
+        assert_eq!(path.get_type(), "string");
+        let path = PathBuf::from(path.to_string());
+        assert!(path.exists());
+
+        Ok(())
+    }
+}
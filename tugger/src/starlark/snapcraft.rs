@@ -28,8 +28,8 @@ use {
     },
     tugger_snapcraft::{
         Adapter, Architecture, Architectures, BuildAttribute, Confinement, Daemon, Grade,
-        RestartCondition, SnapApp, SnapPart, Snapcraft, SnapcraftBuilder, SnapcraftInvocation,
-        SourceType, Type,
+        LayoutType, RestartCondition, SnapApp, SnapHook, SnapInterface, SnapLayout, SnapPart,
+        Snapcraft, SnapcraftBuilder, SnapcraftInvocation, SourceType, Type,
     },
 };
 
@@ -320,6 +320,175 @@ fn value_to_parts(
     }
 }
 
+fn value_to_interfaces(
+    label: &str,
+    value: Value,
+) -> Result<HashMap<Cow<'static, str>, SnapInterface<'static>>, ValueError> {
+    match value.get_type() {
+        "NoneType" => Ok(HashMap::new()),
+        "dict" => {
+            let mut res = HashMap::new();
+
+            for key in &value.iter()? {
+                let attrs = value.at(key.clone())?;
+                if attrs.get_type() != "dict" {
+                    return Err(ValueError::from(RuntimeError {
+                        code: "TUGGER_SNAPCRAFT",
+                        message: format!(
+                            "{} values must be dicts; got {}",
+                            label,
+                            attrs.get_type()
+                        ),
+                        label: label.to_string(),
+                    }));
+                }
+
+                let mut interface = SnapInterface::default();
+
+                for attr in &attrs.iter()? {
+                    let v = attrs.at(attr.clone())?;
+
+                    match attr.to_string().as_str() {
+                        "read" => {
+                            interface.read = optional_str_vec_to_vec(v)?;
+                        }
+                        "write" => {
+                            interface.write = optional_str_vec_to_vec(v)?;
+                        }
+                        _ if v.get_type() != "string" => {
+                            return Err(ValueError::from(RuntimeError {
+                                code: "TUGGER_SNAPCRAFT",
+                                message: format!(
+                                    "{} attribute {} must be a string; got {}",
+                                    label,
+                                    attr,
+                                    v.get_type()
+                                ),
+                                label: label.to_string(),
+                            }));
+                        }
+                        "interface" => {
+                            interface.interface = Some(Cow::Owned(v.to_string()));
+                        }
+                        "content" => {
+                            interface.content = Some(Cow::Owned(v.to_string()));
+                        }
+                        "target" => {
+                            interface.target = Some(Cow::Owned(v.to_string()));
+                        }
+                        "default_provider" | "default-provider" => {
+                            interface.default_provider = Some(Cow::Owned(v.to_string()));
+                        }
+                        name => {
+                            interface
+                                .attributes
+                                .insert(Cow::Owned(name.to_string()), Cow::Owned(v.to_string()));
+                        }
+                    }
+                }
+
+                res.insert(Cow::Owned(key.to_string()), interface);
+            }
+
+            Ok(res)
+        }
+        t => Err(ValueError::from(RuntimeError {
+            code: "TUGGER_SNAPCRAFT",
+            message: format!("{} must be None or dict; got {}", label, t),
+            label: label.to_string(),
+        })),
+    }
+}
+
+fn value_to_hooks(
+    value: Value,
+) -> Result<HashMap<Cow<'static, str>, SnapHook<'static>>, ValueError> {
+    match value.get_type() {
+        "NoneType" => Ok(HashMap::new()),
+        "dict" => {
+            let mut res = HashMap::new();
+
+            for key in &value.iter()? {
+                let v = value.at(key.clone())?;
+
+                let hook_value = v.downcast_ref::<SnapHookValue>().ok_or_else(|| {
+                    ValueError::from(RuntimeError {
+                        code: "TUGGER_SNAPCRAFT",
+                        message: format!("hooks value must be SnapHook; got {}", v.get_type()),
+                        label: "hooks".to_string(),
+                    })
+                })?;
+
+                res.insert(Cow::Owned(key.to_string()), hook_value.inner.clone());
+            }
+
+            Ok(res)
+        }
+        t => Err(ValueError::from(RuntimeError {
+            code: "TUGGER_SNAPCRAFT",
+            message: format!("hooks must be None or dict; got {}", t),
+            label: "hooks".to_string(),
+        })),
+    }
+}
+
+fn value_to_layout(
+    value: Value,
+) -> Result<HashMap<Cow<'static, str>, SnapLayout<'static>>, ValueError> {
+    match value.get_type() {
+        "NoneType" => Ok(HashMap::new()),
+        "dict" => {
+            let mut res = HashMap::new();
+
+            for key in &value.iter()? {
+                let entries = optional_str_hashmap_to_hashmap(value.at(key.clone())?)?;
+
+                let mut layout = SnapLayout::default();
+
+                for (name, v) in entries {
+                    match name.as_ref() {
+                        "bind" => {
+                            layout.bind = Some(v);
+                        }
+                        "bind_file" | "bind-file" => {
+                            layout.bind_file = Some(v);
+                        }
+                        "symlink" => {
+                            layout.symlink = Some(v);
+                        }
+                        "type" => {
+                            layout.layout_type =
+                                Some(LayoutType::try_from(v.as_ref()).map_err(|e| {
+                                    ValueError::from(RuntimeError {
+                                        code: "TUGGER_SNAPCRAFT",
+                                        message: e.to_string(),
+                                        label: "layout".to_string(),
+                                    })
+                                })?);
+                        }
+                        name => {
+                            return Err(ValueError::from(RuntimeError {
+                                code: "TUGGER_SNAPCRAFT",
+                                message: format!("unknown layout key: {}", name),
+                                label: "layout".to_string(),
+                            }));
+                        }
+                    }
+                }
+
+                res.insert(Cow::Owned(key.to_string()), layout);
+            }
+
+            Ok(res)
+        }
+        t => Err(ValueError::from(RuntimeError {
+            code: "TUGGER_SNAPCRAFT",
+            message: format!("layout must be None or dict; got {}", t),
+            label: "layout".to_string(),
+        })),
+    }
+}
+
 fn value_to_filesets(
     value: Value,
 ) -> Result<HashMap<Cow<'static, str>, Vec<Cow<'static, str>>>, ValueError> {
@@ -442,6 +611,46 @@ impl TypedValue for SnapAppValue<'static> {
     }
 }
 
+#[derive(Debug, Default)]
+pub struct SnapHookValue<'a> {
+    pub inner: SnapHook<'a>,
+}
+
+impl TypedValue for SnapHookValue<'static> {
+    type Holder = Mutable<SnapHookValue<'static>>;
+    const TYPE: &'static str = "SnapHook";
+
+    fn values_for_descendant_check_and_freeze(&self) -> Box<dyn Iterator<Item = Value>> {
+        Box::new(std::iter::empty())
+    }
+
+    fn set_attr(&mut self, attribute: &str, value: Value) -> Result<(), ValueError> {
+        match attribute {
+            "command_chain" => {
+                self.inner.command_chain = optional_str_vec_to_vec(value)?;
+            }
+            "environment" => {
+                self.inner.environment = optional_str_hashmap_to_hashmap(value)?;
+            }
+            "passthrough" => {
+                self.inner.passthrough = optional_str_hashmap_to_hashmap(value)?;
+            }
+            "plugs" => {
+                self.inner.plugs = optional_str_vec_to_vec(value)?;
+            }
+            attr => {
+                return Err(ValueError::OperationNotSupported {
+                    op: UnsupportedOperation::SetAttr(attr.to_string()),
+                    left: Self::TYPE.to_string(),
+                    right: None,
+                })
+            }
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct SnapPartValue<'a> {
     pub inner: SnapPart<'a>,
@@ -586,9 +795,15 @@ impl TypedValue for SnapValue<'static> {
             "grade" => {
                 inner.grade = value_to_optional_grade(value)?;
             }
+            "hooks" => {
+                inner.hooks = value_to_hooks(value)?;
+            }
             "icon" => {
                 inner.icon = value.to_optional();
             }
+            "layout" => {
+                inner.layout = value_to_layout(value)?;
+            }
             "license" => {
                 inner.license = value.to_optional();
             }
@@ -602,30 +817,10 @@ impl TypedValue for SnapValue<'static> {
                 inner.parts = value_to_parts(value)?;
             }
             "plugs" => {
-                inner.plugs = match value.try_to_optional()? {
-                    Some(value) => value,
-                    None => {
-                        return Err(ValueError::from(RuntimeError {
-                            code: "TUGGER_SNAPCRAFT",
-                            message: "expected a dict of dict[string, string]; got None"
-                                .to_string(),
-                            label: "plugs".to_string(),
-                        }));
-                    }
-                }
+                inner.plugs = value_to_interfaces("plugs", value)?;
             }
             "slots" => {
-                inner.slots = match value.try_to_optional()? {
-                    Some(value) => value,
-                    None => {
-                        return Err(ValueError::from(RuntimeError {
-                            code: "TUGGER_SNAPCRAFT",
-                            message: "expected a dict of dict[string, string]; got None"
-                                .to_string(),
-                            label: "slots".to_string(),
-                        }));
-                    }
-                }
+                inner.slots = value_to_interfaces("slots", value)?;
             }
             "summary" => {
                 inner.summary = Cow::Owned(value.to_string());
@@ -673,6 +868,20 @@ impl<'a> SnapValue<'a> {
             })
         })
     }
+
+    pub fn validate(&self) -> ValueResult {
+        const LABEL: &str = "Snap.validate()";
+
+        self.inner(LABEL)?.validate().map_err(|e| {
+            ValueError::Runtime(RuntimeError {
+                code: "TUGGER_SNAPCRAFT",
+                message: format!("{:?}", e),
+                label: LABEL.to_string(),
+            })
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
 }
 
 /// Starlark Value wrapper for `SnapcraftBuilder`.
@@ -767,6 +976,11 @@ starlark_module! { snapcraft_module =>
         Ok(Value::new(SnapAppValue::default()))
     }
 
+    #[allow(non_snake_case)]
+    SnapHook() {
+        Ok(Value::new(SnapHookValue::default()))
+    }
+
     #[allow(non_snake_case)]
     SnapPart() {
         Ok(Value::new(SnapPartValue::default()))
@@ -781,6 +995,10 @@ starlark_module! { snapcraft_module =>
         SnapcraftBuilderValue::new_from_snap_value("Snap.to_builder()", this)
     }
 
+    Snap.validate(this: SnapValue) {
+        this.validate()
+    }
+
     #[allow(non_snake_case)]
     SnapcraftBuilder(snap: SnapValue) {
         SnapcraftBuilderValue::new_from_snap_value("SnapcraftBuilder()", snap)
@@ -1003,14 +1221,20 @@ mod tests {
             .collect();
         expected.plugs = [(
             "plug0".into(),
-            [("key0".into(), "value0".into())].iter().cloned().collect(),
+            SnapInterface {
+                attributes: [("key0".into(), "value0".into())].iter().cloned().collect(),
+                ..SnapInterface::default()
+            },
         )]
         .iter()
         .cloned()
         .collect();
         expected.slots = [(
             "slot0".into(),
-            [("key0".into(), "value0".into())].iter().cloned().collect(),
+            SnapInterface {
+                attributes: [("key0".into(), "value0".into())].iter().cloned().collect(),
+                ..SnapInterface::default()
+            },
         )]
         .iter()
         .cloned()
@@ -1029,6 +1253,90 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_hook_basic() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        let hook_value = env.eval("hook = SnapHook(); hook")?;
+        assert_eq!(hook_value.get_type(), "SnapHook");
+
+        env.eval("hook.command_chain = ['chain0']")?;
+        env.eval("hook.environment = {'env0': 'env0_value'}")?;
+        env.eval("hook.passthrough = {'key0': 'key0_value'}")?;
+        env.eval("hook.plugs = ['network']")?;
+
+        let hook = hook_value.downcast_ref::<SnapHookValue>().unwrap();
+        assert_eq!(
+            hook.inner,
+            SnapHook {
+                command_chain: vec!["chain0".into()],
+                environment: [("env0".into(), "env0_value".into())]
+                    .iter()
+                    .cloned()
+                    .collect(),
+                passthrough: [("key0".into(), "key0_value".into())]
+                    .iter()
+                    .cloned()
+                    .collect(),
+                plugs: vec!["network".into()],
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_snap_extended() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        let snap_value = env.eval("snap = Snap('myapp', '1.0', 'summary', 'description'); snap")?;
+        env.eval("part = SnapPart(); part.plugin = 'dump'")?;
+        env.eval("app = SnapApp(); app.command = 'bin/myapp'; app.plugs = ['themes']")?;
+        env.eval("snap.parts = {'myapp': part}")?;
+        env.eval("snap.apps = {'myapp': app}")?;
+        env.eval("snap.confinement = 'strict'")?;
+        env.eval(
+            "snap.plugs = {'themes': {'interface': 'content', 'target': '$SNAP/themes', 'default_provider': 'gtk-common-themes'}}",
+        )?;
+        env.eval("snap.slots = {'data': {'interface': 'content', 'read': ['$SNAP/data']}}")?;
+        env.eval("hook = SnapHook(); hook.plugs = ['network']")?;
+        env.eval("snap.hooks = {'configure': hook}")?;
+        env.eval(
+            "snap.layout = {'/usr/share/myapp': {'bind': '$SNAP/usr/share/myapp'}, '/var/cache/myapp': {'type': 'tmpfs'}}",
+        )?;
+        env.eval("snap.validate()")?;
+
+        {
+            let snap = snap_value.downcast_ref::<SnapValue>().unwrap();
+            let inner = snap.inner("ignored").unwrap();
+
+            assert_eq!(
+                inner.plugs.get("themes"),
+                Some(&SnapInterface {
+                    interface: Some("content".into()),
+                    target: Some("$SNAP/themes".into()),
+                    default_provider: Some("gtk-common-themes".into()),
+                    ..SnapInterface::default()
+                })
+            );
+            assert_eq!(inner.slots["data"].read, vec![Cow::from("$SNAP/data")]);
+            assert_eq!(inner.hooks["configure"].plugs, vec![Cow::from("network")]);
+            assert_eq!(
+                inner.layout.get("/usr/share/myapp"),
+                Some(&SnapLayout::bind("$SNAP/usr/share/myapp"))
+            );
+            assert_eq!(
+                inner.layout.get("/var/cache/myapp"),
+                Some(&SnapLayout::tmpfs())
+            );
+        }
+
+        env.eval("snap.confinement = 'classic'")?;
+        assert!(env.eval("snap.validate()").is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn test_snapcraft_builder_add_invocation() -> Result<()> {
         let mut env = StarlarkEnvironment::new()?;
@@ -13,6 +13,7 @@ use {
         values::Value,
     },
     starlark_dialect_build_targets::EnvironmentContext,
+    tugger_common::testutil::DEFAULT_TEMP_DIR,
 };
 
 /// A Starlark execution environment.
@@ -33,6 +34,7 @@ impl StarlarkEnvironment {
         // Always disable interaction in tests, otherwise reading from stdin can
         // cause hangs.
         tugger_context.disable_interaction = true;
+        tugger_context.http_cache_dir = Some(DEFAULT_TEMP_DIR.path().join("http-cache"));
 
         let (mut env, mut type_values) = starlark::stdlib::global_environment();
         starlark_dialect_build_targets::register_starlark_dialect(&mut env, &mut type_values)
@@ -8,13 +8,13 @@ use {
         file_content::FileContentWrapper,
         wix_msi_builder::WiXMsiBuilderValue,
     },
-    anyhow::Context,
+    anyhow::{Context, Result},
     simple_file_manifest::FileEntry,
     starlark::{
         environment::TypeValues,
         eval::call_stack::CallStack,
         values::{
-            error::{RuntimeError, ValueError},
+            error::{RuntimeError, UnsupportedOperation, ValueError},
             none::NoneType,
             {Mutable, TypedValue, Value, ValueResult},
         },
@@ -24,12 +24,15 @@ use {
         },
     },
     starlark_dialect_build_targets::{
-        get_context_value, EnvironmentContext, ResolvedTarget, ResolvedTargetValue, RunMode,
+        get_context_value, optional_dict_arg, optional_str_arg, EnvironmentContext, ResolvedTarget,
+        ResolvedTargetValue, RunMode,
     },
     std::path::{Path, PathBuf},
     tugger_code_signing::SigningDestination,
     tugger_windows::VcRedistributablePlatform,
-    tugger_wix::{MsiPackage, WiXBundleInstallerBuilder},
+    tugger_wix::{
+        Behavior, BootstrapperUi, ExePackage, ExitCode, MsiPackage, WiXBundleInstallerBuilder,
+    },
 };
 
 fn error_context<F, T>(label: &str, f: F) -> Result<T, ValueError>
@@ -59,6 +62,38 @@ impl TypedValue for WiXBundleBuilderValue<'static> {
     fn values_for_descendant_check_and_freeze(&self) -> Box<dyn Iterator<Item = Value>> {
         Box::new(std::iter::empty())
     }
+
+    fn set_attr(&mut self, attribute: &str, value: Value) -> Result<(), ValueError> {
+        let label = format!("{}.{}", Self::TYPE, &attribute);
+
+        match attribute {
+            "icon_path" => {
+                self.inner.set_icon_path(value.to_string());
+            }
+            "upgrade_code" => {
+                error_context(&label, || self.inner.set_upgrade_code(&value.to_string()))?;
+            }
+            attr => {
+                return Err(ValueError::OperationNotSupported {
+                    op: UnsupportedOperation::SetAttr(attr.to_string()),
+                    left: Self::TYPE.to_string(),
+                    right: None,
+                })
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolve the path of a file to chain into a bundle.
+fn resolve_source_file(label: &str, path: &str) -> Result<String, ValueError> {
+    error_context(label, || {
+        Ok(std::fs::canonicalize(path)
+            .with_context(|| format!("resolving {}", path))?
+            .display()
+            .to_string())
+    })
 }
 
 impl<'a> WiXBundleBuilderValue<'a> {
@@ -109,6 +144,142 @@ impl<'a> WiXBundleBuilderValue<'a> {
         Ok(Value::new(NoneType::None))
     }
 
+    /// WiXBundleBuilder.set_bootstrapper_ui(...)
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_bootstrapper_ui(
+        &mut self,
+        theme: String,
+        license_url: Value,
+        license_path: Value,
+        logo_path: Value,
+        suppress_options_ui: bool,
+        suppress_repair: bool,
+        show_version: bool,
+    ) -> ValueResult {
+        const LABEL: &str = "WiXBundleBuilder.set_bootstrapper_ui()";
+
+        let license_url = optional_str_arg("license_url", &license_url)?;
+        let license_file = optional_str_arg("license_path", &license_path)?
+            .map(|p| resolve_source_file(LABEL, &p))
+            .transpose()?;
+        let logo_file = optional_str_arg("logo_path", &logo_path)?
+            .map(|p| resolve_source_file(LABEL, &p))
+            .transpose()?;
+
+        error_context(LABEL, || {
+            self.inner.set_bootstrapper_ui(BootstrapperUi {
+                theme,
+                license_url,
+                license_file: license_file.map(PathBuf::from),
+                logo_file: logo_file.map(PathBuf::from),
+                suppress_options_ui,
+                suppress_repair,
+                show_version,
+            })
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// WiXBundleBuilder.add_msi_package(path, ...)
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_msi_package(
+        &mut self,
+        path: String,
+        display_name: Value,
+        install_condition: Value,
+        display_internal_ui: bool,
+        vital: bool,
+        permanent: bool,
+        properties: Value,
+    ) -> ValueResult {
+        const LABEL: &str = "WiXBundleBuilder.add_msi_package()";
+
+        let display_name = optional_str_arg("display_name", &display_name)?;
+        let install_condition = optional_str_arg("install_condition", &install_condition)?;
+        optional_dict_arg("properties", "string", "string", &properties)?;
+
+        let mut package = MsiPackage {
+            source_file: Some(resolve_source_file(LABEL, &path)?.into()),
+            display_name: display_name.map(|x| x.into()),
+            install_condition: install_condition.map(|x| x.into()),
+            vital: Some(if vital { "yes" } else { "no" }.into()),
+            permanent: Some(if permanent { "yes" } else { "no" }.into()),
+            ..Default::default()
+        };
+
+        if display_internal_ui {
+            package.display_internal_ui = Some("yes".into());
+        }
+
+        if properties.get_type() == "dict" {
+            for key in properties.iter()?.iter() {
+                let value = properties.at(key.clone())?.to_string();
+                package
+                    .properties
+                    .push((key.to_string().into(), value.into()));
+            }
+        }
+
+        self.inner.chain(package.into());
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    /// WiXBundleBuilder.add_exe_package(path, install_command, detect_condition, ...)
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_exe_package(
+        &mut self,
+        path: String,
+        install_command: String,
+        detect_condition: String,
+        install_condition: Value,
+        repair_command: Value,
+        uninstall_command: Value,
+        per_machine: bool,
+        permanent: bool,
+        vital: bool,
+        exit_codes: Value,
+    ) -> ValueResult {
+        const LABEL: &str = "WiXBundleBuilder.add_exe_package()";
+
+        let install_condition = optional_str_arg("install_condition", &install_condition)?;
+        let repair_command = optional_str_arg("repair_command", &repair_command)?;
+        let uninstall_command = optional_str_arg("uninstall_command", &uninstall_command)?;
+        optional_dict_arg("exit_codes", "int", "string", &exit_codes)?;
+
+        let mut package = ExePackage {
+            source_file: Some(resolve_source_file(LABEL, &path)?.into()),
+            install_command: Some(install_command.into()),
+            detect_condition: Some(detect_condition.into()),
+            install_condition: install_condition.map(|x| x.into()),
+            repair_command: repair_command.map(|x| x.into()),
+            uninstall_command: uninstall_command.map(|x| x.into()),
+            per_machine: Some(if per_machine { "yes" } else { "no" }.into()),
+            permanent: Some(if permanent { "yes" } else { "no" }.into()),
+            vital: Some(if vital { "yes" } else { "no" }.into()),
+            ..Default::default()
+        };
+
+        if exit_codes.get_type() == "dict" {
+            for key in exit_codes.iter()?.iter() {
+                let behavior = exit_codes.at(key.clone())?.to_string();
+                let behavior = error_context(LABEL, || Behavior::try_from(behavior.as_str()))?;
+
+                package.exit_codes.push(ExitCode {
+                    behavior,
+                    // HRESULT style exit codes are commonly written as unsigned
+                    // hex values but are interpreted as signed integers.
+                    value: key.to_int()? as i32,
+                });
+            }
+        }
+
+        self.inner.chain(package.into());
+
+        Ok(Value::new(NoneType::None))
+    }
+
     /// WiXBundleBuilder.add_wix_msi_builder(builder)
     pub fn add_wix_msi_builder(
         &mut self,
@@ -320,6 +491,51 @@ starlark_module! { wix_bundle_builder_module =>
         this.add_wix_msi_builder(builder, display_internal_ui, install_condition)
     }
 
+    WiXBundleBuilder.set_bootstrapper_ui(
+        this,
+        theme: String = "HyperlinkLicense".to_string(),
+        license_url = NoneType::None,
+        license_path = NoneType::None,
+        logo_path = NoneType::None,
+        suppress_options_ui: bool = true,
+        suppress_repair: bool = false,
+        show_version: bool = false
+    ) {
+        let mut this = this.downcast_mut::<WiXBundleBuilderValue>().unwrap().unwrap();
+        this.set_bootstrapper_ui(theme, license_url, license_path, logo_path, suppress_options_ui, suppress_repair, show_version)
+    }
+
+    WiXBundleBuilder.add_msi_package(
+        this,
+        path: String,
+        display_name = NoneType::None,
+        install_condition = NoneType::None,
+        display_internal_ui: bool = false,
+        vital: bool = true,
+        permanent: bool = false,
+        properties = NoneType::None
+    ) {
+        let mut this = this.downcast_mut::<WiXBundleBuilderValue>().unwrap().unwrap();
+        this.add_msi_package(path, display_name, install_condition, display_internal_ui, vital, permanent, properties)
+    }
+
+    WiXBundleBuilder.add_exe_package(
+        this,
+        path: String,
+        install_command: String,
+        detect_condition: String,
+        install_condition = NoneType::None,
+        repair_command = NoneType::None,
+        uninstall_command = NoneType::None,
+        per_machine: bool = true,
+        permanent: bool = false,
+        vital: bool = true,
+        exit_codes = NoneType::None
+    ) {
+        let mut this = this.downcast_mut::<WiXBundleBuilderValue>().unwrap().unwrap();
+        this.add_exe_package(path, install_command, detect_condition, install_condition, repair_command, uninstall_command, per_machine, permanent, vital, exit_codes)
+    }
+
     WiXBundleBuilder.build(env env, call_stack cs, this, target: String) {
         let this = this.downcast_ref::<WiXBundleBuilderValue>().unwrap();
         this.build(env, cs, target)
@@ -338,9 +554,9 @@ starlark_module! { wix_bundle_builder_module =>
 
 #[cfg(test)]
 mod tests {
-    use {super::*, crate::starlark::testutil::*, anyhow::Result};
     #[cfg(windows)]
-    use {crate::starlark::file_content::FileContentValue, tugger_common::testutil::*};
+    use crate::starlark::file_content::FileContentValue;
+    use {super::*, crate::starlark::testutil::*, tugger_common::testutil::*};
 
     #[test]
     fn test_new() -> Result<()> {
@@ -366,6 +582,79 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_set_attrs() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("builder = WiXBundleBuilder('prefix', 'name', '0.1', 'manufacturer')")?;
+        env.eval("builder.upgrade_code = '0c1d2e3f-4a5b-6c7d-8e9f-a0b1c2d3e4f5'")?;
+        env.eval("builder.icon_path = 'app.ico'")?;
+        assert!(env.eval("builder.upgrade_code = 'bogus'").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_bootstrapper_ui() -> Result<()> {
+        let license_path = DEFAULT_TEMP_DIR
+            .path()
+            .join("wix-bundle-builder-license.rtf");
+        std::fs::write(&license_path, "license")?;
+        let license_path_s = license_path.to_string_lossy().replace('\\', "/");
+
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("builder = WiXBundleBuilder('prefix', 'name', '0.1', 'manufacturer')")?;
+        env.eval("builder.set_bootstrapper_ui(license_url = 'https://example.com/license')")?;
+        env.eval(&format!(
+            "builder.set_bootstrapper_ui(theme = 'RtfLicense', license_path = '{}', show_version = True)",
+            license_path_s
+        ))?;
+        assert!(env
+            .eval("builder.set_bootstrapper_ui(theme = 'RtfLicense')")
+            .is_err());
+        assert!(env
+            .eval("builder.set_bootstrapper_ui(theme = 'bogus')")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_packages() -> Result<()> {
+        let msi_path = DEFAULT_TEMP_DIR.path().join("wix-bundle-builder-app.msi");
+        std::fs::write(&msi_path, "msi")?;
+        let msi_path_s = msi_path.to_string_lossy().replace('\\', "/");
+        let exe_path = DEFAULT_TEMP_DIR
+            .path()
+            .join("wix-bundle-builder-prereq.exe");
+        std::fs::write(&exe_path, "exe")?;
+        let exe_path_s = exe_path.to_string_lossy().replace('\\', "/");
+
+        let mut env = StarlarkEnvironment::new()?;
+
+        env.eval("builder = WiXBundleBuilder('prefix', 'name', '0.1', 'manufacturer')")?;
+        env.eval(&format!(
+            "builder.add_exe_package('{}', '/quiet /norestart', 'PrereqVersion >= v1.0', exit_codes = {{1638: 'success', 3010: 'scheduleReboot'}})",
+            exe_path_s
+        ))?;
+        env.eval(&format!(
+            "builder.add_msi_package('{}', display_name = 'App', properties = {{'ADDDESKTOPSHORTCUT': '1'}})",
+            msi_path_s
+        ))?;
+        assert!(env
+            .eval(&format!(
+                "builder.add_exe_package('{}', '/quiet', 'cond', exit_codes = {{1: 'bogus'}})",
+                exe_path_s
+            ))
+            .is_err());
+        assert!(env
+            .eval("builder.add_msi_package('does-not-exist.msi')")
+            .is_err());
+
+        Ok(())
+    }
+
     #[cfg(windows)]
     #[test]
     fn test_build() -> Result<()> {
@@ -27,7 +27,8 @@ use {
         },
     },
     starlark_dialect_build_targets::{
-        get_context_value, EnvironmentContext, ResolvedTarget, ResolvedTargetValue, RunMode,
+        get_context_value, optional_dict_arg, optional_str_arg, EnvironmentContext, ResolvedTarget,
+        ResolvedTargetValue, RunMode,
     },
     std::{
         path::{Path, PathBuf},
@@ -35,7 +36,10 @@ use {
     },
     tugger_code_signing::SigningDestination,
     tugger_windows::VcRedistributablePlatform,
-    tugger_wix::WiXSimpleMsiBuilder,
+    tugger_wix::{
+        WiXCustomAction, WiXCustomActionKind, WiXEnvironmentVariable, WiXInstallScope,
+        WiXProductCode, WiXProperty, WiXRegistryValue, WiXSimpleMsiBuilder, WxsBuilder,
+    },
 };
 
 fn error_context<F, T>(label: &str, f: F) -> Result<T, ValueError>
@@ -73,9 +77,19 @@ impl TypedValue for WiXMsiBuilderValue {
     }
 
     fn set_attr(&mut self, attribute: &str, value: Value) -> Result<(), ValueError> {
-        let mut inner = self.inner(&format!("{}.{}", Self::TYPE, &attribute))?;
+        let label = format!("{}.{}", Self::TYPE, &attribute);
+        let mut inner = self.inner(&label)?;
 
         match attribute {
+            "allow_downgrades" => {
+                inner.builder = inner.builder.clone().allow_downgrades(value.to_bool());
+            }
+            "allow_same_version_upgrades" => {
+                inner.builder = inner
+                    .builder
+                    .clone()
+                    .allow_same_version_upgrades(value.to_bool());
+            }
             "arch" => {
                 inner.arch = value.to_string();
             }
@@ -85,12 +99,24 @@ impl TypedValue for WiXMsiBuilderValue {
             "dialog_bmp_path" => {
                 inner.builder = inner.builder.clone().dialog_bmp_path(value.to_string());
             }
+            "downgrade_error_message" => {
+                inner.builder = inner
+                    .builder
+                    .clone()
+                    .downgrade_error_message(value.to_string());
+            }
             "eula_rtf_path" => {
                 inner.builder = inner.builder.clone().eula_rtf_path(value.to_string());
             }
             "help_url" => {
                 inner.builder = inner.builder.clone().help_url(value.to_string());
             }
+            "install_scope" => {
+                let scope = error_context(&label, || {
+                    WiXInstallScope::try_from(value.to_string().as_str())
+                })?;
+                inner.builder = inner.builder.clone().install_scope(scope);
+            }
             "license_path" => {
                 inner.builder = inner.builder.clone().license_path(value.to_string());
             }
@@ -103,11 +129,24 @@ impl TypedValue for WiXMsiBuilderValue {
             "package_keywords" => {
                 inner.builder = inner.builder.clone().package_keywords(value.to_string());
             }
+            "product_code" => {
+                error_context(&label, || {
+                    let product_code = WiXProductCode::try_from(value.to_string().as_str())?;
+                    inner.builder.set_product_code(product_code)
+                })?;
+            }
             "product_icon_path" => {
                 inner.builder = inner.builder.clone().product_icon_path(value.to_string());
             }
             "upgrade_code" => {
-                inner.builder = inner.builder.clone().upgrade_code(value.to_string());
+                error_context(&label, || {
+                    inner.builder.set_upgrade_code(&value.to_string())
+                })?;
+            }
+            "upgrade_schedule" => {
+                error_context(&label, || {
+                    inner.builder.set_upgrade_schedule(&value.to_string())
+                })?;
             }
             attr => {
                 return Err(ValueError::OperationNotSupported {
@@ -207,6 +246,249 @@ impl WiXMsiBuilderValue {
         Ok(Value::new(NoneType::None))
     }
 
+    pub fn add_registry_value(
+        &mut self,
+        root: String,
+        key: String,
+        value: String,
+        name: Value,
+        value_type: String,
+    ) -> ValueResult {
+        const LABEL: &str = "WiXMSIBuilder.add_registry_value()";
+
+        let name = optional_str_arg("name", &name)?;
+
+        let mut inner = self.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            inner.builder.add_registry_value(WiXRegistryValue {
+                root,
+                key,
+                name,
+                value,
+                value_type,
+            })
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_environment_variable(
+        &mut self,
+        name: String,
+        value: String,
+        action: String,
+        part: String,
+        system: bool,
+    ) -> ValueResult {
+        const LABEL: &str = "WiXMSIBuilder.add_environment_variable()";
+
+        let mut inner = self.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            inner
+                .builder
+                .add_environment_variable(WiXEnvironmentVariable {
+                    name,
+                    value,
+                    action,
+                    part,
+                    system,
+                })
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn add_custom_action(
+        &mut self,
+        label: &str,
+        id: String,
+        kind: WiXCustomActionKind,
+        after: Value,
+        before: Value,
+        condition: Value,
+        execute: String,
+        impersonate: bool,
+        return_type: String,
+    ) -> ValueResult {
+        let after = optional_str_arg("after", &after)?;
+        let before = optional_str_arg("before", &before)?;
+        let condition = optional_str_arg("condition", &condition)?;
+
+        let mut inner = self.inner(label)?;
+
+        error_context(label, || {
+            inner.builder.add_custom_action(WiXCustomAction {
+                id,
+                kind,
+                after,
+                before,
+                condition,
+                execute,
+                impersonate,
+                return_type,
+            })
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_exe_custom_action(
+        &mut self,
+        id: String,
+        command: String,
+        after: Value,
+        before: Value,
+        condition: Value,
+        execute: String,
+        impersonate: bool,
+        return_type: String,
+    ) -> ValueResult {
+        const LABEL: &str = "WiXMSIBuilder.add_exe_custom_action()";
+
+        self.add_custom_action(
+            LABEL,
+            id,
+            WiXCustomActionKind::Exe { command },
+            after,
+            before,
+            condition,
+            execute,
+            impersonate,
+            return_type,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_dll_custom_action(
+        &mut self,
+        id: String,
+        path: String,
+        entry_point: String,
+        after: Value,
+        before: Value,
+        condition: Value,
+        execute: String,
+        impersonate: bool,
+        return_type: String,
+    ) -> ValueResult {
+        const LABEL: &str = "WiXMSIBuilder.add_dll_custom_action()";
+
+        let source = error_context(LABEL, || {
+            std::fs::canonicalize(&path).with_context(|| format!("resolving {}", path))
+        })?;
+
+        self.add_custom_action(
+            LABEL,
+            id,
+            WiXCustomActionKind::Dll {
+                source,
+                entry_point,
+            },
+            after,
+            before,
+            condition,
+            execute,
+            impersonate,
+            return_type,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_script_custom_action(
+        &mut self,
+        id: String,
+        path: String,
+        language: String,
+        function: Value,
+        after: Value,
+        before: Value,
+        condition: Value,
+        execute: String,
+        impersonate: bool,
+        return_type: String,
+    ) -> ValueResult {
+        const LABEL: &str = "WiXMSIBuilder.add_script_custom_action()";
+
+        let function = optional_str_arg("function", &function)?;
+
+        let source = error_context(LABEL, || {
+            std::fs::canonicalize(&path).with_context(|| format!("resolving {}", path))
+        })?;
+
+        self.add_custom_action(
+            LABEL,
+            id,
+            WiXCustomActionKind::Script {
+                source,
+                language,
+                function,
+            },
+            after,
+            before,
+            condition,
+            execute,
+            impersonate,
+            return_type,
+        )
+    }
+
+    pub fn add_property(&mut self, id: String, value: String, secure: bool) -> ValueResult {
+        const LABEL: &str = "WiXMSIBuilder.add_property()";
+
+        let mut inner = self.inner(LABEL)?;
+
+        error_context(LABEL, || {
+            inner
+                .builder
+                .add_property(WiXProperty { id, value, secure })
+        })?;
+
+        Ok(Value::new(NoneType::None))
+    }
+
+    pub fn add_wxs_file(
+        &mut self,
+        path: String,
+        component_group: Value,
+        preprocessor_parameters: Value,
+    ) -> ValueResult {
+        const LABEL: &str = "WiXMSIBuilder.add_wxs_file()";
+
+        let component_group = optional_str_arg("component_group", &component_group)?;
+        optional_dict_arg(
+            "preprocessor_parameters",
+            "string",
+            "string",
+            &preprocessor_parameters,
+        )?;
+
+        let mut builder = error_context(LABEL, || {
+            WxsBuilder::from_path(path).context("constructing WxsBuilder from path")
+        })?;
+
+        match preprocessor_parameters.get_type() {
+            "dict" => {
+                for key in preprocessor_parameters.iter()?.iter() {
+                    let k = key.to_string();
+                    let v = preprocessor_parameters.at(key).unwrap().to_string();
+
+                    builder.set_preprocessor_parameter(k, v);
+                }
+            }
+            "NoneType" => (),
+            _ => panic!("should have validated type above"),
+        }
+
+        let mut inner = self.inner(LABEL)?;
+        inner.builder.add_wxs(builder, component_group);
+
+        Ok(Value::new(NoneType::None))
+    }
+
     pub fn materialize(
         &self,
         type_values: &TypeValues,
@@ -398,6 +680,98 @@ starlark_module! { wix_msi_builder_module =>
         this.add_visual_cpp_redistributable(redist_version, platform)
     }
 
+    WiXMSIBuilder.add_registry_value(
+        this,
+        root: String,
+        key: String,
+        value: String,
+        name = NoneType::None,
+        value_type: String = "string".to_string()
+    ) {
+        let mut this = this.downcast_mut::<WiXMsiBuilderValue>().unwrap().unwrap();
+        this.add_registry_value(root, key, value, name, value_type)
+    }
+
+    WiXMSIBuilder.add_environment_variable(
+        this,
+        name: String,
+        value: String,
+        action: String = "set".to_string(),
+        part: String = "all".to_string(),
+        system: bool = true
+    ) {
+        let mut this = this.downcast_mut::<WiXMsiBuilderValue>().unwrap().unwrap();
+        this.add_environment_variable(name, value, action, part, system)
+    }
+
+    WiXMSIBuilder.add_exe_custom_action(
+        this,
+        id: String,
+        command: String,
+        after = NoneType::None,
+        before = NoneType::None,
+        condition = NoneType::None,
+        execute: String = "deferred".to_string(),
+        impersonate: bool = false,
+        return_type: String = "check".to_string()
+    ) {
+        let mut this = this.downcast_mut::<WiXMsiBuilderValue>().unwrap().unwrap();
+        this.add_exe_custom_action(id, command, after, before, condition, execute, impersonate, return_type)
+    }
+
+    WiXMSIBuilder.add_dll_custom_action(
+        this,
+        id: String,
+        path: String,
+        entry_point: String,
+        after = NoneType::None,
+        before = NoneType::None,
+        condition = NoneType::None,
+        execute: String = "deferred".to_string(),
+        impersonate: bool = false,
+        return_type: String = "check".to_string()
+    ) {
+        let mut this = this.downcast_mut::<WiXMsiBuilderValue>().unwrap().unwrap();
+        this.add_dll_custom_action(id, path, entry_point, after, before, condition, execute, impersonate, return_type)
+    }
+
+    WiXMSIBuilder.add_script_custom_action(
+        this,
+        id: String,
+        path: String,
+        language: String,
+        function = NoneType::None,
+        after = NoneType::None,
+        before = NoneType::None,
+        condition = NoneType::None,
+        execute: String = "deferred".to_string(),
+        impersonate: bool = false,
+        return_type: String = "check".to_string()
+    ) {
+        let mut this = this.downcast_mut::<WiXMsiBuilderValue>().unwrap().unwrap();
+        this.add_script_custom_action(id, path, language, function, after, before, condition, execute, impersonate, return_type)
+    }
+
+    WiXMSIBuilder.add_property(
+        this,
+        id: String,
+        value: String,
+        secure: bool = false
+    ) {
+        let mut this = this.downcast_mut::<WiXMsiBuilderValue>().unwrap().unwrap();
+        this.add_property(id, value, secure)
+    }
+
+    WiXMSIBuilder.add_wxs_file(
+        this,
+        path: String,
+        component_group = NoneType::None,
+        preprocessor_parameters = NoneType::None
+    ) {
+        let mut this = this.downcast_mut::<WiXMsiBuilderValue>().unwrap().unwrap();
+        this.add_wxs_file(path, component_group, preprocessor_parameters)
+    }
+
     WiXMSIBuilder.build(env env, call_stack cs, this, target: String) {
         let this = this.downcast_ref::<WiXMsiBuilderValue>().unwrap();
         this.build(env, cs, target)
@@ -416,9 +790,10 @@ starlark_module! { wix_msi_builder_module =>
 
 #[cfg(test)]
 mod tests {
-    use {super::*, crate::starlark::testutil::*};
+    use {super::*, crate::starlark::testutil::*, tugger_common::testutil::*};
+
     #[cfg(windows)]
-    use {crate::starlark::file_content::FileContentValue, tugger_common::testutil::*};
+    use crate::starlark::file_content::FileContentValue;
 
     #[test]
     fn test_new() -> Result<()> {
@@ -487,6 +862,130 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_add_registry_value() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval("msi = WiXMSIBuilder('prefix', 'name', '0.1', 'manufacturer')")?;
+        env.eval("msi.add_registry_value('HKLM', 'Software\\\\name', '[APPLICATIONFOLDER]', name = 'InstallDir')")?;
+        env.eval(
+            "msi.add_registry_value('HKCU', 'Software\\\\name', '1', value_type = 'integer')",
+        )?;
+        assert!(env
+            .eval("msi.add_registry_value('HKBOGUS', 'Software', '1')")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_environment_variable() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval("msi = WiXMSIBuilder('prefix', 'name', '0.1', 'manufacturer')")?;
+        env.eval("msi.add_environment_variable('NAME_HOME', '[APPLICATIONFOLDER]')")?;
+        env.eval("msi.add_environment_variable('PYTHONPATH', '[APPLICATIONFOLDER]lib', part = 'last', system = False)")?;
+        assert!(env
+            .eval("msi.add_environment_variable('FOO', 'bar', action = 'bogus')")
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_upgrade_policy() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval("msi = WiXMSIBuilder('prefix', 'name', '0.1', 'manufacturer')")?;
+        env.eval("msi.upgrade_code = '0c1d2e3f-4a5b-6c7d-8e9f-a0b1c2d3e4f5'")?;
+        env.eval("msi.product_code = 'version'")?;
+        env.eval("msi.upgrade_schedule = 'afterInstallExecute'")?;
+        env.eval("msi.allow_downgrades = True")?;
+        env.eval("msi.allow_same_version_upgrades = True")?;
+        env.eval("msi.downgrade_error_message = 'newer version installed'")?;
+
+        assert!(env.eval("msi.upgrade_code = 'bogus'").is_err());
+        assert!(env.eval("msi.product_code = 'bogus'").is_err());
+        assert!(env.eval("msi.upgrade_schedule = 'bogus'").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_install_scope() -> Result<()> {
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval("msi = WiXMSIBuilder('prefix', 'name', '0.1', 'manufacturer')")?;
+        env.eval("msi.install_scope = 'perUser'")?;
+        assert!(env.eval("msi.install_scope = 'bogus'").is_err());
+
+        env.eval(
+            "msi.add_environment_variable('NAME_HOME', '[APPLICATIONFOLDER]', system = False)",
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_custom_actions() -> Result<()> {
+        let dll_path = DEFAULT_TEMP_DIR.path().join("wix-msi-builder-helpers.dll");
+        std::fs::write(&dll_path, "dll")?;
+        let dll_path_s = dll_path.to_string_lossy().replace('\\', "/");
+        let script_path = DEFAULT_TEMP_DIR.path().join("wix-msi-builder-script.vbs");
+        std::fs::write(&script_path, "script")?;
+        let script_path_s = script_path.to_string_lossy().replace('\\', "/");
+
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval("msi = WiXMSIBuilder('prefix', 'name', '0.1', 'manufacturer')")?;
+        env.eval("msi.add_exe_custom_action('Register', '\"[APPLICATIONFOLDER]name.exe\" --register', condition = 'NOT Installed')")?;
+        env.eval(&format!(
+            "msi.add_dll_custom_action('Configure', '{}', 'Configure', before = 'InstallFinalize')",
+            dll_path_s
+        ))?;
+        env.eval(&format!(
+            "msi.add_script_custom_action('Check', '{}', 'vbscript', function = 'Check', execute = 'immediate', return_type = 'ignore')",
+            script_path_s
+        ))?;
+        env.eval("msi.add_property('SERVICEPORT', '8080', secure = True)")?;
+
+        assert!(env
+            .eval("msi.add_exe_custom_action('Register', 'foo.exe')")
+            .is_err());
+        assert!(env
+            .eval("msi.add_exe_custom_action('Other', 'foo.exe', execute = 'bogus')")
+            .is_err());
+        assert!(env
+            .eval("msi.add_dll_custom_action('Missing', 'does-not-exist.dll', 'Entry')")
+            .is_err());
+        assert!(env.eval("msi.add_property('SERVICEPORT', '80')").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_wxs_file() -> Result<()> {
+        let wxs_path = DEFAULT_TEMP_DIR.path().join("wix-msi-builder-extra.wxs");
+        std::fs::write(&wxs_path, "<Wix/>")?;
+        let wxs_path_s = wxs_path.to_string_lossy().replace('\\', "/");
+
+        let mut env = StarlarkEnvironment::new()?;
+        env.eval("msi = WiXMSIBuilder('prefix', 'name', '0.1', 'manufacturer')")?;
+        env.eval(&format!(
+            "msi.add_wxs_file('{}', component_group = 'Extra', preprocessor_parameters = {{'foo': 'bar'}})",
+            wxs_path_s
+        ))?;
+
+        let builder_value = env.eval("msi")?;
+        let builder = builder_value.downcast_ref::<WiXMsiBuilderValue>().unwrap();
+        let installer = builder
+            .inner
+            .lock()
+            .unwrap()
+            .builder
+            .to_installer_builder("x64", DEFAULT_TEMP_DIR.path())?;
+        assert!(installer
+            .wxs_files()
+            .contains_key(&PathBuf::from("wix-msi-builder-extra.wxs")));
+
+        Ok(())
+    }
+
     #[cfg(windows)]
     #[test]
     fn to_file_content() -> Result<()> {
@@ -12,6 +12,8 @@ use {
 /// Create a tarball from a filesystem path.
 ///
 /// The uncompressed tar contents will be emitted to the passed writer.
+///
+/// Symlinks are archived as symlinks rather than as the content they point to.
 pub fn write_tarball_from_directory<W: Write, P: AsRef<Path>>(
     fh: &mut W,
     source_path: P,
@@ -21,6 +23,7 @@ pub fn write_tarball_from_directory<W: Write, P: AsRef<Path>>(
 
     let mut builder = tar::Builder::new(fh);
     builder.mode(tar::HeaderMode::Deterministic);
+    builder.follow_symlinks(false);
 
     // The tar crate isn't deterministic when iterating directories. So we
     // do the iteration ourselves.
@@ -51,3 +54,55 @@ pub fn write_tarball_from_directory<W: Write, P: AsRef<Path>>(
 
     Ok(())
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use {super::*, std::path::PathBuf};
+
+    #[test]
+    fn test_symlinks() -> Result<()> {
+        let temp_dir = tempfile::Builder::new().prefix("tugger-test").tempdir()?;
+        let source_path = temp_dir.path().join("source");
+
+        std::fs::create_dir_all(source_path.join("Versions/A"))?;
+        std::fs::write(source_path.join("Versions/A/lib"), b"foo")?;
+        std::os::unix::fs::symlink("A", source_path.join("Versions/Current"))?;
+
+        let mut data = vec![];
+        write_tarball_from_directory(&mut data, source_path.as_path(), None)?;
+
+        let mut archive = tar::Archive::new(std::io::Cursor::new(data));
+        let entries = archive
+            .entries()?
+            .map(|entry| {
+                let entry = entry?;
+
+                Ok((
+                    entry.path()?.to_path_buf(),
+                    entry.header().entry_type(),
+                    entry.link_name()?.map(|p| p.to_path_buf()),
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        assert_eq!(
+            entries,
+            vec![
+                (PathBuf::from("Versions"), tar::EntryType::Directory, None),
+                (PathBuf::from("Versions/A"), tar::EntryType::Directory, None),
+                (
+                    PathBuf::from("Versions/A/lib"),
+                    tar::EntryType::Regular,
+                    None
+                ),
+                (
+                    PathBuf::from("Versions/Current"),
+                    tar::EntryType::Symlink,
+                    Some(PathBuf::from("A"))
+                ),
+            ]
+        );
+
+        Ok(())
+    }
+}